@@ -215,7 +215,8 @@ abstract class _DragDetector extends StatelessWidget {
             buildContext: buildContext,
             session: session,
             configuration: rawConfiguration,
-            position: position);
+            position: position,
+            deviceKind: raw.PointerDeviceKindDetector.instance.current.value);
       } else {
         rawConfiguration.disposeImages();
       }
@@ -326,16 +327,21 @@ class MobileDragDetector extends _DragDetector {
 
   @override
   Widget build(BuildContext context) {
+    final settings =
+        _dragContext?.touchDragSettings.value ?? const raw.TouchDragSettings();
     return MultiTouchDetector(
       child: RawGestureDetector(
+        // Recognizer thresholds are final, new settings need new recognizer.
+        key: ValueKey(settings),
         behavior: hitTestBehavior,
         gestures: {
           raw.SingleDragDelayedGestureRecognizer:
               GestureRecognizerFactoryWithHandlers<
                       raw.SingleDragDelayedGestureRecognizer>(
                   () => raw.SingleDragDelayedGestureRecognizer(
-                        beginDuration: const Duration(milliseconds: 150),
-                        duration: const Duration(milliseconds: 300),
+                        beginDuration: settings.pressAndHoldDuration ~/ 2,
+                        duration: settings.pressAndHoldDuration,
+                        slop: settings.slop,
                       ), (recognizer) {
             recognizer.shouldAcceptTouchAtPosition = isLocationDraggable;
             recognizer.onDragStart = (globalPosition) {
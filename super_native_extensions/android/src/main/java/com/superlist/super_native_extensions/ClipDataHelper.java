@@ -8,9 +8,14 @@ import android.content.ClipData;
 import android.content.ContentResolver;
 import android.content.Context;
 import android.content.res.AssetFileDescriptor;
+import android.database.Cursor;
+import android.media.MediaMetadataRetriever;
 import android.net.Uri;
+import android.os.Build;
 import android.os.Handler;
 import android.os.Looper;
+import android.os.UserManager;
+import android.provider.OpenableColumns;
 import android.util.Log;
 
 import androidx.annotation.Keep;
@@ -21,9 +26,11 @@ import java.io.Closeable;
 import java.io.FileInputStream;
 import java.io.FileNotFoundException;
 import java.io.IOException;
+import java.io.InputStream;
 import java.io.InputStreamReader;
 import java.nio.charset.StandardCharsets;
 import java.util.ArrayList;
+import java.util.Arrays;
 import java.util.concurrent.ExecutorService;
 import java.util.concurrent.Executors;
 
@@ -62,6 +69,182 @@ public final class ClipDataHelper {
 
     native void onData(int handle, Object data);
 
+    // Order of values returned from getMediaMetadata. Must be kept in sync
+    // with android/reader.rs.
+    static final int[] mediaMetadataKeys = {
+            MediaMetadataRetriever.METADATA_KEY_DURATION,
+            MediaMetadataRetriever.METADATA_KEY_HAS_VIDEO,
+            MediaMetadataRetriever.METADATA_KEY_HAS_AUDIO,
+            MediaMetadataRetriever.METADATA_KEY_VIDEO_WIDTH,
+            MediaMetadataRetriever.METADATA_KEY_VIDEO_HEIGHT,
+            MediaMetadataRetriever.METADATA_KEY_VIDEO_ROTATION,
+    };
+
+    public void getMediaMetadata(ClipData data, int index, Context context, int handle) {
+        ExecutorService executor = Executors.newSingleThreadExecutor();
+
+        executor.execute(() -> {
+            Object res = null;
+            try {
+                res = _getMediaMetadata(data, index, context);
+            } catch (Exception e) {
+                Log.w("ClipData", "getMediaMetadata failed", e);
+            }
+            onData(handle, res);
+        });
+    }
+
+    String[] _getMediaMetadata(ClipData data, int index, Context context) throws IOException {
+        if (index >= data.getItemCount()) {
+            return null;
+        }
+        Uri uri = data.getItemAt(index).getUri();
+        if (uri == null) {
+            return null;
+        }
+        MediaMetadataRetriever retriever = new MediaMetadataRetriever();
+        try {
+            retriever.setDataSource(context, uri);
+            String[] res = new String[mediaMetadataKeys.length];
+            for (int i = 0; i < mediaMetadataKeys.length; ++i) {
+                res[i] = retriever.extractMetadata(mediaMetadataKeys[i]);
+            }
+            return res;
+        } finally {
+            retriever.release();
+        }
+    }
+
+    @Nullable
+    static Uri getContentUri(ClipData data, int index) {
+        if (index >= data.getItemCount()) {
+            return null;
+        }
+        Uri uri = data.getItemAt(index).getUri();
+        if (uri == null || !SCHEME_CONTENT.equals(uri.getScheme())) {
+            return null;
+        }
+        return uri;
+    }
+
+    public boolean hasContentUri(ClipData data, int index) {
+        return getContentUri(data, index) != null;
+    }
+
+    // Display name and size of content URI item from OpenableColumns, queried
+    // on background thread. Either may be null. Order must be kept in sync
+    // with android/reader.rs.
+    public void getOpenableColumns(ClipData data, int index, Context context, int handle) {
+        streamExecutor.execute(() -> onData(handle, _getOpenableColumns(data, index, context)));
+    }
+
+    String[] _getOpenableColumns(ClipData data, int index, Context context) {
+        Uri uri = getContentUri(data, index);
+        if (uri == null) {
+            return null;
+        }
+        String[] projection = {OpenableColumns.DISPLAY_NAME, OpenableColumns.SIZE};
+        try (Cursor cursor = context.getContentResolver().query(uri, projection, null, null, null)) {
+            if (cursor == null || !cursor.moveToFirst()) {
+                return null;
+            }
+            String[] res = new String[projection.length];
+            for (int i = 0; i < projection.length; ++i) {
+                int column = cursor.getColumnIndex(projection[i]);
+                if (column >= 0 && !cursor.isNull(column)) {
+                    res[i] = cursor.getString(column);
+                }
+            }
+            return res;
+        } catch (Exception e) {
+            Log.w("ClipData", "Failed to query openable columns", e);
+            return null;
+        }
+    }
+
+    // Opens stream for content URI item on background thread and reports it
+    // through onStreamOpened.
+    public void openStream(ClipData data, int index, String type, Context context, int handle) {
+        streamExecutor.execute(() -> {
+            try {
+                onStreamOpened(handle, _openStream(data, index, type, context), null, false);
+            } catch (SecurityException e) {
+                onStreamOpened(handle, null, e.toString(), true);
+            } catch (Exception e) {
+                Log.w("ClipData", "openStream failed", e);
+                onStreamOpened(handle, null, e.toString(), false);
+            }
+        });
+    }
+
+    native void onStreamOpened(int handle, InputStream stream, String error, boolean accessDenied);
+
+    // Opens stream preferably in requested type. Providers such as the Files
+    // app may not support typed streams, in which case the content is read as
+    // is.
+    InputStream _openStream(ClipData data, int index, String type, Context context)
+            throws IOException {
+        Uri uri = getContentUri(data, index);
+        if (uri == null) {
+            return null;
+        }
+        ContentResolver resolver = context.getContentResolver();
+        try {
+            AssetFileDescriptor descriptor = resolver.openTypedAssetFileDescriptor(uri, type, null);
+            if (descriptor != null) {
+                return descriptor.createInputStream();
+            }
+        } catch (FileNotFoundException | RuntimeException e) {
+            Log.i("ClipData", "Typed stream not available, opening content directly", e);
+        }
+        return resolver.openInputStream(uri);
+    }
+
+    private static final ExecutorService streamExecutor = Executors.newCachedThreadPool();
+
+    static final int streamChunkSize = 1024 * 1024;
+
+    // Reads next chunk of stream on background thread. Completes with empty
+    // array at the end of stream and with error message if reading failed.
+    public void readStream(InputStream stream, int handle) {
+        streamExecutor.execute(() -> {
+            Object res;
+            try {
+                byte[] buffer = new byte[streamChunkSize];
+                int numRead = stream.read(buffer);
+                res = numRead > 0 ? Arrays.copyOf(buffer, numRead) : new byte[0];
+            } catch (Exception e) {
+                Log.w("ClipData", "readStream failed", e);
+                res = e.toString();
+            }
+            onData(handle, res);
+        });
+    }
+
+    public void closeStream(InputStream stream) {
+        closeQuietly(stream);
+    }
+
+    // Clipboard related restrictions of current user. Names must be kept in
+    // sync with android/clipboard_policy.rs.
+    public String[] getClipboardRestrictions(Context context) {
+        ArrayList<String> res = new ArrayList<>();
+        UserManager userManager = (UserManager) context.getSystemService(Context.USER_SERVICE);
+        if (userManager == null) {
+            return res.toArray(new String[0]);
+        }
+        if (userManager.hasUserRestriction(UserManager.DISALLOW_CROSS_PROFILE_COPY_PASTE)) {
+            res.add("crossProfileCopyPaste");
+        }
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.R && userManager.isManagedProfile()) {
+            res.add("managedProfile");
+        }
+        if (!userManager.isSystemUser()) {
+            res.add("secondaryUser");
+        }
+        return res.toArray(new String[0]);
+    }
+
     public Object _getData(ClipData data, int index, String type, Context context) {
         if (index < data.getItemCount()) {
             ClipData.Item item = data.getItemAt(index);
@@ -15,9 +15,13 @@ import android.util.Log;
 import android.view.DragEvent;
 import android.view.InputDevice;
 import android.view.MotionEvent;
+import android.view.SurfaceControl;
 import android.view.View;
 import android.view.ViewParent;
 
+import java.util.Map;
+import java.util.WeakHashMap;
+
 // Wrap drag sessionId in typed object so that we can safely ignore possible local data
 // from sessions not created by super_native_extensions.
 class SessionId {
@@ -59,10 +63,17 @@ public class DragDropHelper {
     native void updateLastTouchPoint(ViewParent rootView, MotionEvent event);
 
     void startDrag(View view, long dragSessionId, ClipData clipData, Bitmap bitmap,
-                   int touchPointX, int touchPointY, int lastTouchEventX, int lastTouchEventY) {
+                   int touchPointX, int touchPointY, int lastTouchEventX, int lastTouchEventY,
+                   boolean animatesOnCancel) {
         final int DRAG_FLAG_GLOBAL = 1 << 8;
         final int DRAG_FLAG_GLOBAL_URI_READ = Intent.FLAG_GRANT_READ_URI_PERMISSION;
-        final int flags = clipData != null ? DRAG_FLAG_GLOBAL | DRAG_FLAG_GLOBAL_URI_READ : 0;
+        final int DRAG_FLAG_REQUEST_SURFACE_FOR_RETURN_ANIMATION = 1 << 11;
+        int flags = clipData != null ? DRAG_FLAG_GLOBAL | DRAG_FLAG_GLOBAL_URI_READ : 0;
+        // With the return animation surface owned by us the system doesn't
+        // animate the shadow back on unhandled drops.
+        if (!animatesOnCancel && Build.VERSION.SDK_INT >= Build.VERSION_CODES.S) {
+            flags |= DRAG_FLAG_REQUEST_SURFACE_FOR_RETURN_ANIMATION;
+        }
         if (view != null) {
             ViewParent parent = view.getParent();
             while (parent.getParent() != null) {
@@ -93,6 +104,17 @@ public class DragDropHelper {
         }
     }
 
+    // Removes the shadow of unhandled drop started without return animation.
+    void releaseDragSurface(DragEvent event) {
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.S) {
+            SurfaceControl surface = event.getDragSurface();
+            if (surface != null) {
+                new SurfaceControl.Transaction().reparent(surface, null).apply();
+                surface.release();
+            }
+        }
+    }
+
     Long getSessionId(DragEvent event) {
         Object localState = event.getLocalState();
         if (localState instanceof SessionId) {
@@ -102,9 +124,24 @@ public class DragDropHelper {
         }
     }
 
+    // Handler currently listening on each view. Context being replaced
+    // (i.e. during hot reload) must not unregister its successor.
+    private final Map<View, Long> dropHandlers = new WeakHashMap<>();
+
     void registerDropHandler(View view, long handlerId) {
         if (view != null) {
+            dropHandlers.put(view, handlerId);
             view.setOnDragListener((v, event) -> onDrag(event, handlerId));
         }
     }
+
+    void unregisterDropHandler(View view, long handlerId) {
+        if (view != null) {
+            Long current = dropHandlers.get(view);
+            if (current != null && current == handlerId) {
+                dropHandlers.remove(view);
+                view.setOnDragListener(null);
+            }
+        }
+    }
 }
@@ -1,6 +1,8 @@
 package com.superlist.super_native_extensions;
 
+import android.content.ClipData;
 import android.content.Context;
+import android.hardware.HardwareBuffer;
 import android.util.Log;
 
 import androidx.annotation.NonNull;
@@ -37,6 +39,27 @@ public class SuperNativeExtensionsPlugin implements FlutterPlugin {
                                    ClipDataHelper ClipDataHelper,
                                    DragDropHelper DragDropHelper);
 
+    /**
+     * Registers ClipData obtained by another plugin so that it can be read
+     * from Dart through ReaderManager.newExternalReader with the returned
+     * handle. The reference is kept until the handle is redeemed or
+     * released with {@link #releaseClipData(long)}. Returns 0 for null.
+     */
+    public static native long registerClipData(ClipData clipData);
+
+    public static native void releaseClipData(long handle);
+
+    /**
+     * Registers HardwareBuffer to be used as drag image texture. Dart passes
+     * the returned handle as shared texture of the drag image. The buffer is
+     * acquired until the handle is used or released with
+     * {@link #releaseHardwareBuffer(long)}. Returns 0 for null or when
+     * hardware buffers are not supported (API below 26).
+     */
+    public static native long registerHardwareBuffer(HardwareBuffer buffer);
+
+    public static native void releaseHardwareBuffer(long handle);
+
     static {
         System.loadLibrary("super_native_extensions");
     }
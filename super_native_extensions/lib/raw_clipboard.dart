@@ -3,3 +3,10 @@ export 'src/reader.dart';
 export 'src/clipboard_reader.dart';
 export 'src/clipboard_writer.dart';
 export 'src/clipboard_events.dart';
+export 'src/clipboard_monitor.dart';
+export 'src/local_transfer.dart';
+export 'src/resource_sweeper.dart';
+export 'src/lan_clipboard_sync.dart';
+export 'src/native_error.dart';
+export 'src/native_log.dart';
+export 'src/services.dart';
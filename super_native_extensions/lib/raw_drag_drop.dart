@@ -1,5 +1,8 @@
 export 'src/drag.dart';
 export 'src/drop.dart';
+export 'src/drag_monitor.dart';
+export 'src/test_injection.dart';
+export 'src/image_data.dart' show SharedTexture, SharedTextureKind;
 export 'src/widget_snapshot/widget_snapshot.dart';
 export 'src/drag_interaction/long_press_handler.dart';
 export 'src/gesture/single_drag.dart';
@@ -1 +1,2 @@
 export 'src/hot_key.dart';
+export 'src/desktop_capability.dart';
@@ -0,0 +1,3 @@
+export 'src/image_data.dart' show ImageData;
+export 'src/tray_icon.dart';
+export 'src/desktop_capability.dart';
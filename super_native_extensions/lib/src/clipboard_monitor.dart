@@ -0,0 +1,30 @@
+import 'package:flutter/foundation.dart';
+
+import 'native/clipboard_monitor.dart'
+    if (dart.library.js) 'web/clipboard_monitor.dart';
+
+class ClipboardChangedEvent {
+  ClipboardChangedEvent({
+    required this.changeCount,
+  });
+
+  static ClipboardChangedEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return ClipboardChangedEvent(changeCount: map['changeCount']);
+  }
+
+  /// Platform change counter, if available (macOS, iOS, Windows).
+  final int? changeCount;
+}
+
+/// Notifies about system clipboard changes. Monitoring is active while there
+/// are listeners.
+abstract class ClipboardMonitor {
+  static final ClipboardMonitor instance = ClipboardMonitorImpl();
+
+  /// Adds listener invoked when clipboard contents change. Completes with
+  /// `false` if clipboard monitoring is not supported on this platform.
+  Future<bool> addListener(ValueChanged<ClipboardChangedEvent> listener);
+
+  void removeListener(ValueChanged<ClipboardChangedEvent> listener);
+}
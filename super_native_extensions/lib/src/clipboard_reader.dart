@@ -4,6 +4,96 @@ import 'native/clipboard_reader.dart'
     if (dart.library.js) 'web/clipboard_reader.dart';
 import 'reader.dart';
 
+/// Remote desktop session the application runs in.
+enum RemoteSessionKind {
+  rdp,
+  citrix,
+}
+
+class RemoteSessionInfo {
+  RemoteSessionInfo({
+    required this.kind,
+    required this.tuningEnabled,
+  });
+
+  static RemoteSessionInfo deserialize(dynamic info) {
+    final map = info as Map;
+    final kind = map['kind'] as String?;
+    return RemoteSessionInfo(
+      kind: kind != null ? RemoteSessionKind.values.byName(kind) : null,
+      tuningEnabled: map['tuningEnabled'],
+    );
+  }
+
+  /// Remote session application runs in, `null` for local session.
+  final RemoteSessionKind? kind;
+
+  /// Whether clipboard behavior is adjusted for remote session.
+  final bool tuningEnabled;
+}
+
+/// Device or profile restrictions affecting the clipboard (Android).
+class ClipboardPolicy {
+  ClipboardPolicy({
+    required this.managedProfile,
+    required this.secondaryUser,
+    required this.crossProfileCopyPasteDisallowed,
+  });
+
+  static ClipboardPolicy deserialize(dynamic policy) {
+    final map = policy as Map;
+    return ClipboardPolicy(
+      managedProfile: map['managedProfile'],
+      secondaryUser: map['secondaryUser'],
+      crossProfileCopyPasteDisallowed: map['crossProfileCopyPasteDisallowed'],
+    );
+  }
+
+  /// Application runs in a work (managed) profile.
+  final bool managedProfile;
+
+  /// Application runs as other than the primary device user.
+  final bool secondaryUser;
+
+  /// Clipboard content can not be shared with other profiles.
+  final bool crossProfileCopyPasteDisallowed;
+}
+
+enum ClipboardType {
+  clipboard,
+
+  /// X11 PRIMARY selection (middle-click paste), Linux only.
+  primary,
+}
+
+/// Clipboard readers and writers operate on.
+class ClipboardTarget {
+  const ClipboardTarget({
+    this.clipboardType,
+    this.pasteboardName,
+  });
+
+  /// Regular clipboard when not specified.
+  final ClipboardType? clipboardType;
+
+  /// Named pasteboard (macOS only), such as `Apple CFPasteboard find`.
+  /// Takes precedence over [clipboardType].
+  final String? pasteboardName;
+
+  Map<String, dynamic> serialize() => {
+        'clipboardType': clipboardType?.name,
+        'pasteboardName': pasteboardName,
+      };
+}
+
+/// Lightweight snapshot of clipboard state obtained through
+/// [ClipboardReader.captureClipboardToken]. Opaque to Dart code.
+class ClipboardToken {
+  ClipboardToken(this.raw);
+
+  final Object raw;
+}
+
 abstract class ClipboardReader {
   static final ClipboardReader instance = ClipboardReaderImpl();
 
@@ -15,5 +105,34 @@ abstract class ClipboardReader {
   /// of the reader. On top of it the content is cached lazily.
   ///
   /// If you need updated information create a new reader.
-  Future<DataReader> newClipboardReader();
+  ///
+  /// [target] selects PRIMARY selection (Linux) or named pasteboard (macOS)
+  /// instead of the regular clipboard.
+  Future<DataReader> newClipboardReader({ClipboardTarget? target});
+
+  /// Returns whether any clipboard item provides any of [formats], without
+  /// reading the data. Meant for enabling paste actions. Formats produced by
+  /// registered format converters and file URI lists are taken into account.
+  /// On iOS this does not trigger the paste permission prompt.
+  Future<bool> canPaste(List<String> formats, {ClipboardTarget? target});
+
+  /// Returns information about remote desktop session the application runs
+  /// in. Only detected on Windows.
+  Future<RemoteSessionInfo> getRemoteSessionInfo();
+
+  /// Forces clipboard adjustments for remote sessions on or off. `null`
+  /// restores automatic behavior (tuned in remote sessions only).
+  Future<void> setRemoteSessionTuning(bool? enabled);
+
+  /// Returns restrictions that apply to the clipboard. Reads blocked by
+  /// policy fail with `blockedByPolicy` error code.
+  Future<ClipboardPolicy> getClipboardPolicy();
+
+  /// Captures token describing current clipboard contents without reading
+  /// them. On iOS this does not trigger the paste permission prompt.
+  Future<ClipboardToken> captureClipboardToken();
+
+  /// Returns reader for current clipboard if its contents did not change
+  /// since the token was captured, `null` otherwise.
+  Future<DataReader?> redeemClipboardToken(ClipboardToken token);
 }
@@ -1,3 +1,4 @@
+import 'clipboard_reader.dart';
 import 'data_provider.dart';
 
 import 'native/clipboard_writer.dart'
@@ -7,4 +8,40 @@ abstract class ClipboardWriter {
   static final ClipboardWriter instance = ClipboardWriterImpl();
 
   Future<void> write(List<DataProviderHandle> providers);
+
+  /// Writes to PRIMARY selection (Linux) or named pasteboard (macOS)
+  /// selected by [target]. Only contents of the regular clipboard can be
+  /// modified afterwards.
+  Future<void> writeToSelection(
+    List<DataProviderHandle> providers,
+    ClipboardTarget target,
+  );
+
+  /// Adds items after the ones currently on clipboard. Fails if clipboard
+  /// contents were replaced by another application or ownership can not be
+  /// verified.
+  Future<void> append(List<DataProviderHandle> providers);
+
+  /// Removes item at [index] from clipboard contents written by this
+  /// application.
+  Future<void> removeItem(int index);
+
+  /// Replaces representations of item at [index] with those of [provider].
+  /// Representations in other formats are kept.
+  Future<void> replaceItem(int index, DataProviderHandle provider);
+
+  /// Moves item at [fromIndex] to [toIndex].
+  Future<void> moveItem({required int fromIndex, required int toIndex});
+
+  /// Clears the clipboard if it still holds contents written by this
+  /// application. Contents of other applications, or contents whose ownership
+  /// can not be verified on this platform, are left alone. Write that has
+  /// not reached the clipboard yet fails with cancelled error. Returns
+  /// whether anything was cleared.
+  Future<bool> clear();
+
+  /// Whether clipboard still holds contents written by this application,
+  /// i.e. whether lazy data providers may still be asked for data. Returns
+  /// `false` when ownership can not be verified.
+  Future<bool> hasOwnership();
 }
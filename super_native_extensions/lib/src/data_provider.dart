@@ -11,6 +11,10 @@ class DataProvider {
   DataProvider({
     required this.representations,
     this.suggestedName,
+    this.fidelity,
+    this.expiresAfter,
+    this.sourceUrl,
+    this.synthesizeRtf = false,
   });
 
   /// Registers this source with native code. The source data will be kept alive
@@ -19,8 +23,51 @@ class DataProvider {
     return DataProviderManager.instance.registerDataProvider(this);
   }
 
+  /// Identifies current isolate to worker isolates that serve data providers
+  /// for it, see [registerForServicePort].
+  static Future<int> servicePort() =>
+      DataProviderManager.instance.getServicePort();
+
+  /// Registers this provider in a worker isolate on behalf of the isolate
+  /// that returned [servicePort]. Lazy data and virtual files are then
+  /// produced in the worker, so deferred requests don't wait for a busy UI
+  /// isolate. Returns id to send to the owning isolate, which wraps it using
+  /// [DataProviderHandle.adopt]. The provider is released when the owner
+  /// disposes the handle or either isolate exits. Not supported on web.
+  Future<int> registerForServicePort(int servicePort) async {
+    final handle = await DataProviderManager.instance
+        .registerDataProvider(this, servicePort: servicePort);
+    return handle.id;
+  }
+
+  /// Limits bandwidth of each virtual file transfer started afterwards to
+  /// given number of bytes per second. `null` removes the limit.
+  static Future<void> setBandwidthLimit(int? bytesPerSecond) async {
+    await DataProviderManager.instance.setBandwidthLimit(bytesPerSecond);
+  }
+
   final List<DataRepresentation> representations;
   final String? suggestedName;
+
+  /// Fidelity level of formats that are alternative representations of the
+  /// same content; higher level means richer representation (i.e.
+  /// full-resolution PNG over thumbnail). Representations are reordered so
+  /// that platforms offer the best one first.
+  final Map<String, int>? fidelity;
+
+  /// When written to the clipboard, the clipboard is cleared after this
+  /// duration unless another application has replaced the contents. Ignored
+  /// on platforms where clipboard ownership can not be verified (Android,
+  /// Linux, web).
+  final Duration? expiresAfter;
+
+  /// URL of the document the content comes from. Written as `SourceURL` of
+  /// CF_HTML on Windows.
+  final String? sourceUrl;
+
+  /// Adds RTF converted from the HTML representation, for applications that
+  /// don't read HTML. Lazy HTML representations are not converted.
+  final bool synthesizeRtf;
 }
 
 sealed class DataRepresentation {
@@ -104,8 +151,13 @@ abstract class WriteProgress {
   /// progress is determined automatically based on total size and size written.
   void updateProgress(double fraction);
 
-  /// Listenable invoked when receiver cancels the write operation.
+  /// Listenable invoked when the write operation is cancelled, either by
+  /// receiver or through [cancel].
   Listenable get onCancel;
+
+  /// Cancels the write operation from the providing side. The sink is closed
+  /// and partially written content discarded.
+  void cancel();
 }
 
 /// Returns stream sink for virtual file. File size must be provided before
@@ -148,7 +200,15 @@ int _nextId = 1;
 class DataProviderHandle {
   DataProviderHandle(this.id, this.provider);
 
+  /// Takes over provider registered for current isolate by a worker isolate
+  /// through [DataProvider.registerForServicePort].
+  static Future<DataProviderHandle> adopt(int providerId) =>
+      DataProviderManager.instance.adoptDataProvider(providerId);
+
   final int id;
+
+  /// Provider as registered. For adopted handle representations are only
+  /// known to the worker isolate and this provider has none.
   final DataProvider provider;
   Listenable get onDispose => _onDispose;
 
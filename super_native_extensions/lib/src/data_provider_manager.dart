@@ -8,6 +8,18 @@ import 'native/data_provider_manager.dart'
 abstract class DataProviderManager {
   static final DataProviderManager instance = DataProviderManagerImpl();
 
-  FutureOr<DataProviderHandle> registerDataProvider(DataProvider provider);
+  /// [servicePort] registers the provider on behalf of another isolate, see
+  /// [DataProvider.registerForServicePort].
+  FutureOr<DataProviderHandle> registerDataProvider(
+    DataProvider provider, {
+    int? servicePort,
+  });
   FutureOr<void> unregisterDataProvider(int providerId);
+
+  /// Identifies current isolate to worker isolates.
+  Future<int> getServicePort();
+
+  /// Wraps provider registered for current isolate by a worker isolate.
+  Future<DataProviderHandle> adoptDataProvider(int providerId);
+  FutureOr<void> setBandwidthLimit(int? bytesPerSecond);
 }
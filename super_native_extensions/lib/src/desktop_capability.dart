@@ -0,0 +1,46 @@
+/// Mechanism providing a desktop feature.
+enum DesktopFeatureBackend {
+  /// Platform API (macOS, Windows).
+  native,
+
+  /// `org.kde.StatusNotifierItem` over D-Bus (Linux).
+  statusNotifier,
+
+  /// `GtkStatusIcon` embedded in X11 system tray (Linux).
+  xEmbed,
+
+  /// Grabbing keys on X11 root window (Linux).
+  x11Grab,
+}
+
+/// Whether tray icons or hot keys can be used in current session.
+class DesktopFeatureCapability {
+  DesktopFeatureCapability({
+    required this.supported,
+    required this.backend,
+    required this.reason,
+  });
+
+  static DesktopFeatureCapability deserialize(dynamic capability) {
+    final map = capability as Map;
+    final backend = map['backend'] as String?;
+    return DesktopFeatureCapability(
+      supported: map['supported'],
+      backend:
+          backend != null ? DesktopFeatureBackend.values.byName(backend) : null,
+      reason: map['reason'],
+    );
+  }
+
+  final bool supported;
+
+  /// Backend used when supported.
+  final DesktopFeatureBackend? backend;
+
+  /// Explains why feature is not supported.
+  final String? reason;
+
+  @override
+  String toString() => 'DesktopFeatureCapability(supported: $supported, '
+      'backend: $backend, reason: $reason)';
+}
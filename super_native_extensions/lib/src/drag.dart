@@ -1,12 +1,14 @@
 import 'dart:ui' as ui;
 
 import 'package:flutter/foundation.dart';
+import 'package:flutter/gestures.dart';
 import 'package:flutter/widgets.dart';
 
 import 'data_provider.dart';
 import 'drop.dart';
 import 'image_data.dart';
 import 'mutex.dart';
+import 'util.dart';
 import 'gesture/pointer_device_kind.dart';
 
 import 'native/drag.dart' if (dart.library.js) 'web/drag.dart';
@@ -18,17 +20,26 @@ class DragConfiguration {
     required this.allowedOperations,
     this.animatesToStartingPositionOnCancelOrFail = true,
     this.prefersFullSizePreviews = false,
+    this.sessionLocalData,
   });
 
   final List<DragItem> items;
   final List<DropOperation> allowedOperations;
 
-  /// macOS specific
+  /// When `false` the platform snap-back animation is suppressed on macOS,
+  /// iOS, Linux and Android (API 31+) and [DragSession.cancelledAtLocation]
+  /// reports the final pointer location so that the application can run its
+  /// own cancel animation.
   final bool animatesToStartingPositionOnCancelOrFail;
 
   /// iOS specific
   final bool prefersFullSizePreviews;
 
+  /// Payload delivered to drop targets of this application as
+  /// [DropEvent.sessionLocalData], including other windows. Unlike item data
+  /// it never reaches the platform clipboard.
+  final Object? sessionLocalData;
+
   DragConfiguration clone() {
     return DragConfiguration(
       items: items.map((e) => e).toList(),
@@ -36,6 +47,7 @@ class DragConfiguration {
       animatesToStartingPositionOnCancelOrFail:
           animatesToStartingPositionOnCancelOrFail,
       prefersFullSizePreviews: prefersFullSizePreviews,
+      sessionLocalData: sessionLocalData,
     );
   }
 
@@ -52,6 +64,7 @@ class DragItem {
     required this.image,
     required this.liftImage,
     this.localData,
+    this.imageTexture,
   });
 
   final DataProviderHandle dataProvider;
@@ -59,6 +72,11 @@ class DragItem {
   /// Image used while dragging
   TargetedWidgetSnapshot image;
 
+  /// When set, drag image pixels are copied from this texture on native
+  /// side instead of reading back [image]; [image] still provides the
+  /// image rect. Only used if kind matches [DragContext.sharedTextureKind].
+  final SharedTexture? imageTexture;
+
   /// If specified this image will be used for lift animation on iOS and Android.
   TargetedWidgetSnapshot? liftImage;
 
@@ -75,11 +93,211 @@ class DragRequest {
     required this.configuration,
     required this.position,
     this.combinedDragImage,
+    this.engineHandle,
+    this.deviceKind,
   });
 
   final DragConfiguration configuration;
   final ui.Offset position;
   final TargetedImageData? combinedDragImage;
+
+  /// View to start the drag from, see
+  /// [DragContext.registerDragSourceForView].
+  final int? engineHandle;
+
+  /// Kind of pointer that started the drag. On Windows drags started from
+  /// touch or stylus are driven by the contact instead of the mouse.
+  final PointerDeviceKind? deviceKind;
+}
+
+/// Thresholds of press-and-hold gesture starting drag from touch input.
+class TouchDragSettings {
+  const TouchDragSettings({
+    this.pressAndHoldDuration = const Duration(milliseconds: 300),
+    this.slop = kTouchSlop,
+    this.suppressSystemPressAndHold = true,
+  });
+
+  static TouchDragSettings deserialize(dynamic settings) {
+    final map = settings as Map;
+    return TouchDragSettings(
+      pressAndHoldDuration:
+          Duration(milliseconds: map['pressAndHoldDurationMs']),
+      slop: map['slop'],
+      suppressSystemPressAndHold: map['suppressSystemPressAndHold'],
+    );
+  }
+
+  dynamic serialize() => {
+        'pressAndHoldDurationMs': pressAndHoldDuration.inMilliseconds,
+        'slop': slop,
+        'suppressSystemPressAndHold': suppressSystemPressAndHold,
+      };
+
+  /// How long the contact must be held before the drag starts.
+  final Duration pressAndHoldDuration;
+
+  /// Maximum pointer movement during press-and-hold, in logical pixels.
+  final double slop;
+
+  /// Disables system press-and-hold feedback (the right click ring on
+  /// Windows), which would otherwise compete with the gesture.
+  final bool suppressSystemPressAndHold;
+
+  @override
+  bool operator ==(Object other) =>
+      other is TouchDragSettings &&
+      other.pressAndHoldDuration == pressAndHoldDuration &&
+      other.slop == slop &&
+      other.suppressSystemPressAndHold == suppressSystemPressAndHold;
+
+  @override
+  int get hashCode =>
+      Object.hash(pressAndHoldDuration, slop, suppressSystemPressAndHold);
+}
+
+/// Modifier keys held down. [meta] is Command on macOS and Windows key on
+/// Windows.
+class KeyModifiers {
+  KeyModifiers({
+    required this.shift,
+    required this.control,
+    required this.alt,
+    required this.meta,
+  });
+
+  static KeyModifiers deserialize(dynamic modifiers) {
+    final map = modifiers as Map;
+    return KeyModifiers(
+      shift: map['shift'],
+      control: map['control'],
+      alt: map['alt'],
+      meta: map['meta'],
+    );
+  }
+
+  final bool shift;
+  final bool control;
+  final bool alt;
+  final bool meta;
+}
+
+/// State of the dragging pointer, reported each time the drag moves.
+class DragSessionUpdate {
+  DragSessionUpdate({
+    required this.screenLocation,
+    required this.velocity,
+    required this.modifiers,
+    required this.allowedOperations,
+  });
+
+  static DragSessionUpdate deserialize(dynamic update) {
+    final map = update as Map;
+    final allowedOperations = map['allowedOperations'] as List?;
+    return DragSessionUpdate(
+      screenLocation: OffsetExt.deserialize(map['screenLocation']),
+      velocity: OffsetExt.deserialize(map['velocity']),
+      modifiers: KeyModifiers.deserialize(map['modifiers']),
+      allowedOperations: allowedOperations
+          ?.map((e) => DropOperation.values.byName(e))
+          .toList(growable: false),
+    );
+  }
+
+  final ui.Offset screenLocation;
+
+  /// Pointer velocity in logical pixels per second.
+  final ui.Offset velocity;
+
+  final KeyModifiers modifiers;
+
+  /// Operations allowed by the session narrowed down by items; `null` for
+  /// drags not started by this application.
+  final List<DropOperation>? allowedOperations;
+}
+
+/// Change of the environment the drag is happening in. Reported on macOS
+/// only. Navigating Spaces or hovering Mission Control can take much longer
+/// than usual before dropping.
+enum DragEnvironmentChange {
+  /// Pointer moved to another display.
+  screenChanged,
+
+  /// Active Space changed while dragging.
+  spaceChanged,
+  missionControlEntered,
+  missionControlExited,
+  stageManagerEntered,
+  stageManagerExited,
+}
+
+class DragEnvironmentEvent {
+  DragEnvironmentEvent({
+    required this.change,
+    required this.screenLocation,
+  });
+
+  static DragEnvironmentEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return DragEnvironmentEvent(
+      change: DragEnvironmentChange.values.byName(map['change']),
+      screenLocation: OffsetExt.deserialize(map['screenLocation']),
+    );
+  }
+
+  final DragEnvironmentChange change;
+  final ui.Offset screenLocation;
+}
+
+enum DataTransferState {
+  /// Drop target started reading data after the drag session ended.
+  started,
+  inProgress,
+  completed,
+  failed,
+}
+
+/// Progress of data transfer that continues after drop completed, such as
+/// Explorer copying virtual files in the background (Windows only).
+class DataTransferProgress {
+  DataTransferProgress({
+    required this.state,
+    this.fileIndex,
+    this.fraction,
+    this.bytesWritten,
+    this.totalBytes,
+  });
+
+  static DataTransferProgress deserialize(dynamic progress) {
+    final map = progress as Map;
+    return DataTransferProgress(
+      state: DataTransferState.values.byName(map['state']),
+      fileIndex: map['fileIndex'],
+      fraction: map['fraction'],
+      bytesWritten: map['bytesWritten'],
+      totalBytes: map['totalBytes'],
+    );
+  }
+
+  final DataTransferState state;
+
+  /// Virtual file being written, only set with [DataTransferState.inProgress].
+  final int? fileIndex;
+  final double? fraction;
+  final int? bytesWritten;
+  final int? totalBytes;
+}
+
+/// Why outgoing drag session ended without drop.
+enum DragCancelReason {
+  /// User pressed escape.
+  escape,
+
+  /// Released over location that doesn't accept the drop.
+  noTarget,
+
+  /// Drop was attempted but rejected by target.
+  targetRefused,
 }
 
 /// Represents a drag session. Allows inspecting local drag data and
@@ -96,15 +314,48 @@ abstract class DragSession {
   /// drag finished with.
   ValueListenable<DropOperation?> get dragCompleted;
 
+  /// Set before [dragCompleted] fires when the session ended without drop
+  /// and the platform reported why; `null` otherwise.
+  DragCancelReason? get cancelReason;
+
+  /// Operation effectively performed on each item, given operations allowed
+  /// for the item. Set before [dragCompleted] fires; `null` on web.
+  List<DropOperation>? get itemOperations;
+
   /// Updated when drag session moves. On mobile and web you will only
   /// get notified when moving over application Window.
   /// On desktop platforms the notification covers entire screen.
   ValueListenable<ui.Offset?> get lastScreenLocation;
 
+  /// Updated together with [lastScreenLocation], and when modifier keys
+  /// change during drag on Windows. Not reported on web.
+  ValueListenable<DragSessionUpdate?> get lastUpdate;
+
+  /// Last change of the drag environment, see [DragEnvironmentChange].
+  ValueListenable<DragEnvironmentEvent?> get lastEnvironmentChange;
+
+  /// Set to final pointer location when drag was cancelled or failed with
+  /// [DragConfiguration.animatesToStartingPositionOnCancelOrFail] disabled.
+  /// Fired before [dragCompleted].
+  ValueListenable<ui.Offset?> get cancelledAtLocation;
+
+  /// Updated after [dragCompleted] while drop target is still reading the
+  /// data in background. Only reported on Windows.
+  ValueListenable<DataTransferProgress?> get dataTransferProgress;
+
   /// Returns local data for each of the draggable items in current session.
   /// Will return `null` if drag session not local, not yet active or already
   /// completed.
   Future<List<Object?>?> getLocalData();
+
+  /// Replaces drag image of each item while the session is in progress.
+  /// Rects are in the same coordinates as when the drag was started.
+  /// [combinedDragImage] is used instead on platforms that need it; when not
+  /// provided it is combined from [images]. Not supported on Android and web.
+  Future<void> updateDragImage(
+    List<TargetedWidgetSnapshot> images, {
+    TargetedWidgetSnapshot? combinedDragImage,
+  });
 }
 
 abstract class DragContextDelegate {
@@ -145,14 +396,41 @@ abstract class DragContext {
     });
   }
 
+  /// Kind of texture drag images can be imported from on this platform,
+  /// if any. See [DragItem.imageTexture].
+  Future<SharedTextureKind?> sharedTextureKind();
+
   DragSession newSession({int? pointer});
   void cancelSession(DragSession session);
 
+  /// Drag is started from view with [engineHandle], or from the view of
+  /// this isolate's engine if not specified.
   Future<void> startDrag({
     required BuildContext buildContext,
     required DragSession session,
     required DragConfiguration configuration,
     required ui.Offset position,
     TargetedWidgetSnapshot? combinedDragImage,
+    int? engineHandle,
+    PointerDeviceKind? deviceKind,
   });
+
+  /// Allows starting drags from additional view, i.e. a secondary window
+  /// served by its own engine. View of this isolate's engine is registered
+  /// on initialization.
+  Future<void> registerDragSourceForView(int engineHandle);
+
+  Future<void> unregisterDragSourceForView(int engineHandle);
+
+  /// Thresholds used by the press-and-hold gesture starting drags from touch
+  /// input.
+  ValueListenable<TouchDragSettings> get touchDragSettings =>
+      touchDragSettingsNotifier;
+
+  @protected
+  final touchDragSettingsNotifier = ValueNotifier(const TouchDragSettings());
+
+  Future<TouchDragSettings> getTouchDragSettings();
+
+  Future<void> setTouchDragSettings(TouchDragSettings settings);
 }
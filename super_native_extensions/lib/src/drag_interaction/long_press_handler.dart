@@ -1,6 +1,7 @@
 import 'dart:async';
 
 import 'package:flutter/foundation.dart';
+import 'package:flutter/gestures.dart';
 import 'package:flutter/widgets.dart';
 
 import 'package:device_info_plus/device_info_plus.dart';
@@ -234,7 +235,8 @@ class LongPressHandler {
                 session: realSession,
                 configuration: dragConfiguration,
                 position: offset,
-                combinedDragImage: targetedImage),
+                combinedDragImage: targetedImage,
+                deviceKind: PointerDeviceKind.touch),
           );
         }
       };
@@ -364,6 +366,10 @@ class _FakeDragSession extends DragSession {
   final _dragCompleted = ValueNotifier<DropOperation?>(null);
   final _dragging = ValueNotifier<bool>(false);
   final _lastScreenLocation = ValueNotifier<Offset?>(null);
+  final _lastUpdate = ValueNotifier<DragSessionUpdate?>(null);
+  final _lastEnvironmentChange = ValueNotifier<DragEnvironmentEvent?>(null);
+  final _cancelledAtLocation = ValueNotifier<Offset?>(null);
+  final _dataTransferProgress = ValueNotifier<DataTransferProgress?>(null);
 
   @override
   ValueListenable<DropOperation?> get dragCompleted => _dragCompleted;
@@ -371,15 +377,49 @@ class _FakeDragSession extends DragSession {
   @override
   ValueListenable<bool> get dragging => _dragging;
 
+  @override
+  DragCancelReason? get cancelReason => original?.cancelReason;
+
+  @override
+  List<DropOperation>? get itemOperations => original?.itemOperations;
+
   @override
   ValueListenable<Offset?> get lastScreenLocation => _lastScreenLocation;
 
+  @override
+  ValueListenable<DragSessionUpdate?> get lastUpdate => _lastUpdate;
+
+  @override
+  ValueListenable<DragEnvironmentEvent?> get lastEnvironmentChange =>
+      _lastEnvironmentChange;
+
+  @override
+  ValueListenable<Offset?> get cancelledAtLocation => _cancelledAtLocation;
+
+  @override
+  ValueListenable<DataTransferProgress?> get dataTransferProgress =>
+      _dataTransferProgress;
+
   void startDrag(DragSession original) {
     this.original = original;
     original.dragCompleted.addListener(_originalDragCompleted);
     original.lastScreenLocation.addListener(() {
       _lastScreenLocation.value = original.lastScreenLocation.value;
     });
+    original.lastUpdate.addListener(() {
+      _lastUpdate.value = original.lastUpdate.value;
+    });
+    original.lastEnvironmentChange.addListener(() {
+      _lastEnvironmentChange.value = original.lastEnvironmentChange.value;
+    });
+    original.cancelledAtLocation.addListener(() {
+      _cancelledAtLocation.value = original.cancelledAtLocation.value;
+    });
+    original.dataTransferProgress.addListener(_originalDataTransferProgress);
+  }
+
+  void _originalDataTransferProgress() {
+    _dataTransferProgress.value = original!.dataTransferProgress.value;
   }
 
   void _originalDragCompleted() {
@@ -389,9 +429,15 @@ class _FakeDragSession extends DragSession {
 
   void _dispose() {
     original?.dragCompleted.removeListener(_originalDragCompleted);
+    original?.dataTransferProgress
+        .removeListener(_originalDataTransferProgress);
     _dragCompleted.dispose();
     _dragging.dispose();
     _lastScreenLocation.dispose();
+    _lastUpdate.dispose();
+    _lastEnvironmentChange.dispose();
+    _cancelledAtLocation.dispose();
+    _dataTransferProgress.dispose();
   }
 
   void beginDragging() {
@@ -409,4 +455,16 @@ class _FakeDragSession extends DragSession {
   Future<List<Object?>?> getLocalData() {
     return original?.getLocalData() ?? Future.value(null);
   }
+
+  @override
+  Future<void> updateDragImage(
+    List<TargetedWidgetSnapshot> images, {
+    TargetedWidgetSnapshot? combinedDragImage,
+  }) {
+    return original?.updateDragImage(
+          images,
+          combinedDragImage: combinedDragImage,
+        ) ??
+        Future.value();
+  }
 }
@@ -6,27 +6,28 @@ import 'drag.dart';
 import 'image_data.dart';
 import 'widget_snapshot/widget_snapshot.dart';
 
-Future<TargetedImageData> combineDragImage(
-    DragConfiguration configuration) async {
+Future<TargetedImageData> combineDragImage(DragConfiguration configuration) =>
+    combineImages(configuration.items.map((e) => e.image).toList());
+
+Future<TargetedImageData> combineImages(
+    List<TargetedWidgetSnapshot> images) async {
   var combinedRect = Rect.zero;
-  for (final item in configuration.items) {
+  for (final item in images) {
     if (combinedRect.isEmpty) {
-      combinedRect = item.image.rect;
+      combinedRect = item.rect;
     } else {
-      combinedRect = combinedRect.expandToInclude(item.image.rect);
+      combinedRect = combinedRect.expandToInclude(item.rect);
     }
   }
-  final scale =
-      configuration.items.firstOrNull?.image.snapshot.image.devicePixelRatio ??
-          1.0;
+  final scale = images.firstOrNull?.snapshot.image.devicePixelRatio ?? 1.0;
   final offset = combinedRect.topLeft;
   final rect = combinedRect.translate(-offset.dx, -offset.dy);
   final recorder = PictureRecorder();
   final canvas = Canvas(recorder);
   canvas.scale(scale, scale);
-  for (final item in configuration.items) {
-    final image = item.image.snapshot;
-    final destinationRect = item.image.rect.translate(-offset.dx, -offset.dy);
+  for (final item in images) {
+    final image = item.snapshot;
+    final destinationRect = item.rect.translate(-offset.dx, -offset.dy);
     canvas.drawImageRect(
         image.image,
         Rect.fromLTWH(
@@ -0,0 +1,85 @@
+import 'package:flutter/foundation.dart';
+
+import 'native/drag_monitor.dart' if (dart.library.js) 'web/drag_monitor.dart';
+
+/// How the application is involved in a drag session.
+enum DragRole {
+  /// Drag was started by this application.
+  source,
+
+  /// Drag (possibly from another application) is over one of application
+  /// windows.
+  target,
+}
+
+class DragSessionInfo {
+  DragSessionInfo({
+    required this.role,
+    required this.sessionId,
+    required this.itemCount,
+    required this.formats,
+  });
+
+  static DragSessionInfo deserialize(dynamic info) {
+    final map = info as Map;
+    return DragSessionInfo(
+      role: DragRole.values.byName(map['role']),
+      sessionId: map['sessionId'],
+      itemCount: map['itemCount'],
+      formats: (map['formats'] as List).cast<String>(),
+    );
+  }
+
+  final DragRole role;
+
+  /// Drag session id for source, drop session id for target.
+  final int sessionId;
+
+  /// Number of dragged items, if known.
+  final int? itemCount;
+
+  /// Formats of dragged items. Only known for target sessions.
+  final List<String> formats;
+}
+
+enum DragActivityEventType {
+  started,
+  updated,
+  ended,
+}
+
+class DragActivityEvent {
+  DragActivityEvent({
+    required this.eventType,
+    required this.info,
+  });
+
+  static DragActivityEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return DragActivityEvent(
+      eventType: DragActivityEventType.values.byName(map['eventType']),
+      info: DragSessionInfo.deserialize(map['info']),
+    );
+  }
+
+  final DragActivityEventType eventType;
+  final DragSessionInfo info;
+}
+
+/// Keeps track of all drag sessions involving the application, both started
+/// by the application and hovering over its windows.
+abstract class DragMonitor {
+  static final DragMonitor instance = DragMonitorImpl();
+
+  /// Whether there is any active drag session involving the application.
+  Future<bool> isDragInProgress();
+
+  /// Most recently started session that is still active.
+  Future<DragSessionInfo?> currentDragSessionInfo();
+
+  /// Listeners are notified when drag session starts, changes or ends.
+  /// Monitoring is active while there is at least one listener.
+  void addListener(ValueChanged<DragActivityEvent> listener);
+
+  void removeListener(ValueChanged<DragActivityEvent> listener);
+}
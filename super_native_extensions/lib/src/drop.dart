@@ -36,14 +36,20 @@ enum DropOperation {
 class BaseDropEvent {
   BaseDropEvent({
     required this.sessionId,
+    this.engineHandle,
   });
 
   @override
   String toString() => {
         'sessionId': sessionId,
+        'engineHandle': engineHandle,
       }.toString();
 
   final int sessionId;
+
+  /// Engine handle of the view the event is for, see
+  /// [DropContext.registerDropTargetForView]. `null` on web.
+  final int? engineHandle;
 }
 
 class DropItem {
@@ -70,10 +76,14 @@ class DropItem {
 class DropEvent extends BaseDropEvent {
   DropEvent({
     required super.sessionId,
+    super.engineHandle,
     required this.locationInView,
     required this.allowedOperations,
     required this.items,
     this.acceptedOperation,
+    this.regionId,
+    this.sessionLocalData,
+    this.localSessionId,
   });
 
   final ui.Offset locationInView;
@@ -81,6 +91,18 @@ class DropEvent extends BaseDropEvent {
   final List<DropItem> items;
   final DropOperation? acceptedOperation;
 
+  /// Region set through [DropContext.setDropRegions] the drag has entered.
+  /// Updates within the same region reuse the returned operation and are
+  /// not delivered.
+  final String? regionId;
+
+  /// [DragConfiguration.sessionLocalData] of the drag session if it was
+  /// started by this application.
+  final Object? sessionLocalData;
+
+  /// Drag session of this application the drop comes from, if any.
+  final int? localSessionId;
+
   @override
   String toString() => {
         'sessionId': sessionId,
@@ -89,6 +111,9 @@ class DropEvent extends BaseDropEvent {
         'allowedOperation':
             allowedOperations.map((e) => e.name).toList(growable: false),
         'acceptedOperation': acceptedOperation?.name,
+        'regionId': regionId,
+        'sessionLocalData': sessionLocalData,
+        'localSessionId': localSessionId,
       }.toString();
 }
 
@@ -150,6 +175,275 @@ class ItemPreviewRequest {
   final Duration fadeOutDuration;
 }
 
+enum DropAcceptanceMode {
+  /// Platform drop handler waits until [DropContextDelegate.onPerformDrop]
+  /// finishes.
+  synchronous,
+
+  /// Drop is acknowledged to the source immediately and a busy indicator
+  /// is shown until [DropContextDelegate.onPerformDrop] finishes. Falls back
+  /// to [synchronous] on platforms where drag data becomes inaccessible
+  /// after the drop.
+  deferred,
+}
+
+class DropManifestRegion {
+  DropManifestRegion({
+    required this.id,
+    required this.rect,
+    this.formats,
+  });
+
+  final String id;
+
+  /// In view coordinates.
+  final ui.Rect rect;
+
+  /// Formats accepted by the region. Formats of the view if not set; must be
+  /// a subset of them otherwise.
+  final List<String>? formats;
+
+  Map serialize() => {
+        'id': id,
+        'rect': rect.serialize(),
+        'formats': formats,
+      };
+}
+
+class DropManifestView {
+  DropManifestView({
+    required this.engineHandle,
+    required this.formats,
+    this.regions,
+  });
+
+  /// Engine handle of the view, see [BaseDropEvent.engineHandle].
+  final int engineHandle;
+  final List<String> formats;
+
+  /// When set, drags outside of all regions (or over a region that accepts
+  /// none of the dragged formats) are rejected without asking the delegate.
+  final List<DropManifestRegion>? regions;
+
+  Map serialize() => {
+        'engineHandle': engineHandle,
+        'formats': formats,
+        'regions': regions?.map((r) => r.serialize()).toList(growable: false),
+      };
+}
+
+class DropManifestPolicies {
+  DropManifestPolicies({
+    this.acceptanceMode,
+    this.analyticsEnabled,
+  });
+
+  final DropAcceptanceMode? acceptanceMode;
+  final bool? analyticsEnabled;
+
+  Map serialize() => {
+        'acceptanceMode': acceptanceMode?.name,
+        'analyticsEnabled': analyticsEnabled,
+      };
+}
+
+/// Declarative configuration of all drop targets, see
+/// [DropContext.setDropManifest].
+class DropManifest {
+  DropManifest({
+    required this.views,
+    this.policies,
+  });
+
+  final List<DropManifestView> views;
+  final DropManifestPolicies? policies;
+
+  Map serialize() => {
+        'views': views.map((v) => v.serialize()).toList(growable: false),
+        'policies': policies?.serialize(),
+      };
+}
+
+/// Region hit-tested by native code, see [DropContext.setDropRegions].
+class DropRegion {
+  /// Either [rect] or [path] must be set.
+  DropRegion({
+    required this.id,
+    this.rect,
+    this.path,
+    this.formats,
+    this.springLoaded = false,
+    this.springLoadDelay,
+  }) : assert((rect == null) != (path == null));
+
+  final String id;
+
+  /// In view coordinates.
+  final ui.Rect? rect;
+
+  /// Closed polygon in view coordinates, evaluated with the even-odd rule.
+  final List<ui.Offset>? path;
+
+  /// Formats accepted by the region. Any format if not set.
+  final List<String>? formats;
+
+  /// Whether [DropContext.onSpringLoadActivated] fires after the drag hovers
+  /// over the region for [springLoadDelay].
+  final bool springLoaded;
+
+  /// Half a second if not set. Must not be negative.
+  final Duration? springLoadDelay;
+
+  Map serialize() => {
+        'id': id,
+        'shape': path != null
+            ? {
+                'type': 'path',
+                'content': path!.map((p) => p.serialize()).toList(),
+              }
+            : {
+                'type': 'rect',
+                'content': rect!.serialize(),
+              },
+        'formats': formats,
+        'springLoaded': springLoaded,
+        'springLoadDelay': springLoadDelay != null
+            ? springLoadDelay!.inMicroseconds / Duration.microsecondsPerSecond
+            : null,
+      };
+}
+
+class DropRegionEvent {
+  DropRegionEvent({
+    required this.sessionId,
+    required this.engineHandle,
+    required this.regionId,
+  });
+
+  static DropRegionEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return DropRegionEvent(
+      sessionId: map['sessionId'],
+      engineHandle: map['engineHandle'],
+      regionId: map['regionId'],
+    );
+  }
+
+  final int sessionId;
+  final int engineHandle;
+  final String regionId;
+}
+
+enum AutoScrollAxis { vertical, horizontal, both }
+
+/// Scrollable region of a view, see [DropContext.setAutoScrollRegions].
+class AutoScrollRegion {
+  AutoScrollRegion({
+    required this.id,
+    required this.rect,
+    this.axis = AutoScrollAxis.vertical,
+    this.edgeSize,
+    this.maxVelocity,
+  });
+
+  final String id;
+
+  /// In view coordinates.
+  final ui.Rect rect;
+  final AutoScrollAxis axis;
+
+  /// Distance from edge in which scrolling starts, 40 if not set.
+  final double? edgeSize;
+
+  /// In points per second, reached at the edge. 1200 if not set.
+  final double? maxVelocity;
+
+  Map serialize() => {
+        'id': id,
+        'rect': rect.serialize(),
+        'axis': axis.name,
+        'edgeSize': edgeSize,
+        'maxVelocity': maxVelocity,
+      };
+}
+
+class AutoScrollEvent {
+  AutoScrollEvent({
+    required this.sessionId,
+    required this.engineHandle,
+    required this.regionId,
+    required this.velocity,
+    required this.delta,
+  });
+
+  static AutoScrollEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return AutoScrollEvent(
+      sessionId: map['sessionId'],
+      engineHandle: map['engineHandle'],
+      regionId: map['regionId'],
+      velocity: OffsetExt.deserialize(map['velocity']),
+      delta: OffsetExt.deserialize(map['delta']),
+    );
+  }
+
+  final int sessionId;
+  final int engineHandle;
+  final String regionId;
+
+  /// In points per second. Zero when scrolling stops.
+  final ui.Offset velocity;
+
+  /// Distance to scroll by for this event.
+  final ui.Offset delta;
+}
+
+/// Result of [DropContextDelegate.onPerformDrop] for drop acknowledged in
+/// [DropAcceptanceMode.deferred] mode.
+class DeferredDropResult {
+  DeferredDropResult({
+    required this.sessionId,
+    required this.success,
+    this.error,
+  });
+
+  final int sessionId;
+  final bool success;
+  final String? error;
+}
+
+class RejectedFormatCount {
+  RejectedFormatCount({
+    required this.format,
+    required this.count,
+  });
+
+  final String format;
+  final int count;
+}
+
+/// Aggregate statistics about drag sessions that entered the view.
+class DropAnalyticsReport {
+  DropAnalyticsReport({
+    required this.sessionsEntered,
+    required this.sessionsDropped,
+    required this.sessionsAbandoned,
+    required this.averageHoverTime,
+    required this.rejectedFormats,
+  });
+
+  final int sessionsEntered;
+  final int sessionsDropped;
+
+  /// Sessions that entered the view but left or were cancelled.
+  final int sessionsAbandoned;
+  final Duration averageHoverTime;
+
+  /// Formats offered by sessions that were never accepted, most frequent
+  /// first.
+  final List<RejectedFormatCount> rejectedFormats;
+}
+
 abstract class DropContextDelegate {
   Future<DropOperation> onDropUpdate(DropEvent event);
   Future<void> onPerformDrop(DropEvent event);
@@ -174,7 +468,70 @@ abstract class DropContext {
   @protected
   Future<void> initialize();
 
-  Future<void> registerDropFormats(List<String> formats);
+  /// Registers formats accepted by view with [engineHandle], or by the
+  /// view of this isolate's engine if not specified.
+  Future<void> registerDropFormats(List<String> formats, {int? engineHandle});
+
+  /// Starts receiving drops into additional view, i.e. a secondary window
+  /// served by its own engine. Events for the view carry its
+  /// [BaseDropEvent.engineHandle]. View of this isolate's engine is
+  /// registered on initialization.
+  Future<void> registerDropTargetForView(int engineHandle);
+
+  Future<void> unregisterDropTargetForView(int engineHandle);
+
+  Future<void> setDropAcceptanceMode(DropAcceptanceMode mode);
+
+  /// Replaces drop configuration of all views described by [manifest].
+  /// Manifest is kept by native code across hot restarts and applied to
+  /// views as soon as they are registered. Throws if the manifest is invalid
+  /// or can not be applied, in which case previous manifest stays in effect.
+  Future<void> setDropManifest(DropManifest manifest);
+
+  /// Replaces regions of view with [engineHandle], or of the view of this
+  /// isolate's engine if not specified. Later regions are on top of earlier
+  /// ones. Empty list removes the regions. Not supported on web.
+  Future<void> setDropRegions(List<DropRegion> regions, {int? engineHandle});
+
+  /// Invoked when drag leaves region it entered, including when it moves
+  /// directly into another region.
+  void Function(DropRegionEvent event)? onDropRegionLeave;
+
+  /// Replaces scrollable regions of view with [engineHandle], or of the view
+  /// of this isolate's engine if not specified. While drag hovers near an
+  /// edge of a region, [onAutoScroll] is invoked at fixed interval. Empty
+  /// list removes the regions. Not supported on web.
+  Future<void> setAutoScrollRegions(
+    List<AutoScrollRegion> regions, {
+    int? engineHandle,
+  });
+
+  /// Invoked while drag scrolls a region, and once with zero velocity when
+  /// scrolling stops.
+  void Function(AutoScrollEvent event)? onAutoScroll;
+
+  /// Invoked once drag hovers over spring loaded region for its
+  /// [DropRegion.springLoadDelay], i.e. to open a folder under the pointer.
+  void Function(DropRegionEvent event)? onSpringLoadActivated;
+
+  /// Invoked after [DropContextDelegate.onPerformDrop] finishes for drops
+  /// acknowledged in [DropAcceptanceMode.deferred] mode.
+  void Function(DeferredDropResult result)? onDeferredDropCompleted;
+
+  /// Enables or disables collecting [DropAnalyticsReport]s. Disabling
+  /// discards collected statistics.
+  Future<void> setAnalyticsEnabled(bool enabled);
+
+  /// Returns statistics collected so far or `null` if analytics is not
+  /// enabled.
+  Future<DropAnalyticsReport?> getAnalytics();
+
+  /// Discards statistics collected so far.
+  Future<void> resetAnalytics();
+
+  /// Updated with current statistics every time a session ends while
+  /// analytics is enabled.
+  ValueListenable<DropAnalyticsReport?> get analytics;
 
   DropContextDelegate? delegate;
 
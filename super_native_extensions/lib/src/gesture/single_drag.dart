@@ -124,6 +124,7 @@ class SingleDragDelayedGestureRecognizer extends LongPressGestureRecognizer {
     super.debugOwner,
     super.supportedDevices,
     super.postAcceptSlopTolerance,
+    this.slop = kTouchSlop,
   }) {
     assert(beginDuration < super.deadline!);
     onLongPressDown = _onLongPressDown;
@@ -136,6 +137,12 @@ class SingleDragDelayedGestureRecognizer extends LongPressGestureRecognizer {
   /// Duration after which the lifting begins.
   final Duration beginDuration;
 
+  /// Maximum pointer movement before the long press is recognized.
+  final double slop;
+
+  @override
+  double? get preAcceptSlopTolerance => slop;
+
   /// Called at the start of the gesture.
   SingleDrag? Function(Offset globalPosition)? onDragStart;
 
@@ -1,18 +1,61 @@
+import 'desktop_capability.dart';
 import 'native/hot_key.dart' if (dart.library.js) 'web/hot_key.dart';
 
+/// Keys without stable platform code across keyboards. Keys the platform
+/// can not register fail hot key creation: there is no media stop key on
+/// macOS and no keys beyond F20. Media keys on macOS can only be swallowed
+/// while the application is active.
+enum SpecialKey {
+  mediaPlayPause,
+  mediaStop,
+  mediaTrackNext,
+  mediaTrackPrevious,
+  audioVolumeUp,
+  audioVolumeDown,
+  audioVolumeMute,
+  f13,
+  f14,
+  f15,
+  f16,
+  f17,
+  f18,
+  f19,
+  f20,
+  f21,
+  f22,
+  f23,
+  f24,
+}
+
 class HotKeyDefinition {
+  /// Ignored when [specialKey] is set.
   final int platformCode;
   final bool alt;
   final bool shift;
   final bool meta;
   final bool control;
 
+  final SpecialKey? specialKey;
+
+  /// When the key is held, only the first press is reported. Otherwise
+  /// auto-repeated presses are reported as well, where the platform delivers
+  /// them.
+  final bool suppressRepeat;
+
+  /// Windows only: detect the hot key through low level keyboard hook
+  /// instead of `RegisterHotKey`. Hooked hot keys can be bound to keys that
+  /// can not be registered and report key up immediately.
+  final bool lowLevelHook;
+
   HotKeyDefinition({
     required this.platformCode,
     required this.alt,
     required this.shift,
     required this.meta,
     required this.control,
+    this.specialKey,
+    this.suppressRepeat = true,
+    this.lowLevelHook = false,
   });
 
   dynamic serialize() => {
@@ -21,12 +64,45 @@ class HotKeyDefinition {
         'shift': shift,
         'meta': meta,
         'control': control,
+        'specialKey': specialKey?.name,
+        'suppressRepeat': suppressRepeat,
+        'lowLevelHook': lowLevelHook,
       };
 }
 
+class HotKeyProfileFailure {
+  HotKeyProfileFailure({
+    required this.index,
+    required this.message,
+  });
+
+  /// Index of the hot key in profile definition.
+  final int index;
+  final String message;
+}
+
+class HotKeyProfileActivation {
+  HotKeyProfileActivation({
+    required this.activated,
+    required this.handles,
+    required this.failures,
+  });
+
+  /// Whether the profile became active.
+  final bool activated;
+
+  /// Handles corresponding to hot keys in profile definition; `null` for
+  /// hot keys that failed to register. Empty when profile was not activated.
+  final List<int?> handles;
+  final List<HotKeyProfileFailure> failures;
+}
+
 abstract class HotKeyManagerDelegate {
   /// Invoked when hot key with given handle is pressed.
   void onHotKeyPressed(int handle);
+
+  /// Invoked when hot key is released. Hot keys registered without low level
+  /// hook on Windows are polled for release and reported with a short delay.
   void onHotKeyReleased(int handle);
 }
 
@@ -42,5 +118,29 @@ abstract class HotKeyManager {
   /// Destroys hot key with given handle;
   Future<void> destroyHotKey(int handle);
 
+  /// Defines (or replaces) named set of hot keys that can be activated
+  /// at once with [activateHotKeyProfile].
+  Future<void> defineHotKeyProfile(
+      String name, List<HotKeyDefinition> hotKeys);
+
+  /// Makes profile with given name active, replacing previously active
+  /// profile. Hot keys present in both profiles keep their handle. With
+  /// [rollbackOnFailure] the previous profile stays active if any hot key
+  /// of the new profile fails to register.
+  Future<HotKeyProfileActivation> activateHotKeyProfile(
+    String name, {
+    bool rollbackOnFailure = false,
+  });
+
+  /// Releases hot keys of currently active profile.
+  Future<void> deactivateHotKeyProfile();
+
+  /// Removes profile definition, deactivating it first if active.
+  Future<void> removeHotKeyProfile(String name);
+
+  /// Whether global hot keys are supported in current session and which
+  /// backend is used. On Linux hot keys are only supported on X11.
+  Future<DesktopFeatureCapability> getCapability();
+
   set delegate(HotKeyManagerDelegate? delegate);
 }
@@ -65,14 +65,45 @@ class ImageData {
   }
 }
 
+/// Kind of GPU texture handle the platform can import drag images from.
+enum SharedTextureKind {
+  /// Global IOSurface ID (macOS, iOS).
+  ioSurface,
+
+  /// DXGI shared resource handle (Windows).
+  dxgiSharedHandle,
+
+  /// Handle returned by `SuperNativeExtensionsPlugin.registerHardwareBuffer`
+  /// (Android).
+  hardwareBuffer,
+}
+
+/// Texture already rendered by the engine that is shared with native side,
+/// so that pixels don't need to be read back in Dart. Texture must stay
+/// alive until drag starts.
+class SharedTexture {
+  SharedTexture({
+    required this.kind,
+    required this.handle,
+    this.devicePixelRatio,
+  });
+
+  final SharedTextureKind kind;
+  final int handle;
+  final double? devicePixelRatio;
+}
+
 class TargetedImageData {
   TargetedImageData({
     required this.imageData,
     required this.rect,
+    this.sharedTexture,
   });
 
+  /// Ignored when [sharedTexture] is set.
   final ImageData imageData;
   final Rect rect;
+  final SharedTexture? sharedTexture;
 }
 
 extension TargetedImageIntoRaw on TargetedWidgetSnapshot {
@@ -22,6 +22,100 @@ abstract class KeyboardLayoutManager {
 
   /// Event fired when current system keyboard layout changes.
   Listenable get onLayoutChanged;
+
+  /// Returns characters produced by each key of current keyboard layout or
+  /// `null` if not supported on this platform.
+  Future<KeyboardLayoutSnapshot?> getSnapshot();
+
+  /// Returns layout selected by the user together with physical key for each
+  /// logical key or `null` if not supported on this platform.
+  Future<CurrentKeyboardLayout?> getCurrentKeyboardLayout();
+
+  /// Registers listener called with new layout when user selects a different
+  /// keyboard layout. Changes are monitored only while a listener is
+  /// registered.
+  void addCurrentLayoutListener(CurrentKeyboardLayoutListener listener);
+
+  void removeCurrentLayoutListener(CurrentKeyboardLayoutListener listener);
+}
+
+typedef CurrentKeyboardLayoutListener = void Function(
+    CurrentKeyboardLayout? layout);
+
+/// Physical key that produces a logical key in current keyboard layout.
+class LogicalKeyMapping {
+  LogicalKeyMapping({
+    required this.logical,
+    required this.physical,
+    required this.shift,
+  });
+
+  static LogicalKeyMapping deserialize(dynamic mapping) {
+    final map = mapping as Map;
+    return LogicalKeyMapping(
+      logical: map['logical'],
+      physical: map['physical'],
+      shift: map['shift'],
+    );
+  }
+
+  final int logical;
+  final int physical;
+
+  /// Whether the logical key requires shift.
+  final bool shift;
+}
+
+class CurrentKeyboardLayout {
+  CurrentKeyboardLayout({
+    required this.name,
+    required this.keys,
+  });
+
+  static CurrentKeyboardLayout? deserialize(dynamic layout) {
+    if (layout == null) {
+      return null;
+    }
+    final map = layout as Map;
+    return CurrentKeyboardLayout(
+      name: map['name'],
+      keys: (map['keys'] as List)
+          .map(LogicalKeyMapping.deserialize)
+          .toList(growable: false),
+    );
+  }
+
+  /// Layout selected by the user: localized name on macOS, layout identifier
+  /// (KLID) on Windows and XKB layout on X11.
+  final String? name;
+  final List<LogicalKeyMapping> keys;
+}
+
+/// Characters produced by a physical key.
+class KeySnapshot {
+  KeySnapshot({
+    required this.platform,
+    required this.physical,
+    this.logical,
+    this.character,
+    this.shiftedCharacter,
+    this.altGrCharacter,
+    this.altGrShiftedCharacter,
+  });
+
+  final int platform;
+  final int physical;
+  final int? logical;
+  final String? character;
+  final String? shiftedCharacter;
+  final String? altGrCharacter;
+  final String? altGrShiftedCharacter;
+}
+
+class KeyboardLayoutSnapshot {
+  KeyboardLayoutSnapshot({required this.keys});
+
+  final List<KeySnapshot> keys;
 }
 
 /// Represents a keyboard layout. Allows converting between platform specific
@@ -0,0 +1,156 @@
+import 'dart:typed_data';
+
+import 'package:flutter/foundation.dart';
+
+import 'clipboard_reader.dart';
+import 'clipboard_writer.dart';
+import 'data_provider.dart';
+import 'reader.dart';
+
+import 'native/lan_clipboard_sync.dart'
+    if (dart.library.js) 'web/lan_clipboard_sync.dart';
+
+class LanPeer {
+  LanPeer({
+    required this.id,
+    required this.name,
+  });
+
+  static LanPeer deserialize(dynamic peer) {
+    final map = peer as Map;
+    return LanPeer(id: map['id'], name: map['name']);
+  }
+
+  /// Identifier unique on the network.
+  final String id;
+
+  /// Device name the peer started synchronization with.
+  final String name;
+}
+
+class LanRepresentation {
+  LanRepresentation({
+    required this.format,
+    required this.data,
+  });
+
+  static LanRepresentation deserialize(dynamic representation) {
+    final map = representation as Map;
+    return LanRepresentation(format: map['format'], data: map['data']);
+  }
+
+  Map serialize() => {
+        'format': format,
+        'data': data,
+      };
+
+  final String format;
+
+  /// [String] or [Uint8List]; other values are not sent.
+  final Object data;
+}
+
+class LanClipboardReceivedEvent {
+  LanClipboardReceivedEvent({
+    required this.senderName,
+    required this.items,
+  });
+
+  static LanClipboardReceivedEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return LanClipboardReceivedEvent(
+      senderName: map['senderName'],
+      items: (map['items'] as List)
+          .map((item) => (item as List)
+              .map(LanRepresentation.deserialize)
+              .toList(growable: false))
+          .toList(growable: false),
+    );
+  }
+
+  final String senderName;
+  final List<List<LanRepresentation>> items;
+}
+
+/// Shares clipboard contents with other instances of the application on the
+/// local network. Requires the `lan_sync` cargo feature; without it all
+/// methods fail.
+///
+/// Instances discover each other over mDNS and only exchange data with peers
+/// started with the same [key], which must be distributed out of band (for
+/// example by pairing through QR code).
+abstract class LanClipboardSync {
+  static final LanClipboardSync instance = LanClipboardSyncImpl();
+
+  /// Starts advertising this instance and discovering peers with the same
+  /// [applicationId]. [key] must be 32 bytes long. Restarts synchronization
+  /// if already running.
+  Future<void> start({
+    required String applicationId,
+    required String deviceName,
+    required Uint8List key,
+  });
+
+  Future<void> stop();
+
+  Future<List<LanPeer>> getPeers();
+
+  /// Sends items to all currently known peers. Returns the number of peers.
+  Future<int> publish(List<List<LanRepresentation>> items);
+
+  void addPeersListener(ValueChanged<List<LanPeer>> listener);
+
+  void removePeersListener(ValueChanged<List<LanPeer>> listener);
+
+  void addReceivedListener(ValueChanged<LanClipboardReceivedEvent> listener);
+
+  void removeReceivedListener(
+      ValueChanged<LanClipboardReceivedEvent> listener);
+
+  /// Reads given [formats] of current clipboard contents and publishes them.
+  Future<int> publishClipboard(List<String> formats) async {
+    final reader = await ClipboardReader.instance.newClipboardReader();
+    try {
+      final items = await reader.getItems();
+      final requests = <(DataReaderItem, String)>[];
+      final requestItems = <int>[];
+      for (final (index, item) in items.indexed) {
+        for (final format in await item.getAvailableFormats()) {
+          if (formats.contains(format)) {
+            requests.add((item, format));
+            requestItems.add(index);
+          }
+        }
+      }
+      final (future, _) = reader.getItemDataMulti(requests);
+      final results = await future;
+      final published = List.generate(items.length, (_) => <LanRepresentation>[]);
+      for (final (index, result) in results.indexed) {
+        final data = result.data;
+        if (data is String || data is Uint8List) {
+          published[requestItems[index]]
+              .add(LanRepresentation(format: result.format, data: data!));
+        }
+      }
+      return publish(published);
+    } finally {
+      await reader.dispose();
+    }
+  }
+
+  /// Puts received items on the system clipboard, where they can be read
+  /// with [ClipboardReader] like local contents. Callers that publish the
+  /// clipboard on change should ignore the change caused by this call.
+  Future<void> writeToClipboard(LanClipboardReceivedEvent event) async {
+    final handles = <DataProviderHandle>[];
+    for (final item in event.items) {
+      final provider = DataProvider(
+        representations: item
+            .map((r) => DataRepresentation.simple(format: r.format, data: r.data))
+            .toList(growable: false),
+      );
+      handles.add(await provider.register());
+    }
+    await ClipboardWriter.instance.write(handles);
+  }
+}
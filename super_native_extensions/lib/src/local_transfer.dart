@@ -0,0 +1,46 @@
+import 'dart:typed_data';
+
+import 'native/local_transfer.dart'
+    if (dart.library.js) 'web/local_transfer.dart';
+
+/// Payload registered for direct transfer to another application on the
+/// same machine.
+class LocalTransferPayload {
+  LocalTransferPayload({
+    required this.token,
+    required this.descriptorFormat,
+    required this.descriptor,
+  });
+
+  static LocalTransferPayload deserialize(dynamic payload) {
+    final map = payload as Map;
+    return LocalTransferPayload(
+      token: map['token'],
+      descriptorFormat: map['descriptorFormat'],
+      descriptor: map['descriptor'],
+    );
+  }
+
+  /// Identifies the payload for [LocalTransferManager.unregisterPayload].
+  final String token;
+
+  /// Format under which [descriptor] should be added to data provider.
+  final String descriptorFormat;
+  final Uint8List descriptor;
+}
+
+/// Serves large payloads directly to other applications built with this
+/// plugin, bypassing the clipboard or drag session. Target reads the
+/// payload through `DataReaderItem.readLocalTransfer`.
+abstract class LocalTransferManager {
+  static final LocalTransferManager instance = LocalTransferManagerImpl();
+
+  /// Registers payload given either as [data] or as [path] of file to
+  /// stream. Fails if local endpoint is not available on this platform.
+  Future<LocalTransferPayload> registerPayload({
+    Uint8List? data,
+    String? path,
+  });
+
+  Future<void> unregisterPayload(String token);
+}
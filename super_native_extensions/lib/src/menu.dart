@@ -26,12 +26,24 @@ typedef MobileMenuWidgetFactory = Widget Function(
   IconThemeData iconTheme,
 );
 
+/// How the menu preview transitions when tapped (iOS only).
+enum MenuPreviewCommitStyle {
+  /// Menu and preview are dismissed.
+  dismiss,
+
+  /// Preview expands into the destination, which the application is
+  /// expected to present in [MenuContextDelegate.onPreviewAction].
+  pop,
+}
+
 class MobileMenuConfiguration {
   MobileMenuConfiguration({
     required this.configurationId,
     required this.liftImage,
     this.previewImage,
     this.previewSize,
+    this.previewRect,
+    this.previewCommitStyle,
     required this.handle,
     required this.backgroundBuilder,
     required this.previewBuilder,
@@ -44,6 +56,13 @@ class MobileMenuConfiguration {
   final TargetedWidgetSnapshot liftImage;
   final WidgetSnapshot? previewImage;
   final ui.Size? previewSize;
+
+  /// Region of the Flutter view (in logical coordinates) to show as preview.
+  /// The region is snapshotted natively so that no image needs to be sent.
+  /// Used when neither [previewImage] nor [previewSize] is set (iOS only).
+  final ui.Rect? previewRect;
+
+  final MenuPreviewCommitStyle? previewCommitStyle;
   final MenuHandle handle;
   final IconThemeData iconTheme;
 
@@ -81,6 +100,12 @@ abstract class MenuContextDelegate {
 
 abstract class MenuHandle {
   Menu get menu;
+
+  /// Updates state of [action] in this menu, including menu currently being
+  /// shown. Supported on macOS and Linux. Menus rendered by Flutter (Windows,
+  /// Android and web) and on iOS need to be rebuilt with the new state.
+  Future<void> updateItemState(MenuAction action, MenuActionState state);
+
   void dispose();
 }
 
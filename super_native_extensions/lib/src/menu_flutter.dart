@@ -11,6 +11,14 @@ class _MenuHandle extends MenuHandle {
 
   _MenuHandle(this.menu);
 
+  @override
+  Future<void> updateItemState(
+    MenuAction action,
+    MenuActionState state,
+  ) async {
+    throw UnsupportedError('Flutter rendered menu must be rebuilt instead');
+  }
+
   @override
   void dispose() {}
 }
@@ -123,12 +123,16 @@ class MenuAction extends MenuElement {
     required this.callback,
     this.attributes = const MenuActionAttributes(),
     this.state = MenuActionState.none,
+    this.radioGroup,
     this.activator,
   });
 
   final VoidCallback callback;
   final MenuActionAttributes attributes;
   final MenuActionState state;
+
+  /// Activating radio item turns off other items of the same group.
+  final String? radioGroup;
   final SingleActivator? activator;
 }
 
@@ -138,6 +142,18 @@ class DeferredMenuElement extends MenuElement {
   final Future<List<MenuElement>> Function(CancellationToken) provider;
 }
 
+/// Submenu with children provided when it is about to open. Supported on
+/// macOS, iOS and Linux.
+class DeferredMenu extends MenuElement {
+  DeferredMenu({
+    super.title,
+    super.image,
+    required this.provider,
+  });
+
+  final Future<List<MenuElement>> Function(CancellationToken) provider;
+}
+
 class MenuResult {
   MenuResult({
     required this.itemSelected,
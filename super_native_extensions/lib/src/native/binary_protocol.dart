@@ -0,0 +1,149 @@
+import 'dart:convert';
+import 'dart:typed_data';
+
+/// Decoder for payloads of channels switched to binary protocol through
+/// `setBinaryProtocol`. Layout is documented in `binary_protocol.rs`.
+
+const _magic = 0x53; // 'S'
+const _version = 2;
+const _headerLength = 8;
+
+const _tagNull = 0;
+const _tagFalse = 1;
+const _tagTrue = 2;
+const _tagI64 = 3;
+const _tagF64 = 4;
+const _tagString = 5;
+const _tagAttachment = 6;
+const _tagList = 7;
+const _tagMap = 8;
+
+enum BinaryRecordKind {
+  itemData(1),
+  progress(2),
+  dropEvent(3);
+
+  const BinaryRecordKind(this.value);
+
+  final int value;
+}
+
+class BinaryProgressUpdate {
+  BinaryProgressUpdate({
+    required this.progressId,
+    required this.fraction,
+    required this.bytesTransferred,
+    required this.totalBytes,
+  });
+
+  final int progressId;
+  final double? fraction;
+  final int? bytesTransferred;
+  final int? totalBytes;
+}
+
+ByteData _checkHeader(Uint8List payload, BinaryRecordKind kind) {
+  final data = ByteData.sublistView(payload);
+  if (payload.length < _headerLength ||
+      data.getUint8(0) != _magic ||
+      data.getUint8(1) != _version) {
+    throw const FormatException('Invalid binary protocol payload');
+  }
+  if (data.getUint8(2) != kind.value) {
+    throw FormatException('Expected ${kind.name} record');
+  }
+  return data;
+}
+
+/// Decodes item data or drop event record.
+Object? decodeBinaryRecord(Object payload, BinaryRecordKind kind) {
+  final Uint8List bytes;
+  final List<Object?> attachments;
+  if (payload is Uint8List) {
+    bytes = payload;
+    attachments = const [];
+  } else {
+    final list = payload as List;
+    bytes = list.first as Uint8List;
+    attachments = list.sublist(1);
+  }
+  final decoder = _Decoder(bytes, _checkHeader(bytes, kind), attachments);
+  return decoder.readValue();
+}
+
+BinaryProgressUpdate decodeBinaryProgress(Uint8List payload) {
+  final data = _checkHeader(payload, BinaryRecordKind.progress);
+  if (payload.length < _headerLength + 32) {
+    throw const FormatException('Truncated progress record');
+  }
+  final fraction = data.getFloat64(16, Endian.little);
+  final bytesTransferred = data.getInt64(24, Endian.little);
+  final totalBytes = data.getInt64(32, Endian.little);
+  return BinaryProgressUpdate(
+    progressId: data.getInt64(8, Endian.little),
+    fraction: fraction.isNaN ? null : fraction,
+    bytesTransferred: bytesTransferred >= 0 ? bytesTransferred : null,
+    totalBytes: totalBytes >= 0 ? totalBytes : null,
+  );
+}
+
+class _Decoder {
+  _Decoder(this.bytes, this.data, this.attachments);
+
+  final Uint8List bytes;
+  final ByteData data;
+  final List<Object?> attachments;
+  int offset = _headerLength;
+
+  int _readUint8() => data.getUint8(offset++);
+
+  int _readLength() {
+    final res = data.getUint32(offset, Endian.little);
+    offset += 4;
+    return res;
+  }
+
+  Object? readValue() {
+    final tag = _readUint8();
+    switch (tag) {
+      case _tagNull:
+        return null;
+      case _tagFalse:
+        return false;
+      case _tagTrue:
+        return true;
+      case _tagI64:
+        final res = data.getInt64(offset, Endian.little);
+        offset += 8;
+        return res;
+      case _tagF64:
+        final res = data.getFloat64(offset, Endian.little);
+        offset += 8;
+        return res;
+      case _tagString:
+        final length = _readLength();
+        final res = utf8.decode(Uint8List.sublistView(
+          bytes,
+          offset,
+          offset + length,
+        ));
+        offset += length;
+        return res;
+      case _tagAttachment:
+        return attachments[_readLength()];
+      case _tagList:
+        final count = _readLength();
+        return List<Object?>.generate(count, (_) => readValue());
+      case _tagMap:
+        final count = _readLength();
+        final res = <Object?, Object?>{};
+        for (var i = 0; i < count; ++i) {
+          final key = readValue();
+          res[key] = readValue();
+        }
+        return res;
+      default:
+        throw FormatException('Unknown binary protocol tag $tag');
+    }
+  }
+}
@@ -0,0 +1,47 @@
+import 'package:flutter/foundation.dart';
+import 'package:flutter/services.dart';
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../clipboard_monitor.dart';
+import 'context.dart';
+
+class ClipboardMonitorImpl extends ClipboardMonitor {
+  ClipboardMonitorImpl() {
+    _channel.setMethodCallHandler(_onMethodCall);
+  }
+
+  Future<dynamic> _onMethodCall(MethodCall call) async {
+    if (call.method == 'clipboardChanged') {
+      final event = ClipboardChangedEvent.deserialize(call.arguments);
+      for (final listener in List.of(_listeners)) {
+        listener(event);
+      }
+    }
+  }
+
+  @override
+  Future<bool> addListener(
+      ValueChanged<ClipboardChangedEvent> listener) async {
+    _listeners.add(listener);
+    if (_listeners.length == 1) {
+      _supported = _channel
+          .invokeMethod('startMonitoring')
+          .then((value) => value as bool);
+    }
+    return _supported;
+  }
+
+  @override
+  void removeListener(ValueChanged<ClipboardChangedEvent> listener) {
+    if (_listeners.remove(listener) && _listeners.isEmpty) {
+      _channel.invokeMethod('stopMonitoring');
+    }
+  }
+
+  final _listeners = <ValueChanged<ClipboardChangedEvent>>[];
+
+  Future<bool> _supported = Future.value(false);
+
+  final _channel = NativeMethodChannel('ClipboardMonitor',
+      context: superNativeExtensionsContext);
+}
@@ -9,14 +9,57 @@ import '../reader_manager.dart';
 
 class ClipboardReaderImpl extends ClipboardReader {
   @override
-  Future<DataReader> newClipboardReader() async {
-    final handle = await _channel.invokeMethod('newClipboardReader');
+  Future<DataReader> newClipboardReader({ClipboardTarget? target}) async {
+    final handle =
+        await _channel.invokeMethod('newClipboardReader', target?.serialize());
     return DataReader(handle: DataReaderHandle.deserialize(handle));
   }
 
+  @override
+  Future<bool> canPaste(
+    List<String> formats, {
+    ClipboardTarget? target,
+  }) async {
+    return await _channel.invokeMethod('canPaste', {
+      'formats': formats,
+      'target': target?.serialize(),
+    });
+  }
+
   @override
   bool get available => true;
 
+  @override
+  Future<RemoteSessionInfo> getRemoteSessionInfo() async {
+    return RemoteSessionInfo.deserialize(
+        await _channel.invokeMethod('getRemoteSessionInfo'));
+  }
+
+  @override
+  Future<ClipboardPolicy> getClipboardPolicy() async {
+    return ClipboardPolicy.deserialize(
+        await _channel.invokeMethod('getClipboardPolicy'));
+  }
+
+  @override
+  Future<void> setRemoteSessionTuning(bool? enabled) async {
+    await _channel.invokeMethod('setRemoteSessionTuning', enabled);
+  }
+
+  @override
+  Future<ClipboardToken> captureClipboardToken() async {
+    return ClipboardToken(await _channel.invokeMethod('captureClipboardToken'));
+  }
+
+  @override
+  Future<DataReader?> redeemClipboardToken(ClipboardToken token) async {
+    final handle =
+        await _channel.invokeMethod('redeemClipboardToken', token.raw);
+    return handle != null
+        ? DataReader(handle: DataReaderHandle.deserialize(handle))
+        : null;
+  }
+
   ClipboardReaderImpl();
 
   final _channel = NativeMethodChannel('ClipboardReader',
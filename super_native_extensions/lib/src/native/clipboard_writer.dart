@@ -2,6 +2,7 @@ import 'package:flutter/services.dart';
 import 'package:irondash_message_channel/irondash_message_channel.dart';
 
 import 'context.dart';
+import '../clipboard_reader.dart';
 import '../data_provider.dart';
 import '../clipboard_writer.dart';
 
@@ -18,6 +19,61 @@ class ClipboardWriterImpl extends ClipboardWriter {
     }
   }
 
+  @override
+  Future<void> writeToSelection(
+    List<DataProviderHandle> providers,
+    ClipboardTarget target,
+  ) async {
+    await _channel.invokeMethod('writeToSelection', {
+      'providerIds': providers.map((e) => e.id).toList(),
+      'target': target.serialize(),
+    });
+    for (final provider in providers) {
+      _activeProviders[provider.id] = provider;
+    }
+  }
+
+  @override
+  Future<void> append(List<DataProviderHandle> providers) async {
+    await _channel.invokeMethod(
+        'appendToClipboard', providers.map((e) => e.id).toList());
+    for (final provider in providers) {
+      _activeProviders[provider.id] = provider;
+    }
+  }
+
+  @override
+  Future<void> removeItem(int index) async {
+    await _channel.invokeMethod('removeClipboardItem', {'index': index});
+  }
+
+  @override
+  Future<void> replaceItem(int index, DataProviderHandle provider) async {
+    await _channel.invokeMethod('replaceClipboardItem', {
+      'index': index,
+      'providerId': provider.id,
+    });
+    _activeProviders[provider.id] = provider;
+  }
+
+  @override
+  Future<void> moveItem({required int fromIndex, required int toIndex}) async {
+    await _channel.invokeMethod('moveClipboardItem', {
+      'fromIndex': fromIndex,
+      'toIndex': toIndex,
+    });
+  }
+
+  @override
+  Future<bool> clear() async {
+    return await _channel.invokeMethod('clearClipboard') as bool;
+  }
+
+  @override
+  Future<bool> hasOwnership() async {
+    return await _channel.invokeMethod('hasClipboardOwnership') as bool;
+  }
+
   Future<dynamic> _onMethodCall(MethodCall call) async {
     if (call.method == 'releaseDataProvider') {
       final provider = _activeProviders.remove(call.arguments as int);
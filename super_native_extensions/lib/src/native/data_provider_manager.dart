@@ -8,6 +8,7 @@ import 'package:flutter/services.dart';
 import 'package:irondash_message_channel/irondash_message_channel.dart';
 
 import 'context.dart';
+import 'shared_buffer.dart';
 import '../data_provider.dart';
 import '../data_provider_manager.dart';
 import '../util.dart';
@@ -20,6 +21,12 @@ extension DataProviderExt on DataProvider {
   dynamic serialize() => {
         'representations': representations.map((e) => e.serialize()),
         'suggestedName': suggestedName,
+        'fidelity': fidelity?.entries
+            .map((e) => {'format': e.key, 'level': e.value})
+            .toList(growable: false),
+        'expiresAfterMs': expiresAfter?.inMilliseconds,
+        'sourceUrl': sourceUrl,
+        'synthesizeRtf': synthesizeRtf,
       };
 }
 
@@ -29,9 +36,14 @@ class DataProviderManagerImpl implements DataProviderManager {
   }
 
   @override
-  Future<DataProviderHandle> registerDataProvider(DataProvider provider) async {
-    final id = await _channel.invokeMethod(
-        "registerDataProvider", provider.serialize());
+  Future<DataProviderHandle> registerDataProvider(
+    DataProvider provider, {
+    int? servicePort,
+  }) async {
+    final id = await _channel.invokeMethod("registerDataProvider", {
+      ...provider.serialize(),
+      'ownerIsolateId': servicePort,
+    });
     final handle = DataProviderHandle(id, provider);
     _handles[id] = handle;
     for (final representation in provider.representations) {
@@ -45,6 +57,30 @@ class DataProviderManagerImpl implements DataProviderManager {
     return handle;
   }
 
+  @override
+  Future<int> getServicePort() async {
+    return await _channel.invokeMethod("getIsolateId");
+  }
+
+  @override
+  Future<DataProviderHandle> adoptDataProvider(int providerId) async {
+    await _channel.invokeMethod("adoptDataProvider", providerId);
+    // Representations live in the worker isolate that serves them.
+    final handle = DataProviderHandle(
+      providerId,
+      DataProvider(representations: const []),
+    );
+    _handles[providerId] = handle;
+    return handle;
+  }
+
+  @override
+  Future<void> setBandwidthLimit(int? bytesPerSecond) async {
+    await _channel.invokeMethod('setBandwidthLimit', {
+      'bytesPerSecond': bytesPerSecond,
+    });
+  }
+
   @override
   Future<void> unregisterDataProvider(int providerId) async {
     await _channel.invokeMethod("unregisterDataProvider", providerId);
@@ -66,8 +102,8 @@ class DataProviderManagerImpl implements DataProviderManager {
       final valueId = args["valueId"] as int;
       final lazyData = _lazyData[valueId];
       if (lazyData != null) {
-        return _ValuePromiseResult.ok(await lazyData.dataProvider())
-            .serialize();
+        final value = SharedBuffers.share(await lazyData.dataProvider());
+        return _ValuePromiseResult.ok(value).serialize();
       } else {
         return _ValuePromiseResult.cancelled().serialize();
       }
@@ -80,6 +116,10 @@ class DataProviderManagerImpl implements DataProviderManager {
           sessionId: sessionId,
           virtualFileId: virtualFileId,
           streamHandle: fileHandle);
+    } else if (call.method == 'providerUnregistered') {
+      // Released by the other isolate of provider registered through
+      // service port, or that isolate exited.
+      await _handles[call.arguments as int]?.dispose();
     } else if (call.method == 'cancelVirtualFile') {
       final sessionId = call.arguments as int;
       // Don't allow cancelling completed sessions. This can happen on
@@ -87,8 +127,7 @@ class DataProviderManagerImpl implements DataProviderManager {
       // triggers cancellation on windows) after having read entire length
       // before dart code hasn't yet closed the sink.
       if (_virtualSessions[sessionId]?.isCompleted != true) {
-        final session = _virtualSessions.remove(sessionId);
-        (session?.progress.onCancel as SimpleNotifier?)?.notify();
+        _virtualSessions[sessionId]?.progress.cancel();
       }
     }
   }
@@ -152,6 +191,10 @@ class DataProviderManagerImpl implements DataProviderManager {
       onError('Virtual file ($virtualFileId)not found');
     }
     progress.onCancel.addListener(() async {
+      // Session has already completed or failed.
+      if (_virtualSessions.remove(sessionId) == null) {
+        return;
+      }
       sink._close(delete: true);
       await _channel.invokeMethod('virtualFileCancel', {
         'sessionId': sessionId,
@@ -170,10 +213,19 @@ class DataProviderManagerImpl implements DataProviderManager {
 }
 
 class WriteProgressImpl extends WriteProgress {
-  WriteProgressImpl(Listenable onCancel, ValueNotifier<double> onProgress)
+  WriteProgressImpl(SimpleNotifier onCancel, ValueNotifier<double> onProgress)
       : _onCancel = onCancel,
         _onProgress = onProgress;
 
+  @override
+  void cancel() {
+    if (_cancelled) {
+      return;
+    }
+    _cancelled = true;
+    _onCancel.notify();
+  }
+
   @override
   void updateProgress(double fraction) {
     _onProgress.value = fraction;
@@ -196,7 +248,8 @@ class WriteProgressImpl extends WriteProgress {
   }
 
   bool _hasExplicitProgress = false;
-  final Listenable _onCancel;
+  bool _cancelled = false;
+  final SimpleNotifier _onCancel;
   final ValueNotifier<double> _onProgress;
 }
 
@@ -223,6 +276,7 @@ class _VirtualSession {
 class _NativeFunctions {
   _NativeFunctions({
     required this.streamWrite,
+    required this.streamWriteDelay,
     required this.streamClose,
   });
 
@@ -235,12 +289,17 @@ class _NativeFunctions {
           .lookup<NativeFunction<Int32 Function(Int32, Pointer<Uint8>, Int64)>>(
               'super_native_extensions_stream_write')
           .asFunction<int Function(int, Pointer<Uint8>, int)>();
+      final streamWriteDelay = dylib
+          .lookup<NativeFunction<Int64 Function(Int32, Int64)>>(
+              'super_native_extensions_stream_write_delay')
+          .asFunction<int Function(int, int)>();
       final streamClose = dylib
           .lookup<NativeFunction<Void Function(Int32, Bool)>>(
               'super_native_extensions_stream_close')
           .asFunction<void Function(int, bool)>();
       _instance = _NativeFunctions(
         streamWrite: streamWrite,
+        streamWriteDelay: streamWriteDelay,
         streamClose: streamClose,
       );
     }
@@ -248,6 +307,10 @@ class _NativeFunctions {
   }
 
   final int Function(int handle, Pointer<Uint8> data, int len) streamWrite;
+
+  /// Microseconds to wait after writing [len] bytes to stay within bandwidth
+  /// limit.
+  final int Function(int handle, int len) streamWriteDelay;
   final void Function(int handle, bool delete) streamClose;
 }
 
@@ -256,6 +319,13 @@ class _VirtualFileSink implements EventSink<Uint8List> {
   final _VirtualSession session;
   final int handle;
   Pointer<Uint8>? _buffer;
+
+  /// Writes are performed in order; with bandwidth limit in effect each write
+  /// may be delayed.
+  Future<void> _pendingWrites = Future.value();
+
+  /// Set once native stream is closed; pending writes are dropped.
+  bool _released = false;
   Future<void> Function() onClose;
   Future<void> Function(String) onError;
 
@@ -271,24 +341,42 @@ class _VirtualFileSink implements EventSink<Uint8List> {
     if (_closed) {
       throw StateError('Stream is already closed');
     }
-    const bufferSize = 16384;
-    _buffer ??= malloc.allocate(bufferSize);
+    _pendingWrites = _pendingWrites.then((_) => _write(data));
+  }
 
+  Future<void> _write(Uint8List data) async {
+    const bufferSize = 16384;
     int numWritten = 0;
-    while (numWritten < data.length) {
+    while (numWritten < data.length && !_released) {
+      _buffer ??= malloc.allocate(bufferSize);
       final len = min(bufferSize, data.length - numWritten);
       _buffer!
           .asTypedList(bufferSize)
           .setRange(0, len, data.sublist(numWritten, numWritten + len));
-      _NativeFunctions.instance.streamWrite(handle, _buffer!, len);
+      if (_NativeFunctions.instance.streamWrite(handle, _buffer!, len) == 0) {
+        // Native stream is gone; there is no point in writing rest of data.
+        // Can't go through addError here as that waits for pending writes.
+        _closed = true;
+        _close(delete: true);
+        await onError('Failed to write to virtual file stream');
+        return;
+      }
       session.didWriteBytes(len);
       numWritten += len;
+      final delay = _NativeFunctions.instance.streamWriteDelay(handle, len);
+      if (delay > 0) {
+        await Future.delayed(Duration(microseconds: delay));
+      }
     }
   }
 
   void _close({
     bool delete = false,
   }) {
+    if (_released) {
+      return;
+    }
+    _released = true;
     if (_buffer != null) {
       malloc.free(_buffer!);
       _buffer = null;
@@ -302,6 +390,7 @@ class _VirtualFileSink implements EventSink<Uint8List> {
       return;
     }
     _closed = true;
+    await _pendingWrites;
     await onError(error.toString());
     _close(delete: true);
   }
@@ -312,6 +401,7 @@ class _VirtualFileSink implements EventSink<Uint8List> {
       return;
     }
     _closed = true;
+    await _pendingWrites;
     _close();
     return onClose();
   }
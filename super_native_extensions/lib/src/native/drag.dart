@@ -1,6 +1,7 @@
 import 'dart:ui' as ui;
 
 import 'package:flutter/foundation.dart';
+import 'package:flutter/gestures.dart';
 import 'package:irondash_engine_context/irondash_engine_context.dart';
 import 'package:flutter/services.dart';
 import 'package:flutter/widgets.dart';
@@ -23,6 +24,7 @@ extension DragConfigurationExt on DragConfiguration {
         'animatesToStartingPositionOnCancelOrFail':
             animatesToStartingPositionOnCancelOrFail,
         'prefersFullSizePreviews': prefersFullSizePreviews,
+        'sessionLocalData': sessionLocalData,
       };
 }
 
@@ -30,7 +32,13 @@ extension DragItemExt on DragItem {
   Future<dynamic> serialize() async => {
         'dataProviderId': dataProvider.id,
         'localData': localData,
-        'image': (await image.intoRaw()).serialize(),
+        'image': imageTexture != null
+            ? TargetedImageData(
+                imageData: ImageData.allocate(width: 0, height: 0),
+                rect: image.rect,
+                sharedTexture: imageTexture,
+              ).serialize()
+            : (await image.intoRaw()).serialize(),
         'liftImage': (await liftImage?.intoRaw())?.serialize()
       };
 }
@@ -40,6 +48,9 @@ extension DragRequestExt on DragRequest {
         'configuration': await configuration.serialize(),
         'position': position.serialize(),
         'combinedDragImage': combinedDragImage?.serialize(),
+        'engineHandle': engineHandle,
+        'deviceKind':
+            deviceKind == PointerDeviceKind.unknown ? null : deviceKind?.name,
       };
 }
 
@@ -56,9 +67,29 @@ class DragSessionImpl extends DragSession {
   @override
   ValueListenable<DropOperation?> get dragCompleted => _dragCompleted;
 
+  @override
+  DragCancelReason? cancelReason;
+
+  @override
+  List<DropOperation>? itemOperations;
+
   @override
   ValueListenable<ui.Offset?> get lastScreenLocation => _lastScreenLocation;
 
+  @override
+  ValueListenable<DragSessionUpdate?> get lastUpdate => _lastUpdate;
+
+  @override
+  ValueListenable<DragEnvironmentEvent?> get lastEnvironmentChange =>
+      _lastEnvironmentChange;
+
+  @override
+  ValueListenable<ui.Offset?> get cancelledAtLocation => _cancelledAtLocation;
+
+  @override
+  ValueListenable<DataTransferProgress?> get dataTransferProgress =>
+      _dataTransferProgress;
+
   int? sessionId;
 
   @override
@@ -70,15 +101,38 @@ class DragSessionImpl extends DragSession {
     }
   }
 
+  @override
+  Future<void> updateDragImage(
+    List<TargetedWidgetSnapshot> images, {
+    TargetedWidgetSnapshot? combinedDragImage,
+  }) async {
+    if (sessionId != null) {
+      await dragContext.updateDragImage(
+        sessionId!,
+        images,
+        combinedDragImage: combinedDragImage,
+      );
+    }
+  }
+
+  /// [dataTransferProgress] is left alive, it can still be updated after
+  /// the session ended.
   void dispose() {
     _dragging.dispose();
     _dragCompleted.dispose();
     _lastScreenLocation.dispose();
+    _lastUpdate.dispose();
+    _lastEnvironmentChange.dispose();
+    _cancelledAtLocation.dispose();
   }
 
   final _dragging = ValueNotifier<bool>(false);
   final _dragCompleted = ValueNotifier<DropOperation?>(null);
   final _lastScreenLocation = ValueNotifier<ui.Offset?>(null);
+  final _lastUpdate = ValueNotifier<DragSessionUpdate?>(null);
+  final _lastEnvironmentChange = ValueNotifier<DragEnvironmentEvent?>(null);
+  final _cancelledAtLocation = ValueNotifier<ui.Offset?>(null);
+  final _dataTransferProgress = ValueNotifier<DataTransferProgress?>(null);
 }
 
 final _channel =
@@ -90,6 +144,11 @@ class DragContextImpl extends DragContext {
   final _sessions = <int, DragSessionImpl>{};
   final _dataProviders = <int, DataProviderHandle>{};
 
+  /// Ended sessions that may still receive [DataTransferProgress]. Held weakly
+  /// as most sessions never do.
+  final _endedSessions = <int, WeakReference<DragSessionImpl>>{};
+  late final _endedSessionsFinalizer = Finalizer<int>(_endedSessions.remove);
+
   @override
   Future<void> initialize() async {
     super.initialize();
@@ -98,6 +157,7 @@ class DragContextImpl extends DragContext {
     await _channel.invokeMethod('newContext', {
       'engineHandle': engineHandle,
     });
+    touchDragSettingsNotifier.value = await getTouchDragSettings();
   }
 
   Future<dynamic> _handleMethodCall(MethodCall call) async {
@@ -167,28 +227,73 @@ class DragContextImpl extends DragContext {
       return handleError(() async {
         final arguments = call.arguments as Map;
         final sessionId = arguments['sessionId'];
-        final screenLocation =
-            OffsetExt.deserialize(arguments['screenLocation']);
+        final update = DragSessionUpdate.deserialize(arguments);
         final session = _sessions[sessionId];
         if (session != null) {
           if (!session._dragging.value &&
               session._dragCompleted.value == null) {
             session._dragging.value = true;
           }
-          session._lastScreenLocation.value = screenLocation;
+          session._lastScreenLocation.value = update.screenLocation;
+          session._lastUpdate.value = update;
         }
       }, () => null);
+    } else if (call.method == 'dragSessionEnvironmentDidChange') {
+      return handleError(() async {
+        final arguments = call.arguments as Map;
+        final sessionId = arguments['sessionId'];
+        _sessions[sessionId]?._lastEnvironmentChange.value =
+            DragEnvironmentEvent.deserialize(arguments);
+      }, () => null);
+    } else if (call.method == 'dragSessionDidCancel') {
+      return handleError(() async {
+        final arguments = call.arguments as Map;
+        final sessionId = arguments['sessionId'];
+        final screenLocation =
+            OffsetExt.deserialize(arguments['screenLocation']);
+        _sessions[sessionId]?._cancelledAtLocation.value = screenLocation;
+      }, () => null);
     } else if (call.method == 'dragSessionDidEnd') {
       return handleError(() async {
         final arguments = call.arguments as Map;
         final sessionId = arguments['sessionId'];
         final dropOperation =
             DropOperation.values.byName(arguments['dropOperation']);
+        final cancelReason = arguments['cancelReason'] as String?;
+        final itemOperations = arguments['itemOperations'] as List?;
         final session = _sessions.remove(sessionId);
         if (session != null) {
+          session.cancelReason = cancelReason != null
+              ? DragCancelReason.values.byName(cancelReason)
+              : null;
+          session.itemOperations = itemOperations
+              ?.map((e) => DropOperation.values.byName(e))
+              .toList(growable: false);
           session._dragging.value = false;
           session._dragCompleted.value = dropOperation;
           session.dispose();
+          _endedSessions[sessionId] = WeakReference(session);
+          _endedSessionsFinalizer.attach(
+            session,
+            sessionId,
+            detach: session,
+          );
+        }
+      }, () => null);
+    } else if (call.method == 'dragDataTransferProgress') {
+      return handleError(() async {
+        final arguments = call.arguments as Map;
+        final sessionId = arguments['sessionId'];
+        final progress =
+            DataTransferProgress.deserialize(arguments['progress']);
+        final session = _endedSessions[sessionId]?.target;
+        if (session != null) {
+          session._dataTransferProgress.value = progress;
+          if (progress.state == DataTransferState.completed ||
+              progress.state == DataTransferState.failed) {
+            _endedSessions.remove(sessionId);
+            _endedSessionsFinalizer.detach(session);
+          }
         }
       }, () => null);
     } else {
@@ -212,12 +317,36 @@ class DragContextImpl extends DragContext {
     session.dispose();
   }
 
+  @override
+  Future<SharedTextureKind?> sharedTextureKind() async {
+    final kind = await _channel.invokeMethod('sharedTextureKind') as String?;
+    return kind != null ? SharedTextureKind.values.byName(kind) : null;
+  }
+
   Future<List<Object?>?> getLocalData(int sessionId) async {
     return _channel.invokeMethod('getLocalData', {
       'sessionId': sessionId,
     });
   }
 
+  Future<void> updateDragImage(
+    int sessionId,
+    List<TargetedWidgetSnapshot> images, {
+    TargetedWidgetSnapshot? combinedDragImage,
+  }) async {
+    final needsCombinedDragImage =
+        (await _channel.invokeMethod('needsCombinedDragImage')) as bool;
+    final raw = await Future.wait(images.map((e) => e.intoRaw()));
+    final combined = needsCombinedDragImage
+        ? (await combinedDragImage?.intoRaw()) ?? await combineImages(images)
+        : null;
+    await _channel.invokeMethod('updateDragImage', {
+      'sessionId': sessionId,
+      'images': raw.map((e) => e.serialize()).toList(),
+      'combinedDragImage': combined?.serialize(),
+    });
+  }
+
   @override
   Future<void> startDrag({
     required BuildContext buildContext,
@@ -225,6 +354,8 @@ class DragContextImpl extends DragContext {
     required DragConfiguration configuration,
     required Offset position,
     TargetedWidgetSnapshot? combinedDragImage,
+    int? engineHandle,
+    PointerDeviceKind? deviceKind,
   }) async {
     final needsCombinedDragImage =
         (await _channel.invokeMethod('needsCombinedDragImage')) as bool;
@@ -235,6 +366,8 @@ class DragContextImpl extends DragContext {
           ? (await combinedDragImage?.intoRaw()) ??
               await combineDragImage(configuration)
           : null,
+      engineHandle: engineHandle,
+      deviceKind: deviceKind,
     );
 
     final sessionId =
@@ -246,4 +379,28 @@ class DragContextImpl extends DragContext {
       _dataProviders[item.dataProvider.id] = item.dataProvider;
     }
   }
+
+  @override
+  Future<void> registerDragSourceForView(int engineHandle) async {
+    await _channel.invokeMethod(
+        'registerDragSourceForView', {'engineHandle': engineHandle});
+  }
+
+  @override
+  Future<void> unregisterDragSourceForView(int engineHandle) async {
+    await _channel.invokeMethod(
+        'unregisterDragSourceForView', {'engineHandle': engineHandle});
+  }
+
+  @override
+  Future<TouchDragSettings> getTouchDragSettings() async {
+    return TouchDragSettings.deserialize(
+        await _channel.invokeMethod('getTouchDragSettings'));
+  }
+
+  @override
+  Future<void> setTouchDragSettings(TouchDragSettings settings) async {
+    await _channel.invokeMethod('setTouchDragSettings', settings.serialize());
+    touchDragSettingsNotifier.value = settings;
+  }
 }
@@ -0,0 +1,52 @@
+import 'package:flutter/foundation.dart';
+import 'package:flutter/services.dart';
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../drag_monitor.dart';
+import 'context.dart';
+
+class DragMonitorImpl extends DragMonitor {
+  DragMonitorImpl() {
+    _channel.setMethodCallHandler(_onMethodCall);
+  }
+
+  Future<dynamic> _onMethodCall(MethodCall call) async {
+    if (call.method == 'onDragActivity') {
+      final event = DragActivityEvent.deserialize(call.arguments);
+      for (final listener in List.of(_listeners)) {
+        listener(event);
+      }
+    }
+  }
+
+  @override
+  Future<bool> isDragInProgress() async {
+    return await _channel.invokeMethod('isDragInProgress');
+  }
+
+  @override
+  Future<DragSessionInfo?> currentDragSessionInfo() async {
+    final info = await _channel.invokeMethod('currentDragSessionInfo');
+    return info != null ? DragSessionInfo.deserialize(info) : null;
+  }
+
+  @override
+  void addListener(ValueChanged<DragActivityEvent> listener) {
+    _listeners.add(listener);
+    if (_listeners.length == 1) {
+      _channel.invokeMethod('startMonitoring');
+    }
+  }
+
+  @override
+  void removeListener(ValueChanged<DragActivityEvent> listener) {
+    if (_listeners.remove(listener) && _listeners.isEmpty) {
+      _channel.invokeMethod('stopMonitoring');
+    }
+  }
+
+  final _listeners = <ValueChanged<DragActivityEvent>>[];
+
+  final _channel = NativeMethodChannel('DragMonitor',
+      context: superNativeExtensionsContext);
+}
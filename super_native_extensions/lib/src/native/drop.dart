@@ -9,6 +9,7 @@ import '../image_data.dart';
 import '../mutex.dart';
 import '../reader.dart';
 import '../util.dart';
+import 'binary_protocol.dart';
 import 'context.dart';
 import 'image_data.dart';
 import 'reader_manager.dart';
@@ -35,7 +36,10 @@ extension ItemPreviewExt on ItemPreview {
 extension BaseDropEventExt on BaseDropEvent {
   static BaseDropEvent deserialize(dynamic event) {
     final map = event as Map;
-    return BaseDropEvent(sessionId: map['sessionId']);
+    return BaseDropEvent(
+      sessionId: map['sessionId'],
+      engineHandle: map['engineHandle'],
+    );
   }
 }
 
@@ -56,10 +60,14 @@ typedef ReaderProvider = DataReader? Function(int sessionId);
 class DropEventImpl extends DropEvent {
   DropEventImpl({
     required super.sessionId,
+    super.engineHandle,
     required super.locationInView,
     required super.allowedOperations,
     required super.items,
     super.acceptedOperation,
+    super.regionId,
+    super.sessionLocalData,
+    super.localSessionId,
     this.reader,
   });
 
@@ -88,6 +96,7 @@ class DropEventImpl extends DropEvent {
 
     return DropEventImpl(
       sessionId: sessionId,
+      engineHandle: map['engineHandle'],
       locationInView: OffsetExt.deserialize(map['locationInView']),
       items: (map['items'] as Iterable)
           .mapIndexed(deserializeItem)
@@ -98,6 +107,9 @@ class DropEventImpl extends DropEvent {
       acceptedOperation: acceptedOperation != null
           ? DropOperation.values.byName(acceptedOperation)
           : null,
+      regionId: map['regionId'],
+      sessionLocalData: map['sessionLocalData'],
+      localSessionId: map['localSessionId'],
       reader: reader,
     );
   }
@@ -105,6 +117,26 @@ class DropEventImpl extends DropEvent {
   final DataReader? reader;
 }
 
+extension DropAnalyticsReportExt on DropAnalyticsReport {
+  static DropAnalyticsReport deserialize(dynamic report) {
+    final map = report as Map;
+    return DropAnalyticsReport(
+      sessionsEntered: map['sessionsEntered'],
+      sessionsDropped: map['sessionsDropped'],
+      sessionsAbandoned: map['sessionsAbandoned'],
+      averageHoverTime: Duration(
+          microseconds: ((map['averageHoverMillis'] as num) * 1000).round()),
+      rejectedFormats: (map['rejectedFormats'] as List)
+          .cast<Map>()
+          .map((e) => RejectedFormatCount(
+                format: e['format'],
+                count: e['count'],
+              ))
+          .toList(growable: false),
+    );
+  }
+}
+
 class DropContextImpl extends DropContext {
   DropContextImpl();
 
@@ -133,12 +165,16 @@ class DropContextImpl extends DropContext {
   }
 
   Future<dynamic> _handleMethodCall(MethodCall call) async {
-    if (call.method == 'onDropUpdate') {
+    if (call.method == 'onDropUpdate' ||
+        call.method == 'onDropUpdateBinary') {
       return handleError(() async {
-        final session = _sessionForEvent(call.arguments);
+        final arguments = call.method == 'onDropUpdateBinary'
+            ? decodeBinaryRecord(call.arguments, BinaryRecordKind.dropEvent)
+            : call.arguments;
+        final session = _sessionForEvent(arguments);
         return session.mutex.protect(() async {
           final event = await DropEventImpl.deserialize(
-              call.arguments, _getReaderForSession);
+              arguments, _getReaderForSession);
           session.reader = event.reader;
           final operation = await delegate?.onDropUpdate(event);
           return (operation ?? DropOperation.none).name;
@@ -190,13 +226,104 @@ class DropContextImpl extends DropContext {
           return {'preview': null};
         }
       }, () => {'preview': null});
+    } else if (call.method == 'onDeferredDropCompleted') {
+      final map = call.arguments as Map;
+      onDeferredDropCompleted?.call(DeferredDropResult(
+        sessionId: map['sessionId'],
+        success: map['success'],
+        error: map['error'],
+      ));
+      return null;
+    } else if (call.method == 'onDropRegionLeave') {
+      onDropRegionLeave?.call(DropRegionEvent.deserialize(call.arguments));
+      return null;
+    } else if (call.method == 'autoScroll') {
+      onAutoScroll?.call(AutoScrollEvent.deserialize(call.arguments));
+      return null;
+    } else if (call.method == 'springLoadActivated') {
+      onSpringLoadActivated?.call(DropRegionEvent.deserialize(call.arguments));
+      return null;
+    } else if (call.method == 'onDropAnalytics') {
+      _analytics.value = DropAnalyticsReportExt.deserialize(call.arguments);
+      return null;
     } else {
       return null;
     }
   }
 
   @override
-  Future<void> registerDropFormats(List<String> formats) {
-    return _channel.invokeMethod("registerDropFormats", {'formats': formats});
+  Future<void> registerDropFormats(List<String> formats, {int? engineHandle}) {
+    return _channel.invokeMethod("registerDropFormats", {
+      'formats': formats,
+      'engineHandle': engineHandle,
+    });
+  }
+
+  @override
+  Future<void> registerDropTargetForView(int engineHandle) async {
+    await _channel.invokeMethod(
+        'registerDropTargetForView', {'engineHandle': engineHandle});
+  }
+
+  @override
+  Future<void> unregisterDropTargetForView(int engineHandle) async {
+    await _channel.invokeMethod(
+        'unregisterDropTargetForView', {'engineHandle': engineHandle});
+  }
+
+  @override
+  Future<void> setDropAcceptanceMode(DropAcceptanceMode mode) async {
+    await _channel.invokeMethod('setDropAcceptanceMode', {'mode': mode.name});
+  }
+
+  @override
+  Future<void> setDropManifest(DropManifest manifest) async {
+    await _channel.invokeMethod('setDropManifest', manifest.serialize());
+  }
+
+  @override
+  Future<void> setDropRegions(
+    List<DropRegion> regions, {
+    int? engineHandle,
+  }) async {
+    await _channel.invokeMethod('setDropRegions', {
+      'engineHandle': engineHandle,
+      'regions': regions.map((r) => r.serialize()).toList(growable: false),
+    });
+  }
+
+  @override
+  Future<void> setAutoScrollRegions(
+    List<AutoScrollRegion> regions, {
+    int? engineHandle,
+  }) async {
+    await _channel.invokeMethod('setAutoScrollRegions', {
+      'engineHandle': engineHandle,
+      'regions': regions.map((r) => r.serialize()).toList(growable: false),
+    });
+  }
+
+  @override
+  Future<void> setAnalyticsEnabled(bool enabled) async {
+    await _channel.invokeMethod('setAnalyticsEnabled', {'enabled': enabled});
+    if (!enabled) {
+      _analytics.value = null;
+    }
+  }
+
+  @override
+  Future<DropAnalyticsReport?> getAnalytics() async {
+    final report = await _channel.invokeMethod('getAnalytics');
+    return report != null ? DropAnalyticsReportExt.deserialize(report) : null;
   }
+
+  @override
+  Future<void> resetAnalytics() async {
+    await _channel.invokeMethod('resetAnalytics');
+  }
+
+  @override
+  ValueListenable<DropAnalyticsReport?> get analytics => _analytics;
+
+  final _analytics = ValueNotifier<DropAnalyticsReport?>(null);
 }
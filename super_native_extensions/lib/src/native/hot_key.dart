@@ -1,6 +1,7 @@
 import 'package:flutter/services.dart';
 import 'package:irondash_message_channel/irondash_message_channel.dart';
 
+import '../desktop_capability.dart';
 import '../hot_key.dart';
 import 'context.dart';
 
@@ -27,6 +28,53 @@ class HotKeyManagerImpl extends HotKeyManager {
     await _channel.invokeMethod('destroyHotKey', {'handle': handle});
   }
 
+  @override
+  Future<void> defineHotKeyProfile(
+      String name, List<HotKeyDefinition> hotKeys) async {
+    await _channel.invokeMethod('defineHotKeyProfile', {
+      'name': name,
+      'hotKeys': hotKeys.map((e) => e.serialize()).toList(growable: false),
+    });
+  }
+
+  @override
+  Future<HotKeyProfileActivation> activateHotKeyProfile(
+    String name, {
+    bool rollbackOnFailure = false,
+  }) async {
+    final res = await _channel.invokeMethod('activateHotKeyProfile', {
+      'name': name,
+      'rollbackOnFailure': rollbackOnFailure,
+    }) as Map;
+    return HotKeyProfileActivation(
+      activated: res['activated'],
+      handles: (res['handles'] as List).cast<int?>(),
+      failures: (res['failures'] as List)
+          .cast<Map>()
+          .map((e) => HotKeyProfileFailure(
+                index: e['index'],
+                message: e['message'],
+              ))
+          .toList(growable: false),
+    );
+  }
+
+  @override
+  Future<void> deactivateHotKeyProfile() async {
+    await _channel.invokeMethod('deactivateHotKeyProfile');
+  }
+
+  @override
+  Future<void> removeHotKeyProfile(String name) async {
+    await _channel.invokeMethod('removeHotKeyProfile', {'name': name});
+  }
+
+  @override
+  Future<DesktopFeatureCapability> getCapability() async {
+    return DesktopFeatureCapability.deserialize(
+        await _channel.invokeMethod('getCapability'));
+  }
+
   @override
   set delegate(HotKeyManagerDelegate? delegate) {
     _delegate = delegate;
@@ -11,9 +11,18 @@ extension ImageDataExt on ImageData {
       };
 }
 
+extension SharedTextureExt on SharedTexture {
+  dynamic serialize() => {
+        'kind': kind.name,
+        'handle': handle,
+        'devicePixelRatio': devicePixelRatio,
+      };
+}
+
 extension TargettedImageDataExt on TargetedImageData {
   dynamic serialize() => {
         'imageData': imageData.serialize(),
         'rect': rect.serialize(),
+        'sharedTexture': sharedTexture?.serialize(),
       };
 }
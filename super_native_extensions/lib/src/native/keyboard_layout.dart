@@ -29,6 +29,11 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
     if (call.method == 'onLayoutChanged') {
       _update(model.KeyboardLayout.deserialize(call.arguments));
       _onLayoutChanged.notify();
+    } else if (call.method == 'keyboardLayoutChanged') {
+      final layout = CurrentKeyboardLayout.deserialize(call.arguments);
+      for (final listener in List.of(_currentLayoutListeners)) {
+        listener(layout);
+      }
     }
   }
 
@@ -79,6 +84,52 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
 
   bool _supported = false;
 
+  @override
+  Future<KeyboardLayoutSnapshot?> getSnapshot() async {
+    final res = await _channel.invokeMethod('getKeyboardLayoutSnapshot');
+    if (res == null) {
+      return null;
+    }
+    final keys = ((res as Map)['keys'] as List).cast<Map>();
+    return KeyboardLayoutSnapshot(
+      keys: keys
+          .map((key) => KeySnapshot(
+                platform: key['platform'],
+                physical: key['physical'],
+                logical: key['logical'],
+                character: key['character'],
+                shiftedCharacter: key['shiftedCharacter'],
+                altGrCharacter: key['altGrCharacter'],
+                altGrShiftedCharacter: key['altGrShiftedCharacter'],
+              ))
+          .toList(growable: false),
+    );
+  }
+
+  @override
+  Future<CurrentKeyboardLayout?> getCurrentKeyboardLayout() async {
+    return CurrentKeyboardLayout.deserialize(
+        await _channel.invokeMethod('getCurrentKeyboardLayout'));
+  }
+
+  final _currentLayoutListeners = <CurrentKeyboardLayoutListener>[];
+
+  @override
+  void addCurrentLayoutListener(CurrentKeyboardLayoutListener listener) {
+    _currentLayoutListeners.add(listener);
+    if (_currentLayoutListeners.length == 1) {
+      _channel.invokeMethod('startLayoutMonitoring');
+    }
+  }
+
+  @override
+  void removeCurrentLayoutListener(CurrentKeyboardLayoutListener listener) {
+    if (_currentLayoutListeners.remove(listener) &&
+        _currentLayoutListeners.isEmpty) {
+      _channel.invokeMethod('stopLayoutMonitoring');
+    }
+  }
+
   @override
   bool get supported => _supported;
 
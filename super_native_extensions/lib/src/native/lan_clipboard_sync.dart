@@ -0,0 +1,90 @@
+import 'dart:typed_data';
+
+import 'package:flutter/foundation.dart';
+import 'package:flutter/services.dart';
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../lan_clipboard_sync.dart';
+import 'context.dart';
+
+class LanClipboardSyncImpl extends LanClipboardSync {
+  LanClipboardSyncImpl() {
+    _channel.setMethodCallHandler(_onMethodCall);
+  }
+
+  Future<dynamic> _onMethodCall(MethodCall call) async {
+    if (call.method == 'lanPeersChanged') {
+      final peers = (call.arguments as List)
+          .map(LanPeer.deserialize)
+          .toList(growable: false);
+      for (final listener in List.of(_peersListeners)) {
+        listener(peers);
+      }
+    } else if (call.method == 'lanClipboardReceived') {
+      final event = LanClipboardReceivedEvent.deserialize(call.arguments);
+      for (final listener in List.of(_receivedListeners)) {
+        listener(event);
+      }
+    }
+  }
+
+  @override
+  Future<void> start({
+    required String applicationId,
+    required String deviceName,
+    required Uint8List key,
+  }) async {
+    await _channel.invokeMethod('startLanSync', {
+      'applicationId': applicationId,
+      'deviceName': deviceName,
+      'key': key,
+    });
+  }
+
+  @override
+  Future<void> stop() async {
+    await _channel.invokeMethod('stopLanSync');
+  }
+
+  @override
+  Future<List<LanPeer>> getPeers() async {
+    final peers = await _channel.invokeMethod('getLanPeers') as List;
+    return peers.map(LanPeer.deserialize).toList(growable: false);
+  }
+
+  @override
+  Future<int> publish(List<List<LanRepresentation>> items) async {
+    return await _channel.invokeMethod('publishLanClipboard', {
+      'items': items
+          .map((item) => item.map((r) => r.serialize()).toList(growable: false))
+          .toList(growable: false),
+    }) as int;
+  }
+
+  @override
+  void addPeersListener(ValueChanged<List<LanPeer>> listener) {
+    _peersListeners.add(listener);
+  }
+
+  @override
+  void removePeersListener(ValueChanged<List<LanPeer>> listener) {
+    _peersListeners.remove(listener);
+  }
+
+  @override
+  void addReceivedListener(ValueChanged<LanClipboardReceivedEvent> listener) {
+    _receivedListeners.add(listener);
+  }
+
+  @override
+  void removeReceivedListener(
+      ValueChanged<LanClipboardReceivedEvent> listener) {
+    _receivedListeners.remove(listener);
+  }
+
+  final _peersListeners = <ValueChanged<List<LanPeer>>>[];
+  final _receivedListeners = <ValueChanged<LanClipboardReceivedEvent>>[];
+
+  final _channel = NativeMethodChannel('LanSyncManager',
+      context: superNativeExtensionsContext);
+}
@@ -0,0 +1,29 @@
+import 'dart:typed_data';
+
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../local_transfer.dart';
+import 'context.dart';
+
+class LocalTransferManagerImpl extends LocalTransferManager {
+  @override
+  Future<LocalTransferPayload> registerPayload({
+    Uint8List? data,
+    String? path,
+  }) async {
+    assert((data == null) != (path == null));
+    final res = await _channel.invokeMethod('registerPayload', {
+      'data': data,
+      'path': path,
+    });
+    return LocalTransferPayload.deserialize(res);
+  }
+
+  @override
+  Future<void> unregisterPayload(String token) async {
+    await _channel.invokeMethod('unregisterPayload', token);
+  }
+
+  final _channel = NativeMethodChannel('LocalTransferManager',
+      context: superNativeExtensionsContext);
+}
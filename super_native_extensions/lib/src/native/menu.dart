@@ -95,6 +95,7 @@ extension on MenuAction {
               destructive: attributes.destructive),
           'attributes': await attributes.serialize(),
           'state': state.name,
+          'radioGroup': radioGroup,
           'activator': activator?.serialize(),
         }
       };
@@ -109,6 +110,17 @@ extension on DeferredMenuElement {
       };
 }
 
+extension on DeferredMenu {
+  Future<dynamic> serialize(MenuSerializationOptions options) async => {
+        'type': 'deferredMenu',
+        'content': {
+          'uniqueId': uniqueId,
+          'title': title,
+          'image': await _serializeImage(image, options, destructive: false),
+        }
+      };
+}
+
 extension on MenuElement {
   Future<dynamic> serialize(MenuSerializationOptions options) {
     if (this is Menu) {
@@ -117,6 +129,8 @@ extension on MenuElement {
       return (this as MenuAction).serialize(options);
     } else if (this is DeferredMenuElement) {
       return (this as DeferredMenuElement).serialize();
+    } else if (this is DeferredMenu) {
+      return (this as DeferredMenu).serialize(options);
     } else if (this is MenuSeparator) {
       return (this as MenuSeparator).serialize();
     } else {
@@ -150,6 +164,18 @@ class NativeMenuHandle extends MenuHandle {
 
   final _onDispose = <VoidCallback>[];
 
+  @override
+  Future<void> updateItemState(
+    MenuAction action,
+    MenuActionState state,
+  ) async {
+    await _channel.invokeMethod('updateMenuItemState', {
+      'menuHandle': handle,
+      'uniqueId': action.uniqueId,
+      'state': state.name,
+    });
+  }
+
   @override
   void dispose() {
     for (final c in _onDispose) {
@@ -317,6 +343,23 @@ class MenuContextImpl extends MenuContext {
         }
         return {'elements': res};
       }, () => {'elements': []});
+    } else if (call.method == 'getDeferredMenuItems') {
+      return handleError(() async {
+        final id = call.arguments as int;
+        final element = _elementWithId(id);
+        Iterable<dynamic> res = [];
+        if (element != null && element.element is DeferredMenu) {
+          final menu = await _getDeferredElements(
+            element.handle,
+            (element.element as DeferredMenu).provider,
+          );
+          res = await Future.wait(menu.map((e) {
+            element.handle.elements.add(e);
+            return e.serialize(element.handle.serializationOptions);
+          }));
+        }
+        return {'elements': res};
+      }, () => {'elements': []});
     } else {
       return null;
     }
@@ -325,10 +368,17 @@ class MenuContextImpl extends MenuContext {
   Future<List<MenuElement>> getDeferredMenu(
     MenuHandle handle,
     DeferredMenuElement element,
+  ) {
+    return _getDeferredElements(handle, element.provider);
+  }
+
+  Future<List<MenuElement>> _getDeferredElements(
+    MenuHandle handle,
+    Future<List<MenuElement>> Function(CancellationToken) provider,
   ) async {
     final completer = Completer<List<MenuElement>>();
     final token = SimpleCancellationToken();
-    element.provider(token).then((value) {
+    provider(token).then((value) {
       if (!token.cancelled) {
         token.dispose();
         completer.complete(value);
@@ -356,6 +406,8 @@ extension MenuConfigurationExt on MobileMenuConfiguration {
             ? (await ImageData.fromImage(previewImage!.image)).serialize()
             : null,
         'previewSize': previewSize?.serialize(),
+        'previewRect': previewRect?.serialize(),
+        'previewCommitStyle': previewCommitStyle?.name,
         'liftImage': (await liftImage.intoRaw()).serialize(),
         'menuHandle': (handle as NativeMenuHandle).handle,
       };
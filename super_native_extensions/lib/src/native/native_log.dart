@@ -0,0 +1,35 @@
+import 'package:flutter/foundation.dart';
+import 'package:flutter/services.dart';
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../native_log.dart';
+import 'context.dart';
+
+class NativeLogImpl extends NativeLog {
+  NativeLogImpl() {
+    _channel.setMethodCallHandler(_onMethodCall);
+  }
+
+  Future<dynamic> _onMethodCall(MethodCall call) async {
+    if (call.method == 'onLog') {
+      _handler?.call(NativeLogEvent.deserialize(call.arguments));
+    }
+  }
+
+  @override
+  Future<void> setLogHandler(
+    ValueChanged<NativeLogEvent>? handler, {
+    NativeLogLevel level = NativeLogLevel.info,
+  }) async {
+    _handler = handler;
+    await _channel.invokeMethod(
+      'setLogHandler',
+      handler != null ? level.name : null,
+    );
+  }
+
+  ValueChanged<NativeLogEvent>? _handler;
+
+  final _channel = NativeMethodChannel('LogHandler',
+      context: superNativeExtensionsContext);
+}
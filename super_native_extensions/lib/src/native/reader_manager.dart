@@ -5,7 +5,9 @@ import 'package:flutter/foundation.dart';
 import 'package:flutter/services.dart';
 import 'package:irondash_message_channel/irondash_message_channel.dart';
 
+import 'binary_protocol.dart';
 import 'context.dart';
+import 'shared_buffer.dart';
 import '../reader.dart';
 import '../reader_manager.dart';
 import 'virtual_file.dart';
@@ -97,6 +99,45 @@ class ReaderManagerImpl extends ReaderManager {
   (Future<Object?>, ReadProgress) getItemData(
     DataReaderItemHandle handle, {
     required String format,
+    Duration? timeout,
+  }) {
+    return _getItemData(
+      handle,
+      format: format,
+      timeout: timeout,
+      allowPartial: false,
+    );
+  }
+
+  @override
+  (Future<PartialItemData>, ReadProgress) getItemDataPartial(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    final (data, progress) = _getItemData(
+      handle,
+      format: format,
+      timeout: timeout,
+      allowPartial: true,
+    );
+    return (
+      data.then((value) {
+        final map = value as Map;
+        return PartialItemData(
+          data: map['data'],
+          wasTruncated: map['wasTruncated'],
+        );
+      }),
+      progress,
+    );
+  }
+
+  (Future<Object?>, ReadProgress) _getItemData(
+    DataReaderItemHandle handle, {
+    required String format,
+    required Duration? timeout,
+    required bool allowPartial,
   }) {
     if (handle._reader._disposed) {
       throw StateError("Attempting to get data from disposed reader.");
@@ -109,8 +150,17 @@ class ReaderManagerImpl extends ReaderManager {
       "readerHandle": handle._readerHandle,
       "format": format,
       "progressId": progress.id,
+      "allowPartial": allowPartial,
+      "timeoutMs": timeout?.inMilliseconds,
     }).then((value) {
       _completeProgress(progress.id);
+      value = SharedBuffers.resolve(value);
+      if (_binaryItemData) {
+        if (value is List) {
+          value = value.map(SharedBuffers.resolve).toList(growable: false);
+        }
+        value = decodeBinaryRecord(value, BinaryRecordKind.itemData);
+      }
       completer.complete(value);
     }, onError: (error) {
       _completeProgress(progress.id);
@@ -190,11 +240,34 @@ class ReaderManagerImpl extends ReaderManager {
     return (completer.future, progress);
   }
 
+  /// Invokes method that reports progress under `progressId` argument.
+  (Future<T>, ReadProgress) _invokeWithProgress<T>(
+    String method,
+    Map<String, Object?> arguments,
+    T Function(dynamic) convert,
+  ) {
+    final progress = ReadProgressImpl(readerManager: this);
+    final completer = Completer<T>();
+    _progressMap[progress.id] = progress;
+    _channel.invokeMethod(method, {
+      ...arguments,
+      "progressId": progress.id,
+    }).then((value) {
+      _completeProgress(progress.id);
+      completer.complete(convert(value));
+    }, onError: (error) {
+      _completeProgress(progress.id);
+      completer.completeError(error);
+    });
+    return (completer.future, progress);
+  }
+
   void _completeProgress(int progressId) {
     final progress = _progressMap.remove(progressId);
     if (progress != null) {
       progress._fraction.value = 1.0;
     }
+    _compositeParts.remove(progressId)?._partFinished();
   }
 
   Future<dynamic> _onMethodCall(MethodCall call) async {
@@ -208,9 +281,50 @@ class ReaderManagerImpl extends ReaderManager {
       final progressId = args['progressId'] as int;
       final fraction = args['fraction'] as double?;
       _progressMap[progressId]?._fraction.value = fraction;
+    } else if (call.method == 'updateProgressBinary') {
+      final update = decodeBinaryProgress(call.arguments as Uint8List);
+      _progressMap[update.progressId]?._fraction.value = update.fraction;
+    } else if (call.method == 'convertFormat') {
+      return _convertFormat(call.arguments as Map);
+    } else if (call.method == 'formatsAvailable') {
+      final args = call.arguments as Map;
+      final subscription = _formatSubscriptions[args['subscriptionId'] as int];
+      if (subscription != null) {
+        subscription.onFormatsAvailable(
+          $DataReaderItemHandle._(
+              itemHandle: args['itemHandle'], reader: subscription.reader),
+          (args['formats'] as List).cast<String>(),
+        );
+      }
+    } else if (call.method == 'readerTransferred') {
+      final args = call.arguments as Map;
+      final reader = DataReaderHandle.deserialize(args['reader']);
+      final waiter = _transferWaiters.remove(reader._handle);
+      if (waiter != null) {
+        waiter.complete(reader);
+      } else {
+        // Source isolate may send the handle after clone was delivered.
+        _transferredReaders[reader._handle] = reader;
+      }
+    } else if (call.method == 'formatSubscriptionEnded') {
+      final args = call.arguments as Map;
+      final subscription =
+          _formatSubscriptions.remove(args['subscriptionId'] as int);
+      subscription?.onEnded?.call();
     }
   }
 
+  Future<Object?> _convertFormat(Map args) async {
+    final converter = _formatConverters[args['converterId'] as int];
+    if (converter == null) {
+      throw PlatformException(
+        code: 'unknownConverter',
+        message: 'Format converter ${args['converterId']} is not registered',
+      );
+    }
+    return await converter(args['data']);
+  }
+
   void cancelProgress(int progressId) {
     _channel.invokeMethod('cancelProgress', progressId);
   }
@@ -220,6 +334,580 @@ class ReaderManagerImpl extends ReaderManager {
 
   final _progressMap = <int, ReadProgressImpl>{};
 
+  /// Composite progresses by ids of their parts that did not finish yet.
+  final _compositeParts = <int, _CompositeReadProgress>{};
+
+  bool _binaryItemData = false;
+
+  final _formatConverters = <int, FormatConverterCallback>{};
+  int _nextFormatConverterId = 1;
+
+  final _formatSubscriptions = <int,
+      ({
+    DataReaderHandle reader,
+    FormatsAvailableCallback onFormatsAvailable,
+    VoidCallback? onEnded,
+  })>{};
+  int _nextFormatSubscriptionId = 1;
+
+  final _transferredReaders = <int, DataReaderHandle>{};
+  final _transferWaiters = <int, Completer<DataReaderHandle>>{};
+
+  @override
+  Future<DataReaderHandle> newExternalReader({
+    int? handle,
+    String? pasteboardName,
+  }) async {
+    assert((handle == null) != (pasteboardName == null));
+    final source = handle != null
+        ? {'type': 'handle', 'handle': handle}
+        : {'type': 'pasteboardName', 'name': pasteboardName};
+    final res = await _channel.invokeMethod('newExternalReader', source);
+    return DataReaderHandle.deserialize(res);
+  }
+
+  @override
+  Future<List<ItemFormatConversion>> getItemFormatConversions(
+      DataReaderItemHandle handle) async {
+    final res = await _channel.invokeMethod("getItemFormatConversions", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+    }) as List;
+    return res.map(ItemFormatConversion.deserialize).toList(growable: false);
+  }
+
+  @override
+  Future<void> setFormatConversionEnabled(
+    DataReaderHandle reader,
+    FormatConversion conversion, {
+    required bool enabled,
+  }) async {
+    await _channel.invokeMethod("setFormatConversionEnabled", {
+      "readerHandle": reader._handle,
+      "conversion": conversion.name,
+      "enabled": enabled,
+    });
+  }
+
+  @override
+  Future<SourceUrl?> getItemSourceUrl(DataReaderItemHandle handle) async {
+    final res = await _channel.invokeMethod("getItemSourceUrl", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+    });
+    return res != null ? SourceUrl.deserialize(res) : null;
+  }
+
+  @override
+  Future<WebArchive?> getItemWebArchive(DataReaderItemHandle handle) async {
+    final res = await _channel.invokeMethod("getItemWebArchive", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+    });
+    return res != null ? WebArchive.deserialize(res) : null;
+  }
+
+  @override
+  Future<Uint8List?> rasterizeItemMetafile(
+    DataReaderItemHandle handle, {
+    int? width,
+    int? height,
+  }) {
+    return _channel.invokeMethod("rasterizeItemMetafile", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "width": width,
+      "height": height,
+    });
+  }
+
+  @override
+  Future<ItemMetadata> getItemMetadata(DataReaderItemHandle handle) async {
+    final res = await _channel.invokeMethod("getItemMetadata", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+    });
+    return ItemMetadata.deserialize(res);
+  }
+
+  @override
+  Future<MediaInfo?> getItemMediaInfo(DataReaderItemHandle handle) async {
+    final res = await _channel.invokeMethod("getItemMediaInfo", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+    });
+    return res != null ? MediaInfo.deserialize(res) : null;
+  }
+
+  @override
+  Future<bool> isRemoteContent(DataReaderHandle reader) async {
+    return await _channel.invokeMethod("isRemoteContent", reader._handle);
+  }
+
+  @override
+  Future<bool> isStale(DataReaderHandle reader) async {
+    return await _channel.invokeMethod("isStale", reader._handle);
+  }
+
+  @override
+  Future<DataReaderHandle?> refresh(DataReaderHandle reader) async {
+    final res = await _channel.invokeMethod("refresh", reader._handle);
+    return res != null ? DataReaderHandle.deserialize(res) : null;
+  }
+
+  @override
+  Future<int> getIsolateId() async {
+    return await _channel.invokeMethod("getIsolateId");
+  }
+
+  @override
+  Future<int> cloneReaderForIsolate(
+    DataReaderHandle reader,
+    int targetIsolateId,
+  ) async {
+    return await _channel.invokeMethod("cloneReaderForIsolate", {
+      "readerHandle": reader._handle,
+      "targetIsolateId": targetIsolateId,
+    });
+  }
+
+  @override
+  Future<DataReaderHandle> receiveTransferredReader(int handle) {
+    final reader = _transferredReaders.remove(handle);
+    if (reader != null) {
+      return Future.value(reader);
+    }
+    final completer = Completer<DataReaderHandle>();
+    _transferWaiters[handle] = completer;
+    return completer.future;
+  }
+
+  @override
+  Future<CompositeReadProgress> createCompositeProgress({
+    required int partCount,
+    List<double>? weights,
+  }) async {
+    final parent = ReadProgressImpl(readerManager: this);
+    final partIds = List.generate(partCount, (_) => ReadProgressImpl._nextId++);
+    _progressMap[parent.id] = parent;
+    try {
+      await _channel.invokeMethod("createCompositeProgress", {
+        "progressId": parent.id,
+        "childProgressIds": partIds,
+        "weights": weights,
+      });
+    } catch (_) {
+      _completeProgress(parent.id);
+      rethrow;
+    }
+    final res = _CompositeReadProgress(
+      readerManager: this,
+      progress: parent,
+      partIds: partIds,
+    );
+    if (partCount == 0) {
+      _completeProgress(parent.id);
+    }
+    for (final id in partIds) {
+      _compositeParts[id] = res;
+    }
+    return res;
+  }
+
+  @override
+  Future<void> setOutOfProcessReading(bool enabled) async {
+    await _channel.invokeMethod("setOutOfProcessReading", enabled);
+  }
+
+  @override
+  Future<void> setExcludeRemoteContent(bool exclude) async {
+    await _channel.invokeMethod("setExcludeRemoteContent", exclude);
+  }
+
+  @override
+  Future<void> registerClipboardFormat({
+    required String identifier,
+    required String platformName,
+  }) async {
+    await _channel.invokeMethod("registerClipboardFormat", {
+      "identifier": identifier,
+      "platformName": platformName,
+    });
+  }
+
+  @override
+  (Future<String?>, ReadProgress) readItemLocalTransfer(
+    DataReaderItemHandle handle, {
+    required String targetPath,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("readItemLocalTransfer", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "targetPath": targetPath,
+    }, (value) => value as String?);
+  }
+
+  @override
+  (Future<List<ResolvedFile>>, ReadProgress) resolveItemsToFiles(
+    DataReaderHandle reader, {
+    Iterable<DataReaderItemHandle>? items,
+    required String targetFolder,
+    required List<String> fileUriFormats,
+  }) {
+    if (reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("resolveItemsToFiles", {
+      "readerHandle": reader._handle,
+      "itemHandles": items?.map((e) => e._itemHandle).toList(growable: false),
+      "fileUriFormats": fileUriFormats,
+      "targetFolder": targetFolder,
+    }, (value) {
+      return (value as List).map((e) {
+        final source = e['source'] as String?;
+        return ResolvedFile(
+          $DataReaderItemHandle._(itemHandle: e['itemHandle'], reader: reader),
+          path: e['path'],
+          format: e['format'],
+          source:
+              source != null ? ResolvedFileSource.values.byName(source) : null,
+        );
+      }).toList(growable: false);
+    });
+  }
+
+  @override
+  Future<void> setFilePolicy(FilePolicy? policy) async {
+    await _channel.invokeMethod('setFilePolicy', {
+      'policy': policy?.serialize(),
+    });
+  }
+
+  @override
+  Future<String> getManagedDirectory() async {
+    return await _channel.invokeMethod('getManagedDirectory');
+  }
+
+  @override
+  Future<void> setManagedDirectoryQuota(int? quota) async {
+    await _channel.invokeMethod('setManagedDirectoryQuota', quota);
+  }
+
+  @override
+  Future<String> claimManagedFile({
+    required String path,
+    required String targetFolder,
+  }) async {
+    return await _channel.invokeMethod('claimManagedFile', {
+      'path': path,
+      'targetFolder': targetFolder,
+    });
+  }
+
+  @override
+  Future<void> setTransformRules(List<TransformRule> rules) async {
+    await _channel.invokeMethod('setTransformRules', {
+      'rules': rules.map((r) => r.serialize()).toList(growable: false),
+    });
+  }
+
+  @override
+  Future<void> setRtfHtmlConversionEnabled(bool enabled) async {
+    await _channel.invokeMethod('setRtfHtmlConversionEnabled', enabled);
+  }
+
+  @override
+  Future<void> setBinaryProtocol({
+    bool itemData = false,
+    bool progress = false,
+    bool dropEvents = false,
+  }) async {
+    await _channel.invokeMethod('setBinaryProtocol', {
+      'itemData': itemData,
+      'progress': progress,
+      'dropEvents': dropEvents,
+    });
+    _binaryItemData = itemData;
+  }
+
+  @override
+  Future<void> setSharedBufferTransfer({int? thresholdBytes}) async {
+    await _channel.invokeMethod('setSharedBufferTransfer', {
+      'thresholdBytes': thresholdBytes,
+    });
+    SharedBuffers.thresholdBytes = thresholdBytes;
+  }
+
+  @override
+  (Future<VirtualFile>, ReadProgress) getVirtualFileData(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("getVirtualFileData", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "format": format,
+      "timeoutMs": timeout?.inMilliseconds,
+    }, (value) {
+      final response = value as Map;
+      return _VirtualFile(
+        readerManager: this,
+        handle: response['readerHandle'],
+        fileName: response['fileName'],
+        length: response['fileSize'],
+      );
+    });
+  }
+
+  @override
+  (Future<ManagedFile>, ReadProgress) getVirtualFileManaged(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("getVirtualFileManaged", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "format": format,
+      "timeoutMs": timeout?.inMilliseconds,
+    }, (value) {
+      final response = value as Map;
+      return _ManagedFile(
+        readerManager: this,
+        path: response['path'],
+        lease: response['lease'],
+        finalizableHandle: response['finalizableHandle'],
+      );
+    });
+  }
+
+  @override
+  Future<void> disposeFileLease(int lease) async {
+    await _channel.invokeMethod("disposeFileLease", lease);
+  }
+
+  @override
+  (Future<VirtualFile?>, ReadProgress) getItemDataStream(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("getItemDataStream", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "format": format,
+      "timeoutMs": timeout?.inMilliseconds,
+    }, (value) {
+      if (value == null) {
+        return null;
+      }
+      final response = value as Map;
+      return _VirtualFile(
+        readerManager: this,
+        handle: response['readerHandle'],
+        fileName: response['fileName'],
+        length: response['fileSize'],
+      );
+    });
+  }
+
+  @override
+  (Future<ImportResult>, ReadProgress) importItem(
+    DataReaderItemHandle handle, {
+    required List<ImportTarget> targets,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("importItem", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "targets": targets.map((t) => t.serialize()).toList(growable: false),
+    }, ImportResult.deserialize);
+  }
+
+  @override
+  (Future<ArchiveListing?>, ReadProgress) getArchiveEntries(
+    DataReaderItemHandle handle, {
+    required List<String> fileUriFormats,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("getArchiveEntries", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "fileUriFormats": fileUriFormats,
+    }, (value) => value != null ? ArchiveListing.deserialize(value) : null);
+  }
+
+  @override
+  Future<VirtualFile> readArchiveEntry(String archivePath, int index) async {
+    final response = await _channel.invokeMethod("readArchiveEntry", {
+      "archivePath": archivePath,
+      "index": index,
+    }) as Map;
+    return _VirtualFile(
+      readerManager: this,
+      handle: response['readerHandle'],
+      fileName: response['fileName'],
+      length: response['fileSize'],
+    );
+  }
+
+  @override
+  Future<String> extractArchiveEntry(
+    String archivePath,
+    int index,
+    String targetFolder,
+  ) async {
+    return await _channel.invokeMethod("extractArchiveEntry", {
+      "archivePath": archivePath,
+      "index": index,
+      "targetFolder": targetFolder,
+    });
+  }
+
+  @override
+  Future<int> registerFormatConverter({
+    required String sourceFormat,
+    required String targetFormat,
+    required FormatConverterCallback convert,
+  }) async {
+    final id = _nextFormatConverterId++;
+    _formatConverters[id] = convert;
+    await _channel.invokeMethod('registerFormatConverter', {
+      'converterId': id,
+      'sourceFormat': sourceFormat,
+      'targetFormat': targetFormat,
+    });
+    return id;
+  }
+
+  @override
+  Future<void> unregisterFormatConverter(int id) async {
+    _formatConverters.remove(id);
+    await _channel.invokeMethod('unregisterFormatConverter', id);
+  }
+
+  @override
+  (Future<List<RichTextSpan>?>, ReadProgress) getItemRichText(
+    DataReaderItemHandle handle,
+  ) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("getItemRichText", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+    }, (value) {
+      return (value as List?)
+          ?.map(RichTextSpan.deserialize)
+          .toList(growable: false);
+    });
+  }
+
+  @override
+  (Future<TextWithEntities?>, ReadProgress) getItemTextWithEntities(
+    DataReaderItemHandle handle, {
+    required String format,
+    List<EntityKind>? kinds,
+  }) {
+    if (handle._reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("getItemTextWithEntities", {
+      "itemHandle": handle._itemHandle,
+      "readerHandle": handle._readerHandle,
+      "format": format,
+      "kinds": kinds?.map((e) => e.name).toList(growable: false),
+    }, (value) => value != null ? TextWithEntities.deserialize(value) : null);
+  }
+
+  @override
+  (Future<List<ItemDataResult>>, ReadProgress) getItemDataMulti(
+    DataReaderHandle reader, {
+    required List<(DataReaderItemHandle, String)> items,
+    Duration? timeout,
+  }) {
+    if (reader._disposed) {
+      throw StateError("Attempting to get data from disposed reader.");
+    }
+    return _invokeWithProgress("getItemDataMulti", {
+      "readerHandle": reader._handle,
+      "items": items
+          .map((e) => {"itemHandle": e.$1._itemHandle, "format": e.$2})
+          .toList(growable: false),
+      "timeoutMs": timeout?.inMilliseconds,
+    }, (value) {
+      return (value as List).map((e) {
+        return ItemDataResult(
+          $DataReaderItemHandle._(itemHandle: e['itemHandle'], reader: reader),
+          format: e['format'],
+          data: e['data'],
+          error: e['error'],
+        );
+      }).toList(growable: false);
+    });
+  }
+
+  @override
+  (Future<DataReaderHandle>, ReadProgress) snapshotReader(
+    DataReaderHandle reader, {
+    int? sizeLimit,
+  }) {
+    return _invokeWithProgress("snapshotReader", {
+      "readerHandle": reader._handle,
+      "sizeLimit": sizeLimit,
+    }, DataReaderHandle.deserialize);
+  }
+
+  @override
+  Future<FormatSubscription> subscribeToFormats(
+    DataReaderHandle reader, {
+    List<String>? formats,
+    Duration? timeout,
+    required FormatsAvailableCallback onFormatsAvailable,
+    VoidCallback? onEnded,
+  }) async {
+    final id = _nextFormatSubscriptionId++;
+    // Registered before subscribing, events may arrive before the reply.
+    _formatSubscriptions[id] = (
+      reader: reader,
+      onFormatsAvailable: onFormatsAvailable,
+      onEnded: onEnded,
+    );
+    try {
+      await _channel.invokeMethod('subscribeToFormats', {
+        'readerHandle': reader._handle,
+        'subscriptionId': id,
+        'formats': formats,
+        'timeoutMs': timeout?.inMilliseconds,
+      });
+    } catch (_) {
+      _formatSubscriptions.remove(id);
+      rethrow;
+    }
+    return FormatSubscription(() async {
+      if (_formatSubscriptions.remove(id) != null) {
+        await _channel.invokeMethod('unsubscribeFromFormats', id);
+      }
+    });
+  }
+
   @override
   VirtualFile createVirtualFileFromUri(Uri uri) {
     final file = File(uri.toFilePath());
@@ -271,6 +959,7 @@ class ReaderManagerImpl extends ReaderManager {
       return DataReaderItemInfo(
         handle,
         formats: (e['formats'] as List).cast<String>(),
+        formatFidelity: (e['formatFidelity'] as List).cast<int>(),
         synthesizedFormats: (e['synthesizedFormats'] as List).cast<String>(),
         virtualReceivers: receivers,
         suggestedName: e['suggestedName'],
@@ -286,7 +975,16 @@ class ReadProgressImpl extends ReadProgress {
 
   ReadProgressImpl({
     required this.readerManager,
-  }) : id = _nextId++;
+  }) : id = _takeReservedId() ?? _nextId++;
+
+  /// Id reserved for part of composite progress, used by next progress.
+  static int? _reservedId;
+
+  static int? _takeReservedId() {
+    final res = _reservedId;
+    _reservedId = null;
+    return res;
+  }
 
   final ReaderManagerImpl readerManager;
 
@@ -307,6 +1005,54 @@ class ReadProgressImpl extends ReadProgress {
   final _fraction = ValueNotifier<double?>(null);
 }
 
+class _CompositeReadProgress extends CompositeReadProgress {
+  _CompositeReadProgress({
+    required this.readerManager,
+    required this.progress,
+    required this.partIds,
+  }) : _remaining = partIds.length;
+
+  final ReaderManagerImpl readerManager;
+
+  @override
+  final ReadProgressImpl progress;
+
+  final List<int> partIds;
+
+  int _remaining;
+
+  @override
+  int get partCount => partIds.length;
+
+  @override
+  T runPart<T>(int index, T Function() request) {
+    ReadProgressImpl._reservedId = partIds[index];
+    try {
+      return request();
+    } catch (_) {
+      skipPart(index);
+      rethrow;
+    } finally {
+      ReadProgressImpl._reservedId = null;
+    }
+  }
+
+  @override
+  void skipPart(int index) {
+    final id = partIds[index];
+    if (readerManager._compositeParts[id] == this) {
+      readerManager.cancelProgress(id);
+      readerManager._completeProgress(id);
+    }
+  }
+
+  void _partFinished() {
+    if (--_remaining == 0) {
+      readerManager._completeProgress(progress.id);
+    }
+  }
+}
+
 class _VirtualFile extends VirtualFile {
   _VirtualFile({
     required this.readerManager,
@@ -336,6 +1082,35 @@ class _VirtualFile extends VirtualFile {
   final int? length;
 }
 
+class _ManagedFile extends ManagedFile {
+  _ManagedFile({
+    required this.readerManager,
+    required this.path,
+    required this.lease,
+    required this.finalizableHandle,
+  });
+
+  @override
+  Future<void> dispose() async {
+    if (!_disposed) {
+      _disposed = true;
+      await readerManager.disposeFileLease(lease);
+    }
+  }
+
+  final ReaderManagerImpl readerManager;
+
+  @override
+  final String path;
+
+  final int lease;
+
+  /// Disposes the lease once this file is garbage collected.
+  final FinalizableHandle finalizableHandle;
+
+  bool _disposed = false;
+}
+
 class _VirtualFileReceiver extends VirtualFileReceiver {
   _VirtualFileReceiver({
     required this.readerManager,
@@ -0,0 +1,42 @@
+import 'package:flutter/foundation.dart';
+import 'package:flutter/services.dart';
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../resource_sweeper.dart';
+import 'context.dart';
+
+class ResourceSweeperImpl extends ResourceSweeper {
+  ResourceSweeperImpl() {
+    _channel.setMethodCallHandler(_onMethodCall);
+  }
+
+  Future<dynamic> _onMethodCall(MethodCall call) async {
+    if (call.method == 'onSweepReport') {
+      _lastReport.value = SweepReport.deserialize(call.arguments);
+    }
+  }
+
+  @override
+  Future<void> configure(SweeperConfiguration configuration) async {
+    await _channel.invokeMethod('configure', configuration.serialize());
+  }
+
+  @override
+  Future<SweepReport> sweepNow() async {
+    return SweepReport.deserialize(await _channel.invokeMethod('sweepNow'));
+  }
+
+  @override
+  Future<List<TaskDiagnostics>> getTaskDiagnostics() async {
+    final res = await _channel.invokeMethod('getTaskDiagnostics') as List;
+    return res.map(TaskDiagnostics.deserialize).toList(growable: false);
+  }
+
+  @override
+  ValueListenable<SweepReport?> get lastReport => _lastReport;
+
+  final _lastReport = ValueNotifier<SweepReport?>(null);
+
+  final _channel = NativeMethodChannel('ResourceSweeper',
+      context: superNativeExtensionsContext);
+}
@@ -0,0 +1,63 @@
+import 'package:flutter/services.dart';
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../data_provider.dart';
+import '../reader.dart';
+import '../services.dart';
+import 'context.dart';
+import 'reader_manager.dart';
+
+class ServicesManagerImpl extends ServicesManager {
+  ServicesManagerImpl() {
+    _channel.setMethodCallHandler(_onMethodCall);
+  }
+
+  Future<dynamic> _onMethodCall(MethodCall call) async {
+    if (call.method == 'releaseDataProvider') {
+      final provider = _activeProviders.remove(call.arguments as int);
+      if (provider != null) {
+        provider.dispose();
+      }
+    } else if (call.method == 'onServicesData') {
+      final map = call.arguments as Map;
+      final reader = DataReader(
+        handle: $DataReaderHandle.deserialize(map['reader']),
+      );
+      final event = ServicesDataEvent(
+        reader: reader,
+        source: ServicesDataSource.values.byName(map['source']),
+        userData: map['userData'],
+      );
+      final handler = onServicesData;
+      if (handler != null) {
+        handler(event);
+      } else {
+        reader.dispose();
+      }
+    }
+  }
+
+  @override
+  Future<void> setServicesContent(
+    List<DataProviderHandle> providers, {
+    List<String> returnTypes = const [],
+  }) async {
+    await _channel.invokeMethod('setServicesContent', {
+      'providerIds': providers.map((e) => e.id).toList(),
+      'returnTypes': returnTypes,
+    });
+    for (final provider in providers) {
+      _activeProviders[provider.id] = provider;
+    }
+  }
+
+  @override
+  Future<void> setServicesProviderEnabled(bool enabled) async {
+    await _channel.invokeMethod('setServicesProviderEnabled', enabled);
+  }
+
+  final _channel = NativeMethodChannel('ServicesManager',
+      context: superNativeExtensionsContext);
+
+  final _activeProviders = <int, DataProviderHandle>{};
+}
@@ -0,0 +1,84 @@
+import 'dart:ffi';
+import 'dart:typed_data';
+
+import 'package:ffi/ffi.dart';
+
+import 'context.dart';
+
+/// Large byte payloads exchanged through native memory instead of being
+/// copied by the message channel. See `shared_buffer.rs`.
+class SharedBuffers {
+  SharedBuffers._();
+
+  /// Payloads of at least this size are shared. `null` disables sharing.
+  static int? thresholdBytes;
+
+  /// Key marking shared buffer references (`shared_buffer.rs`).
+  static const _tag = '__sharedBuffer';
+
+  /// Wraps buffer referenced by [value] in external typed data that releases
+  /// the buffer once collected. Other values are returned unchanged.
+  static Object? resolve(Object? value) {
+    if (thresholdBytes == null ||
+        value is! Map ||
+        value.length != 4 ||
+        value[_tag] != true) {
+      return value;
+    }
+    final id = value['id'] as int;
+    final address = value['address'] as int;
+    final length = value['length'] as int;
+    return Pointer<Uint8>.fromAddress(address).asTypedList(
+      length,
+      finalizer: _NativeFunctions.instance.finalize,
+      token: Pointer<Void>.fromAddress(id),
+    );
+  }
+
+  /// Moves [value] to native buffer if it is large enough. Rust takes the
+  /// returned reference over without copying.
+  static Object? share(Object? value) {
+    final threshold = thresholdBytes;
+    if (threshold == null || value is! Uint8List || value.length < threshold) {
+      return value;
+    }
+    final functions = _NativeFunctions.instance;
+    final handle = malloc<Int64>();
+    try {
+      final buffer = functions.allocate(value.length, handle);
+      if (buffer == nullptr) {
+        return value;
+      }
+      buffer.asTypedList(value.length).setAll(0, value);
+      return {_tag: true, 'id': handle.value};
+    } finally {
+      malloc.free(handle);
+    }
+  }
+}
+
+class _NativeFunctions {
+  _NativeFunctions({
+    required this.allocate,
+    required this.finalize,
+  });
+
+  static _NativeFunctions? _instance;
+
+  static _NativeFunctions get instance {
+    if (_instance == null) {
+      final dylib = openNativeLibrary();
+      final allocate = dylib
+          .lookup<NativeFunction<Pointer<Uint8> Function(Int64, Pointer<Int64>)>>(
+              'super_native_extensions_shared_buffer_allocate')
+          .asFunction<Pointer<Uint8> Function(int, Pointer<Int64>)>();
+      final finalize = dylib.lookup<NativeFinalizerFunction>(
+          'super_native_extensions_shared_buffer_finalize');
+      _instance = _NativeFunctions(allocate: allocate, finalize: finalize);
+    }
+    return _instance!;
+  }
+
+  final Pointer<Uint8> Function(int len, Pointer<Int64> outHandle) allocate;
+  final Pointer<NativeFinalizerFunction> finalize;
+}
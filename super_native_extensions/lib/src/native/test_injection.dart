@@ -0,0 +1,32 @@
+import 'dart:ui' as ui;
+
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../data_provider.dart';
+import '../drop.dart';
+import '../test_injection.dart';
+import '../util.dart';
+import 'context.dart';
+
+class TestInjectionImpl extends TestInjection {
+  @override
+  Future<SimulatedDragResult> simulateDrag({
+    required List<DataProviderHandle> providers,
+    required List<ui.Offset> locations,
+    List<DropOperation> allowedOperations = const [DropOperation.copy],
+    bool performDrop = true,
+    int? engineHandle,
+  }) async {
+    final res = await _channel.invokeMethod('simulateDrag', {
+      'engineHandle': engineHandle,
+      'providerIds': providers.map((e) => e.id).toList(),
+      'locations': locations.map((e) => e.serialize()).toList(),
+      'allowedOperations': allowedOperations.map((e) => e.name).toList(),
+      'performDrop': performDrop,
+    });
+    return SimulatedDragResult.deserialize(res);
+  }
+
+  final _channel = NativeMethodChannel('TestInjection',
+      context: superNativeExtensionsContext);
+}
@@ -0,0 +1,73 @@
+import 'package:flutter/services.dart';
+import 'package:irondash_message_channel/irondash_message_channel.dart';
+
+import '../desktop_capability.dart';
+import '../image_data.dart';
+import '../tray_icon.dart';
+import '../util.dart';
+import 'context.dart';
+import 'image_data.dart';
+
+class TrayIconManagerImpl extends TrayIconManager {
+  TrayIconManagerImpl() {
+    _channel.setMethodCallHandler(_onMethodCall);
+  }
+
+  Future<dynamic> _onMethodCall(MethodCall call) async {
+    if (call.method == 'onTrayIconEvent') {
+      final arguments = call.arguments as Map;
+      _delegate?.onTrayIconEvent(TrayIconEvent(
+        handle: arguments['handle'],
+        type: TrayIconEventType.values.byName(arguments['eventType']),
+        position: OffsetExt.deserialize(arguments['position']),
+      ));
+    }
+  }
+
+  @override
+  Future<int?> createTrayIcon({
+    required ImageData image,
+    String? tooltip,
+    bool customTooltip = false,
+  }) async {
+    return _channel.invokeMethod('createTrayIcon', {
+      'image': image.serialize(),
+      'tooltip': tooltip,
+      'customTooltip': customTooltip,
+    });
+  }
+
+  @override
+  Future<void> updateTrayIcon(
+    int handle, {
+    ImageData? image,
+    String? tooltip,
+  }) async {
+    await _channel.invokeMethod('updateTrayIcon', {
+      'handle': handle,
+      'image': image?.serialize(),
+      'tooltip': tooltip,
+    });
+  }
+
+  @override
+  Future<void> destroyTrayIcon(int handle) async {
+    await _channel.invokeMethod('destroyTrayIcon', {'handle': handle});
+  }
+
+  @override
+  Future<DesktopFeatureCapability> getCapability() async {
+    return DesktopFeatureCapability.deserialize(
+        await _channel.invokeMethod('getCapability'));
+  }
+
+  @override
+  set delegate(TrayIconManagerDelegate? delegate) {
+    _delegate = delegate;
+  }
+
+  TrayIconManagerDelegate? _delegate;
+
+  final _channel = NativeMethodChannel('TrayIconManager',
+      context: superNativeExtensionsContext);
+}
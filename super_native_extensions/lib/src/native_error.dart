@@ -0,0 +1,49 @@
+import 'package:flutter/services.dart';
+
+/// How caller can recover from a native extensions error.
+enum NativeErrorRecovery {
+  /// Transient failure, same operation may succeed when retried.
+  retry,
+
+  /// Needs user to do something first (grant permission, copy again).
+  userAction,
+
+  /// Operation was cancelled, nothing to recover from.
+  notNeeded,
+
+  /// Retrying will fail the same way.
+  unrecoverable,
+}
+
+extension NativeErrorInfo on PlatformException {
+  /// Whether this error was reported by super_native_extensions.
+  bool get isNativeExtensionsError => code == 'super_native_extensions_error';
+
+  /// Error code (for example `timeout` or `blockedByPolicy`) sent as
+  /// [details], or `null` for other errors.
+  String? get nativeErrorCode =>
+      isNativeExtensionsError && details is String ? details as String : null;
+
+  /// Recovery hint derived from [nativeErrorCode]. Mirrors
+  /// `NativeExtensionsError::recovery` except that I/O errors are always
+  /// reported as [NativeErrorRecovery.unrecoverable].
+  NativeErrorRecovery? get nativeErrorRecovery {
+    final code = nativeErrorCode;
+    if (code == null) {
+      return null;
+    }
+    switch (code) {
+      case 'timeout':
+      case 'sourceAppUnresponsive':
+        return NativeErrorRecovery.retry;
+      case 'blockedByPolicy':
+      case 'accessDenied':
+      case 'clipboardNotOwned':
+        return NativeErrorRecovery.userAction;
+      case 'cancelled':
+        return NativeErrorRecovery.notNeeded;
+      default:
+        return NativeErrorRecovery.unrecoverable;
+    }
+  }
+}
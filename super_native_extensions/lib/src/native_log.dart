@@ -0,0 +1,72 @@
+import 'package:flutter/foundation.dart';
+
+import 'native/native_log.dart' if (dart.library.js) 'web/native_log.dart';
+
+/// Ordered from most to least severe.
+enum NativeLogLevel {
+  error,
+  warn,
+  info,
+  debug,
+  trace,
+}
+
+/// Event logged by native code.
+class NativeLogEvent {
+  NativeLogEvent({
+    required this.level,
+    required this.module,
+    required this.message,
+    this.operationId,
+    this.operation,
+    this.duration,
+    this.file,
+    this.line,
+  });
+
+  static NativeLogEvent deserialize(dynamic event) {
+    final map = event as Map;
+    final durationMs = map['durationMs'] as double?;
+    return NativeLogEvent(
+      level: NativeLogLevel.values.byName(map['level']),
+      module: map['module'],
+      message: map['message'],
+      operationId: map['operationId'],
+      operation: map['operation'],
+      duration: durationMs != null
+          ? Duration(microseconds: (durationMs * 1000).round())
+          : null,
+      file: map['file'],
+      line: map['line'],
+    );
+  }
+
+  final NativeLogLevel level;
+  final String module;
+  final String message;
+
+  /// Set for events of long running operations; all events of one operation
+  /// share the id.
+  final int? operationId;
+  final String? operation;
+
+  /// Set for events ending an operation.
+  final Duration? duration;
+  final String? file;
+  final int? line;
+
+  @override
+  String toString() => '[${level.name}] $module: $message';
+}
+
+abstract class NativeLog {
+  static final NativeLog instance = NativeLogImpl();
+
+  /// Forwards native log events of [level] and more severe ones to
+  /// [handler]. Events are still written to the platform log. `null`
+  /// handler stops forwarding.
+  Future<void> setLogHandler(
+    ValueChanged<NativeLogEvent>? handler, {
+    NativeLogLevel level = NativeLogLevel.info,
+  });
+}
@@ -19,14 +19,293 @@ class DataReader {
     required DataReaderHandle handle,
   }) : _handle = handle;
 
+  /// Creates reader for platform data object registered by native code of
+  /// another plugin, through `super_native_extensions_register_data_object`
+  /// on Windows or `SuperNativeExtensionsPlugin.registerClipData` on Android.
+  /// The handle can only be redeemed once.
+  static Future<DataReader> fromExternalHandle(int handle) async {
+    return DataReader(
+      handle: await ReaderManager.instance.newExternalReader(handle: handle),
+    );
+  }
+
+  /// Creates reader for named pasteboard on macOS and iOS.
+  static Future<DataReader> forPasteboard(String name) async {
+    return DataReader(
+      handle: await ReaderManager.instance
+          .newExternalReader(pasteboardName: name),
+    );
+  }
+
+  /// Enables or disables built-in format conversion for this reader.
+  /// Items obtained afterwards report formats accordingly.
+  Future<void> setFormatConversionEnabled(
+    FormatConversion conversion, {
+    required bool enabled,
+  }) {
+    return _mutex.protect(() async {
+      await ReaderManager.instance
+          .setFormatConversionEnabled(_handle, conversion, enabled: enabled);
+      _items = null;
+    });
+  }
+
+  /// Whether reading the data may pull it from another device through
+  /// Universal Clipboard (macOS, iOS). Progress of such transfer is reported
+  /// through [ReadProgress] of the read.
+  Future<bool> isRemoteContent() =>
+      ReaderManager.instance.isRemoteContent(_handle);
+
+  /// Creates progress combining [partCount] read requests, weighted by
+  /// [weights] (such as file sizes) or equally when not set. Weights must be
+  /// finite. Not available on web.
+  static Future<CompositeReadProgress> createCompositeProgress({
+    required int partCount,
+    List<double>? weights,
+  }) =>
+      ReaderManager.instance
+          .createCompositeProgress(partCount: partCount, weights: weights);
+
+  /// When enabled, data from other applications is read in a helper process
+  /// so that misbehaving sources can not block or crash the application.
+  /// Applies to readers created afterwards. Windows only; fails with
+  /// unsupported operation error elsewhere.
+  static Future<void> setOutOfProcessReading(bool enabled) =>
+      ReaderManager.instance.setOutOfProcessReading(enabled);
+
+  /// Excludes Universal Clipboard content from clipboard readers created
+  /// afterwards (macOS, iOS).
+  static Future<void> setExcludeRemoteContent(bool exclude) =>
+      ReaderManager.instance.setExcludeRemoteContent(exclude);
+
+  /// Makes private clipboard format [platformName] of another application
+  /// appear as [identifier] when reading and writing (Windows only). Names
+  /// of formats handled by the plugin itself are rejected.
+  static Future<void> registerClipboardFormat({
+    required String identifier,
+    required String platformName,
+  }) =>
+      ReaderManager.instance.registerClipboardFormat(
+        identifier: identifier,
+        platformName: platformName,
+      );
+
+  /// Replaces policy for files received from other applications; `null`
+  /// allows all files. Blocked files are rejected before they are written
+  /// and reads fail with `FilePolicyViolation` error.
+  static Future<void> setFilePolicy(FilePolicy? policy) =>
+      ReaderManager.instance.setFilePolicy(policy);
+
+  /// Directory received files are written to. Unclaimed files count against
+  /// the quota, oldest are evicted once it is exceeded. The directory is
+  /// removed when the application exits. Not available on web.
+  static Future<String> getManagedDirectory() =>
+      ReaderManager.instance.getManagedDirectory();
+
+  /// Sets maximum total size of unclaimed files in managed directory in
+  /// bytes; `null` for unlimited. Defaults to 1 GiB.
+  static Future<void> setManagedDirectoryQuota(int? quota) =>
+      ReaderManager.instance.setManagedDirectoryQuota(quota);
+
+  /// Moves file at [path] out of managed directory into [targetFolder] so
+  /// that it is no longer evicted. Returns the new path. Leased files can
+  /// not be claimed.
+  static Future<String> claimManagedFile({
+    required String path,
+    required String targetFolder,
+  }) =>
+      ReaderManager.instance.claimManagedFile(
+        path: path,
+        targetFolder: targetFolder,
+      );
+
+  /// Replaces transformations applied to text read from readers of current
+  /// isolate. Rules run in order; empty list removes all rules.
+  static Future<void> setTransformRules(List<TransformRule> rules) =>
+      ReaderManager.instance.setTransformRules(rules);
+
+  /// When enabled, readers of current isolate synthesize HTML for items that
+  /// only have RTF. Only inline styling and links are preserved. Not
+  /// supported on web.
+  static Future<void> setRtfHtmlConversionEnabled(bool enabled) =>
+      ReaderManager.instance.setRtfHtmlConversionEnabled(enabled);
+
+  /// Registers converter synthesizing [targetFormat] from [sourceFormat] for
+  /// readers of current isolate. Items that have [sourceFormat] but not
+  /// [targetFormat] report [targetFormat] right after [sourceFormat];
+  /// reading it passes the source data to [convert]. Returns id for
+  /// [unregisterFormatConverter].
+  static Future<int> registerFormatConverter({
+    required String sourceFormat,
+    required String targetFormat,
+    required FormatConverterCallback convert,
+  }) =>
+      ReaderManager.instance.registerFormatConverter(
+        sourceFormat: sourceFormat,
+        targetFormat: targetFormat,
+        convert: convert,
+      );
+
+  static Future<void> unregisterFormatConverter(int id) =>
+      ReaderManager.instance.unregisterFormatConverter(id);
+
+  /// Whether clipboard contents changed since this reader was created or
+  /// last refreshed. Always `false` for readers that don't read clipboard.
+  Future<bool> isStale() => ReaderManager.instance.isStale(_handle);
+
+  /// Rebinds clipboard reader to current clipboard contents if they changed.
+  /// Items obtained earlier keep reading contents they were created for.
+  /// Returns whether the contents changed.
+  Future<bool> refresh() {
+    return _mutex.protect(() async {
+      final handle = await ReaderManager.instance.refresh(_handle);
+      if (handle == null) {
+        return false;
+      }
+      _handle = handle;
+      _items = null;
+      return true;
+    });
+  }
+
+  /// Id of current isolate, to be sent to isolates that hand readers over to
+  /// it through [cloneForIsolate].
+  static Future<int> currentIsolateId() =>
+      ReaderManager.instance.getIsolateId();
+
+  /// Makes this reader available to isolate [targetIsolateId] without copying
+  /// the data. Returns handle to send to the target isolate, which obtains
+  /// the reader through [receiveTransferred]. The clone is disposed
+  /// independently of this reader.
+  Future<int> cloneForIsolate(int targetIsolateId) =>
+      ReaderManager.instance.cloneReaderForIsolate(_handle, targetIsolateId);
+
+  /// Returns reader another isolate cloned for current isolate through
+  /// [cloneForIsolate].
+  static Future<DataReader> receiveTransferred(int handle) async {
+    return DataReader(
+      handle: await ReaderManager.instance.receiveTransferredReader(handle),
+    );
+  }
+
+  /// Resolves items to local files, whatever the source provided: files
+  /// referenced by items are returned as they are, virtual files and item
+  /// data are written into [targetFolder]. [fileUriFormats] are formats
+  /// holding file URI on current platform. All reader items are resolved
+  /// unless [items] are specified.
+  (Future<List<ResolvedFile>>, ReadProgress) resolveItemsToFiles({
+    required String targetFolder,
+    required List<String> fileUriFormats,
+    Iterable<DataReaderItem>? items,
+  }) {
+    return ReaderManager.instance.resolveItemsToFiles(
+      _handle,
+      items: items?.map((e) => e._handle),
+      targetFolder: targetFolder,
+      fileUriFormats: fileUriFormats,
+    );
+  }
+
+  /// Reads data of multiple (item, format) pairs with single request and
+  /// progress. Failure of one entry doesn't fail the others; cancelling the
+  /// progress returns results of entries read so far. Entries that take
+  /// longer than [timeout] fail with timeout error.
+  (Future<List<ItemDataResult>>, ReadProgress) getItemDataMulti(
+    List<(DataReaderItem, String)> items, {
+    Duration? timeout,
+  }) {
+    return ReaderManager.instance.getItemDataMulti(
+      _handle,
+      items: items.map((e) => (e.$1._handle, e.$2)).toList(growable: false),
+      timeout: timeout,
+    );
+  }
+
+  /// Returns reader that serves current contents of this reader from
+  /// memory, so that reading does not fail or return different data when
+  /// clipboard contents change before paste completes. Virtual files are
+  /// still read from the source and formats blocked by file policy are not
+  /// copied. Fails if data exceeds [sizeLimit] bytes (256 MiB by default)
+  /// or progress is cancelled.
+  (Future<DataReader>, ReadProgress) snapshot({int? sizeLimit}) {
+    final (future, progress) =
+        ReaderManager.instance.snapshotReader(_handle, sizeLimit: sizeLimit);
+    return (future.then((handle) => DataReader(handle: handle)), progress);
+  }
+
+  /// Reports formats added to clipboard contents after this reader was
+  /// created, for example by applications that provide some formats with a
+  /// delay. Reports end when all [formats] (any formats when `null`) are
+  /// available, after [timeout] (3 seconds by default) or when cancelled;
+  /// [onEnded] is invoked unless the subscription was cancelled. Clipboard
+  /// is checked whenever it changes, so formats added without changing the
+  /// clipboard are not reported. Readers that don't read clipboard end the
+  /// subscription right away.
+  Future<FormatSubscription> subscribeToFormats({
+    List<String>? formats,
+    Duration? timeout,
+    required void Function(DataReaderItem item, List<String> formats)
+        onFormatsAvailable,
+    VoidCallback? onEnded,
+  }) {
+    return ReaderManager.instance.subscribeToFormats(
+      _handle,
+      formats: formats,
+      timeout: timeout,
+      onFormatsAvailable: (handle, formats) =>
+          onFormatsAvailable(DataReaderItem(handle: handle), formats),
+      onEnded: onEnded,
+    );
+  }
+
   Future<void> dispose() => ReaderManager.instance.dispose(_handle);
 
   final _mutex = Mutex();
 
-  final DataReaderHandle _handle;
+  DataReaderHandle _handle;
   List<DataReaderItem>? _items;
 }
 
+/// Built-in conversions readers perform to provide formats missing in the
+/// source data.
+enum FormatConversion {
+  /// PNG from TIFF (macOS).
+  tiffToPng,
+
+  /// PNG from DIB or DIBV5 bitmap (Windows).
+  bitmapToPng,
+
+  /// `text/html` fragment from CF_HTML (Windows).
+  cfHtmlToHtml,
+}
+
+/// Conversion that applies to an item given its source formats.
+class ItemFormatConversion {
+  ItemFormatConversion({
+    required this.conversion,
+    required this.sourceFormat,
+    required this.targetFormat,
+    required this.enabled,
+  });
+
+  static ItemFormatConversion deserialize(dynamic conversion) {
+    final map = conversion as Map;
+    return ItemFormatConversion(
+      conversion: FormatConversion.values.byName(map['conversion']),
+      sourceFormat: map['sourceFormat'],
+      targetFormat: map['targetFormat'],
+      enabled: map['enabled'],
+    );
+  }
+
+  final FormatConversion conversion;
+  final String sourceFormat;
+  final String targetFormat;
+
+  /// Disabled conversions are not reported in item formats.
+  final bool enabled;
+}
+
 /// Progress of a read operation.
 abstract class ReadProgress {
   /// Range is 0.0 to 1.0.
@@ -43,10 +322,41 @@ abstract class ReadProgress {
   void cancel();
 }
 
+/// Progress of a batch of read requests, such as reading many dropped files,
+/// made of weighted parts. Cancelling [progress] cancels all parts.
+abstract class CompositeReadProgress {
+  /// Weighted total of the parts; reaches 1.0 once all parts finished.
+  ReadProgress get progress;
+
+  int get partCount;
+
+  /// Runs [request] so that the read it starts reports as part [index].
+  /// The read must be started synchronously, as with
+  /// [DataReaderItem.getDataForFormat]. Every part must be run once;
+  /// [skipPart] finishes part that won't be read.
+  T runPart<T>(int index, T Function() request);
+
+  void skipPart(int index);
+}
+
+/// Result of [DataReaderItem.getPartialDataForFormat].
+class PartialItemData {
+  PartialItemData({
+    required this.data,
+    required this.wasTruncated,
+  });
+
+  final Object? data;
+
+  /// Whether the read was cancelled before all data was received.
+  final bool wasTruncated;
+}
+
 class DataReaderItemInfo {
   DataReaderItemInfo(
     this._handle, {
     required this.formats,
+    required this.formatFidelity,
     required this.synthesizedFormats,
     required this.virtualReceivers,
     required this.suggestedName,
@@ -55,6 +365,12 @@ class DataReaderItemInfo {
 
   DataReaderItem get item => DataReaderItem(handle: _handle);
   final List<String> formats;
+
+  /// Fidelity rank for each of [formats]; higher rank means richer
+  /// representation of the same content (i.e. HTML over plain text). Unknown
+  /// formats have rank 0.
+  final List<int> formatFidelity;
+
   final List<String> synthesizedFormats;
   final List<VirtualFileReceiver> virtualReceivers;
   final String? suggestedName;
@@ -71,10 +387,157 @@ class DataReaderItem {
     });
   }
 
+  /// Fails if data is not received within [timeout]; progress is cancelled
+  /// in that case.
   (Future<Object?>, ReadProgress) getDataForFormat(
-    String format,
-  ) {
-    return ReaderManager.instance.getItemData(_handle, format: format);
+    String format, {
+    Duration? timeout,
+  }) {
+    return ReaderManager.instance
+        .getItemData(_handle, format: format, timeout: timeout);
+  }
+
+  /// Like [getDataForFormat], but cancelling the progress completes with data
+  /// received so far instead of failing. Reads that go through format
+  /// conversion or transform rules are never truncated.
+  (Future<PartialItemData>, ReadProgress) getPartialDataForFormat(
+    String format, {
+    Duration? timeout,
+  }) {
+    return ReaderManager.instance
+        .getItemDataPartial(_handle, format: format, timeout: timeout);
+  }
+
+  /// Format synthesized for items that reference files, see
+  /// [getFileUriList].
+  static const fileUriListFormat = 'fileUriList';
+
+  /// Returns normalized absolute paths of local files referenced by this
+  /// item (`CF_HDROP`, `public.file-url` or `text/uri-list`), or `null` if
+  /// the item doesn't reference any. Not supported on web.
+  (Future<List<String>?>, ReadProgress) getFileUriList({Duration? timeout}) {
+    final (data, progress) =
+        getDataForFormat(fileUriListFormat, timeout: timeout);
+    return (
+      data.then((value) => (value as List?)?.cast<String>()),
+      progress,
+    );
+  }
+
+  /// Built-in conversions that apply to this item.
+  Future<List<ItemFormatConversion>> getFormatConversions() {
+    return ReaderManager.instance.getItemFormatConversions(_handle);
+  }
+
+  /// Returns URL of the page this item was copied or dragged from, if known.
+  Future<SourceUrl?> getSourceUrl() {
+    return ReaderManager.instance.getItemSourceUrl(_handle);
+  }
+
+  /// Returns decoded web archive (Safari content on macOS and iOS), if the
+  /// item contains one.
+  Future<WebArchive?> getWebArchive() {
+    return ReaderManager.instance.getItemWebArchive(_handle);
+  }
+
+  /// Renders metafile content of this item (Windows) to PNG. When only one
+  /// dimension (in pixels, up to 16384) is specified the other is derived
+  /// from picture aspect ratio; natural size is used otherwise.
+  Future<Uint8List?> rasterizeMetafile({
+    int? width,
+    int? height,
+  }) {
+    return ReaderManager.instance
+        .rasterizeItemMetafile(_handle, width: width, height: height);
+  }
+
+  /// Returns item properties that are available without reading item data.
+  Future<ItemMetadata> getMetadata() {
+    return ReaderManager.instance.getItemMetadata(_handle);
+  }
+
+  /// Returns media description if this item is an audio or video clip.
+  Future<MediaInfo?> getMediaInfo() {
+    return ReaderManager.instance.getItemMediaInfo(_handle);
+  }
+
+  /// Streams payload registered by source application through
+  /// `LocalTransferManager` into [targetPath]. Returns `null` if the item
+  /// has no such payload or the source can not be reached.
+  (Future<String?>, ReadProgress) readLocalTransfer({
+    required String targetPath,
+  }) {
+    return ReaderManager.instance
+        .readItemLocalTransfer(_handle, targetPath: targetPath);
+  }
+
+  /// Reads virtual file in [format] in chunks without providing a target
+  /// folder. Files that can only be copied are staged in a temporary folder
+  /// that is removed once the returned file is closed. [timeout] only
+  /// applies to the staging.
+  (Future<VirtualFile>, ReadProgress) getVirtualFileData(
+    String format, {
+    Duration? timeout,
+  }) {
+    return ReaderManager.instance
+        .getVirtualFileData(_handle, format: format, timeout: timeout);
+  }
+
+  /// Copies virtual file to a folder of the managed directory that is
+  /// removed together with the file. Not available on web.
+  (Future<ManagedFile>, ReadProgress) getVirtualFileManaged(
+    String format, {
+    Duration? timeout,
+  }) {
+    return ReaderManager.instance
+        .getVirtualFileManaged(_handle, format: format, timeout: timeout);
+  }
+
+  /// Returns data for given format as stream of chunks. Large values are
+  /// not loaded into memory at once where the platform supports it. Returns
+  /// `null` if the value is not available or is not binary/textual.
+  /// [timeout] only applies to opening the stream.
+  (Future<VirtualFile?>, ReadProgress) getDataStream(
+    String format, {
+    Duration? timeout,
+  }) {
+    return ReaderManager.instance
+        .getItemDataStream(_handle, format: format, timeout: timeout);
+  }
+
+  /// Lists entries if the item is a zip archive, `null` otherwise. Archives
+  /// that are not local files are copied into managed directory first.
+  /// [fileUriFormats] are formats holding file URI on current platform.
+  (Future<ArchiveListing?>, ReadProgress) getArchiveEntries({
+    required List<String> fileUriFormats,
+  }) {
+    return ReaderManager.instance
+        .getArchiveEntries(_handle, fileUriFormats: fileUriFormats);
+  }
+
+  /// Imports the item as the first of [targets] (in order of preference)
+  /// that it can satisfy. Format selection, virtual file extraction and size
+  /// limits are handled natively in a single call.
+  (Future<ImportResult>, ReadProgress) importItem(List<ImportTarget> targets) {
+    return ReaderManager.instance.importItem(_handle, targets: targets);
+  }
+
+  /// Reads the richest HTML or RTF representation of the item as a flat list
+  /// of styled runs. Only inline styling is preserved; block elements become
+  /// line breaks. Returns `null` if the item has no rich text.
+  (Future<List<RichTextSpan>?>, ReadProgress) getRichText() {
+    return ReaderManager.instance.getItemRichText(_handle);
+  }
+
+  /// Reads item text in [format] and detects entities of given [kinds] (all
+  /// kinds by default) in it, using platform detector where available.
+  /// Returns `null` if the data is not text.
+  (Future<TextWithEntities?>, ReadProgress) getTextWithEntities(
+    String format, {
+    List<EntityKind>? kinds,
+  }) {
+    return ReaderManager.instance
+        .getItemTextWithEntities(_handle, format: format, kinds: kinds);
   }
 
   static Future<List<DataReaderItemInfo>> getItemInfo(
@@ -155,6 +618,15 @@ abstract class VirtualFile {
   }
 }
 
+/// Virtual file copied to managed directory by
+/// [DataReaderItem.getVirtualFileManaged]. The file is removed once disposed
+/// or garbage collected, or when the reader is disposed.
+abstract class ManagedFile {
+  String get path;
+
+  Future<void> dispose();
+}
+
 abstract class VirtualFileReceiver {
   String get format;
 
@@ -167,3 +639,547 @@ abstract class VirtualFileReceiver {
     required String targetFolder,
   });
 }
+
+/// URL of the page the item was copied or dragged from.
+class SourceUrl {
+  SourceUrl({
+    required this.url,
+    required this.sourceFormat,
+  });
+
+  static SourceUrl deserialize(dynamic url) {
+    final map = url as Map;
+    return SourceUrl(
+      url: map['url'],
+      sourceFormat: map['sourceFormat'],
+    );
+  }
+
+  final String url;
+
+  /// Format the URL was extracted from.
+  final String sourceFormat;
+}
+
+class WebResource {
+  WebResource({
+    required this.url,
+    required this.mimeType,
+    required this.textEncoding,
+    required this.data,
+  });
+
+  static WebResource deserialize(dynamic resource) {
+    final map = resource as Map;
+    return WebResource(
+      url: map['url'],
+      mimeType: map['mimeType'],
+      textEncoding: map['textEncoding'],
+      data: map['data'],
+    );
+  }
+
+  final String? url;
+  final String? mimeType;
+  final String? textEncoding;
+  final Uint8List data;
+}
+
+/// Decoded `com.apple.webarchive` (Safari) content.
+class WebArchive {
+  WebArchive({
+    required this.mainResource,
+    required this.subresources,
+  });
+
+  static WebArchive deserialize(dynamic archive) {
+    final map = archive as Map;
+    return WebArchive(
+      mainResource: WebResource.deserialize(map['mainResource']),
+      subresources: (map['subresources'] as List)
+          .map(WebResource.deserialize)
+          .toList(growable: false),
+    );
+  }
+
+  final WebResource mainResource;
+
+  /// Subresources of main resource followed by resources of subframes.
+  final List<WebResource> subresources;
+}
+
+enum MediaKind {
+  audio,
+  video,
+}
+
+/// Audio or video item description.
+class MediaInfo {
+  MediaInfo({
+    required this.kind,
+    required this.format,
+    required this.duration,
+    required this.codecs,
+    required this.width,
+    required this.height,
+    required this.sampleRate,
+    required this.channelCount,
+    required this.path,
+  });
+
+  static MediaInfo deserialize(dynamic info) {
+    final map = info as Map;
+    final durationMillis = map['durationMillis'] as int?;
+    return MediaInfo(
+      kind: MediaKind.values.byName(map['kind']),
+      format: map['format'],
+      duration: durationMillis != null
+          ? Duration(milliseconds: durationMillis)
+          : null,
+      codecs: (map['codecs'] as List).cast<String>(),
+      width: map['width'],
+      height: map['height'],
+      sampleRate: map['sampleRate'],
+      channelCount: map['channelCount'],
+      path: map['path'],
+    );
+  }
+
+  final MediaKind kind;
+
+  /// Media format of the item.
+  final String format;
+  final Duration? duration;
+
+  /// Codec identifiers (i.e. `avc1`, `mp4a`, `pcm`), in track order.
+  final List<String> codecs;
+  final int? width;
+  final int? height;
+  final int? sampleRate;
+  final int? channelCount;
+
+  /// Local path of the media file, if item refers to one. Reading the file
+  /// directly avoids loading the clip into memory.
+  final String? path;
+}
+
+/// Transformation applied to textual item data in given formats before it
+/// is returned from [DataReaderItem.getDataForFormat].
+sealed class TransformRule {
+  TransformRule({required this.formats});
+
+  final List<String> formats;
+
+  Map<String, dynamic> serialize();
+}
+
+/// Removes leading and trailing whitespace.
+class TrimWhitespaceRule extends TransformRule {
+  TrimWhitespaceRule({required super.formats});
+
+  @override
+  Map<String, dynamic> serialize() => {
+        'type': 'trimWhitespace',
+        'formats': formats,
+      };
+}
+
+/// Removes tracking query parameters from every line that is an http(s) URL.
+/// When [parameters] is not set a built-in list (`utm_*`, `fbclid`, ...) is
+/// used. Entries ending with `*` match by prefix.
+class StripUrlTrackingRule extends TransformRule {
+  StripUrlTrackingRule({required super.formats, this.parameters});
+
+  final List<String>? parameters;
+
+  @override
+  Map<String, dynamic> serialize() => {
+        'type': 'stripUrlTracking',
+        'formats': formats,
+        'parameters': parameters,
+      };
+}
+
+/// Converts lines that are absolute Windows paths (`C:\dir\file`) to WSL
+/// paths (`/mnt/c/dir/file`).
+class WindowsPathToWslRule extends TransformRule {
+  WindowsPathToWslRule({required super.formats, this.mountRoot});
+
+  /// Defaults to `/mnt`.
+  final String? mountRoot;
+
+  @override
+  Map<String, dynamic> serialize() => {
+        'type': 'windowsPathToWsl',
+        'formats': formats,
+        'mountRoot': mountRoot,
+      };
+}
+
+/// Replaces every occurrence of [pattern] (matched literally).
+class ReplaceRule extends TransformRule {
+  ReplaceRule({
+    required super.formats,
+    required this.pattern,
+    required this.replacement,
+  });
+
+  final String pattern;
+  final String replacement;
+
+  @override
+  Map<String, dynamic> serialize() => {
+        'type': 'replace',
+        'formats': formats,
+        'pattern': pattern,
+        'replacement': replacement,
+      };
+}
+
+enum ResolvedFileSource {
+  /// Item referenced existing local file.
+  localFile,
+
+  /// Virtual file was extracted into target folder.
+  virtualFile,
+
+  /// Item data was written into a file in target folder.
+  spilledData,
+}
+
+class ResolvedFile {
+  ResolvedFile(
+    this._handle, {
+    required this.path,
+    required this.format,
+    required this.source,
+  });
+
+  DataReaderItem get item => DataReaderItem(handle: _handle);
+
+  /// `null` if item has no data that could be stored in a file.
+  final String? path;
+  final String? format;
+  final ResolvedFileSource? source;
+  final DataReaderItemHandle _handle;
+}
+
+/// Acceptable result of [DataReaderItem.importItem].
+sealed class ImportTarget {
+  ImportTarget({required this.formats, this.maxSize});
+
+  /// Formats in order of preference; first available wins.
+  final List<String> formats;
+
+  /// Content larger than this (in bytes) is skipped.
+  final int? maxSize;
+
+  Map<String, dynamic> serialize();
+}
+
+/// Item data in one of [formats]. Virtual files in these formats are read
+/// into memory.
+class ImportDataTarget extends ImportTarget {
+  ImportDataTarget({required super.formats, super.maxSize});
+
+  @override
+  Map<String, dynamic> serialize() => {
+        'type': 'data',
+        'formats': formats,
+        'maxSize': maxSize,
+      };
+}
+
+/// Path to local file. Virtual files in [formats] are copied into
+/// [targetFolder]; otherwise file URI read from [fileUriFormats] is resolved
+/// to local path.
+class ImportFileTarget extends ImportTarget {
+  ImportFileTarget({
+    required super.formats,
+    required this.fileUriFormats,
+    required this.targetFolder,
+    super.maxSize,
+  });
+
+  final List<String> fileUriFormats;
+  final String targetFolder;
+
+  @override
+  Map<String, dynamic> serialize() => {
+        'type': 'file',
+        'formats': formats,
+        'fileUriFormats': fileUriFormats,
+        'targetFolder': targetFolder,
+        'maxSize': maxSize,
+      };
+}
+
+class ImportResult {
+  ImportResult({
+    required this.targetIndex,
+    required this.format,
+    required this.data,
+    required this.path,
+    required this.sizeExceeded,
+  });
+
+  static ImportResult deserialize(dynamic result) {
+    final map = result as Map;
+    return ImportResult(
+      targetIndex: map['targetIndex'],
+      format: map['format'],
+      data: map['data'],
+      path: map['path'],
+      sizeExceeded: map['sizeExceeded'],
+    );
+  }
+
+  /// Index of the satisfied target; `null` if no target matched.
+  final int? targetIndex;
+
+  /// Format of [data] or of file at [path].
+  final String? format;
+
+  /// Data for [ImportDataTarget].
+  final Object? data;
+
+  /// Local path for [ImportFileTarget].
+  final String? path;
+
+  /// Whether any target was skipped because the content was too large.
+  final bool sizeExceeded;
+}
+
+class ItemMetadata {
+  ItemMetadata({
+    required this.size,
+    required this.lastModified,
+    required this.sourceApplication,
+  });
+
+  static ItemMetadata deserialize(dynamic metadata) {
+    final map = metadata as Map;
+    final lastModified = map['lastModified'] as int?;
+    return ItemMetadata(
+      size: map['size'],
+      lastModified: lastModified != null
+          ? DateTime.fromMillisecondsSinceEpoch(lastModified)
+          : null,
+      sourceApplication: map['sourceApplication'],
+    );
+  }
+
+  /// Estimated size of item content in bytes.
+  final int? size;
+  final DateTime? lastModified;
+
+  /// Identifier of application that provided the item (bundle identifier on
+  /// macOS, executable path on Windows).
+  final String? sourceApplication;
+}
+
+class FilePolicy {
+  FilePolicy({
+    this.blockedExtensions = const [],
+    this.blockedFormats = const [],
+  });
+
+  /// Extensions without leading dot, compared case insensitively.
+  final List<String> blockedExtensions;
+
+  /// Platform formats, compared case insensitively.
+  final List<String> blockedFormats;
+
+  Map<String, dynamic> serialize() => {
+        'blockedExtensions': blockedExtensions,
+        'blockedFormats': blockedFormats,
+      };
+}
+
+class ArchiveEntry {
+  ArchiveEntry({
+    required this.index,
+    required this.name,
+    required this.size,
+    required this.compressedSize,
+    required this.isDirectory,
+  });
+
+  static ArchiveEntry deserialize(dynamic entry) {
+    final map = entry as Map;
+    return ArchiveEntry(
+      index: map['index'],
+      name: map['name'],
+      size: map['size'],
+      compressedSize: map['compressedSize'],
+      isDirectory: map['isDirectory'],
+    );
+  }
+
+  final int index;
+
+  /// Path of the entry inside archive, using `/` as separator.
+  final String name;
+  final int size;
+  final int compressedSize;
+  final bool isDirectory;
+}
+
+class ArchiveListing {
+  ArchiveListing({
+    required this.archivePath,
+    required this.entries,
+  });
+
+  static ArchiveListing deserialize(dynamic listing) {
+    final map = listing as Map;
+    return ArchiveListing(
+      archivePath: map['archivePath'],
+      entries: (map['entries'] as List)
+          .map(ArchiveEntry.deserialize)
+          .toList(growable: false),
+    );
+  }
+
+  /// Local path of the archive.
+  final String archivePath;
+  final List<ArchiveEntry> entries;
+
+  /// Decompresses [entry] into memory and returns it as virtual file.
+  Future<VirtualFile> readEntry(ArchiveEntry entry) {
+    return ReaderManager.instance.readArchiveEntry(archivePath, entry.index);
+  }
+
+  /// Extracts [entry] into [targetFolder] and returns path of the file.
+  /// Directory structure inside the archive is not recreated.
+  Future<String> extractEntry(ArchiveEntry entry, String targetFolder) {
+    return ReaderManager.instance
+        .extractArchiveEntry(archivePath, entry.index, targetFolder);
+  }
+}
+
+/// Converts data read in source format of a format converter to its target
+/// format.
+typedef FormatConverterCallback = Future<Object?> Function(Object? data);
+
+enum EntityKind {
+  url,
+  email,
+  phoneNumber,
+  date,
+  address,
+}
+
+class DetectedEntity {
+  DetectedEntity({
+    required this.kind,
+    required this.start,
+    required this.end,
+    required this.value,
+  });
+
+  static DetectedEntity deserialize(dynamic entity) {
+    final map = entity as Map;
+    return DetectedEntity(
+      kind: EntityKind.values.byName(map['kind']),
+      start: map['start'],
+      end: map['end'],
+      value: map['value'],
+    );
+  }
+
+  final EntityKind kind;
+
+  /// Start of the entity in UTF-16 code units.
+  final int start;
+
+  /// End (exclusive) of the entity in UTF-16 code units.
+  final int end;
+
+  /// Normalized value: absolute URL for links (`mailto:` and `tel:` for
+  /// email addresses and phone numbers), `YYYY-MM-DD` for dates.
+  final String? value;
+}
+
+/// Run of text with uniform inline style, see [DataReaderItem.getRichText].
+class RichTextSpan {
+  RichTextSpan({
+    required this.text,
+    required this.bold,
+    required this.italic,
+    required this.underline,
+    required this.link,
+  });
+
+  static RichTextSpan deserialize(dynamic value) {
+    final map = value as Map;
+    return RichTextSpan(
+      text: map['text'],
+      bold: map['bold'],
+      italic: map['italic'],
+      underline: map['underline'],
+      link: map['link'],
+    );
+  }
+
+  final String text;
+  final bool bold;
+  final bool italic;
+  final bool underline;
+
+  /// Target of the link the text is part of.
+  final String? link;
+}
+
+class TextWithEntities {
+  TextWithEntities({
+    required this.text,
+    required this.entities,
+  });
+
+  static TextWithEntities deserialize(dynamic value) {
+    final map = value as Map;
+    return TextWithEntities(
+      text: map['text'],
+      entities: (map['entities'] as List)
+          .map(DetectedEntity.deserialize)
+          .toList(growable: false),
+    );
+  }
+
+  final String text;
+  final List<DetectedEntity> entities;
+}
+
+class ItemDataResult {
+  ItemDataResult(
+    this._handle, {
+    required this.format,
+    required this.data,
+    required this.error,
+  });
+
+  DataReaderItem get item => DataReaderItem(handle: _handle);
+
+  final DataReaderItemHandle _handle;
+  final String format;
+
+  /// `null` if the item has no data in [format] or reading failed.
+  final Object? data;
+
+  /// Set if reading this entry failed.
+  final String? error;
+}
+
+typedef FormatsAvailableCallback = void Function(
+    DataReaderItemHandle item, List<String> formats);
+
+/// Returned by [DataReader.subscribeToFormats].
+class FormatSubscription {
+  FormatSubscription(this._cancel);
+
+  /// Stops reporting formats; `onEnded` is not invoked afterwards.
+  Future<void> cancel() => _cancel();
+
+  final Future<void> Function() _cancel;
+}
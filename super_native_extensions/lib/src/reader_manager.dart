@@ -1,3 +1,7 @@
+import 'dart:typed_data';
+
+import 'package:flutter/foundation.dart';
+
 import 'reader.dart';
 
 import 'native/reader_manager.dart'
@@ -18,9 +22,17 @@ abstract class ReaderManager {
 
   Future<List<String>> getItemFormats(DataReaderItemHandle handle);
 
+  /// Read fails if it doesn't finish within [timeout].
   (Future<Object?>, ReadProgress) getItemData(
     DataReaderItemHandle handle, {
     required String format,
+    Duration? timeout,
+  });
+
+  (Future<PartialItemData>, ReadProgress) getItemDataPartial(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
   });
 
   /// Loads as many item infos as possible within the given timeout.
@@ -30,4 +42,215 @@ abstract class ReaderManager {
   });
 
   VirtualFile createVirtualFileFromUri(Uri uri);
+
+  /// Wraps platform object registered by another plugin in a reader.
+  /// Either [handle] (Windows, Android) or [pasteboardName] (macOS, iOS)
+  /// must be specified.
+  Future<DataReaderHandle> newExternalReader({
+    int? handle,
+    String? pasteboardName,
+  });
+
+  /// Built-in conversions that apply to the item, including disabled ones.
+  Future<List<ItemFormatConversion>> getItemFormatConversions(
+      DataReaderItemHandle handle);
+
+  Future<void> setFormatConversionEnabled(
+    DataReaderHandle reader,
+    FormatConversion conversion, {
+    required bool enabled,
+  });
+
+  /// Returns URL of the page the item originates from, if known.
+  Future<SourceUrl?> getItemSourceUrl(DataReaderItemHandle handle);
+
+  /// Returns decoded web archive if the item contains one.
+  Future<WebArchive?> getItemWebArchive(DataReaderItemHandle handle);
+
+  /// Renders metafile content of the item (Windows) to PNG. Returns `null`
+  /// if the item has no metafile representation.
+  Future<Uint8List?> rasterizeItemMetafile(
+    DataReaderItemHandle handle, {
+    int? width,
+    int? height,
+  });
+
+  /// Returns media description if the item is an audio or video clip.
+  Future<MediaInfo?> getItemMediaInfo(DataReaderItemHandle handle);
+
+  Future<ItemMetadata> getItemMetadata(DataReaderItemHandle handle);
+
+  /// Whether reading the data may pull it from another device (Universal
+  /// Clipboard on macOS and iOS).
+  Future<bool> isRemoteContent(DataReaderHandle reader);
+
+  /// Whether clipboard changed since the reader was created.
+  Future<bool> isStale(DataReaderHandle reader);
+
+  /// Returns new reader for current clipboard contents if they changed since
+  /// [reader] was created, `null` otherwise.
+  Future<DataReaderHandle?> refresh(DataReaderHandle reader);
+
+  /// Id of current isolate that other isolates pass to
+  /// [cloneReaderForIsolate].
+  Future<int> getIsolateId();
+
+  /// Registers platform reader of [reader] for isolate [targetIsolateId].
+  /// Returns handle the target isolate passes to [receiveTransferredReader].
+  Future<int> cloneReaderForIsolate(
+    DataReaderHandle reader,
+    int targetIsolateId,
+  );
+
+  /// Completes with reader cloned for current isolate under [handle].
+  Future<DataReaderHandle> receiveTransferredReader(int handle);
+
+  /// Copies data of all items of [reader] into memory and returns reader
+  /// serving it. Virtual files are still read from the source.
+  (Future<DataReaderHandle>, ReadProgress) snapshotReader(
+    DataReaderHandle reader, {
+    int? sizeLimit,
+  });
+
+  Future<CompositeReadProgress> createCompositeProgress({
+    required int partCount,
+    List<double>? weights,
+  });
+
+  Future<void> setOutOfProcessReading(bool enabled);
+
+  /// Excludes content from other devices from clipboard readers created
+  /// afterwards (macOS, iOS).
+  Future<void> setExcludeRemoteContent(bool exclude);
+
+  /// Makes named platform clipboard format appear as [identifier] when
+  /// reading and writing (Windows only, no-op elsewhere).
+  Future<void> registerClipboardFormat({
+    required String identifier,
+    required String platformName,
+  });
+
+  /// Streams payload advertised through `LocalTransferManager` into
+  /// [targetPath]. Returns `null` if the item has no local transfer
+  /// descriptor or the source can not be reached; caller should fall back
+  /// to regular representations.
+  (Future<String?>, ReadProgress) readItemLocalTransfer(
+    DataReaderItemHandle handle, {
+    required String targetPath,
+  });
+
+  (Future<List<ResolvedFile>>, ReadProgress) resolveItemsToFiles(
+    DataReaderHandle reader, {
+    Iterable<DataReaderItemHandle>? items,
+    required String targetFolder,
+    required List<String> fileUriFormats,
+  });
+
+  Future<void> setTransformRules(List<TransformRule> rules);
+
+  Future<void> setRtfHtmlConversionEnabled(bool enabled);
+
+  /// Switches high volume channels of this isolate to compact binary
+  /// encoding: data returned by [getItemData], progress updates and drop
+  /// update events. Decoding is handled internally, so results are the same
+  /// as with default encoding.
+  Future<void> setBinaryProtocol({
+    bool itemData = false,
+    bool progress = false,
+    bool dropEvents = false,
+  });
+
+  /// Byte payloads of at least [thresholdBytes] (data returned by
+  /// [getItemData] and lazy data provided to native code) are exchanged
+  /// through native memory instead of being copied by the message channel.
+  /// `null` disables sharing.
+  Future<void> setSharedBufferTransfer({int? thresholdBytes});
+
+  Future<void> setFilePolicy(FilePolicy? policy);
+
+  Future<String> getManagedDirectory();
+
+  Future<void> setManagedDirectoryQuota(int? quota);
+
+  Future<String> claimManagedFile({
+    required String path,
+    required String targetFolder,
+  });
+
+  (Future<VirtualFile>, ReadProgress) getVirtualFileData(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  });
+
+  (Future<ManagedFile>, ReadProgress) getVirtualFileManaged(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  });
+
+  Future<void> disposeFileLease(int lease);
+
+  /// [timeout] only applies to opening the stream.
+  (Future<VirtualFile?>, ReadProgress) getItemDataStream(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  });
+
+  (Future<ImportResult>, ReadProgress) importItem(
+    DataReaderItemHandle handle, {
+    required List<ImportTarget> targets,
+  });
+
+  (Future<List<RichTextSpan>?>, ReadProgress) getItemRichText(
+    DataReaderItemHandle handle,
+  );
+
+  (Future<ArchiveListing?>, ReadProgress) getArchiveEntries(
+    DataReaderItemHandle handle, {
+    required List<String> fileUriFormats,
+  });
+
+  Future<VirtualFile> readArchiveEntry(String archivePath, int index);
+
+  Future<String> extractArchiveEntry(
+    String archivePath,
+    int index,
+    String targetFolder,
+  );
+
+  Future<int> registerFormatConverter({
+    required String sourceFormat,
+    required String targetFormat,
+    required FormatConverterCallback convert,
+  });
+
+  Future<void> unregisterFormatConverter(int id);
+
+  (Future<TextWithEntities?>, ReadProgress) getItemTextWithEntities(
+    DataReaderItemHandle handle, {
+    required String format,
+    List<EntityKind>? kinds,
+  });
+
+  /// [timeout] applies to each entry separately.
+  (Future<List<ItemDataResult>>, ReadProgress) getItemDataMulti(
+    DataReaderHandle reader, {
+    required List<(DataReaderItemHandle, String)> items,
+    Duration? timeout,
+  });
+
+  /// Reports formats added to clipboard contents after [reader] was created
+  /// through [onFormatsAvailable], until all [formats] (any formats when
+  /// `null`) are available, [timeout] elapses (3 seconds by default) or the
+  /// subscription is cancelled. [onEnded] is invoked unless cancelled.
+  /// Subscriptions of readers that don't read clipboard end right away.
+  Future<FormatSubscription> subscribeToFormats(
+    DataReaderHandle reader, {
+    List<String>? formats,
+    Duration? timeout,
+    required FormatsAvailableCallback onFormatsAvailable,
+    VoidCallback? onEnded,
+  });
 }
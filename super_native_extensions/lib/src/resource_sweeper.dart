@@ -0,0 +1,104 @@
+import 'package:flutter/foundation.dart';
+
+import 'native/resource_sweeper.dart'
+    if (dart.library.js) 'web/resource_sweeper.dart';
+
+class SweeperConfiguration {
+  SweeperConfiguration({
+    this.interval,
+    this.staleFileAge = const Duration(days: 1),
+    this.idleProviderTimeout,
+    this.dropPermissionTimeout,
+  });
+
+  /// Interval between sweeps. `null` disables periodic sweeping.
+  final Duration? interval;
+
+  /// Temporary files of other processes older than this are removed.
+  final Duration staleFileAge;
+
+  /// Data providers unreferenced for this long are released. `null` keeps
+  /// them until disposed from Dart.
+  final Duration? idleProviderTimeout;
+
+  /// Android drag and drop permissions held longer are released. `null`
+  /// keeps them for the lifetime of the reader.
+  final Duration? dropPermissionTimeout;
+
+  dynamic serialize() => {
+        'intervalMillis': interval?.inMilliseconds,
+        'staleFileAgeMillis': staleFileAge.inMilliseconds,
+        'idleProviderTimeoutMillis': idleProviderTimeout?.inMilliseconds,
+        'dropPermissionTimeoutMillis': dropPermissionTimeout?.inMilliseconds,
+      };
+}
+
+/// Resources reclaimed by single sweep.
+class SweepReport {
+  SweepReport({
+    required this.removedFiles,
+    required this.releasedProviders,
+    required this.releasedDropPermissions,
+  });
+
+  static SweepReport deserialize(dynamic report) {
+    final map = report as Map;
+    return SweepReport(
+      removedFiles: (map['removedFiles'] as List).cast<String>(),
+      releasedProviders: map['releasedProviders'],
+      releasedDropPermissions: map['releasedDropPermissions'],
+    );
+  }
+
+  final List<String> removedFiles;
+  final int releasedProviders;
+  final int releasedDropPermissions;
+}
+
+/// Pending native tasks of one kind of owner (i.e. `DragManager`).
+class TaskDiagnostics {
+  TaskDiagnostics({
+    required this.name,
+    required this.scopeCount,
+    required this.taskCount,
+  });
+
+  static TaskDiagnostics deserialize(dynamic diagnostics) {
+    final map = diagnostics as Map;
+    return TaskDiagnostics(
+      name: map['name'],
+      scopeCount: map['scopeCount'],
+      taskCount: map['taskCount'],
+    );
+  }
+
+  final String name;
+
+  /// Number of open scopes, usually one per isolate or reader.
+  final int scopeCount;
+  final int taskCount;
+
+  @override
+  String toString() =>
+      'TaskDiagnostics($name, scopes: $scopeCount, tasks: $taskCount)';
+}
+
+/// Periodically reclaims native resources leaked by the application or by
+/// processes that did not exit cleanly.
+abstract class ResourceSweeper {
+  static final ResourceSweeper instance = ResourceSweeperImpl();
+
+  Future<void> configure(SweeperConfiguration configuration);
+
+  /// Runs sweep immediately and returns what was reclaimed.
+  Future<SweepReport> sweepNow();
+
+  /// Returns pending native tasks grouped by owner. Tasks of an isolate
+  /// should drop to zero once the isolate is gone; meant for leak checks
+  /// in tests.
+  Future<List<TaskDiagnostics>> getTaskDiagnostics();
+
+  /// Fired after periodic sweep that reclaimed anything. Only delivered
+  /// after [configure] was called.
+  ValueListenable<SweepReport?> get lastReport;
+}
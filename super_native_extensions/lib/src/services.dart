@@ -0,0 +1,50 @@
+import 'package:flutter/foundation.dart';
+
+import 'data_provider.dart';
+import 'reader.dart';
+
+import 'native/services.dart' if (dart.library.js) 'web/services.dart';
+
+enum ServicesDataSource {
+  /// Service replaced the advertised content.
+  returnedData,
+
+  /// Application was invoked as a service provider.
+  serviceRequest,
+}
+
+class ServicesDataEvent {
+  ServicesDataEvent({
+    required this.reader,
+    required this.source,
+    required this.userData,
+  });
+
+  final DataReader reader;
+  final ServicesDataSource source;
+
+  /// User data of the `NSServices` entry for service requests.
+  final String? userData;
+}
+
+/// macOS Services menu integration. Other platforms fail with unsupported
+/// operation error.
+abstract class ServicesManager {
+  static final ServicesManager instance = ServicesManagerImpl();
+
+  /// Offers [providers] to services operating on current selection, and
+  /// accepts data in [returnTypes] back. Providers are disposed once they
+  /// are no longer advertised. Empty lists remove the content.
+  Future<void> setServicesContent(
+    List<DataProviderHandle> providers, {
+    List<String> returnTypes = const [],
+  });
+
+  /// Enables handling of service requests declared in `NSServices` of
+  /// Info.plist.
+  Future<void> setServicesProviderEnabled(bool enabled);
+
+  /// Invoked with data returned by a service or sent to this application as
+  /// a service provider.
+  ValueChanged<ServicesDataEvent>? onServicesData;
+}
@@ -0,0 +1,52 @@
+import 'dart:ui' as ui;
+
+import 'data_provider.dart';
+import 'drop.dart';
+import 'native/test_injection.dart'
+    if (dart.library.js) 'web/test_injection.dart';
+
+class SimulatedDragResult {
+  SimulatedDragResult({
+    required this.sessionId,
+    required this.operation,
+    required this.dropped,
+  });
+
+  static SimulatedDragResult deserialize(dynamic result) {
+    final map = result as Map;
+    return SimulatedDragResult(
+      sessionId: map['sessionId'],
+      operation: DropOperation.values.byName(map['operation']),
+      dropped: map['dropped'],
+    );
+  }
+
+  /// `null` if none of the formats registered for the view matched and the
+  /// drag was not delivered.
+  final int? sessionId;
+
+  /// Operation accepted at the last location; [DropOperation.none] if the
+  /// drag left the view.
+  final DropOperation operation;
+  final bool dropped;
+}
+
+/// Simulated input for integration tests. Requires native library built
+/// with the `test_injection` cargo feature.
+abstract class TestInjection {
+  static final TestInjection instance = TestInjectionImpl();
+
+  /// Sends drop events for a drag carrying [providers] that enters view at
+  /// first of [locations] and moves through the rest. Drops at the last
+  /// location if [performDrop] is set and the drop was accepted, otherwise
+  /// leaves the view. Uses primary view of this isolate unless
+  /// [engineHandle] is specified. Data is read from the providers upfront;
+  /// clipboard is not used. Virtual files are not supported.
+  Future<SimulatedDragResult> simulateDrag({
+    required List<DataProviderHandle> providers,
+    required List<ui.Offset> locations,
+    List<DropOperation> allowedOperations = const [DropOperation.copy],
+    bool performDrop = true,
+    int? engineHandle,
+  });
+}
@@ -0,0 +1,64 @@
+import 'dart:ui';
+
+import 'desktop_capability.dart';
+import 'image_data.dart';
+import 'native/tray_icon.dart' if (dart.library.js) 'web/tray_icon.dart';
+
+enum TrayIconEventType {
+  leftClick,
+  rightClick,
+  middleClick,
+  doubleClick,
+
+  /// Sent when pointer hovers over icon created with `customTooltip`.
+  tooltipRequested,
+}
+
+class TrayIconEvent {
+  TrayIconEvent({
+    required this.handle,
+    required this.type,
+    required this.position,
+  });
+
+  final int handle;
+  final TrayIconEventType type;
+
+  /// Pointer location in screen coordinates.
+  final Offset position;
+}
+
+abstract class TrayIconManagerDelegate {
+  void onTrayIconEvent(TrayIconEvent event);
+}
+
+abstract class TrayIconManager {
+  static final _instance = TrayIconManagerImpl();
+
+  static TrayIconManager get instance => _instance;
+
+  /// Creates tray icon. Returns null if not supported on this platform.
+  /// When [customTooltip] is set the platform tooltip is not shown and
+  /// [TrayIconEventType.tooltipRequested] event is sent instead.
+  Future<int?> createTrayIcon({
+    required ImageData image,
+    String? tooltip,
+    bool customTooltip = false,
+  });
+
+  /// Updates image and/or tooltip of tray icon with given handle.
+  Future<void> updateTrayIcon(
+    int handle, {
+    ImageData? image,
+    String? tooltip,
+  });
+
+  /// Destroys tray icon with given handle.
+  Future<void> destroyTrayIcon(int handle);
+
+  /// Whether tray icons are supported in current session and which backend
+  /// is used. On Linux this depends on desktop environment and sandbox.
+  Future<DesktopFeatureCapability> getCapability();
+
+  set delegate(TrayIconManagerDelegate? delegate);
+}
@@ -0,0 +1,14 @@
+import 'package:flutter/foundation.dart';
+
+import '../clipboard_monitor.dart';
+
+class ClipboardMonitorImpl extends ClipboardMonitor {
+  @override
+  Future<bool> addListener(
+      ValueChanged<ClipboardChangedEvent> listener) async {
+    return false;
+  }
+
+  @override
+  void removeListener(ValueChanged<ClipboardChangedEvent> listener) {}
+}
@@ -12,7 +12,12 @@ import 'reader_manager.dart';
 
 class ClipboardReaderImpl extends ClipboardReader {
   @override
-  Future<DataReader> newClipboardReader() async {
+  Future<DataReader> newClipboardReader({ClipboardTarget? target}) async {
+    if (target != null &&
+        (target.pasteboardName != null ||
+            target.clipboardType == ClipboardType.primary)) {
+      throw UnsupportedError('Clipboard target is not supported on web');
+    }
     final items = await window.navigator.clipboard.read().toDart;
     final handle = $DataReaderHandle(
       items.toDart
@@ -25,6 +30,42 @@ class ClipboardReaderImpl extends ClipboardReader {
     return DataReader(handle: handle as DataReaderHandle);
   }
 
+  @override
+  Future<bool> canPaste(
+    List<String> formats, {
+    ClipboardTarget? target,
+  }) async {
+    // Browser only lists clipboard formats after asking for permission.
+    throw UnsupportedError('canPaste is not supported on web');
+  }
+
   @override
   bool get available => clipboardItemAvailable;
+
+  @override
+  Future<RemoteSessionInfo> getRemoteSessionInfo() async {
+    return RemoteSessionInfo(kind: null, tuningEnabled: false);
+  }
+
+  @override
+  Future<void> setRemoteSessionTuning(bool? enabled) async {}
+
+  @override
+  Future<ClipboardPolicy> getClipboardPolicy() async {
+    return ClipboardPolicy(
+      managedProfile: false,
+      secondaryUser: false,
+      crossProfileCopyPasteDisallowed: false,
+    );
+  }
+
+  @override
+  Future<ClipboardToken> captureClipboardToken() async {
+    throw UnsupportedError('Clipboard tokens are not supported on web');
+  }
+
+  @override
+  Future<DataReader?> redeemClipboardToken(ClipboardToken token) async {
+    return null;
+  }
 }
@@ -4,8 +4,10 @@ import 'dart:js_interop_unsafe';
 import 'package:flutter/foundation.dart';
 import 'package:web/web.dart' as web;
 
+import '../clipboard_reader.dart';
 import '../clipboard_writer.dart';
 import '../data_provider.dart';
+import 'js_interop.dart';
 
 class ClipboardWriterImpl extends ClipboardWriter {
   List<DataProviderHandle> _currentPayload = [];
@@ -27,26 +29,32 @@ class ClipboardWriterImpl extends ClipboardWriter {
         // Writing URI list to clipboard on web is not supported
         continue;
       }
+      // Formats not supported by the browser (i.e. custom MIME types) are
+      // written as web custom formats.
+      final type = clipboardTypeForFormat(repr.format);
+      if (type == null) {
+        continue;
+      }
       if (repr is DataRepresentationSimple) {
         final value = web.Blob(
           [_toJS(repr.data)].toJS,
           web.BlobPropertyBag(
-            type: repr.format,
+            type: type,
           ),
         );
-        representations.setProperty(repr.format.toJS, value);
+        representations.setProperty(type.toJS, value);
       } else if (repr is DataRepresentationLazy) {
         Future<web.Blob> fn() async {
           final data = await repr.dataProvider();
           return web.Blob(
             [_toJS(data)].toJS,
             web.BlobPropertyBag(
-              type: repr.format,
+              type: type,
             ),
           );
         }
 
-        representations.setProperty(repr.format.toJS, fn().toJS);
+        representations.setProperty(type.toJS, fn().toJS);
       }
     }
     return web.ClipboardItem(representations);
@@ -62,4 +70,43 @@ class ClipboardWriterImpl extends ClipboardWriter {
     final items = providers.map((e) => translateProvider(e.provider));
     await clipboard.write(items.toList(growable: false).toJS).toDart;
   }
+
+  @override
+  Future<void> writeToSelection(
+    List<DataProviderHandle> providers,
+    ClipboardTarget target,
+  ) {
+    throw UnsupportedError('writeToSelection is not supported on web');
+  }
+
+  @override
+  Future<void> append(List<DataProviderHandle> providers) {
+    throw UnsupportedError('append is not supported on web');
+  }
+
+  @override
+  Future<void> removeItem(int index) {
+    throw UnsupportedError('removeItem is not supported on web');
+  }
+
+  @override
+  Future<void> replaceItem(int index, DataProviderHandle provider) {
+    throw UnsupportedError('replaceItem is not supported on web');
+  }
+
+  @override
+  Future<void> moveItem({required int fromIndex, required int toIndex}) {
+    throw UnsupportedError('moveItem is not supported on web');
+  }
+
+  @override
+  Future<bool> clear() async {
+    // Ownership can not be verified in browser.
+    return false;
+  }
+
+  @override
+  Future<bool> hasOwnership() async {
+    return false;
+  }
 }
@@ -5,10 +5,29 @@ import '../data_provider_manager.dart';
 
 class DataProviderManagerImpl extends DataProviderManager {
   @override
-  FutureOr<DataProviderHandle> registerDataProvider(DataProvider provider) {
+  FutureOr<DataProviderHandle> registerDataProvider(
+    DataProvider provider, {
+    int? servicePort,
+  }) {
+    if (servicePort != null) {
+      throw UnsupportedError('Service ports are not supported on web');
+    }
     return DataProviderHandle(0, provider);
   }
 
+  @override
+  Future<int> getServicePort() {
+    throw UnsupportedError('Service ports are not supported on web');
+  }
+
+  @override
+  Future<DataProviderHandle> adoptDataProvider(int providerId) {
+    throw UnsupportedError('Service ports are not supported on web');
+  }
+
   @override
   FutureOr<void> unregisterDataProvider(int providerId) {}
+
+  @override
+  FutureOr<void> setBandwidthLimit(int? bytesPerSecond) {}
 }
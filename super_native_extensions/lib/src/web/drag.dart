@@ -10,6 +10,7 @@ import '../drag.dart';
 import '../drag_interaction/long_press_session.dart';
 import '../drop.dart';
 import '../gesture/pointer_device_kind.dart';
+import '../image_data.dart';
 import '../widget_snapshot/widget_snapshot.dart';
 import 'drag_overlay.dart';
 import 'drop.dart';
@@ -30,16 +31,51 @@ class DragSessionImpl extends DragSession implements DragDriverDelegate {
   @override
   ValueNotifier<bool> get dragging => _dragging;
 
+  @override
+  DragCancelReason? get cancelReason => null;
+
+  @override
+  List<DropOperation>? get itemOperations => null;
+
   @override
   Future<List<Object?>?> getLocalData() async {
     return _state?.getLocalData();
   }
 
+  @override
+  Future<void> updateDragImage(
+    List<TargetedWidgetSnapshot> images, {
+    TargetedWidgetSnapshot? combinedDragImage,
+  }) async {}
+
   @override
   ValueListenable<Offset?> get lastScreenLocation => _lastScreenLocation;
 
   final _lastScreenLocation = ValueNotifier<Offset?>(null);
 
+  @override
+  ValueListenable<DragSessionUpdate?> get lastUpdate => _lastUpdate;
+
+  final _lastUpdate = ValueNotifier<DragSessionUpdate?>(null);
+
+  @override
+  ValueListenable<DragEnvironmentEvent?> get lastEnvironmentChange =>
+      _lastEnvironmentChange;
+
+  final _lastEnvironmentChange = ValueNotifier<DragEnvironmentEvent?>(null);
+
+  /// Web drags are driven by Flutter and always animate back.
+  @override
+  ValueListenable<Offset?> get cancelledAtLocation => _cancelledAtLocation;
+
+  final _cancelledAtLocation = ValueNotifier<Offset?>(null);
+
+  @override
+  ValueListenable<DataTransferProgress?> get dataTransferProgress =>
+      _dataTransferProgress;
+
+  final _dataTransferProgress = ValueNotifier<DataTransferProgress?>(null);
+
   @override
   void cancel() {
     if (!_ended) {
@@ -104,6 +140,10 @@ class DragSessionImpl extends DragSession implements DragDriverDelegate {
     _dragCompleted.dispose();
     _dragging.dispose();
     _lastScreenLocation.dispose();
+    _lastUpdate.dispose();
+    _lastEnvironmentChange.dispose();
+    _cancelledAtLocation.dispose();
+    _dataTransferProgress.dispose();
   }
 
   _SessionState? _state;
@@ -277,6 +317,9 @@ class DragContextImpl extends DragContext {
     session.dispose();
   }
 
+  @override
+  Future<SharedTextureKind?> sharedTextureKind() async => null;
+
   @override
   Future<void> startDrag({
     required BuildContext buildContext,
@@ -284,6 +327,8 @@ class DragContextImpl extends DragContext {
     required DragConfiguration configuration,
     required Offset position,
     TargetedWidgetSnapshot? combinedDragImage,
+    int? engineHandle,
+    PointerDeviceKind? deviceKind,
   }) async {
     final session_ = session as DragSessionImpl;
     session_.init(
@@ -293,4 +338,21 @@ class DragContextImpl extends DragContext {
       combinedDragImage,
     );
   }
+
+  @override
+  Future<void> registerDragSourceForView(int engineHandle) {
+    throw UnsupportedError('registerDragSourceForView is not supported on web');
+  }
+
+  @override
+  Future<void> unregisterDragSourceForView(int engineHandle) async {}
+
+  @override
+  Future<TouchDragSettings> getTouchDragSettings() async =>
+      touchDragSettings.value;
+
+  @override
+  Future<void> setTouchDragSettings(TouchDragSettings settings) async {
+    touchDragSettingsNotifier.value = settings;
+  }
 }
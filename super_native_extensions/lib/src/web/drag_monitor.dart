@@ -0,0 +1,17 @@
+import 'package:flutter/foundation.dart';
+
+import '../drag_monitor.dart';
+
+class DragMonitorImpl extends DragMonitor {
+  @override
+  Future<bool> isDragInProgress() async => false;
+
+  @override
+  Future<DragSessionInfo?> currentDragSessionInfo() async => null;
+
+  @override
+  void addListener(ValueChanged<DragActivityEvent> listener) {}
+
+  @override
+  void removeListener(ValueChanged<DragActivityEvent> listener) {}
+}
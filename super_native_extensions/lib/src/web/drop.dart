@@ -227,7 +227,46 @@ class DropContextImpl extends DropContext {
   }
 
   @override
-  Future<void> registerDropFormats(List<String> formats) async {}
+  Future<void> registerDropFormats(List<String> formats,
+      {int? engineHandle}) async {}
+
+  @override
+  Future<void> registerDropTargetForView(int engineHandle) {
+    throw UnsupportedError('registerDropTargetForView is not supported on web');
+  }
+
+  @override
+  Future<void> unregisterDropTargetForView(int engineHandle) async {}
+
+  @override
+  Future<void> setDropAcceptanceMode(DropAcceptanceMode mode) async {}
+
+  @override
+  Future<void> setDropManifest(DropManifest manifest) async {}
+
+  @override
+  Future<void> setDropRegions(
+    List<DropRegion> regions, {
+    int? engineHandle,
+  }) async {}
+
+  @override
+  Future<void> setAutoScrollRegions(
+    List<AutoScrollRegion> regions, {
+    int? engineHandle,
+  }) async {}
+
+  @override
+  Future<void> setAnalyticsEnabled(bool enabled) async {}
+
+  @override
+  Future<DropAnalyticsReport?> getAnalytics() async => null;
+
+  @override
+  Future<void> resetAnalytics() async {}
+
+  @override
+  final analytics = ValueNotifier<DropAnalyticsReport?>(null);
 
   DropEvent _createLocalDropEvent({
     required DragConfiguration configuration,
@@ -261,6 +300,7 @@ class DropContextImpl extends DropContext {
           )
           .toList(growable: false),
       acceptedOperation: acceptedOperation,
+      sessionLocalData: configuration.sessionLocalData,
     );
   }
 
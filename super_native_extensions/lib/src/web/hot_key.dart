@@ -1,3 +1,4 @@
+import '../desktop_capability.dart';
 import '../hot_key.dart';
 
 class HotKeyManagerImpl extends HotKeyManager {
@@ -11,4 +12,35 @@ class HotKeyManagerImpl extends HotKeyManager {
 
   @override
   Future<void> destroyHotKey(int handle) async {}
+
+  @override
+  Future<void> defineHotKeyProfile(
+      String name, List<HotKeyDefinition> hotKeys) async {}
+
+  @override
+  Future<HotKeyProfileActivation> activateHotKeyProfile(
+    String name, {
+    bool rollbackOnFailure = false,
+  }) async {
+    return HotKeyProfileActivation(
+      activated: false,
+      handles: const [],
+      failures: const [],
+    );
+  }
+
+  @override
+  Future<void> deactivateHotKeyProfile() async {}
+
+  @override
+  Future<void> removeHotKeyProfile(String name) async {}
+
+  @override
+  Future<DesktopFeatureCapability> getCapability() async {
+    return DesktopFeatureCapability(
+      supported: false,
+      backend: null,
+      reason: 'Hot keys are not supported on web',
+    );
+  }
 }
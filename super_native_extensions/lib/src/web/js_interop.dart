@@ -26,3 +26,36 @@ extension DataTransferItemExt on web.DataTransferItem {
 bool get clipboardItemAvailable {
   return web.window.getProperty('ClipboardItem'.toJS) != null;
 }
+
+/// Types that browsers must support when writing to clipboard.
+const _mandatoryClipboardTypes = {'text/plain', 'text/html', 'image/png'};
+
+/// Prefix of web custom formats (i.e. `web application/x-my-format`).
+const webCustomFormatPrefix = 'web ';
+
+/// Whether [type] can be written through `navigator.clipboard.write()`.
+/// Browsers without `ClipboardItem.supports` are assumed to support
+/// mandatory types and web custom formats.
+bool clipboardItemSupports(String type) {
+  final clipboardItem = web.window.getProperty<JSObject?>('ClipboardItem'.toJS);
+  if (clipboardItem == null) {
+    return false;
+  }
+  if (!clipboardItem.has('supports')) {
+    return _mandatoryClipboardTypes.contains(type) ||
+        type.startsWith(webCustomFormatPrefix);
+  }
+  return clipboardItem
+      .callMethod<JSBoolean>('supports'.toJS, type.toJS)
+      .toDart;
+}
+
+/// Clipboard type used to write [format]: the format itself if the browser
+/// supports it, otherwise web custom format. `null` if neither is supported.
+String? clipboardTypeForFormat(String format) {
+  if (clipboardItemSupports(format)) {
+    return format;
+  }
+  final custom = '$webCustomFormatPrefix$format';
+  return clipboardItemSupports(custom) ? custom : null;
+}
@@ -16,4 +16,16 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
 
   @override
   bool get supported => false;
+
+  @override
+  Future<KeyboardLayoutSnapshot?> getSnapshot() async => null;
+
+  @override
+  Future<CurrentKeyboardLayout?> getCurrentKeyboardLayout() async => null;
+
+  @override
+  void addCurrentLayoutListener(CurrentKeyboardLayoutListener listener) {}
+
+  @override
+  void removeCurrentLayoutListener(CurrentKeyboardLayoutListener listener) {}
 }
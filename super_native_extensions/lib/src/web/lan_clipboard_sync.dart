@@ -0,0 +1,40 @@
+import 'dart:typed_data';
+
+import 'package:flutter/foundation.dart';
+
+import '../lan_clipboard_sync.dart';
+
+class LanClipboardSyncImpl extends LanClipboardSync {
+  @override
+  Future<void> start({
+    required String applicationId,
+    required String deviceName,
+    required Uint8List key,
+  }) async {
+    throw UnsupportedError('LAN clipboard sync is not supported on web');
+  }
+
+  @override
+  Future<void> stop() async {}
+
+  @override
+  Future<List<LanPeer>> getPeers() async => [];
+
+  @override
+  Future<int> publish(List<List<LanRepresentation>> items) async {
+    throw UnsupportedError('LAN clipboard sync is not supported on web');
+  }
+
+  @override
+  void addPeersListener(ValueChanged<List<LanPeer>> listener) {}
+
+  @override
+  void removePeersListener(ValueChanged<List<LanPeer>> listener) {}
+
+  @override
+  void addReceivedListener(ValueChanged<LanClipboardReceivedEvent> listener) {}
+
+  @override
+  void removeReceivedListener(
+      ValueChanged<LanClipboardReceivedEvent> listener) {}
+}
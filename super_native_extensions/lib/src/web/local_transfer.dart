@@ -0,0 +1,16 @@
+import 'dart:typed_data';
+
+import '../local_transfer.dart';
+
+class LocalTransferManagerImpl extends LocalTransferManager {
+  @override
+  Future<LocalTransferPayload> registerPayload({
+    Uint8List? data,
+    String? path,
+  }) {
+    throw UnsupportedError('Local transfer is not supported on web');
+  }
+
+  @override
+  Future<void> unregisterPayload(String token) async {}
+}
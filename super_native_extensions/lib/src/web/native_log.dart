@@ -0,0 +1,11 @@
+import 'package:flutter/foundation.dart';
+
+import '../native_log.dart';
+
+class NativeLogImpl extends NativeLog {
+  @override
+  Future<void> setLogHandler(
+    ValueChanged<NativeLogEvent>? handler, {
+    NativeLogLevel level = NativeLogLevel.info,
+  }) async {}
+}
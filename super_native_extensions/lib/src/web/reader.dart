@@ -65,14 +65,30 @@ class ClipboardItemHandle extends $DataReaderItemHandle {
 
   final web.ClipboardItem item;
 
+  List<String> get _types => item.types.toDart.cast<String>();
+
+  /// Web custom formats are reported without the `web ` prefix; formats
+  /// present in both forms are only reported once.
   @override
   Future<List<String>> getFormats() async {
-    return item.types.toDart.cast<String>();
+    final formats = <String>[];
+    for (final type in _types) {
+      final format = type.startsWith(webCustomFormatPrefix)
+          ? type.substring(webCustomFormatPrefix.length)
+          : type;
+      if (!formats.contains(format)) {
+        formats.add(format);
+      }
+    }
+    return formats;
   }
 
   @override
   Future<Object?> getDataForFormat(String format) async {
-    final data = await item.getType(format).toDart;
+    final type = _types.contains(format)
+        ? format
+        : '$webCustomFormatPrefix$format';
+    final data = await item.getType(type).toDart;
     if (format.startsWith('text/')) {
       return data.text().toDart;
     } else {
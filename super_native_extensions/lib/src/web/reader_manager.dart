@@ -52,10 +52,14 @@ class ReaderManagerImpl extends ReaderManager {
   (Future<Object?>, ReadProgress) getItemData(
     DataReaderItemHandle handle, {
     required String format,
+    Duration? timeout,
   }) {
     final impl = handle as $DataReaderItemHandle;
     final progress = SimpleProgress();
-    final res = impl.getDataForFormat(format);
+    var res = impl.getDataForFormat(format);
+    if (timeout != null) {
+      res = res.timeout(timeout);
+    }
     final completer = Completer<Object?>();
     res.then((value) {
       progress.done();
@@ -67,6 +71,21 @@ class ReaderManagerImpl extends ReaderManager {
     return (completer.future, progress);
   }
 
+  @override
+  (Future<PartialItemData>, ReadProgress) getItemDataPartial(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    // Browser reads can not be cancelled.
+    final (data, progress) =
+        getItemData(handle, format: format, timeout: timeout);
+    return (
+      data.then((value) => PartialItemData(data: value, wasTruncated: false)),
+      progress,
+    );
+  }
+
   @override
   Future<List<String>> getItemFormats(DataReaderItemHandle handle) {
     final impl = handle as $DataReaderItemHandle;
@@ -102,6 +121,7 @@ class ReaderManagerImpl extends ReaderManager {
       final info = DataReaderItemInfo(
         handle,
         formats: formats,
+        formatFidelity: List.filled(formats.length, 0),
         synthesizedFormats: [],
         virtualReceivers: receivers,
         suggestedName: await impl.suggestedName(),
@@ -119,4 +139,291 @@ class ReaderManagerImpl extends ReaderManager {
   VirtualFile createVirtualFileFromUri(Uri uri) {
     throw UnsupportedError('createVirtualFileFromUri is not supported on web');
   }
+
+  @override
+  Future<DataReaderHandle> newExternalReader({
+    int? handle,
+    String? pasteboardName,
+  }) {
+    throw UnsupportedError('newExternalReader is not supported on web');
+  }
+
+  @override
+  Future<List<ItemFormatConversion>> getItemFormatConversions(
+      DataReaderItemHandle handle) async {
+    // Browser provides data as is.
+    return [];
+  }
+
+  @override
+  Future<void> setFormatConversionEnabled(
+    DataReaderHandle reader,
+    FormatConversion conversion, {
+    required bool enabled,
+  }) async {}
+
+  @override
+  Future<SourceUrl?> getItemSourceUrl(DataReaderItemHandle handle) async {
+    return null;
+  }
+
+  @override
+  Future<WebArchive?> getItemWebArchive(DataReaderItemHandle handle) async {
+    return null;
+  }
+
+  @override
+  Future<Uint8List?> rasterizeItemMetafile(
+    DataReaderItemHandle handle, {
+    int? width,
+    int? height,
+  }) async {
+    return null;
+  }
+
+  @override
+  Future<ItemMetadata> getItemMetadata(DataReaderItemHandle handle) async {
+    return ItemMetadata(
+      size: null,
+      lastModified: null,
+      sourceApplication: null,
+    );
+  }
+
+  @override
+  Future<MediaInfo?> getItemMediaInfo(DataReaderItemHandle handle) async {
+    return null;
+  }
+
+  @override
+  Future<bool> isRemoteContent(DataReaderHandle reader) async {
+    return false;
+  }
+
+  @override
+  Future<bool> isStale(DataReaderHandle reader) async {
+    return false;
+  }
+
+  @override
+  Future<DataReaderHandle?> refresh(DataReaderHandle reader) async {
+    return null;
+  }
+
+  @override
+  Future<int> getIsolateId() {
+    throw UnsupportedError('getIsolateId is not supported on web');
+  }
+
+  @override
+  Future<int> cloneReaderForIsolate(
+    DataReaderHandle reader,
+    int targetIsolateId,
+  ) {
+    throw UnsupportedError('cloneReaderForIsolate is not supported on web');
+  }
+
+  @override
+  Future<DataReaderHandle> receiveTransferredReader(int handle) {
+    throw UnsupportedError('receiveTransferredReader is not supported on web');
+  }
+
+  @override
+  (Future<List<ResolvedFile>>, ReadProgress) resolveItemsToFiles(
+    DataReaderHandle reader, {
+    Iterable<DataReaderItemHandle>? items,
+    required String targetFolder,
+    required List<String> fileUriFormats,
+  }) {
+    throw UnsupportedError('resolveItemsToFiles is not supported on web');
+  }
+
+  @override
+  Future<void> setFilePolicy(FilePolicy? policy) async {}
+
+  @override
+  Future<String> getManagedDirectory() {
+    throw UnsupportedError('getManagedDirectory is not supported on web');
+  }
+
+  @override
+  Future<void> setManagedDirectoryQuota(int? quota) async {}
+
+  @override
+  Future<String> claimManagedFile({
+    required String path,
+    required String targetFolder,
+  }) {
+    throw UnsupportedError('claimManagedFile is not supported on web');
+  }
+
+  @override
+  Future<CompositeReadProgress> createCompositeProgress({
+    required int partCount,
+    List<double>? weights,
+  }) {
+    throw UnsupportedError('createCompositeProgress is not supported on web');
+  }
+
+  @override
+  Future<void> setOutOfProcessReading(bool enabled) {
+    throw UnsupportedError('setOutOfProcessReading is not supported on web');
+  }
+
+  @override
+  Future<void> setExcludeRemoteContent(bool exclude) {
+    throw UnsupportedError('setExcludeRemoteContent is not supported on web');
+  }
+
+  @override
+  Future<void> registerClipboardFormat({
+    required String identifier,
+    required String platformName,
+  }) async {}
+
+  @override
+  (Future<String?>, ReadProgress) readItemLocalTransfer(
+    DataReaderItemHandle handle, {
+    required String targetPath,
+  }) {
+    final progress = SimpleProgress()..done();
+    return (Future.value(null), progress);
+  }
+
+  @override
+  Future<void> setTransformRules(List<TransformRule> rules) async {}
+
+  @override
+  Future<void> setRtfHtmlConversionEnabled(bool enabled) async {}
+
+  @override
+  Future<void> setBinaryProtocol({
+    bool itemData = false,
+    bool progress = false,
+    bool dropEvents = false,
+  }) async {}
+
+  @override
+  Future<void> setSharedBufferTransfer({int? thresholdBytes}) async {}
+
+  @override
+  (Future<VirtualFile>, ReadProgress) getVirtualFileData(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    throw UnsupportedError('getVirtualFileData is not supported on web');
+  }
+
+  @override
+  (Future<ManagedFile>, ReadProgress) getVirtualFileManaged(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    throw UnsupportedError('getVirtualFileManaged is not supported on web');
+  }
+
+  @override
+  Future<void> disposeFileLease(int lease) async {}
+
+  @override
+  (Future<VirtualFile?>, ReadProgress) getItemDataStream(
+    DataReaderItemHandle handle, {
+    required String format,
+    Duration? timeout,
+  }) {
+    final progress = SimpleProgress()..done();
+    return (Future.value(null), progress);
+  }
+
+  @override
+  (Future<ImportResult>, ReadProgress) importItem(
+    DataReaderItemHandle handle, {
+    required List<ImportTarget> targets,
+  }) {
+    throw UnsupportedError('importItem is not supported on web');
+  }
+
+  @override
+  (Future<ArchiveListing?>, ReadProgress) getArchiveEntries(
+    DataReaderItemHandle handle, {
+    required List<String> fileUriFormats,
+  }) {
+    final progress = SimpleProgress()..done();
+    return (Future.value(null), progress);
+  }
+
+  @override
+  Future<VirtualFile> readArchiveEntry(String archivePath, int index) {
+    throw UnsupportedError('readArchiveEntry is not supported on web');
+  }
+
+  @override
+  Future<String> extractArchiveEntry(
+    String archivePath,
+    int index,
+    String targetFolder,
+  ) {
+    throw UnsupportedError('extractArchiveEntry is not supported on web');
+  }
+
+  @override
+  Future<int> registerFormatConverter({
+    required String sourceFormat,
+    required String targetFormat,
+    required FormatConverterCallback convert,
+  }) {
+    throw UnsupportedError('registerFormatConverter is not supported on web');
+  }
+
+  @override
+  Future<void> unregisterFormatConverter(int id) async {}
+
+  @override
+  (Future<List<RichTextSpan>?>, ReadProgress) getItemRichText(
+    DataReaderItemHandle handle,
+  ) {
+    final progress = SimpleProgress()..done();
+    return (Future.value(null), progress);
+  }
+
+  @override
+  (Future<TextWithEntities?>, ReadProgress) getItemTextWithEntities(
+    DataReaderItemHandle handle, {
+    required String format,
+    List<EntityKind>? kinds,
+  }) {
+    final progress = SimpleProgress()..done();
+    return (Future.value(null), progress);
+  }
+
+  @override
+  (Future<List<ItemDataResult>>, ReadProgress) getItemDataMulti(
+    DataReaderHandle reader, {
+    required List<(DataReaderItemHandle, String)> items,
+    Duration? timeout,
+  }) {
+    throw UnsupportedError('getItemDataMulti is not supported on web');
+  }
+
+  @override
+  (Future<DataReaderHandle>, ReadProgress) snapshotReader(
+    DataReaderHandle reader, {
+    int? sizeLimit,
+  }) {
+    throw UnsupportedError('snapshotReader is not supported on web');
+  }
+
+  @override
+  Future<FormatSubscription> subscribeToFormats(
+    DataReaderHandle reader, {
+    List<String>? formats,
+    Duration? timeout,
+    required FormatsAvailableCallback onFormatsAvailable,
+    VoidCallback? onEnded,
+  }) async {
+    onEnded?.call();
+    return FormatSubscription(() async {});
+  }
 }
@@ -0,0 +1,25 @@
+import 'package:flutter/foundation.dart';
+
+import '../resource_sweeper.dart';
+
+class ResourceSweeperImpl extends ResourceSweeper {
+  @override
+  Future<void> configure(SweeperConfiguration configuration) async {}
+
+  @override
+  Future<SweepReport> sweepNow() async {
+    return SweepReport(
+      removedFiles: [],
+      releasedProviders: 0,
+      releasedDropPermissions: 0,
+    );
+  }
+
+  @override
+  Future<List<TaskDiagnostics>> getTaskDiagnostics() async {
+    return [];
+  }
+
+  @override
+  final lastReport = ValueNotifier<SweepReport?>(null);
+}
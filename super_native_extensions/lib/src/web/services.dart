@@ -0,0 +1,18 @@
+import '../data_provider.dart';
+import '../services.dart';
+
+class ServicesManagerImpl extends ServicesManager {
+  @override
+  Future<void> setServicesContent(
+    List<DataProviderHandle> providers, {
+    List<String> returnTypes = const [],
+  }) {
+    throw UnsupportedError('setServicesContent is not supported on web');
+  }
+
+  @override
+  Future<void> setServicesProviderEnabled(bool enabled) {
+    throw UnsupportedError(
+        'setServicesProviderEnabled is not supported on web');
+  }
+}
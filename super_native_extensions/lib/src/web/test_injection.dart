@@ -0,0 +1,18 @@
+import 'dart:ui' as ui;
+
+import '../data_provider.dart';
+import '../drop.dart';
+import '../test_injection.dart';
+
+class TestInjectionImpl extends TestInjection {
+  @override
+  Future<SimulatedDragResult> simulateDrag({
+    required List<DataProviderHandle> providers,
+    required List<ui.Offset> locations,
+    List<DropOperation> allowedOperations = const [DropOperation.copy],
+    bool performDrop = true,
+    int? engineHandle,
+  }) {
+    throw UnsupportedError('Test injection is not supported on web');
+  }
+}
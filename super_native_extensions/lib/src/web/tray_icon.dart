@@ -0,0 +1,36 @@
+import '../desktop_capability.dart';
+import '../image_data.dart';
+import '../tray_icon.dart';
+
+class TrayIconManagerImpl extends TrayIconManager {
+  @override
+  Future<int?> createTrayIcon({
+    required ImageData image,
+    String? tooltip,
+    bool customTooltip = false,
+  }) async {
+    return null;
+  }
+
+  @override
+  Future<void> updateTrayIcon(
+    int handle, {
+    ImageData? image,
+    String? tooltip,
+  }) async {}
+
+  @override
+  Future<void> destroyTrayIcon(int handle) async {}
+
+  @override
+  Future<DesktopFeatureCapability> getCapability() async {
+    return DesktopFeatureCapability(
+      supported: false,
+      backend: null,
+      reason: 'Tray icons are not supported on web',
+    );
+  }
+
+  @override
+  set delegate(TrayIconManagerDelegate? delegate) {}
+}
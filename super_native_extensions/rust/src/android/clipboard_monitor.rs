@@ -0,0 +1,24 @@
+use std::rc::Weak;
+
+use crate::{
+    clipboard_monitor::ClipboardMonitorDelegate,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+/// Android only delivers clipboard changes to the focused application through
+/// `OnPrimaryClipChangedListener`, which requires a Java listener class.
+pub struct PlatformClipboardMonitor {}
+
+impl PlatformClipboardMonitor {
+    pub fn new(_delegate: Weak<dyn ClipboardMonitorDelegate>) -> Self {
+        Self {}
+    }
+
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformClipboardMonitor>) {}
+
+    pub fn start(&self) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn stop(&self) {}
+}
@@ -0,0 +1,103 @@
+//! Work profile and multi-user clipboard restrictions.
+//!
+//! Device policy may prevent clipboard content from crossing profile
+//! boundaries. Platform clipboard silently returns empty clip in that case,
+//! but some DLP implementations throw `SecurityException` from
+//! `ClipboardManager` instead. Such failures are reported as
+//! [`NativeExtensionsError::BlockedByPolicy`] carrying the restriction that
+//! most likely caused it.
+
+use jni::{
+    objects::{JObject, JObjectArray, JString},
+    JNIEnv,
+};
+
+use crate::{
+    android::{CLIP_DATA_HELPER, CONTEXT, JAVA_VM},
+    api_model::ClipboardPolicy,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+use super::util::JniResult;
+
+const RESTRICTION_CROSS_PROFILE_COPY_PASTE: &str = "crossProfileCopyPaste";
+const RESTRICTION_MANAGED_PROFILE: &str = "managedProfile";
+const RESTRICTION_SECONDARY_USER: &str = "secondaryUser";
+
+/// Restrictions reported by `ClipDataHelper.getClipboardRestrictions`.
+fn clipboard_restrictions(env: &mut JNIEnv) -> JniResult<Vec<String>> {
+    let context = CONTEXT.get().unwrap().as_obj();
+    let restrictions: JObjectArray = env
+        .call_method(
+            CLIP_DATA_HELPER.get().unwrap().as_obj(),
+            "getClipboardRestrictions",
+            "(Landroid/content/Context;)[Ljava/lang/String;",
+            &[context.into()],
+        )?
+        .l()?
+        .into();
+    (0..env.get_array_length(&restrictions)?)
+        .map(|i| {
+            let restriction: JString = env.get_object_array_element(&restrictions, i)?.into();
+            let restriction = env.get_string(&restriction)?;
+            Ok(restriction.into())
+        })
+        .collect()
+}
+
+pub fn clipboard_policy() -> NativeExtensionsResult<ClipboardPolicy> {
+    let mut env = JAVA_VM
+        .get()
+        .ok_or_else(|| NativeExtensionsError::OtherError("JAVA_VM not set".into()))?
+        .attach_current_thread()?;
+    let restrictions = clipboard_restrictions(&mut env)?;
+    let has = |restriction: &str| restrictions.iter().any(|r| r == restriction);
+    Ok(ClipboardPolicy {
+        managed_profile: has(RESTRICTION_MANAGED_PROFILE),
+        secondary_user: has(RESTRICTION_SECONDARY_USER),
+        cross_profile_copy_paste_disallowed: has(RESTRICTION_CROSS_PROFILE_COPY_PASTE),
+    })
+}
+
+/// Most specific restriction that explains blocked clipboard access.
+fn policy_source(env: &mut JNIEnv) -> Option<String> {
+    let restrictions = clipboard_restrictions(env).ok()?;
+    [
+        RESTRICTION_CROSS_PROFILE_COPY_PASTE,
+        RESTRICTION_MANAGED_PROFILE,
+    ]
+    .into_iter()
+    .find(|r| restrictions.iter().any(|restriction| restriction == r))
+    .map(|r| r.to_owned())
+}
+
+pub fn describe_exception(env: &mut JNIEnv, exception: &JObject) -> JniResult<String> {
+    let description: JString = env
+        .call_method(exception, "toString", "()Ljava/lang/String;", &[])?
+        .l()?
+        .into();
+    Ok(env.get_string(&description)?.into())
+}
+
+/// Converts result of clipboard manager call. Pending `SecurityException`
+/// is cleared and reported as `BlockedByPolicy`.
+pub fn check_clipboard_access<T>(
+    env: &mut JNIEnv,
+    result: JniResult<T>,
+) -> NativeExtensionsResult<T> {
+    match result {
+        Err(jni::errors::Error::JavaException) => {
+            let exception = env.exception_occurred()?;
+            env.exception_clear()?;
+            if env.is_instance_of(&exception, "java/lang/SecurityException")? {
+                Err(NativeExtensionsError::BlockedByPolicy(policy_source(env)))
+            } else {
+                let description = describe_exception(env, &exception)?;
+                Err(NativeExtensionsError::OtherError(format!(
+                    "JNI: {description}"
+                )))
+            }
+        }
+        result => Ok(result?),
+    }
+}
@@ -27,10 +27,17 @@ use crate::{
     value_promise::{ValuePromise, ValuePromiseResult},
 };
 
-use super::util::{jstring_from_utf8, uri_from_string, uri_from_utf8};
+use super::{
+    clipboard_policy::check_clipboard_access,
+    util::{jstring_from_utf8, uri_from_string, uri_from_utf8},
+};
 
 type JniResult<T> = jni::errors::Result<T>;
 
+thread_local! {
+    static CURRENT_CLIP: RefCell<Vec<Arc<DataProviderHandle>>> = const { RefCell::new(Vec::new()) };
+}
+
 struct DataProviderRecord {
     data: DataProvider,
     delegate: Capsule<Weak<dyn PlatformDataProviderDelegate>>,
@@ -284,9 +291,6 @@ impl PlatformDataProvider {
         let handles: Vec<_> = providers.iter().map(|p| p.1.clone()).collect();
         let providers: Vec<_> = providers.into_iter().map(|p| p.0).collect();
 
-        thread_local! {
-            static CURRENT_CLIP: RefCell<Vec<Arc<DataProviderHandle>>> = const { RefCell::new(Vec::new()) };
-        }
         // ClipManager doesn't provide any lifetime management for clip so just
         // keep the data awake until the clip is replaced.
         CURRENT_CLIP.with(|r| r.replace(handles));
@@ -298,6 +302,37 @@ impl PlatformDataProvider {
 
         let clip_data = Self::create_clip_data_for_data_providers(&mut env, providers)?;
 
+        let clipboard_manager = Self::clipboard_manager(&mut env)?;
+        let res = env.call_method(
+            clipboard_manager,
+            "setPrimaryClip",
+            "(Landroid/content/ClipData;)V",
+            &[(&clip_data).into()],
+        );
+        check_clipboard_access(&mut env, res)?;
+
+        Ok(())
+    }
+
+    pub async fn clear_clipboard() -> NativeExtensionsResult<()> {
+        let mut env = JAVA_VM
+            .get()
+            .ok_or_else(|| NativeExtensionsError::OtherError("JAVA_VM not set".into()))?
+            .attach_current_thread()?;
+        let clipboard_manager = Self::clipboard_manager(&mut env)?;
+        // Available since API 28.
+        let res = env.call_method(clipboard_manager, "clearPrimaryClip", "()V", &[]);
+        check_clipboard_access(&mut env, res)?;
+        CURRENT_CLIP.with(|r| r.take());
+        Ok(())
+    }
+
+    /// Clip data is written eagerly; there is nothing to flush.
+    pub fn flush_clipboard() -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+
+    fn clipboard_manager<'a>(env: &mut JNIEnv<'a>) -> NativeExtensionsResult<JObject<'a>> {
         let context = CONTEXT.get().unwrap().as_obj();
         let context_class = env.find_class("android/content/Context")?;
         let clipboard_service = env
@@ -311,14 +346,7 @@ impl PlatformDataProvider {
                 &[(&clipboard_service).into()],
             )?
             .l()?;
-        env.call_method(
-            clipboard_manager,
-            "setPrimaryClip",
-            "(Landroid/content/ClipData;)V",
-            &[(&clip_data).into()],
-        )?;
-
-        Ok(())
+        Ok(clipboard_manager)
     }
 }
 
@@ -11,10 +11,14 @@ use jni::{objects::JObject, sys::jsize, JNIEnv};
 
 use crate::{
     android::{DRAG_DROP_HELPER, JAVA_VM},
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, ImageData, Point},
+    api_model::{
+        DataProviderId, DragConfiguration, DragRequest, DropOperation, ImageData, KeyModifiers,
+        Point,
+    },
     data_provider_manager::DataProviderHandle,
     drag_manager::{
         DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
+        UpdateDragImageRequest,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
 };
@@ -37,6 +41,7 @@ struct DragSession {
     platform_context_delegate: Weak<dyn PlatformDragContextDelegate>,
     data_providers: Vec<Arc<DataProviderHandle>>,
     last_drop_operation: Cell<Option<DropOperation>>,
+    last_location: RefCell<Point>,
 }
 
 thread_local! {
@@ -61,6 +66,10 @@ impl PlatformDragContext {
         CONTEXTS.with(|c| c.borrow_mut().insert(self.id, weak_self));
     }
 
+    pub fn engine_handle(&self) -> i64 {
+        self.engine_handle
+    }
+
     fn create_bitmap<'a>(
         env: &mut JNIEnv<'a>,
         image: &ImageData,
@@ -114,6 +123,15 @@ impl PlatformDragContext {
         true
     }
 
+    /// Sessions end on their own; events of sessions of released context
+    /// are ignored.
+    pub fn cancel_sessions(&self) {}
+
+    /// Modifier state is not available for touch drags.
+    pub fn current_modifiers() -> KeyModifiers {
+        KeyModifiers::default()
+    }
+
     pub async fn start_drag(
         &self,
         request: DragRequest,
@@ -150,6 +168,10 @@ impl PlatformDragContext {
             y: image.rect.center().y * device_pixel_ratio,
         };
 
+        let animates_on_cancel = request
+            .configuration
+            .animates_to_starting_position_on_cancel_or_fail;
+
         let mut sessions = self.sessions.borrow_mut();
         sessions.insert(
             session_id,
@@ -159,6 +181,7 @@ impl PlatformDragContext {
                 platform_context_delegate: self.delegate.clone(),
                 data_providers: provider_handles,
                 last_drop_operation: Cell::new(None),
+                last_location: RefCell::new(return_point.clone()),
             },
         );
 
@@ -168,7 +191,7 @@ impl PlatformDragContext {
         env.call_method(
             DRAG_DROP_HELPER.get().unwrap().as_obj(),
             "startDrag",
-            "(Landroid/view/View;JLandroid/content/ClipData;Landroid/graphics/Bitmap;IIII)V",
+            "(Landroid/view/View;JLandroid/content/ClipData;Landroid/graphics/Bitmap;IIIIZ)V",
             &[
                 view.as_obj().into(),
                 session_id.into(),
@@ -178,6 +201,7 @@ impl PlatformDragContext {
                 (point_in_rect.y.round() as i32).into(),
                 (return_point.x.round() as i32).into(),
                 (return_point.y.round() as i32).into(),
+                animates_on_cancel.into(),
             ],
         )?;
 
@@ -216,6 +240,13 @@ impl PlatformDragContext {
         }
     }
 
+    pub fn update_drag_image(
+        &self,
+        _request: &UpdateDragImageRequest,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     pub fn get_local_data_for_session_id(
         &self,
         session_id: DragSessionId,
@@ -252,14 +283,17 @@ impl DragSession {
     ) -> NativeExtensionsResult<HandleEventResult> {
         let action = event.get_action(env)?;
         if action == DragAction::DragLocation {
+            let location = Point {
+                x: event.get_x(env)? as f64,
+                y: event.get_y(env)? as f64,
+            };
+            self.last_location.replace(location.clone());
             if let Some(delegate) = self.platform_context_delegate.upgrade() {
                 delegate.drag_session_did_move_to_location(
                     self.platform_context_id,
                     session_id,
-                    Point {
-                        x: event.get_x(env)? as f64,
-                        y: event.get_y(env)? as f64,
-                    },
+                    location,
+                    PlatformDragContext::current_modifiers(),
                 );
             }
         }
@@ -273,10 +307,30 @@ impl DragSession {
                         .unwrap_or(DropOperation::Copy),
                     false => DropOperation::None,
                 };
+                if !result
+                    && !self
+                        .configuration
+                        .animates_to_starting_position_on_cancel_or_fail
+                {
+                    // The shadow surface was handed over instead of being
+                    // animated back; remove it and let Dart animate.
+                    env.call_method(
+                        DRAG_DROP_HELPER.get().unwrap().as_obj(),
+                        "releaseDragSurface",
+                        "(Landroid/view/DragEvent;)V",
+                        &[event.0.into()],
+                    )?;
+                    delegate.drag_session_did_cancel(
+                        self.platform_context_id,
+                        session_id,
+                        self.last_location.borrow().clone(),
+                    );
+                }
                 delegate.drag_session_did_end_with_operation(
                     self.platform_context_id,
                     session_id,
                     operation,
+                    None,
                 );
             }
             Ok(HandleEventResult::RemoveSession)
@@ -3,10 +3,11 @@ use std::{
     collections::HashMap,
     rc::{Rc, Weak},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use irondash_engine_context::EngineContext;
-use irondash_message_channel::{IsolateId, Value};
+use irondash_message_channel::Value;
 use irondash_run_loop::RunLoop;
 use jni::{
     objects::{GlobalRef, JClass, JObject, JString, JValue},
@@ -17,6 +18,7 @@ use jni::{
 use crate::{
     android::{CONTEXT, DRAG_DROP_HELPER, JAVA_VM},
     api_model::{DropOperation, Point},
+    drag_manager::DragSessionId,
     drop_manager::{
         BaseDropEvent, DropEvent, DropItem, DropSessionId, PlatformDropContextDelegate,
         PlatformDropContextId,
@@ -34,6 +36,8 @@ use super::{
 
 pub struct PlatformDropContext {
     id: PlatformDropContextId,
+    /// Identifies this context in drag events delivered by the view.
+    handler_id: i64,
     engine_handle: i64,
     delegate: Weak<dyn PlatformDropContextDelegate>,
     next_session_id: Cell<i64>,
@@ -45,8 +49,43 @@ struct Session {
     last_operation: Cell<DropOperation>,
 }
 
+/// `DragAndDropPermissions` held for a drop reader.
+struct PermissionGrant {
+    permissions: RefCell<Option<GlobalRef>>,
+    acquired: Instant,
+}
+
+impl PermissionGrant {
+    fn release(&self) {
+        if let Some(permissions) = self.permissions.take() {
+            PlatformDropContext::release_permissions(permissions).ok_log();
+        }
+    }
+}
+
 thread_local! {
-    static CONTEXTS: RefCell<HashMap<PlatformDropContextId, Weak<PlatformDropContext>>> = RefCell::new(HashMap::new());
+    static CONTEXTS: RefCell<HashMap<i64, Weak<PlatformDropContext>>> = RefCell::new(HashMap::new());
+    static NEXT_HANDLER_ID: Cell<i64> = Cell::new(1);
+    static PERMISSION_GRANTS: RefCell<Vec<Weak<PermissionGrant>>> = RefCell::new(Vec::new());
+}
+
+/// Releases drag and drop permissions held for longer than `max_age`, even
+/// if the reader they belong to is still alive. Afterwards the reader can no
+/// longer access content URIs. Returns number of released permissions.
+pub fn release_expired_drop_permissions(max_age: Duration) -> usize {
+    let expired: Vec<_> = PERMISSION_GRANTS.with(|grants| {
+        let mut grants = grants.borrow_mut();
+        grants.retain(|g| g.strong_count() > 0);
+        grants
+            .iter()
+            .filter_map(|g| g.upgrade())
+            .filter(|g| g.acquired.elapsed() >= max_age && g.permissions.borrow().is_some())
+            .collect()
+    });
+    for grant in &expired {
+        grant.release();
+    }
+    expired.len()
 }
 
 impl PlatformDropContext {
@@ -57,6 +96,7 @@ impl PlatformDropContext {
     ) -> NativeExtensionsResult<Self> {
         Ok(Self {
             id,
+            handler_id: NEXT_HANDLER_ID.with(|id| id.next_id()),
             engine_handle,
             delegate,
             next_session_id: Cell::new(0),
@@ -65,7 +105,7 @@ impl PlatformDropContext {
     }
 
     fn _assign_weak_self(&self, weak_self: Weak<Self>) -> NativeExtensionsResult<()> {
-        CONTEXTS.with(|c| c.borrow_mut().insert(self.id, weak_self));
+        CONTEXTS.with(|c| c.borrow_mut().insert(self.handler_id, weak_self));
 
         let mut env = JAVA_VM
             .get()
@@ -78,7 +118,22 @@ impl PlatformDropContext {
             DRAG_DROP_HELPER.get().unwrap().as_obj(),
             "registerDropHandler",
             "(Landroid/view/View;J)V",
-            &[view.as_obj().into(), self.id.0.into()],
+            &[view.as_obj().into(), self.handler_id.into()],
+        )?;
+        Ok(())
+    }
+
+    fn unregister_drop_handler(&self) -> NativeExtensionsResult<()> {
+        let mut env = JAVA_VM
+            .get()
+            .ok_or_else(|| NativeExtensionsError::OtherError("JAVA_VM not set".into()))?
+            .attach_current_thread()?;
+        let view = EngineContext::get()?.get_flutter_view(self.engine_handle)?;
+        env.call_method(
+            DRAG_DROP_HELPER.get().unwrap().as_obj(),
+            "unregisterDropHandler",
+            "(Landroid/view/View;J)V",
+            &[view.as_obj().into(), self.handler_id.into()],
         )?;
         Ok(())
     }
@@ -87,6 +142,21 @@ impl PlatformDropContext {
         self._assign_weak_self(weak_self).ok_log();
     }
 
+    // Clip data is only guaranteed to be accessible while handling ACTION_DROP.
+    pub fn supports_deferred_drop(&self) -> bool {
+        false
+    }
+
+    pub fn show_busy_indicator(
+        &self,
+        _session_id: DropSessionId,
+        _location: &Point,
+    ) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+
+    pub fn hide_busy_indicator(&self, _session_id: DropSessionId) {}
+
     pub fn register_drop_formats(&self, _formats: &[String]) -> NativeExtensionsResult<()> {
         Ok(())
     }
@@ -117,7 +187,7 @@ impl PlatformDropContext {
         event: &DragEvent<'a, '_>,
         session_id: DropSessionId,
         env: &mut JNIEnv<'a>,
-        mut local_data: Vec<Value>,
+        (local_session_id, mut local_data): (Option<DragSessionId>, Vec<Value>),
         allowed_operations: Vec<DropOperation>,
         accepted_operation: Option<DropOperation>,
         reader: Option<(Rc<PlatformDataReader>, RegisteredDataReader)>,
@@ -131,6 +201,7 @@ impl PlatformDropContext {
                         item_id: (index as i64).into(),
                         formats: reader.get_formats_for_item_sync(*item)?,
                         local_data: local_data.get(index).cloned().unwrap_or(Value::Null),
+                        allowed_operations: None,
                     });
                 }
                 items
@@ -173,6 +244,7 @@ impl PlatformDropContext {
                         item_id: (index as i64).into(),
                         formats: mime_types.clone(),
                         local_data,
+                        allowed_operations: None,
                     })
                     .collect()
             }
@@ -181,6 +253,10 @@ impl PlatformDropContext {
         let density = Self::get_display_density(env)?;
         Ok(DropEvent {
             session_id,
+            engine_handle: self.id.engine_handle,
+            region_id: None,
+            session_local_data: Value::Null,
+            local_session_id,
             location_in_view: Point {
                 x: event.get_x(env)? as f64 / density,
                 y: event.get_y(env)? as f64 / density,
@@ -218,9 +294,13 @@ impl PlatformDropContext {
                 &[(&event).into()],
             )?
             .l()?;
-        let permissions = env.new_global_ref(permission)?;
+        let grant = Rc::new(PermissionGrant {
+            permissions: RefCell::new(Some(env.new_global_ref(permission)?)),
+            acquired: Instant::now(),
+        });
+        PERMISSION_GRANTS.with(|grants| grants.borrow_mut().push(Rc::downgrade(&grant)));
         Ok(Arc::new(DropNotifier::new(move || {
-            Self::release_permissions(permissions).ok_log();
+            grant.release();
         })))
     }
 
@@ -231,12 +311,14 @@ impl PlatformDropContext {
     ) -> NativeExtensionsResult<bool> {
         let event = DragEvent(event);
         if let Some(delegate) = self.delegate.upgrade() {
-            // We're conflating drag and drop context ids here. However it works
-            // because at this point there are both IsolateId. In future with
-            // flutter multi-view they should probably be based in view handle
+            // Only drag contexts of the same view track sessions started from
+            // it; drag events are delivered to listeners of every view.
             let drag_contexts = delegate.get_platform_drag_contexts();
 
-            for drag_context in &drag_contexts {
+            for drag_context in drag_contexts
+                .iter()
+                .filter(|c| c.engine_handle() == self.engine_handle)
+            {
                 // forward the event to drag context. Necessary to know when current
                 // drag session ends for example.
                 drag_context.on_drop_event(env, &event)?;
@@ -257,13 +339,15 @@ impl PlatformDropContext {
 
             let session_id = event.get_session_id(env)?;
 
-            let get_local_data = || {
+            // Local drag session and local data of its items.
+            let get_local_session = || {
                 session_id
                     .and_then(|session_id| {
                         drag_contexts
                             .iter()
                             .filter_map(|c| c.get_local_data_for_session_id(session_id).ok())
                             .next()
+                            .map(|data| (Some(session_id), data))
                     })
                     .unwrap_or_default()
             };
@@ -297,7 +381,7 @@ impl PlatformDropContext {
                         &event,
                         current_session.id,
                         env,
-                        get_local_data(),
+                        get_local_session(),
                         get_allowed_operations(),
                         None, // accepted operation
                         None, // reader
@@ -326,6 +410,7 @@ impl PlatformDropContext {
                         self.id,
                         BaseDropEvent {
                             session_id: current_session.id,
+                            engine_handle: self.id.engine_handle,
                         },
                     );
                     Ok(true)
@@ -336,7 +421,7 @@ impl PlatformDropContext {
                         && accepted_operation != DropOperation::UserCancelled
                         && accepted_operation != DropOperation::Forbidden
                     {
-                        let local_data = get_local_data();
+                        let local_session = get_local_session();
                         let clip_data = event.get_clip_data(env)?;
 
                         let reader = if env.is_same_object(&clip_data, JObject::null())? {
@@ -366,7 +451,7 @@ impl PlatformDropContext {
                             &event,
                             current_session.id,
                             env,
-                            local_data,
+                            local_session,
                             get_allowed_operations(),
                             Some(accepted_operation),
                             reader,
@@ -394,6 +479,7 @@ impl PlatformDropContext {
                         self.id,
                         BaseDropEvent {
                             session_id: current_session.id,
+                            engine_handle: self.id.engine_handle,
                         },
                     );
                     self.current_session.replace(None);
@@ -409,7 +495,9 @@ impl PlatformDropContext {
 
 impl Drop for PlatformDropContext {
     fn drop(&mut self) {
-        CONTEXTS.with(|c| c.borrow_mut().remove(&self.id));
+        CONTEXTS.with(|c| c.borrow_mut().remove(&self.handler_id));
+        // View may be gone together with its engine.
+        self.unregister_drop_handler().ok();
     }
 }
 
@@ -451,10 +539,10 @@ pub extern "C" fn Java_com_superlist_super_1native_1extensions_DragDropHelper_on
     mut env: JNIEnv<'a>,
     _class: JClass,
     event: JObject<'a>,
-    drag_context: jlong,
+    handler_id: jlong,
 ) -> jvalue {
     let context = CONTEXTS
-        .with(|c| c.borrow().get(&IsolateId(drag_context)).cloned())
+        .with(|c| c.borrow().get(&handler_id).cloned())
         .and_then(|v| v.upgrade());
     match context {
         Some(context) => {
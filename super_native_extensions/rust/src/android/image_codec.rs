@@ -0,0 +1,24 @@
+//! Platform codec for the `image_transcode` feature. Android clipboard
+//! exposes images as content URIs, so there is nothing to transcode.
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+pub fn png_format() -> String {
+    "image/png".into()
+}
+
+pub fn decodable_formats() -> Vec<String> {
+    Vec::new()
+}
+
+pub fn encodable_formats() -> Vec<String> {
+    Vec::new()
+}
+
+pub fn decode_to_png(_data: &[u8]) -> NativeExtensionsResult<Vec<u8>> {
+    Err(NativeExtensionsError::UnsupportedOperation)
+}
+
+pub fn encode_from_png(_data: &[u8], _format: &str) -> NativeExtensionsResult<Vec<u8>> {
+    Err(NativeExtensionsError::UnsupportedOperation)
+}
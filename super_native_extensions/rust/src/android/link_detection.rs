@@ -0,0 +1,137 @@
+use jni::{
+    objects::{JObject, JObjectArray, JString},
+    JNIEnv,
+};
+
+use crate::{
+    android::JAVA_VM,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    link_detection::{detect_entities_fallback, DetectedEntity, EntityKind},
+};
+
+use super::util::JniResult;
+
+const LINKIFY_WEB_URLS: i32 = 0x01;
+const LINKIFY_EMAIL_ADDRESSES: i32 = 0x02;
+const LINKIFY_PHONE_NUMBERS: i32 = 0x04;
+
+/// Runs `Linkify.addLinks` on a `SpannableString` and collects resulting
+/// `URLSpan`s. Span offsets are Java string indices (UTF-16).
+fn linkify_spans(env: &mut JNIEnv, text: &str, mask: i32) -> JniResult<Vec<DetectedEntity>> {
+    // Caller may be a long lived native thread; spans additionally get
+    // frames of their own so that references don't accumulate per span.
+    env.with_local_frame(16, |env| linkify_spans_in_frame(env, text, mask))
+}
+
+fn linkify_spans_in_frame(
+    env: &mut JNIEnv,
+    text: &str,
+    mask: i32,
+) -> JniResult<Vec<DetectedEntity>> {
+    let string = env.new_string(text)?;
+    let spannable = env.new_object(
+        "android/text/SpannableString",
+        "(Ljava/lang/CharSequence;)V",
+        &[(&string).into()],
+    )?;
+    env.call_static_method(
+        "android/text/util/Linkify",
+        "addLinks",
+        "(Landroid/text/Spannable;I)Z",
+        &[(&spannable).into(), mask.into()],
+    )?;
+    let length = env.call_method(&spannable, "length", "()I", &[])?.i()?;
+    let span_class = env.find_class("android/text/style/URLSpan")?;
+    let spans: JObjectArray = env
+        .call_method(
+            &spannable,
+            "getSpans",
+            "(IILjava/lang/Class;)[Ljava/lang/Object;",
+            &[0.into(), length.into(), (&span_class).into()],
+        )?
+        .l()?
+        .into();
+    let mut res = Vec::new();
+    for i in 0..env.get_array_length(&spans)? {
+        let entity = env.with_local_frame(8, |env| span_entity(env, &spannable, &spans, i))?;
+        res.push(entity);
+    }
+    Ok(res)
+}
+
+fn span_entity(
+    env: &mut JNIEnv,
+    spannable: &JObject,
+    spans: &JObjectArray,
+    index: i32,
+) -> JniResult<DetectedEntity> {
+    let span = env.get_object_array_element(spans, index)?;
+    let start = env
+        .call_method(
+            spannable,
+            "getSpanStart",
+            "(Ljava/lang/Object;)I",
+            &[(&span).into()],
+        )?
+        .i()?;
+    let end = env
+        .call_method(
+            spannable,
+            "getSpanEnd",
+            "(Ljava/lang/Object;)I",
+            &[(&span).into()],
+        )?
+        .i()?;
+    let url: JString = env
+        .call_method(&span, "getURL", "()Ljava/lang/String;", &[])?
+        .l()?
+        .into();
+    let url: String = env.get_string(&url)?.into();
+    let kind = if url.starts_with("mailto:") {
+        EntityKind::Email
+    } else if url.starts_with("tel:") {
+        EntityKind::PhoneNumber
+    } else {
+        EntityKind::Url
+    };
+    Ok(DetectedEntity {
+        kind,
+        start: start as i64,
+        end: end as i64,
+        value: Some(url),
+    })
+}
+
+/// Linkify does not detect dates, these come from the built-in detector.
+/// Addresses are not supported (`Linkify.MAP_ADDRESSES` is deprecated).
+pub fn detect_entities_with_linkify(
+    text: &str,
+    kinds: &[EntityKind],
+) -> NativeExtensionsResult<Option<Vec<DetectedEntity>>> {
+    let mut mask = 0;
+    for kind in kinds {
+        mask |= match kind {
+            EntityKind::Url => LINKIFY_WEB_URLS,
+            EntityKind::Email => LINKIFY_EMAIL_ADDRESSES,
+            EntityKind::PhoneNumber => LINKIFY_PHONE_NUMBERS,
+            EntityKind::Date | EntityKind::Address => 0,
+        };
+    }
+    let mut res = if mask != 0 {
+        let mut env = JAVA_VM
+            .get()
+            .ok_or_else(|| NativeExtensionsError::OtherError("JAVA_VM not set".into()))?
+            .attach_current_thread()?;
+        linkify_spans(&mut env, text, mask)?
+    } else {
+        Vec::new()
+    };
+    if kinds.contains(&EntityKind::Date) {
+        for date in detect_entities_fallback(text, &[EntityKind::Date]) {
+            if !res.iter().any(|e| e.start < date.end && date.start < e.end) {
+                res.push(date);
+            }
+        }
+    }
+    Ok(Some(res))
+}
@@ -3,7 +3,9 @@ use std::rc::{Rc, Weak};
 use irondash_message_channel::IsolateId;
 
 use crate::{
-    api_model::{ImageData, Menu, ShowContextMenuRequest, ShowContextMenuResponse},
+    api_model::{
+        ImageData, Menu, MenuActionState, ShowContextMenuRequest, ShowContextMenuResponse,
+    },
     error::{NativeExtensionsError, NativeExtensionsResult},
     menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
 };
@@ -21,6 +23,15 @@ impl PlatformMenu {
     ) -> NativeExtensionsResult<Rc<Self>> {
         Ok(Rc::new(Self {}))
     }
+    /// Context menus on Android are rendered by Flutter, which rebuilds
+    /// the menu with new state instead.
+    pub fn update_item_state(
+        &self,
+        _unique_id: i64,
+        _state: MenuActionState,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
 }
 
 impl PlatformMenuContext {
@@ -1,13 +1,21 @@
+mod clipboard_monitor;
+pub mod clipboard_policy;
 mod data_provider;
 mod drag;
 mod drag_common;
 mod drop;
 mod hot_key;
+#[cfg(feature = "image_transcode")]
+pub mod image_codec;
 mod keyboard_layout;
+mod link_detection;
 mod menu;
 mod reader;
+pub mod shared_texture;
+mod tray_icon;
 mod util;
 
+pub use clipboard_monitor::*;
 pub use data_provider::*;
 pub use drag::*;
 pub use drop::*;
@@ -15,3 +23,4 @@ pub use hot_key::*;
 pub use keyboard_layout::*;
 pub use menu::*;
 pub use reader::*;
+pub use tray_icon::*;
@@ -1,29 +1,46 @@
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
-    path::PathBuf,
+    fs::{self, File},
+    future::Future,
+    io::Write,
+    path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex,
+    },
 };
 
+use async_trait::async_trait;
 use irondash_message_channel::Value;
 use irondash_run_loop::{util::FutureCompleter, RunLoop};
 use jni::{
     objects::{GlobalRef, JByteArray, JObject, JObjectArray, JString},
-    sys::{jbyte, jint},
+    sys::{jboolean, jbyte, jint, jlong},
     AttachGuard, JNIEnv,
 };
+use once_cell::sync::Lazy;
 
 use url::Url;
 
 use crate::{
     android::{CLIP_DATA_HELPER, CONTEXT, JAVA_VM},
     error::{NativeExtensionsError, NativeExtensionsResult},
-    reader_manager::{ReadProgress, VirtualFileReader},
-    util::DropNotifier,
+    link_detection::{DetectedEntity, EntityKind},
+    log::OkLog,
+    media_info::MediaMetadata,
+    reader_manager::{
+        ExternalReaderSource, FormatConversion, ItemFormatConversion, ItemMetadata,
+        ReadProgressHandle, VirtualFileReader,
+    },
+    util::{get_target_path, DropNotifier},
 };
 
-use super::MIME_TYPE_URI_LIST;
+use super::{
+    clipboard_policy::check_clipboard_access, link_detection::detect_entities_with_linkify,
+    MIME_TYPE_TEXT_HTML, MIME_TYPE_TEXT_PLAIN, MIME_TYPE_URI_LIST,
+};
 
 pub struct PlatformDataReader {
     clip_data: Option<GlobalRef>,
@@ -99,6 +116,9 @@ impl PlatformDataReader {
         &self,
         item: i64,
     ) -> NativeExtensionsResult<Option<String>> {
+        if let Some(display_name) = self.get_openable_columns(item).await?.display_name {
+            return Ok(Some(display_name));
+        }
         let formats = self.get_formats_for_item_sync(item)?;
         if formats.iter().any(|s| s == MIME_TYPE_URI_LIST) {
             let uri = self
@@ -124,6 +144,45 @@ impl PlatformDataReader {
         static NEXT_HANDLE: Cell<i64> = const { Cell::new(1) };
         static PENDING:
             RefCell<HashMap<i64,irondash_run_loop::util::FutureCompleter<NativeExtensionsResult<Value>>>> = RefCell::new(HashMap::new());
+        static PENDING_STREAMS:
+            RefCell<HashMap<i64, FutureCompleter<NativeExtensionsResult<Option<GlobalRef>>>>> = RefCell::new(HashMap::new());
+    }
+
+    /// Completes `open_content_stream` once `ClipDataHelper.openStream` opened
+    /// the stream on background thread.
+    #[no_mangle]
+    #[allow(non_snake_case)]
+    pub extern "C" fn Java_com_superlist_super_1native_1extensions_ClipDataHelper_onStreamOpened(
+        mut env: jni::JNIEnv,
+        _class: jni::objects::JClass,
+        handle: jint,
+        stream: JObject,
+        error: JString,
+        access_denied: jboolean,
+    ) {
+        let sender = RunLoop::sender_for_main_thread().unwrap();
+        let mut result = move || {
+            if !env.is_same_object(&stream, JObject::null())? {
+                return Ok(Some(env.new_global_ref(stream)?));
+            }
+            if env.is_same_object(&error, JObject::null())? {
+                return Ok(None);
+            }
+            let description: String = env.get_string(&error)?.into();
+            Err(if access_denied != 0 {
+                NativeExtensionsError::AccessDenied(Some(description))
+            } else {
+                NativeExtensionsError::VirtualFileReceiveError(description)
+            })
+        };
+        let result: NativeExtensionsResult<Option<GlobalRef>> = result();
+
+        sender.send(move || {
+            let completer = Self::PENDING_STREAMS.with(|m| m.borrow_mut().remove(&(handle as i64)));
+            if let Some(completer) = completer {
+                completer.complete(result);
+            }
+        });
     }
 
     #[no_mangle]
@@ -145,6 +204,19 @@ impl PlatformDataReader {
                 let data: JString = data.into();
                 let data = env.get_string(&data)?;
                 Ok(Value::String(data.into()))
+            } else if env.is_instance_of(&data, "[Ljava/lang/String;")? {
+                let data: JObjectArray = data.into();
+                let res = (0..env.get_array_length(&data)?)
+                    .map(|i| {
+                        let string: JString = env.get_object_array_element(&data, i)?.into();
+                        if env.is_same_object(&string, JObject::null())? {
+                            Ok(Value::Null)
+                        } else {
+                            Ok(Value::String(env.get_string(&string)?.into()))
+                        }
+                    })
+                    .collect::<Result<Vec<_>, NativeExtensionsError>>()?;
+                Ok(Value::List(res))
             } else {
                 let mut res = Vec::new();
                 let data: JByteArray = data.into();
@@ -167,7 +239,7 @@ impl PlatformDataReader {
         &self,
         item: i64,
         format: String,
-        _progress: Option<Arc<ReadProgress>>,
+        _progress: Option<ReadProgressHandle>,
     ) -> NativeExtensionsResult<Value> {
         match &self.clip_data {
             Some(clip_data) => {
@@ -217,6 +289,12 @@ impl PlatformDataReader {
         }))
     }
 
+    /// Formats from which `fileUriList` is synthesized. Content URIs do not
+    /// refer to local files, so there are none.
+    pub fn file_uri_list_source_formats() -> Vec<String> {
+        Vec::new()
+    }
+
     pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
         let (mut env, context) = Self::get_env_and_context()?;
         let clipboard_service = env
@@ -234,17 +312,88 @@ impl PlatformDataReader {
                 &[(&clipboard_service).into()],
             )?
             .l()?;
-        let clip_data = env
-            .call_method(
-                clipboard_manager,
-                "getPrimaryClip",
-                "()Landroid/content/ClipData;",
-                &[],
-            )?
-            .l()?;
+        let clip_data = env.call_method(
+            clipboard_manager,
+            "getPrimaryClip",
+            "()Landroid/content/ClipData;",
+            &[],
+        );
+        let clip_data = check_clipboard_access(&mut env, clip_data)?.l()?;
         Self::from_clip_data(&env, clip_data, None)
     }
 
+    pub fn new_with_external_source(
+        source: ExternalReaderSource,
+    ) -> NativeExtensionsResult<Rc<Self>> {
+        match source {
+            ExternalReaderSource::Handle { handle } => {
+                let clip_data = EXTERNAL_CLIP_DATA
+                    .lock()
+                    .unwrap()
+                    .remove(&handle)
+                    .ok_or_else(|| {
+                        NativeExtensionsError::OtherError(format!(
+                            "External clip data {handle} not registered"
+                        ))
+                    })?;
+                Ok(Rc::new(Self {
+                    clip_data: Some(clip_data),
+                    _source_drop_notifier: None,
+                }))
+            }
+            _ => Err(NativeExtensionsError::UnsupportedOperation),
+        }
+    }
+
+    /// Out of process reading is only supported on Windows.
+    pub fn set_out_of_process_reading(_enabled: bool) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Formats are identified by name already; there is nothing to register.
+    pub fn register_clipboard_format(
+        _identifier: &str,
+        _platform_name: &str,
+    ) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+
+    /// Universal Clipboard is only available on Apple platforms.
+    pub fn is_remote_content(&self) -> NativeExtensionsResult<bool> {
+        Ok(false)
+    }
+
+    pub fn set_exclude_remote_content(_exclude: bool) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// ClipboardManager has no change counter; clipboard tokens are
+    /// validated by comparing formats.
+    pub fn clipboard_change_count() -> NativeExtensionsResult<Option<i64>> {
+        Ok(None)
+    }
+
+    /// Availability is determined by enumerating clipboard items.
+    pub fn clipboard_has_any_format(_formats: &[String]) -> NativeExtensionsResult<Option<bool>> {
+        Ok(None)
+    }
+
+    pub fn detect_entities(
+        text: &str,
+        kinds: &[EntityKind],
+    ) -> NativeExtensionsResult<Option<Vec<DetectedEntity>>> {
+        detect_entities_with_linkify(text, kinds)
+    }
+
+    pub fn set_format_conversion_enabled(&self, _conversion: FormatConversion, _enabled: bool) {}
+
+    pub fn get_format_conversions_for_item(
+        &self,
+        _item: i64,
+    ) -> NativeExtensionsResult<Vec<ItemFormatConversion>> {
+        Ok(Vec::new())
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
@@ -253,38 +402,389 @@ impl PlatformDataReader {
         Ok(false)
     }
 
-    pub async fn can_read_virtual_file_for_item(
+    /// Metafiles are Windows only.
+    pub async fn rasterize_metafile_for_item(
         &self,
         _item: i64,
-        _format: &str,
+        _width: Option<i64>,
+        _height: Option<i64>,
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Size of content URI items as reported by the content provider.
+    pub async fn get_item_metadata(&self, item: i64) -> NativeExtensionsResult<ItemMetadata> {
+        Ok(ItemMetadata {
+            size: self.get_openable_columns(item).await?.size,
+            ..Default::default()
+        })
+    }
+
+    /// Queries MediaMetadataRetriever for item content URI.
+    pub async fn get_media_metadata_for_item(
+        &self,
+        item: i64,
+    ) -> NativeExtensionsResult<Option<MediaMetadata>> {
+        let Some(clip_data) = &self.clip_data else {
+            return Ok(None);
+        };
+        let (future, completer) = FutureCompleter::new();
+        let (mut env, context) = Self::get_env_and_context()?;
+
+        let handle = Self::NEXT_HANDLE.with(|h| {
+            let res = h.get();
+            h.set(res + 1);
+            res
+        });
+        Self::PENDING.with(|m| m.borrow_mut().insert(handle, completer));
+
+        env.call_method(
+            CLIP_DATA_HELPER.get().unwrap().as_obj(),
+            "getMediaMetadata",
+            "(Landroid/content/ClipData;ILandroid/content/Context;I)V",
+            &[
+                clip_data.as_obj().into(),
+                (item as i32).into(),
+                context.into(),
+                (handle as i32).into(),
+            ],
+        )?;
+
+        let Value::List(values) = future.await? else {
+            return Ok(None);
+        };
+        // Same order as ClipDataHelper.mediaMetadataKeys
+        let value = |index: usize| match values.get(index) {
+            Some(Value::String(value)) => Some(value.as_str()),
+            _ => None,
+        };
+        let number = |index: usize| value(index).and_then(|v| v.parse::<i64>().ok());
+        // Absent means "no" once the retriever has accepted the source
+        let flag = |index: usize| Some(value(index) == Some("yes"));
+        let (mut width, mut height) = (number(3), number(4));
+        if matches!(number(5), Some(90) | Some(270)) {
+            std::mem::swap(&mut width, &mut height);
+        }
+        Ok(Some(MediaMetadata {
+            duration_millis: number(0),
+            has_video: flag(1),
+            has_audio: flag(2),
+            width,
+            height,
+            ..Default::default()
+        }))
+    }
+
+    /// Registers completer for data delivered through `ClipDataHelper.onData`.
+    fn new_pending_data() -> (i64, impl Future<Output = NativeExtensionsResult<Value>>) {
+        let (future, completer) = FutureCompleter::new();
+        let handle = Self::NEXT_HANDLE.with(|h| {
+            let res = h.get();
+            h.set(res + 1);
+            res
+        });
+        Self::PENDING.with(|m| m.borrow_mut().insert(handle, completer));
+        (handle, future)
+    }
+
+    fn has_content_uri(&self, item: i64) -> NativeExtensionsResult<bool> {
+        let Some(clip_data) = &self.clip_data else {
+            return Ok(false);
+        };
+        let (mut env, _) = Self::get_env_and_context()?;
+        Ok(env
+            .call_method(
+                CLIP_DATA_HELPER.get().unwrap().as_obj(),
+                "hasContentUri",
+                "(Landroid/content/ClipData;I)Z",
+                &[clip_data.as_obj().into(), (item as i32).into()],
+            )?
+            .z()?)
+    }
+
+    /// Content URI items (i.e. files dropped from the Files app) are exposed
+    /// as virtual files in all formats other than text and URI.
+    fn is_content_stream_format(&self, item: i64, format: &str) -> NativeExtensionsResult<bool> {
+        if matches!(
+            format,
+            MIME_TYPE_TEXT_PLAIN | MIME_TYPE_TEXT_HTML | MIME_TYPE_URI_LIST
+        ) {
+            return Ok(false);
+        }
+        self.has_content_uri(item)
+    }
+
+    async fn get_openable_columns(&self, item: i64) -> NativeExtensionsResult<OpenableColumns> {
+        let Some(clip_data) = &self.clip_data else {
+            return Ok(OpenableColumns::default());
+        };
+        let (handle, future) = Self::new_pending_data();
+        let (mut env, context) = Self::get_env_and_context()?;
+        env.call_method(
+            CLIP_DATA_HELPER.get().unwrap().as_obj(),
+            "getOpenableColumns",
+            "(Landroid/content/ClipData;ILandroid/content/Context;I)V",
+            &[
+                clip_data.as_obj().into(),
+                (item as i32).into(),
+                context.into(),
+                (handle as i32).into(),
+            ],
+        )?;
+        drop(env);
+        let Value::List(values) = future.await? else {
+            return Ok(OpenableColumns::default());
+        };
+        // Same order as ClipDataHelper.getOpenableColumns
+        let column = |index: usize| match values.get(index) {
+            Some(Value::String(value)) => Some(value.clone()),
+            _ => None,
+        };
+        Ok(OpenableColumns {
+            display_name: column(0),
+            size: column(1).and_then(|size| size.parse().ok()),
+        })
+    }
+
+    async fn open_content_stream(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<Option<ContentStreamReader>> {
+        let Some(clip_data) = &self.clip_data else {
+            return Ok(None);
+        };
+        if !self.is_content_stream_format(item, format)? {
+            return Ok(None);
+        }
+        let columns = self.get_openable_columns(item).await?;
+        let (future, completer) = FutureCompleter::new();
+        let handle = Self::NEXT_HANDLE.with(|h| {
+            let res = h.get();
+            h.set(res + 1);
+            res
+        });
+        Self::PENDING_STREAMS.with(|m| m.borrow_mut().insert(handle, completer));
+        let (mut env, context) = Self::get_env_and_context()?;
+        let format = env.new_string(format)?;
+        env.call_method(
+            CLIP_DATA_HELPER.get().unwrap().as_obj(),
+            "openStream",
+            "(Landroid/content/ClipData;ILjava/lang/String;Landroid/content/Context;I)V",
+            &[
+                clip_data.as_obj().into(),
+                (item as i32).into(),
+                (&format).into(),
+                context.into(),
+                (handle as i32).into(),
+            ],
+        )?;
+        drop(env);
+        Ok(future.await?.map(|stream| ContentStreamReader {
+            stream,
+            file_name: columns.display_name,
+            file_size: columns.size,
+            closed: Cell::new(false),
+        }))
+    }
+
+    pub async fn can_read_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
     ) -> NativeExtensionsResult<bool> {
-        Ok(false)
+        self.is_content_stream_format(item, format)
     }
 
     pub async fn can_copy_virtual_file_for_item(
         &self,
-        _item: i64,
-        _format: &str,
+        item: i64,
+        format: &str,
     ) -> NativeExtensionsResult<bool> {
-        Ok(false)
+        self.is_content_stream_format(item, format)
     }
 
     pub async fn create_virtual_file_reader_for_item(
         &self,
-        _item: i64,
-        _format: &str,
-        _progress: Arc<ReadProgress>,
+        item: i64,
+        format: &str,
+        _progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
-        Ok(None)
+        Ok(self
+            .open_content_stream(item, format)
+            .await?
+            .map(|stream| Rc::new(stream) as Rc<dyn VirtualFileReader>))
+    }
+
+    /// Content URI items are streamed through `ContentResolver`; other data
+    /// is only available in memory and caller falls back to
+    /// `get_data_for_item`.
+    pub async fn get_data_stream_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        self.create_virtual_file_reader_for_item(item, format, progress)
+            .await
     }
 
     pub async fn copy_virtual_file_for_item(
         &self,
-        _item: i64,
-        _format: &str,
-        _target_folder: PathBuf,
-        _progress: Arc<ReadProgress>,
+        item: i64,
+        format: &str,
+        target_folder: PathBuf,
+        progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<PathBuf> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+        let Some(stream) = self.open_content_stream(item, format).await? else {
+            return Err(NativeExtensionsError::VirtualFileUnsupported);
+        };
+        let file_name = match stream.file_name.clone() {
+            Some(name) => Some(name),
+            None => self.get_suggested_name_for_item(item).await?,
+        };
+        // Display name comes from the content provider, get_target_path keeps
+        // it from escaping the target folder.
+        let path = get_target_path(&target_folder, file_name.as_deref().unwrap_or_default());
+        let res = stream.copy_to(&path, &progress).await;
+        stream.close().ok_log();
+        match res {
+            Ok(()) => Ok(path),
+            Err(err) => {
+                fs::remove_file(&path).ok();
+                Err(err)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct OpenableColumns {
+    display_name: Option<String>,
+    size: Option<i64>,
+}
+
+/// `InputStream` of content URI, read on background thread in chunks.
+struct ContentStreamReader {
+    stream: GlobalRef,
+    file_name: Option<String>,
+    file_size: Option<i64>,
+    closed: Cell<bool>,
+}
+
+impl ContentStreamReader {
+    async fn copy_to(
+        &self,
+        path: &Path,
+        progress: &ReadProgressHandle,
+    ) -> NativeExtensionsResult<()> {
+        let mut file = File::create(path)?;
+        let mut written = 0;
+        loop {
+            if progress.cancellation_token().is_cancelled() {
+                return Err(NativeExtensionsError::Cancelled);
+            }
+            let chunk = self.read_next().await?;
+            if chunk.is_empty() {
+                break;
+            }
+            file.write_all(&chunk)?;
+            written += chunk.len() as i64;
+            progress.report_bytes(written, self.file_size);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl VirtualFileReader for ContentStreamReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        if self.closed.get() {
+            return Err(NativeExtensionsError::VirtualFileReceiveError(
+                "Stream already closed".into(),
+            ));
+        }
+        let (handle, future) = PlatformDataReader::new_pending_data();
+        let (mut env, _) = PlatformDataReader::get_env_and_context()?;
+        env.call_method(
+            CLIP_DATA_HELPER.get().unwrap().as_obj(),
+            "readStream",
+            "(Ljava/io/InputStream;I)V",
+            &[self.stream.as_obj().into(), (handle as i32).into()],
+        )?;
+        drop(env);
+        match future.await? {
+            Value::U8List(data) => Ok(data),
+            Value::String(error) => Err(NativeExtensionsError::VirtualFileReceiveError(error)),
+            _ => Err(NativeExtensionsError::VirtualFileReceiveError(
+                "Unexpected stream data".into(),
+            )),
+        }
+    }
+
+    fn file_size(&self) -> NativeExtensionsResult<Option<i64>> {
+        Ok(self.file_size)
+    }
+
+    fn file_name(&self) -> Option<String> {
+        self.file_name.clone()
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        if !self.closed.replace(true) {
+            let (mut env, _) = PlatformDataReader::get_env_and_context()?;
+            env.call_method(
+                CLIP_DATA_HELPER.get().unwrap().as_obj(),
+                "closeStream",
+                "(Ljava/io/InputStream;)V",
+                &[self.stream.as_obj().into()],
+            )?;
+        }
+        Ok(())
     }
 }
+
+impl Drop for ContentStreamReader {
+    fn drop(&mut self) {
+        self.close().ok_log();
+    }
+}
+
+/// `ClipData` objects registered by other plugins through
+/// `SuperNativeExtensionsPlugin.registerClipData`, waiting to be wrapped in
+/// a reader.
+static EXTERNAL_CLIP_DATA: Lazy<Mutex<HashMap<i64, GlobalRef>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_EXTERNAL_HANDLE: AtomicI64 = AtomicI64::new(1);
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "C" fn Java_com_superlist_super_1native_1extensions_SuperNativeExtensionsPlugin_registerClipData(
+    env: JNIEnv,
+    _class: jni::objects::JClass,
+    clip_data: JObject,
+) -> jlong {
+    if env
+        .is_same_object(&clip_data, JObject::null())
+        .unwrap_or(true)
+    {
+        return 0;
+    }
+    let Some(clip_data) = env.new_global_ref(clip_data).ok_log() else {
+        return 0;
+    };
+    let handle = NEXT_EXTERNAL_HANDLE.fetch_add(1, Ordering::Relaxed);
+    EXTERNAL_CLIP_DATA.lock().unwrap().insert(handle, clip_data);
+    handle
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "C" fn Java_com_superlist_super_1native_1extensions_SuperNativeExtensionsPlugin_releaseClipData(
+    _env: JNIEnv,
+    _class: jni::objects::JClass,
+    handle: jlong,
+) {
+    EXTERNAL_CLIP_DATA.lock().unwrap().remove(&handle);
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_int, c_void, CStr},
+    mem, ptr, slice,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex,
+    },
+};
+
+use jni::{objects::JObject, sys::jlong, JNIEnv};
+use once_cell::sync::Lazy;
+
+use crate::{
+    api_model::{ImageData, SharedTexture, SharedTextureKind},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    shared_texture::{image_data_from_pixels, PixelOrder},
+};
+
+pub const SHARED_TEXTURE_KIND: Option<SharedTextureKind> = Some(SharedTextureKind::HardwareBuffer);
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(non_camel_case_types)]
+struct AHardwareBuffer_Desc {
+    width: u32,
+    height: u32,
+    layers: u32,
+    format: u32,
+    usage: u64,
+    stride: u32,
+    rfu0: u32,
+    rfu1: u64,
+}
+
+const AHARDWAREBUFFER_FORMAT_R8G8B8A8_UNORM: u32 = 1;
+const AHARDWAREBUFFER_USAGE_CPU_READ_OFTEN: u64 = 3;
+
+const RTLD_LAZY: c_int = 1;
+
+extern "C" {
+    fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+}
+
+type DescribeFn = unsafe extern "C" fn(*const c_void, *mut AHardwareBuffer_Desc);
+type LockFn = unsafe extern "C" fn(*mut c_void, u64, i32, *const c_void, *mut *mut c_void) -> i32;
+type UnlockFn = unsafe extern "C" fn(*mut c_void, *mut i32) -> i32;
+type AcquireFn = unsafe extern "C" fn(*mut c_void);
+type ReleaseFn = unsafe extern "C" fn(*mut c_void);
+type FromHardwareBufferFn =
+    unsafe extern "C" fn(*mut jni::sys::JNIEnv, jni::sys::jobject) -> *mut c_void;
+
+/// `AHardwareBuffer` API is only available since API 26, which is above
+/// minimum supported version, so it is resolved at runtime.
+struct HardwareBufferApi {
+    describe: DescribeFn,
+    lock: LockFn,
+    unlock: UnlockFn,
+    acquire: AcquireFn,
+    release: ReleaseFn,
+    from_hardware_buffer: FromHardwareBufferFn,
+}
+
+static HARDWARE_BUFFER_API: Lazy<Option<HardwareBufferApi>> = Lazy::new(|| unsafe {
+    let lib = dlopen(c"libandroid.so".as_ptr(), RTLD_LAZY);
+    if lib.is_null() {
+        return None;
+    }
+    let symbol = |name: &CStr| {
+        let res = dlsym(lib, name.as_ptr());
+        (!res.is_null()).then_some(res)
+    };
+    Some(HardwareBufferApi {
+        describe: mem::transmute(symbol(c"AHardwareBuffer_describe")?),
+        lock: mem::transmute(symbol(c"AHardwareBuffer_lock")?),
+        unlock: mem::transmute(symbol(c"AHardwareBuffer_unlock")?),
+        acquire: mem::transmute(symbol(c"AHardwareBuffer_acquire")?),
+        release: mem::transmute(symbol(c"AHardwareBuffer_release")?),
+        from_hardware_buffer: mem::transmute(symbol(c"AHardwareBuffer_fromHardwareBuffer")?),
+    })
+});
+
+/// Reference to `AHardwareBuffer` acquired on registration.
+struct BufferRef(*mut c_void);
+
+// AHardwareBuffer is reference counted and can be used from any thread.
+unsafe impl Send for BufferRef {}
+
+impl Drop for BufferRef {
+    fn drop(&mut self) {
+        if let Some(api) = HARDWARE_BUFFER_API.as_ref() {
+            unsafe { (api.release)(self.0) };
+        }
+    }
+}
+
+/// Buffers registered by native code, keyed by the handle Dart passes in
+/// [`SharedTexture::handle`]. Dart never sees the buffer address itself.
+static BUFFERS: Lazy<Mutex<HashMap<i64, BufferRef>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_BUFFER_HANDLE: AtomicI64 = AtomicI64::new(1);
+
+fn register_buffer(buffer: *mut c_void) -> i64 {
+    let Some(api) = HARDWARE_BUFFER_API.as_ref() else {
+        return 0;
+    };
+    if buffer.is_null() {
+        return 0;
+    }
+    unsafe { (api.acquire)(buffer) };
+    let handle = NEXT_BUFFER_HANDLE.fetch_add(1, Ordering::Relaxed);
+    BUFFERS.lock().unwrap().insert(handle, BufferRef(buffer));
+    handle
+}
+
+/// Registers `AHardwareBuffer` to be used as drag image. The buffer is
+/// acquired until the returned handle is used in a drag request or released
+/// with [`super_native_extensions_release_hardware_buffer`]. Returns 0 for
+/// null buffer or if the API is not available.
+///
+/// # Safety
+///
+/// `buffer` must be null or a valid `AHardwareBuffer` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn super_native_extensions_register_hardware_buffer(
+    buffer: *mut c_void,
+) -> i64 {
+    register_buffer(buffer)
+}
+
+#[no_mangle]
+pub extern "C" fn super_native_extensions_release_hardware_buffer(handle: i64) {
+    BUFFERS.lock().unwrap().remove(&handle);
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "C" fn Java_com_superlist_super_1native_1extensions_SuperNativeExtensionsPlugin_registerHardwareBuffer(
+    env: JNIEnv,
+    _class: jni::objects::JClass,
+    buffer: JObject,
+) -> jlong {
+    let Some(api) = HARDWARE_BUFFER_API.as_ref() else {
+        return 0;
+    };
+    if env.is_same_object(&buffer, JObject::null()).unwrap_or(true) {
+        return 0;
+    }
+    let buffer = unsafe { (api.from_hardware_buffer)(env.get_raw(), buffer.as_raw()) };
+    register_buffer(buffer)
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "C" fn Java_com_superlist_super_1native_1extensions_SuperNativeExtensionsPlugin_releaseHardwareBuffer(
+    _env: JNIEnv,
+    _class: jni::objects::JClass,
+    handle: jlong,
+) {
+    super_native_extensions_release_hardware_buffer(handle);
+}
+
+/// Copies content of registered `AHardwareBuffer`. The handle is redeemed,
+/// buffer is released once copied.
+pub fn image_data_from_shared_texture(
+    texture: &SharedTexture,
+) -> NativeExtensionsResult<ImageData> {
+    if texture.kind != SharedTextureKind::HardwareBuffer {
+        return Err(NativeExtensionsError::UnsupportedOperation);
+    }
+    let api = HARDWARE_BUFFER_API
+        .as_ref()
+        .ok_or(NativeExtensionsError::UnsupportedOperation)?;
+    // Released when going out of scope
+    let buffer_ref = BUFFERS
+        .lock()
+        .unwrap()
+        .remove(&texture.handle)
+        .ok_or_else(|| {
+            NativeExtensionsError::OtherError(format!(
+                "Hardware buffer {} not registered",
+                texture.handle
+            ))
+        })?;
+    let buffer = buffer_ref.0;
+    unsafe {
+        let mut desc = AHardwareBuffer_Desc::default();
+        (api.describe)(buffer, &mut desc);
+        if desc.format != AHARDWAREBUFFER_FORMAT_R8G8B8A8_UNORM {
+            return Err(NativeExtensionsError::InvalidData);
+        }
+        let mut address = ptr::null_mut();
+        let res = (api.lock)(
+            buffer,
+            AHARDWAREBUFFER_USAGE_CPU_READ_OFTEN,
+            -1,
+            ptr::null(),
+            &mut address,
+        );
+        if res != 0 {
+            return Err(NativeExtensionsError::OtherError(format!(
+                "AHardwareBuffer_lock failed: {res}"
+            )));
+        }
+        // Stride is in pixels
+        let bytes_per_row = desc.stride as usize * 4;
+        let pixels =
+            slice::from_raw_parts(address as *const u8, bytes_per_row * desc.height as usize);
+        let image = image_data_from_pixels(
+            pixels,
+            desc.width as i32,
+            desc.height as i32,
+            bytes_per_row,
+            PixelOrder::Rgba,
+            texture.device_pixel_ratio,
+        );
+        (api.unlock)(buffer, ptr::null_mut());
+        Ok(image)
+    }
+}
@@ -0,0 +1,38 @@
+use std::rc::Weak;
+
+use crate::{
+    api_model::ImageData,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    tray_icon_manager::{TrayIconCreateRequest, TrayIconHandle, TrayIconManagerDelegate},
+};
+
+pub struct PlatformTrayIconManager {}
+
+impl PlatformTrayIconManager {
+    pub fn new(_delegate: Weak<dyn TrayIconManagerDelegate>) -> Self {
+        Self {}
+    }
+
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformTrayIconManager>) {}
+
+    pub async fn create_tray_icon(
+        &self,
+        _handle: TrayIconHandle,
+        _request: TrayIconCreateRequest,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn update_tray_icon(
+        &self,
+        _handle: TrayIconHandle,
+        _image: Option<ImageData>,
+        _tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn destroy_tray_icon(&self, _handle: TrayIconHandle) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+}
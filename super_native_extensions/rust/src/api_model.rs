@@ -2,7 +2,7 @@ use std::rc::Rc;
 
 use irondash_message_channel::{IntoValue, TryFromValue, Value};
 
-use crate::platform_impl::platform::PlatformMenu;
+use crate::{platform_impl::platform::PlatformMenu, util::sanitize_path_component};
 
 #[derive(Clone, Debug, Default, PartialEq, TryFromValue, IntoValue)]
 #[irondash(rename_all = "camelCase")]
@@ -84,6 +84,19 @@ pub struct ImageData {
 }
 
 impl ImageData {
+    /// Whether dimensions and row stride describe pixels within `data`.
+    pub fn is_valid(&self) -> bool {
+        let (width, height, bytes_per_row) = (
+            self.width as i64,
+            self.height as i64,
+            self.bytes_per_row as i64,
+        );
+        if width < 0 || height < 0 || bytes_per_row < width * 4 {
+            return false;
+        }
+        height == 0 || self.data.len() as i64 >= bytes_per_row * (height - 1) + width * 4
+    }
+
     pub fn point_width(&self) -> f64 {
         self.width as f64 / self.device_pixel_ratio.unwrap_or(1.0)
     }
@@ -100,6 +113,12 @@ impl ImageData {
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct DataProviderValueId(i64);
 
+impl From<i64> for DataProviderValueId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct DataProviderId(i64);
 
@@ -125,6 +144,13 @@ pub enum DataRepresentation {
         format: String,
         storage_suggestion: Option<VirtualFileStorage>,
     },
+    /// Folder with known structure whose files are generated lazily. The
+    /// folder itself is named after the provider suggested name.
+    #[irondash(rename_all = "camelCase")]
+    VirtualFolder {
+        format: String,
+        entries: Vec<VirtualFolderEntry>,
+    },
 }
 
 impl DataRepresentation {
@@ -135,6 +161,9 @@ impl DataRepresentation {
                 id: _,
                 format: _,
                 storage_suggestion: _,
+            } | Self::VirtualFolder {
+                format: _,
+                entries: _,
             }
         )
     }
@@ -147,15 +176,69 @@ impl DataRepresentation {
                 format,
                 storage_suggestion: _,
             } => format,
+            DataRepresentation::VirtualFolder { format, entries: _ } => format,
         }
     }
 }
 
+#[derive(Debug, TryFromValue, IntoValue, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub struct VirtualFolderEntry {
+    /// Path relative to the folder, components separated by `/`. Parent
+    /// directories are created implicitly.
+    pub path: String,
+    /// Virtual file providing the contents; `None` for directories.
+    pub id: Option<DataProviderValueId>,
+    pub storage_suggestion: Option<VirtualFileStorage>,
+}
+
+impl VirtualFolderEntry {
+    pub fn is_directory(&self) -> bool {
+        self.id.is_none()
+    }
+
+    /// Sanitized path components with empty, `.` and `..` components
+    /// removed so that entries can not escape the folder.
+    pub fn components(&self) -> Vec<String> {
+        self.path
+            .split(['/', '\\'])
+            .filter_map(sanitize_path_component)
+            .collect()
+    }
+}
+
 #[derive(Debug, TryFromValue, IntoValue, Clone, PartialEq, Eq)]
 #[irondash(rename_all = "camelCase")]
 pub struct DataProvider {
     pub representations: Vec<DataRepresentation>,
     pub suggested_name: Option<String>,
+    /// Fidelity levels declared for alternative representations of the same
+    /// content. When present representations are reordered so that platforms
+    /// offer the best one first.
+    pub fidelity: Option<Vec<RepresentationFidelity>>,
+    /// When written to the clipboard, the clipboard is cleared after this
+    /// many milliseconds unless another application has replaced it.
+    pub expires_after_ms: Option<i64>,
+    /// URL of the document the content comes from. Written as `SourceURL` of
+    /// CF_HTML on Windows.
+    pub source_url: Option<String>,
+    /// Adds RTF converted from the HTML representation, for applications
+    /// that don't read HTML.
+    pub synthesize_rtf: Option<bool>,
+    /// Isolate that owns the provider (writes it to clipboard or drags it)
+    /// when it is registered by a worker isolate. The registering isolate
+    /// then serves lazy data and virtual files, so that deferred requests
+    /// don't wait for a busy UI isolate. Defaults to the registering isolate.
+    pub owner_isolate_id: Option<i64>,
+}
+
+/// Fidelity of a representation; higher level means richer representation
+/// (i.e. full-resolution PNG over medium JPEG over thumbnail).
+#[derive(Debug, TryFromValue, IntoValue, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub struct RepresentationFidelity {
+    pub format: String,
+    pub level: i64,
 }
 
 //
@@ -169,11 +252,130 @@ pub enum VirtualFileStorage {
 
 //
 
+#[derive(Debug, IntoValue, Copy, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum RemoteSessionKind {
+    Rdp,
+    Citrix,
+}
+
+#[derive(Debug, IntoValue)]
+#[irondash(rename_all = "camelCase")]
+pub struct RemoteSessionInfo {
+    /// Remote session application runs in, None for local session.
+    pub kind: Option<RemoteSessionKind>,
+    /// Whether clipboard behavior is adjusted for remote session.
+    pub tuning_enabled: bool,
+}
+
+/// Selection clipboard readers and writers operate on.
+#[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Eq, Default)]
+#[irondash(rename_all = "camelCase")]
+pub enum ClipboardType {
+    #[default]
+    Clipboard,
+    /// X11 PRIMARY selection (middle-click paste), Linux only.
+    Primary,
+}
+
+/// Clipboard readers and writers operate on.
+#[derive(Debug, TryFromValue, IntoValue, Clone, PartialEq, Eq, Default)]
+#[irondash(rename_all = "camelCase")]
+pub struct ClipboardTarget {
+    /// Regular clipboard when not specified.
+    pub clipboard_type: Option<ClipboardType>,
+    /// Named pasteboard (macOS only), such as `Apple CFPasteboard find` or
+    /// `Apple CFPasteboard font`. Takes precedence over `clipboard_type`.
+    pub pasteboard_name: Option<String>,
+}
+
+/// Mechanism used to provide tray icons or global hot keys.
+#[derive(Debug, IntoValue, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum DesktopFeatureBackend {
+    /// Platform API (macOS, Windows).
+    Native,
+    /// `org.kde.StatusNotifierItem` over D-Bus.
+    StatusNotifier,
+    /// `GtkStatusIcon` embedded in X11 system tray.
+    XEmbed,
+    /// Grabbing keys on X11 root window.
+    X11Grab,
+}
+
+#[derive(Debug, IntoValue, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct DesktopFeatureCapability {
+    pub supported: bool,
+    /// Backend used when supported.
+    pub backend: Option<DesktopFeatureBackend>,
+    /// Explains why feature is not supported.
+    pub reason: Option<String>,
+}
+
+impl DesktopFeatureCapability {
+    pub fn supported(backend: DesktopFeatureBackend) -> Self {
+        Self {
+            supported: true,
+            backend: Some(backend),
+            reason: None,
+        }
+    }
+
+    pub fn unsupported(reason: impl Into<String>) -> Self {
+        Self {
+            supported: false,
+            backend: None,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+#[derive(Debug, IntoValue, Default)]
+#[irondash(rename_all = "camelCase")]
+pub struct ClipboardPolicy {
+    /// Application runs in a work (managed) profile.
+    pub managed_profile: bool,
+    /// Application runs as other than the primary device user.
+    pub secondary_user: bool,
+    /// Clipboard content can not be shared with other profiles.
+    pub cross_profile_copy_paste_disallowed: bool,
+}
+
+//
+
+/// Kind of GPU texture handle the platform can import drag images from.
+#[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum SharedTextureKind {
+    /// Global IOSurface ID (macOS, iOS).
+    IoSurface,
+    /// DXGI shared resource handle (Windows).
+    DxgiSharedHandle,
+    /// `AHardwareBuffer` registered through
+    /// `SuperNativeExtensionsPlugin.registerHardwareBuffer` (Android).
+    HardwareBuffer,
+}
+
+/// Texture already rendered by the engine that is shared with native side,
+/// so that pixels don't need to be read back in Dart and sent over the
+/// channel. Texture must stay alive until `startDrag` returns.
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct SharedTexture {
+    pub kind: SharedTextureKind,
+    pub handle: i64,
+    pub device_pixel_ratio: Option<f64>,
+}
+
 #[derive(TryFromValue, Debug)]
 #[irondash(rename_all = "camelCase")]
 pub struct TargettedImage {
+    /// Ignored when `shared_texture` is set; pixel data is then filled in
+    /// from the texture before the image reaches platform code.
     pub image_data: ImageData,
     pub rect: Rect,
+    pub shared_texture: Option<SharedTexture>,
 }
 
 #[derive(TryFromValue, Debug)]
@@ -184,6 +386,26 @@ pub struct DragItem {
     pub lift_image: Option<TargettedImage>,
     pub image: TargettedImage,
     pub local_data: Value,
+    /// Restricts operations for this item to a subset of session operations.
+    pub allowed_operations: Option<Vec<DropOperation>>,
+}
+
+impl DragItem {
+    /// Operations allowed for the item within session that allows
+    /// `session_operations`.
+    pub fn effective_allowed_operations(
+        &self,
+        session_operations: &[DropOperation],
+    ) -> Vec<DropOperation> {
+        match &self.allowed_operations {
+            Some(allowed) => session_operations
+                .iter()
+                .filter(|o| allowed.contains(o))
+                .copied()
+                .collect(),
+            None => session_operations.to_vec(),
+        }
+    }
 }
 
 #[derive(TryFromValue, Debug)]
@@ -191,14 +413,35 @@ pub struct DragItem {
 pub struct DragConfiguration {
     pub items: Vec<DragItem>,
     pub allowed_operations: Vec<DropOperation>,
+    /// When false the platform snap-back animation is suppressed and
+    /// `dragSessionDidCancel` is sent with final pointer location instead,
+    /// so that the application can run its own cancel animation.
     pub animates_to_starting_position_on_cancel_or_fail: bool,
     pub prefers_full_size_previews: bool,
+    /// Session payload kept by [`crate::drag_manager::DragManager`] and
+    /// handed to drop targets of this process. Unlike item data it is never
+    /// written to the platform pasteboard.
+    pub session_local_data: Option<Value>,
 }
 
 impl DragConfiguration {
     pub fn get_local_data(&self) -> Vec<Value> {
         self.items.iter().map(|i| i.local_data.clone()).collect()
     }
+
+    /// Platforms only support operations for the whole session, so session
+    /// allows operations that at least one item allows.
+    pub fn restrict_to_item_operations(&mut self) {
+        let items: Vec<_> = self
+            .items
+            .iter()
+            .map(|i| i.effective_allowed_operations(&self.allowed_operations))
+            .collect();
+        if !items.is_empty() {
+            self.allowed_operations
+                .retain(|o| items.iter().any(|item| item.contains(o)));
+        }
+    }
 }
 
 #[derive(TryFromValue)]
@@ -207,6 +450,58 @@ pub struct DragRequest {
     pub configuration: DragConfiguration,
     pub combined_drag_image: Option<TargettedImage>,
     pub position: Point,
+    /// Engine handle of the view to start drag from. Primary view of the
+    /// isolate if not set.
+    pub engine_handle: Option<i64>,
+    /// Kind of pointer that started the drag. Mouse if not set.
+    pub device_kind: Option<PointerDeviceKind>,
+}
+
+#[derive(Debug, TryFromValue, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum PointerDeviceKind {
+    Mouse,
+    Touch,
+    Stylus,
+    InvertedStylus,
+    Trackpad,
+}
+
+impl PointerDeviceKind {
+    pub fn is_touch_or_stylus(&self) -> bool {
+        matches!(
+            self,
+            PointerDeviceKind::Touch
+                | PointerDeviceKind::Stylus
+                | PointerDeviceKind::InvertedStylus
+        )
+    }
+}
+
+/// Thresholds of press-and-hold gesture starting drag from touch input. The
+/// gesture itself is recognized in Dart, which reads the thresholds through
+/// `getTouchDragSettings`.
+#[derive(Debug, TryFromValue, IntoValue, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct TouchDragSettings {
+    /// How long the contact must be held before the drag starts.
+    pub press_and_hold_duration_ms: i64,
+    /// Maximum pointer movement during press-and-hold, in logical pixels.
+    pub slop: f64,
+    /// Disables system press-and-hold feedback (the right click ring on
+    /// Windows) in registered views, which would otherwise compete with the
+    /// gesture. Views are left untouched until settings are set explicitly.
+    pub suppress_system_press_and_hold: bool,
+}
+
+impl Default for TouchDragSettings {
+    fn default() -> Self {
+        Self {
+            press_and_hold_duration_ms: 300,
+            slop: 18.0,
+            suppress_system_press_and_hold: true,
+        }
+    }
 }
 
 #[derive(Debug, TryFromValue, IntoValue, Copy, Clone, PartialEq, Eq)]
@@ -220,12 +515,102 @@ pub enum DropOperation {
     Link,          // macOS, Windows, Linux
 }
 
+impl DropOperation {
+    /// Operation performed on an item that only allows `allowed` when the
+    /// session ended with `self`. Items that can not be moved or linked are
+    /// treated as copied.
+    pub fn restrict_to(self, allowed: &[DropOperation]) -> DropOperation {
+        match self {
+            DropOperation::None | DropOperation::UserCancelled | DropOperation::Forbidden => self,
+            operation if allowed.contains(&operation) => operation,
+            _ if allowed.contains(&DropOperation::Copy) => DropOperation::Copy,
+            _ => DropOperation::None,
+        }
+    }
+}
+
+/// Modifier keys held down. `meta` is Command on macOS and Windows key on
+/// Windows.
+#[derive(Debug, IntoValue, Clone, Copy, Default, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub struct KeyModifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+/// Change of desktop environment during outgoing drag. Reported on macOS
+/// only. User navigating Spaces or hovering Mission Control can take much
+/// longer than usual before dropping.
+#[derive(Debug, IntoValue, Copy, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum DragEnvironmentChange {
+    /// Pointer moved to another display.
+    ScreenChanged,
+    /// Active Space changed while dragging.
+    SpaceChanged,
+    MissionControlEntered,
+    MissionControlExited,
+    StageManagerEntered,
+    StageManagerExited,
+}
+
+/// Reason for outgoing drag session ending without drop.
+#[derive(Debug, IntoValue, Copy, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum DragCancelReason {
+    Escape,        // user pressed escape
+    NoTarget,      // released over location that doesn't accept the drop
+    TargetRefused, // drop was attempted but rejected by target
+}
+
+#[derive(Debug, IntoValue, Copy, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum DataTransferState {
+    /// Drop target started reading data after the drag session ended.
+    Started,
+    InProgress,
+    Completed,
+    Failed,
+}
+
+/// Progress of data transfer that continues after drop completed, such as
+/// Explorer copying virtual files in the background (Windows only).
+#[derive(Debug, IntoValue, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct DataTransferProgress {
+    pub state: DataTransferState,
+    /// Virtual file being written, only set with `inProgress`.
+    pub file_index: Option<i64>,
+    pub fraction: Option<f64>,
+    pub bytes_written: Option<i64>,
+    pub total_bytes: Option<i64>,
+}
+
+#[derive(TryFromValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum MenuPreviewCommitStyle {
+    /// Menu and preview are dismissed.
+    Dismiss,
+    /// Preview expands into the destination, which the app is expected to
+    /// present in `onPreviewAction`.
+    Pop,
+}
+
 #[derive(TryFromValue, Debug)]
 #[irondash(rename_all = "camelCase")]
 pub struct MenuConfiguration {
     pub configuration_id: i64,
     pub preview_image: Option<ImageData>,
     pub preview_size: Option<Size>,
+    /// Region of the Flutter view (in logical coordinates) to show as preview.
+    /// The region is snapshotted natively so that no image needs to be sent
+    /// over the channel. Used when neither `preview_image` nor `preview_size`
+    /// is set (iOS only).
+    pub preview_rect: Option<Rect>,
+    /// How the preview transitions when tapped (iOS only).
+    pub preview_commit_style: Option<MenuPreviewCommitStyle>,
     pub lift_image: TargettedImage,
     pub menu_handle: i64,
     #[irondash(skip)]
@@ -270,7 +655,7 @@ pub struct Activator {
     pub control: bool,
 }
 
-#[derive(TryFromValue, Debug, PartialEq, Eq)]
+#[derive(TryFromValue, Debug, Clone, Copy, PartialEq, Eq)]
 #[irondash(rename_all = "camelCase")]
 pub enum MenuActionState {
     None,
@@ -309,6 +694,8 @@ pub struct MenuAction {
     pub subitle: Option<String>,
     pub attributes: MenuActionAttributes,
     pub state: MenuActionState,
+    /// Activating radio item turns off other items of the same group.
+    pub radio_group: Option<String>,
     pub activator: Option<Activator>,
 }
 
@@ -318,6 +705,17 @@ pub struct DeferredMenuElement {
     pub unique_id: i64,
 }
 
+/// Submenu with children requested from Dart (`getDeferredMenuItems`) when
+/// it is about to open.
+#[derive(TryFromValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct DeferredMenu {
+    pub unique_id: i64,
+    pub identifier: Option<String>,
+    pub title: Option<String>,
+    pub image: Option<MenuImage>,
+}
+
 #[derive(TryFromValue, Debug)]
 #[irondash(rename_all = "camelCase")]
 pub struct MenuSeparator {
@@ -330,5 +728,6 @@ pub enum MenuElement {
     Action(MenuAction),
     Menu(Menu),
     Deferred(DeferredMenuElement),
+    DeferredMenu(DeferredMenu),
     Separator(MenuSeparator),
 }
@@ -0,0 +1,390 @@
+//! Enumeration and extraction of zip archive entries.
+//!
+//! Dropped or pasted archives can be listed natively and individual entries
+//! extracted, without the application bundling its own archive code. Only
+//! the central directory is read when listing; entries are decompressed one
+//! at a time on a worker thread. Supported compression methods are stored
+//! and deflate; zip64 and encrypted archives are not supported.
+
+use std::{
+    cell::RefCell,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use flate2::{read::DeflateDecoder, CrcReader};
+use irondash_message_channel::IntoValue;
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop,
+};
+
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    util::get_target_path,
+};
+
+#[derive(IntoValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    pub index: i64,
+    /// Path of the entry inside archive, using `/` as separator.
+    pub name: String,
+    pub size: i64,
+    pub compressed_size: i64,
+    pub is_directory: bool,
+}
+
+struct CentralEntry {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc32: u32,
+    compressed_size: u64,
+    size: u64,
+    local_header_offset: u64,
+}
+
+/// Formats that are known to contain zip archives.
+pub fn is_archive_format(format: &str) -> bool {
+    matches!(
+        format.to_ascii_lowercase().as_str(),
+        "application/zip"
+            | "application/x-zip-compressed"
+            | "public.zip-archive"
+            | "com.pkware.zip-archive"
+    )
+}
+
+fn invalid(message: &str) -> NativeExtensionsError {
+    NativeExtensionsError::OtherError(format!("invalid zip archive: {message}"))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+pub struct ZipArchive<R: Read + Seek> {
+    reader: RefCell<R>,
+    /// Length of the archive; all offsets and sizes are validated against it.
+    len: u64,
+    entries: Vec<CentralEntry>,
+}
+
+impl ZipArchive<File> {
+    pub fn open(path: &Path) -> NativeExtensionsResult<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> ZipArchive<R> {
+    const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+    const CENTRAL_FILE_HEADER: u32 = 0x02014b50;
+    const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+
+    /// Largest entry [`Self::read_entry`] decompresses into memory.
+    pub const MAX_IN_MEMORY_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+
+    pub fn new(mut reader: R) -> NativeExtensionsResult<Self> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        // End of central directory record is 22 bytes followed by comment of
+        // up to 64k.
+        let tail_len = len.min(22 + 0xFFFF);
+        reader.seek(SeekFrom::Start(len - tail_len))?;
+        let mut tail = vec![0u8; tail_len as usize];
+        reader.read_exact(&mut tail)?;
+        let eocd_offset = (0..tail.len().saturating_sub(21))
+            .rev()
+            .find(|&i| u32_at(&tail, i) == Self::END_OF_CENTRAL_DIRECTORY)
+            .ok_or_else(|| invalid("end of central directory not found"))?;
+        let eocd = &tail[eocd_offset..];
+        let entry_count = u16_at(eocd, 10);
+        let directory_size = u32_at(eocd, 12);
+        let directory_offset = u32_at(eocd, 16);
+        if entry_count == 0xFFFF || directory_offset == 0xFFFFFFFF {
+            return Err(invalid("zip64 is not supported"));
+        }
+        // Central directory precedes the end of central directory record.
+        let eocd_position = len - tail_len + eocd_offset as u64;
+        if directory_offset as u64 + directory_size as u64 > eocd_position {
+            return Err(invalid("central directory extends past end of archive"));
+        }
+        reader.seek(SeekFrom::Start(directory_offset as u64))?;
+        let mut directory = vec![0u8; directory_size as usize];
+        reader.read_exact(&mut directory)?;
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        let mut offset = 0;
+        for _ in 0..entry_count {
+            if offset + 46 > directory.len()
+                || u32_at(&directory, offset) != Self::CENTRAL_FILE_HEADER
+            {
+                return Err(invalid("malformed central directory"));
+            }
+            let header = &directory[offset..];
+            let name_len = u16_at(header, 28) as usize;
+            let extra_len = u16_at(header, 30) as usize;
+            let comment_len = u16_at(header, 32) as usize;
+            if 46 + name_len > header.len() {
+                return Err(invalid("malformed central directory"));
+            }
+            // Names not flagged as UTF-8 are CP437; ASCII subset is the same.
+            let name = String::from_utf8_lossy(&header[46..46 + name_len]).into_owned();
+            let entry = CentralEntry {
+                name,
+                flags: u16_at(header, 8),
+                method: u16_at(header, 10),
+                crc32: u32_at(header, 16),
+                compressed_size: u32_at(header, 20) as u64,
+                size: u32_at(header, 24) as u64,
+                local_header_offset: u32_at(header, 42) as u64,
+            };
+            if entry.local_header_offset + 30 + entry.compressed_size > directory_offset as u64 {
+                return Err(invalid("entry extends past end of archive"));
+            }
+            entries.push(entry);
+            offset += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Self {
+            reader: RefCell::new(reader),
+            len,
+            entries,
+        })
+    }
+
+    pub fn entries(&self) -> Vec<ArchiveEntry> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, e)| ArchiveEntry {
+                index: index as i64,
+                name: e.name.clone(),
+                size: e.size as i64,
+                compressed_size: e.compressed_size as i64,
+                is_directory: e.name.ends_with('/'),
+            })
+            .collect()
+    }
+
+    fn entry(&self, index: i64) -> NativeExtensionsResult<&CentralEntry> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.entries.get(index))
+            .ok_or_else(|| NativeExtensionsError::OtherError(format!("no archive entry {index}")))
+    }
+
+    /// Last path component of the entry, safe to use as file name.
+    pub fn entry_file_name(&self, index: i64) -> NativeExtensionsResult<String> {
+        let entry = self.entry(index)?;
+        let name = entry
+            .name
+            .rsplit(['/', '\\'])
+            .find(|c| !c.is_empty() && *c != "." && *c != "..")
+            .ok_or_else(|| invalid("entry has no file name"))?;
+        Ok(name.to_owned())
+    }
+
+    /// Decompresses entry into `out`, verifying its size and checksum.
+    fn copy_entry<W: Write>(&self, index: i64, out: &mut W) -> NativeExtensionsResult<()> {
+        let entry = self.entry(index)?;
+        if entry.flags & 0x1 != 0 {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(entry.local_header_offset))?;
+        let mut header = [0u8; 30];
+        reader.read_exact(&mut header)?;
+        if u32_at(&header, 0) != Self::LOCAL_FILE_HEADER {
+            return Err(invalid("malformed local file header"));
+        }
+        // Local header may have different extra field than central directory.
+        let data_start = entry.local_header_offset
+            + 30
+            + u16_at(&header, 26) as u64
+            + u16_at(&header, 28) as u64;
+        if data_start + entry.compressed_size > self.len {
+            return Err(invalid("entry extends past end of archive"));
+        }
+        reader.seek(SeekFrom::Start(data_start))?;
+        let compressed = (&mut *reader).take(entry.compressed_size);
+        let data: Box<dyn Read + '_> = match entry.method {
+            0 => Box::new(compressed),
+            8 => Box::new(DeflateDecoder::new(compressed)),
+            _ => return Err(NativeExtensionsError::UnsupportedOperation),
+        };
+        // One byte past declared size is enough to detect larger entries.
+        let mut data = CrcReader::new(data.take(entry.size + 1));
+        let written = io::copy(&mut data, out)?;
+        if written != entry.size || data.crc().sum() != entry.crc32 {
+            return Err(invalid("entry checksum mismatch"));
+        }
+        Ok(())
+    }
+
+    /// Decompresses entry into memory. Fails for entries larger than
+    /// [`Self::MAX_IN_MEMORY_ENTRY_SIZE`].
+    pub fn read_entry(&self, index: i64) -> NativeExtensionsResult<Vec<u8>> {
+        let size = self.entry(index)?.size;
+        if size > Self::MAX_IN_MEMORY_ENTRY_SIZE {
+            return Err(NativeExtensionsError::OtherError(format!(
+                "archive entry {index} is too large to be read into memory"
+            )));
+        }
+        let mut data = Vec::with_capacity(size as usize);
+        self.copy_entry(index, &mut data)?;
+        Ok(data)
+    }
+
+    /// Extracts entry into `target_folder`. Directory structure inside the
+    /// archive is not recreated, which also keeps entries from escaping the
+    /// folder.
+    pub fn extract_entry(
+        &self,
+        index: i64,
+        target_folder: &Path,
+    ) -> NativeExtensionsResult<PathBuf> {
+        let name = self.entry_file_name(index)?;
+        let path = get_target_path(target_folder, &name);
+        let res = File::create(&path)
+            .map_err(NativeExtensionsError::from)
+            .and_then(|file| {
+                let mut file = BufWriter::new(file);
+                self.copy_entry(index, &mut file)?;
+                file.flush()?;
+                Ok(())
+            });
+        match res {
+            Ok(()) => Ok(path),
+            Err(err) => {
+                fs::remove_file(&path).ok_log();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Opens archive at `path` and runs `f` with it on a worker thread, so that
+/// reading and decompressing entries doesn't block the run loop.
+pub async fn with_archive<T, F>(path: PathBuf, f: F) -> NativeExtensionsResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&ZipArchive<File>) -> NativeExtensionsResult<T> + Send + 'static,
+{
+    let (future, completer) = FutureCompleter::new();
+    let mut completer = Capsule::new(completer);
+    let sender = RunLoop::current().new_sender();
+    thread::spawn(move || {
+        let res = ZipArchive::open(&path).and_then(|archive| f(&archive));
+        sender.send(move || {
+            let completer = completer.take().unwrap();
+            completer.complete(res);
+        });
+    });
+    future.await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::ZipArchive;
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+        crc.sum()
+    }
+
+    // Raw deflate stream of "deflated " repeated 20 times.
+    const DEFLATED: [u8; 14] = [
+        0x4b, 0x49, 0x4d, 0xcb, 0x49, 0x2c, 0x49, 0x4d, 0x51, 0x48, 0x19, 0x3a, 0x0c, 0x00,
+    ];
+
+    /// Builds archive from (name, method, compressed data, uncompressed data).
+    fn build_zip(entries: &[(&str, u16, &[u8], &[u8])]) -> Vec<u8> {
+        let mut res = Vec::new();
+        let mut directory = Vec::new();
+        for (name, method, compressed, data) in entries {
+            let mut common = Vec::new();
+            common.extend_from_slice(&0u16.to_le_bytes()); // flags
+            common.extend_from_slice(&method.to_le_bytes());
+            common.extend_from_slice(&0u32.to_le_bytes()); // time & date
+            common.extend_from_slice(&crc32(data).to_le_bytes());
+            common.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            common.extend_from_slice(&0u16.to_le_bytes()); // extra length
+
+            directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            directory.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            directory.extend_from_slice(&common);
+            directory.extend_from_slice(&[0u8; 6]); // comment, disk, internal attributes
+            directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+            directory.extend_from_slice(&(res.len() as u32).to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+
+            res.extend_from_slice(&0x04034b50u32.to_le_bytes());
+            res.extend_from_slice(&20u16.to_le_bytes());
+            res.extend_from_slice(&common);
+            res.extend_from_slice(name.as_bytes());
+            res.extend_from_slice(compressed);
+        }
+        let directory_offset = res.len() as u32;
+        res.extend_from_slice(&directory);
+        res.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        res.extend_from_slice(&[0u8; 4]); // disk numbers
+        res.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        res.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        res.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        res.extend_from_slice(&directory_offset.to_le_bytes());
+        res.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        res
+    }
+
+    #[test]
+    fn test_invalid_sizes() {
+        let deflated = "deflated ".repeat(20);
+        // Declared size smaller than actual contents.
+        let zip = build_zip(&[("deflated.txt", 8, &DEFLATED, &deflated.as_bytes()[..100])]);
+        let archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+        assert!(archive.read_entry(0).is_err());
+
+        // Compressed size past end of archive.
+        let mut zip = build_zip(&[("stored.txt", 0, b"stored\n", b"stored\n")]);
+        let directory_offset = zip.len() - 22 - 46 - "stored.txt".len();
+        zip[directory_offset + 20..directory_offset + 24].copy_from_slice(&0xFFFFu32.to_le_bytes());
+        assert!(ZipArchive::new(Cursor::new(zip)).is_err());
+    }
+
+    #[test]
+    fn test_zip_archive() {
+        let deflated = "deflated ".repeat(20);
+        let zip = build_zip(&[
+            ("dir/", 0, b"", b""),
+            ("dir/stored.txt", 0, b"stored\n", b"stored\n"),
+            ("deflated.txt", 8, &DEFLATED, deflated.as_bytes()),
+        ]);
+        let archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+        let entries = archive.entries();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].is_directory);
+        assert_eq!(entries[1].name, "dir/stored.txt");
+        assert_eq!(archive.entry_file_name(1).unwrap(), "stored.txt");
+        assert_eq!(archive.read_entry(1).unwrap(), b"stored\n");
+        assert_eq!(archive.read_entry(2).unwrap(), deflated.as_bytes());
+        assert!(archive.read_entry(3).is_err());
+    }
+}
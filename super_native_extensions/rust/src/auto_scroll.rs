@@ -0,0 +1,169 @@
+//! Edge auto scrolling during drag.
+//!
+//! Dart registers scrollable regions of a view. While a drag hovers near an
+//! edge of such region, the drop manager sends `autoScroll` events at fixed
+//! interval, regardless of how often the platform reports drag location.
+//! Velocity grows linearly from zero at the inner boundary of the edge area
+//! to the maximum at the edge. When the drag moves out of the edge area, one
+//! last event with zero velocity is sent.
+
+use std::time::Duration;
+
+use irondash_message_channel::{IntoValue, TryFromValue};
+
+use crate::{
+    api_model::{Point, Rect},
+    drop_manager::DropSessionId,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+/// Interval between `autoScroll` events while scrolling.
+pub const AUTO_SCROLL_INTERVAL: Duration = Duration::from_millis(33);
+
+const DEFAULT_EDGE_SIZE: f64 = 40.0;
+const DEFAULT_MAX_VELOCITY: f64 = 1200.0;
+
+#[derive(TryFromValue, Clone, Copy, Debug, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum AutoScrollAxis {
+    Vertical,
+    Horizontal,
+    Both,
+}
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct AutoScrollRegion {
+    pub id: String,
+    /// In view coordinates.
+    pub rect: Rect,
+    /// Vertical if not set.
+    pub axis: Option<AutoScrollAxis>,
+    /// Distance from edge in which scrolling starts.
+    pub edge_size: Option<f64>,
+    /// In points per second, reached at the edge.
+    pub max_velocity: Option<f64>,
+}
+
+/// Velocity along one axis for position within `start..start + length`.
+fn edge_velocity(position: f64, start: f64, length: f64, edge: f64, max: f64) -> f64 {
+    let edge = edge.min(length / 2.0);
+    if edge <= 0.0 {
+        return 0.0;
+    }
+    let from_start = position - start;
+    let from_end = start + length - position;
+    if from_start < edge {
+        -max * (1.0 - from_start / edge)
+    } else if from_end < edge {
+        max * (1.0 - from_end / edge)
+    } else {
+        0.0
+    }
+}
+
+impl AutoScrollRegion {
+    fn contains(&self, point: &Point) -> bool {
+        point.x >= self.rect.x
+            && point.y >= self.rect.y
+            && point.x < self.rect.x + self.rect.width
+            && point.y < self.rect.y + self.rect.height
+    }
+
+    /// Scroll velocity at location, zero if location is not near an edge.
+    fn velocity(&self, location: &Point) -> Point {
+        let axis = self.axis.unwrap_or(AutoScrollAxis::Vertical);
+        let edge = self.edge_size.unwrap_or(DEFAULT_EDGE_SIZE);
+        let max = self.max_velocity.unwrap_or(DEFAULT_MAX_VELOCITY);
+        let rect = &self.rect;
+        let mut velocity = Point::default();
+        if axis != AutoScrollAxis::Vertical {
+            velocity.x = edge_velocity(location.x, rect.x, rect.width, edge, max);
+        }
+        if axis != AutoScrollAxis::Horizontal {
+            velocity.y = edge_velocity(location.y, rect.y, rect.height, edge, max);
+        }
+        velocity
+    }
+}
+
+/// Scrollable regions of a view, later regions are nested in (or on top of)
+/// earlier ones.
+#[derive(Clone, Debug, Default)]
+pub struct AutoScrollRegions {
+    regions: Vec<AutoScrollRegion>,
+}
+
+impl AutoScrollRegions {
+    pub fn new(regions: Vec<AutoScrollRegion>) -> NativeExtensionsResult<Self> {
+        for region in &regions {
+            let valid = |v: Option<f64>| v.map(|v| v.is_finite() && v >= 0.0).unwrap_or(true);
+            if !valid(region.edge_size) || !valid(region.max_velocity) {
+                return Err(NativeExtensionsError::OtherError(format!(
+                    "Invalid auto scroll configuration for region '{}'",
+                    region.id
+                )));
+            }
+        }
+        Ok(Self { regions })
+    }
+
+    /// Topmost region under location that would scroll and its velocity.
+    pub fn velocity_at(&self, location: &Point) -> Option<(&str, Point)> {
+        self.regions
+            .iter()
+            .rev()
+            .filter(|region| region.contains(location))
+            .map(|region| (region.id.as_str(), region.velocity(location)))
+            .find(|(_, velocity)| velocity.x != 0.0 || velocity.y != 0.0)
+    }
+}
+
+/// Session that is currently auto scrolling.
+pub struct AutoScrollState {
+    pub region_id: String,
+    pub velocity: Point,
+    /// Identifies the timer sending events, so that timer of previous
+    /// scrolling stops when scrolling restarts within one interval.
+    pub timer_id: i64,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+pub struct AutoScrollEvent {
+    pub session_id: DropSessionId,
+    pub engine_handle: i64,
+    pub region_id: String,
+    /// In points per second. Zero when scrolling stops.
+    pub velocity: Point,
+    /// Distance to scroll by for this event.
+    pub delta: Point,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AutoScrollAxis, AutoScrollRegion, AutoScrollRegions};
+    use crate::api_model::{Point, Rect};
+
+    #[test]
+    fn test_velocity_near_edges() {
+        let regions = AutoScrollRegions::new(vec![AutoScrollRegion {
+            id: "list".into(),
+            rect: Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 100.0,
+                height: 400.0,
+            },
+            axis: Some(AutoScrollAxis::Vertical),
+            edge_size: Some(40.0),
+            max_velocity: Some(1000.0),
+        }])
+        .unwrap();
+        let at = |x, y| regions.velocity_at(&Point { x, y }).map(|(_, v)| v);
+        assert_eq!(at(50.0, 0.0), Some(Point { x: 0.0, y: -1000.0 }));
+        assert_eq!(at(50.0, 380.0), Some(Point { x: 0.0, y: 500.0 }));
+        assert_eq!(at(5.0, 200.0), None);
+        assert_eq!(at(150.0, 0.0), None);
+    }
+}
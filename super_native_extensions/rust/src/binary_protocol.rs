@@ -0,0 +1,281 @@
+//! Compact binary encoding for high volume channels.
+//!
+//! Isolates can opt in per channel through `setBinaryProtocol`. Affected
+//! payloads (item data, progress updates, drop update events) are then sent
+//! as a single `Uint8List` instead of generic values, which avoids building
+//! maps on both sides of the channel.
+//!
+//! All numbers are little endian. Every payload starts with 8 byte header:
+//! magic `b'S'`, version, record kind and five bytes of padding.
+//!
+//! Progress records have fixed layout following the header: progress id
+//! (i64), fraction (f64, NaN when unknown), bytes transferred and total
+//! bytes (i64, -1 when unknown).
+//!
+//! Other records contain single encoded value, tagged by one byte:
+//!
+//! - `0` null, `1` false, `2` true
+//! - `3` i64, `4` f64
+//! - `5` string: u32 length followed by UTF-8 bytes
+//! - `6` attachment: u32 index
+//! - `7` list: u32 count followed by values
+//! - `8` map: u32 count followed by key and value pairs
+//!
+//! Typed lists and values without byte representation (such as finalizable
+//! handles) are not copied into the payload but moved to attachments. Record
+//! without attachments is sent as `Uint8List`; otherwise as list of the
+//! payload followed by the attachments. Typed lists therefore arrive in Dart
+//! as typed data of their own.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use irondash_message_channel::{IsolateId, TryFromValue, Value};
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+const MAGIC: u8 = b'S';
+const VERSION: u8 = 2;
+const HEADER_LEN: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum RecordKind {
+    ItemData = 1,
+    Progress = 2,
+    DropEvent = 3,
+}
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_I64: u8 = 3;
+const TAG_F64: u8 = 4;
+const TAG_STRING: u8 = 5;
+const TAG_ATTACHMENT: u8 = 6;
+const TAG_LIST: u8 = 7;
+const TAG_MAP: u8 = 8;
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+pub struct BinaryProtocolConfiguration {
+    item_data: Option<bool>,
+    progress: Option<bool>,
+    drop_events: Option<bool>,
+}
+
+/// Channels for which isolate requested binary encoding.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct BinaryChannels {
+    pub item_data: bool,
+    pub progress: bool,
+    pub drop_events: bool,
+}
+
+thread_local! {
+    static CHANNELS: RefCell<HashMap<IsolateId, BinaryChannels>> = RefCell::new(HashMap::new());
+}
+
+pub fn configure_binary_channels(isolate: IsolateId, configuration: BinaryProtocolConfiguration) {
+    let channels = BinaryChannels {
+        item_data: configuration.item_data.unwrap_or(false),
+        progress: configuration.progress.unwrap_or(false),
+        drop_events: configuration.drop_events.unwrap_or(false),
+    };
+    CHANNELS.with(|c| c.borrow_mut().insert(isolate, channels));
+}
+
+pub fn binary_channels(isolate: IsolateId) -> BinaryChannels {
+    CHANNELS.with(|c| c.borrow().get(&isolate).copied().unwrap_or_default())
+}
+
+pub fn remove_binary_channels(isolate: IsolateId) {
+    CHANNELS.with(|c| c.borrow_mut().remove(&isolate));
+}
+
+struct Encoder {
+    data: Vec<u8>,
+    attachments: Vec<Value>,
+    /// Set when a length does not fit in u32.
+    overflow: bool,
+}
+
+impl Encoder {
+    fn new(kind: RecordKind, capacity: usize) -> Self {
+        let mut data = Vec::with_capacity(HEADER_LEN + capacity);
+        data.extend_from_slice(&[MAGIC, VERSION, kind as u8]);
+        data.resize(HEADER_LEN, 0);
+        Self {
+            data,
+            attachments: Vec::new(),
+            overflow: false,
+        }
+    }
+
+    fn put_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    fn put_len(&mut self, value: usize) {
+        let value = u32::try_from(value).unwrap_or_else(|_| {
+            self.overflow = true;
+            0
+        });
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_i64(&mut self, value: i64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_f64(&mut self, value: f64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_attachment(&mut self, value: Value) {
+        self.put_u8(TAG_ATTACHMENT);
+        self.put_len(self.attachments.len());
+        self.attachments.push(value);
+    }
+
+    fn put_value(&mut self, value: Value) {
+        match value {
+            Value::Null => self.put_u8(TAG_NULL),
+            Value::Bool(false) => self.put_u8(TAG_FALSE),
+            Value::Bool(true) => self.put_u8(TAG_TRUE),
+            Value::I64(value) => {
+                self.put_u8(TAG_I64);
+                self.put_i64(value);
+            }
+            Value::F64(value) => {
+                self.put_u8(TAG_F64);
+                self.put_f64(value);
+            }
+            Value::String(value) => {
+                self.put_u8(TAG_STRING);
+                self.put_len(value.len());
+                self.data.extend_from_slice(value.as_bytes());
+            }
+            Value::List(values) => {
+                self.put_u8(TAG_LIST);
+                self.put_len(values.len());
+                for value in values {
+                    self.put_value(value);
+                }
+            }
+            Value::Map(map) => {
+                self.put_u8(TAG_MAP);
+                self.put_len(map.iter().count());
+                for (key, value) in map {
+                    self.put_value(key);
+                    self.put_value(value);
+                }
+            }
+            // Typed lists and values that only the message channel can
+            // transfer.
+            value => self.put_attachment(value),
+        }
+    }
+
+    fn finish(self) -> NativeExtensionsResult<Value> {
+        if self.overflow {
+            return Err(NativeExtensionsError::OtherError(
+                "Value is too large for binary protocol".into(),
+            ));
+        }
+        if self.attachments.is_empty() {
+            Ok(Value::U8List(self.data))
+        } else {
+            let mut values = Vec::with_capacity(self.attachments.len() + 1);
+            values.push(Value::U8List(self.data));
+            values.extend(self.attachments);
+            Ok(Value::List(values))
+        }
+    }
+}
+
+fn encoded_len_hint(value: &Value) -> usize {
+    match value {
+        Value::String(string) => string.len() + 8,
+        _ => 64,
+    }
+}
+
+pub fn encode_record(kind: RecordKind, value: Value) -> NativeExtensionsResult<Value> {
+    let mut encoder = Encoder::new(kind, encoded_len_hint(&value));
+    encoder.put_value(value);
+    encoder.finish()
+}
+
+pub fn encode_progress(
+    progress_id: i64,
+    fraction: Option<f64>,
+    bytes_transferred: Option<i64>,
+    total_bytes: Option<i64>,
+) -> Value {
+    let mut encoder = Encoder::new(RecordKind::Progress, 32);
+    encoder.put_i64(progress_id);
+    encoder.put_f64(fraction.unwrap_or(f64::NAN));
+    encoder.put_i64(bytes_transferred.unwrap_or(-1));
+    encoder.put_i64(total_bytes.unwrap_or(-1));
+    Value::U8List(encoder.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use super::{encode_progress, encode_record, Encoder, RecordKind};
+
+    fn payload(value: Value) -> Vec<u8> {
+        match value {
+            Value::U8List(data) => data,
+            _ => panic!("Expected U8List"),
+        }
+    }
+
+    #[test]
+    fn test_progress_layout() {
+        let data = payload(encode_progress(7, None, Some(10), None));
+        assert_eq!(data.len(), 40);
+        assert_eq!(&data[..3], &[b'S', 2, RecordKind::Progress as u8]);
+        assert_eq!(i64::from_le_bytes(data[8..16].try_into().unwrap()), 7);
+        assert!(f64::from_le_bytes(data[16..24].try_into().unwrap()).is_nan());
+        assert_eq!(i64::from_le_bytes(data[24..32].try_into().unwrap()), 10);
+        assert_eq!(i64::from_le_bytes(data[32..40].try_into().unwrap()), -1);
+    }
+
+    #[test]
+    fn test_record_without_attachments() {
+        let value = Value::List(vec![Value::String("ab".into()), Value::I64(3)]);
+        let data = payload(encode_record(RecordKind::ItemData, value).unwrap());
+        // Header, list tag and count, string tag, length and bytes, i64 tag
+        // and value.
+        assert_eq!(data.len(), 8 + 5 + 7 + 9);
+        assert_eq!(data[8], 7);
+        assert_eq!(&data[14..20], &[2, 0, 0, 0, b'a', b'b']);
+    }
+
+    #[test]
+    fn test_typed_list_is_attached() {
+        let value = Value::List(vec![
+            Value::String("ab".into()),
+            Value::F64List(vec![1.0, 2.0]),
+        ]);
+        let Value::List(values) = encode_record(RecordKind::ItemData, value).unwrap() else {
+            panic!("Expected List");
+        };
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[1], Value::F64List(vec![1.0, 2.0]));
+        let data = payload(values[0].clone());
+        // Attachment tag and index follow the string.
+        assert_eq!(&data[20..25], &[6, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_length_overflow() {
+        let mut encoder = Encoder::new(RecordKind::ItemData, 0);
+        encoder.put_len(u32::MAX as usize + 1);
+        assert!(encoder.finish().is_err());
+    }
+}
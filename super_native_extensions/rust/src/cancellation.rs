@@ -0,0 +1,256 @@
+//! Cancellation of long running operations.
+//!
+//! Tokens form a tree: cancelling a token cancels all tokens derived from it
+//! through [`CancellationToken::child`], but not its parent. This lets e.g. an
+//! isolate token cancel every operation started by the isolate while each
+//! operation can still be cancelled on its own.
+//!
+//! Tokens are the only cancellation mechanism of read progress
+//! ([`ReadProgressHandle`](crate::reader_manager::ReadProgressHandle)),
+//! local transfers and virtual file writes
+//! ([`WriteProgress`](crate::data_provider_manager::WriteProgress)).
+//! Pending Dart requests of drag sessions and menus are owned by task scopes
+//! instead, see [`crate::task_scope`].
+//!
+//! Tokens are `Send + Sync`. Handlers registered with
+//! [`CancellationToken::on_cancel`] run exactly once, on the thread that
+//! cancelled the token, or immediately when the token is already cancelled.
+
+use std::{
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::{Arc, Mutex, Weak},
+    task::{Poll, Waker},
+};
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+type Handler = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct State {
+    cancelled: bool,
+    next_handler_id: usize,
+    handlers: Vec<(usize, Handler)>,
+    children: Vec<Weak<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<State>,
+}
+
+impl Inner {
+    fn cancel(&self) {
+        let (handlers, children) = {
+            let mut state = self.state.lock().unwrap();
+            if state.cancelled {
+                return;
+            }
+            state.cancelled = true;
+            (
+                std::mem::take(&mut state.handlers),
+                std::mem::take(&mut state.children),
+            )
+        };
+        for (_, handler) in handlers {
+            handler();
+        }
+        for child in children {
+            if let Some(child) = child.upgrade() {
+                child.cancel();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates token that is cancelled together with this one. Child of
+    /// cancelled token starts cancelled.
+    pub fn child(&self) -> Self {
+        let child = Self::new();
+        let mut state = self.inner.state.lock().unwrap();
+        if state.cancelled {
+            child.inner.state.lock().unwrap().cancelled = true;
+        } else {
+            state.children.retain(|c| c.strong_count() > 0);
+            state.children.push(Arc::downgrade(&child.inner));
+        }
+        child
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.state.lock().unwrap().cancelled
+    }
+
+    /// Registers handler invoked when the token is cancelled. Dropping the
+    /// returned registration unregisters the handler.
+    #[must_use]
+    pub fn on_cancel<F: FnOnce() + Send + 'static>(&self, handler: F) -> CancellationRegistration {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.cancelled {
+            drop(state);
+            handler();
+            return CancellationRegistration {
+                token: Weak::new(),
+                id: 0,
+            };
+        }
+        let id = state.next_handler_id;
+        state.next_handler_id += 1;
+        state.handlers.push((id, Box::new(handler)));
+        CancellationRegistration {
+            token: Arc::downgrade(&self.inner),
+            id,
+        }
+    }
+
+    /// Polls `future` until it completes or the token is cancelled. On
+    /// cancellation the future is dropped and `Cancelled` error returned;
+    /// this abandons platform reads that don't observe the token.
+    pub async fn run<T>(
+        &self,
+        future: impl Future<Output = NativeExtensionsResult<T>>,
+    ) -> NativeExtensionsResult<T> {
+        let waker = Arc::new(Mutex::new(None::<Waker>));
+        let waker_clone = waker.clone();
+        let _registration = self.on_cancel(move || {
+            if let Some(waker) = waker_clone.lock().unwrap().take() {
+                waker.wake();
+            }
+        });
+        let mut future = pin!(future);
+        poll_fn(|cx| {
+            if self.is_cancelled() {
+                return Poll::Ready(Err(NativeExtensionsError::Cancelled));
+            }
+            if let Poll::Ready(res) = future.as_mut().poll(cx) {
+                return Poll::Ready(res);
+            }
+            *waker.lock().unwrap() = Some(cx.waker().clone());
+            // Cancelled before the waker was stored.
+            if self.is_cancelled() {
+                return Poll::Ready(Err(NativeExtensionsError::Cancelled));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+pub struct CancellationRegistration {
+    token: Weak<Inner>,
+    id: usize,
+}
+
+impl Drop for CancellationRegistration {
+    fn drop(&mut self) {
+        if let Some(inner) = self.token.upgrade() {
+            let removed = {
+                let mut state = inner.state.lock().unwrap();
+                let index = state.handlers.iter().position(|(id, _)| *id == self.id);
+                index.map(|index| state.handlers.remove(index))
+            };
+            // Handler may own registrations of its own; drop outside of lock.
+            drop(removed);
+        }
+    }
+}
+
+#[allow(dead_code)]
+fn assert_cancellation_token_is_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<CancellationToken>();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use std::{
+        future::{pending, ready, Future},
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use crate::error::NativeExtensionsError;
+
+    use super::CancellationToken;
+
+    #[test]
+    fn test_cancel_runs_handlers_once() {
+        let token = CancellationToken::new();
+        let count = Arc::new(AtomicUsize::new(0));
+        let count_clone = count.clone();
+        let _registration = token.on_cancel(move || {
+            count_clone.fetch_add(1, Ordering::Relaxed);
+        });
+        token.cancel();
+        token.cancel();
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn test_dropped_registration() {
+        let token = CancellationToken::new();
+        let count = Arc::new(AtomicUsize::new(0));
+        let count_clone = count.clone();
+        let registration = token.on_cancel(move || {
+            count_clone.fetch_add(1, Ordering::Relaxed);
+        });
+        drop(registration);
+        token.cancel();
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_run() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let token = CancellationToken::new();
+        let mut completed = pin!(token.run(ready(Ok(1))));
+        assert!(matches!(
+            completed.as_mut().poll(&mut cx),
+            Poll::Ready(Ok(1))
+        ));
+
+        let mut pending = pin!(token.run(pending::<Result<(), NativeExtensionsError>>()));
+        assert!(pending.as_mut().poll(&mut cx).is_pending());
+        token.cancel();
+        assert!(matches!(
+            pending.as_mut().poll(&mut cx),
+            Poll::Ready(Err(NativeExtensionsError::Cancelled))
+        ));
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let parent = CancellationToken::new();
+        let child = parent.child();
+        let grandchild = child.child();
+        child.cancel();
+        assert!(!parent.is_cancelled());
+        assert!(grandchild.is_cancelled());
+
+        let other = parent.child();
+        parent.cancel();
+        assert!(other.is_cancelled());
+        assert!(parent.child().is_cancelled());
+    }
+}
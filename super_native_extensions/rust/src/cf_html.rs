@@ -0,0 +1,195 @@
+//! CF_HTML ("HTML Format") clipboard format.
+//!
+//! CF_HTML is UTF-8 HTML preceded by a header with byte offsets of the
+//! document and of the copied fragment. Windows readers get the fragment as
+//! synthesized `text/html` when the item doesn't have it. HTML that Dart
+//! provides without the header (either as `text/html` or `HTML Format`) is
+//! wrapped before it is handed to other applications. Rich text conversions
+//! strip the header on all platforms, since CF_HTML data also travels through
+//! remote desktop and cross-platform clipboards.
+
+use std::borrow::Cow;
+
+use irondash_message_channel::Value;
+
+pub const CF_HTML_FORMAT: &str = "HTML Format";
+pub const HTML_FORMAT: &str = "text/html";
+
+const START_FRAGMENT_MARKER: &str = "<!--StartFragment-->";
+const END_FRAGMENT_MARKER: &str = "<!--EndFragment-->";
+
+pub fn has_header(data: &[u8]) -> bool {
+    data.starts_with(b"Version:")
+}
+
+fn header_offset(header: &str, key: &str) -> Option<usize> {
+    header
+        .lines()
+        .find_map(|line| line.strip_prefix(key))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Fragment of CF_HTML data; `None` if data has no header.
+pub fn extract_fragment(data: &[u8]) -> Option<String> {
+    if !has_header(data) {
+        return None;
+    }
+    // Header is ASCII and ends where markup starts.
+    let header_end = data.iter().position(|b| *b == b'<').unwrap_or(data.len());
+    let header = String::from_utf8_lossy(&data[..header_end]);
+    let range = match (
+        header_offset(&header, "StartFragment:"),
+        header_offset(&header, "EndFragment:"),
+    ) {
+        (Some(start), Some(end)) if start <= end && end <= data.len() => start..end,
+        // Some applications write wrong offsets, fall back to markers.
+        _ => {
+            let html = &data[header_end..];
+            let find = |marker: &str| {
+                html.windows(marker.len())
+                    .position(|w| w == marker.as_bytes())
+                    .map(|p| p + header_end)
+            };
+            let start = find(START_FRAGMENT_MARKER)? + START_FRAGMENT_MARKER.len();
+            let end = find(END_FRAGMENT_MARKER).filter(|end| *end >= start)?;
+            start..end
+        }
+    };
+    Some(
+        String::from_utf8_lossy(&data[range])
+            .trim_end_matches('\0')
+            .to_owned(),
+    )
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.to_ascii_lowercase().find(needle)
+}
+
+/// Fragment of HTML that may have CF_HTML header, or the HTML unchanged.
+pub fn strip_header(html: &str) -> Cow<str> {
+    match extract_fragment(html.as_bytes()) {
+        Some(fragment) => Cow::Owned(fragment),
+        None => Cow::Borrowed(html),
+    }
+}
+
+/// Wraps HTML in CF_HTML document. If the HTML is a full document the body
+/// becomes the fragment. Result is null terminated.
+pub fn wrap_html(html: &str, source_url: Option<&str>) -> Vec<u8> {
+    let html = html.trim_end_matches('\0');
+    let (prefix, fragment, suffix) = match find_ignore_case(html, "<body") {
+        Some(body) => {
+            let start = html[body..]
+                .find('>')
+                .map(|end| body + end + 1)
+                .unwrap_or(html.len());
+            let end = find_ignore_case(&html[start..], "</body")
+                .map(|end| start + end)
+                .unwrap_or(html.len());
+            (
+                format!("{}{START_FRAGMENT_MARKER}", &html[..start]),
+                &html[start..end],
+                format!("{END_FRAGMENT_MARKER}{}", &html[end..]),
+            )
+        }
+        None => (
+            format!("<html>\r\n<body>\r\n{START_FRAGMENT_MARKER}"),
+            html,
+            format!("{END_FRAGMENT_MARKER}\r\n</body>\r\n</html>"),
+        ),
+    };
+    // Header value must not span lines.
+    let source_url = source_url.and_then(|url| url.lines().next());
+    let header = |start_html: usize, end_html: usize, start_fragment: usize, end_fragment| {
+        let mut header = format!(
+            "Version:0.9\r\n\
+             StartHTML:{start_html:010}\r\n\
+             EndHTML:{end_html:010}\r\n\
+             StartFragment:{start_fragment:010}\r\n\
+             EndFragment:{end_fragment:010}\r\n"
+        );
+        if let Some(url) = source_url {
+            header.push_str(&format!("SourceURL:{url}\r\n"));
+        }
+        header
+    };
+    let start_html = header(0, 0, 0, 0).len();
+    let start_fragment = start_html + prefix.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + suffix.len();
+    let mut res = header(start_html, end_html, start_fragment, end_fragment).into_bytes();
+    res.extend_from_slice(prefix.as_bytes());
+    res.extend_from_slice(fragment.as_bytes());
+    res.extend_from_slice(suffix.as_bytes());
+    res.push(0);
+    res
+}
+
+/// CF_HTML data for a representation value. Data that already has the
+/// header is returned unchanged.
+pub fn cf_html_from_value(value: &Value, source_url: Option<&str>) -> Option<Vec<u8>> {
+    match value {
+        Value::String(html) if has_header(html.as_bytes()) => {
+            let mut data = html.as_bytes().to_vec();
+            if data.last() != Some(&0) {
+                data.push(0);
+            }
+            Some(data)
+        }
+        Value::String(html) => Some(wrap_html(html, source_url)),
+        Value::U8List(data) if has_header(data) => Some(data.clone()),
+        Value::U8List(data) => Some(wrap_html(&String::from_utf8_lossy(data), source_url)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use super::{cf_html_from_value, extract_fragment, has_header, wrap_html};
+
+    #[test]
+    fn test_wrap_and_extract() {
+        let data = wrap_html("<b>Bold</b>", Some("https://x.com"));
+        assert!(has_header(&data));
+        assert_eq!(data.last(), Some(&0));
+        let text = String::from_utf8_lossy(&data);
+        assert!(text.contains("SourceURL:https://x.com\r\n"));
+        assert_eq!(extract_fragment(&data).as_deref(), Some("<b>Bold</b>"));
+    }
+
+    #[test]
+    fn test_wrap_document() {
+        let data = wrap_html("<html><BODY class=\"a\"><i>x</i></body></html>", None);
+        let text = String::from_utf8_lossy(&data);
+        assert!(text.contains("<BODY class=\"a\"><!--StartFragment--><i>x</i>"));
+        assert_eq!(extract_fragment(&data).as_deref(), Some("<i>x</i>"));
+    }
+
+    #[test]
+    fn test_extract_with_wrong_offsets() {
+        let data = "Version:0.9\r\nStartFragment:0000009999\r\nEndFragment:0000009999\r\n\
+                    <html><body><!--StartFragment--><b>Bold</b><!--EndFragment--></body></html>";
+        assert_eq!(
+            extract_fragment(data.as_bytes()).as_deref(),
+            Some("<b>Bold</b>")
+        );
+        assert_eq!(extract_fragment(b"<b>plain</b>"), None);
+    }
+
+    #[test]
+    fn test_value_with_header_is_not_wrapped_again() {
+        let wrapped = wrap_html("<b>Bold</b>", None);
+        let string = String::from_utf8_lossy(&wrapped[..wrapped.len() - 1]).into_owned();
+        assert_eq!(
+            cf_html_from_value(&Value::String(string), None),
+            Some(wrapped.clone())
+        );
+        assert_eq!(
+            cf_html_from_value(&Value::U8List(wrapped.clone()), None),
+            Some(wrapped)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    rc::{Rc, Weak},
+};
+
+use irondash_message_channel::{
+    IntoPlatformResult, IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler,
+    MethodInvoker, PlatformError, PlatformResult, RegisteredMethodHandler, Value,
+};
+
+use crate::{
+    context::Context,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    platform_impl::platform::{PlatformClipboardMonitor, PlatformDataReader},
+    reader_manager::GetDataReaderManager,
+};
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ClipboardChangedEvent {
+    /// Platform change counter, if available (macOS, iOS, Windows).
+    change_count: Option<i64>,
+}
+
+/// Notifies subscribed isolates about system clipboard changes.
+pub struct ClipboardMonitor {
+    invoker: Late<MethodInvoker>,
+    subscribers: RefCell<HashSet<IsolateId>>,
+    /// Native users, such as format subscriptions of clipboard readers.
+    retain_count: Cell<usize>,
+    platform_monitor: Late<Rc<PlatformClipboardMonitor>>,
+}
+
+pub trait ClipboardMonitorDelegate {
+    fn clipboard_changed(&self);
+}
+
+pub trait GetClipboardMonitor {
+    fn clipboard_monitor(&self) -> Rc<ClipboardMonitor>;
+}
+
+impl GetClipboardMonitor for Context {
+    fn clipboard_monitor(&self) -> Rc<ClipboardMonitor> {
+        self.get_attachment(ClipboardMonitor::new).handler()
+    }
+}
+
+impl ClipboardMonitor {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {
+            invoker: Late::new(),
+            subscribers: RefCell::new(HashSet::new()),
+            retain_count: Cell::new(0),
+            platform_monitor: Late::new(),
+        }
+        .register("ClipboardMonitor")
+    }
+
+    /// Returns `false` if clipboard monitoring is not supported on current
+    /// platform.
+    fn is_monitoring(&self) -> bool {
+        !self.subscribers.borrow().is_empty() || self.retain_count.get() > 0
+    }
+
+    /// Starts platform monitor unless already running. Returns `false` if
+    /// clipboard monitoring is not supported on current platform.
+    fn ensure_started(&self) -> NativeExtensionsResult<bool> {
+        if !self.is_monitoring() {
+            match self.platform_monitor.start() {
+                Err(NativeExtensionsError::UnsupportedOperation) => return Ok(false),
+                res => res?,
+            }
+        }
+        Ok(true)
+    }
+
+    fn stop_if_unused(&self) {
+        if !self.is_monitoring() {
+            self.platform_monitor.stop();
+        }
+    }
+
+    fn start_monitoring(&self, isolate_id: IsolateId) -> NativeExtensionsResult<bool> {
+        if !self.subscribers.borrow().contains(&isolate_id) {
+            if !self.ensure_started()? {
+                return Ok(false);
+            }
+            self.subscribers.borrow_mut().insert(isolate_id);
+        }
+        Ok(true)
+    }
+
+    fn stop_monitoring(&self, isolate_id: IsolateId) -> NativeExtensionsResult<()> {
+        if self.subscribers.borrow_mut().remove(&isolate_id) {
+            self.stop_if_unused();
+        }
+        Ok(())
+    }
+
+    /// Keeps monitor running for native code until balanced by
+    /// [`Self::release`]. Returns `false` (without retaining) if clipboard
+    /// monitoring is not supported on current platform.
+    pub fn retain(&self) -> NativeExtensionsResult<bool> {
+        if !self.ensure_started()? {
+            return Ok(false);
+        }
+        self.retain_count.set(self.retain_count.get() + 1);
+        Ok(true)
+    }
+
+    pub fn release(&self) {
+        self.retain_count
+            .set(self.retain_count.get().saturating_sub(1));
+        self.stop_if_unused();
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "startMonitoring" => self.start_monitoring(call.isolate).into_platform_result(),
+            "stopMonitoring" => self.stop_monitoring(call.isolate).into_platform_result(),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+impl MethodHandler for ClipboardMonitor {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
+
+    fn assign_invoker(&self, invoker: MethodInvoker) {
+        self.invoker.set(invoker);
+    }
+
+    fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        let platform_monitor = Rc::new(PlatformClipboardMonitor::new(weak_self));
+        platform_monitor.assign_weak_self(Rc::downgrade(&platform_monitor));
+        self.platform_monitor.set(platform_monitor);
+    }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        self.stop_monitoring(isolate).ok_log();
+    }
+}
+
+impl ClipboardMonitorDelegate for ClipboardMonitor {
+    fn clipboard_changed(&self) {
+        Context::get().data_reader_manager().clipboard_changed();
+        let change_count = PlatformDataReader::clipboard_change_count()
+            .ok_log()
+            .flatten();
+        for isolate in self.subscribers.borrow().iter() {
+            self.invoker.call_method(
+                *isolate,
+                "clipboardChanged",
+                ClipboardChangedEvent { change_count },
+                |r| {
+                    r.ok_log();
+                },
+            );
+        }
+    }
+}
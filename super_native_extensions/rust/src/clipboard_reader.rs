@@ -2,21 +2,240 @@ use std::rc::Rc;
 
 use async_trait::async_trait;
 use irondash_message_channel::{
-    AsyncMethodHandler, MethodCall, PlatformError, PlatformResult, RegisteredAsyncMethodHandler,
-    Value,
+    AsyncMethodHandler, IntoValue, IsolateId, MethodCall, PlatformError, PlatformResult,
+    RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
 
 use crate::{
-    context::Context, platform_impl::platform::PlatformDataReader,
-    reader_manager::GetDataReaderManager,
+    api_model::{ClipboardTarget, ClipboardType},
+    context::Context,
+    error::NativeExtensionsResult,
+    platform_impl::platform::PlatformDataReader,
+    reader_manager::{GetDataReaderManager, RegisteredDataReader},
 };
 
+#[cfg(target_os = "windows")]
+use crate::platform_impl::platform::remote_session::{
+    remote_session_info, set_tuning as set_remote_session_tuning,
+};
+
+#[cfg(not(target_os = "windows"))]
+fn remote_session_info() -> crate::api_model::RemoteSessionInfo {
+    crate::api_model::RemoteSessionInfo {
+        kind: None,
+        tuning_enabled: false,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn set_remote_session_tuning(_enabled: Option<bool>) {}
+
+#[cfg(target_os = "android")]
+use crate::platform_impl::platform::clipboard_policy::clipboard_policy;
+
+#[cfg(not(target_os = "android"))]
+fn clipboard_policy() -> crate::error::NativeExtensionsResult<crate::api_model::ClipboardPolicy> {
+    Ok(Default::default())
+}
+
+#[cfg(target_os = "linux")]
+fn new_primary_selection_reader() -> NativeExtensionsResult<Rc<PlatformDataReader>> {
+    PlatformDataReader::new_primary_selection_reader()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn new_primary_selection_reader() -> NativeExtensionsResult<Rc<PlatformDataReader>> {
+    Err(crate::error::NativeExtensionsError::UnsupportedOperation)
+}
+
+#[cfg(target_os = "macos")]
+fn new_named_pasteboard_reader(name: &str) -> NativeExtensionsResult<Rc<PlatformDataReader>> {
+    PlatformDataReader::new_named_pasteboard_reader(name)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn new_named_pasteboard_reader(_name: &str) -> NativeExtensionsResult<Rc<PlatformDataReader>> {
+    Err(crate::error::NativeExtensionsError::UnsupportedOperation)
+}
+
+#[cfg(target_os = "macos")]
+fn named_pasteboard_change_count(name: &str) -> NativeExtensionsResult<Option<i64>> {
+    PlatformDataReader::named_pasteboard_change_count(name)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn named_pasteboard_change_count(_name: &str) -> NativeExtensionsResult<Option<i64>> {
+    Err(crate::error::NativeExtensionsError::UnsupportedOperation)
+}
+
+fn new_platform_clipboard_reader(
+    target: &ClipboardTarget,
+) -> NativeExtensionsResult<Rc<PlatformDataReader>> {
+    if let Some(name) = &target.pasteboard_name {
+        return new_named_pasteboard_reader(name);
+    }
+    match target.clipboard_type.unwrap_or_default() {
+        ClipboardType::Clipboard => PlatformDataReader::new_clipboard_reader(),
+        ClipboardType::Primary => new_primary_selection_reader(),
+    }
+}
+
+/// PRIMARY selection has no change counter.
+fn change_count(target: &ClipboardTarget) -> NativeExtensionsResult<Option<i64>> {
+    if let Some(name) = &target.pasteboard_name {
+        return named_pasteboard_change_count(name);
+    }
+    match target.clipboard_type.unwrap_or_default() {
+        ClipboardType::Clipboard => PlatformDataReader::clipboard_change_count(),
+        ClipboardType::Primary => Ok(None),
+    }
+}
+
+/// Lightweight snapshot of clipboard state. Can be redeemed for a reader
+/// later as long as clipboard contents did not change in the meanwhile.
+#[derive(IntoValue, TryFromValue, Debug, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct ClipboardToken {
+    target: ClipboardTarget,
+    /// Platform change counter, if available. When missing the token is
+    /// validated by comparing formats only.
+    change_count: Option<i64>,
+    /// Formats of each clipboard item. Only recorded when there is no change
+    /// counter.
+    item_formats: Vec<Vec<String>>,
+}
+
+async fn item_formats(reader: &PlatformDataReader) -> NativeExtensionsResult<Vec<Vec<String>>> {
+    let mut res = Vec::new();
+    for item in reader.get_items().await? {
+        res.push(reader.get_formats_for_item(item).await?);
+    }
+    Ok(res)
+}
+
+/// Creates reader for current clipboard contents together with token
+/// describing them.
+pub async fn new_clipboard_reader_with_token(
+    target: ClipboardTarget,
+) -> NativeExtensionsResult<(Rc<PlatformDataReader>, ClipboardToken)> {
+    // Change count is read first so that a change racing with reader
+    // creation makes the token stale rather than describing old contents.
+    let change_count = change_count(&target)?;
+    let reader = new_platform_clipboard_reader(&target)?;
+    // Enumerating items may be expensive (or prompt the user), only do it
+    // when there is no cheaper way to detect changes.
+    let item_formats = match change_count {
+        Some(_) => Vec::new(),
+        None => item_formats(&reader).await?,
+    };
+    let token = ClipboardToken {
+        item_formats,
+        target,
+        change_count,
+    };
+    Ok((reader, token))
+}
+
+impl ClipboardToken {
+    /// Whether clipboard contents still match the token.
+    pub async fn is_current(&self) -> NativeExtensionsResult<bool> {
+        if self.change_count.is_some() {
+            return Ok(change_count(&self.target)? == self.change_count);
+        }
+        let reader = new_platform_clipboard_reader(&self.target)?;
+        Ok(item_formats(&reader).await? == self.item_formats)
+    }
+
+    /// Whether the platform provides change counter. Without one, checking
+    /// the token means enumerating clipboard items.
+    pub fn has_change_count(&self) -> bool {
+        self.change_count.is_some()
+    }
+
+    pub fn target(&self) -> &ClipboardTarget {
+        &self.target
+    }
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct CanPasteRequest {
+    formats: Vec<String>,
+    target: Option<ClipboardTarget>,
+}
+
 pub struct ClipboardReader {}
 
 impl ClipboardReader {
     pub fn new() -> RegisteredAsyncMethodHandler<Self> {
         Self {}.register("ClipboardReader")
     }
+
+    /// Target is optional; regular clipboard is used when missing.
+    async fn new_clipboard_reader(
+        &self,
+        isolate_id: IsolateId,
+        target: Option<ClipboardTarget>,
+    ) -> NativeExtensionsResult<RegisteredDataReader> {
+        let (reader, token) = new_clipboard_reader_with_token(target.unwrap_or_default()).await?;
+        Ok(Context::get()
+            .data_reader_manager()
+            .register_clipboard_reader(reader, token, isolate_id))
+    }
+
+    /// Whether any clipboard item has any of the formats. Meant for enabling
+    /// paste actions, so the platform is asked directly when it can answer
+    /// without enumerating items.
+    async fn can_paste(
+        &self,
+        isolate_id: IsolateId,
+        request: CanPasteRequest,
+    ) -> NativeExtensionsResult<bool> {
+        let formats = Context::get()
+            .data_reader_manager()
+            .formats_satisfying(isolate_id, request.formats);
+        let target = request.target.unwrap_or_default();
+        if target == ClipboardTarget::default() {
+            if let Some(res) = PlatformDataReader::clipboard_has_any_format(&formats)? {
+                return Ok(res);
+            }
+        }
+        let reader = new_platform_clipboard_reader(&target)?;
+        for item in reader.get_items().await? {
+            let item_formats = reader.get_formats_for_item(item).await?;
+            if item_formats.iter().any(|f| formats.contains(f)) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn capture_clipboard_token(&self) -> NativeExtensionsResult<ClipboardToken> {
+        // Reader is only used to list formats and released immediately.
+        let (_, token) = new_clipboard_reader_with_token(ClipboardTarget::default()).await?;
+        Ok(token)
+    }
+
+    /// Returns reader for current clipboard if it still matches the token,
+    /// `None` otherwise.
+    async fn redeem_clipboard_token(
+        &self,
+        isolate_id: IsolateId,
+        token: ClipboardToken,
+    ) -> NativeExtensionsResult<Option<RegisteredDataReader>> {
+        if !token.is_current().await? {
+            return Ok(None);
+        }
+        let (reader, current) = new_clipboard_reader_with_token(token.target.clone()).await?;
+        if current != token {
+            return Ok(None);
+        }
+        Ok(Some(
+            Context::get()
+                .data_reader_manager()
+                .register_clipboard_reader(reader, current, isolate_id),
+        ))
+    }
 }
 
 pub trait GetClipboardReader {
@@ -33,12 +252,24 @@ impl GetClipboardReader for Context {
 impl AsyncMethodHandler for ClipboardReader {
     async fn on_method_call(&self, call: MethodCall) -> PlatformResult {
         match call.method.as_str() {
-            "newClipboardReader" => {
-                let reader = PlatformDataReader::new_clipboard_reader()?;
-                Ok(Context::get()
-                    .data_reader_manager()
-                    .register_platform_reader(reader, call.isolate)
-                    .into())
+            "newClipboardReader" => Ok(self
+                .new_clipboard_reader(call.isolate, call.args.try_into()?)
+                .await?
+                .into()),
+            "canPaste" => Ok(self
+                .can_paste(call.isolate, call.args.try_into()?)
+                .await?
+                .into()),
+            "captureClipboardToken" => Ok(self.capture_clipboard_token().await?.into()),
+            "redeemClipboardToken" => Ok(self
+                .redeem_clipboard_token(call.isolate, call.args.try_into()?)
+                .await?
+                .into()),
+            "getRemoteSessionInfo" => Ok(remote_session_info().into()),
+            "getClipboardPolicy" => Ok(clipboard_policy()?.into()),
+            "setRemoteSessionTuning" => {
+                set_remote_session_tuning(call.args.try_into()?);
+                Ok(Value::Null)
             }
             _ => Err(PlatformError {
                 code: "invalid_method".into(),
@@ -1,23 +1,86 @@
 use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
     rc::{Rc, Weak},
     sync::Arc,
+    time::Instant,
 };
 
 use async_trait::async_trait;
 use irondash_message_channel::{
     AsyncMethodHandler, AsyncMethodInvoker, IntoPlatformResult, IsolateId, Late, MethodCall,
-    PlatformError, PlatformResult, RegisteredAsyncMethodHandler, Value,
+    PlatformError, PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
+use irondash_run_loop::{util::FutureCompleter, RunLoop};
 
 use crate::{
-    api_model::DataProviderId, context::Context, data_provider_manager::GetDataProviderManager,
-    error::NativeExtensionsResult, log::OkLog, platform_impl::platform::PlatformDataProvider,
+    api_model::{ClipboardTarget, ClipboardType, DataProviderId},
+    context::Context,
+    data_provider_manager::{DataProviderHandle, GetDataProviderManager},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::{OkLog, OperationSpan},
+    platform_impl::platform::{PlatformDataProvider, PlatformDataReader},
+    shutdown,
+    task_scope::TaskScope,
     util::DropNotifier,
 };
 
 pub struct ClipboardWriter {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
+    pending_write: RefCell<Option<PendingWrite>>,
+    contents: RefCell<Option<ClipboardContents>>,
+    /// Scheduled clearing of the clipboard for providers with
+    /// `expiresAfterMs`.
+    expires_at: Cell<Option<Instant>>,
+    /// Isolates with providers in pending or current contents.
+    owners: RefCell<HashSet<IsolateId>>,
+    tasks: TaskScope,
+}
+
+type ClipboardItem = (Rc<PlatformDataProvider>, Arc<DataProviderHandle>);
+
+/// Write waiting for the next run loop turn. Writes requested before that
+/// replace it, so that the clipboard is only set once.
+struct PendingWrite {
+    providers: Vec<ClipboardItem>,
+    completer: FutureCompleter<NativeExtensionsResult<()>>,
+}
+
+/// Last write that made it to the clipboard. Kept so that items can be
+/// modified without Dart having to resend all of them.
+struct ClipboardContents {
+    providers: Vec<ClipboardItem>,
+    /// Change count after the write; if it differs now, another application
+    /// has replaced the clipboard.
+    change_count: Option<i64>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct WriteToSelectionRequest {
+    provider_ids: Vec<DataProviderId>,
+    target: ClipboardTarget,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct RemoveItemRequest {
+    index: i64,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ReplaceItemRequest {
+    index: i64,
+    provider_id: DataProviderId,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct MoveItemRequest {
+    from_index: i64,
+    to_index: i64,
 }
 
 impl ClipboardWriter {
@@ -25,10 +88,44 @@ impl ClipboardWriter {
         Self {
             weak_self: Late::new(),
             invoker: Late::new(),
+            pending_write: RefCell::new(None),
+            contents: RefCell::new(None),
+            expires_at: Cell::new(None),
+            owners: RefCell::new(HashSet::new()),
+            tasks: TaskScope::new("ClipboardWriter"),
         }
         .register("ClipboardWriter")
     }
 
+    /// Contents with providers of the isolate would keep asking it for lazy
+    /// data. Pending write is cancelled; written contents are flushed so that
+    /// data not depending on the isolate stays on the clipboard. Where the
+    /// platform can't flush, contents are kept and lazy data requests fail.
+    /// Part of [`shutdown::shutdown`].
+    pub fn shutdown(&self, isolate: IsolateId) {
+        if !self.owners.borrow_mut().remove(&isolate) {
+            return;
+        }
+        if let Some(pending) = self.pending_write.take() {
+            pending
+                .completer
+                .complete(Err(NativeExtensionsError::Cancelled));
+        }
+        self.expires_at.take();
+        if self.has_clipboard_ownership().ok_log() == Some(true) {
+            match PlatformDataProvider::flush_clipboard() {
+                Ok(()) => {
+                    self.contents.take();
+                }
+                Err(NativeExtensionsError::UnsupportedOperation) => {}
+                Err(err) => {
+                    Err::<(), _>(err).ok_log();
+                }
+            }
+        }
+        self.owners.borrow_mut().clear();
+    }
+
     fn release_data_provider(&self, isolate_id: IsolateId, provider_id: DataProviderId) {
         self.invoker
             .call_method_sync(isolate_id, "releaseDataProvider", provider_id, |r| {
@@ -36,11 +133,11 @@ impl ClipboardWriter {
             })
     }
 
-    async fn write_to_clipboard(
+    fn clipboard_items(
         &self,
         isolate_id: IsolateId,
         provider_ids: Vec<DataProviderId>,
-    ) -> NativeExtensionsResult<()> {
+    ) -> NativeExtensionsResult<Vec<ClipboardItem>> {
         let mut providers = Vec::<_>::new();
         let data_provider_manager = Context::get().data_provider_manager();
         for provider_id in provider_ids {
@@ -53,9 +150,305 @@ impl ClipboardWriter {
             });
             providers.push((provider, Arc::new(notifier.into())));
         }
-        PlatformDataProvider::write_to_clipboard(providers).await?;
+        Ok(providers)
+    }
+
+    pub async fn write_to_clipboard(
+        &self,
+        isolate_id: IsolateId,
+        provider_ids: Vec<DataProviderId>,
+    ) -> NativeExtensionsResult<()> {
+        let providers = self.clipboard_items(isolate_id, provider_ids.clone())?;
+        let span = OperationSpan::new(module_path!(), "writeToClipboard");
+        self.owners.replace(HashSet::from([isolate_id]));
+        let res = self.schedule_write(providers).await;
+        span.finish(&res);
+        res?;
+        // New contents; previous expiration no longer applies.
+        self.expires_at.take();
+        self.schedule_expiration(&provider_ids);
         Ok(())
     }
+
+    /// Schedules clearing of the clipboard for earliest expiration of given
+    /// providers, unless already scheduled earlier.
+    fn schedule_expiration(&self, provider_ids: &[DataProviderId]) {
+        // Without change count the clipboard could not be cleared anyway, as
+        // it may have been replaced by another application in the meanwhile.
+        let verifiable = self
+            .contents
+            .borrow()
+            .as_ref()
+            .map(|c| c.change_count.is_some())
+            .unwrap_or(false);
+        if !verifiable {
+            return;
+        }
+        let data_provider_manager = Context::get().data_provider_manager();
+        let Some(expires_after) = provider_ids
+            .iter()
+            .filter_map(|id| data_provider_manager.get_expiration(*id))
+            .min()
+        else {
+            return;
+        };
+        let at = Instant::now() + expires_after;
+        if matches!(self.expires_at.get(), Some(current) if current <= at) {
+            return;
+        }
+        self.expires_at.set(Some(at));
+        let weak_self = self.weak_self.clone();
+        RunLoop::current()
+            .schedule(expires_after, move || {
+                if let Some(this) = weak_self.upgrade() {
+                    // Superseded by new contents or earlier expiration.
+                    if this.expires_at.get() == Some(at) {
+                        let this_clone = this.clone();
+                        this.tasks.spawn(async move {
+                            this_clone.clear_clipboard().await.ok_log();
+                        });
+                    }
+                }
+            })
+            .detach();
+    }
+
+    /// Like `writeToClipboard` but with explicit target. Only contents of
+    /// the regular clipboard are tracked; contents of PRIMARY selection and
+    /// named pasteboards can not be modified afterwards.
+    async fn write_to_selection(
+        &self,
+        isolate_id: IsolateId,
+        request: WriteToSelectionRequest,
+    ) -> NativeExtensionsResult<()> {
+        if let Some(name) = &request.target.pasteboard_name {
+            let providers = self.clipboard_items(isolate_id, request.provider_ids)?;
+            return write_to_named_pasteboard(providers, name).await;
+        }
+        match request.target.clipboard_type.unwrap_or_default() {
+            ClipboardType::Clipboard => {
+                self.write_to_clipboard(isolate_id, request.provider_ids)
+                    .await
+            }
+            ClipboardType::Primary => {
+                let providers = self.clipboard_items(isolate_id, request.provider_ids)?;
+                write_to_primary_selection(providers).await
+            }
+        }
+    }
+
+    /// Items currently on clipboard (or about to be written). Fails if the
+    /// clipboard has since been replaced by another application.
+    fn owned_items(&self) -> NativeExtensionsResult<Vec<ClipboardItem>> {
+        if let Some(pending) = self.pending_write.borrow().as_ref() {
+            return Ok(pending.providers.clone());
+        }
+        let change_count = PlatformDataReader::clipboard_change_count()?;
+        let mut contents = self.contents.borrow_mut();
+        match contents.as_ref() {
+            Some(c) if c.change_count.is_some() && c.change_count == change_count => {
+                Ok(c.providers.clone())
+            }
+            // Without change count ownership can not be verified. Providers
+            // are kept as the clipboard may still need them.
+            Some(c) if c.change_count.is_none() => Err(NativeExtensionsError::ClipboardNotOwned),
+            _ => {
+                // Release providers of replaced contents.
+                contents.take();
+                Err(NativeExtensionsError::ClipboardNotOwned)
+            }
+        }
+    }
+
+    /// Whether clipboard still holds contents written by this writer, i.e.
+    /// whether lazy data providers may still be asked for data.
+    fn has_clipboard_ownership(&self) -> NativeExtensionsResult<bool> {
+        match self.owned_items() {
+            Ok(_) => Ok(true),
+            Err(NativeExtensionsError::ClipboardNotOwned) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Clears the clipboard if it still holds contents written by this
+    /// writer; contents of other applications are left alone. Where
+    /// ownership can not be verified (no change count, i.e. Android and
+    /// Linux) the clipboard is never cleared. Pending write is cancelled.
+    /// Returns whether anything was cleared.
+    async fn clear_clipboard(&self) -> NativeExtensionsResult<bool> {
+        self.expires_at.take();
+        // Pending write never makes it to the clipboard.
+        let had_pending = match self.pending_write.take() {
+            Some(pending) => {
+                pending
+                    .completer
+                    .complete(Err(NativeExtensionsError::Cancelled));
+                true
+            }
+            None => false,
+        };
+        let owned = match self.owned_items() {
+            Ok(_) => true,
+            Err(NativeExtensionsError::ClipboardNotOwned) => false,
+            Err(err) => return Err(err),
+        };
+        if owned {
+            PlatformDataProvider::clear_clipboard().await?;
+            // Releases the providers.
+            self.contents.take();
+            self.owners.borrow_mut().clear();
+        }
+        Ok(owned || had_pending)
+    }
+
+    /// Adds items after the ones currently on clipboard. No platform can add
+    /// items to existing contents, so all items are written again.
+    async fn append_to_clipboard(
+        &self,
+        isolate_id: IsolateId,
+        provider_ids: Vec<DataProviderId>,
+    ) -> NativeExtensionsResult<()> {
+        let mut items = self.owned_items()?;
+        let new_items = self.clipboard_items(isolate_id, provider_ids.clone())?;
+        self.owners.borrow_mut().insert(isolate_id);
+        items.extend(new_items);
+        self.schedule_write(items).await?;
+        self.schedule_expiration(&provider_ids);
+        Ok(())
+    }
+
+    fn check_index(items: &[ClipboardItem], index: i64) -> NativeExtensionsResult<usize> {
+        if index >= 0 && (index as usize) < items.len() {
+            Ok(index as usize)
+        } else {
+            Err(NativeExtensionsError::OtherError(format!(
+                "clipboard item index {index} out of range"
+            )))
+        }
+    }
+
+    async fn remove_clipboard_item(
+        &self,
+        request: RemoveItemRequest,
+    ) -> NativeExtensionsResult<()> {
+        let mut items = self.owned_items()?;
+        let index = Self::check_index(&items, request.index)?;
+        items.remove(index);
+        self.schedule_write(items).await
+    }
+
+    /// Replaces representations of item at `index` with those of the
+    /// provider; representations in other formats are kept.
+    async fn replace_clipboard_item(
+        &self,
+        isolate_id: IsolateId,
+        request: ReplaceItemRequest,
+    ) -> NativeExtensionsResult<()> {
+        let mut items = self.owned_items()?;
+        let index = Self::check_index(&items, request.index)?;
+        let data_provider_manager = Context::get().data_provider_manager();
+        let merged_id =
+            data_provider_manager.register_merged_provider(&items[index].0, request.provider_id)?;
+        let provider = data_provider_manager.get_platform_data_provider(merged_id)?;
+        // Merged provider keeps Dart providers of both parts alive.
+        let base_handle = items[index].1.clone();
+        let weak_self = self.weak_self.clone();
+        let provider_id = request.provider_id;
+        let notifier = DropNotifier::new(move || {
+            drop(base_handle);
+            Context::get()
+                .data_provider_manager()
+                .unregister_provider(merged_id)
+                .ok_log();
+            if let Some(this) = weak_self.upgrade() {
+                this.release_data_provider(isolate_id, provider_id);
+            }
+        });
+        self.owners.borrow_mut().insert(isolate_id);
+        items[index] = (provider, Arc::new(notifier.into()));
+        self.schedule_write(items).await?;
+        self.schedule_expiration(&[merged_id]);
+        Ok(())
+    }
+
+    async fn move_clipboard_item(&self, request: MoveItemRequest) -> NativeExtensionsResult<()> {
+        let mut items = self.owned_items()?;
+        let from = Self::check_index(&items, request.from_index)?;
+        let to = Self::check_index(&items, request.to_index)?;
+        let item = items.remove(from);
+        items.insert(to, item);
+        self.schedule_write(items).await
+    }
+
+    async fn schedule_write(&self, providers: Vec<ClipboardItem>) -> NativeExtensionsResult<()> {
+        let (future, completer) = FutureCompleter::new();
+        let superseded = self.pending_write.replace(Some(PendingWrite {
+            providers,
+            completer,
+        }));
+        match superseded {
+            // Never made it to the clipboard; dropping the providers releases
+            // them on Dart side.
+            Some(superseded) => superseded.completer.complete(Ok(())),
+            None => {
+                let weak_self = self.weak_self.clone();
+                RunLoop::current()
+                    .schedule_next(move || {
+                        if let Some(this) = weak_self.upgrade() {
+                            this.flush_pending_write();
+                        }
+                    })
+                    .detach();
+            }
+        }
+        future.await
+    }
+
+    fn flush_pending_write(&self) {
+        if let Some(pending) = self.pending_write.take() {
+            let weak_self = self.weak_self.clone();
+            self.tasks.spawn(async move {
+                let providers = pending.providers.clone();
+                let res = PlatformDataProvider::write_to_clipboard(pending.providers).await;
+                if let Some(this) = weak_self.upgrade() {
+                    let contents = res.is_ok().then(|| ClipboardContents {
+                        providers,
+                        change_count: PlatformDataReader::clipboard_change_count()
+                            .ok_log()
+                            .flatten(),
+                    });
+                    this.contents.replace(contents);
+                }
+                pending.completer.complete(res);
+            });
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn write_to_primary_selection(providers: Vec<ClipboardItem>) -> NativeExtensionsResult<()> {
+    PlatformDataProvider::write_to_primary_selection(providers).await
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn write_to_primary_selection(_providers: Vec<ClipboardItem>) -> NativeExtensionsResult<()> {
+    Err(NativeExtensionsError::UnsupportedOperation)
+}
+
+#[cfg(target_os = "macos")]
+async fn write_to_named_pasteboard(
+    providers: Vec<ClipboardItem>,
+    name: &str,
+) -> NativeExtensionsResult<()> {
+    PlatformDataProvider::write_to_named_pasteboard(providers, name).await
+}
+
+#[cfg(not(target_os = "macos"))]
+async fn write_to_named_pasteboard(
+    _providers: Vec<ClipboardItem>,
+    _name: &str,
+) -> NativeExtensionsResult<()> {
+    Err(NativeExtensionsError::UnsupportedOperation)
 }
 
 pub trait GetClipboardWriter {
@@ -76,6 +469,28 @@ impl AsyncMethodHandler for ClipboardWriter {
                 .write_to_clipboard(call.isolate, call.args.try_into()?)
                 .await
                 .into_platform_result(),
+            "writeToSelection" => self
+                .write_to_selection(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "appendToClipboard" => self
+                .append_to_clipboard(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "removeClipboardItem" => self
+                .remove_clipboard_item(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "replaceClipboardItem" => self
+                .replace_clipboard_item(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "moveClipboardItem" => self
+                .move_clipboard_item(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "clearClipboard" => self.clear_clipboard().await.into_platform_result(),
+            "hasClipboardOwnership" => self.has_clipboard_ownership().into_platform_result(),
             _ => Err(PlatformError {
                 code: "invalid_method".into(),
                 message: Some(format!("Unknown Method: {}", call.method)),
@@ -91,4 +506,8 @@ impl AsyncMethodHandler for ClipboardWriter {
     fn assign_invoker(&self, invoker: AsyncMethodInvoker) {
         self.invoker.set(invoker);
     }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        shutdown::shutdown(isolate);
+    }
 }
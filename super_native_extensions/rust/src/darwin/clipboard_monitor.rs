@@ -0,0 +1,80 @@
+use std::{cell::Cell, rc::Weak, time::Duration};
+
+use irondash_message_channel::Late;
+use irondash_run_loop::RunLoop;
+
+use crate::{
+    clipboard_monitor::ClipboardMonitorDelegate, error::NativeExtensionsResult, log::OkLog,
+};
+
+use super::PlatformDataReader;
+
+/// Neither NSPasteboard nor UIPasteboard (while in background) notify about
+/// changes, so the change count is polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct PlatformClipboardMonitor {
+    delegate: Weak<dyn ClipboardMonitorDelegate>,
+    weak_self: Late<Weak<Self>>,
+    /// Incremented on start and stop to invalidate scheduled polls.
+    generation: Cell<u64>,
+    running: Cell<bool>,
+    last_change_count: Cell<Option<i64>>,
+}
+
+impl PlatformClipboardMonitor {
+    pub fn new(delegate: Weak<dyn ClipboardMonitorDelegate>) -> Self {
+        Self {
+            delegate,
+            weak_self: Late::new(),
+            generation: Cell::new(0),
+            running: Cell::new(false),
+            last_change_count: Cell::new(None),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformClipboardMonitor>) {
+        self.weak_self.set(weak);
+    }
+
+    pub fn start(&self) -> NativeExtensionsResult<()> {
+        if !self.running.replace(true) {
+            self.last_change_count
+                .set(PlatformDataReader::clipboard_change_count()?);
+            self.generation.set(self.generation.get() + 1);
+            self.schedule_poll();
+        }
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        self.running.set(false);
+        self.generation.set(self.generation.get() + 1);
+    }
+
+    fn schedule_poll(&self) {
+        let generation = self.generation.get();
+        let weak_self = self.weak_self.clone();
+        RunLoop::current()
+            .schedule(POLL_INTERVAL, move || {
+                if let Some(this) = weak_self.upgrade() {
+                    if this.generation.get() == generation {
+                        this.poll();
+                        this.schedule_poll();
+                    }
+                }
+            })
+            .detach();
+    }
+
+    fn poll(&self) {
+        let change_count = PlatformDataReader::clipboard_change_count()
+            .ok_log()
+            .flatten();
+        if change_count != self.last_change_count.replace(change_count) {
+            if let Some(delegate) = self.delegate.upgrade() {
+                delegate.clipboard_changed();
+            }
+        }
+    }
+}
@@ -2,7 +2,10 @@ use std::{
     ffi::{CStr, OsStr},
     os::unix::prelude::OsStrExt,
     path::PathBuf,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use core_foundation::{
@@ -144,3 +147,20 @@ pub fn uti_conforms_to(uti: &str, conforms_to: &str) -> bool {
 
     conforms_to != 0
 }
+
+/// Pasteboard type present when content comes from another device through
+/// Universal Clipboard. Data of such content is fetched over the network on
+/// first read, which can take several seconds.
+pub const TYPE_REMOTE_CLIPBOARD: &str = "com.apple.is-remote-clipboard";
+
+static EXCLUDE_REMOTE_CLIPBOARD: AtomicBool = AtomicBool::new(false);
+
+/// When set, clipboard readers created afterwards ignore Universal Clipboard
+/// content.
+pub fn set_exclude_remote_clipboard(exclude: bool) {
+    EXCLUDE_REMOTE_CLIPBOARD.store(exclude, Ordering::Relaxed);
+}
+
+pub fn exclude_remote_clipboard() -> bool {
+    EXCLUDE_REMOTE_CLIPBOARD.load(Ordering::Relaxed)
+}
@@ -0,0 +1,117 @@
+use objc2::{
+    class, msg_send, msg_send_id,
+    rc::{autoreleasepool, Id},
+    runtime::NSObject,
+    Encode, Encoding,
+};
+use objc2_foundation::{NSArray, NSError, NSString, NSURL};
+
+use crate::{
+    error::NativeExtensionsResult,
+    link_detection::{format_date, DetectedEntity, EntityKind},
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct NSRange {
+    location: usize,
+    length: usize,
+}
+
+unsafe impl Encode for NSRange {
+    const ENCODING: Encoding = Encoding::Struct("_NSRange", &[usize::ENCODING, usize::ENCODING]);
+}
+
+const NS_TEXT_CHECKING_TYPE_DATE: u64 = 1 << 3;
+const NS_TEXT_CHECKING_TYPE_ADDRESS: u64 = 1 << 4;
+const NS_TEXT_CHECKING_TYPE_LINK: u64 = 1 << 5;
+const NS_TEXT_CHECKING_TYPE_PHONE_NUMBER: u64 = 1 << 11;
+
+/// Runs `NSDataDetector` over the text. Ranges reported by the detector are
+/// already in UTF-16 code units.
+pub fn detect_entities_with_data_detector(
+    text: &str,
+    kinds: &[EntityKind],
+) -> NativeExtensionsResult<Option<Vec<DetectedEntity>>> {
+    let mut types = 0u64;
+    for kind in kinds {
+        types |= match kind {
+            EntityKind::Url | EntityKind::Email => NS_TEXT_CHECKING_TYPE_LINK,
+            EntityKind::PhoneNumber => NS_TEXT_CHECKING_TYPE_PHONE_NUMBER,
+            EntityKind::Date => NS_TEXT_CHECKING_TYPE_DATE,
+            EntityKind::Address => NS_TEXT_CHECKING_TYPE_ADDRESS,
+        };
+    }
+    if types == 0 {
+        return Ok(Some(Vec::new()));
+    }
+    autoreleasepool(|_| unsafe {
+        let detector: Option<Id<NSObject>> = msg_send_id![
+            class!(NSDataDetector),
+            dataDetectorWithTypes: types,
+            error: std::ptr::null_mut::<*mut NSError>()
+        ];
+        let Some(detector) = detector else {
+            return Ok(None);
+        };
+        let string = NSString::from_str(text);
+        let range = NSRange {
+            location: 0,
+            length: string.length(),
+        };
+        let matches: Id<NSArray<NSObject>> =
+            msg_send_id![&detector, matchesInString: &*string, options: 0usize, range: range];
+        let mut res = Vec::new();
+        for result in matches.iter() {
+            let result_type: u64 = msg_send![result, resultType];
+            let range: NSRange = msg_send![result, range];
+            let (kind, value) = match result_type {
+                NS_TEXT_CHECKING_TYPE_LINK => {
+                    let url: Option<Id<NSURL>> = msg_send_id![result, URL];
+                    let url = url
+                        .and_then(|url| url.absoluteString())
+                        .map(|url| url.to_string());
+                    let is_email = url
+                        .as_deref()
+                        .map(|url| url.starts_with("mailto:"))
+                        .unwrap_or(false);
+                    let kind = if is_email {
+                        EntityKind::Email
+                    } else {
+                        EntityKind::Url
+                    };
+                    (kind, url)
+                }
+                NS_TEXT_CHECKING_TYPE_PHONE_NUMBER => {
+                    let number: Option<Id<NSString>> = msg_send_id![result, phoneNumber];
+                    let number = number.map(|number| {
+                        let digits: String = number
+                            .to_string()
+                            .chars()
+                            .filter(|c| c.is_ascii_digit() || *c == '+')
+                            .collect();
+                        format!("tel:{digits}")
+                    });
+                    (EntityKind::PhoneNumber, number)
+                }
+                NS_TEXT_CHECKING_TYPE_DATE => {
+                    let date: Option<Id<NSObject>> = msg_send_id![result, date];
+                    let date = date.map(|date| {
+                        let interval: f64 = msg_send![&date, timeIntervalSince1970];
+                        format_date(interval.floor() as i64)
+                    });
+                    (EntityKind::Date, date)
+                }
+                NS_TEXT_CHECKING_TYPE_ADDRESS => (EntityKind::Address, None),
+                _ => continue,
+            };
+            res.push(DetectedEntity {
+                kind,
+                start: range.location as i64,
+                end: (range.location + range.length) as i64,
+                value,
+            });
+        }
+        Ok(Some(res))
+    })
+}
@@ -1,5 +1,5 @@
 use std::{
-    cell::Cell,
+    cell::{Cell, RefCell},
     collections::HashMap,
     env::temp_dir,
     fs::File,
@@ -11,7 +11,7 @@ use std::{
 
 use block2::RcBlock;
 use irondash_message_channel::{IsolateId, Late};
-use irondash_run_loop::{spawn, util::Capsule, RunLoop, RunLoopSender};
+use irondash_run_loop::{util::Capsule, RunLoop, RunLoopSender};
 use objc2::{
     extern_class, extern_methods, mutability::InteriorMutable, rc::Id, runtime::NSObject, ClassType,
 };
@@ -25,11 +25,11 @@ use crate::{
     data_provider_manager::{
         DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult, VirtualSessionHandle,
     },
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::common::to_nserror,
     util::Movable,
-    value_promise::ValuePromiseResult,
+    value_promise::{ValuePromise, ValuePromiseResult},
 };
 
 use super::{
@@ -191,6 +191,19 @@ impl PlatformDataProvider {
         Ok(())
     }
 
+    pub async fn clear_clipboard() -> NativeExtensionsResult<()> {
+        let array = NSArray::<NSItemProvider>::from_vec(Vec::new());
+        let pasteboard = unsafe { UIPasteboard::generalPasteboard() };
+        unsafe { pasteboard.setItemProviders(&array) };
+        Ok(())
+    }
+
+    /// Item providers load lazy data on demand and can't be rendered
+    /// upfront, so contents must be kept.
+    pub fn flush_clipboard() -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     async fn precache(&self) {
         let to_fetch = {
             let state = self.state.lock().unwrap();
@@ -301,13 +314,22 @@ impl DataProviderSession {
                     callback(None, None);
                     return;
                 }
-                spawn(async move {
-                    let data = source_delegate
-                        .get_lazy_data_async(source.isolate_id, id)
-                        .await;
+                // Promise is set (or cancelled when isolate is destroyed)
+                // before `on_done` is invoked. When scope of the isolate is
+                // already closed `on_done` runs before the promise is stored.
+                let promise = Rc::new(RefCell::new(None::<Arc<ValuePromise>>));
+                let promise_clone = promise.clone();
+                let on_done = Box::new(move || {
+                    let data = promise_clone
+                        .borrow_mut()
+                        .take()
+                        .and_then(|p| p.try_take())
+                        .unwrap_or(ValuePromiseResult::Cancelled);
                     let data = value_promise_res_to_nsdata(&data);
                     callback(data.as_deref(), None);
                 });
+                let res = source_delegate.get_lazy_data(source.isolate_id, id, Some(on_done));
+                promise.replace(Some(res));
             }
             None => {
                 callback(None, None);
@@ -460,8 +482,8 @@ impl DataProviderSession {
                     id,
                     stream_handle,
                     Box::new(move |_| {}),
-                    Box::new(move |cnt| {
-                        let completed = (cnt * 1000.0).round() as i64;
+                    Box::new(move |update| {
+                        let completed = (update.fraction.unwrap_or(0.0) * 1000.0).round() as i64;
                         unsafe { progress_clone.setCompletedUnitCount(completed) };
                     }),
                     Box::new(move |result| match result {
@@ -12,18 +12,21 @@ use irondash_message_channel::{Late, Value};
 use irondash_run_loop::{platform::PollSession, RunLoop};
 use objc2::{
     declare_class, msg_send_id, mutability,
-    rc::Id,
+    rc::{Id, WeakId},
     runtime::{NSObject, NSObjectProtocol, ProtocolObject},
     ClassType, DeclaredClass,
 };
 use objc2_foundation::{ns_string, CGPoint, CGRect, NSArray, NSDictionary, NSNumber};
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DragConfiguration, DragRequest, DropOperation, KeyModifiers, Point,
+        TargettedImage,
+    },
     data_provider_manager::DataProviderHandle,
     drag_manager::{
         DataProviderEntry, DragSessionId, GetAdditionalItemsResult, GetDragConfigurationResult,
-        PlatformDragContextDelegate, PlatformDragContextId,
+        PlatformDragContextDelegate, PlatformDragContextId, UpdateDragImageRequest,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     platform_impl::platform::os::util::IgnoreInteractionEvents,
@@ -67,9 +70,11 @@ struct Session {
     weak_self: Late<Weak<Self>>,
     in_progress: Cell<bool>,
     sent_did_end: Cell<bool>,
+    last_location: RefCell<Point>,
     configuration: RefCell<DragConfiguration>,
     data_providers: RefCell<Vec<Arc<DataProviderHandle>>>,
     views: RefCell<HashMap<(usize, ImageType), Id<UIImageView>>>, // index -> view
+    drag_items: RefCell<Vec<WeakId<UIDragItem>>>,
 }
 
 impl Session {
@@ -94,10 +99,12 @@ impl Session {
             weak_self: Late::new(),
             in_progress: Cell::new(false),
             sent_did_end: Cell::new(false),
+            last_location: RefCell::new(Point::default()),
             session_id,
             configuration: RefCell::new(configuration),
             data_providers: RefCell::new(Vec::new()),
             views: RefCell::new(HashMap::new()),
+            drag_items: RefCell::new(Vec::new()),
         }
     }
 
@@ -134,9 +141,12 @@ impl Session {
             // lift is complete. So instead we set it later in `will_begin`. After `will_begin`
             // it is safe to set preview provider as it will be picked immediately and won't leak.
             if self.in_progress.get() {
-                self.set_preview_provider(&drag_item);
+                self.set_preview_provider(&drag_item, false);
             }
 
+            self.drag_items
+                .borrow_mut()
+                .push(WeakId::from_id(&drag_item));
             drag_item
         }
     }
@@ -206,17 +216,19 @@ impl Session {
         }
     }
 
-    unsafe fn set_preview_provider(&self, item: &UIDragItem) {
+    /// With `replace` the preview provider is set even if item already has
+    /// one or has no lift image.
+    unsafe fn set_preview_provider(&self, item: &UIDragItem, replace: bool) {
         let preview_provider = item.previewProvider();
         // If lift image is specified now create preview provider for dragging.
         // If this is done when creating items the whole session leaks...
-        if preview_provider.is_none() {
+        if preview_provider.is_none() || replace {
             let Some((index, _)) = PlatformDragContext::item_info(item) else {
                 return;
             };
             let configuration = self.configuration.borrow();
             let drag_item = &configuration.items[index];
-            if drag_item.lift_image.is_none() {
+            if drag_item.lift_image.is_none() && !replace {
                 return;
             }
             let image = self.image_view_for_item(index, ImageType::Drag);
@@ -246,15 +258,56 @@ impl Session {
                 // workaround for memory leak, see [create_item].
                 let items = session.items();
                 for item in items.iter() {
-                    self.set_preview_provider(item);
+                    self.set_preview_provider(item, false);
                 }
             }
         }
     }
 
+    fn update_drag_image(&self, images: &[TargettedImage]) {
+        {
+            let mut configuration = self.configuration.borrow_mut();
+            for (item, image) in configuration.items.iter_mut().zip(images) {
+                item.image = image.clone();
+            }
+        }
+        // Drag image views are recreated from updated configuration.
+        let mut removed = Vec::new();
+        self.views.borrow_mut().retain(|(_, ty), view| {
+            if *ty == ImageType::Drag {
+                removed.push(view.clone());
+                false
+            } else {
+                true
+            }
+        });
+        for view in removed {
+            unsafe { view.removeFromSuperview() };
+        }
+        if !self.in_progress.get() {
+            // Preview providers will be set when drag begins.
+            return;
+        }
+        let items: Vec<_> = self
+            .drag_items
+            .borrow()
+            .iter()
+            .filter_map(|item| item.load())
+            .collect();
+        for item in items {
+            unsafe { self.set_preview_provider(&item, true) };
+        }
+    }
+
     fn did_move(&self, _session: &ProtocolObject<dyn UIDragSession>, location: Point) {
+        self.last_location.replace(location.clone());
         if let Some(delegate) = self.context_delegate.upgrade() {
-            delegate.drag_session_did_move_to_location(self.context_id, self.session_id, location);
+            delegate.drag_session_did_move_to_location(
+                self.context_id,
+                self.session_id,
+                location,
+                PlatformDragContext::current_modifiers(),
+            );
         }
     }
 
@@ -268,6 +321,7 @@ impl Session {
                 self.context_id,
                 self.session_id,
                 DropOperation::from_platform(operation),
+                None,
             );
         }
     }
@@ -306,10 +360,18 @@ impl Session {
             return; // already cancelled
         }
         if let Some(delegate) = self.context_delegate.upgrade() {
+            if !self.animates_on_cancel() {
+                delegate.drag_session_did_cancel(
+                    self.context_id,
+                    self.session_id,
+                    self.last_location.borrow().clone(),
+                );
+            }
             delegate.drag_session_did_end_with_operation(
                 self.context_id,
                 self.session_id,
                 DropOperation::None,
+                None,
             );
         }
     }
@@ -367,7 +429,13 @@ impl Session {
         }
     }
 
-    fn preview_for_canceling(&self, index: usize) -> Id<UITargetedDragPreview> {
+    fn animates_on_cancel(&self) -> bool {
+        self.configuration
+            .borrow()
+            .animates_to_starting_position_on_cancel_or_fail
+    }
+
+    fn preview_for_canceling(&self, index: usize) -> Option<Id<UITargetedDragPreview>> {
         let view_container = self.view_container.clone();
         // Fade the container view out. UIKit seems to keep the view
         // visible for way too long after cancellation, which is obvious
@@ -398,7 +466,12 @@ impl Session {
             })
             .detach();
 
-        self.preview_for_item_type(index, ImageType::Lift)
+        // Without cancel preview UIKit fades the item out in place.
+        if self.animates_on_cancel() {
+            Some(self.preview_for_item_type(index, ImageType::Lift))
+        } else {
+            None
+        }
     }
 }
 
@@ -412,6 +485,7 @@ impl Drop for Session {
                     self.context_id,
                     self.session_id,
                     DropOperation::UserCancelled,
+                    None,
                 );
             }
         }
@@ -479,6 +553,15 @@ impl PlatformDragContext {
         false
     }
 
+    /// UIKit sessions can't be ended early; they end when the interaction
+    /// is removed from the view as the context is released.
+    pub fn cancel_sessions(&self) {}
+
+    /// Modifier state is not available for touch drags.
+    pub fn current_modifiers() -> KeyModifiers {
+        KeyModifiers::default()
+    }
+
     pub async fn start_drag(
         &self,
         _request: DragRequest,
@@ -674,7 +757,7 @@ impl PlatformDragContext {
             .borrow()
             .get(&session_id)
             .cloned()
-            .map(|session| session.preview_for_canceling(index))
+            .and_then(|session| session.preview_for_canceling(index))
     }
 
     fn prefers_full_size_previews(
@@ -709,12 +792,27 @@ impl PlatformDragContext {
         }
     }
 
-    pub fn get_local_data(
+    /// Session and local data of items of local drag.
+    pub fn get_local_session(
         &self,
         session: &ProtocolObject<dyn UIDragSession>,
-    ) -> Option<Vec<Value>> {
+    ) -> Option<(DragSessionId, Vec<Value>)> {
         self.get_session(session)
-            .map(|s| s.configuration.borrow().get_local_data())
+            .map(|s| (s.session_id, s.configuration.borrow().get_local_data()))
+    }
+
+    pub fn update_drag_image(
+        &self,
+        request: &UpdateDragImageRequest,
+    ) -> NativeExtensionsResult<()> {
+        let session = self
+            .sessions
+            .borrow()
+            .get(&request.session_id)
+            .cloned()
+            .ok_or(NativeExtensionsError::DragSessionNotFound)?;
+        session.update_drag_image(&request.images);
+        Ok(())
     }
 
     pub fn get_local_data_for_session_id(
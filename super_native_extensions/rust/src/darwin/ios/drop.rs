@@ -18,7 +18,7 @@ use objc2::{
 use objc2_foundation::{CGPoint, CGRect};
 
 use crate::{
-    api_model::{DropOperation, Size},
+    api_model::{DropOperation, Point, Size},
     drop_manager::{
         BaseDropEvent, DropEvent, DropItem, DropItemId, DropSessionId, ItemPreview,
         ItemPreviewRequest, PlatformDropContextDelegate, PlatformDropContextId,
@@ -107,16 +107,14 @@ impl Session {
 
         // local data
         let local_session = unsafe { self.platform_session.localDragSession() };
-        let local_data = local_session
-            .and_then(|session| {
-                let drag_contexts = delegate.get_platform_drag_contexts();
-                drag_contexts
-                    .iter()
-                    .map(|c| c.get_local_data(&session))
-                    .find(|c| c.is_some())
-                    .flatten()
-            })
-            .unwrap_or_default();
+        let local_session = local_session.and_then(|session| {
+            let drag_contexts = delegate.get_platform_drag_contexts();
+            drag_contexts
+                .iter()
+                .find_map(|c| c.get_local_session(&session))
+        });
+        let local_session_id = local_session.as_ref().map(|s| s.0);
+        let local_data = local_session.map(|s| s.1).unwrap_or_default();
 
         // formats
         let mut items = Vec::new();
@@ -138,6 +136,7 @@ impl Session {
                 item_id: item.item_id(),
                 formats,
                 local_data,
+                allowed_operations: None,
             });
         }
 
@@ -156,6 +155,10 @@ impl Session {
 
         Ok(DropEvent {
             session_id: self.session_id(),
+            engine_handle: self.context_id.engine_handle,
+            region_id: None,
+            session_local_data: Value::Null,
+            local_session_id,
             location_in_view: location.into(),
             allowed_operations,
             items,
@@ -224,6 +227,7 @@ impl Session {
                 self.context_id,
                 BaseDropEvent {
                     session_id: self.session_id(),
+                    engine_handle: self.context_id.engine_handle,
                 },
             );
         }
@@ -236,6 +240,7 @@ impl Session {
                 self.context_id,
                 BaseDropEvent {
                     session_id: self.session_id(),
+                    engine_handle: self.context_id.engine_handle,
                 },
             );
         }
@@ -385,6 +390,22 @@ impl PlatformDropContext {
         })
     }
 
+    // Item loading must start during performDrop; UIKit already presents
+    // progress for drops that take long to load.
+    pub fn supports_deferred_drop(&self) -> bool {
+        false
+    }
+
+    pub fn show_busy_indicator(
+        &self,
+        _session_id: DropSessionId,
+        _location: &Point,
+    ) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+
+    pub fn hide_busy_indicator(&self, _session_id: DropSessionId) {}
+
     pub fn register_drop_formats(&self, _formats: &[String]) -> NativeExtensionsResult<()> {
         Ok(())
     }
@@ -0,0 +1,41 @@
+//! Platform codec for the `image_transcode` feature, based on `UIImage`.
+
+use objc2::rc::{autoreleasepool, Id};
+use objc2_foundation::NSData;
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+use super::uikit::{UIImage, UIImagePNGRepresentation};
+
+pub fn png_format() -> String {
+    "public.png".into()
+}
+
+pub fn decodable_formats() -> Vec<String> {
+    vec![
+        "public.jpeg".into(),
+        "public.heic".into(),
+        "public.tiff".into(),
+        "com.microsoft.bmp".into(),
+    ]
+}
+
+/// iOS applications read PNG directly.
+pub fn encodable_formats() -> Vec<String> {
+    Vec::new()
+}
+
+pub fn decode_to_png(data: &[u8]) -> NativeExtensionsResult<Vec<u8>> {
+    autoreleasepool(|_| unsafe {
+        let data = NSData::with_bytes(data);
+        let image = UIImage::imageWithData(&data)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Failed to decode image".into()))?;
+        let png = Id::retain(UIImagePNGRepresentation(&image))
+            .ok_or_else(|| NativeExtensionsError::OtherError("Failed to encode image".into()))?;
+        Ok(png.bytes().to_vec())
+    })
+}
+
+pub fn encode_from_png(_data: &[u8], _format: &str) -> NativeExtensionsResult<Vec<u8>> {
+    Err(NativeExtensionsError::UnsupportedOperation)
+}
@@ -13,5 +13,9 @@ impl PlatformKeyboardLayout {
         None
     }
 
+    pub fn get_current_layout_name(&self) -> Option<String> {
+        None
+    }
+
     pub fn assign_weak_self(&self, _weak: Weak<PlatformKeyboardLayout>) {}
 }
@@ -10,7 +10,7 @@ use std::{
 use block2::RcBlock;
 use irondash_engine_context::EngineContext;
 use irondash_message_channel::{IsolateId, Late};
-use irondash_run_loop::{platform::PollSession, spawn, RunLoop};
+use irondash_run_loop::{platform::PollSession, RunLoop};
 use objc2_foundation::{CGPoint, CGRect, CGSize, NSArray, NSString};
 
 use objc2::{
@@ -23,7 +23,7 @@ use objc2::{
 use crate::{
     api_model::{
         ImageData, Menu, MenuActionState, MenuConfiguration, MenuElement, MenuImage,
-        ShowContextMenuRequest, ShowContextMenuResponse,
+        MenuPreviewCommitStyle, ShowContextMenuRequest, ShowContextMenuResponse,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
@@ -39,9 +39,10 @@ use super::{
     uikit::{
         UIAction, UIActivityIndicatorView, UIActivityIndicatorViewStyleMedium, UIColor,
         UIContextMenuConfiguration, UIContextMenuInteraction, UIContextMenuInteractionAnimating,
-        UIContextMenuInteractionDelegate, UIDeferredMenuElement,
-        UIDeferredMenuElementCompletionBlock, UIImage, UIImageView, UIMenuElement,
-        UIMenuElementAttributes, UIMenuElementAttributesDestructive,
+        UIContextMenuInteractionCommitAnimating, UIContextMenuInteractionCommitStyleDismiss,
+        UIContextMenuInteractionCommitStylePop, UIContextMenuInteractionDelegate,
+        UIDeferredMenuElement, UIDeferredMenuElementCompletionBlock, UIImage, UIImageView,
+        UIMenuElement, UIMenuElementAttributes, UIMenuElementAttributesDestructive,
         UIMenuElementAttributesDisabled, UIMenuElementState, UIMenuElementStateMixed,
         UIMenuElementStateOff, UIMenuElementStateOn, UIPreviewParameters, UIPreviewTarget,
         UITargetedPreview, UIView, UIViewAnimationOptionNone, UIViewController,
@@ -91,6 +92,16 @@ impl PlatformMenu {
         Ok(Rc::new(res))
     }
 
+    /// UIMenu copies its children, so actions can not be updated after the
+    /// menu was created.
+    pub fn update_item_state(
+        &self,
+        _unique_id: i64,
+        _state: MenuActionState,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     unsafe fn convert_string(str: &Option<String>) -> Option<Id<NSString>> {
         str.as_ref().map(|str| NSString::from_str(str))
     }
@@ -221,52 +232,94 @@ impl PlatformMenu {
                 );
                 Ok(Id::into_super(menu))
             }
-            MenuElement::Deferred(deferred) => {
-                let delegate = delegate.clone();
-                let provider = RcBlock::new(
-                    move |completion_block: NonNull<UIDeferredMenuElementCompletionBlock>| {
-                        let delegate = delegate.clone();
-                        let item_selected = item_selected.clone();
-                        let completion_block =
-                            unsafe { RcBlock::copy(completion_block.as_ptr()).unwrap() };
-                        spawn(async move {
-                            if let Some(delegate) = delegate.upgrade() {
-                                let menu = delegate
-                                    .get_deferred_menu(isolate_id, deferred.unique_id)
-                                    .await;
-
-                                let array = match menu {
-                                    Ok(elements) => {
-                                        let elements = Self::convert_elements(
-                                            elements,
-                                            isolate_id,
-                                            &Rc::downgrade(&delegate),
-                                            item_selected,
-                                        );
-                                        match elements {
-                                            Ok(elements) => Some(NSArray::from_vec(elements)),
-                                            Err(_) => None,
-                                        }
-                                    }
-                                    Err(_) => None,
-                                };
-                                let array = array.unwrap_or_default();
-                                let array =
-                                    unsafe { NonNull::new_unchecked(Id::as_ptr(&array) as *mut _) };
-                                completion_block.call((array,));
-                            }
-                        });
-                    },
+            MenuElement::Deferred(deferred) => Ok(Self::deferred_element(
+                deferred.unique_id,
+                false,
+                isolate_id,
+                delegate,
+                item_selected,
+            )),
+            MenuElement::DeferredMenu(menu) => {
+                // UIKit shows loading indicator until the element provides
+                // the children.
+                let children = NSArray::from_vec(vec![Self::deferred_element(
+                    menu.unique_id,
+                    true,
+                    isolate_id,
+                    delegate,
+                    item_selected,
+                )]);
+                let menu = UIMenu::menuWithTitle_image_identifier_options_children(
+                    &Self::convert_string(&menu.title).unwrap_or_default(),
+                    Self::convert_image(&menu.image).as_deref(),
+                    Self::convert_string(&menu.identifier).as_deref(),
+                    0,
+                    &children,
                 );
-
-                let res = UIDeferredMenuElement::elementWithProvider(&provider);
-                Ok(Id::into_super(res))
+                Ok(Id::into_super(menu))
             }
             MenuElement::Separator(_separator) => {
                 panic!("Separator should be converted to inline section")
             }
         }
     }
+
+    unsafe fn deferred_element(
+        unique_id: i64,
+        submenu: bool,
+        isolate_id: IsolateId,
+        delegate: &Weak<dyn PlatformMenuDelegate>,
+        item_selected: Rc<Cell<bool>>,
+    ) -> Id<UIMenuElement> {
+        let delegate = delegate.clone();
+        let provider = RcBlock::new(
+            move |completion_block: NonNull<UIDeferredMenuElementCompletionBlock>| {
+                let delegate = delegate.clone();
+                let item_selected = item_selected.clone();
+                let completion_block = unsafe { RcBlock::copy(completion_block.as_ptr()).unwrap() };
+                let Some(spawner) = delegate.upgrade() else {
+                    return;
+                };
+                spawner.spawn(
+                    isolate_id,
+                    Box::pin(async move {
+                        if let Some(delegate) = delegate.upgrade() {
+                            let menu = if submenu {
+                                delegate
+                                    .get_deferred_menu_items(isolate_id, unique_id)
+                                    .await
+                            } else {
+                                delegate.get_deferred_menu(isolate_id, unique_id).await
+                            };
+
+                            let array = match menu {
+                                Ok(elements) => {
+                                    let elements = Self::convert_elements(
+                                        elements,
+                                        isolate_id,
+                                        &Rc::downgrade(&delegate),
+                                        item_selected,
+                                    );
+                                    match elements {
+                                        Ok(elements) => Some(NSArray::from_vec(elements)),
+                                        Err(_) => None,
+                                    }
+                                }
+                                Err(_) => None,
+                            };
+                            let array = array.unwrap_or_default();
+                            let array =
+                                unsafe { NonNull::new_unchecked(Id::as_ptr(&array) as *mut _) };
+                            completion_block.call((array,));
+                        }
+                    }),
+                );
+            },
+        );
+
+        let res = UIDeferredMenuElement::elementWithProvider(&provider);
+        Id::into_super(res)
+    }
 }
 
 struct MenuSession {
@@ -433,7 +486,32 @@ impl PlatformMenuContext {
                     });
                     Some(preview_provider)
                 }
-                _ => None,
+                _ => menu_configuration.preview_rect.as_ref().map(|rect| {
+                    let controller = view_controller.retain();
+                    let flutter_view = self.view.retain();
+                    let rect: CGRect = rect.clone().into();
+                    RcBlock::new(move || {
+                        // Snapshot is taken when the preview is about to be shown
+                        // so that it reflects current content of the region.
+                        let container = UIView::initWithFrame(
+                            UIView::alloc(),
+                            CGRect::new(CGPoint::ZERO, rect.size),
+                        );
+                        container.setClipsToBounds(true);
+                        if let Some(snapshot) = flutter_view.snapshotViewAfterScreenUpdates(false) {
+                            let mut frame = flutter_view.bounds();
+                            frame.origin = CGPoint {
+                                x: -rect.origin.x,
+                                y: -rect.origin.y,
+                            };
+                            snapshot.setFrame(frame);
+                            container.addSubview(&snapshot);
+                        }
+                        controller.setView(Some(&container));
+                        controller.setPreferredContentSize(rect.size);
+                        Id::autorelease_return(controller.retain())
+                    })
+                }),
             };
 
             UIContextMenuConfiguration::configurationWithIdentifier_previewProvider_actionProvider(
@@ -565,12 +643,19 @@ impl PlatformMenuContext {
         &self,
         _interaction: &UIContextMenuInteraction,
         configuration: &UIContextMenuConfiguration,
-        _animator: &ProtocolObject<dyn UIContextMenuInteractionAnimating>,
+        animator: &ProtocolObject<dyn UIContextMenuInteractionCommitAnimating>,
     ) {
         let sessions = self.sessions.borrow();
         let session = sessions.get(&MenuSession::get_id(configuration));
         let delegate = self.delegate.upgrade();
         if let (Some(session), Some(delegate)) = (session, delegate) {
+            if let Some(style) = session.configuration.preview_commit_style {
+                let style = match style {
+                    MenuPreviewCommitStyle::Dismiss => UIContextMenuInteractionCommitStyleDismiss,
+                    MenuPreviewCommitStyle::Pop => UIContextMenuInteractionCommitStylePop,
+                };
+                unsafe { animator.setPreferredCommitStyle(style) };
+            }
             delegate.on_preview_action(self.id, session.configuration.configuration_id);
         }
     }
@@ -698,7 +783,7 @@ declare_class!(
             &self,
             interaction: &UIContextMenuInteraction,
             configuration: &UIContextMenuConfiguration,
-            animator: &ProtocolObject<dyn UIContextMenuInteractionAnimating>,
+            animator: &ProtocolObject<dyn UIContextMenuInteractionCommitAnimating>,
         ) {
             self.ivars().with_state(
                 |state| {
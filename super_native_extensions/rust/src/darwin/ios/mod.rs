@@ -4,10 +4,13 @@ mod drag;
 mod drag_common;
 mod drop;
 mod hot_key;
+#[cfg(feature = "image_transcode")]
+pub mod image_codec;
 mod keyboard_layout;
 mod menu;
 mod objc_drop_notifier;
 mod reader;
+mod tray_icon;
 mod util;
 
 pub use data_provider::*;
@@ -17,6 +20,7 @@ pub use hot_key::*;
 pub use keyboard_layout::*;
 pub use menu::*;
 pub use reader::*;
+pub use tray_icon::*;
 
 #[allow(non_upper_case_globals)]
 #[allow(non_snake_case)]
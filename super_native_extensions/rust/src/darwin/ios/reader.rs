@@ -2,7 +2,7 @@ use std::{
     cell::RefCell,
     fs::{self, File},
     io::Read,
-    path::PathBuf,
+    path::{Path, PathBuf},
     ptr::NonNull,
     rc::{Rc, Weak},
     sync::{Arc, Mutex},
@@ -17,6 +17,7 @@ use irondash_run_loop::{
     util::{Capsule, FutureCompleter},
     RunLoop,
 };
+use log::info;
 use objc2_foundation::{
     NSArray, NSCopying, NSData, NSError, NSFileCoordinator, NSFileCoordinatorReadingOptions,
     NSItemProvider, NSPropertyListReadOptions, NSPropertyListSerialization, NSString, NSURL,
@@ -30,12 +31,21 @@ use objc2::{
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
+    link_detection::{DetectedEntity, EntityKind},
     log::OkLog,
+    media_info::MediaMetadata,
     platform_impl::platform::{
-        common::{path_from_url, uti_conforms_to, NSURLSecurtyScopeAccess},
+        common::{
+            exclude_remote_clipboard, path_from_url, set_exclude_remote_clipboard, uti_conforms_to,
+            NSURLSecurtyScopeAccess, TYPE_REMOTE_CLIPBOARD,
+        },
+        data_detector::detect_entities_with_data_detector,
         progress_bridge::bridge_progress,
     },
-    reader_manager::{ReadProgress, VirtualFileReader},
+    reader_manager::{
+        ExternalReaderSource, FormatConversion, ItemFormatConversion, ItemMetadata,
+        ReadProgressHandle, VirtualFileReader,
+    },
     util::{get_target_path, Movable},
     value_promise::Promise,
 };
@@ -44,6 +54,10 @@ use super::uikit::{UIDragItem, UIPasteboard};
 
 pub struct PlatformDataReader {
     source: ReaderSource,
+    /// Content comes from another device through Universal Clipboard.
+    is_remote: bool,
+    /// Remote content was excluded when reader was created.
+    remote_excluded: bool,
 }
 
 enum ReaderSource {
@@ -53,6 +67,9 @@ enum ReaderSource {
 
 impl PlatformDataReader {
     fn get_items_providers(&self) -> Vec<Id<NSItemProvider>> {
+        if self.remote_excluded {
+            return Vec::new();
+        }
         match &self.source {
             ReaderSource::Pasteboard(pasteboard) => {
                 let providers = unsafe { pasteboard.itemProviders() };
@@ -131,7 +148,7 @@ impl PlatformDataReader {
         &self,
         item: i64,
         format: String,
-        read_progress: Option<Arc<ReadProgress>>,
+        read_progress: Option<ReadProgressHandle>,
     ) -> NativeExtensionsResult<Value> {
         let (future, completer) = FutureCompleter::new();
         unsafe {
@@ -173,12 +190,106 @@ impl PlatformDataReader {
         future.await
     }
 
-    pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
+    fn new_with_pasteboard(pasteboard: Id<UIPasteboard>) -> Rc<Self> {
+        // Checking types does not fetch remote data.
+        let is_remote = unsafe { pasteboard.types() }
+            .iter()
+            .any(|t| t.to_string() == TYPE_REMOTE_CLIPBOARD);
         let res = Rc::new(Self {
-            source: ReaderSource::Pasteboard(unsafe { UIPasteboard::generalPasteboard() }),
+            source: ReaderSource::Pasteboard(pasteboard),
+            is_remote,
+            remote_excluded: is_remote && exclude_remote_clipboard(),
         });
         res.assign_weak_self(Rc::downgrade(&res));
-        Ok(res)
+        res
+    }
+
+    /// Formats from which `fileUriList` is synthesized.
+    pub fn file_uri_list_source_formats() -> Vec<String> {
+        vec!["public.file-url".into()]
+    }
+
+    pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
+        Ok(Self::new_with_pasteboard(unsafe {
+            UIPasteboard::generalPasteboard()
+        }))
+    }
+
+    /// Whether reading data may pull it from another device. Progress of
+    /// such transfer is reported through `ReadProgressHandle` of the data request.
+    pub fn is_remote_content(&self) -> NativeExtensionsResult<bool> {
+        Ok(self.is_remote)
+    }
+
+    pub fn set_exclude_remote_content(exclude: bool) -> NativeExtensionsResult<()> {
+        set_exclude_remote_clipboard(exclude);
+        Ok(())
+    }
+
+    /// Change count of the general pasteboard. Unlike reading items this
+    /// does not trigger the paste permission prompt.
+    pub fn clipboard_change_count() -> NativeExtensionsResult<Option<i64>> {
+        let change_count = unsafe { UIPasteboard::generalPasteboard().changeCount() };
+        Ok(Some(change_count as i64))
+    }
+
+    /// Neither the item count, types of the first item nor the `has*`
+    /// properties trigger the paste permission prompt, so the answer is based
+    /// on them alone. Item providers are never accessed.
+    pub fn clipboard_has_any_format(formats: &[String]) -> NativeExtensionsResult<Option<bool>> {
+        let pasteboard = unsafe { UIPasteboard::generalPasteboard() };
+        let count = unsafe { pasteboard.numberOfItems() };
+        if count == 0 {
+            return Ok(Some(false));
+        }
+        let types = unsafe { pasteboard.types() };
+        if types.iter().any(|t| formats.contains(&t.to_string())) {
+            return Ok(Some(true));
+        }
+        let requested = |uti: &str| formats.iter().any(|f| uti_conforms_to(f, uti));
+        let res = unsafe {
+            (pasteboard.hasStrings() && requested("public.text"))
+                || (pasteboard.hasURLs() && requested("public.url"))
+                || (pasteboard.hasImages() && requested("public.image"))
+        };
+        Ok(Some(res))
+    }
+
+    pub fn detect_entities(
+        text: &str,
+        kinds: &[EntityKind],
+    ) -> NativeExtensionsResult<Option<Vec<DetectedEntity>>> {
+        detect_entities_with_data_detector(text, kinds)
+    }
+
+    pub fn new_with_external_source(
+        source: ExternalReaderSource,
+    ) -> NativeExtensionsResult<Rc<Self>> {
+        match source {
+            ExternalReaderSource::PasteboardName { name } => {
+                let pasteboard = unsafe {
+                    UIPasteboard::pasteboardWithName_create(&NSString::from_str(&name), false)
+                }
+                .ok_or_else(|| {
+                    NativeExtensionsError::OtherError(format!("Pasteboard {name} not found"))
+                })?;
+                Ok(Self::new_with_pasteboard(pasteboard))
+            }
+            _ => Err(NativeExtensionsError::UnsupportedOperation),
+        }
+    }
+
+    /// Out of process reading is only supported on Windows.
+    pub fn set_out_of_process_reading(_enabled: bool) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Formats are identified by name already; there is nothing to register.
+    pub fn register_clipboard_format(
+        _identifier: &str,
+        _platform_name: &str,
+    ) -> NativeExtensionsResult<()> {
+        Ok(())
     }
 
     pub fn new_with_drop_session_items(
@@ -186,11 +297,22 @@ impl PlatformDataReader {
     ) -> NativeExtensionsResult<Rc<Self>> {
         let res = Rc::new(Self {
             source: ReaderSource::DropSessionItems(items),
+            is_remote: false,
+            remote_excluded: false,
         });
         res.assign_weak_self(Rc::downgrade(&res));
         Ok(res)
     }
 
+    pub fn set_format_conversion_enabled(&self, _conversion: FormatConversion, _enabled: bool) {}
+
+    pub fn get_format_conversions_for_item(
+        &self,
+        _item: i64,
+    ) -> NativeExtensionsResult<Vec<ItemFormatConversion>> {
+        Ok(Vec::new())
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
@@ -199,6 +321,30 @@ impl PlatformDataReader {
         Ok(false)
     }
 
+    /// Metafiles are Windows only.
+    pub async fn rasterize_metafile_for_item(
+        &self,
+        _item: i64,
+        _width: Option<i64>,
+        _height: Option<i64>,
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Platform provides no item metadata.
+    pub async fn get_item_metadata(&self, _item: i64) -> NativeExtensionsResult<ItemMetadata> {
+        Ok(ItemMetadata::default())
+    }
+
+    /// No platform media API is used here; media info is parsed from the
+    /// container instead.
+    pub async fn get_media_metadata_for_item(
+        &self,
+        _item: i64,
+    ) -> NativeExtensionsResult<Option<MediaMetadata>> {
+        Ok(None)
+    }
+
     pub async fn can_copy_virtual_file_for_item(
         &self,
         item: i64,
@@ -240,7 +386,7 @@ impl PlatformDataReader {
         &self,
         item: i64,
         format: &str,
-        read_progress: Arc<ReadProgress>,
+        read_progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
         let providers = self.get_items_providers();
         if item >= providers.len() as i64 {
@@ -284,16 +430,88 @@ impl PlatformDataReader {
                 &block,
             )
         };
+        bridge_progress(ns_progress, read_progress.clone());
+        match future.await {
+            // Providers backed by file promises (such as videos from Photos)
+            // may not support opening in place.
+            Err(err) => {
+                info!("In place file representation not available ({err}), loading copy");
+                self.load_file_representation(item, format, read_progress)
+                    .await
+            }
+            res => res,
+        }
+    }
+
+    /// Loads copy of the item through `loadFileRepresentationForTypeIdentifier`.
+    /// The copy is removed once the completion handler returns, so it is
+    /// opened in the handler and read through the open descriptor.
+    async fn load_file_representation(
+        &self,
+        item: i64,
+        format: &str,
+        read_progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        let providers = self.get_items_providers();
+        if item >= providers.len() as i64 {
+            return Err(NativeExtensionsError::OtherError("Invalid item".into()));
+        }
+        let (future, completer) = FutureCompleter::new();
+
+        // travels between threads, must be refcounted because block is Fn
+        let completer = Arc::new(Mutex::new(Capsule::new(completer)));
+        let provider = &providers[item as usize];
+        let sender = RunLoop::current().new_sender();
+        let block = RcBlock::new(move |url: *mut NSURL, error: *mut NSError| {
+            let url = unsafe { Id::retain(url) };
+            let error = unsafe { Id::retain(error) };
+            let res = match (url, error) {
+                (Some(url), _) => FileRepresentationReader::open(&path_from_url(&url)),
+                (_, Some(error)) => Err(NativeExtensionsError::VirtualFileReceiveError(
+                    error.localizedDescription().to_string(),
+                )),
+                (_, _) => Err(NativeExtensionsError::VirtualFileReceiveError(
+                    "Unknown error".into(),
+                )),
+            };
+            let completer = completer.clone();
+            sender.send(move || {
+                let completer = completer
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .expect("Block invoked more than once");
+                let res = res.map::<Option<Rc<dyn VirtualFileReader>>, _>(|f| Some(Rc::new(f)));
+                completer.complete(res);
+            });
+        });
+        let ns_progress = unsafe {
+            provider.loadFileRepresentationForTypeIdentifier_completionHandler(
+                &NSString::from_str(format),
+                &block,
+            )
+        };
         bridge_progress(ns_progress, read_progress);
         future.await
     }
 
+    /// Loads file representation of the item so that data is read from disk
+    /// in chunks instead of being loaded into memory.
+    pub async fn get_data_stream_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        self.load_file_representation(item, format, progress).await
+    }
+
     pub async fn copy_virtual_file_for_item(
         &self,
         item: i64,
         format: &str,
         target_folder: PathBuf,
-        read_progress: Arc<ReadProgress>,
+        read_progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<PathBuf> {
         let (future, completer) = FutureCompleter::new();
         let providers = self.get_items_providers();
@@ -313,8 +531,8 @@ impl PlatformDataReader {
                     let source_path = path_from_url(&url);
                     let source_name = source_path
                         .file_name()
-                        .expect("Missing file name")
-                        .to_string_lossy();
+                        .map(|name| name.to_string_lossy())
+                        .unwrap_or_default();
                     let target_path = get_target_path(&target_folder, &source_name);
                     match fs::rename(&source_path, &target_path) {
                         Ok(_) => Ok(target_path),
@@ -354,6 +572,55 @@ impl PlatformDataReader {
     pub fn assign_weak_self(&self, _weak: Weak<PlatformDataReader>) {}
 }
 
+struct FileRepresentationReader {
+    file: RefCell<Option<File>>,
+    file_name: Option<String>,
+    file_size: Option<i64>,
+}
+
+impl FileRepresentationReader {
+    fn open(path: &Path) -> NativeExtensionsResult<Self> {
+        let file = File::open(path)?;
+        let file_size = file.metadata().ok().map(|m| m.len() as i64);
+        Ok(Self {
+            file: RefCell::new(Some(file)),
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            file_size,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl VirtualFileReader for FileRepresentationReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        let mut file = self.file.borrow_mut();
+        let Some(file) = file.as_mut() else {
+            return Err(NativeExtensionsError::VirtualFileReceiveError(
+                "File already closed".into(),
+            ));
+        };
+        let mut buf = vec![0; 1024 * 1024];
+        let size = file.read(&mut buf)?;
+        buf.truncate(size);
+        Ok(buf)
+    }
+
+    fn file_size(&self) -> NativeExtensionsResult<Option<i64>> {
+        Ok(self.file_size)
+    }
+
+    fn file_name(&self) -> Option<String> {
+        self.file_name.clone()
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        self.file.borrow_mut().take();
+        Ok(())
+    }
+}
+
 struct FileWithBackgroundCoordinator {
     // used to block the coordinator thread
     coordinator_thread_release: Arc<Promise<()>>,
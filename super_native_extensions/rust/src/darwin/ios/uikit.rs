@@ -10,7 +10,7 @@ use objc2::{
     ClassType, ProtocolType, RefEncode,
 };
 use objc2_foundation::{
-    CGFloat, CGPoint, CGRect, CGSize, NSArray, NSItemProvider, NSString, NSTimeInterval,
+    CGFloat, CGPoint, CGRect, CGSize, NSArray, NSData, NSItemProvider, NSString, NSTimeInterval,
 };
 
 use crate::platform_impl::platform::common::CGAffineTransform;
@@ -43,9 +43,17 @@ extern_methods!(
 
         #[method_id(@__retain_semantics Other systemImageNamed:)]
         pub unsafe fn systemImageNamed(name: &NSString) -> Option<Id<UIImage>>;
+
+        #[method_id(@__retain_semantics Other imageWithData:)]
+        pub unsafe fn imageWithData(data: &NSData) -> Option<Id<UIImage>>;
     }
 );
 
+extern "C" {
+    /// Returns autoreleased data.
+    pub fn UIImagePNGRepresentation(image: &UIImage) -> *mut NSData;
+}
+
 pub enum _CGImage {}
 
 unsafe impl RefEncode for _CGImage {
@@ -128,6 +136,15 @@ extern_methods!(
         #[method(removeFromSuperview)]
         pub unsafe fn removeFromSuperview(&self);
 
+        #[method(setClipsToBounds:)]
+        pub unsafe fn setClipsToBounds(&self, clips: bool);
+
+        #[method_id(@__retain_semantics Other snapshotViewAfterScreenUpdates:)]
+        pub unsafe fn snapshotViewAfterScreenUpdates(
+            &self,
+            after_updates: bool,
+        ) -> Option<Id<UIView>>;
+
         #[method(addInteraction:)]
         pub unsafe fn addInteraction(&self, interaction: &NSObject);
 
@@ -282,11 +299,33 @@ extern_methods!(
         #[method_id(@__retain_semantics Other generalPasteboard)]
         pub unsafe fn generalPasteboard() -> Id<Self>;
 
+        #[method_id(@__retain_semantics Other pasteboardWithName:create:)]
+        pub unsafe fn pasteboardWithName_create(name: &NSString, create: bool)
+            -> Option<Id<Self>>;
+
         #[method(setItemProviders:)]
         pub unsafe fn setItemProviders(&self, item_providers: &NSArray<NSItemProvider>);
 
         #[method_id(@__retain_semantics Other itemProviders)]
         pub unsafe fn itemProviders(&self) -> Id<NSArray<NSItemProvider>>;
+
+        #[method_id(@__retain_semantics Other types)]
+        pub unsafe fn types(&self) -> Id<NSArray<NSString>>;
+
+        #[method(changeCount)]
+        pub unsafe fn changeCount(&self) -> NSInteger;
+
+        #[method(numberOfItems)]
+        pub unsafe fn numberOfItems(&self) -> NSInteger;
+
+        #[method(hasStrings)]
+        pub unsafe fn hasStrings(&self) -> bool;
+
+        #[method(hasURLs)]
+        pub unsafe fn hasURLs(&self) -> bool;
+
+        #[method(hasImages)]
+        pub unsafe fn hasImages(&self) -> bool;
     }
 );
 
@@ -605,6 +644,22 @@ extern_protocol!(
     unsafe impl ProtocolType for dyn UIContextMenuInteractionAnimating {}
 );
 
+pub type UIContextMenuInteractionCommitStyle = NSInteger;
+
+pub const UIContextMenuInteractionCommitStyleDismiss: UIContextMenuInteractionCommitStyle = 0;
+pub const UIContextMenuInteractionCommitStylePop: UIContextMenuInteractionCommitStyle = 1;
+
+extern_protocol!(
+    pub unsafe trait UIContextMenuInteractionCommitAnimating:
+        UIContextMenuInteractionAnimating
+    {
+        #[method(setPreferredCommitStyle:)]
+        unsafe fn setPreferredCommitStyle(&self, style: UIContextMenuInteractionCommitStyle);
+    }
+
+    unsafe impl ProtocolType for dyn UIContextMenuInteractionCommitAnimating {}
+);
+
 extern_protocol!(
     pub unsafe trait UIDragInteractionDelegate: NSObjectProtocol {
         #[method_id(@__retain_semantics Other dragInteraction:itemsForBeginningSession:)]
@@ -891,7 +946,7 @@ extern_protocol!(
             &self,
             interaction: &UIContextMenuInteraction,
             configuration: &UIContextMenuConfiguration,
-            animator: &ProtocolObject<dyn UIContextMenuInteractionAnimating>,
+            animator: &ProtocolObject<dyn UIContextMenuInteractionCommitAnimating>,
         );
 
         #[optional]
@@ -28,13 +28,14 @@ use objc2_foundation::{NSArray, NSError, NSProgress, NSProgressKindFile, NSStrin
 use once_cell::sync::Lazy;
 
 use crate::{
-    api_model::{DataProvider, DataProviderValueId, DataRepresentation},
+    api_model::{DataProvider, DataProviderValueId, DataRepresentation, VirtualFolderEntry},
     data_provider_manager::{
         DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult, VirtualSessionHandle,
     },
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::common::{path_from_url, to_nserror},
+    util::sanitize_path_component,
     value_promise::ValuePromiseResult,
 };
 
@@ -95,6 +96,20 @@ impl PlatformDataProvider {
         self.weak_self.set(weak_self);
     }
 
+    /// Formats of provider representations, which are used directly as
+    /// pasteboard types.
+    pub fn pasteboard_types(&self) -> Vec<String> {
+        self.data
+            .representations
+            .iter()
+            .filter_map(|r| match r {
+                DataRepresentation::Simple { format, data: _ } => Some(format.clone()),
+                DataRepresentation::Lazy { format, id: _ } => Some(format.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// If retain_handle is false, writer will not retain the DataProviderHandle. This is useful
     /// for drag and drop where the item will live in dragging pasteboard after drag sessions is done.
     pub fn create_writer(
@@ -130,6 +145,34 @@ impl PlatformDataProvider {
         unsafe { pasteboard.writeObjects(&Id::cast(array)) };
         Ok(())
     }
+
+    pub async fn clear_clipboard() -> NativeExtensionsResult<()> {
+        let pasteboard = unsafe { NSPasteboard::generalPasteboard() };
+        unsafe { pasteboard.clearContents() };
+        Ok(())
+    }
+
+    /// Pasteboard asks for lazy data only while the provider exists and
+    /// offers no way to render it upfront, so contents must be kept.
+    pub fn flush_clipboard() -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub async fn write_to_named_pasteboard(
+        providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        name: &str,
+    ) -> NativeExtensionsResult<()> {
+        let items: Vec<_> = providers
+            .into_iter()
+            .map(|p| p.0.create_writer(p.1, true, false))
+            .collect();
+        let array = NSArray::from_vec(items);
+        let name = NSString::from_str(name);
+        let pasteboard = unsafe { NSPasteboard::pasteboardWithName(&name) };
+        unsafe { pasteboard.clearContents() };
+        unsafe { pasteboard.writeObjects(&Id::cast(array)) };
+        Ok(())
+    }
 }
 
 pub struct ItemState {
@@ -141,8 +184,60 @@ pub struct ItemState {
 }
 
 struct VirtualFileInfo {
-    id: DataProviderValueId,
     format: String,
+    contents: VirtualFileContents,
+}
+
+enum VirtualFileContents {
+    File(DataProviderValueId),
+    Folder(Vec<VirtualFolderEntry>),
+}
+
+/// Files of a virtual folder being written; completion is invoked after
+/// last file is done.
+struct FolderWriteState {
+    completion_fn: Cell<Option<Box<dyn FnOnce(Option<Id<NSError>>)>>>,
+    remaining: Cell<usize>,
+    fractions: RefCell<Vec<f64>>,
+    error: RefCell<Option<String>>,
+    progress: Id<NSProgress>,
+}
+
+impl FolderWriteState {
+    fn file_progress(&self, index: usize, fraction: f64) {
+        let mut fractions = self.fractions.borrow_mut();
+        fractions[index] = fraction;
+        let total = fractions.iter().sum::<f64>() / fractions.len() as f64;
+        let completed = (total * 1000.0).round() as i64;
+        unsafe { self.progress.setCompletedUnitCount(completed) };
+    }
+
+    fn file_done(&self, result: VirtualFileResult) {
+        match result {
+            VirtualFileResult::Done => {}
+            VirtualFileResult::Error { message } => {
+                self.error.borrow_mut().get_or_insert(message);
+            }
+            VirtualFileResult::Cancelled => {
+                self.error.borrow_mut().get_or_insert("Cancelled".into());
+            }
+        }
+        self.remaining.set(self.remaining.get() - 1);
+        if self.remaining.get() == 0 {
+            unsafe { self.progress.unpublish() };
+            if let Some(completion_fn) = self.completion_fn.take() {
+                let error = self.error.take();
+                completion_fn(error.map(|e| to_nserror("super_dnd", 0, &e)));
+            }
+        }
+    }
+}
+
+fn create_promised_file(path: PathBuf) -> std::io::Result<i32> {
+    let file = File::create(&path)?;
+    let descriptor = file.into_raw_fd();
+    FILE_PATHS.lock().unwrap().insert(descriptor, path);
+    Ok(descriptor)
 }
 
 impl ItemState {
@@ -178,8 +273,12 @@ impl ItemState {
                     format,
                     storage_suggestion: _,
                 } => Some(VirtualFileInfo {
-                    id: *id,
                     format: format.clone(),
+                    contents: VirtualFileContents::File(*id),
+                }),
+                DataRepresentation::VirtualFolder { format, entries } => Some(VirtualFileInfo {
+                    format: format.clone(),
+                    contents: VirtualFileContents::Folder(entries.clone()),
                 }),
                 _ => None,
             })
@@ -269,8 +368,9 @@ impl ItemState {
             Some(data_provider) => {
                 let data = &data_provider.data;
                 data.suggested_name
-                    .as_ref()
-                    .map(|name| NSString::from_str(name))
+                    .as_deref()
+                    .and_then(sanitize_path_component)
+                    .map(|name| NSString::from_str(&name))
                     .unwrap_or(unsafe { NSString::string() })
             }
             None => unsafe { NSString::string() },
@@ -292,35 +392,30 @@ impl ItemState {
         self: &Rc<Self>,
         url: &NSURL,
         completion_fn: Box<dyn FnOnce(Option<Id<NSError>>)>,
-        info: VirtualFileInfo,
+        id: DataProviderValueId,
         data_provider: Rc<PlatformDataProvider>,
         delegate: Rc<dyn PlatformDataProviderDelegate>,
         data_provider_handle: Arc<DataProviderHandle>,
     ) {
-        let progress = Self::progress_for_url(url);
-
-        let path = path_from_url(url);
-        let file = File::create(&path);
-        let file = match file {
-            Ok(file) => file,
+        let descriptor = match create_promised_file(path_from_url(url)) {
+            Ok(descriptor) => descriptor,
             Err(err) => {
                 let error = to_nserror("super_dnd", 0, &err.to_string());
                 completion_fn(Some(error));
                 return;
             }
         };
-        let descriptor = file.into_raw_fd();
-        FILE_PATHS.lock().unwrap().insert(descriptor, path);
+        let progress = Self::progress_for_url(url);
 
         let progress_clone = progress.clone();
         let progress_clone2 = progress.clone();
         let notifier = delegate.get_virtual_file(
             data_provider.isolate_id,
-            info.id,
+            id,
             descriptor,
             Box::new(|_| {}),
-            Box::new(move |cnt| {
-                let completed = (cnt * 1000.0).round() as i64;
+            Box::new(move |update| {
+                let completed = (update.fraction.unwrap_or(0.0) * 1000.0).round() as i64;
                 unsafe { progress_clone.setCompletedUnitCount(completed) };
             }),
             Box::new(move |result| {
@@ -351,6 +446,93 @@ impl ItemState {
         }
     }
 
+    /// Creates the folder structure at `url` and writes all files in
+    /// parallel.
+    fn file_promise_do_write_folder(
+        self: &Rc<Self>,
+        url: &NSURL,
+        completion_fn: Box<dyn FnOnce(Option<Id<NSError>>)>,
+        entries: Vec<VirtualFolderEntry>,
+        data_provider: Rc<PlatformDataProvider>,
+        delegate: Rc<dyn PlatformDataProviderDelegate>,
+        data_provider_handle: Arc<DataProviderHandle>,
+    ) {
+        let root = path_from_url(url);
+        let mut files = Vec::new();
+        let mut prepare = || -> std::io::Result<()> {
+            std::fs::create_dir_all(&root)?;
+            for entry in &entries {
+                let components = entry.components();
+                if components.is_empty() {
+                    continue;
+                }
+                let path: PathBuf = components.iter().fold(root.clone(), |p, c| p.join(c));
+                match entry.id {
+                    Some(id) => {
+                        if let Some(parent) = path.parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                        files.push((create_promised_file(path)?, id));
+                    }
+                    None => std::fs::create_dir_all(&path)?,
+                }
+            }
+            Ok(())
+        };
+        if let Err(err) = prepare() {
+            for (descriptor, _) in files {
+                platform_stream_close(descriptor, true);
+            }
+            let error = to_nserror("super_dnd", 0, &err.to_string());
+            completion_fn(Some(error));
+            return;
+        }
+        if files.is_empty() {
+            completion_fn(None);
+            return;
+        }
+
+        let progress = Self::progress_for_url(url);
+        let state = Rc::new(FolderWriteState {
+            completion_fn: Cell::new(Some(completion_fn)),
+            remaining: Cell::new(files.len()),
+            fractions: RefCell::new(vec![0.0; files.len()]),
+            error: RefCell::new(None),
+            progress: progress.clone(),
+        });
+        let mut notifiers = Vec::new();
+        for (index, (descriptor, id)) in files.into_iter().enumerate() {
+            let state_progress = state.clone();
+            let state_done = state.clone();
+            let data_provider_handle = data_provider_handle.clone();
+            let notifier = delegate.get_virtual_file(
+                data_provider.isolate_id,
+                id,
+                descriptor,
+                Box::new(|_| {}),
+                Box::new(move |update| {
+                    state_progress.file_progress(index, update.fraction.unwrap_or(0.0))
+                }),
+                Box::new(move |result| {
+                    let _handle = data_provider_handle;
+                    state_done.file_done(result);
+                }),
+            );
+            self.virtual_files.borrow_mut().push(notifier.clone());
+            notifiers.push(Arc::downgrade(&notifier));
+        }
+        let cancellation_handler = RcBlock::new(move || {
+            for notifier in &notifiers {
+                if let Some(notifier) = notifier.upgrade() {
+                    notifier.dispose();
+                }
+            }
+        });
+        unsafe {
+            progress.setCancellationHandler(Some(&cancellation_handler));
+        }
+    }
+
     fn file_promise_write_to_url(
         self: &Rc<Self>,
         url: &NSURL,
@@ -363,14 +545,24 @@ impl ItemState {
 
         match (info, data_provider, delegate, data_provider_handle) {
             (Some(info), Some(clipboard), Some(delegate), Some(drop_notifier)) => {
-                self.file_promise_do_write(
-                    url,
-                    completion_fn,
-                    info,
-                    clipboard,
-                    delegate,
-                    drop_notifier,
-                );
+                match info.contents {
+                    VirtualFileContents::File(id) => self.file_promise_do_write(
+                        url,
+                        completion_fn,
+                        id,
+                        clipboard,
+                        delegate,
+                        drop_notifier,
+                    ),
+                    VirtualFileContents::Folder(entries) => self.file_promise_do_write_folder(
+                        url,
+                        completion_fn,
+                        entries,
+                        clipboard,
+                        delegate,
+                        drop_notifier,
+                    ),
+                }
             }
             _ => {
                 let error = to_nserror("super_dnd", 0, "data not found");
@@ -2,16 +2,21 @@ use std::{
     cell::RefCell,
     collections::HashMap,
     os::raw::c_ushort,
+    ptr::NonNull,
     rc::{Rc, Weak},
     sync::Arc,
     time::Duration,
 };
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation},
+    api_model::{
+        DataProviderId, DragCancelReason, DragConfiguration, DragRequest, DropOperation,
+        KeyModifiers,
+    },
     data_provider_manager::DataProviderHandle,
     drag_manager::{
         DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
+        UpdateDragImageRequest,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     value_promise::PromiseResult,
@@ -19,9 +24,11 @@ use crate::{
 
 use super::{
     drag_common::DropOperationExt,
+    drag_environment::DragEnvironment,
     util::{class_builder_from_name, flip_rect, ns_image_from_image_data, EventExt},
 };
 
+use block2::RcBlock;
 use core_foundation::base::CFRelease;
 use core_graphics::event::{CGEventField, CGEventType};
 
@@ -29,17 +36,18 @@ use irondash_engine_context::EngineContext;
 use irondash_message_channel::Value;
 use irondash_run_loop::{platform::PollSession, RunLoop};
 use objc2_app_kit::{
-    NSApplication, NSDragOperation, NSDraggingContext, NSDraggingItem, NSDraggingSession, NSEvent,
-    NSEventPhase, NSEventType, NSView,
+    NSApplication, NSDragOperation, NSDraggingContext, NSDraggingItem,
+    NSDraggingItemEnumerationOptions, NSDraggingSession, NSEvent, NSEventModifierFlags,
+    NSEventPhase, NSEventType, NSPasteboardItem, NSView,
 };
-use objc2_foundation::{MainThreadMarker, NSArray, NSPoint, NSProcessInfo, NSRect};
+use objc2_foundation::{MainThreadMarker, NSArray, NSDictionary, NSPoint, NSProcessInfo, NSRect};
 
 use objc2::{
     class,
     ffi::NSInteger,
     msg_send,
     rc::Id,
-    runtime::{Bool, Sel},
+    runtime::{AnyObject, Bool, Sel},
     sel, ClassType,
 };
 
@@ -55,7 +63,9 @@ extern "C" {
 
 struct DragSession {
     session_id: DragSessionId,
+    session: Id<NSDraggingSession>,
     configuration: DragConfiguration,
+    environment: DragEnvironment,
     _data_provider_handles: Vec<Arc<DataProviderHandle>>,
 }
 
@@ -160,6 +170,24 @@ impl PlatformDragContext {
         false
     }
 
+    /// AppKit can't end dragging session early. Sessions are forgotten
+    /// instead, which releases their data providers; remaining callbacks of
+    /// the sessions are ignored.
+    pub fn cancel_sessions(&self) {
+        self.sessions.borrow_mut().clear();
+    }
+
+    pub fn current_modifiers() -> KeyModifiers {
+        let flags = unsafe { NSEvent::modifierFlags_class() }.0;
+        let has = |flag: NSEventModifierFlags| flags & flag.0 != 0;
+        KeyModifiers {
+            shift: has(NSEventModifierFlags::NSEventModifierFlagShift),
+            control: has(NSEventModifierFlags::NSEventModifierFlagControl),
+            alt: has(NSEventModifierFlags::NSEventModifierFlagOption),
+            meta: has(NSEventModifierFlags::NSEventModifierFlagCommand),
+        }
+    }
+
     pub async fn start_drag(
         &self,
         request: DragRequest,
@@ -220,11 +248,14 @@ impl PlatformDragContext {
         unsafe { session.setAnimatesToStartingPositionsOnCancelOrFail(animates) };
 
         let dragging_sequence_number = unsafe { session.draggingSequenceNumber() };
+        let environment = DragEnvironment::new(&self.view, unsafe { NSEvent::mouseLocation() });
         self.sessions.borrow_mut().insert(
             dragging_sequence_number,
             DragSession {
                 session_id,
+                session: session.clone(),
                 configuration: request.configuration,
+                environment,
                 _data_provider_handles: data_provider_handles,
             },
         );
@@ -341,7 +372,7 @@ impl PlatformDragContext {
     pub fn drag_ended(
         &self,
         session: &NSDraggingSession,
-        _point: NSPoint,
+        point: NSPoint,
         operation: NSDragOperation,
     ) {
         let user_cancelled = unsafe {
@@ -357,11 +388,10 @@ impl PlatformDragContext {
         };
 
         let dragging_sequence_number = unsafe { session.draggingSequenceNumber() };
-        let session = self
-            .sessions
-            .borrow_mut()
-            .remove(&dragging_sequence_number)
-            .expect("Drag session unexpectedly missing");
+        // Session may have been forgotten by `cancel_sessions`.
+        let Some(session) = self.sessions.borrow_mut().remove(&dragging_sequence_number) else {
+            return;
+        };
 
         let operations = DropOperation::from_platform_mask(operation);
         // there might be multiple operation, use the order from from_platform_mask
@@ -372,7 +402,27 @@ impl PlatformDragContext {
             operation
         };
         if let Some(delegate) = self.delegate.upgrade() {
-            delegate.drag_session_did_end_with_operation(self.id, session.session_id, operation);
+            if matches!(
+                operation,
+                DropOperation::None | DropOperation::UserCancelled
+            ) && !session
+                .configuration
+                .animates_to_starting_position_on_cancel_or_fail
+            {
+                delegate.drag_session_did_cancel(self.id, session.session_id, point.into());
+            }
+            // AppKit doesn't tell whether a drop was attempted.
+            let cancel_reason = match operation {
+                DropOperation::UserCancelled => Some(DragCancelReason::Escape),
+                DropOperation::None => Some(DragCancelReason::NoTarget),
+                _ => None,
+            };
+            delegate.drag_session_did_end_with_operation(
+                self.id,
+                session.session_id,
+                operation,
+                cancel_reason,
+            );
         }
 
         // Fix hover after mouse move
@@ -388,13 +438,30 @@ impl PlatformDragContext {
     }
 
     pub fn drag_moved(&self, session: &NSDraggingSession, point: NSPoint) {
-        let sessions = self.sessions.borrow();
         let dragging_sequence_number = unsafe { session.draggingSequenceNumber() };
-        let session = sessions
-            .get(&dragging_sequence_number)
-            .expect("Drag session unexpectedly missing");
+        let (session_id, changes) = {
+            let mut sessions = self.sessions.borrow_mut();
+            let Some(session) = sessions.get_mut(&dragging_sequence_number) else {
+                return;
+            };
+            let changes = session.environment.update(&self.view, point);
+            (session.session_id, changes)
+        };
         if let Some(delegate) = self.delegate.upgrade() {
-            delegate.drag_session_did_move_to_location(self.id, session.session_id, point.into());
+            delegate.drag_session_did_move_to_location(
+                self.id,
+                session_id,
+                point.into(),
+                Self::current_modifiers(),
+            );
+            for change in changes {
+                delegate.drag_session_environment_did_change(
+                    self.id,
+                    session_id,
+                    change,
+                    point.into(),
+                );
+            }
         }
     }
 
@@ -444,11 +511,60 @@ impl PlatformDragContext {
         }
     }
 
-    pub fn get_local_data(&self, dragging_sequence_number: NSInteger) -> Option<Vec<Value>> {
+    pub fn update_drag_image(
+        &self,
+        request: &UpdateDragImageRequest,
+    ) -> NativeExtensionsResult<()> {
+        let session = self
+            .sessions
+            .borrow()
+            .values()
+            .find(|s| s.session_id == request.session_id)
+            .map(|s| s.session.clone())
+            .ok_or(NativeExtensionsError::DragSessionNotFound)?;
+        let images: Vec<_> = request
+            .images
+            .iter()
+            .map(|image| {
+                let mut rect: NSRect = image.rect.clone().into();
+                flip_rect(&self.view, &mut rect);
+                (
+                    rect,
+                    ns_image_from_image_data(vec![image.image_data.clone()]),
+                )
+            })
+            .collect();
+        let block = RcBlock::new(
+            move |item: NonNull<NSDraggingItem>, index: NSInteger, _stop: NonNull<Bool>| {
+                if let Some((rect, image)) = images.get(index as usize) {
+                    let item = unsafe { item.as_ref() };
+                    unsafe { item.setDraggingFrame_contents(*rect, Some(image)) };
+                }
+            },
+        );
+        unsafe {
+            let class =
+                Id::retain(NSPasteboardItem::class() as *const _ as *mut AnyObject).unwrap();
+            session.enumerateDraggingItemsWithOptions_forView_classes_searchOptions_usingBlock(
+                NSDraggingItemEnumerationOptions(0),
+                Some(&self.view),
+                &NSArray::from_vec(vec![class]),
+                &NSDictionary::dictionary(),
+                &block,
+            );
+        }
+        Ok(())
+    }
+
+    /// Session and local data of items of local drag.
+    pub fn get_local_session(
+        &self,
+        dragging_sequence_number: NSInteger,
+    ) -> Option<(DragSessionId, Vec<Value>)> {
         let sessions = self.sessions.borrow();
         sessions
             .get(&dragging_sequence_number)
-            .map(|s| s.configuration.get_local_data())
+            .map(|s| (s.session_id, s.configuration.get_local_data()))
     }
 
     pub fn get_local_data_for_session_id(
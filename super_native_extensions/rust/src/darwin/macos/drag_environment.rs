@@ -0,0 +1,210 @@
+//! Detection of desktop environment changes during outgoing drag.
+//!
+//! AppKit doesn't notify drag source when user switches Spaces or opens
+//! Mission Control mid-drag; only the dragging session keeps moving. The
+//! state is therefore sampled on session moves: display under the pointer,
+//! whether the source window is still on the active Space and whether the
+//! window under the pointer belongs to Mission Control (Dock) or Stage
+//! Manager (WindowManager).
+
+use std::time::{Duration, Instant};
+
+use core_foundation::{
+    array::CFArray,
+    base::{CFType, TCFType},
+    dictionary::{CFDictionary, CFDictionaryRef},
+    number::CFNumber,
+    string::CFString,
+};
+use core_graphics::window::{
+    copy_window_info, kCGNullWindowID, kCGWindowBounds, kCGWindowLayer,
+    kCGWindowListExcludeDesktopElements, kCGWindowListOptionOnScreenOnly, kCGWindowOwnerName,
+};
+use objc2::{class, msg_send, msg_send_id, rc::Id, runtime::Bool};
+use objc2_app_kit::NSView;
+use objc2_foundation::{NSArray, NSObject, NSPoint, NSRect};
+
+use crate::api_model::DragEnvironmentChange;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SystemOverlay {
+    MissionControl,
+    StageManager,
+}
+
+impl SystemOverlay {
+    fn entered(self) -> DragEnvironmentChange {
+        match self {
+            SystemOverlay::MissionControl => DragEnvironmentChange::MissionControlEntered,
+            SystemOverlay::StageManager => DragEnvironmentChange::StageManagerEntered,
+        }
+    }
+
+    fn exited(self) -> DragEnvironmentChange {
+        match self {
+            SystemOverlay::MissionControl => DragEnvironmentChange::MissionControlExited,
+            SystemOverlay::StageManager => DragEnvironmentChange::StageManagerExited,
+        }
+    }
+}
+
+pub struct DragEnvironment {
+    screen: Option<usize>,
+    on_active_space: bool,
+    overlay: Option<SystemOverlay>,
+    last_overlay_check: Option<Instant>,
+}
+
+impl DragEnvironment {
+    /// Querying window list is relatively expensive.
+    const OVERLAY_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+    pub fn new(view: &NSView, point: NSPoint) -> Self {
+        Self {
+            screen: screen_index_for_point(point),
+            on_active_space: is_on_active_space(view),
+            overlay: None,
+            last_overlay_check: None,
+        }
+    }
+
+    /// Returns changes since last update.
+    pub fn update(&mut self, view: &NSView, point: NSPoint) -> Vec<DragEnvironmentChange> {
+        let mut res = Vec::new();
+        let screen = screen_index_for_point(point);
+        if screen.is_some() && screen != self.screen {
+            self.screen = screen;
+            res.push(DragEnvironmentChange::ScreenChanged);
+        }
+        let on_active_space = is_on_active_space(view);
+        if on_active_space != self.on_active_space {
+            self.on_active_space = on_active_space;
+            res.push(DragEnvironmentChange::SpaceChanged);
+        }
+        let now = Instant::now();
+        let check_overlay = self
+            .last_overlay_check
+            .map(|t| now.duration_since(t) >= Self::OVERLAY_CHECK_INTERVAL)
+            .unwrap_or(true);
+        if check_overlay {
+            self.last_overlay_check = Some(now);
+            let overlay = overlay_at_point(point);
+            if overlay != self.overlay {
+                if let Some(previous) = self.overlay {
+                    res.push(previous.exited());
+                }
+                if let Some(overlay) = overlay {
+                    res.push(overlay.entered());
+                }
+                self.overlay = overlay;
+            }
+        }
+        res
+    }
+}
+
+fn screen_frames() -> Vec<NSRect> {
+    unsafe {
+        let screens: Id<NSArray<NSObject>> = msg_send_id![class!(NSScreen), screens];
+        screens
+            .iter()
+            .map(|s| {
+                let frame: NSRect = msg_send![s, frame];
+                frame
+            })
+            .collect()
+    }
+}
+
+fn rect_contains(rect: &NSRect, point: NSPoint) -> bool {
+    point.x >= rect.origin.x
+        && point.x < rect.origin.x + rect.size.width
+        && point.y >= rect.origin.y
+        && point.y < rect.origin.y + rect.size.height
+}
+
+fn screen_index_for_point(point: NSPoint) -> Option<usize> {
+    screen_frames()
+        .iter()
+        .position(|frame| rect_contains(frame, point))
+}
+
+fn is_on_active_space(view: &NSView) -> bool {
+    unsafe {
+        match view.window() {
+            Some(window) => {
+                let on_active_space: Bool = msg_send![&*window, isOnActiveSpace];
+                on_active_space.as_bool()
+            }
+            None => true,
+        }
+    }
+}
+
+fn number_value(dictionary: &CFDictionary<CFString, CFType>, key: &str) -> Option<f64> {
+    dictionary
+        .find(&CFString::new(key))
+        .and_then(|v| v.downcast::<CFNumber>())
+        .and_then(|n| n.to_f64())
+}
+
+/// Returns system overlay owning topmost window under the point. Mission
+/// Control windows are owned by Dock, same as the Dock itself, but unlike
+/// the Dock they cover the whole screen.
+fn overlay_at_point(point: NSPoint) -> Option<SystemOverlay> {
+    let frames = screen_frames();
+    // Window list uses flipped coordinates with origin at top left corner of
+    // primary screen.
+    let primary_height = frames.first()?.size.height;
+    let screen = frames.iter().find(|frame| rect_contains(frame, point))?;
+    let (x, y) = (point.x, primary_height - point.y);
+    let windows: CFArray = copy_window_info(
+        kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements,
+        kCGNullWindowID,
+    )?;
+    // Windows are ordered front to back.
+    for window in windows.iter() {
+        let window = unsafe {
+            CFDictionary::<CFString, CFType>::wrap_under_get_rule(*window as CFDictionaryRef)
+        };
+        let (owner, layer, bounds) = unsafe {
+            (
+                window.find(&CFString::wrap_under_get_rule(kCGWindowOwnerName)),
+                window.find(&CFString::wrap_under_get_rule(kCGWindowLayer)),
+                window.find(&CFString::wrap_under_get_rule(kCGWindowBounds)),
+            )
+        };
+        let Some(bounds) = bounds else { continue };
+        let bounds = unsafe {
+            CFDictionary::<CFString, CFType>::wrap_under_get_rule(
+                bounds.as_CFTypeRef() as CFDictionaryRef
+            )
+        };
+        let (Some(bx), Some(by), Some(width), Some(height)) = (
+            number_value(&bounds, "X"),
+            number_value(&bounds, "Y"),
+            number_value(&bounds, "Width"),
+            number_value(&bounds, "Height"),
+        ) else {
+            continue;
+        };
+        if x < bx || x >= bx + width || y < by || y >= by + height {
+            continue;
+        }
+        let layer = layer
+            .and_then(|l| l.downcast::<CFNumber>())
+            .and_then(|l| l.to_i64())
+            .unwrap_or(0);
+        let owner = owner
+            .and_then(|o| o.downcast::<CFString>())
+            .map(|o| o.to_string())
+            .unwrap_or_default();
+        let covers_screen = width >= screen.size.width && height >= screen.size.height;
+        return match owner.as_str() {
+            "Dock" if layer > 0 && covers_screen => Some(SystemOverlay::MissionControl),
+            "WindowManager" => Some(SystemOverlay::StageManager),
+            _ => None,
+        };
+    }
+    None
+}
@@ -16,13 +16,17 @@ use objc2::{
     sel, ClassType,
 };
 use objc2_app_kit::{
-    NSDragOperation, NSDraggingInfo, NSDraggingItem, NSDraggingItemEnumerationOptions,
-    NSFilePromiseReceiver, NSPasteboardItem, NSView,
+    NSControlSize, NSDragOperation, NSDraggingInfo, NSDraggingItem,
+    NSDraggingItemEnumerationOptions, NSFilePromiseReceiver, NSPasteboardItem, NSProgressIndicator,
+    NSProgressIndicatorStyle, NSView,
+};
+use objc2_foundation::{
+    ns_string, MainThreadMarker, NSArray, NSDictionary, NSMutableArray, NSPoint, NSRect, NSSize,
+    NSString,
 };
-use objc2_foundation::{ns_string, NSArray, NSDictionary, NSMutableArray, NSRect, NSString};
 
 use crate::{
-    api_model::DropOperation,
+    api_model::{DropOperation, Point},
     drop_manager::{
         BaseDropEvent, DropEvent, DropItem, DropSessionId, ItemPreviewRequest,
         PlatformDropContextDelegate, PlatformDropContextId,
@@ -42,6 +46,7 @@ pub struct PlatformDropContext {
     view: Id<NSView>,
     delegate: Weak<dyn PlatformDropContextDelegate>,
     sessions: RefCell<HashMap<isize /* draggingSequenceNumber */, Rc<Session>>>,
+    busy_indicators: RefCell<HashMap<DropSessionId, Id<NSProgressIndicator>>>,
 }
 
 static ONCE: std::sync::Once = std::sync::Once::new();
@@ -76,12 +81,11 @@ impl Session {
 
         let dragging_sequence_number = unsafe { dragging_info.draggingSequenceNumber() };
         let drag_contexts = delegate.get_platform_drag_contexts();
-        let local_data = drag_contexts
+        let local_session = drag_contexts
             .iter()
-            .map(|c| c.get_local_data(dragging_sequence_number))
-            .find(|c| c.is_some())
-            .flatten()
-            .unwrap_or_default();
+            .find_map(|c| c.get_local_session(dragging_sequence_number));
+        let local_session_id = local_session.as_ref().map(|s| s.0);
+        let local_data = local_session.map(|s| s.1).unwrap_or_default();
 
         let location = unsafe { dragging_info.draggingLocation() }; // window coordinates
         let location = self.context_view.convertPoint_fromView(location, None);
@@ -94,11 +98,16 @@ impl Session {
                 item_id: (*item).into(),
                 formats: self.reader.get_formats_for_item_sync(*item)?,
                 local_data: local_data.get(index).cloned().unwrap_or(Value::Null),
+                allowed_operations: None,
             })
         }
 
         Ok(DropEvent {
             session_id: self.id,
+            engine_handle: self.context_id.engine_handle,
+            region_id: None,
+            session_local_data: Value::Null,
+            local_session_id,
             location_in_view: location.into(),
             allowed_operations: DropOperation::from_platform_mask(operation_mask),
             accepted_operation,
@@ -135,6 +144,7 @@ impl Session {
             self.context_id,
             BaseDropEvent {
                 session_id: self.id,
+                engine_handle: self.context_id.engine_handle,
             },
         );
         Ok(())
@@ -255,6 +265,7 @@ impl Session {
             self.context_id,
             BaseDropEvent {
                 session_id: self.id,
+                engine_handle: self.context_id.engine_handle,
             },
         );
         Ok(())
@@ -275,6 +286,7 @@ impl PlatformDropContext {
             view: unsafe { Id::cast(view) },
             delegate,
             sessions: RefCell::new(HashMap::new()),
+            busy_indicators: RefCell::new(HashMap::new()),
         })
     }
 
@@ -285,6 +297,51 @@ impl PlatformDropContext {
         self.weak_self.set(weak_self);
     }
 
+    pub fn supports_deferred_drop(&self) -> bool {
+        true
+    }
+
+    /// Shows small spinner at drop location while deferred drop is in progress.
+    pub fn show_busy_indicator(
+        &self,
+        session_id: DropSessionId,
+        location: &Point,
+    ) -> NativeExtensionsResult<()> {
+        const SIZE: f64 = 16.0;
+        let mtm = MainThreadMarker::new().unwrap();
+        let frame = NSRect::new(
+            NSPoint::new(location.x - SIZE / 2.0, location.y - SIZE / 2.0),
+            NSSize::new(SIZE, SIZE),
+        );
+        unsafe {
+            let indicator = NSProgressIndicator::initWithFrame(mtm.alloc(), frame);
+            indicator.setStyle(NSProgressIndicatorStyle::Spinning);
+            indicator.setControlSize(NSControlSize::Small);
+            indicator.setIndeterminate(true);
+            indicator.setDisplayedWhenStopped(false);
+            self.view.addSubview(&indicator);
+            indicator.startAnimation(None);
+            if let Some(previous) = self
+                .busy_indicators
+                .borrow_mut()
+                .insert(session_id, indicator)
+            {
+                previous.removeFromSuperview();
+            }
+        }
+        Ok(())
+    }
+
+    pub fn hide_busy_indicator(&self, session_id: DropSessionId) {
+        let indicator = self.busy_indicators.borrow_mut().remove(&session_id);
+        if let Some(indicator) = indicator {
+            unsafe {
+                indicator.stopAnimation(None);
+                indicator.removeFromSuperview();
+            }
+        }
+    }
+
     pub fn register_drop_formats(&self, types: &[String]) -> NativeExtensionsResult<()> {
         let types: Vec<_> = types.iter().map(|ty| NSString::from_str(ty)).collect();
         let our_types = NSArray::from_vec(types);
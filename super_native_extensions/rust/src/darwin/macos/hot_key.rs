@@ -2,16 +2,20 @@ use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
     mem,
+    ptr::{self, NonNull},
     rc::Weak,
 };
 
+use block2::RcBlock;
 use core_foundation::base::OSStatus;
 use irondash_message_channel::Late;
 use log::warn;
+use objc2::{rc::Id, runtime::AnyObject};
+use objc2_app_kit::{NSEvent, NSEventMask, NSEventModifierFlags, NSEventType};
 
 use crate::{
-    error::NativeExtensionsResult,
-    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate, SpecialKey},
 };
 
 use super::hot_key_sys::{
@@ -23,16 +27,61 @@ use super::hot_key_sys::{
 
 const HOT_KEY_TAG: u32 = 1314080844; // NSHL
 
+/// Virtual key codes (`kVK_*`) of special keys. There are no keys beyond
+/// F20; media keys are handled by [special_key_to_media_key].
+fn special_key_to_key_code(key: SpecialKey) -> Option<u32> {
+    match key {
+        SpecialKey::F13 => Some(0x69),
+        SpecialKey::F14 => Some(0x6B),
+        SpecialKey::F15 => Some(0x71),
+        SpecialKey::F16 => Some(0x6A),
+        SpecialKey::F17 => Some(0x40),
+        SpecialKey::F18 => Some(0x4F),
+        SpecialKey::F19 => Some(0x50),
+        SpecialKey::F20 => Some(0x5A),
+        _ => None,
+    }
+}
+
+/// `NX_KEYTYPE_*` of media keys. These are delivered as system defined
+/// events, not key events, and can not be registered as Carbon hot keys.
+/// There is no stop key.
+fn special_key_to_media_key(key: SpecialKey) -> Option<i64> {
+    match key {
+        SpecialKey::AudioVolumeUp => Some(0),
+        SpecialKey::AudioVolumeDown => Some(1),
+        SpecialKey::AudioVolumeMute => Some(7),
+        SpecialKey::MediaPlayPause => Some(16),
+        SpecialKey::MediaTrackNext => Some(17),
+        SpecialKey::MediaTrackPrevious => Some(18),
+        _ => None,
+    }
+}
+
+const NX_SUBTYPE_AUX_CONTROL_BUTTONS: i16 = 8;
+const NX_KEYDOWN: i64 = 0xA;
+const NX_KEYUP: i64 = 0xB;
+
 struct HotKey {
     handle: HotKeyHandle,
     key_ref: EventHotKeyRef,
 }
+
+struct MediaHotKey {
+    handle: HotKeyHandle,
+    media_key: i64,
+    request: HotKeyCreateRequest,
+}
 pub struct PlatformHotKeyManager {
     delegate: Weak<dyn HotKeyManagerDelegate>,
     weak_self: Late<Weak<PlatformHotKeyManager>>,
     event_handler_ref: Cell<EventHandlerRef>,
     next_id: Cell<u32>,
     hot_keys: RefCell<HashMap<u32, HotKey>>,
+    media_hot_keys: RefCell<Vec<MediaHotKey>>,
+    /// Global and local monitor of system defined events, installed while
+    /// there are media hot keys.
+    media_key_monitors: RefCell<Vec<Id<AnyObject>>>,
 }
 
 impl PlatformHotKeyManager {
@@ -43,6 +92,8 @@ impl PlatformHotKeyManager {
             event_handler_ref: Cell::new(std::ptr::null_mut()),
             next_id: Cell::new(1),
             hot_keys: RefCell::new(HashMap::new()),
+            media_hot_keys: RefCell::new(Vec::new()),
+            media_key_monitors: RefCell::new(Vec::new()),
         }
     }
 
@@ -95,11 +146,122 @@ impl PlatformHotKeyManager {
         }
     }
 
+    /// Global monitor only receives events sent to other applications,
+    /// local monitor covers this application and can also swallow the
+    /// event. Media keys pressed while other application is active are still
+    /// handled by the system.
+    fn install_media_key_monitors(&self) {
+        let weak = self.weak_self.clone();
+        let global_handler = RcBlock::new(move |event: NonNull<NSEvent>| {
+            if let Some(manager) = weak.upgrade() {
+                manager.on_system_defined_event(unsafe { event.as_ref() });
+            }
+        });
+        let weak = self.weak_self.clone();
+        let local_handler = RcBlock::new(move |event: NonNull<NSEvent>| -> *mut NSEvent {
+            if let Some(manager) = weak.upgrade() {
+                if manager.on_system_defined_event(unsafe { event.as_ref() }) {
+                    return ptr::null_mut();
+                }
+            }
+            event.as_ptr()
+        });
+        let monitors = unsafe {
+            [
+                NSEvent::addGlobalMonitorForEventsMatchingMask_handler(
+                    NSEventMask::SystemDefined,
+                    &global_handler,
+                ),
+                NSEvent::addLocalMonitorForEventsMatchingMask_handler(
+                    NSEventMask::SystemDefined,
+                    &local_handler,
+                ),
+            ]
+        };
+        self.media_key_monitors
+            .borrow_mut()
+            .extend(monitors.into_iter().flatten());
+    }
+
+    fn remove_media_key_monitors(&self) {
+        for monitor in self.media_key_monitors.borrow_mut().drain(..) {
+            unsafe { NSEvent::removeMonitor(&monitor) };
+        }
+    }
+
+    fn modifiers_match(flags: NSEventModifierFlags, request: &HotKeyCreateRequest) -> bool {
+        let is_set = |flag: NSEventModifierFlags| flags.0 & flag.0 != 0;
+        is_set(NSEventModifierFlags::NSEventModifierFlagCommand) == request.meta
+            && is_set(NSEventModifierFlags::NSEventModifierFlagShift) == request.shift
+            && is_set(NSEventModifierFlags::NSEventModifierFlagOption) == request.alt
+            && is_set(NSEventModifierFlags::NSEventModifierFlagControl) == request.control
+    }
+
+    /// Returns whether the event belongs to media hot key.
+    fn on_system_defined_event(&self, event: &NSEvent) -> bool {
+        let (data, flags) = unsafe {
+            if event.r#type() != NSEventType::SystemDefined
+                || event.subtype().0 != NX_SUBTYPE_AUX_CONTROL_BUTTONS
+            {
+                return false;
+            }
+            (event.data1() as i64, event.modifierFlags())
+        };
+        let media_key = (data >> 16) & 0xFFFF;
+        let key_state = (data >> 8) & 0xFF;
+        let is_repeat = data & 0x1 != 0;
+        let (handle, suppress_repeat) = {
+            let media_hot_keys = self.media_hot_keys.borrow();
+            let Some(hot_key) = media_hot_keys
+                .iter()
+                .find(|k| k.media_key == media_key && Self::modifiers_match(flags, &k.request))
+            else {
+                return false;
+            };
+            (hot_key.handle, hot_key.request.suppress_repeat())
+        };
+        let Some(delegate) = self.delegate.upgrade() else {
+            return true;
+        };
+        match key_state {
+            NX_KEYDOWN if !(is_repeat && suppress_repeat) => delegate.on_hot_key_pressed(handle),
+            NX_KEYUP => delegate.on_hot_key_released(handle),
+            _ => {}
+        }
+        true
+    }
+
+    fn create_media_hot_key(
+        &self,
+        handle: HotKeyHandle,
+        media_key: i64,
+        request: HotKeyCreateRequest,
+    ) -> NativeExtensionsResult<()> {
+        if self.media_key_monitors.borrow().is_empty() {
+            self.install_media_key_monitors();
+        }
+        self.media_hot_keys.borrow_mut().push(MediaHotKey {
+            handle,
+            media_key,
+            request,
+        });
+        Ok(())
+    }
+
     pub fn create_hot_key(
         &self,
         handle: HotKeyHandle,
         request: HotKeyCreateRequest,
     ) -> NativeExtensionsResult<()> {
+        if let Some(media_key) = request.special_key.and_then(special_key_to_media_key) {
+            return self.create_media_hot_key(handle, media_key, request);
+        }
+        let key_code = match request.special_key {
+            Some(key) => {
+                special_key_to_key_code(key).ok_or(NativeExtensionsError::UnsupportedOperation)?
+            }
+            None => request.platform_code as u32,
+        };
         let id = self.next_id.get();
         self.next_id.replace(id + 1);
 
@@ -126,7 +288,7 @@ impl PlatformHotKeyManager {
 
         unsafe {
             RegisterEventHotKey(
-                request.platform_code as u32,
+                key_code,
                 modifiers,
                 hot_key_id,
                 GetEventDispatcherTarget(),
@@ -143,6 +305,16 @@ impl PlatformHotKeyManager {
     }
 
     pub fn destroy_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<()> {
+        let removed_last_media_key = {
+            let mut media_hot_keys = self.media_hot_keys.borrow_mut();
+            let count = media_hot_keys.len();
+            media_hot_keys.retain(|k| k.handle != handle);
+            count > 0 && media_hot_keys.is_empty()
+        };
+        if removed_last_media_key {
+            self.remove_media_key_monitors();
+        }
+
         let mut hot_keys = self.hot_keys.borrow_mut();
 
         let hot_key_id = hot_keys.iter().find(|f| f.1.handle == handle).map(|e| *e.0);
@@ -160,6 +332,7 @@ impl PlatformHotKeyManager {
 
 impl Drop for PlatformHotKeyManager {
     fn drop(&mut self) {
+        self.remove_media_key_monitors();
         if !self.event_handler_ref.get().is_null() {
             unsafe { RemoveEventHandler(self.event_handler_ref.get()) };
         }
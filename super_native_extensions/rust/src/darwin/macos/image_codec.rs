@@ -0,0 +1,45 @@
+//! Platform codec for the `image_transcode` feature, based on
+//! `NSBitmapImageRep`.
+
+use objc2::rc::autoreleasepool;
+use objc2_app_kit::{NSBitmapImageFileType, NSBitmapImageRep};
+use objc2_foundation::{NSData, NSDictionary};
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+pub fn png_format() -> String {
+    "public.png".into()
+}
+
+/// TIFF is converted by the reader regardless of the feature.
+pub fn decodable_formats() -> Vec<String> {
+    vec!["public.jpeg".into(), "com.microsoft.bmp".into()]
+}
+
+/// Many Cocoa applications only read TIFF images from the pasteboard.
+pub fn encodable_formats() -> Vec<String> {
+    vec!["public.tiff".into()]
+}
+
+fn transcode(data: &[u8], file_type: NSBitmapImageFileType) -> NativeExtensionsResult<Vec<u8>> {
+    autoreleasepool(|_| unsafe {
+        let data = NSData::with_bytes(data);
+        let rep = NSBitmapImageRep::imageRepWithData(&data)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Failed to decode image".into()))?;
+        let res = rep
+            .representationUsingType_properties(file_type, &NSDictionary::dictionary())
+            .ok_or_else(|| NativeExtensionsError::OtherError("Failed to encode image".into()))?;
+        Ok(res.bytes().to_vec())
+    })
+}
+
+pub fn decode_to_png(data: &[u8]) -> NativeExtensionsResult<Vec<u8>> {
+    transcode(data, NSBitmapImageFileType::PNG)
+}
+
+pub fn encode_from_png(data: &[u8], format: &str) -> NativeExtensionsResult<Vec<u8>> {
+    match format {
+        "public.tiff" => transcode(data, NSBitmapImageFileType::TIFF),
+        _ => Err(NativeExtensionsError::UnsupportedOperation),
+    }
+}
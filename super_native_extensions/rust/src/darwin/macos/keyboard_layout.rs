@@ -6,22 +6,23 @@ use std::{
 };
 
 use core_foundation::{
-    base::CFRelease,
+    base::{CFRelease, TCFType},
     data::{CFDataGetBytePtr, CFDataRef},
     dictionary::CFDictionaryRef,
-    string::CFStringRef,
+    string::{CFString, CFStringRef},
 };
 use irondash_message_channel::Late;
 
 use crate::keyboard_layout_manager::{Key, KeyboardLayout, KeyboardLayoutDelegate};
 
 use super::keyboard_layout_sys::{
-    altKey, cmdKey, kTISNotifySelectedKeyboardInputSourceChanged, kTISPropertyUnicodeKeyLayoutData,
-    kUCKeyActionDisplay, kUCKeyTranslateNoDeadKeysMask, shiftKey, CFNotificationCenterAddObserver,
-    CFNotificationCenterGetDistributedCenter, CFNotificationCenterRef,
-    CFNotificationCenterRemoveObserver, CFNotificationSuspensionBehaviorCoalesce, CFObject,
-    LMGetKbdType, TISCopyCurrentASCIICapableKeyboardLayoutInputSource, TISGetInputSourceProperty,
-    UCKeyTranslate,
+    altKey, cmdKey, kTISNotifySelectedKeyboardInputSourceChanged, kTISPropertyLocalizedName,
+    kTISPropertyUnicodeKeyLayoutData, kUCKeyActionDisplay, kUCKeyTranslateNoDeadKeysMask, shiftKey,
+    CFNotificationCenterAddObserver, CFNotificationCenterGetDistributedCenter,
+    CFNotificationCenterRef, CFNotificationCenterRemoveObserver,
+    CFNotificationSuspensionBehaviorCoalesce, CFObject, LMGetKbdType,
+    TISCopyCurrentASCIICapableKeyboardLayoutInputSource, TISCopyCurrentKeyboardLayoutInputSource,
+    TISGetInputSourceProperty, UCKeyTranslate,
 };
 
 pub struct PlatformKeyboardLayout {
@@ -52,6 +53,20 @@ impl PlatformKeyboardLayout {
         )
     }
 
+    pub fn get_current_layout_name(&self) -> Option<String> {
+        unsafe {
+            let input_source = TISCopyCurrentKeyboardLayoutInputSource();
+            if input_source.is_null() {
+                return None;
+            }
+            let name = TISGetInputSourceProperty(input_source, kTISPropertyLocalizedName);
+            let res = (!name.is_null())
+                .then(|| CFString::wrap_under_get_rule(name as CFStringRef).to_string());
+            CFRelease(input_source);
+            res
+        }
+    }
+
     fn create_keyboard_layout(&self) -> KeyboardLayout {
         let key_map = get_key_map();
         unsafe {
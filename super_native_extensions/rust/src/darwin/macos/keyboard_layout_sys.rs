@@ -8,8 +8,10 @@ pub type CFNotificationCenterRef = CFObject;
 #[link(name = "Carbon", kind = "framework")]
 extern "C" {
     pub static kTISPropertyUnicodeKeyLayoutData: CFObject;
+    pub static kTISPropertyLocalizedName: CFObject;
     pub static kTISNotifySelectedKeyboardInputSourceChanged: CFStringRef;
     pub fn TISCopyCurrentASCIICapableKeyboardLayoutInputSource() -> CFObject;
+    pub fn TISCopyCurrentKeyboardLayoutInputSource() -> CFObject;
     pub fn TISGetInputSourceProperty(input_source: CFObject, property_key: CFObject)
         -> *mut c_void;
 
@@ -0,0 +1,130 @@
+use std::ffi::c_void;
+
+use objc2::{
+    class, msg_send, msg_send_id,
+    rc::{autoreleasepool, Id},
+    runtime::NSObject,
+    Encode, Encoding,
+};
+use objc2_foundation::{NSArray, NSDictionary, NSSize, NSString, NSURL};
+
+use crate::media_info::{fourcc_to_string, MediaMetadata};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct CMTime {
+    value: i64,
+    timescale: i32,
+    flags: u32,
+    epoch: i64,
+}
+
+// CMTime is declared as anonymous struct
+unsafe impl Encode for CMTime {
+    const ENCODING: Encoding = Encoding::Struct(
+        "?",
+        &[i64::ENCODING, i32::ENCODING, u32::ENCODING, i64::ENCODING],
+    );
+}
+
+const K_CMTIME_FLAGS_VALID: u32 = 1;
+
+#[repr(C)]
+struct AudioStreamBasicDescription {
+    sample_rate: f64,
+    format_id: u32,
+    format_flags: u32,
+    bytes_per_packet: u32,
+    frames_per_packet: u32,
+    bytes_per_frame: u32,
+    channels_per_frame: u32,
+    bits_per_channel: u32,
+    reserved: u32,
+}
+
+#[link(name = "AVFoundation", kind = "framework")]
+extern "C" {
+    static AVMediaTypeVideo: &'static NSString;
+    static AVMediaTypeAudio: &'static NSString;
+}
+
+#[link(name = "CoreMedia", kind = "framework")]
+extern "C" {
+    fn CMFormatDescriptionGetMediaSubType(desc: *const c_void) -> u32;
+    fn CMAudioFormatDescriptionGetStreamBasicDescription(
+        desc: *const c_void,
+    ) -> *const AudioStreamBasicDescription;
+}
+
+unsafe fn tracks_with_media_type(asset: &NSObject, media_type: &NSString) -> Id<NSArray<NSObject>> {
+    msg_send_id![asset, tracksWithMediaType: media_type]
+}
+
+/// Returns CMFormatDescriptionRef for each format of the track.
+unsafe fn format_descriptions(track: &NSObject) -> Vec<Id<NSObject>> {
+    let descriptions: Id<NSArray<NSObject>> = msg_send_id![track, formatDescriptions];
+    (0..descriptions.count())
+        .map(|i| descriptions.objectAtIndex(i))
+        .collect()
+}
+
+fn description_ptr(description: &Id<NSObject>) -> *const c_void {
+    Id::as_ptr(description) as *const c_void
+}
+
+/// Loads metadata of a local media file through AVFoundation. Asset
+/// properties are loaded synchronously so this must not be called on main
+/// thread.
+pub fn media_metadata_for_file_url(url: &str) -> Option<MediaMetadata> {
+    autoreleasepool(|_| unsafe {
+        let url = NSURL::URLWithString(&NSString::from_str(url))?;
+        let asset: Option<Id<NSObject>> = msg_send_id![
+            class!(AVURLAsset),
+            URLAssetWithURL: &*url,
+            options: None::<&NSDictionary>
+        ];
+        let asset = asset?;
+        let mut res = MediaMetadata::default();
+
+        let duration: CMTime = msg_send![&asset, duration];
+        if duration.flags & K_CMTIME_FLAGS_VALID != 0 && duration.timescale > 0 {
+            res.duration_millis = duration
+                .value
+                .checked_mul(1000)
+                .map(|v| v / duration.timescale as i64);
+        }
+
+        let video_tracks = tracks_with_media_type(&asset, AVMediaTypeVideo);
+        let audio_tracks = tracks_with_media_type(&asset, AVMediaTypeAudio);
+        res.has_video = Some(video_tracks.count() > 0);
+        res.has_audio = Some(audio_tracks.count() > 0);
+
+        for i in 0..video_tracks.count() {
+            let track = video_tracks.objectAtIndex(i);
+            if res.width.is_none() {
+                let size: NSSize = msg_send![&track, naturalSize];
+                res.width = Some(size.width.round() as i64);
+                res.height = Some(size.height.round() as i64);
+            }
+            for description in format_descriptions(&track) {
+                let codec = CMFormatDescriptionGetMediaSubType(description_ptr(&description));
+                res.codecs.push(fourcc_to_string(&codec.to_be_bytes()));
+            }
+        }
+        for i in 0..audio_tracks.count() {
+            let track = audio_tracks.objectAtIndex(i);
+            for description in format_descriptions(&track) {
+                let description = description_ptr(&description);
+                let codec = CMFormatDescriptionGetMediaSubType(description);
+                res.codecs.push(fourcc_to_string(&codec.to_be_bytes()));
+                let basic = CMAudioFormatDescriptionGetStreamBasicDescription(description);
+                if let Some(basic) = basic.as_ref() {
+                    res.sample_rate.get_or_insert(basic.sample_rate as i64);
+                    res.channel_count
+                        .get_or_insert(basic.channels_per_frame as i64);
+                }
+            }
+        }
+        Some(res)
+    })
+}
@@ -3,7 +3,7 @@ use std::rc::{Rc, Weak};
 use block2::{Block, RcBlock};
 use irondash_engine_context::EngineContext;
 use irondash_message_channel::IsolateId;
-use irondash_run_loop::{spawn, util::FutureCompleter, RunLoop};
+use irondash_run_loop::{util::FutureCompleter, RunLoop};
 use objc2::{
     extern_class, extern_methods,
     mutability::MainThreadOnly,
@@ -15,12 +15,14 @@ use objc2_foundation::{ns_string, MainThreadMarker, NSPoint, NSString, NSUIntege
 
 use crate::{
     api_model::{
-        Activator, ImageData, Menu, MenuElement, MenuImage, ShowContextMenuRequest,
-        ShowContextMenuResponse,
+        Activator, ImageData, Menu, MenuActionState, MenuElement, MenuImage,
+        ShowContextMenuRequest, ShowContextMenuResponse,
     },
     error::NativeExtensionsResult,
     log::OkLog,
-    menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
+    menu_manager::{
+        MenuItemStates, PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate,
+    },
 };
 
 use super::util::{flip_position, ns_image_for_menu_item};
@@ -30,8 +32,11 @@ pub struct PlatformMenuContext {
     view: Id<NSView>,
 }
 
+type ItemStates = MenuItemStates<Id<NSMenuItem>>;
+
 pub struct PlatformMenu {
     menu: Id<NSMenu>,
+    states: Rc<ItemStates>,
 }
 
 impl std::fmt::Debug for PlatformMenu {
@@ -98,10 +103,37 @@ impl PlatformMenu {
         NSEventModifierFlags(res)
     }
 
+    fn ns_state(state: MenuActionState) -> isize {
+        match state {
+            MenuActionState::None => 0,
+            MenuActionState::CheckOn => 1,
+            MenuActionState::CheckOff => 0,
+            MenuActionState::CheckMixed => -1,
+            MenuActionState::RadioOn => 1,
+            MenuActionState::RadioOff => 0,
+        }
+    }
+
+    fn apply_states(updates: Vec<(Id<NSMenuItem>, MenuActionState)>) {
+        for (item, state) in updates {
+            unsafe { item.setState(Self::ns_state(state)) };
+        }
+    }
+
+    pub fn update_item_state(
+        &self,
+        unique_id: i64,
+        state: MenuActionState,
+    ) -> NativeExtensionsResult<()> {
+        Self::apply_states(self.states.updates_for_state(unique_id, state)?);
+        Ok(())
+    }
+
     unsafe fn translate_menu(
         menu: &Menu,
         isolate: IsolateId,
         delegate: Weak<dyn PlatformMenuDelegate>,
+        states: &Rc<ItemStates>,
         main_thread_marker: MainThreadMarker,
     ) -> Id<NSMenu> {
         let title = menu.title.as_deref().unwrap_or_default();
@@ -110,8 +142,13 @@ impl PlatformMenu {
             &NSString::from_str(title),
         );
         for child in &menu.children {
-            let child =
-                Self::translate_element(child, isolate, delegate.clone(), main_thread_marker);
+            let child = Self::translate_element(
+                child,
+                isolate,
+                delegate.clone(),
+                states,
+                main_thread_marker,
+            );
             res.addItem(&child);
         }
         Id::into_super(res)
@@ -120,22 +157,30 @@ impl PlatformMenu {
     async unsafe fn load_deferred_menu_item(
         item: &NSMenuItem,
         item_id: i64,
+        submenu: bool,
         isolate: IsolateId,
         weak_delegate: Weak<dyn PlatformMenuDelegate>,
+        weak_states: Weak<ItemStates>,
         main_thread_marker: MainThreadMarker,
     ) {
-        if let Some(delegate) = weak_delegate.upgrade() {
+        if let (Some(delegate), Some(states)) = (weak_delegate.upgrade(), weak_states.upgrade()) {
             let parent_menu = item.menu();
             let Some(parent_menu) = parent_menu else {
                 return;
             };
-            let elements = delegate.get_deferred_menu(isolate, item_id).await.ok_log();
+            let elements = if submenu {
+                delegate.get_deferred_menu_items(isolate, item_id).await
+            } else {
+                delegate.get_deferred_menu(isolate, item_id).await
+            };
+            let elements = elements.ok_log();
 
             for element in elements.unwrap_or_default() {
                 let element = Self::translate_element(
                     &element,
                     isolate,
                     weak_delegate.clone(),
+                    &states,
                     main_thread_marker,
                 );
                 let index = parent_menu.indexOfItem(item);
@@ -149,6 +194,7 @@ impl PlatformMenu {
         element: &MenuElement,
         isolate: IsolateId,
         delegate: Weak<dyn PlatformMenuDelegate>,
+        states: &Rc<ItemStates>,
         main_thread_marker: MainThreadMarker,
     ) -> Id<NSMenuItem> {
         match element {
@@ -164,7 +210,11 @@ impl PlatformMenu {
                     )
                 } else {
                     let action = menu_action.unique_id;
+                    let states = Rc::downgrade(states);
                     let action = move |_item: *mut NSMenuItem| {
+                        if let Some(states) = states.upgrade() {
+                            Self::apply_states(states.updates_for_activation(action));
+                        }
                         if let Some(delegate) = delegate.upgrade() {
                             delegate.on_action(isolate, action);
                         }
@@ -193,16 +243,14 @@ impl PlatformMenu {
                     }
                 }
 
-                let state: isize = match menu_action.state {
-                    crate::api_model::MenuActionState::None => 0,
-                    crate::api_model::MenuActionState::CheckOn => 1,
-                    crate::api_model::MenuActionState::CheckOff => 0,
-                    crate::api_model::MenuActionState::CheckMixed => -1,
-                    crate::api_model::MenuActionState::RadioOn => 1,
-                    crate::api_model::MenuActionState::RadioOff => 0,
-                };
-                item.setState(state);
-                Id::into_super(item)
+                item.setState(Self::ns_state(menu_action.state));
+                let item = Id::into_super(item);
+                states.register(
+                    menu_action.unique_id,
+                    item.clone(),
+                    menu_action.radio_group.clone(),
+                );
+                item
             }
             MenuElement::Menu(menu) => {
                 let title = menu.title.as_deref().unwrap_or_default();
@@ -217,48 +265,106 @@ impl PlatformMenu {
                     let image = ns_image_for_menu_item(data.clone());
                     item.setImage(Some(&image));
                 }
-                let submenu =
-                    Self::translate_menu(menu, isolate, delegate.clone(), main_thread_marker);
+                let submenu = Self::translate_menu(
+                    menu,
+                    isolate,
+                    delegate.clone(),
+                    states,
+                    main_thread_marker,
+                );
                 item.setSubmenu(Some(&submenu));
                 item
             }
-            MenuElement::Deferred(item) => {
-                let item_id = item.unique_id;
-                let action = move |item: *mut NSMenuItem| {
-                    let item = unsafe { &*item };
-                    let delegate = delegate.clone();
-                    let item = item.retain();
-                    spawn(async move {
-                        Self::load_deferred_menu_item(
-                            &item,
-                            item_id,
-                            isolate,
-                            delegate.clone(),
-                            main_thread_marker,
-                        )
-                        .await;
-                    });
-                };
-                let action = RcBlock::new(action);
-
-                let item = SNEDeferredMenuItem::initWithBlock(
-                    main_thread_marker.alloc::<SNEDeferredMenuItem>(),
-                    &action,
+            MenuElement::Deferred(item) => Self::deferred_item(
+                item.unique_id,
+                false,
+                isolate,
+                delegate,
+                states,
+                main_thread_marker,
+            ),
+            MenuElement::DeferredMenu(menu) => {
+                let title = NSString::from_str(menu.title.as_deref().unwrap_or_default());
+                let item = NSMenuItem::initWithTitle_action_keyEquivalent(
+                    main_thread_marker.alloc::<NSMenuItem>(),
+                    &title,
+                    None,
+                    ns_string!(""),
                 );
-                Id::into_super(item)
+                if let Some(MenuImage::Image { data }) = &menu.image {
+                    let image = ns_image_for_menu_item(data.clone());
+                    item.setImage(Some(&image));
+                }
+                // Placeholder replaces itself with the children once the
+                // submenu is shown.
+                let submenu = SNEMenu::initWithTitle(main_thread_marker.alloc::<SNEMenu>(), &title);
+                submenu.addItem(&Self::deferred_item(
+                    menu.unique_id,
+                    true,
+                    isolate,
+                    delegate,
+                    states,
+                    main_thread_marker,
+                ));
+                item.setSubmenu(Some(&submenu));
+                item
             }
             MenuElement::Separator(_) => NSMenuItem::separatorItem(main_thread_marker),
         }
     }
 
+    unsafe fn deferred_item(
+        item_id: i64,
+        submenu: bool,
+        isolate: IsolateId,
+        delegate: Weak<dyn PlatformMenuDelegate>,
+        states: &Rc<ItemStates>,
+        main_thread_marker: MainThreadMarker,
+    ) -> Id<NSMenuItem> {
+        let states = Rc::downgrade(states);
+        let action = move |item: *mut NSMenuItem| {
+            let item = unsafe { &*item };
+            let delegate = delegate.clone();
+            let states = states.clone();
+            let item = item.retain();
+            let Some(spawner) = delegate.upgrade() else {
+                return;
+            };
+            spawner.spawn(
+                isolate,
+                Box::pin(async move {
+                    Self::load_deferred_menu_item(
+                        &item,
+                        item_id,
+                        submenu,
+                        isolate,
+                        delegate,
+                        states,
+                        main_thread_marker,
+                    )
+                    .await;
+                }),
+            );
+        };
+        let action = RcBlock::new(action);
+
+        let item = SNEDeferredMenuItem::initWithBlock(
+            main_thread_marker.alloc::<SNEDeferredMenuItem>(),
+            &action,
+        );
+        Id::into_super(item)
+    }
+
     pub fn new(
         isolate: IsolateId,
         delegate: Weak<dyn PlatformMenuDelegate>,
         menu: Menu,
     ) -> NativeExtensionsResult<Rc<Self>> {
         let main_thread_marker = MainThreadMarker::new().unwrap();
-        let menu = unsafe { Self::translate_menu(&menu, isolate, delegate, main_thread_marker) };
-        Ok(Rc::new(Self { menu }))
+        let states = Rc::new(ItemStates::new());
+        let menu =
+            unsafe { Self::translate_menu(&menu, isolate, delegate, &states, main_thread_marker) };
+        Ok(Rc::new(Self { menu, states }))
     }
 }
 
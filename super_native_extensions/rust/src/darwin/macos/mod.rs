@@ -1,13 +1,19 @@
 mod data_provider;
 mod drag;
 mod drag_common;
+mod drag_environment;
 mod drop;
 mod hot_key;
 mod hot_key_sys;
+#[cfg(feature = "image_transcode")]
+pub mod image_codec;
 mod keyboard_layout;
 mod keyboard_layout_sys;
+mod media;
 mod menu;
 mod reader;
+mod services;
+mod tray_icon;
 mod util;
 
 pub use data_provider::*;
@@ -17,3 +23,5 @@ pub use hot_key::*;
 pub use keyboard_layout::*;
 pub use menu::*;
 pub use reader::*;
+pub use services::*;
+pub use tray_icon::*;
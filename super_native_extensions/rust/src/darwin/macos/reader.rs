@@ -1,22 +1,21 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs,
     path::PathBuf,
     ptr::NonNull,
     rc::{Rc, Weak},
-    sync::Arc,
     thread,
 };
 
-use block2::RcBlock;
+use block2::{Block, RcBlock};
 use irondash_message_channel::{value_darwin::ValueObjcConversion, Value};
 use irondash_run_loop::{
     util::{Capsule, FutureCompleter},
     RunLoop,
 };
 use objc2::{
-    msg_send_id,
+    msg_send, msg_send_id,
     rc::{autoreleasepool, Id},
     runtime::{AnyObject, NSObject},
     ClassType,
@@ -26,17 +25,30 @@ use objc2_app_kit::{
 };
 
 use objc2_foundation::{
-    ns_string, NSArray, NSData, NSDictionary, NSError, NSOperationQueue, NSString, NSURL,
+    ns_string, NSArray, NSData, NSDictionary, NSError, NSOperationQueue, NSProgress, NSString,
+    NSURL,
 };
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
+    link_detection::{DetectedEntity, EntityKind},
     log::OkLog,
-    platform_impl::platform::common::{format_from_url, path_from_url, uti_conforms_to},
-    reader_manager::{ReadProgress, VirtualFileReader},
+    media_info::MediaMetadata,
+    platform_impl::platform::{
+        common::{
+            exclude_remote_clipboard, format_from_url, path_from_url, set_exclude_remote_clipboard,
+            uti_conforms_to, TYPE_REMOTE_CLIPBOARD,
+        },
+        data_detector::detect_entities_with_data_detector,
+        progress_bridge::bridge_progress,
+    },
+    reader_manager::{
+        ExternalReaderSource, FormatConversion, ItemFormatConversion, ItemMetadata,
+        ReadProgressHandle, VirtualFileReader,
+    },
 };
 
-use super::PlatformDataProvider;
+use super::{media::media_metadata_for_file_url, PlatformDataProvider};
 
 #[derive(Hash, Eq, PartialEq)]
 struct ValueCacheKey {
@@ -50,6 +62,7 @@ pub struct PlatformDataReader {
     promise_receivers: RefCell<Vec<Option<Id<NSFilePromiseReceiver>>>>,
     cached_formats: RefCell<HashMap<i64, Vec<String>>>,
     value_cache: RefCell<HashMap<ValueCacheKey, Value>>,
+    disabled_conversions: RefCell<HashSet<FormatConversion>>,
 }
 
 impl PlatformDataReader {
@@ -167,6 +180,14 @@ impl PlatformDataReader {
     }
 
     fn needs_to_synthesize_png(&self, item: i64) -> bool {
+        !self
+            .disabled_conversions
+            .borrow()
+            .contains(&FormatConversion::TiffToPng)
+            && self.can_synthesize_png(item)
+    }
+
+    fn can_synthesize_png(&self, item: i64) -> bool {
         let Ok(items) = self.get_pasteboard_items() else {
             return false;
         };
@@ -184,6 +205,33 @@ impl PlatformDataReader {
         has_tiff && !has_png
     }
 
+    pub fn set_format_conversion_enabled(&self, conversion: FormatConversion, enabled: bool) {
+        let changed = if enabled {
+            self.disabled_conversions.borrow_mut().remove(&conversion)
+        } else {
+            self.disabled_conversions.borrow_mut().insert(conversion)
+        };
+        if changed {
+            self.cached_formats.borrow_mut().clear();
+        }
+    }
+
+    pub fn get_format_conversions_for_item(
+        &self,
+        item: i64,
+    ) -> NativeExtensionsResult<Vec<ItemFormatConversion>> {
+        let mut res = Vec::new();
+        if self.can_synthesize_png(item) {
+            res.push(ItemFormatConversion {
+                conversion: FormatConversion::TiffToPng,
+                source_format: "public.tiff".into(),
+                target_format: "public.png".into(),
+                enabled: self.needs_to_synthesize_png(item),
+            });
+        }
+        Ok(res)
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         item: i64,
@@ -192,6 +240,58 @@ impl PlatformDataReader {
         Ok(format == "public.png" && self.needs_to_synthesize_png(item))
     }
 
+    /// Metafiles are Windows only.
+    pub async fn rasterize_metafile_for_item(
+        &self,
+        _item: i64,
+        _width: Option<i64>,
+        _height: Option<i64>,
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Metadata of file the item refers to, if any.
+    pub async fn get_item_metadata(&self, item: i64) -> NativeExtensionsResult<ItemMetadata> {
+        let url = self
+            .do_get_data_for_item(item, "public.file-url".to_owned())
+            .await?;
+        let url = Self::value_to_string(url)
+            .and_then(|url| unsafe { NSURL::URLWithString(&NSString::from_str(&url)) });
+        let mut metadata = url
+            .map(|url| ItemMetadata::for_local_file(&path_from_url(&url)))
+            .unwrap_or_default();
+        // Bundle identifier of the source application, per nspasteboard.org
+        // convention. Only written by cooperating applications.
+        let source = self
+            .do_get_data_for_item(item, "org.nspasteboard.source".to_owned())
+            .await?;
+        metadata.source_application = Self::value_to_string(source).filter(|s| !s.is_empty());
+        Ok(metadata)
+    }
+
+    pub async fn get_media_metadata_for_item(
+        &self,
+        item: i64,
+    ) -> NativeExtensionsResult<Option<MediaMetadata>> {
+        let url = self
+            .do_get_data_for_item(item, "public.file-url".to_owned())
+            .await?;
+        let Some(url) = Self::value_to_string(url) else {
+            return Ok(None);
+        };
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let sender = RunLoop::current().new_sender();
+        thread::spawn(move || {
+            let res = media_metadata_for_file_url(&url);
+            sender.send(move || {
+                let completer = completer.take().unwrap();
+                completer.complete(Ok(res));
+            });
+        });
+        future.await
+    }
+
     fn item_has_virtual_file(&self, item: i64) -> bool {
         let Ok(items) = self.get_pasteboard_items() else {
             return false;
@@ -317,7 +417,25 @@ impl PlatformDataReader {
         &self,
         item: i64,
         data_type: String,
-        _progress: Option<Arc<ReadProgress>>,
+        progress: Option<ReadProgressHandle>,
+    ) -> NativeExtensionsResult<Value> {
+        // NSPasteboard gives no progress for Universal Clipboard transfer, at
+        // least show that the read may take a while.
+        let remote_progress = progress.filter(|_| self.is_remote_content().unwrap_or(false));
+        if let Some(progress) = &remote_progress {
+            progress.report_progress(None);
+        }
+        let res = self.get_data_for_item_inner(item, data_type).await;
+        if let Some(progress) = &remote_progress {
+            progress.report_progress(Some(1.0));
+        }
+        res
+    }
+
+    async fn get_data_for_item_inner(
+        &self,
+        item: i64,
+        data_type: String,
     ) -> NativeExtensionsResult<Value> {
         if data_type == "public.png" && self.needs_to_synthesize_png(item) {
             let tiff = self
@@ -413,12 +531,108 @@ impl PlatformDataReader {
         Ok(res)
     }
 
+    /// Formats from which `fileUriList` is synthesized.
+    pub fn file_uri_list_source_formats() -> Vec<String> {
+        vec!["public.file-url".into()]
+    }
+
     pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
+        let res = Self::from_pasteboard(unsafe { NSPasteboard::generalPasteboard() });
+        if exclude_remote_clipboard() && res.is_remote_content()? {
+            res.pasteboard_items.replace(Some(Default::default()));
+        }
+        Ok(res)
+    }
+
+    /// Reader for pasteboard with given name, such as the find pasteboard
+    /// (`NSPasteboardNameFind`). Pasteboard is created if it does not exist.
+    pub fn new_named_pasteboard_reader(name: &str) -> NativeExtensionsResult<Rc<Self>> {
+        let name = NSString::from_str(name);
         Ok(Self::from_pasteboard(unsafe {
-            NSPasteboard::generalPasteboard()
+            NSPasteboard::pasteboardWithName(&name)
         }))
     }
 
+    /// Whether reading data may pull it from another device through
+    /// Universal Clipboard.
+    pub fn is_remote_content(&self) -> NativeExtensionsResult<bool> {
+        let types = unsafe { self.pasteboard.types() }.unwrap_or_default();
+        Ok(types.iter().any(|t| t.to_string() == TYPE_REMOTE_CLIPBOARD))
+    }
+
+    /// Types of the general pasteboard are the union of item types, so this
+    /// doesn't need to enumerate items. Returns `None` when the pasteboard
+    /// has file promises, whose types are only known to promise receivers.
+    pub fn clipboard_has_any_format(formats: &[String]) -> NativeExtensionsResult<Option<bool>> {
+        let types: Vec<String> = unsafe { NSPasteboard::generalPasteboard().types() }
+            .unwrap_or_default()
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        if exclude_remote_clipboard() && types.iter().any(|t| t == TYPE_REMOTE_CLIPBOARD) {
+            return Ok(Some(false));
+        }
+        let has_type = |format: &str| types.iter().any(|t| t == format);
+        let res = formats.iter().any(|format| {
+            // PNG is synthesized from TIFF.
+            has_type(format) || (format == "public.png" && has_type("public.tiff"))
+        });
+        if !res && has_type("com.apple.NSFilePromiseItemMetaData") {
+            return Ok(None);
+        }
+        Ok(Some(res))
+    }
+
+    pub fn set_exclude_remote_content(exclude: bool) -> NativeExtensionsResult<()> {
+        set_exclude_remote_clipboard(exclude);
+        Ok(())
+    }
+
+    /// Change count of the general pasteboard.
+    pub fn clipboard_change_count() -> NativeExtensionsResult<Option<i64>> {
+        let change_count = unsafe { NSPasteboard::generalPasteboard().changeCount() };
+        Ok(Some(change_count as i64))
+    }
+
+    pub fn named_pasteboard_change_count(name: &str) -> NativeExtensionsResult<Option<i64>> {
+        let name = NSString::from_str(name);
+        let change_count = unsafe { NSPasteboard::pasteboardWithName(&name).changeCount() };
+        Ok(Some(change_count as i64))
+    }
+
+    pub fn detect_entities(
+        text: &str,
+        kinds: &[EntityKind],
+    ) -> NativeExtensionsResult<Option<Vec<DetectedEntity>>> {
+        detect_entities_with_data_detector(text, kinds)
+    }
+
+    pub fn new_with_external_source(
+        source: ExternalReaderSource,
+    ) -> NativeExtensionsResult<Rc<Self>> {
+        match source {
+            ExternalReaderSource::PasteboardName { name } => {
+                let pasteboard =
+                    unsafe { NSPasteboard::pasteboardWithName(&NSString::from_str(&name)) };
+                Ok(Self::from_pasteboard(pasteboard))
+            }
+            _ => Err(NativeExtensionsError::UnsupportedOperation),
+        }
+    }
+
+    /// Out of process reading is only supported on Windows.
+    pub fn set_out_of_process_reading(_enabled: bool) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Formats are identified by name already; there is nothing to register.
+    pub fn register_clipboard_format(
+        _identifier: &str,
+        _platform_name: &str,
+    ) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+
     pub fn from_pasteboard(pasteboard: Id<NSPasteboard>) -> Rc<Self> {
         let res = Rc::new(Self {
             pasteboard,
@@ -426,6 +640,7 @@ impl PlatformDataReader {
             promise_receivers: RefCell::new(Vec::new()),
             cached_formats: RefCell::new(HashMap::new()),
             value_cache: RefCell::new(HashMap::new()),
+            disabled_conversions: RefCell::new(HashSet::new()),
         });
         res.assign_weak_self(Rc::downgrade(&res));
         res
@@ -452,7 +667,18 @@ impl PlatformDataReader {
         &self,
         _item: i64,
         _format: &str,
-        _progress: Arc<ReadProgress>,
+        _progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        Ok(None)
+    }
+
+    /// Item data is only available in memory; caller falls back to
+    /// `get_data_for_item`.
+    pub async fn get_data_stream_for_item(
+        &self,
+        _item: i64,
+        _format: &str,
+        _progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
         Ok(None)
     }
@@ -462,15 +688,32 @@ impl PlatformDataReader {
         item: i64,
         _format: &str,
         target_folder: PathBuf,
-        _progress: Arc<ReadProgress>,
+        progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<PathBuf> {
         let receiver = self.get_promise_receiver_for_item(item)?;
         match receiver {
             Some(receiver) => {
-                let res = autoreleasepool(|_| {
+                let (res, subscriber) = autoreleasepool(|_| {
                     let target_folder = target_folder.to_string_lossy();
                     let url =
                         unsafe { NSURL::fileURLWithPath(&NSString::from_str(&target_folder)) };
+                    // Source publishes progress (in bytes) for the promised
+                    // file through file coordination.
+                    let publishing_handler = RcBlock::new(
+                        move |ns_progress: NonNull<NSProgress>| -> *mut Block<dyn Fn()> {
+                            if let Some(ns_progress) = unsafe { Id::retain(ns_progress.as_ptr()) } {
+                                bridge_progress(ns_progress, progress.clone());
+                            }
+                            std::ptr::null_mut()
+                        },
+                    );
+                    let subscriber: Id<AnyObject> = unsafe {
+                        msg_send_id![
+                            NSProgress::class(),
+                            addSubscriberForFileURL: &*url,
+                            withPublishingHandler: &*publishing_handler
+                        ]
+                    };
                     let queue = unsafe { NSOperationQueue::mainQueue() };
                     let (future, completer) = FutureCompleter::new();
                     let completer = Rc::new(RefCell::new(Some(completer)));
@@ -502,13 +745,15 @@ impl PlatformDataReader {
                             &block,
                         )
                     };
-                    future
+                    (future, subscriber)
                 });
-                res.await
+                let res = res.await;
+                unsafe {
+                    let _: () = msg_send![NSProgress::class(), removeSubscriber: &*subscriber];
+                }
+                res
             }
-            None => Err(NativeExtensionsError::OtherError(
-                "FilePromiseReceiver is not available".to_owned(),
-            )),
+            None => Err(NativeExtensionsError::VirtualFileUnsupported),
         }
     }
 
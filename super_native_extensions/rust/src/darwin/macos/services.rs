@@ -0,0 +1,271 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use irondash_message_channel::Late;
+use objc2::{
+    class, declare_class, msg_send, msg_send_id, mutability,
+    rc::Id,
+    runtime::{AnyObject, Bool, NSObject, NSObjectProtocol, Sel},
+    sel, ClassType, DeclaredClass,
+};
+use objc2_app_kit::{NSApplication, NSPasteboard, NSView};
+use objc2_foundation::{MainThreadMarker, NSArray, NSString};
+
+use crate::{
+    error::NativeExtensionsResult,
+    platform_impl::platform::PlatformDataReader,
+    services_manager::{ServicesContent, ServicesDataSource, ServicesManagerDelegate},
+};
+
+use super::util::class_builder_from_name;
+
+thread_local! {
+    static MANAGER: RefCell<Weak<PlatformServicesManager>> = RefCell::new(Weak::new());
+}
+
+static ONCE: std::sync::Once = std::sync::Once::new();
+
+pub struct PlatformServicesManager {
+    weak_self: Late<Weak<Self>>,
+    delegate: Weak<dyn ServicesManagerDelegate>,
+    main_thread_marker: MainThreadMarker,
+    content: RefCell<Option<ServicesContent>>,
+    /// Pasteboard types of the content providers.
+    send_types: RefCell<Vec<String>>,
+    provider: RefCell<Option<Id<SNEServicesProvider>>>,
+}
+
+fn to_ns_array(types: &[String]) -> Id<NSArray<NSString>> {
+    NSArray::from_vec(types.iter().map(|t| NSString::from_str(t)).collect())
+}
+
+impl PlatformServicesManager {
+    pub fn new(delegate: Weak<dyn ServicesManagerDelegate>) -> Self {
+        Self {
+            weak_self: Late::new(),
+            delegate,
+            main_thread_marker: MainThreadMarker::new().unwrap(),
+            content: RefCell::new(None),
+            send_types: RefCell::new(Vec::new()),
+            provider: RefCell::new(None),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformServicesManager>) {
+        MANAGER.with(|m| m.replace(weak.clone()));
+        self.weak_self.set(weak);
+    }
+
+    pub fn set_content(&self, content: Option<ServicesContent>) -> NativeExtensionsResult<()> {
+        ONCE.call_once(prepare_flutter);
+        let mut send_types = Vec::<String>::new();
+        for (provider, _) in content.iter().flat_map(|c| c.providers.iter()) {
+            for pasteboard_type in provider.pasteboard_types() {
+                if !send_types.contains(&pasteboard_type) {
+                    send_types.push(pasteboard_type);
+                }
+            }
+        }
+        let return_types = content
+            .as_ref()
+            .map(|c| c.return_types.clone())
+            .unwrap_or_default();
+        // AppKit only asks responders about types registered here.
+        let app = NSApplication::sharedApplication(self.main_thread_marker);
+        let send = to_ns_array(&send_types);
+        let ret = to_ns_array(&return_types);
+        unsafe {
+            let _: () = msg_send![&app, registerServicesMenuSendTypes: &*send, returnTypes: &*ret];
+        }
+        self.send_types.replace(send_types);
+        self.content.replace(content);
+        Ok(())
+    }
+
+    pub fn set_provider_enabled(&self, enabled: bool) -> NativeExtensionsResult<()> {
+        let app = NSApplication::sharedApplication(self.main_thread_marker);
+        let provider = if enabled {
+            let provider = SNEServicesProvider::alloc().set_ivars(self.weak_self.clone());
+            let provider: Id<SNEServicesProvider> = unsafe { msg_send_id![super(provider), init] };
+            Some(provider)
+        } else {
+            None
+        };
+        let object: Option<&AnyObject> = provider.as_deref().map(|p| {
+            let object: &AnyObject = p;
+            object
+        });
+        unsafe {
+            let _: () = msg_send![&app, setServicesProvider: object];
+        }
+        self.provider.replace(provider);
+        Ok(())
+    }
+
+    fn is_valid_requestor(
+        &self,
+        send_type: Option<&NSString>,
+        return_type: Option<&NSString>,
+    ) -> bool {
+        let content = self.content.borrow();
+        let Some(content) = content.as_ref() else {
+            return false;
+        };
+        let contains = |types: &[String], t: Option<&NSString>| match t {
+            Some(t) => types.contains(&t.to_string()),
+            None => true,
+        };
+        (send_type.is_some() || return_type.is_some())
+            && (send_type.is_none() || !content.providers.is_empty())
+            && contains(&self.send_types.borrow(), send_type)
+            && contains(&content.return_types, return_type)
+    }
+
+    fn write_selection(&self, pasteboard: &NSPasteboard) -> bool {
+        let content = self.content.borrow();
+        let Some(content) = content.as_ref().filter(|c| !c.providers.is_empty()) else {
+            return false;
+        };
+        let items: Vec<_> = content
+            .providers
+            .iter()
+            .map(|p| p.0.create_writer(p.1.clone(), true, false))
+            .collect();
+        let array = NSArray::from_vec(items);
+        unsafe { pasteboard.clearContents() };
+        unsafe { pasteboard.writeObjects(&Id::cast(array)) }
+    }
+
+    /// Service pasteboards are private to the request so the reader keeps
+    /// seeing the data after the service call returns.
+    fn deliver(
+        &self,
+        pasteboard: &NSPasteboard,
+        source: ServicesDataSource,
+        user_data: Option<String>,
+    ) -> bool {
+        let Some(delegate) = self.delegate.upgrade() else {
+            return false;
+        };
+        let reader = PlatformDataReader::from_pasteboard(pasteboard.retain());
+        delegate.on_services_data(reader, source, user_data);
+        true
+    }
+}
+
+impl Drop for PlatformServicesManager {
+    fn drop(&mut self) {
+        if self.provider.borrow().is_some() {
+            let app = NSApplication::sharedApplication(self.main_thread_marker);
+            let object: Option<&AnyObject> = None;
+            unsafe {
+                let _: () = msg_send![&app, setServicesProvider: object];
+            }
+        }
+    }
+}
+
+declare_class!(
+    struct SNEServicesProvider;
+
+    unsafe impl ClassType for SNEServicesProvider {
+        type Super = NSObject;
+        type Mutability = mutability::InteriorMutable;
+        const NAME: &'static str = "SNEServicesProvider";
+    }
+
+    impl DeclaredClass for SNEServicesProvider {
+        type Ivars = Weak<PlatformServicesManager>;
+    }
+
+    unsafe impl NSObjectProtocol for SNEServicesProvider {}
+
+    unsafe impl SNEServicesProvider {
+        #[method(handleServiceRequest:userData:error:)]
+        fn handle_service_request(
+            &self,
+            pasteboard: &NSPasteboard,
+            user_data: Option<&NSString>,
+            _error: *mut *mut NSString,
+        ) {
+            if let Some(manager) = self.ivars().upgrade() {
+                manager.deliver(
+                    pasteboard,
+                    ServicesDataSource::ServiceRequest,
+                    user_data.map(|d| d.to_string()),
+                );
+            }
+        }
+    }
+);
+
+//
+//
+//
+
+fn prepare_flutter() {
+    unsafe {
+        let mut class = class_builder_from_name("FlutterView");
+
+        class.add_method(
+            sel!(validRequestorForSendType:returnType:),
+            valid_requestor_for_send_type as extern "C" fn(_, _, _, _) -> _,
+        );
+
+        class.add_method(
+            sel!(writeSelectionToPasteboard:types:),
+            write_selection_to_pasteboard as extern "C" fn(_, _, _, _) -> _,
+        );
+
+        class.add_method(
+            sel!(readSelectionFromPasteboard:),
+            read_selection_from_pasteboard as extern "C" fn(_, _, _) -> _,
+        );
+    }
+}
+
+fn with_manager<F, R>(callback: F, default: R) -> R
+where
+    F: FnOnce(Rc<PlatformServicesManager>) -> R,
+{
+    match MANAGER.with(|m| m.borrow().upgrade()) {
+        Some(manager) => callback(manager),
+        None => default,
+    }
+}
+
+extern "C" fn valid_requestor_for_send_type(
+    this: &NSView,
+    _sel: Sel,
+    send_type: Option<&NSString>,
+    return_type: Option<&NSString>,
+) -> *mut AnyObject {
+    if with_manager(|m| m.is_valid_requestor(send_type, return_type), false) {
+        return this as *const NSView as *mut AnyObject;
+    }
+    unsafe {
+        msg_send![super(this, class!(NSView)), validRequestorForSendType: send_type, returnType: return_type]
+    }
+}
+
+extern "C" fn write_selection_to_pasteboard(
+    _this: &NSView,
+    _sel: Sel,
+    pasteboard: &NSPasteboard,
+    _types: &NSArray<NSString>,
+) -> Bool {
+    Bool::new(with_manager(|m| m.write_selection(pasteboard), false))
+}
+
+extern "C" fn read_selection_from_pasteboard(
+    _this: &NSView,
+    _sel: Sel,
+    pasteboard: &NSPasteboard,
+) -> Bool {
+    Bool::new(with_manager(
+        |m| m.deliver(pasteboard, ServicesDataSource::ReturnedData, None),
+        false,
+    ))
+}
@@ -13,4 +13,9 @@ pub use os::*;
 #[allow(dead_code)]
 mod common;
 
+mod clipboard_monitor;
+mod data_detector;
 mod progress_bridge;
+pub mod shared_texture;
+
+pub use clipboard_monitor::*;
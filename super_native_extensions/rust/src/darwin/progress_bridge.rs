@@ -8,17 +8,35 @@ use objc2::{
     runtime::NSObject,
     ClassType, DeclaredClass,
 };
-use objc2_foundation::{ns_string, NSKeyValueObservingOptions, NSProgress, NSString};
+use objc2_foundation::{
+    ns_string, NSKeyValueObservingOptions, NSProgress, NSProgressKindFile, NSString,
+};
 
-use crate::{reader_manager::ReadProgress, util::Movable};
+use crate::{
+    cancellation::CancellationRegistration, reader_manager::ReadProgressHandle, util::Movable,
+};
 
-/// Bridges NSProgress to ReadProgress. Will retain ReadProgress for as long as the
+/// Bridges NSProgress to ReadProgressHandle. Will retain the handle for as long as the
 /// NSProgress is alive.
-#[allow(dead_code)]
-pub fn bridge_progress(ns_progress: Id<NSProgress>, read_progress: Arc<ReadProgress>) {
+pub fn bridge_progress(ns_progress: Id<NSProgress>, read_progress: ReadProgressHandle) {
+    let cancellable = unsafe { ns_progress.isCancellable() };
+    let cancellation = cancellable.then(|| {
+        read_progress.set_cancellable(true);
+        let weak = WeakId::<NSProgress>::new(&ns_progress);
+        let weak = unsafe { Movable::new(weak) };
+        read_progress.cancellation_token().on_cancel(move || {
+            let progress = weak.load();
+            if let Some(progress) = progress {
+                unsafe {
+                    progress.cancel();
+                }
+            }
+        })
+    });
     let bridge = SNEProgressBridge::new(ProgressBridgeInner {
         ns_progress: WeakId::new(&ns_progress),
         read_progress: read_progress.clone(),
+        _cancellation: cancellation,
     });
     let key = &ASSOCIATED_OBJECT_KEY as *const _ as *const c_void;
     unsafe {
@@ -29,33 +47,33 @@ pub fn bridge_progress(ns_progress: Id<NSProgress>, read_progress: Arc<ReadProgr
             OBJC_ASSOCIATION_RETAIN,
         )
     }
-    let cancellable = unsafe { ns_progress.isCancellable() };
-    if cancellable {
-        let weak = WeakId::<NSProgress>::new(&ns_progress);
-        let weak = unsafe { Movable::new(weak) };
-        read_progress.set_cancellation_handler(Some(Box::new(move || {
-            let progress = weak.load();
-            if let Some(progress) = progress {
-                unsafe {
-                    progress.cancel();
-                }
-            }
-        })));
-    }
 }
 
 static ASSOCIATED_OBJECT_KEY: char = 'k';
 
 pub struct ProgressBridgeInner {
     ns_progress: WeakId<NSProgress>,
-    read_progress: Arc<ReadProgress>,
+    read_progress: ReadProgressHandle,
+    /// Cancels the NSProgress when read is cancelled.
+    _cancellation: Option<CancellationRegistration>,
 }
 
 impl ProgressBridgeInner {
     fn update(&mut self) {
         if let Some(ns_progress) = self.ns_progress.load() {
-            let fraction = unsafe { ns_progress.fractionCompleted() };
-            self.read_progress.report_progress(Some(fraction));
+            // File progress is counted in bytes.
+            let is_file = unsafe { ns_progress.kind() }
+                .map(|kind| &*kind == unsafe { NSProgressKindFile })
+                .unwrap_or(false);
+            if is_file {
+                let completed = unsafe { ns_progress.completedUnitCount() };
+                let total = unsafe { ns_progress.totalUnitCount() };
+                self.read_progress
+                    .report_bytes(completed, (total > 0).then_some(total));
+            } else {
+                let fraction = unsafe { ns_progress.fractionCompleted() };
+                self.read_progress.report_progress(Some(fraction));
+            }
         }
     }
 }
@@ -163,7 +181,7 @@ mod tests {
     use block2::RcBlock;
     use objc2_foundation::NSProgress;
 
-    use crate::{reader_manager::ReadProgress, util::DropNotifier, value_promise::Promise};
+    use crate::{reader_manager::ReadProgressHandle, util::DropNotifier, value_promise::Promise};
 
     #[test]
     fn test_cancellation() {
@@ -173,13 +191,13 @@ mod tests {
         let ns_progress = unsafe { NSProgress::new() };
 
         let cancellable_clone = cancellable.clone();
-        let read_progress = Arc::new(ReadProgress::new(
+        let read_progress = ReadProgressHandle::new(
             Arc::new(DropNotifier::new(move || {})),
             move |c| {
                 cancellable_clone.set(c);
             },
             move |_| {},
-        ));
+        );
 
         let cancelled_clone = cancelled.clone();
         let handler = RcBlock::new(move || {
@@ -205,15 +223,15 @@ mod tests {
             {
                 let progress = progress.clone();
                 let dropped = dropped.clone();
-                let read_progress = Arc::new(ReadProgress::new(
+                let read_progress = ReadProgressHandle::new(
                     Arc::new(DropNotifier::new(move || {
                         dropped.set(true);
                     })),
                     |_| {},
                     move |p| {
-                        progress.set(p.unwrap());
+                        progress.set(p.fraction.unwrap());
                     },
-                ));
+                );
                 super::bridge_progress(ns_progress.clone(), read_progress);
             }
             unsafe {
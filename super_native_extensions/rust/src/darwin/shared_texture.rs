@@ -0,0 +1,81 @@
+use std::{ffi::c_void, slice};
+
+use core_foundation::base::CFRelease;
+
+use crate::{
+    api_model::{ImageData, SharedTexture, SharedTextureKind},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    shared_texture::{image_data_from_pixels, PixelOrder},
+};
+
+pub const SHARED_TEXTURE_KIND: Option<SharedTextureKind> = Some(SharedTextureKind::IoSurface);
+
+type IOSurfaceRef = *mut c_void;
+
+const K_IOSURFACE_LOCK_READ_ONLY: u32 = 1;
+
+// kCVPixelFormatType_32BGRA, kCVPixelFormatType_32RGBA
+const PIXEL_FORMAT_BGRA: u32 = u32::from_be_bytes(*b"BGRA");
+const PIXEL_FORMAT_RGBA: u32 = u32::from_be_bytes(*b"RGBA");
+
+#[link(name = "IOSurface", kind = "framework")]
+extern "C" {
+    fn IOSurfaceLookup(csid: u32) -> IOSurfaceRef;
+    fn IOSurfaceLock(buffer: IOSurfaceRef, options: u32, seed: *mut u32) -> i32;
+    fn IOSurfaceUnlock(buffer: IOSurfaceRef, options: u32, seed: *mut u32) -> i32;
+    fn IOSurfaceGetBaseAddress(buffer: IOSurfaceRef) -> *mut c_void;
+    fn IOSurfaceGetBytesPerRow(buffer: IOSurfaceRef) -> usize;
+    fn IOSurfaceGetWidth(buffer: IOSurfaceRef) -> usize;
+    fn IOSurfaceGetHeight(buffer: IOSurfaceRef) -> usize;
+    fn IOSurfaceGetPixelFormat(buffer: IOSurfaceRef) -> u32;
+}
+
+unsafe fn copy_surface(
+    surface: IOSurfaceRef,
+    device_pixel_ratio: Option<f64>,
+) -> NativeExtensionsResult<ImageData> {
+    let order = match IOSurfaceGetPixelFormat(surface) {
+        PIXEL_FORMAT_BGRA => PixelOrder::Bgra,
+        PIXEL_FORMAT_RGBA => PixelOrder::Rgba,
+        _ => return Err(NativeExtensionsError::InvalidData),
+    };
+    if IOSurfaceLock(surface, K_IOSURFACE_LOCK_READ_ONLY, std::ptr::null_mut()) != 0 {
+        return Err(NativeExtensionsError::OtherError(
+            "failed to lock IOSurface".into(),
+        ));
+    }
+    let height = IOSurfaceGetHeight(surface);
+    let bytes_per_row = IOSurfaceGetBytesPerRow(surface);
+    let pixels = slice::from_raw_parts(
+        IOSurfaceGetBaseAddress(surface) as *const u8,
+        bytes_per_row * height,
+    );
+    let image = image_data_from_pixels(
+        pixels,
+        IOSurfaceGetWidth(surface) as i32,
+        height as i32,
+        bytes_per_row,
+        order,
+        device_pixel_ratio,
+    );
+    IOSurfaceUnlock(surface, K_IOSURFACE_LOCK_READ_ONLY, std::ptr::null_mut());
+    Ok(image)
+}
+
+/// Copies content of IOSurface identified by global ID.
+pub fn image_data_from_shared_texture(
+    texture: &SharedTexture,
+) -> NativeExtensionsResult<ImageData> {
+    if texture.kind != SharedTextureKind::IoSurface {
+        return Err(NativeExtensionsError::UnsupportedOperation);
+    }
+    unsafe {
+        let surface = IOSurfaceLookup(texture.handle as u32);
+        if surface.is_null() {
+            return Err(NativeExtensionsError::InvalidData);
+        }
+        let res = copy_surface(surface, texture.device_pixel_ratio);
+        CFRelease(surface as *const _);
+        res
+    }
+}
@@ -5,6 +5,7 @@ use std::{
     rc::{Rc, Weak},
     slice,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
@@ -12,31 +13,76 @@ use irondash_message_channel::{
     AsyncMethodHandler, AsyncMethodInvoker, IntoPlatformResult, IntoValue, IsolateId, Late,
     MethodCall, PlatformError, PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
-use irondash_run_loop::spawn;
 
 use crate::{
-    api_model::{DataProvider, DataProviderId, DataProviderValueId},
+    api_model::{DataProvider, DataProviderId, DataProviderValueId, DataRepresentation},
+    cancellation::CancellationToken,
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_fidelity::apply_declared_fidelity,
     log::OkLog,
     platform_impl::platform::{platform_stream_close, platform_stream_write, PlatformDataProvider},
+    rtf_html,
+    services_manager::GetServicesManager,
+    shared_buffer::resolve_shared_buffer,
+    shutdown,
+    task_scope::TaskScopes,
+    throttle::{self, set_bandwidth_limit},
     util::{DropNotifier, NextId},
     value_promise::{ValuePromise, ValuePromiseResult, ValuePromiseSetCancel},
 };
 
+#[cfg(target_os = "windows")]
+use crate::platform_impl::platform::check_virtual_folder;
+
+/// Lazy value produced by native code when first requested, instead of
+/// being requested from Dart.
+pub type SynthesizedValue = Rc<dyn Fn() -> NativeExtensionsResult<Value>>;
+
 pub enum VirtualFileResult {
     Done,
     Error { message: String },
     Cancelled,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WriteProgressUpdate {
+    /// 0.0 - 1.0, `None` when indeterminate.
+    pub fraction: Option<f64>,
+    pub bytes_written: Option<i64>,
+    pub total_bytes: Option<i64>,
+}
+
+/// Cancellation state of a virtual file being written by Dart provider;
+/// progress itself is delivered through the `on_progress` callback. Cancelled
+/// when the session is disposed by the consumer or cancelled by the provider.
+/// `Send + Sync`, so that platform code serving the file on other threads
+/// (i.e. the Windows `IStream`) can check for cancellation.
+#[derive(Clone, Default)]
+pub struct WriteProgress {
+    token: CancellationToken,
+}
+
+impl WriteProgress {
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
 /// Keeps the virtual session alive
-pub struct VirtualSessionHandle(DropNotifier);
+pub struct VirtualSessionHandle {
+    notifier: DropNotifier,
+    progress: WriteProgress,
+}
 
 #[allow(dead_code)]
 impl VirtualSessionHandle {
     pub fn dispose(&self) {
-        self.0.dispose();
+        self.notifier.dispose();
+    }
+
+    pub fn progress(&self) -> &WriteProgress {
+        &self.progress
     }
 }
 
@@ -71,7 +117,7 @@ pub trait PlatformDataProviderDelegate {
         virtual_file_id: DataProviderValueId,
         stream_handle: i32,
         on_size_known: Box<dyn Fn(Option<i64>)>,
-        on_progress: Box<dyn Fn(f64 /* 0.0 - 1.0 */)>,
+        on_progress: Box<dyn Fn(WriteProgressUpdate)>,
         on_done: Box<dyn FnOnce(VirtualFileResult)>,
     ) -> Arc<VirtualSessionHandle>;
 }
@@ -80,8 +126,12 @@ pub struct DataProviderManager {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
     next_id: Cell<i64>,
+    /// Synthesized values use negative ids so that they never collide with
+    /// ids of lazy values assigned by Dart.
+    next_synthesized_value_id: Cell<i64>,
     providers: RefCell<HashMap<DataProviderId, DataProviderEntry>>,
     virtual_sessions: RefCell<HashMap<VirtualSessionId, VirtualFileSession>>,
+    tasks: TaskScopes<IsolateId>,
 }
 
 pub trait GetDataProviderManager {
@@ -95,8 +145,20 @@ impl GetDataProviderManager for Context {
 }
 
 struct DataProviderEntry {
+    /// Isolate that owns the provider.
     isolate_id: IsolateId,
+    /// Isolate that registered the provider and serves its lazy values.
+    service_isolate_id: IsolateId,
+    /// Provider as registered, before synthesized representations were
+    /// added; used to merge providers.
+    source: DataProvider,
     platform_data_provider: Rc<PlatformDataProvider>,
+    /// When the sweeper first noticed that nothing but this manager
+    /// references the provider.
+    unreferenced_since: Cell<Option<Instant>>,
+    expires_after: Option<Duration>,
+    /// Lazy representations added by native code.
+    synthesized_values: HashMap<DataProviderValueId, SynthesizedValue>,
 }
 
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
@@ -110,10 +172,35 @@ impl From<i64> for VirtualSessionId {
 
 struct VirtualFileSession {
     isolate_id: IsolateId,
+    stream_handle: i32,
     size_known: Cell<bool>,
     on_size_known: Box<dyn Fn(Option<i64>)>,
-    on_progress: Box<dyn Fn(f64 /* 0.0 - 1.0 */)>,
+    on_progress: Box<dyn Fn(WriteProgressUpdate)>,
     on_done: Box<dyn FnOnce(VirtualFileResult)>,
+    progress: WriteProgress,
+}
+
+/// Virtual folders can only be dragged out on macOS and Windows. Elsewhere
+/// registering provider with a virtual folder fails instead of silently
+/// dropping the folder.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn check_virtual_folder(source: &DataProvider) -> NativeExtensionsResult<()> {
+    let has_folder = source.representations.iter().any(|r| {
+        matches!(
+            r,
+            crate::api_model::DataRepresentation::VirtualFolder { .. }
+        )
+    });
+    if has_folder {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn check_virtual_folder(_source: &DataProvider) -> NativeExtensionsResult<()> {
+    Ok(())
 }
 
 impl DataProviderManager {
@@ -122,8 +209,10 @@ impl DataProviderManager {
             weak_self: Late::new(),
             invoker: Late::new(),
             next_id: Cell::new(1),
+            next_synthesized_value_id: Cell::new(-1),
             providers: RefCell::new(HashMap::new()),
             virtual_sessions: RefCell::new(HashMap::new()),
+            tasks: TaskScopes::new("DataProviderManager"),
         }
         .register("DataProviderManager")
     }
@@ -139,11 +228,46 @@ impl DataProviderManager {
             .ok_or(NativeExtensionsError::DataSourceNotFound)
     }
 
+    /// Time after which clipboard contents with this provider should be
+    /// cleared.
+    pub fn get_expiration(&self, provider_id: DataProviderId) -> Option<Duration> {
+        self.providers
+            .borrow()
+            .get(&provider_id)
+            .and_then(|e| e.expires_after)
+    }
+
+    /// Provider as registered by Dart together with the isolate that serves
+    /// its lazy values.
+    pub fn get_data_provider(
+        &self,
+        provider_id: DataProviderId,
+    ) -> NativeExtensionsResult<(DataProvider, IsolateId)> {
+        self.providers
+            .borrow()
+            .get(&provider_id)
+            .map(|e| (e.source.clone(), e.service_isolate_id))
+            .ok_or(NativeExtensionsError::DataSourceNotFound)
+    }
+
     fn register_provider(
         &self,
         source: DataProvider,
         isolate_id: IsolateId,
     ) -> NativeExtensionsResult<DataProviderId> {
+        check_virtual_folder(&source)?;
+        let registered = source.clone();
+        let mut source = source;
+        if source.synthesize_rtf == Some(true) {
+            rtf_html::add_synthesized_rtf(&mut source);
+        }
+        apply_declared_fidelity(&mut source);
+        let synthesized_values = self.add_synthesized_values(&mut source);
+        let expires_after = source
+            .expires_after_ms
+            .map(|ms| Duration::from_millis(ms.max(0) as u64));
+        let owner_isolate_id = source.owner_isolate_id.map(IsolateId).unwrap_or(isolate_id);
+        // Platform providers only use the isolate to request data.
         let platform_data_source = Rc::new(PlatformDataProvider::new(
             self.weak_self.clone(),
             isolate_id,
@@ -154,18 +278,177 @@ impl DataProviderManager {
         self.providers.borrow_mut().insert(
             id,
             DataProviderEntry {
-                isolate_id,
+                isolate_id: owner_isolate_id,
+                service_isolate_id: isolate_id,
+                source: registered,
                 platform_data_provider: platform_data_source,
+                unreferenced_since: Cell::new(None),
+                expires_after,
+                synthesized_values,
             },
         );
         Ok(id)
     }
 
-    fn unregister_provider(&self, source: DataProviderId) -> NativeExtensionsResult<()> {
+    /// Adds image formats preferred by the platform as lazy representations
+    /// so that they are only encoded when requested.
+    #[cfg(feature = "image_transcode")]
+    fn add_synthesized_values(
+        &self,
+        source: &mut DataProvider,
+    ) -> HashMap<DataProviderValueId, SynthesizedValue> {
+        let mut res = HashMap::new();
+        for (format, value) in crate::image_transcode::transcoded_images(source) {
+            let id: DataProviderValueId = self.next_synthesized_value_id.get().into();
+            self.next_synthesized_value_id
+                .set(self.next_synthesized_value_id.get() - 1);
+            source
+                .representations
+                .push(DataRepresentation::Lazy { id, format });
+            res.insert(id, value);
+        }
+        res
+    }
+
+    #[cfg(not(feature = "image_transcode"))]
+    fn add_synthesized_values(
+        &self,
+        _source: &mut DataProvider,
+    ) -> HashMap<DataProviderValueId, SynthesizedValue> {
+        HashMap::new()
+    }
+
+    fn synthesized_value(&self, value_id: DataProviderValueId) -> Option<SynthesizedValue> {
+        self.providers
+            .borrow()
+            .values()
+            .find_map(|entry| entry.synthesized_values.get(&value_id).cloned())
+    }
+
+    pub fn unregister_provider(&self, source: DataProviderId) -> NativeExtensionsResult<()> {
         self.providers.borrow_mut().remove(&source);
         Ok(())
     }
 
+    /// Unregisters provider on request of Dart in `isolate_id`. Provider
+    /// registered by a worker isolate is also released in the other isolate,
+    /// whose handle would otherwise linger.
+    fn unregister_provider_from_isolate(
+        &self,
+        isolate_id: IsolateId,
+        source: DataProviderId,
+    ) -> NativeExtensionsResult<()> {
+        let entry = self.providers.borrow_mut().remove(&source);
+        if let Some(entry) = entry {
+            self.notify_provider_unregistered(&entry, source, isolate_id);
+        }
+        Context::get()
+            .services_manager()
+            .provider_unregistered(source);
+        Ok(())
+    }
+
+    /// Lets isolates of the entry other than `except` release their handle.
+    fn notify_provider_unregistered(
+        &self,
+        entry: &DataProviderEntry,
+        provider_id: DataProviderId,
+        except: IsolateId,
+    ) {
+        let mut isolates = vec![entry.isolate_id, entry.service_isolate_id];
+        isolates.dedup();
+        for isolate in isolates.into_iter().filter(|i| *i != except) {
+            self.invoker
+                .call_method_sync(isolate, "providerUnregistered", provider_id, |r| {
+                    r.ok_log();
+                });
+        }
+    }
+
+    /// Checks that provider registered by worker isolate for `isolate_id`
+    /// exists before the owner wraps it in a handle.
+    fn adopt_provider(
+        &self,
+        isolate_id: IsolateId,
+        provider_id: DataProviderId,
+    ) -> NativeExtensionsResult<()> {
+        match self.providers.borrow().get(&provider_id) {
+            Some(entry) if entry.isolate_id == isolate_id => Ok(()),
+            _ => Err(NativeExtensionsError::DataSourceNotFound),
+        }
+    }
+
+    /// Registers provider combining `base` with representations of `update`.
+    /// Representations of `update` replace those of `base` in the same
+    /// format, other representations of `base` are kept. Lazy values of both
+    /// must be served by the same isolate. The merged provider has no Dart
+    /// counterpart and must be unregistered by the caller.
+    pub fn register_merged_provider(
+        &self,
+        base: &Rc<PlatformDataProvider>,
+        update: DataProviderId,
+    ) -> NativeExtensionsResult<DataProviderId> {
+        let (merged, service_isolate_id) = {
+            let providers = self.providers.borrow();
+            let base = providers
+                .values()
+                .find(|e| Rc::ptr_eq(&e.platform_data_provider, base))
+                .ok_or(NativeExtensionsError::DataSourceNotFound)?;
+            let update = providers
+                .get(&update)
+                .ok_or(NativeExtensionsError::DataSourceNotFound)?;
+            if base.isolate_id != update.isolate_id
+                || base.service_isolate_id != update.service_isolate_id
+            {
+                return Err(NativeExtensionsError::OtherError(
+                    "Providers served by different isolates can not be merged".into(),
+                ));
+            }
+            let mut merged = base.source.clone();
+            let update_source = &update.source;
+            merged.representations.retain(|r| {
+                !update_source
+                    .representations
+                    .iter()
+                    .any(|u| u.format() == r.format())
+            });
+            merged
+                .representations
+                .extend(update_source.representations.iter().cloned());
+            merged.suggested_name = update_source
+                .suggested_name
+                .clone()
+                .or(merged.suggested_name);
+            merged.fidelity = update_source.fidelity.clone().or(merged.fidelity);
+            merged.expires_after_ms = update_source.expires_after_ms.or(merged.expires_after_ms);
+            merged.source_url = update_source.source_url.clone().or(merged.source_url);
+            merged.synthesize_rtf = update_source.synthesize_rtf.or(merged.synthesize_rtf);
+            merged.owner_isolate_id = Some(base.isolate_id.0);
+            (merged, base.service_isolate_id)
+        };
+        self.register_provider(merged, service_isolate_id)
+    }
+
+    /// Releases providers that have not been referenced by clipboard or drag
+    /// session for at least `max_idle`. These are normally unregistered by
+    /// Dart after `releaseDataProvider`; lingering ones indicate a leak.
+    /// Returns number of released providers.
+    pub fn release_idle_providers(&self, max_idle: Duration) -> usize {
+        let now = Instant::now();
+        let mut providers = self.providers.borrow_mut();
+        let count = providers.len();
+        providers.retain(|_, entry| {
+            if Rc::strong_count(&entry.platform_data_provider) > 1 {
+                entry.unreferenced_since.set(None);
+                return true;
+            }
+            let since = entry.unreferenced_since.get().unwrap_or(now);
+            entry.unreferenced_since.set(Some(since));
+            now.duration_since(since) < max_idle
+        });
+        count - providers.len()
+    }
+
     fn virtual_file_update_progress(
         &self,
         progress: VirtualFileUpdateProgress,
@@ -174,7 +457,18 @@ impl DataProviderManager {
         let session = sessions
             .get(&progress.session_id)
             .ok_or(NativeExtensionsError::VirtualFileSessionNotFound)?;
-        (session.on_progress)(progress.progress);
+        // Fraction is derived from bytes when not reported explicitly.
+        let fraction = progress.progress.or_else(|| {
+            let total = progress.total_bytes.filter(|total| *total > 0)?;
+            let written = progress.bytes_written?;
+            Some((written as f64 / total as f64).min(1.0))
+        });
+        let update = WriteProgressUpdate {
+            fraction,
+            bytes_written: progress.bytes_written,
+            total_bytes: progress.total_bytes,
+        };
+        (session.on_progress)(update);
         Ok(())
     }
 
@@ -191,12 +485,23 @@ impl DataProviderManager {
         Ok(())
     }
 
-    fn virtual_file_complete(&self, complete: VirtualFileComplete) -> NativeExtensionsResult<()> {
+    /// Removes the session and releases throttle of its stream, in case Dart
+    /// didn't close the stream (i.e. the isolate is gone).
+    fn end_virtual_session(
+        &self,
+        session_id: VirtualSessionId,
+    ) -> NativeExtensionsResult<VirtualFileSession> {
         let session = self
             .virtual_sessions
             .borrow_mut()
-            .remove(&complete.session_id)
+            .remove(&session_id)
             .ok_or(NativeExtensionsError::VirtualFileSessionNotFound)?;
+        throttle::stream_closed(session.stream_handle);
+        Ok(session)
+    }
+
+    fn virtual_file_complete(&self, complete: VirtualFileComplete) -> NativeExtensionsResult<()> {
+        let session = self.end_virtual_session(complete.session_id)?;
         if !session.size_known.get() {
             (session.on_size_known)(None);
         }
@@ -205,11 +510,7 @@ impl DataProviderManager {
     }
 
     fn virtual_file_error(&self, error: VirtualFileError) -> NativeExtensionsResult<()> {
-        let session = self
-            .virtual_sessions
-            .borrow_mut()
-            .remove(&error.session_id)
-            .ok_or(NativeExtensionsError::VirtualFileSessionNotFound)?;
+        let session = self.end_virtual_session(error.session_id)?;
         if !session.size_known.get() {
             (session.on_size_known)(None);
         }
@@ -220,11 +521,8 @@ impl DataProviderManager {
     }
 
     fn virtual_file_cancel(&self, complete: VirtualFileCancel) -> NativeExtensionsResult<()> {
-        let session = self
-            .virtual_sessions
-            .borrow_mut()
-            .remove(&complete.session_id)
-            .ok_or(NativeExtensionsError::VirtualFileSessionNotFound)?;
+        let session = self.end_virtual_session(complete.session_id)?;
+        session.progress.token.cancel();
         if !session.size_known.get() {
             (session.on_size_known)(None);
         }
@@ -243,19 +541,31 @@ impl PlatformDataProviderDelegate for DataProviderManager {
     ) -> Arc<ValuePromise> {
         let res = Arc::new(ValuePromise::new());
         let res_clone = res.clone();
+        let res_abort = res.clone();
+        let on_done = Rc::new(Cell::new(on_done));
+        let on_done_abort = on_done.clone();
         let weak_self = self.weak_self.clone();
-        spawn(async move {
-            let this = weak_self.upgrade();
-            if let Some(this) = this {
-                let res = this.get_lazy_data_async(isolate_id, data_id).await;
-                res_clone.set(res);
-                if let Some(on_done) = on_done {
+        self.tasks.spawn_or_else(
+            isolate_id,
+            async move {
+                let this = weak_self.upgrade();
+                if let Some(this) = this {
+                    let res = this.get_lazy_data_async(isolate_id, data_id).await;
+                    res_clone.set(res);
+                    if let Some(on_done) = on_done.take() {
+                        on_done();
+                    }
+                } else {
+                    res_clone.cancel();
+                }
+            },
+            move || {
+                res_abort.cancel();
+                if let Some(on_done) = on_done_abort.take() {
                     on_done();
                 }
-            } else {
-                res_clone.cancel();
-            }
-        });
+            },
+        );
         res
     }
 
@@ -270,11 +580,19 @@ impl PlatformDataProviderDelegate for DataProviderManager {
             value_id: DataProviderValueId,
         }
 
+        if let Some(value) = self.synthesized_value(value_id) {
+            return ValuePromiseResult::Ok {
+                value: value().ok_log().unwrap_or(Value::Null),
+            };
+        }
         let res = self
             .invoker
             .call_method_cv(isolate_id, "getLazyData", LazyDataRequest { value_id })
             .await;
         match res {
+            Ok(ValuePromiseResult::Ok { value }) => ValuePromiseResult::Ok {
+                value: resolve_shared_buffer(value),
+            },
             Ok(res) => res,
             Err(_) => ValuePromiseResult::Cancelled,
         }
@@ -286,17 +604,20 @@ impl PlatformDataProviderDelegate for DataProviderManager {
         virtual_file_id: DataProviderValueId,
         stream_handle: i32,
         on_size_known: Box<dyn Fn(Option<i64>)>,
-        on_progress: Box<dyn Fn(f64 /* 0.0 - 1.0 */)>,
+        on_progress: Box<dyn Fn(WriteProgressUpdate)>,
         on_done: Box<dyn FnOnce(VirtualFileResult)>,
     ) -> Arc<VirtualSessionHandle> {
         let weak_self = self.weak_self.clone();
         let session_id: VirtualSessionId = self.next_id.next_id().into();
+        let progress = WriteProgress::default();
         let sesion = VirtualFileSession {
             isolate_id,
+            stream_handle,
             size_known: Cell::new(false),
             on_size_known,
             on_progress,
             on_done,
+            progress: progress.clone(),
         };
         self.virtual_sessions
             .borrow_mut()
@@ -320,14 +641,23 @@ impl PlatformDataProviderDelegate for DataProviderManager {
                 r.ok_log();
             },
         );
-        Arc::new(VirtualSessionHandle(DropNotifier::new(move || {
-            if let Some(this) = weak_self.upgrade() {
-                this.invoker
-                    .call_method_sync(isolate_id, "cancelVirtualFile", session_id, |r| {
-                        r.ok_log();
-                    });
-            }
-        })))
+        let progress_clone = progress.clone();
+        Arc::new(VirtualSessionHandle {
+            notifier: DropNotifier::new(move || {
+                progress_clone.token.cancel();
+                if let Some(this) = weak_self.upgrade() {
+                    this.invoker.call_method_sync(
+                        isolate_id,
+                        "cancelVirtualFile",
+                        session_id,
+                        |r| {
+                            r.ok_log();
+                        },
+                    );
+                }
+            }),
+            progress,
+        })
     }
 }
 
@@ -335,7 +665,10 @@ impl PlatformDataProviderDelegate for DataProviderManager {
 #[irondash(rename_all = "camelCase")]
 struct VirtualFileUpdateProgress {
     session_id: VirtualSessionId,
-    progress: f64,
+    /// 0.0 - 1.0
+    progress: Option<f64>,
+    bytes_written: Option<i64>,
+    total_bytes: Option<i64>,
 }
 
 #[derive(Debug, TryFromValue)]
@@ -357,6 +690,14 @@ struct VirtualFileCancel {
     session_id: VirtualSessionId,
 }
 
+#[derive(Debug, TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct BandwidthLimit {
+    /// Applies to virtual file sessions started afterwards. `None` removes
+    /// the limit.
+    bytes_per_second: Option<i64>,
+}
+
 #[derive(Debug, TryFromValue)]
 #[irondash(rename_all = "camelCase")]
 struct VirtualFileError {
@@ -372,7 +713,13 @@ impl AsyncMethodHandler for DataProviderManager {
                 .register_provider(call.args.try_into()?, call.isolate)
                 .into_platform_result(),
             "unregisterDataProvider" => self
-                .unregister_provider(call.args.try_into()?)
+                .unregister_provider_from_isolate(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            // Identifies the isolate to worker isolates registering providers
+            // on its behalf.
+            "getIsolateId" => Ok(call.isolate.0.into()),
+            "adoptDataProvider" => self
+                .adopt_provider(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
             "virtualFileUpdateProgress" => self
                 .virtual_file_update_progress(call.args.try_into()?)
@@ -389,6 +736,11 @@ impl AsyncMethodHandler for DataProviderManager {
             "virtualFileCancel" => self
                 .virtual_file_cancel(call.args.try_into()?)
                 .into_platform_result(),
+            "setBandwidthLimit" => {
+                let limit: BandwidthLimit = call.args.try_into()?;
+                set_bandwidth_limit(limit.bytes_per_second);
+                Ok(Value::Null)
+            }
             _ => Err(PlatformError {
                 code: "invalid_method".into(),
                 message: Some(format!("Unknown Method: {}", call.method)),
@@ -407,11 +759,16 @@ impl AsyncMethodHandler for DataProviderManager {
 
     // Called when engine is about to be destroyed.
     fn on_isolate_destroyed(&self, isolate_id: IsolateId) {
-        let mut providers = self.providers.borrow_mut();
-        let providers_to_remove: Vec<_> = providers
+        // Clipboard must be flushed while the providers still exist.
+        shutdown::shutdown(isolate_id);
+        // Providers served by a worker isolate can not produce data once the
+        // worker is gone; the owner is told to release them.
+        let providers_to_remove: Vec<_> = self
+            .providers
+            .borrow()
             .iter()
-            .filter_map(|(id, source)| {
-                if source.isolate_id == isolate_id {
+            .filter_map(|(id, entry)| {
+                if entry.isolate_id == isolate_id || entry.service_isolate_id == isolate_id {
                     Some(*id)
                 } else {
                     None
@@ -419,7 +776,10 @@ impl AsyncMethodHandler for DataProviderManager {
             })
             .collect();
         for source_id in providers_to_remove {
-            providers.remove(&source_id);
+            let entry = self.providers.borrow_mut().remove(&source_id);
+            if let Some(entry) = entry {
+                self.notify_provider_unregistered(&entry, source_id, isolate_id);
+            }
         }
 
         let sessions_to_remove: Vec<_> = {
@@ -439,6 +799,7 @@ impl AsyncMethodHandler for DataProviderManager {
             self.virtual_file_cancel(VirtualFileCancel { session_id })
                 .ok_log();
         }
+        self.tasks.close(isolate_id);
     }
 }
 
@@ -451,10 +812,28 @@ pub extern "C" fn super_native_extensions_stream_write(
     len: i64,
 ) -> i32 {
     let buf = unsafe { slice::from_raw_parts(data as *const u8, len as usize) };
-    platform_stream_write(handle, buf)
+    let res = platform_stream_write(handle, buf);
+    if res == 0 {
+        // Failed stream receives no more writes.
+        throttle::stream_closed(handle);
+    }
+    res
+}
+
+/// Returns number of microseconds the writer must wait after writing `len`
+/// bytes to stay within bandwidth limit.
+#[no_mangle]
+pub extern "C" fn super_native_extensions_stream_write_delay(handle: i32, len: i64) -> i64 {
+    // On Windows the stream consumer is paced instead.
+    if cfg!(target_os = "windows") {
+        return 0;
+    }
+    let delay = throttle::stream_write_delay(handle, len.max(0) as usize);
+    delay.as_micros().min(i64::MAX as u128) as i64
 }
 
 #[no_mangle]
 pub extern "C" fn super_native_extensions_stream_close(handle: i32, delete: bool) {
+    throttle::stream_closed(handle);
     platform_stream_close(handle, delete);
 }
@@ -3,6 +3,7 @@ use std::{
     collections::HashMap,
     rc::{Rc, Weak},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
@@ -11,26 +12,39 @@ use irondash_message_channel::{
     AsyncMethodHandler, AsyncMethodInvoker, IntoPlatformResult, IntoValue, IsolateId, Late,
     MethodCall, PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
-use irondash_run_loop::spawn;
 use log::warn;
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragItem, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DataTransferProgress, DragCancelReason, DragConfiguration,
+        DragEnvironmentChange, DragItem, DragRequest, DropOperation, KeyModifiers, Point,
+        SharedTextureKind, TargettedImage, TouchDragSettings,
+    },
     context::Context,
     data_provider_manager::{DataProviderHandle, GetDataProviderManager},
+    drag_monitor::{DragRole, DragSessionInfo, GetDragMonitor},
     drop_manager::GetDropManager,
     error::{NativeExtensionsError, NativeExtensionsResult},
-    log::{OkLog, OkLogUnexpected},
+    log::{OkLog, OkLogUnexpected, OperationSpan},
     menu_manager::GetMenuManager,
     platform_impl::platform::{
         PlatformDataProvider, PlatformDragContext, PlatformDropContext, PlatformMenuContext,
     },
+    shared_texture::{resolve_drag_request, resolve_image, shared_texture_kind},
+    shutdown,
+    task_scope::TaskScopes,
     util::{DropNotifier, NextId},
     value_promise::{Promise, PromiseResult},
 };
 
-// Each isolate has its own DragContext.
-pub type PlatformDragContextId = IsolateId;
+/// Drag context is created for each Flutter view registered by an isolate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlatformDragContextId {
+    pub isolate: IsolateId,
+    /// Engine handle of the view, see
+    /// [`PlatformDropContextId`](crate::drop_manager::PlatformDropContextId).
+    pub engine_handle: i64,
+}
 
 pub struct DataProviderEntry {
     pub provider: Rc<PlatformDataProvider>,
@@ -72,18 +86,49 @@ pub trait PlatformDragContextDelegate {
         location: Point,
     ) -> Arc<Promise<PromiseResult<bool>>>;
 
+    /// `modifiers` are the modifier keys held down at the time of the move.
     fn drag_session_did_move_to_location(
         &self,
         id: PlatformDragContextId,
         session_id: DragSessionId,
         screen_location: Point,
+        modifiers: KeyModifiers,
+    );
+
+    fn drag_session_environment_did_change(
+        &self,
+        id: PlatformDragContextId,
+        session_id: DragSessionId,
+        change: DragEnvironmentChange,
+        screen_location: Point,
     );
 
+    /// `cancel_reason` is provided on desktop platforms when session ended
+    /// without drop.
     fn drag_session_did_end_with_operation(
         &self,
         id: PlatformDragContextId,
         session_id: DragSessionId,
         operation: DropOperation,
+        cancel_reason: Option<DragCancelReason>,
+    );
+
+    /// Invoked for cancelled or failed drag sessions that have snap-back
+    /// animation disabled, before `drag_session_did_end_with_operation`.
+    fn drag_session_did_cancel(
+        &self,
+        id: PlatformDragContextId,
+        session_id: DragSessionId,
+        screen_location: Point,
+    );
+
+    /// Reports data transfer that outlives the session, after
+    /// `drag_session_did_end_with_operation`.
+    fn drag_session_data_transfer_progress(
+        &self,
+        id: PlatformDragContextId,
+        session_id: DragSessionId,
+        progress: DataTransferProgress,
     );
 }
 
@@ -106,9 +151,46 @@ pub struct DragManager {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
     contexts: RefCell<HashMap<PlatformDragContextId, Rc<PlatformDragContext>>>,
+    /// View registered through `newContext`, used when request doesn't
+    /// specify a view.
+    primary_views: RefCell<HashMap<IsolateId, i64>>,
+    touch_drag_settings: RefCell<HashMap<IsolateId, TouchDragSettings>>,
     next_session_id: Cell<i64>,
+    item_operations: RefCell<HashMap<DragSessionId, SessionItemOperations>>,
+    motion: RefCell<HashMap<DragSessionId, SessionMotion>>,
+    session_local_data: RefCell<HashMap<DragSessionId, Value>>,
+    tasks: TaskScopes<IsolateId>,
+}
+
+const UNSTARTED_SESSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Last known position of dragging pointer, used to compute velocity.
+struct SessionMotion {
+    location: Point,
+    time: Instant,
+    velocity: Point,
 }
 
+/// Per-item operation restrictions of an active session.
+struct SessionItemOperations {
+    isolate: IsolateId,
+    /// Sessions that were configured but never started are dropped after
+    /// [`UNSTARTED_SESSION_TIMEOUT`].
+    configured_at: Instant,
+    session: Vec<DropOperation>,
+    /// Session operations narrowed to those allowed by items.
+    allowed: Vec<DropOperation>,
+    items: Vec<Vec<DropOperation>>,
+}
+
+#[cfg(target_os = "windows")]
+fn apply_touch_drag_settings(context: &PlatformDragContext, settings: &TouchDragSettings) {
+    context.set_system_press_and_hold_enabled(!settings.suppress_system_press_and_hold);
+}
+
+#[cfg(not(target_os = "windows"))]
+fn apply_touch_drag_settings(_context: &PlatformDragContext, _settings: &TouchDragSettings) {}
+
 pub trait GetDragManager {
     fn drag_manager(&self) -> Rc<DragManager>;
 }
@@ -125,43 +207,273 @@ struct DragContextInitRequest {
     engine_handle: i64,
 }
 
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ViewRequest {
+    engine_handle: i64,
+}
+
 #[derive(TryFromValue)]
 #[irondash(rename_all = "camelCase")]
 pub struct LocalDataRequest {
     session_id: DragSessionId,
 }
 
+#[derive(TryFromValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct UpdateDragImageRequest {
+    pub session_id: DragSessionId,
+    /// New image for each item of the session. Rects are in the same
+    /// coordinates as when the drag was started.
+    pub images: Vec<TargettedImage>,
+    /// Replaces item images on platforms that need combined drag image.
+    pub combined_drag_image: Option<TargettedImage>,
+}
+
 impl DragManager {
     pub fn new() -> RegisteredAsyncMethodHandler<Self> {
         Self {
             weak_self: Late::new(),
             invoker: Late::new(),
             contexts: RefCell::new(HashMap::new()),
+            primary_views: RefCell::new(HashMap::new()),
+            touch_drag_settings: RefCell::new(HashMap::new()),
             next_session_id: Cell::new(0),
+            item_operations: RefCell::new(HashMap::new()),
+            motion: RefCell::new(HashMap::new()),
+            session_local_data: RefCell::new(HashMap::new()),
+            tasks: TaskScopes::new("DragManager"),
         }
         .register("DragManager")
     }
 
+    /// Narrows session operations to those allowed by at least one item and
+    /// remembers per-item restrictions for drop time.
+    fn begin_item_operations(
+        &self,
+        isolate: IsolateId,
+        session_id: DragSessionId,
+        configuration: &mut DragConfiguration,
+    ) {
+        self.forget_unstarted_sessions();
+        let session = configuration.allowed_operations.clone();
+        configuration.restrict_to_item_operations();
+        let items = configuration
+            .items
+            .iter()
+            .map(|i| i.effective_allowed_operations(&session))
+            .collect();
+        self.item_operations.borrow_mut().insert(
+            session_id,
+            SessionItemOperations {
+                isolate,
+                configured_at: Instant::now(),
+                session,
+                allowed: configuration.allowed_operations.clone(),
+                items,
+            },
+        );
+    }
+
+    fn begin_session_local_data(
+        &self,
+        session_id: DragSessionId,
+        configuration: &mut DragConfiguration,
+    ) {
+        if let Some(data) = configuration.session_local_data.take() {
+            self.session_local_data
+                .borrow_mut()
+                .insert(session_id, data);
+        }
+    }
+
+    fn forget_session(&self, session_id: DragSessionId) {
+        self.item_operations.borrow_mut().remove(&session_id);
+        self.session_local_data.borrow_mut().remove(&session_id);
+    }
+
+    /// Platforms that request configuration before starting the drag don't
+    /// report sessions that never started, such as cancelled lifts.
+    fn forget_unstarted_sessions(&self) {
+        let monitor = Context::get().drag_monitor();
+        let unstarted: Vec<_> = self
+            .item_operations
+            .borrow()
+            .iter()
+            .filter(|(id, operations)| {
+                operations.configured_at.elapsed() > UNSTARTED_SESSION_TIMEOUT
+                    && !monitor.is_session_active(DragRole::Source, (**id).into())
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for session_id in unstarted {
+            self.forget_session(session_id);
+        }
+    }
+
+    fn add_item_operations(&self, session_id: DragSessionId, items: &[DragItem]) {
+        if let Some(operations) = self.item_operations.borrow_mut().get_mut(&session_id) {
+            for item in items {
+                let allowed = item.effective_allowed_operations(&operations.session);
+                operations.items.push(allowed);
+            }
+        }
+    }
+
+    /// Pointer velocity in logical pixels per second, smoothed over recent
+    /// moves.
+    fn update_velocity(&self, session_id: DragSessionId, location: &Point) -> Point {
+        const SMOOTHING: f64 = 0.5;
+        let now = Instant::now();
+        let mut motion = self.motion.borrow_mut();
+        let previous = motion.get(&session_id);
+        let velocity = match previous {
+            Some(previous) => {
+                let elapsed = now.duration_since(previous.time).as_secs_f64();
+                if elapsed > 0.0 {
+                    Point {
+                        x: SMOOTHING * (location.x - previous.location.x) / elapsed
+                            + (1.0 - SMOOTHING) * previous.velocity.x,
+                        y: SMOOTHING * (location.y - previous.location.y) / elapsed
+                            + (1.0 - SMOOTHING) * previous.velocity.y,
+                    }
+                } else {
+                    previous.velocity.clone()
+                }
+            }
+            None => Point::default(),
+        };
+        motion.insert(
+            session_id,
+            SessionMotion {
+                location: location.clone(),
+                time: now,
+                velocity: velocity.clone(),
+            },
+        );
+        velocity
+    }
+
+    /// Per-item allowed operations of local drag session.
+    pub fn local_item_operations(
+        &self,
+        session_id: DragSessionId,
+    ) -> Option<Vec<Vec<DropOperation>>> {
+        self.item_operations
+            .borrow()
+            .get(&session_id)
+            .map(|operations| operations.items.clone())
+    }
+
+    /// Session payload of local drag session. Sessions of all isolates are
+    /// kept here, so the payload also reaches other windows.
+    pub fn local_session_data(&self, session_id: DragSessionId) -> Option<Value> {
+        self.session_local_data.borrow().get(&session_id).cloned()
+    }
+
+    /// Platform sessions must not outlive the isolate; on Windows the OLE
+    /// drag loop would keep running. Part of [`shutdown::shutdown`].
+    pub fn shutdown(&self, isolate: IsolateId) {
+        let contexts: Vec<_> = self
+            .contexts
+            .borrow()
+            .iter()
+            .filter(|(id, _)| id.isolate == isolate)
+            .map(|(_, context)| context.clone())
+            .collect();
+        for context in contexts {
+            context.cancel_sessions();
+        }
+    }
+
     fn new_context(
         &self,
         isolate: IsolateId,
         request: DragContextInitRequest,
     ) -> NativeExtensionsResult<()> {
-        if self.contexts.borrow().get(&isolate).is_some() {
+        self.primary_views
+            .borrow_mut()
+            .insert(isolate, request.engine_handle);
+        self.register_view(
+            isolate,
+            ViewRequest {
+                engine_handle: request.engine_handle,
+            },
+        )
+    }
+
+    fn register_view(
+        &self,
+        isolate: IsolateId,
+        request: ViewRequest,
+    ) -> NativeExtensionsResult<()> {
+        let id = PlatformDragContextId {
+            isolate,
+            engine_handle: request.engine_handle,
+        };
+        if self.contexts.borrow().get(&id).is_some() {
             // Can happen during hot reload
-            warn!("DragContext already exists for isolate {:?}", isolate);
+            warn!("DragContext already exists for {:?}", id);
             return Ok(());
         }
         let context = Rc::new(PlatformDragContext::new(
-            isolate,
+            id,
             request.engine_handle,
             self.weak_self.clone(),
         )?);
         context.assign_weak_self(Rc::downgrade(&context));
-        self.contexts.borrow_mut().insert(isolate, context);
+        if let Some(settings) = self.touch_drag_settings.borrow().get(&isolate) {
+            apply_touch_drag_settings(&context, settings);
+        }
+        self.contexts.borrow_mut().insert(id, context);
         Ok(())
     }
 
+    fn set_touch_drag_settings(&self, isolate: IsolateId, settings: TouchDragSettings) {
+        for (id, context) in self.contexts.borrow().iter() {
+            if id.isolate == isolate {
+                apply_touch_drag_settings(context, &settings);
+            }
+        }
+        self.touch_drag_settings
+            .borrow_mut()
+            .insert(isolate, settings);
+    }
+
+    fn get_touch_drag_settings(&self, isolate: IsolateId) -> TouchDragSettings {
+        self.touch_drag_settings
+            .borrow()
+            .get(&isolate)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn unregister_view(&self, isolate: IsolateId, request: ViewRequest) {
+        self.contexts.borrow_mut().remove(&PlatformDragContextId {
+            isolate,
+            engine_handle: request.engine_handle,
+        });
+    }
+
+    /// Context of given view, or of primary view if not specified.
+    fn context_for_view(
+        &self,
+        isolate: IsolateId,
+        engine_handle: Option<i64>,
+    ) -> NativeExtensionsResult<Rc<PlatformDragContext>> {
+        let engine_handle = engine_handle
+            .or_else(|| self.primary_views.borrow().get(&isolate).copied())
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        self.contexts
+            .borrow()
+            .get(&PlatformDragContextId {
+                isolate,
+                engine_handle,
+            })
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)
+    }
+
     pub fn get_platform_drag_contexts(&self) -> Vec<Rc<PlatformDragContext>> {
         self.contexts.borrow().values().cloned().collect()
     }
@@ -181,7 +493,12 @@ impl DragManager {
             let handle: DataProviderHandle = DropNotifier::new(move || {
                 if let Some(this) = weak_self.upgrade() {
                     // Isolate could have been destroyed in the meanwhile.
-                    if this.contexts.borrow().contains_key(&isolate) {
+                    if this
+                        .contexts
+                        .borrow()
+                        .keys()
+                        .any(|id| id.isolate == isolate)
+                    {
                         this.release_data_provider(isolate, provider_id);
                     }
                 }
@@ -209,6 +526,7 @@ impl DragManager {
         struct DragConfigurationRequest {
             session_id: DragSessionId,
             location: Point,
+            engine_handle: i64,
         }
         #[derive(TryFromValue, Debug)]
         #[irondash(rename_all = "camelCase")]
@@ -219,18 +537,21 @@ impl DragManager {
         let configuration: DragConfigurationResponse = self
             .invoker
             .call_method_cv(
-                id,
+                id.isolate,
                 "getConfigurationForDragRequest",
                 DragConfigurationRequest {
                     location,
                     session_id,
+                    engine_handle: id.engine_handle,
                 },
             )
             .await?;
         let configuration = configuration.configuration;
         match configuration {
-            Some(configuration) => {
-                let providers = self.build_data_provider_map(id, &configuration.items)?;
+            Some(mut configuration) => {
+                let providers = self.build_data_provider_map(id.isolate, &configuration.items)?;
+                self.begin_item_operations(id.isolate, session_id, &mut configuration);
+                self.begin_session_local_data(session_id, &mut configuration);
                 Ok(Some(GetDragConfigurationResult {
                     session_id,
                     configuration,
@@ -252,6 +573,7 @@ impl DragManager {
         struct AdditionalItemsRequest {
             session_id: DragSessionId,
             location: Point,
+            engine_handle: i64,
         }
         #[derive(TryFromValue, Debug)]
         #[irondash(rename_all = "camelCase")]
@@ -261,17 +583,19 @@ impl DragManager {
         let response: AdditionalItemsResponse = self
             .invoker
             .call_method_cv(
-                id,
+                id.isolate,
                 "getAdditionalItemsForLocation",
                 AdditionalItemsRequest {
                     location,
                     session_id,
+                    engine_handle: id.engine_handle,
                 },
             )
             .await?;
         match response.items {
             Some(items) => {
-                let providers = self.build_data_provider_map(id, &items)?;
+                let providers = self.build_data_provider_map(id.isolate, &items)?;
+                self.add_item_operations(session_id, &items);
                 Ok(Some(GetAdditionalItemsResult { items, providers }))
             }
             None => Ok(None),
@@ -287,13 +611,17 @@ impl DragManager {
         #[irondash(rename_all = "camelCase")]
         struct LocationDraggableRequest {
             location: Point,
+            engine_handle: i64,
         }
         let result: bool = self
             .invoker
             .call_method_cv(
-                id,
+                id.isolate,
                 "isLocationDraggable",
-                LocationDraggableRequest { location },
+                LocationDraggableRequest {
+                    location,
+                    engine_handle: id.engine_handle,
+                },
             )
             .await?;
         Ok(result)
@@ -302,19 +630,30 @@ impl DragManager {
     async fn start_drag(
         &self,
         isolate: IsolateId,
-        request: DragRequest,
+        mut request: DragRequest,
     ) -> NativeExtensionsResult<DragSessionId> {
-        let context = self
-            .contexts
-            .borrow()
-            .get(&isolate)
-            .cloned()
-            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let context = self.context_for_view(isolate, request.engine_handle)?;
+        resolve_drag_request(&mut request)?;
         let session_id = DragSessionId(self.next_session_id.next_id());
         let provider_map = self.build_data_provider_map(isolate, &request.configuration.items)?;
-        context
-            .start_drag(request, provider_map, session_id)
-            .await?;
+        let item_count = request.configuration.items.len() as i64;
+        self.begin_item_operations(isolate, session_id, &mut request.configuration);
+        self.begin_session_local_data(session_id, &mut request.configuration);
+        let span = OperationSpan::new(module_path!(), "startDrag");
+        let res = context.start_drag(request, provider_map, session_id).await;
+        span.finish(&res);
+        if res.is_err() {
+            self.forget_session(session_id);
+        }
+        res?;
+        Context::get()
+            .drag_monitor()
+            .session_did_update(DragSessionInfo {
+                role: DragRole::Source,
+                session_id: session_id.into(),
+                item_count: Some(item_count),
+                formats: Vec::new(),
+            });
         Ok(session_id)
     }
 
@@ -323,17 +662,53 @@ impl DragManager {
         isolate: IsolateId,
         request: LocalDataRequest,
     ) -> NativeExtensionsResult<Option<Vec<Value>>> {
-        let context = self
+        let contexts: Vec<_> = self
             .contexts
             .borrow()
-            .get(&isolate)
-            .cloned()
-            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
-        match context.get_local_data_for_session_id(request.session_id) {
-            Ok(value) => Ok(Some(value)),
-            Err(NativeExtensionsError::DragSessionNotFound) => Ok(None),
-            Err(error) => Err(error),
+            .iter()
+            .filter(|(id, _)| id.isolate == isolate)
+            .map(|(_, context)| context.clone())
+            .collect();
+        if contexts.is_empty() {
+            return Err(NativeExtensionsError::PlatformContextNotFound);
         }
+        // Session can belong to any view of the isolate.
+        for context in contexts {
+            match context.get_local_data_for_session_id(request.session_id) {
+                Ok(value) => return Ok(Some(value)),
+                Err(NativeExtensionsError::DragSessionNotFound) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(None)
+    }
+
+    fn update_drag_image(
+        &self,
+        isolate: IsolateId,
+        mut request: UpdateDragImageRequest,
+    ) -> NativeExtensionsResult<()> {
+        for image in request.images.iter_mut() {
+            resolve_image(image)?;
+        }
+        if let Some(image) = request.combined_drag_image.as_mut() {
+            resolve_image(image)?;
+        }
+        let contexts: Vec<_> = self
+            .contexts
+            .borrow()
+            .iter()
+            .filter(|(id, _)| id.isolate == isolate)
+            .map(|(_, context)| context.clone())
+            .collect();
+        // Session can belong to any view of the isolate.
+        for context in contexts {
+            match context.update_drag_image(&request) {
+                Err(NativeExtensionsError::DragSessionNotFound) => {}
+                res => return res,
+            }
+        }
+        Err(NativeExtensionsError::DragSessionNotFound)
     }
 
     fn release_data_provider(&self, isolate_id: IsolateId, provider_id: DataProviderId) {
@@ -346,6 +721,11 @@ impl DragManager {
     fn needs_combined_drag_image(&self) -> NativeExtensionsResult<bool> {
         Ok(PlatformDragContext::needs_combined_drag_image())
     }
+
+    /// Texture kind that can be used for drag images instead of pixel data.
+    fn shared_texture_kind(&self) -> NativeExtensionsResult<Option<SharedTextureKind>> {
+        Ok(shared_texture_kind())
+    }
 }
 
 #[async_trait(?Send)]
@@ -364,7 +744,21 @@ impl AsyncMethodHandler for DragManager {
                 self.new_context(call.isolate, call.args.try_into()?)?;
                 Ok(Value::Null)
             }
+            "registerDragSourceForView" => {
+                self.register_view(call.isolate, call.args.try_into()?)?;
+                Ok(Value::Null)
+            }
+            "unregisterDragSourceForView" => {
+                self.unregister_view(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            "setTouchDragSettings" => {
+                self.set_touch_drag_settings(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            "getTouchDragSettings" => Ok(self.get_touch_drag_settings(call.isolate).into()),
             "needsCombinedDragImage" => self.needs_combined_drag_image().into_platform_result(),
+            "sharedTextureKind" => self.shared_texture_kind().into_platform_result(),
             "startDrag" => self
                 .start_drag(call.isolate, call.args.try_into()?)
                 .await
@@ -372,12 +766,31 @@ impl AsyncMethodHandler for DragManager {
             "getLocalData" => self
                 .get_local_data(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
+            "updateDragImage" => self
+                .update_drag_image(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
             _ => Ok(Value::Null),
         }
     }
 
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
-        self.contexts.borrow_mut().remove(&isolate);
+        shutdown::shutdown(isolate);
+        self.contexts
+            .borrow_mut()
+            .retain(|id, _| id.isolate != isolate);
+        let sessions: Vec<_> = self
+            .item_operations
+            .borrow()
+            .iter()
+            .filter(|(_, operations)| operations.isolate == isolate)
+            .map(|(id, _)| *id)
+            .collect();
+        for session_id in sessions {
+            self.forget_session(session_id);
+        }
+        self.primary_views.borrow_mut().remove(&isolate);
+        self.touch_drag_settings.borrow_mut().remove(&isolate);
+        self.tasks.close(isolate);
     }
 }
 
@@ -397,28 +810,33 @@ impl PlatformDragContextDelegate for DragManager {
     ) -> Arc<Promise<PromiseResult<GetDragConfigurationResult>>> {
         let res = Arc::new(Promise::new());
         let res_clone = res.clone();
+        let res_abort = res.clone();
         let weak_self = self.weak_self.clone();
         let session_id = DragSessionId(self.next_session_id.next_id());
-        spawn(async move {
-            let this = weak_self.upgrade();
-            if let Some(this) = this {
-                match this
-                    .get_drag_configuration_for_location(id, session_id, location)
-                    .await
-                    .ok_log_unexpected()
-                    .flatten()
-                {
-                    Some(data) => {
-                        res_clone.set(PromiseResult::Ok { value: data });
-                    }
-                    None => {
-                        res_clone.set(PromiseResult::Cancelled);
+        self.tasks.spawn_or_else(
+            id.isolate,
+            async move {
+                let this = weak_self.upgrade();
+                if let Some(this) = this {
+                    match this
+                        .get_drag_configuration_for_location(id, session_id, location)
+                        .await
+                        .ok_log_unexpected()
+                        .flatten()
+                    {
+                        Some(data) => {
+                            res_clone.set(PromiseResult::Ok { value: data });
+                        }
+                        None => {
+                            res_clone.set(PromiseResult::Cancelled);
+                        }
                     }
+                } else {
+                    res_clone.set(PromiseResult::Cancelled);
                 }
-            } else {
-                res_clone.set(PromiseResult::Cancelled);
-            }
-        });
+            },
+            move || res_abort.set(PromiseResult::Cancelled),
+        );
         res
     }
 
@@ -430,27 +848,32 @@ impl PlatformDragContextDelegate for DragManager {
     ) -> Arc<Promise<PromiseResult<GetAdditionalItemsResult>>> {
         let res = Arc::new(Promise::new());
         let res_clone = res.clone();
+        let res_abort = res.clone();
         let weak_self = self.weak_self.clone();
-        spawn(async move {
-            let this = weak_self.upgrade();
-            if let Some(this) = this {
-                match this
-                    .get_additional_items_for_location(id, session_id, location)
-                    .await
-                    .ok_log_unexpected()
-                    .flatten()
-                {
-                    Some(data) => {
-                        res_clone.set(PromiseResult::Ok { value: data });
-                    }
-                    None => {
-                        res_clone.set(PromiseResult::Cancelled);
+        self.tasks.spawn_or_else(
+            id.isolate,
+            async move {
+                let this = weak_self.upgrade();
+                if let Some(this) = this {
+                    match this
+                        .get_additional_items_for_location(id, session_id, location)
+                        .await
+                        .ok_log_unexpected()
+                        .flatten()
+                    {
+                        Some(data) => {
+                            res_clone.set(PromiseResult::Ok { value: data });
+                        }
+                        None => {
+                            res_clone.set(PromiseResult::Cancelled);
+                        }
                     }
+                } else {
+                    res_clone.set(PromiseResult::Cancelled);
                 }
-            } else {
-                res_clone.set(PromiseResult::Cancelled);
-            }
-        });
+            },
+            move || res_abort.set(PromiseResult::Cancelled),
+        );
         res
     }
 
@@ -461,22 +884,27 @@ impl PlatformDragContextDelegate for DragManager {
     ) -> Arc<Promise<PromiseResult<bool>>> {
         let res = Arc::new(Promise::new());
         let res_clone = res.clone();
+        let res_abort = res.clone();
         let weak_self = self.weak_self.clone();
-        spawn(async move {
-            let this = weak_self.upgrade();
-            if let Some(this) = this {
-                let draggable = this
-                    .is_location_draggable(id, location)
-                    .await
-                    .ok_log_unexpected();
-                match draggable {
-                    Some(draggable) => res_clone.set(PromiseResult::Ok { value: draggable }),
-                    None => res_clone.set(PromiseResult::Cancelled),
+        self.tasks.spawn_or_else(
+            id.isolate,
+            async move {
+                let this = weak_self.upgrade();
+                if let Some(this) = this {
+                    let draggable = this
+                        .is_location_draggable(id, location)
+                        .await
+                        .ok_log_unexpected();
+                    match draggable {
+                        Some(draggable) => res_clone.set(PromiseResult::Ok { value: draggable }),
+                        None => res_clone.set(PromiseResult::Cancelled),
+                    }
+                } else {
+                    res_clone.set(PromiseResult::Cancelled);
                 }
-            } else {
-                res_clone.set(PromiseResult::Cancelled);
-            }
-        });
+            },
+            move || res_abort.set(PromiseResult::Cancelled),
+        );
         res
     }
 
@@ -485,19 +913,70 @@ impl PlatformDragContextDelegate for DragManager {
         id: PlatformDragContextId,
         session_id: DragSessionId,
         screen_location: Point,
+        modifiers: KeyModifiers,
     ) {
         #[derive(IntoValue)]
         #[irondash(rename_all = "camelCase")]
         struct DragMoveRequest {
             session_id: DragSessionId,
             screen_location: Point,
+            velocity: Point,
+            modifiers: KeyModifiers,
+            allowed_operations: Option<Vec<DropOperation>>,
         }
+        // Drags on mobile platforms are started by the system.
+        let monitor = Context::get().drag_monitor();
+        if !monitor.is_session_active(DragRole::Source, session_id.into()) {
+            monitor.session_did_update(DragSessionInfo {
+                role: DragRole::Source,
+                session_id: session_id.into(),
+                item_count: None,
+                formats: Vec::new(),
+            });
+        }
+        let velocity = self.update_velocity(session_id, &screen_location);
+        let allowed_operations = self
+            .item_operations
+            .borrow()
+            .get(&session_id)
+            .map(|o| o.allowed.clone());
         self.invoker.call_method_sync(
-            id,
+            id.isolate,
             "dragSessionDidMove",
             DragMoveRequest {
                 session_id,
                 screen_location,
+                velocity,
+                modifiers,
+                allowed_operations,
+            },
+            |r| {
+                r.ok_log();
+            },
+        )
+    }
+
+    fn drag_session_environment_did_change(
+        &self,
+        id: PlatformDragContextId,
+        session_id: DragSessionId,
+        change: DragEnvironmentChange,
+        screen_location: Point,
+    ) {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct DragEnvironmentChangeRequest {
+            session_id: DragSessionId,
+            change: DragEnvironmentChange,
+            screen_location: Point,
+        }
+        self.invoker.call_method_sync(
+            id.isolate,
+            "dragSessionEnvironmentDidChange",
+            DragEnvironmentChangeRequest {
+                session_id,
+                change,
+                screen_location,
             },
             |r| {
                 r.ok_log();
@@ -510,20 +989,95 @@ impl PlatformDragContextDelegate for DragManager {
         id: PlatformDragContextId,
         session_id: DragSessionId,
         operation: DropOperation,
+        cancel_reason: Option<DragCancelReason>,
     ) {
         #[derive(IntoValue)]
         #[irondash(rename_all = "camelCase")]
         struct DragEndRequest {
             session_id: DragSessionId,
             drop_operation: DropOperation,
+            cancel_reason: Option<DragCancelReason>,
+            /// Operation effectively performed on each item.
+            item_operations: Option<Vec<DropOperation>>,
         }
 
+        self.motion.borrow_mut().remove(&session_id);
+        self.session_local_data.borrow_mut().remove(&session_id);
+        let item_operations =
+            self.item_operations
+                .borrow_mut()
+                .remove(&session_id)
+                .map(|operations| {
+                    operations
+                        .items
+                        .iter()
+                        .map(|allowed| operation.restrict_to(allowed))
+                        .collect()
+                });
+
+        Context::get()
+            .drag_monitor()
+            .session_did_end(DragRole::Source, session_id.into());
         self.invoker.call_method_sync(
-            id,
+            id.isolate,
             "dragSessionDidEnd",
             DragEndRequest {
                 session_id,
                 drop_operation: operation,
+                cancel_reason,
+                item_operations,
+            },
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
+
+    fn drag_session_did_cancel(
+        &self,
+        id: PlatformDragContextId,
+        session_id: DragSessionId,
+        screen_location: Point,
+    ) {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct DragCancelRequest {
+            session_id: DragSessionId,
+            screen_location: Point,
+        }
+
+        self.invoker.call_method_sync(
+            id.isolate,
+            "dragSessionDidCancel",
+            DragCancelRequest {
+                session_id,
+                screen_location,
+            },
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
+
+    fn drag_session_data_transfer_progress(
+        &self,
+        id: PlatformDragContextId,
+        session_id: DragSessionId,
+        progress: DataTransferProgress,
+    ) {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct DataTransferProgressEvent {
+            session_id: DragSessionId,
+            progress: DataTransferProgress,
+        }
+
+        self.invoker.call_method_sync(
+            id.isolate,
+            "dragDataTransferProgress",
+            DataTransferProgressEvent {
+                session_id,
+                progress,
             },
             |r| {
                 r.ok_log();
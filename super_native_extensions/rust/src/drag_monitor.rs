@@ -0,0 +1,176 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use irondash_message_channel::{
+    IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler, MethodInvoker,
+    PlatformError, PlatformResult, RegisteredMethodHandler, Value,
+};
+
+use crate::{context::Context, log::OkLog};
+
+/// How the application is involved in a drag session.
+#[derive(IntoValue, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[irondash(rename_all = "camelCase")]
+pub enum DragRole {
+    /// Drag was started by this application.
+    Source,
+    /// Drag (possibly from another application) is over one of application
+    /// windows.
+    Target,
+}
+
+#[derive(IntoValue, Clone, Debug, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct DragSessionInfo {
+    pub role: DragRole,
+    /// Drag session id for source, drop session id for target.
+    pub session_id: i64,
+    /// Number of dragged items, if known.
+    pub item_count: Option<i64>,
+    /// Formats of dragged items. Only known for target sessions.
+    pub formats: Vec<String>,
+}
+
+#[derive(IntoValue, Clone, Copy)]
+#[irondash(rename_all = "camelCase")]
+enum DragActivityEventType {
+    Started,
+    Updated,
+    Ended,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct DragActivityEvent {
+    event_type: DragActivityEventType,
+    info: DragSessionInfo,
+}
+
+/// Keeps track of all drag sessions involving the application, both started
+/// by the application and hovering over its windows. Drag and drop
+/// managers report activity here; Dart can query the state or subscribe
+/// to changes.
+pub struct DragMonitor {
+    invoker: Late<MethodInvoker>,
+    /// Active sessions in order in which they started.
+    sessions: RefCell<Vec<DragSessionInfo>>,
+    /// Isolates subscribed to activity events.
+    isolates: RefCell<HashSet<IsolateId>>,
+}
+
+pub trait GetDragMonitor {
+    fn drag_monitor(&self) -> Rc<DragMonitor>;
+}
+
+impl GetDragMonitor for Context {
+    fn drag_monitor(&self) -> Rc<DragMonitor> {
+        self.get_attachment(DragMonitor::new).handler()
+    }
+}
+
+impl DragMonitor {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {
+            invoker: Late::new(),
+            sessions: RefCell::new(Vec::new()),
+            isolates: RefCell::new(HashSet::new()),
+        }
+        .register("DragMonitor")
+    }
+
+    /// Reports new or changed session. Sessions are identified by role and
+    /// session id.
+    pub fn session_did_update(&self, info: DragSessionInfo) {
+        let event_type = {
+            let mut sessions = self.sessions.borrow_mut();
+            let existing = sessions
+                .iter_mut()
+                .find(|s| s.role == info.role && s.session_id == info.session_id);
+            match existing {
+                Some(existing) if *existing == info => return,
+                Some(existing) => {
+                    *existing = info.clone();
+                    DragActivityEventType::Updated
+                }
+                None => {
+                    sessions.push(info.clone());
+                    DragActivityEventType::Started
+                }
+            }
+        };
+        self.notify(event_type, info);
+    }
+
+    pub fn session_did_end(&self, role: DragRole, session_id: i64) {
+        let info = {
+            let mut sessions = self.sessions.borrow_mut();
+            let index = sessions
+                .iter()
+                .position(|s| s.role == role && s.session_id == session_id);
+            match index {
+                Some(index) => sessions.remove(index),
+                None => return,
+            }
+        };
+        self.notify(DragActivityEventType::Ended, info);
+    }
+
+    pub fn is_session_active(&self, role: DragRole, session_id: i64) -> bool {
+        self.sessions
+            .borrow()
+            .iter()
+            .any(|s| s.role == role && s.session_id == session_id)
+    }
+
+    fn notify(&self, event_type: DragActivityEventType, info: DragSessionInfo) {
+        let event: Value = DragActivityEvent { event_type, info }.into();
+        for isolate in self.isolates.borrow().iter() {
+            self.invoker
+                .call_method(*isolate, "onDragActivity", event.clone(), |r| {
+                    r.ok_log();
+                });
+        }
+    }
+
+    fn is_drag_in_progress(&self) -> bool {
+        !self.sessions.borrow().is_empty()
+    }
+
+    /// Most recently started session that is still active.
+    fn current_drag_session_info(&self) -> Option<DragSessionInfo> {
+        self.sessions.borrow().last().cloned()
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "isDragInProgress" => Ok(self.is_drag_in_progress().into()),
+            "currentDragSessionInfo" => Ok(self.current_drag_session_info().into()),
+            "startMonitoring" => {
+                self.isolates.borrow_mut().insert(call.isolate);
+                Ok(Value::Null)
+            }
+            "stopMonitoring" => {
+                self.isolates.borrow_mut().remove(&call.isolate);
+                Ok(Value::Null)
+            }
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+impl MethodHandler for DragMonitor {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
+
+    fn assign_invoker(&self, invoker: MethodInvoker) {
+        self.invoker.set(invoker);
+    }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        self.isolates.borrow_mut().remove(&isolate);
+    }
+}
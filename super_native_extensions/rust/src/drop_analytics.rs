@@ -0,0 +1,132 @@
+//! Aggregate statistics about incoming drag sessions.
+//!
+//! When enabled for a drop context the drop manager records every session
+//! that enters the view: whether it ended with a drop, how long the pointer
+//! hovered and which formats were offered by sessions that were never
+//! accepted. Only aggregates are kept, individual sessions are forgotten once
+//! they end.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use irondash_message_channel::IntoValue;
+
+use crate::{api_model::DropOperation, drop_manager::DropSessionId};
+
+#[derive(IntoValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct RejectedFormatCount {
+    pub format: String,
+    pub count: i64,
+}
+
+#[derive(IntoValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropAnalyticsReport {
+    pub sessions_entered: i64,
+    pub sessions_dropped: i64,
+    /// Sessions that entered the view but left or were cancelled.
+    pub sessions_abandoned: i64,
+    pub average_hover_millis: f64,
+    /// Formats offered by sessions that were never accepted, most frequent
+    /// first.
+    pub rejected_formats: Vec<RejectedFormatCount>,
+}
+
+struct SessionRecord {
+    entered: Instant,
+    dropped: Option<Instant>,
+    accepted: bool,
+    formats: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct DropAnalytics {
+    sessions: HashMap<DropSessionId, SessionRecord>,
+    entered: i64,
+    finished: i64,
+    dropped: i64,
+    total_hover: Duration,
+    rejected_formats: HashMap<String, i64>,
+}
+
+impl DropAnalytics {
+    pub fn session_updated(&mut self, session_id: DropSessionId, formats: &[String]) {
+        let entered = &mut self.entered;
+        let record = self.sessions.entry(session_id).or_insert_with(|| {
+            *entered += 1;
+            SessionRecord {
+                entered: Instant::now(),
+                dropped: None,
+                accepted: false,
+                formats: Vec::new(),
+            }
+        });
+        for format in formats {
+            if !record.formats.contains(format) {
+                record.formats.push(format.clone());
+            }
+        }
+    }
+
+    pub fn operation_resolved(&mut self, session_id: DropSessionId, operation: DropOperation) {
+        if let Some(record) = self.sessions.get_mut(&session_id) {
+            if matches!(
+                operation,
+                DropOperation::Copy | DropOperation::Move | DropOperation::Link
+            ) {
+                record.accepted = true;
+            }
+        }
+    }
+
+    pub fn session_dropped(&mut self, session_id: DropSessionId) {
+        if let Some(record) = self.sessions.get_mut(&session_id) {
+            record.dropped = Some(Instant::now());
+        }
+    }
+
+    /// Folds the session into aggregates. Returns `false` if the session was
+    /// not tracked (i.e. already finished).
+    pub fn session_finished(&mut self, session_id: DropSessionId) -> bool {
+        let Some(record) = self.sessions.remove(&session_id) else {
+            return false;
+        };
+        self.finished += 1;
+        let hover_end = record.dropped.unwrap_or_else(Instant::now);
+        self.total_hover += hover_end.saturating_duration_since(record.entered);
+        if record.dropped.is_some() {
+            self.dropped += 1;
+        } else if !record.accepted {
+            for format in record.formats {
+                *self.rejected_formats.entry(format).or_default() += 1;
+            }
+        }
+        true
+    }
+
+    pub fn report(&self) -> DropAnalyticsReport {
+        let mut rejected_formats: Vec<_> = self
+            .rejected_formats
+            .iter()
+            .map(|(format, count)| RejectedFormatCount {
+                format: format.clone(),
+                count: *count,
+            })
+            .collect();
+        rejected_formats.sort_by(|a, b| b.count.cmp(&a.count).then(a.format.cmp(&b.format)));
+        DropAnalyticsReport {
+            sessions_entered: self.entered,
+            sessions_dropped: self.dropped,
+            sessions_abandoned: self.finished - self.dropped,
+            average_hover_millis: if self.finished > 0 {
+                self.total_hover.as_secs_f64() * 1000.0 / self.finished as f64
+            } else {
+                0.0
+            },
+            rejected_formats,
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::HashMap,
     rc::{Rc, Weak},
     sync::Arc,
+    time::Duration,
 };
 
 use async_trait::async_trait;
@@ -10,27 +11,60 @@ use irondash_message_channel::{
     AsyncMethodHandler, AsyncMethodInvoker, IntoPlatformResult, IntoValue, IsolateId, Late,
     MethodCall, MethodCallError, PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
-use irondash_run_loop::{spawn, RunLoop};
+use irondash_run_loop::{Handle, RunLoop};
 use log::warn;
 
 use crate::{
     api_model::{DropOperation, ImageData, Point, Rect, Size},
+    auto_scroll::{
+        AutoScrollEvent, AutoScrollRegion, AutoScrollRegions, AutoScrollState, AUTO_SCROLL_INTERVAL,
+    },
+    binary_protocol::{binary_channels, encode_record, RecordKind},
     context::Context,
-    drag_manager::{GetDragManager, PlatformDragContextId},
+    drag_manager::{DragSessionId, GetDragManager},
+    drag_monitor::{DragRole, DragSessionInfo, GetDragMonitor},
+    drop_analytics::{DropAnalytics, DropAnalyticsReport},
+    drop_manifest::DropManifest,
+    drop_regions::{ActiveDropRegion, DropRegion, DropRegionEvent, DropRegions},
     error::{NativeExtensionsError, NativeExtensionsResult},
-    log::{OkLog, OkLogUnexpected},
+    log::{OkLog, OkLogUnexpected, OperationSpan},
     platform_impl::platform::{PlatformDataReader, PlatformDragContext, PlatformDropContext},
     reader_manager::{GetDataReaderManager, RegisteredDataReader},
+    task_scope::TaskScopes,
+    util::NextId,
     value_promise::{Promise, PromiseResult},
 };
 
-// Each isolate has its own DropContext.
-pub type PlatformDropContextId = IsolateId;
+/// Drop context is created for each Flutter view registered by an isolate.
+/// Views are identified by handle of the engine, platform view is looked up
+/// through `EngineContext` (one view per engine).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlatformDropContextId {
+    pub isolate: IsolateId,
+    pub engine_handle: i64,
+}
 
 pub struct DropManager {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
     contexts: RefCell<HashMap<PlatformDropContextId, Rc<PlatformDropContext>>>,
+    /// Formats last successfully registered with each context; restored when
+    /// manifest can not be applied to all contexts.
+    registered_formats: RefCell<HashMap<PlatformDropContextId, Vec<String>>>,
+    /// View registered through `newContext`, used by requests that don't
+    /// specify a view.
+    primary_views: RefCell<HashMap<IsolateId, i64>>,
+    /// Present for isolates that opted into analytics.
+    analytics: RefCell<HashMap<IsolateId, DropAnalytics>>,
+    acceptance_modes: RefCell<HashMap<IsolateId, DropAcceptanceMode>>,
+    /// Kept across isolates so that it survives hot restart.
+    manifest: RefCell<Option<Rc<DropManifest>>>,
+    regions: RefCell<HashMap<PlatformDropContextId, DropRegions>>,
+    active_regions: RefCell<HashMap<(PlatformDropContextId, DropSessionId), ActiveDropRegion>>,
+    auto_scroll_regions: RefCell<HashMap<PlatformDropContextId, AutoScrollRegions>>,
+    auto_scrolling: RefCell<HashMap<(PlatformDropContextId, DropSessionId), AutoScrollState>>,
+    next_auto_scroll_timer_id: Cell<i64>,
+    tasks: TaskScopes<IsolateId>,
 }
 
 pub trait GetDropManager {
@@ -53,6 +87,65 @@ struct DropContextInitRequest {
 #[irondash(rename_all = "camelCase")]
 struct RegisterDropFormatsRequest {
     formats: Vec<String>,
+    /// Primary view if not set.
+    engine_handle: Option<i64>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ViewRequest {
+    engine_handle: i64,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetDropRegionsRequest {
+    /// Primary view if not set.
+    engine_handle: Option<i64>,
+    /// Empty list removes regions of the view.
+    regions: Vec<DropRegion>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetAutoScrollRegionsRequest {
+    /// Primary view if not set.
+    engine_handle: Option<i64>,
+    /// Empty list removes regions of the view.
+    regions: Vec<AutoScrollRegion>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetAnalyticsEnabledRequest {
+    enabled: bool,
+}
+
+#[derive(TryFromValue, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[irondash(rename_all = "camelCase")]
+pub enum DropAcceptanceMode {
+    /// Platform drop handler waits until Dart finishes `onPerformDrop`.
+    #[default]
+    Synchronous,
+    /// Drop is acknowledged to the source immediately and a busy indicator
+    /// is shown until Dart finishes `onPerformDrop`. Falls back to
+    /// synchronous mode on platforms where drag data becomes inaccessible
+    /// after the drop.
+    Deferred,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetDropAcceptanceModeRequest {
+    mode: DropAcceptanceMode,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct DeferredDropResult {
+    session_id: DropSessionId,
+    success: bool,
+    error: Option<String>,
 }
 
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
@@ -70,6 +163,12 @@ impl From<i64> for DropSessionId {
     }
 }
 
+impl From<DropSessionId> for i64 {
+    fn from(s: DropSessionId) -> Self {
+        s.0
+    }
+}
+
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct DropItemId(i64);
 
@@ -85,23 +184,37 @@ pub struct DropItem {
     pub item_id: DropItemId, // unique ID within session, consistent between events
     pub formats: Vec<String>,
     pub local_data: Value,
+    /// Operations the item allows, for items of local drags only.
+    pub allowed_operations: Option<Vec<DropOperation>>,
 }
 
 #[derive(IntoValue, Debug)]
 #[irondash(rename_all = "camelCase")]
 pub struct DropEvent {
     pub session_id: DropSessionId,
+    /// Engine handle of the view the drop context belongs to.
+    pub engine_handle: i64,
+    /// Region under location if view has drop regions. Filled in by
+    /// [`DropManager`].
+    pub region_id: Option<String>,
     pub location_in_view: Point,
     pub allowed_operations: Vec<DropOperation>,
     pub accepted_operation: Option<DropOperation>,
     pub items: Vec<DropItem>,
     pub reader: Option<RegisteredDataReader>,
+    /// Session payload of local drag. Filled in by [`DropManager`].
+    pub session_local_data: Value,
+    /// Drag session of this application the drop comes from, as resolved
+    /// by platform drop context.
+    pub local_session_id: Option<DragSessionId>,
 }
 
 #[derive(IntoValue, Debug)]
 #[irondash(rename_all = "camelCase")]
 pub struct BaseDropEvent {
     pub session_id: DropSessionId,
+    /// Engine handle of the view the drop context belongs to.
+    pub engine_handle: i64,
 }
 
 #[derive(IntoValue)]
@@ -169,6 +282,17 @@ impl DropManager {
             weak_self: Late::new(),
             invoker: Late::new(),
             contexts: RefCell::new(HashMap::new()),
+            registered_formats: RefCell::new(HashMap::new()),
+            primary_views: RefCell::new(HashMap::new()),
+            analytics: RefCell::new(HashMap::new()),
+            acceptance_modes: RefCell::new(HashMap::new()),
+            manifest: RefCell::new(None),
+            regions: RefCell::new(HashMap::new()),
+            active_regions: RefCell::new(HashMap::new()),
+            auto_scroll_regions: RefCell::new(HashMap::new()),
+            auto_scrolling: RefCell::new(HashMap::new()),
+            next_auto_scroll_timer_id: Cell::new(0),
+            tasks: TaskScopes::new("DropManager"),
         }
         .register("DropManager")
     }
@@ -178,13 +302,32 @@ impl DropManager {
         isolate: IsolateId,
         request: RegisterDropFormatsRequest,
     ) -> NativeExtensionsResult<()> {
+        let engine_handle = request
+            .engine_handle
+            .or_else(|| self.primary_views.borrow().get(&isolate).copied())
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let id = PlatformDropContextId {
+            isolate,
+            engine_handle,
+        };
         let context = self
             .contexts
             .borrow()
-            .get(&isolate)
+            .get(&id)
             .cloned()
             .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
-        context.register_drop_formats(&request.formats)
+        self.register_context_formats(id, &context, request.formats)
+    }
+
+    fn register_context_formats(
+        &self,
+        id: PlatformDropContextId,
+        context: &PlatformDropContext,
+        formats: Vec<String>,
+    ) -> NativeExtensionsResult<()> {
+        context.register_drop_formats(&formats)?;
+        self.registered_formats.borrow_mut().insert(id, formats);
+        Ok(())
     }
 
     fn new_context(
@@ -192,21 +335,530 @@ impl DropManager {
         isolate: IsolateId,
         request: DropContextInitRequest,
     ) -> NativeExtensionsResult<()> {
-        if self.contexts.borrow().get(&isolate).is_some() {
+        self.primary_views
+            .borrow_mut()
+            .insert(isolate, request.engine_handle);
+        self.apply_manifest_policies(isolate);
+        self.register_drop_target_for_view(
+            isolate,
+            ViewRequest {
+                engine_handle: request.engine_handle,
+            },
+        )
+    }
+
+    /// Creates drop context for additional view (window) so that drops into
+    /// it are reported with coordinates relative to that view.
+    fn register_drop_target_for_view(
+        &self,
+        isolate: IsolateId,
+        request: ViewRequest,
+    ) -> NativeExtensionsResult<()> {
+        let id = PlatformDropContextId {
+            isolate,
+            engine_handle: request.engine_handle,
+        };
+        if self.contexts.borrow().get(&id).is_some() {
             // Can happen during hot reload
-            warn!("DropContext already exists for isolate {:?}", isolate);
+            warn!("DropContext already exists for {:?}", id);
             return Ok(());
         }
         let context = Rc::new(PlatformDropContext::new(
-            isolate,
+            id,
             request.engine_handle,
             self.weak_self.clone(),
         )?);
         context.assign_weak_self(Rc::downgrade(&context));
-        self.contexts.borrow_mut().insert(isolate, context);
+        let manifest = self.manifest.borrow().clone();
+        if let Some(view) = manifest
+            .as_ref()
+            .and_then(|m| m.view(request.engine_handle))
+        {
+            self.register_context_formats(id, &context, view.formats.clone())?;
+        }
+        self.contexts.borrow_mut().insert(id, context);
+        Ok(())
+    }
+
+    /// Validates and applies manifest. Replaces previous manifest; formats
+    /// registered for views it no longer lists stay registered. Applied
+    /// either fully or not at all: when formats can not be registered with
+    /// one of the contexts, contexts already updated get their previous
+    /// formats back and the previous manifest stays in effect.
+    fn set_drop_manifest(&self, manifest: DropManifest) -> NativeExtensionsResult<()> {
+        manifest.validate()?;
+        let contexts: Vec<_> = self
+            .contexts
+            .borrow()
+            .iter()
+            .map(|(id, context)| (*id, context.clone()))
+            .collect();
+        let mut updated = Vec::new();
+        for (id, context) in contexts {
+            let Some(view) = manifest.view(id.engine_handle) else {
+                continue;
+            };
+            let previous = self.registered_formats.borrow().get(&id).cloned();
+            if let Err(err) = self.register_context_formats(id, &context, view.formats.clone()) {
+                for (id, context, previous) in updated {
+                    self.register_context_formats(id, &context, previous)
+                        .ok_log();
+                }
+                return Err(err);
+            }
+            updated.push((id, context, previous.unwrap_or_default()));
+        }
+        self.manifest.replace(Some(Rc::new(manifest)));
+        let isolates: Vec<_> = self.primary_views.borrow().keys().copied().collect();
+        for isolate in isolates {
+            self.apply_manifest_policies(isolate);
+        }
+        Ok(())
+    }
+
+    fn apply_manifest_policies(&self, isolate: IsolateId) {
+        let manifest = self.manifest.borrow().clone();
+        let Some(policies) = manifest.as_ref().and_then(|m| m.policies.as_ref()) else {
+            return;
+        };
+        if let Some(mode) = policies.acceptance_mode {
+            self.acceptance_modes.borrow_mut().insert(isolate, mode);
+        }
+        if let Some(enabled) = policies.analytics_enabled {
+            self.set_analytics_enabled(isolate, SetAnalyticsEnabledRequest { enabled });
+        }
+    }
+
+    /// Whether manifest regions of the view allow the drag at its location.
+    fn manifest_accepts(&self, event: &DropEvent, formats: &[String]) -> bool {
+        let manifest = self.manifest.borrow();
+        let view = manifest.as_ref().and_then(|m| m.view(event.engine_handle));
+        match view {
+            Some(view) => view.accepts(&event.location_in_view, formats),
+            None => true,
+        }
+    }
+
+    fn set_drop_regions(
+        &self,
+        isolate: IsolateId,
+        request: SetDropRegionsRequest,
+    ) -> NativeExtensionsResult<()> {
+        let engine_handle = request
+            .engine_handle
+            .or_else(|| self.primary_view(isolate))
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let id = PlatformDropContextId {
+            isolate,
+            engine_handle,
+        };
+        if request.regions.is_empty() {
+            self.regions.borrow_mut().remove(&id);
+        } else {
+            let regions = DropRegions::new(request.regions)?;
+            self.regions.borrow_mut().insert(id, regions);
+        }
+        // Operations cached for previous regions are no longer valid.
+        self.active_regions
+            .borrow_mut()
+            .retain(|(i, _), _| *i != id);
+        Ok(())
+    }
+
+    /// Resolves region for the event. Returns `Some` when drag is over the
+    /// same region as before and Dart doesn't need to be asked.
+    fn update_active_region(
+        &self,
+        id: PlatformDropContextId,
+        event: &mut DropEvent,
+        formats: &[String],
+    ) -> Option<DropOperation> {
+        let region = {
+            let regions = self.regions.borrow();
+            let regions = regions.get(&id)?;
+            regions
+                .hit_test(&event.location_in_view, formats)
+                .map(|region| (region.id.clone(), region.spring_load_delay()))
+        };
+        let key = (id, event.session_id);
+        let Some((region_id, spring_load_delay)) = region else {
+            let previous = self.active_regions.borrow_mut().remove(&key);
+            if let Some(previous) = previous {
+                self.send_drop_region_leave(key, previous.region_id);
+            }
+            return Some(DropOperation::None);
+        };
+        if let Some(operation) = self
+            .active_regions
+            .borrow()
+            .get(&key)
+            .and_then(|active| active.cached_operation(&region_id, &event.allowed_operations))
+        {
+            return Some(operation);
+        }
+        // Spring load timer keeps running when only operations change.
+        let previous = self.active_regions.borrow_mut().remove(&key);
+        let spring_load = match previous {
+            Some(previous) if previous.region_id == region_id => previous.spring_load,
+            previous => {
+                if let Some(previous) = previous {
+                    self.send_drop_region_leave(key, previous.region_id);
+                }
+                spring_load_delay.map(|delay| self.schedule_spring_load(key, &region_id, delay))
+            }
+        };
+        self.active_regions.borrow_mut().insert(
+            key,
+            ActiveDropRegion {
+                region_id: region_id.clone(),
+                allowed_operations: event.allowed_operations.clone(),
+                operation: None,
+                spring_load,
+            },
+        );
+        event.region_id = Some(region_id);
+        None
+    }
+
+    fn send_drop_region_leave(
+        &self,
+        key: (PlatformDropContextId, DropSessionId),
+        region_id: String,
+    ) {
+        let (id, session_id) = key;
+        let leave = DropRegionEvent {
+            session_id,
+            engine_handle: id.engine_handle,
+            region_id,
+        };
+        self.invoker
+            .call_method_sync(id.isolate, "onDropRegionLeave", leave, |r| {
+                r.ok_log();
+            });
+    }
+
+    fn schedule_spring_load(
+        &self,
+        key: (PlatformDropContextId, DropSessionId),
+        region_id: &str,
+        delay: Duration,
+    ) -> Handle {
+        let weak_self = self.weak_self.clone();
+        let region_id = region_id.to_owned();
+        RunLoop::current().schedule(delay, move || {
+            let Some(this) = weak_self.upgrade() else {
+                return;
+            };
+            let (id, session_id) = key;
+            let event = DropRegionEvent {
+                session_id,
+                engine_handle: id.engine_handle,
+                region_id,
+            };
+            this.invoker
+                .call_method_sync(id.isolate, "springLoadActivated", event, |r| {
+                    r.ok_log();
+                });
+        })
+    }
+
+    fn set_auto_scroll_regions(
+        &self,
+        isolate: IsolateId,
+        request: SetAutoScrollRegionsRequest,
+    ) -> NativeExtensionsResult<()> {
+        let engine_handle = request
+            .engine_handle
+            .or_else(|| self.primary_view(isolate))
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let id = PlatformDropContextId {
+            isolate,
+            engine_handle,
+        };
+        if request.regions.is_empty() {
+            self.auto_scroll_regions.borrow_mut().remove(&id);
+        } else {
+            let regions = AutoScrollRegions::new(request.regions)?;
+            self.auto_scroll_regions.borrow_mut().insert(id, regions);
+        }
+        // Scrolling of the view stops until next drag update resolves it
+        // against the new regions.
+        let mut stopped = Vec::new();
+        self.auto_scrolling.borrow_mut().retain(|key, state| {
+            if key.0 == id {
+                stopped.push((*key, std::mem::take(&mut state.region_id)));
+                false
+            } else {
+                true
+            }
+        });
+        for (key, region_id) in stopped {
+            self.send_auto_scroll(key, region_id, Point::default());
+        }
         Ok(())
     }
 
+    /// Starts, updates or stops auto scrolling for drag at event location.
+    fn update_auto_scroll(&self, id: PlatformDropContextId, event: &DropEvent) {
+        let key = (id, event.session_id);
+        let scroll = self
+            .auto_scroll_regions
+            .borrow()
+            .get(&id)
+            .and_then(|regions| regions.velocity_at(&event.location_in_view))
+            .map(|(region_id, velocity)| (region_id.to_owned(), velocity));
+        match scroll {
+            Some((region_id, velocity)) => {
+                let mut auto_scrolling = self.auto_scrolling.borrow_mut();
+                if let Some(state) = auto_scrolling.get_mut(&key) {
+                    state.region_id = region_id;
+                    state.velocity = velocity;
+                    return;
+                }
+                let timer_id = self.next_auto_scroll_timer_id.next_id();
+                auto_scrolling.insert(
+                    key,
+                    AutoScrollState {
+                        region_id,
+                        velocity,
+                        timer_id,
+                    },
+                );
+                drop(auto_scrolling);
+                self.auto_scroll_tick(key, timer_id);
+            }
+            None => {
+                let previous = self.auto_scrolling.borrow_mut().remove(&key);
+                if let Some(previous) = previous {
+                    self.send_auto_scroll(key, previous.region_id, Point::default());
+                }
+            }
+        }
+    }
+
+    fn auto_scroll_tick(&self, key: (PlatformDropContextId, DropSessionId), timer_id: i64) {
+        let state = self
+            .auto_scrolling
+            .borrow()
+            .get(&key)
+            .filter(|state| state.timer_id == timer_id)
+            .map(|state| (state.region_id.clone(), state.velocity.clone()));
+        let Some((region_id, velocity)) = state else {
+            return;
+        };
+        self.send_auto_scroll(key, region_id, velocity);
+        let weak_self = self.weak_self.clone();
+        RunLoop::current()
+            .schedule(AUTO_SCROLL_INTERVAL, move || {
+                if let Some(this) = weak_self.upgrade() {
+                    this.auto_scroll_tick(key, timer_id);
+                }
+            })
+            .detach();
+    }
+
+    fn send_auto_scroll(
+        &self,
+        key: (PlatformDropContextId, DropSessionId),
+        region_id: String,
+        velocity: Point,
+    ) {
+        let (id, session_id) = key;
+        let interval = AUTO_SCROLL_INTERVAL.as_secs_f64();
+        let event = AutoScrollEvent {
+            session_id,
+            engine_handle: id.engine_handle,
+            region_id,
+            delta: Point {
+                x: velocity.x * interval,
+                y: velocity.y * interval,
+            },
+            velocity,
+        };
+        self.invoker
+            .call_method_sync(id.isolate, "autoScroll", event, |r| {
+                r.ok_log();
+            });
+    }
+
+    /// `None` operation means Dart failed to answer; the region is entered
+    /// again on next update so that Dart is asked again.
+    fn region_operation_resolved(
+        &self,
+        id: PlatformDropContextId,
+        session_id: DropSessionId,
+        region_id: &str,
+        operation: Option<DropOperation>,
+    ) {
+        let mut active_regions = self.active_regions.borrow_mut();
+        let key = (id, session_id);
+        if let Some(active) = active_regions.get_mut(&key) {
+            if active.region_id == region_id && active.operation.is_none() {
+                match operation {
+                    Some(operation) => active.operation = Some(operation),
+                    None => {
+                        active_regions.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Formats registered for the drop context, `None` if there is no drop
+    /// target for the view.
+    pub fn registered_drop_formats(&self, id: PlatformDropContextId) -> Option<Vec<String>> {
+        if !self.contexts.borrow().contains_key(&id) {
+            return None;
+        }
+        Some(
+            self.registered_formats
+                .borrow()
+                .get(&id)
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// View registered through `newContext` by the isolate.
+    pub fn primary_view(&self, isolate: IsolateId) -> Option<i64> {
+        self.primary_views.borrow().get(&isolate).copied()
+    }
+
+    fn unregister_drop_target_for_view(&self, isolate: IsolateId, request: ViewRequest) {
+        let id = PlatformDropContextId {
+            isolate,
+            engine_handle: request.engine_handle,
+        };
+        self.contexts.borrow_mut().remove(&id);
+        self.registered_formats.borrow_mut().remove(&id);
+    }
+
+    fn set_analytics_enabled(&self, isolate: IsolateId, request: SetAnalyticsEnabledRequest) {
+        let mut analytics = self.analytics.borrow_mut();
+        if request.enabled {
+            analytics.entry(isolate).or_default();
+        } else {
+            analytics.remove(&isolate);
+        }
+    }
+
+    fn get_analytics(&self, isolate: IsolateId) -> Option<DropAnalyticsReport> {
+        self.analytics.borrow().get(&isolate).map(|a| a.report())
+    }
+
+    fn with_analytics<F: FnOnce(&mut DropAnalytics)>(&self, id: PlatformDropContextId, f: F) {
+        if let Some(analytics) = self.analytics.borrow_mut().get_mut(&id.isolate) {
+            f(analytics);
+        }
+    }
+
+    /// Reports per-item operation restrictions of local drag, which
+    /// platforms do not carry.
+    fn apply_item_operations(event: &mut DropEvent) {
+        let Some(session_id) = event.local_session_id else {
+            return;
+        };
+        if let Some(operations) = Context::get()
+            .drag_manager()
+            .local_item_operations(session_id)
+        {
+            if operations.len() == event.items.len() {
+                for (item, allowed) in event.items.iter_mut().zip(operations) {
+                    item.allowed_operations = Some(allowed);
+                }
+            }
+        }
+    }
+
+    /// Session payload is not carried by platforms; it is taken directly
+    /// from the drag manager for the session platform drop context resolved.
+    fn apply_session_local_data(event: &mut DropEvent) {
+        let Some(session_id) = event.local_session_id else {
+            return;
+        };
+        if let Some(data) = Context::get().drag_manager().local_session_data(session_id) {
+            event.session_local_data = data;
+        }
+    }
+
+    fn session_finished(&self, id: PlatformDropContextId, session_id: DropSessionId) {
+        self.active_regions.borrow_mut().remove(&(id, session_id));
+        self.auto_scrolling.borrow_mut().remove(&(id, session_id));
+        let report = match self.analytics.borrow_mut().get_mut(&id.isolate) {
+            Some(analytics) if analytics.session_finished(session_id) => Some(analytics.report()),
+            _ => None,
+        };
+        if let Some(report) = report {
+            self.invoker
+                .call_method_sync(id.isolate, "onDropAnalytics", report, |r| {
+                    r.ok_log();
+                });
+        }
+    }
+
+    /// Returns drop context if deferred drop should be used for this drop.
+    fn deferred_drop_context(&self, id: PlatformDropContextId) -> Option<Rc<PlatformDropContext>> {
+        let mode = self
+            .acceptance_modes
+            .borrow()
+            .get(&id.isolate)
+            .copied()
+            .unwrap_or_default();
+        if mode != DropAcceptanceMode::Deferred {
+            return None;
+        }
+        self.contexts
+            .borrow()
+            .get(&id)
+            .filter(|c| c.supports_deferred_drop())
+            .cloned()
+    }
+
+    fn perform_deferred_drop(
+        &self,
+        context: Rc<PlatformDropContext>,
+        id: PlatformDropContextId,
+        event: DropEvent,
+        res: Box<dyn FnOnce(Result<(), MethodCallError>)>,
+    ) {
+        let session_id = event.session_id;
+        context
+            .show_busy_indicator(session_id, &event.location_in_view)
+            .ok_log();
+        let context = Rc::downgrade(&context);
+        let weak_self = self.weak_self.clone();
+        self.invoker.call_method_sync_cv(
+            id.isolate,
+            "onPerformDrop",
+            event,
+            move |r: Result<(), MethodCallError>| {
+                if let Some(context) = context.upgrade() {
+                    context.hide_busy_indicator(session_id);
+                }
+                if let Some(this) = weak_self.upgrade() {
+                    let result = DeferredDropResult {
+                        session_id,
+                        success: r.is_ok(),
+                        error: r.err().map(|e| e.to_string()),
+                    };
+                    this.invoker.call_method_sync(
+                        id.isolate,
+                        "onDeferredDropCompleted",
+                        result,
+                        |r| {
+                            r.ok_log();
+                        },
+                    );
+                }
+            },
+        );
+        // Let platform drop handler return right away; Dart still receives
+        // calls dispatched during the drop (see send_perform_drop).
+        RunLoop::current()
+            .schedule_next(move || res(Ok(())))
+            .detach();
+    }
+
     pub fn get_platform_drop_contexts(&self) -> Vec<Rc<PlatformDropContext>> {
         self.contexts.borrow().values().cloned().collect()
     }
@@ -218,7 +870,7 @@ impl DropManager {
     ) -> NativeExtensionsResult<ItemPreviewResponse> {
         let result = self
             .invoker
-            .call_method_cv(id, "getPreviewForItem", request)
+            .call_method_cv(id.isolate, "getPreviewForItem", request)
             .await?;
         Ok(result)
     }
@@ -240,15 +892,70 @@ impl AsyncMethodHandler for DropManager {
                 self.new_context(call.isolate, call.args.try_into()?)?;
                 Ok(Value::Null)
             }
+            "registerDropTargetForView" => self
+                .register_drop_target_for_view(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "unregisterDropTargetForView" => {
+                self.unregister_drop_target_for_view(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
             "registerDropFormats" => self
                 .register_drop_formats(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
+            "setAnalyticsEnabled" => {
+                self.set_analytics_enabled(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            "setDropAcceptanceMode" => {
+                let request: SetDropAcceptanceModeRequest = call.args.try_into()?;
+                self.acceptance_modes
+                    .borrow_mut()
+                    .insert(call.isolate, request.mode);
+                Ok(Value::Null)
+            }
+            "setDropRegions" => self
+                .set_drop_regions(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setAutoScrollRegions" => self
+                .set_auto_scroll_regions(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setDropManifest" => self
+                .set_drop_manifest(call.args.try_into()?)
+                .into_platform_result(),
+            "getAnalytics" => Ok(self.get_analytics(call.isolate).into()),
+            "resetAnalytics" => {
+                if let Some(analytics) = self.analytics.borrow_mut().get_mut(&call.isolate) {
+                    *analytics = DropAnalytics::default();
+                }
+                Ok(Value::Null)
+            }
             _ => Ok(Value::Null),
         }
     }
 
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
-        self.contexts.borrow_mut().remove(&isolate);
+        self.contexts
+            .borrow_mut()
+            .retain(|id, _| id.isolate != isolate);
+        self.registered_formats
+            .borrow_mut()
+            .retain(|id, _| id.isolate != isolate);
+        self.primary_views.borrow_mut().remove(&isolate);
+        self.analytics.borrow_mut().remove(&isolate);
+        self.acceptance_modes.borrow_mut().remove(&isolate);
+        self.regions
+            .borrow_mut()
+            .retain(|id, _| id.isolate != isolate);
+        self.active_regions
+            .borrow_mut()
+            .retain(|(id, _), _| id.isolate != isolate);
+        self.auto_scroll_regions
+            .borrow_mut()
+            .retain(|id, _| id.isolate != isolate);
+        self.auto_scrolling
+            .borrow_mut()
+            .retain(|(id, _), _| id.isolate != isolate);
+        self.tasks.close(isolate);
     }
 }
 
@@ -260,21 +967,100 @@ impl PlatformDropContextDelegate for DropManager {
     fn send_drop_update(
         &self,
         id: PlatformDropContextId,
-        event: DropEvent,
+        mut event: DropEvent,
         res: Box<dyn FnOnce(Result<DropOperation, MethodCallError>)>,
     ) {
-        self.invoker
-            .call_method_sync_cv(id, "onDropUpdate", event, res);
+        Self::apply_item_operations(&mut event);
+        Self::apply_session_local_data(&mut event);
+        let mut formats = Vec::<String>::new();
+        for format in event.items.iter().flat_map(|i| i.formats.iter()) {
+            if !formats.contains(format) {
+                formats.push(format.clone());
+            }
+        }
+        Context::get()
+            .drag_monitor()
+            .session_did_update(DragSessionInfo {
+                role: DragRole::Target,
+                session_id: event.session_id.into(),
+                item_count: Some(event.items.len() as i64),
+                formats: formats.clone(),
+            });
+        let session_id = event.session_id;
+        self.with_analytics(id, |analytics| {
+            analytics.session_updated(session_id, &formats);
+        });
+        // Scrolling doesn't depend on whether the drop would be accepted.
+        self.update_auto_scroll(id, &event);
+        if !self.manifest_accepts(&event, &formats) {
+            res(Ok(DropOperation::None));
+            return;
+        }
+        if let Some(operation) = self.update_active_region(id, &mut event, &formats) {
+            res(Ok(operation));
+            return;
+        }
+        let region_id = event.region_id.clone();
+        let weak_self = self.weak_self.clone();
+        let (method, event) = if binary_channels(id.isolate).drop_events {
+            match encode_record(RecordKind::DropEvent, event.into()) {
+                Ok(event) => ("onDropUpdateBinary", event),
+                Err(err) => {
+                    warn!("Failed to encode drop event: {err}");
+                    res(Ok(DropOperation::None));
+                    return;
+                }
+            }
+        } else {
+            ("onDropUpdate", event.into())
+        };
+        self.invoker.call_method_sync_cv(
+            id.isolate,
+            method,
+            event,
+            move |r: Result<DropOperation, MethodCallError>| {
+                if let Some(this) = weak_self.upgrade() {
+                    if let Ok(operation) = &r {
+                        this.with_analytics(id, |analytics| {
+                            analytics.operation_resolved(session_id, *operation);
+                        });
+                    }
+                    if let Some(region_id) = &region_id {
+                        let operation = r.as_ref().ok().copied();
+                        this.region_operation_resolved(id, session_id, region_id, operation);
+                    }
+                }
+                res(r)
+            },
+        );
     }
 
     fn send_perform_drop(
         &self,
         id: PlatformDropContextId,
-        event: DropEvent,
+        mut event: DropEvent,
         res: Box<dyn FnOnce(Result<(), MethodCallError>)>,
     ) {
+        event.region_id = self
+            .active_regions
+            .borrow()
+            .get(&(id, event.session_id))
+            .map(|active| active.region_id.clone());
+        Self::apply_item_operations(&mut event);
+        Self::apply_session_local_data(&mut event);
+        let session_id = event.session_id;
+        self.with_analytics(id, |analytics| analytics.session_dropped(session_id));
+        let span = OperationSpan::new(module_path!(), "performDrop");
+        let res: Box<dyn FnOnce(Result<(), MethodCallError>)> = Box::new(move |r| {
+            span.finish(&r);
+            res(r)
+        });
+        if let Some(context) = self.deferred_drop_context(id) {
+            self.perform_deferred_drop(context, id, event, res);
+            return;
+        }
         self.invoker
-            .call_method_sync_cv(id, "onPerformDrop", event, |r| {
+            .call_method_sync_cv(id.isolate, "onPerformDrop", event, |r| {
                 // Delay result callback one run loop turn. This is necessary because
                 // AsyncMethodHandler::on_message executes messages using RunLoop::spawn,
                 // whcih means that calls such as PlatformReader::get_data_for_item are delayed
@@ -287,15 +1073,23 @@ impl PlatformDropContextDelegate for DropManager {
     }
 
     fn send_drop_leave(&self, id: PlatformDropContextId, event: BaseDropEvent) {
+        Context::get()
+            .drag_monitor()
+            .session_did_end(DragRole::Target, event.session_id.into());
+        self.session_finished(id, event.session_id);
         self.invoker
-            .call_method_sync(id, "onDropLeave", event, |r| {
+            .call_method_sync(id.isolate, "onDropLeave", event, |r| {
                 r.ok_log();
             });
     }
 
     fn send_drop_ended(&self, id: PlatformDropContextId, event: BaseDropEvent) {
+        Context::get()
+            .drag_monitor()
+            .session_did_end(DragRole::Target, event.session_id.into());
+        self.session_finished(id, event.session_id);
         self.invoker
-            .call_method_sync(id, "onDropEnded", event, |r| {
+            .call_method_sync(id.isolate, "onDropEnded", event, |r| {
                 r.ok_log();
             });
     }
@@ -307,32 +1101,37 @@ impl PlatformDropContextDelegate for DropManager {
     ) -> RegisteredDataReader {
         Context::get()
             .data_reader_manager()
-            .register_platform_reader(platform_reader, id)
+            .register_platform_reader(platform_reader, id.isolate)
     }
 
     fn get_preview_for_item(
         &self,
-        id: PlatformDragContextId,
+        id: PlatformDropContextId,
         request: ItemPreviewRequest,
     ) -> Arc<Promise<PromiseResult<ItemPreviewResponse>>> {
         let res = Arc::new(Promise::new());
         let res_clone = res.clone();
+        let res_abort = res.clone();
         let weak_self = self.weak_self.clone();
-        spawn(async move {
-            let this = weak_self.upgrade();
-            if let Some(this) = this {
-                let draggable = this
-                    .get_preview_for_item(id, request)
-                    .await
-                    .ok_log_unexpected();
-                match draggable {
-                    Some(draggable) => res_clone.set(PromiseResult::Ok { value: draggable }),
-                    None => res_clone.set(PromiseResult::Cancelled),
+        self.tasks.spawn_or_else(
+            id.isolate,
+            async move {
+                let this = weak_self.upgrade();
+                if let Some(this) = this {
+                    let draggable = this
+                        .get_preview_for_item(id, request)
+                        .await
+                        .ok_log_unexpected();
+                    match draggable {
+                        Some(draggable) => res_clone.set(PromiseResult::Ok { value: draggable }),
+                        None => res_clone.set(PromiseResult::Cancelled),
+                    }
+                } else {
+                    res_clone.set(PromiseResult::Cancelled);
                 }
-            } else {
-                res_clone.set(PromiseResult::Cancelled);
-            }
-        });
+            },
+            move || res_abort.set(PromiseResult::Cancelled),
+        );
         res
     }
 }
@@ -0,0 +1,211 @@
+//! Declarative drop target configuration.
+//!
+//! Instead of registering drop targets imperatively from widgets, an
+//! application can describe all of them once in a manifest: accepted formats
+//! and regions for each view along with drop policies. The manifest is
+//! validated when set and is kept by view id rather than by isolate, so after
+//! hot restart the new isolate gets the same configuration as soon as it
+//! creates its drop contexts, regardless of the order in which widgets
+//! register.
+
+use std::collections::HashSet;
+
+use irondash_message_channel::TryFromValue;
+
+use crate::{
+    api_model::{Point, Rect},
+    drop_manager::DropAcceptanceMode,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropManifestRegion {
+    pub id: String,
+    /// In view coordinates.
+    pub rect: Rect,
+    /// Formats accepted by the region. Formats of the view if not set.
+    pub formats: Option<Vec<String>>,
+}
+
+impl DropManifestRegion {
+    fn contains(&self, point: &Point) -> bool {
+        point.x >= self.rect.x
+            && point.y >= self.rect.y
+            && point.x < self.rect.x + self.rect.width
+            && point.y < self.rect.y + self.rect.height
+    }
+}
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropManifestView {
+    pub engine_handle: i64,
+    pub formats: Vec<String>,
+    /// When present, drags outside of all regions (or over a region that
+    /// accepts none of the dragged formats) are rejected without asking Dart.
+    pub regions: Option<Vec<DropManifestRegion>>,
+}
+
+impl DropManifestView {
+    /// Whether drag at location carrying given formats may be accepted.
+    pub fn accepts(&self, location: &Point, formats: &[String]) -> bool {
+        let Some(regions) = &self.regions else {
+            return true;
+        };
+        regions
+            .iter()
+            .filter(|region| region.contains(location))
+            .any(|region| {
+                let accepted = region.formats.as_ref().unwrap_or(&self.formats);
+                formats.iter().any(|f| accepted.contains(f))
+            })
+    }
+}
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropManifestPolicies {
+    pub acceptance_mode: Option<DropAcceptanceMode>,
+    pub analytics_enabled: Option<bool>,
+}
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropManifest {
+    pub views: Vec<DropManifestView>,
+    pub policies: Option<DropManifestPolicies>,
+}
+
+fn invalid(message: String) -> NativeExtensionsError {
+    NativeExtensionsError::OtherError(format!("Invalid drop manifest: {message}"))
+}
+
+impl DropManifest {
+    pub fn validate(&self) -> NativeExtensionsResult<()> {
+        let mut engine_handles = HashSet::new();
+        for view in &self.views {
+            if !engine_handles.insert(view.engine_handle) {
+                return Err(invalid(format!("duplicate view {}", view.engine_handle)));
+            }
+            if view.formats.iter().any(|f| f.is_empty()) {
+                return Err(invalid(format!(
+                    "empty format in view {}",
+                    view.engine_handle
+                )));
+            }
+            let mut region_ids = HashSet::new();
+            for region in view.regions.iter().flatten() {
+                if !region_ids.insert(region.id.as_str()) {
+                    return Err(invalid(format!(
+                        "duplicate region '{}' in view {}",
+                        region.id, view.engine_handle
+                    )));
+                }
+                if !(region.rect.width > 0.0 && region.rect.height > 0.0) {
+                    return Err(invalid(format!("region '{}' is empty", region.id)));
+                }
+                if let Some(formats) = &region.formats {
+                    if let Some(format) = formats.iter().find(|f| !view.formats.contains(f)) {
+                        return Err(invalid(format!(
+                            "region '{}' accepts {format}, which view {} does not register",
+                            region.id, view.engine_handle
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn view(&self, engine_handle: i64) -> Option<&DropManifestView> {
+        self.views.iter().find(|v| v.engine_handle == engine_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_model::{Point, Rect};
+
+    use super::{DropManifest, DropManifestRegion, DropManifestView};
+
+    fn region(id: &str, x: f64, formats: Option<&[&str]>) -> DropManifestRegion {
+        DropManifestRegion {
+            id: id.into(),
+            rect: Rect {
+                x,
+                y: 0.0,
+                width: 10.0,
+                height: 10.0,
+            },
+            formats: formats.map(|f| f.iter().map(|f| f.to_string()).collect()),
+        }
+    }
+
+    fn view(engine_handle: i64, regions: Option<Vec<DropManifestRegion>>) -> DropManifestView {
+        DropManifestView {
+            engine_handle,
+            formats: vec!["public.png".into(), "public.utf8-plain-text".into()],
+            regions,
+        }
+    }
+
+    fn manifest(views: Vec<DropManifestView>) -> DropManifest {
+        DropManifest {
+            views,
+            policies: None,
+        }
+    }
+
+    fn formats(formats: &[&str]) -> Vec<String> {
+        formats.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(manifest(vec![view(1, None), view(2, None)])
+            .validate()
+            .is_ok());
+        assert!(manifest(vec![view(1, None), view(1, None)])
+            .validate()
+            .is_err());
+        let duplicate_region = view(
+            1,
+            Some(vec![region("a", 0.0, None), region("a", 20.0, None)]),
+        );
+        assert!(manifest(vec![duplicate_region]).validate().is_err());
+        let mut empty_region = region("a", 0.0, None);
+        empty_region.rect.width = 0.0;
+        assert!(manifest(vec![view(1, Some(vec![empty_region]))])
+            .validate()
+            .is_err());
+        let unknown_format = region("a", 0.0, Some(&["public.jpeg"]));
+        assert!(manifest(vec![view(1, Some(vec![unknown_format]))])
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_accepts() {
+        let view = view(
+            1,
+            Some(vec![
+                region("images", 0.0, Some(&["public.png"])),
+                region("any", 20.0, None),
+            ]),
+        );
+        let png = formats(&["public.png"]);
+        let text = formats(&["public.utf8-plain-text"]);
+        assert!(view.accepts(&Point { x: 5.0, y: 5.0 }, &png));
+        assert!(!view.accepts(&Point { x: 5.0, y: 5.0 }, &text));
+        assert!(view.accepts(&Point { x: 25.0, y: 5.0 }, &text));
+        // Outside of all regions.
+        assert!(!view.accepts(&Point { x: 15.0, y: 5.0 }, &png));
+    }
+
+    #[test]
+    fn test_view_without_regions_accepts_everything() {
+        let view = view(1, None);
+        assert!(view.accepts(&Point { x: -1.0, y: -1.0 }, &formats(&["public.jpeg"])));
+    }
+}
@@ -0,0 +1,274 @@
+//! Drop regions hit-tested in Rust.
+//!
+//! Dart can register regions for a view instead of resolving the drop
+//! location itself on every `onDropUpdate`. While a drag moves within the
+//! same region, the operation Dart returned for it is reused and Dart is not
+//! called again. Dart is notified through `onDropUpdate` (with `regionId`)
+//! when the drag enters a region and through `onDropRegionLeave` when it
+//! leaves a region, including when it moves directly into another one.
+//!
+//! Spring loaded regions additionally fire `springLoadActivated` once the
+//! drag hovers over them for the configured delay. This is timer based on
+//! all platforms, including iOS: `UISpringLoadedInteraction` needs its own
+//! hit-testable view, which would take touches from the Flutter view.
+
+use std::time::Duration;
+
+use irondash_message_channel::{IntoValue, TryFromValue};
+use irondash_run_loop::Handle;
+
+use crate::{
+    api_model::{DropOperation, Point, Rect},
+    drop_manager::DropSessionId,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+};
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase", tag = "type", content = "content")]
+pub enum DropRegionShape {
+    Rect(Rect),
+    /// Closed polygon, evaluated with the even-odd rule.
+    Path(Vec<Point>),
+}
+
+impl DropRegionShape {
+    fn contains(&self, point: &Point) -> bool {
+        match self {
+            DropRegionShape::Rect(rect) => {
+                point.x >= rect.x
+                    && point.y >= rect.y
+                    && point.x < rect.x + rect.width
+                    && point.y < rect.y + rect.height
+            }
+            DropRegionShape::Path(points) => {
+                let mut inside = false;
+                let mut prev = match points.last() {
+                    Some(prev) => prev,
+                    None => return false,
+                };
+                for p in points {
+                    if (p.y > point.y) != (prev.y > point.y)
+                        && point.x < (prev.x - p.x) * (point.y - p.y) / (prev.y - p.y) + p.x
+                    {
+                        inside = !inside;
+                    }
+                    prev = p;
+                }
+                inside
+            }
+        }
+    }
+}
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropRegion {
+    pub id: String,
+    /// In view coordinates.
+    pub shape: DropRegionShape,
+    /// Formats accepted by the region. Any format if not set.
+    pub formats: Option<Vec<String>>,
+    pub spring_loaded: Option<bool>,
+    /// In seconds, [`DEFAULT_SPRING_LOAD_DELAY`] if not set.
+    pub spring_load_delay: Option<f64>,
+}
+
+/// Matches the default spring loading delay of macOS Finder.
+pub const DEFAULT_SPRING_LOAD_DELAY: f64 = 0.5;
+
+impl DropRegion {
+    fn accepts(&self, location: &Point, formats: &[String]) -> bool {
+        self.shape.contains(location)
+            && match &self.formats {
+                Some(accepted) => formats.iter().any(|f| accepted.contains(f)),
+                None => true,
+            }
+    }
+
+    /// Hover time after which spring load activates, if spring loaded.
+    pub fn spring_load_delay(&self) -> Option<Duration> {
+        if self.spring_loaded != Some(true) {
+            return None;
+        }
+        let delay = self.spring_load_delay.unwrap_or(DEFAULT_SPRING_LOAD_DELAY);
+        Duration::try_from_secs_f64(delay).ok()
+    }
+}
+
+/// Regions of a view, later regions are on top of earlier ones.
+#[derive(Clone, Debug, Default)]
+pub struct DropRegions {
+    regions: Vec<DropRegion>,
+}
+
+impl DropRegions {
+    pub fn new(regions: Vec<DropRegion>) -> NativeExtensionsResult<Self> {
+        for (index, region) in regions.iter().enumerate() {
+            if regions[..index].iter().any(|r| r.id == region.id) {
+                return Err(NativeExtensionsError::OtherError(format!(
+                    "Duplicate drop region '{}'",
+                    region.id
+                )));
+            }
+            let invalid_delay = region
+                .spring_load_delay
+                .is_some_and(|delay| Duration::try_from_secs_f64(delay).is_err());
+            if invalid_delay {
+                return Err(NativeExtensionsError::OtherError(format!(
+                    "Drop region '{}' has invalid spring load delay",
+                    region.id
+                )));
+            }
+            if let DropRegionShape::Path(points) = &region.shape {
+                if points.len() < 3 {
+                    return Err(NativeExtensionsError::OtherError(format!(
+                        "Drop region '{}' needs at least three points",
+                        region.id
+                    )));
+                }
+            }
+        }
+        Ok(Self { regions })
+    }
+
+    /// Topmost region at location that accepts any of the formats.
+    pub fn hit_test(&self, location: &Point, formats: &[String]) -> Option<&DropRegion> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|region| region.accepts(location, formats))
+    }
+}
+
+/// Region the drag of a session is over.
+pub struct ActiveDropRegion {
+    pub region_id: String,
+    pub allowed_operations: Vec<DropOperation>,
+    /// Operation Dart returned for the region; `None` while pending.
+    /// Updates while pending are answered with [`DropOperation::None`]
+    /// instead of calling Dart again.
+    pub operation: Option<DropOperation>,
+    /// Pending spring load timer, cancelled when dropped.
+    pub spring_load: Option<Handle>,
+}
+
+impl ActiveDropRegion {
+    /// Operation to reuse for update with given region and operations.
+    pub fn cached_operation(
+        &self,
+        region_id: &str,
+        allowed_operations: &[DropOperation],
+    ) -> Option<DropOperation> {
+        if self.region_id == region_id && self.allowed_operations == allowed_operations {
+            Some(self.operation.unwrap_or(DropOperation::None))
+        } else {
+            None
+        }
+    }
+}
+
+/// Sent as `onDropRegionLeave` and `springLoadActivated`.
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropRegionEvent {
+    pub session_id: DropSessionId,
+    pub engine_handle: i64,
+    pub region_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActiveDropRegion, DropRegion, DropRegionShape, DropRegions};
+    use crate::api_model::{DropOperation, Point, Rect};
+
+    fn region(id: &str, shape: DropRegionShape, formats: Option<&[&str]>) -> DropRegion {
+        DropRegion {
+            id: id.into(),
+            shape,
+            formats: formats.map(|f| f.iter().map(|f| f.to_string()).collect()),
+            spring_loaded: None,
+            spring_load_delay: None,
+        }
+    }
+
+    #[test]
+    fn test_topmost_accepting_region_wins() {
+        let regions = DropRegions::new(vec![
+            region(
+                "back",
+                DropRegionShape::Rect(Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 100.0,
+                    height: 100.0,
+                }),
+                None,
+            ),
+            region(
+                "front",
+                DropRegionShape::Path(vec![
+                    Point { x: 0.0, y: 0.0 },
+                    Point { x: 50.0, y: 0.0 },
+                    Point { x: 0.0, y: 50.0 },
+                ]),
+                Some(&["public.png"]),
+            ),
+        ])
+        .unwrap();
+        let png = ["public.png".to_string()];
+        let text = ["public.utf8-plain-text".to_string()];
+        let at = |x, y, formats: &[String]| {
+            regions
+                .hit_test(&Point { x, y }, formats)
+                .map(|r| r.id.clone())
+        };
+        assert_eq!(at(10.0, 10.0, &png).as_deref(), Some("front"));
+        assert_eq!(at(10.0, 10.0, &text).as_deref(), Some("back"));
+        assert_eq!(at(40.0, 40.0, &png).as_deref(), Some("back"));
+        assert_eq!(at(150.0, 10.0, &png), None);
+    }
+
+    #[test]
+    fn test_invalid_spring_load_delay_is_rejected() {
+        for delay in [-1.0, f64::NAN, f64::INFINITY, 1e30] {
+            let mut region = region(
+                "region",
+                DropRegionShape::Rect(Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 10.0,
+                    height: 10.0,
+                }),
+                None,
+            );
+            region.spring_loaded = Some(true);
+            region.spring_load_delay = Some(delay);
+            assert!(DropRegions::new(vec![region]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_pending_operation_is_not_requested_again() {
+        let mut active = ActiveDropRegion {
+            region_id: "region".into(),
+            allowed_operations: vec![DropOperation::Copy],
+            operation: None,
+            spring_load: None,
+        };
+        let copy = [DropOperation::Copy];
+        assert_eq!(
+            active.cached_operation("region", &copy),
+            Some(DropOperation::None)
+        );
+        active.operation = Some(DropOperation::Copy);
+        assert_eq!(
+            active.cached_operation("region", &copy),
+            Some(DropOperation::Copy)
+        );
+        assert_eq!(active.cached_operation("other", &copy), None);
+        assert_eq!(
+            active.cached_operation("region", &[DropOperation::Move]),
+            None
+        );
+    }
+}
@@ -21,6 +21,47 @@ pub enum NativeExtensionsError {
     PlatformMenuNotFound,
     InvalidMenuElement,
     InvalidMenuConfigurationId,
+    /// Clipboard access was denied by device or profile policy. Contains
+    /// the restriction responsible, if known.
+    BlockedByPolicy(Option<String>),
+    /// Clipboard contents were replaced by another application.
+    ClipboardNotOwned,
+    /// Incoming file was rejected by [`crate::file_policy::FilePolicy`].
+    /// `rule` is the blocked extension or format that matched.
+    FilePolicyViolation {
+        file_name: Option<String>,
+        format: Option<String>,
+        rule: String,
+    },
+    /// Read did not finish within requested timeout.
+    Timeout,
+    /// Requested format is not (or no longer) provided by the source.
+    FormatNotAvailable(String),
+    /// Application providing the data did not respond in time or refused
+    /// the call; reading again later may succeed.
+    SourceAppUnresponsive(Option<String>),
+    /// Operation was cancelled through its progress or because the owning
+    /// isolate went away.
+    Cancelled,
+    /// Operating system denied access to the data, for example to a file or
+    /// content URI without permission.
+    AccessDenied(Option<String>),
+    /// Virtual files are not supported by the platform or by the source.
+    VirtualFileUnsupported,
+}
+
+/// How Dart caller can recover from an error. Dart derives the same value
+/// from the error code in `native_error.dart`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorRecovery {
+    /// Transient failure, same operation may succeed when retried.
+    Retry,
+    /// Needs user to do something first (grant permission, copy again).
+    UserAction,
+    /// Operation was cancelled, nothing to recover from.
+    NotNeeded,
+    /// Retrying will fail the same way.
+    Unrecoverable,
 }
 
 pub type NativeExtensionsResult<T> = Result<T, NativeExtensionsError>;
@@ -55,6 +96,39 @@ impl Display for NativeExtensionsError {
             NativeExtensionsError::InvalidMenuConfigurationId => {
                 write!(f, "invalid menu configuration id")
             }
+            NativeExtensionsError::BlockedByPolicy(source) => match source {
+                Some(source) => write!(f, "clipboard access blocked by policy: {source}"),
+                None => write!(f, "clipboard access blocked by policy"),
+            },
+            NativeExtensionsError::ClipboardNotOwned => {
+                write!(f, "clipboard is not owned by this application")
+            }
+            NativeExtensionsError::FilePolicyViolation {
+                file_name,
+                format,
+                rule,
+            } => write!(
+                f,
+                "file {} ({}) blocked by policy rule {rule:?}",
+                file_name.as_deref().unwrap_or("<unnamed>"),
+                format.as_deref().unwrap_or("unknown format"),
+            ),
+            NativeExtensionsError::Timeout => write!(f, "operation timed out"),
+            NativeExtensionsError::FormatNotAvailable(format) => {
+                write!(f, "format {format} is not available")
+            }
+            NativeExtensionsError::SourceAppUnresponsive(m) => match m {
+                Some(m) => write!(f, "source application is not responding: {m}"),
+                None => write!(f, "source application is not responding"),
+            },
+            NativeExtensionsError::Cancelled => write!(f, "operation cancelled"),
+            NativeExtensionsError::AccessDenied(m) => match m {
+                Some(m) => write!(f, "access denied: {m}"),
+                None => write!(f, "access denied"),
+            },
+            NativeExtensionsError::VirtualFileUnsupported => {
+                write!(f, "virtual files are not supported")
+            }
         }
     }
 }
@@ -62,31 +136,58 @@ impl Display for NativeExtensionsError {
 impl std::error::Error for NativeExtensionsError {}
 
 impl NativeExtensionsError {
-    fn get_detail(&self) -> Value {
+    fn code(&self) -> &'static str {
         match self {
-            NativeExtensionsError::UnknownError => "unknownError".into(),
-            NativeExtensionsError::MethodCallError(_) => "methodCallError".into(),
-            NativeExtensionsError::OtherError(_) => "otherError".into(),
-            NativeExtensionsError::DataSourceNotFound => "dataSourceNotFound".into(),
-            NativeExtensionsError::ReaderNotFound => "readerNotFound".into(),
-            NativeExtensionsError::PlatformContextNotFound => "platformContextNotFound".into(),
-            NativeExtensionsError::UnsupportedOperation => "unsupportedOperation".into(),
-            NativeExtensionsError::VirtualFileSessionNotFound => {
-                "virtualFileSessionNotFound".into()
-            }
-            NativeExtensionsError::VirtualFileReceiveError(_) => "virtualFileReceiveError".into(),
-            NativeExtensionsError::IOError(_) => "ioError".into(),
-            NativeExtensionsError::InvalidData => "invalidData".into(),
-            NativeExtensionsError::DragSessionNotFound => "dragSessionNotFound".into(),
-            NativeExtensionsError::MouseEventNotFound => "mouseEventNotFound".into(),
-            NativeExtensionsError::EngineContextError(_) => "engineContextError".into(),
-            NativeExtensionsError::PlatformMenuNotFound => "platformMenuNotFound".into(),
-            NativeExtensionsError::InvalidMenuElement => "invalidMenuElement".into(),
-            NativeExtensionsError::InvalidMenuConfigurationId => {
-                "invalidMenuConfigurationId".into()
+            NativeExtensionsError::UnknownError => "unknownError",
+            NativeExtensionsError::MethodCallError(_) => "methodCallError",
+            NativeExtensionsError::OtherError(_) => "otherError",
+            NativeExtensionsError::DataSourceNotFound => "dataSourceNotFound",
+            NativeExtensionsError::ReaderNotFound => "readerNotFound",
+            NativeExtensionsError::PlatformContextNotFound => "platformContextNotFound",
+            NativeExtensionsError::UnsupportedOperation => "unsupportedOperation",
+            NativeExtensionsError::VirtualFileSessionNotFound => "virtualFileSessionNotFound",
+            NativeExtensionsError::VirtualFileReceiveError(_) => "virtualFileReceiveError",
+            NativeExtensionsError::IOError(_) => "ioError",
+            NativeExtensionsError::InvalidData => "invalidData",
+            NativeExtensionsError::DragSessionNotFound => "dragSessionNotFound",
+            NativeExtensionsError::MouseEventNotFound => "mouseEventNotFound",
+            NativeExtensionsError::EngineContextError(_) => "engineContextError",
+            NativeExtensionsError::PlatformMenuNotFound => "platformMenuNotFound",
+            NativeExtensionsError::InvalidMenuElement => "invalidMenuElement",
+            NativeExtensionsError::InvalidMenuConfigurationId => "invalidMenuConfigurationId",
+            NativeExtensionsError::BlockedByPolicy(_) => "blockedByPolicy",
+            NativeExtensionsError::ClipboardNotOwned => "clipboardNotOwned",
+            NativeExtensionsError::FilePolicyViolation { .. } => "filePolicyViolation",
+            NativeExtensionsError::Timeout => "timeout",
+            NativeExtensionsError::FormatNotAvailable(_) => "formatNotAvailable",
+            NativeExtensionsError::SourceAppUnresponsive(_) => "sourceAppUnresponsive",
+            NativeExtensionsError::Cancelled => "cancelled",
+            NativeExtensionsError::AccessDenied(_) => "accessDenied",
+            NativeExtensionsError::VirtualFileUnsupported => "virtualFileUnsupported",
+        }
+    }
+
+    pub fn recovery(&self) -> ErrorRecovery {
+        match self {
+            NativeExtensionsError::Timeout | NativeExtensionsError::SourceAppUnresponsive(_) => {
+                ErrorRecovery::Retry
             }
+            NativeExtensionsError::BlockedByPolicy(_)
+            | NativeExtensionsError::AccessDenied(_)
+            | NativeExtensionsError::ClipboardNotOwned => ErrorRecovery::UserAction,
+            NativeExtensionsError::IOError(e) => match e.kind() {
+                io::ErrorKind::PermissionDenied => ErrorRecovery::UserAction,
+                io::ErrorKind::Interrupted | io::ErrorKind::TimedOut => ErrorRecovery::Retry,
+                _ => ErrorRecovery::Unrecoverable,
+            },
+            NativeExtensionsError::Cancelled => ErrorRecovery::NotNeeded,
+            _ => ErrorRecovery::Unrecoverable,
         }
     }
+
+    fn get_detail(&self) -> Value {
+        Value::String(self.code().into())
+    }
 }
 
 impl From<NativeExtensionsError> for PlatformError {
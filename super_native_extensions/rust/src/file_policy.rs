@@ -0,0 +1,63 @@
+//! Ingress policy for files received from other applications.
+//!
+//! The policy is evaluated natively before a virtual file is extracted or
+//! a file path is delivered to Dart, so that all entry points (drop, paste,
+//! import pipeline) enforce the same rules.
+
+use std::path::Path;
+
+use irondash_message_channel::TryFromValue;
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+#[derive(TryFromValue, Debug, Clone, Default)]
+#[irondash(rename_all = "camelCase")]
+pub struct FilePolicy {
+    /// Extensions without leading dot, compared case insensitively.
+    blocked_extensions: Vec<String>,
+    /// Formats (MIME types, UTIs or Windows format names), compared case
+    /// insensitively.
+    blocked_formats: Vec<String>,
+}
+
+impl FilePolicy {
+    fn blocked_extension_rule(&self, file_name: &str) -> Option<&str> {
+        let extension = Path::new(file_name).extension()?.to_str()?;
+        self.blocked_extensions
+            .iter()
+            .find(|e| e.trim_start_matches('.').eq_ignore_ascii_case(extension))
+            .map(|e| e.as_str())
+    }
+
+    fn blocked_format_rule(&self, format: &str) -> Option<&str> {
+        self.blocked_formats
+            .iter()
+            .find(|f| f.eq_ignore_ascii_case(format))
+            .map(|f| f.as_str())
+    }
+
+    /// Returns [`NativeExtensionsError::FilePolicyViolation`] if either file
+    /// name or format is blocked. Missing values are not checked.
+    pub fn check(
+        &self,
+        file_name: Option<&str>,
+        format: Option<&str>,
+    ) -> NativeExtensionsResult<()> {
+        let rule = file_name
+            .and_then(|name| self.blocked_extension_rule(name))
+            .or_else(|| format.and_then(|format| self.blocked_format_rule(format)));
+        match rule {
+            Some(rule) => Err(NativeExtensionsError::FilePolicyViolation {
+                file_name: file_name.map(|n| n.to_owned()),
+                format: format.map(|f| f.to_owned()),
+                rule: rule.to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn check_path(&self, path: &Path, format: Option<&str>) -> NativeExtensionsResult<()> {
+        let file_name = path.file_name().map(|n| n.to_string_lossy());
+        self.check(file_name.as_deref(), format)
+    }
+}
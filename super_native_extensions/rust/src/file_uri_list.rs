@@ -0,0 +1,198 @@
+//! Synthesized `fileUriList` format.
+//!
+//! Items that carry file references in a platform format (`CF_HDROP` on
+//! Windows, `public.file-url` on macOS and iOS, `text/uri-list` on Linux) get
+//! additional `fileUriList` format. Reading it returns list of normalized
+//! absolute paths: file URLs are decoded, `.` and `..` components resolved,
+//! and on Windows separators converted to backslashes. Entries that are not
+//! local files are skipped. Legacy `NSFilenamesPboardType` is exposed by
+//! AppKit as `public.file-url` of individual items.
+
+use irondash_message_channel::Value;
+
+pub const FILE_URI_LIST_FORMAT: &str = "fileUriList";
+
+/// Source format among item formats, if item can synthesize `fileUriList`.
+pub fn source_format<'a>(formats: &'a [String], sources: &[String]) -> Option<&'a String> {
+    if formats.iter().any(|f| f == FILE_URI_LIST_FORMAT) {
+        return None;
+    }
+    formats.iter().find(|f| sources.contains(f))
+}
+
+/// Inserts `fileUriList` after the source format.
+pub fn extend_formats(mut formats: Vec<String>, sources: &[String]) -> Vec<String> {
+    if let Some(source) = source_format(&formats, sources) {
+        let index = formats.iter().position(|f| f == source).unwrap_or_default();
+        formats.insert(index + 1, FILE_URI_LIST_FORMAT.into());
+    }
+    formats
+}
+
+/// Paths referenced by data read in source format.
+pub fn paths_from_value(value: Value) -> Vec<String> {
+    paths_from_value_for(value, cfg!(windows))
+}
+
+fn paths_from_value_for(value: Value, windows: bool) -> Vec<String> {
+    let entries: Vec<String> = match value {
+        Value::String(string) => string.lines().map(|l| l.to_owned()).collect(),
+        Value::U8List(data) => String::from_utf8_lossy(&data)
+            .lines()
+            .map(|l| l.to_owned())
+            .collect(),
+        Value::List(values) => values
+            .into_iter()
+            .filter_map(|v| match v {
+                Value::String(string) => Some(string),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    entries
+        .iter()
+        .map(|e| e.trim_end_matches(['\r', '\0']))
+        // Comments are allowed in text/uri-list.
+        .filter(|e| !e.is_empty() && !e.starts_with('#'))
+        .filter_map(|e| path_from_entry(e, windows))
+        .collect()
+}
+
+fn path_from_entry(entry: &str, windows: bool) -> Option<String> {
+    let path = match strip_prefix_ignore_case(entry, "file:") {
+        Some(rest) => {
+            let rest = percent_decode(rest.split(['?', '#']).next().unwrap_or_default());
+            match rest.strip_prefix("//") {
+                Some(rest) => {
+                    let (host, path) = match rest.find('/') {
+                        Some(index) => rest.split_at(index),
+                        None => (rest, ""),
+                    };
+                    if host.is_empty() || host.eq_ignore_ascii_case("localhost") {
+                        path.to_owned()
+                    } else if windows {
+                        format!("//{host}{path}")
+                    } else {
+                        return None;
+                    }
+                }
+                None => rest,
+            }
+        }
+        None if entry.contains("://") => return None,
+        None => entry.to_owned(),
+    };
+    normalize(&path, windows)
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let decoded = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(byte) => {
+                res.push(byte);
+                i += 3;
+            }
+            None => {
+                res.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&res).into_owned()
+}
+
+/// Resolves `.` and `..` components. Returns `None` for relative paths.
+fn normalize(path: &str, windows: bool) -> Option<String> {
+    let (root, rest) = if windows {
+        let path = path.replace('\\', "/");
+        let path = path.as_str();
+        // URL paths have slash before drive letter.
+        let path = match path.as_bytes() {
+            [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &path[1..],
+            _ => path,
+        };
+        match path.as_bytes() {
+            [drive, b':', b'/', ..] if drive.is_ascii_alphabetic() => (
+                format!("{}:\\", path[..1].to_ascii_uppercase()),
+                path[3..].to_owned(),
+            ),
+            [b'/', b'/', ..] => ("\\\\".to_owned(), path[2..].to_owned()),
+            _ => return None,
+        }
+    } else {
+        match path.strip_prefix('/') {
+            Some(rest) => ("/".to_owned(), rest.to_owned()),
+            None => return None,
+        }
+    };
+    let mut components = Vec::<&str>::new();
+    for component in rest.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    let separator = if windows { "\\" } else { "/" };
+    Some(format!("{root}{}", components.join(separator)))
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use super::{extend_formats, path_from_entry, paths_from_value_for, FILE_URI_LIST_FORMAT};
+
+    #[test]
+    fn test_unix_entries() {
+        let value = Value::String(
+            "# comment\r\nfile:///tmp/a%20b/../c%20d.txt\r\nfile://localhost/x/./y\r\n\
+             file://server/share\r\nhttps://example.com/\r\n"
+                .into(),
+        );
+        assert_eq!(
+            paths_from_value_for(value, false),
+            vec!["/tmp/c d.txt".to_owned(), "/x/y".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_windows_entries() {
+        let path = |e| path_from_entry(e, true);
+        assert_eq!(path("c:\\Users\\a\\..\\b").as_deref(), Some("C:\\Users\\b"));
+        assert_eq!(path("file:///C:/x%23/y").as_deref(), Some("C:\\x#\\y"));
+        assert_eq!(
+            path("file://server/share/f").as_deref(),
+            Some("\\\\server\\share\\f")
+        );
+        assert_eq!(path("relative\\path"), None);
+    }
+
+    #[test]
+    fn test_extend_formats() {
+        let sources = ["text/uri-list".to_owned()];
+        let formats = vec!["text/uri-list".to_owned(), "text/plain".to_owned()];
+        assert_eq!(
+            extend_formats(formats, &sources),
+            vec!["text/uri-list", FILE_URI_LIST_FORMAT, "text/plain"]
+        );
+    }
+}
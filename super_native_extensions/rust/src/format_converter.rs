@@ -0,0 +1,103 @@
+//! Formats synthesized on read by registered converters.
+//!
+//! A converter turns data in a source format into a target format. When an
+//! item has the source format but not the target format, the target format
+//! is reported as synthesized right after the source format and reading it
+//! transparently reads and converts the source data. This complements the
+//! built-in platform conversions ([`crate::reader_manager::FormatConversion`]).
+
+use std::rc::Rc;
+
+use async_trait::async_trait;
+use irondash_message_channel::{TryFromValue, Value};
+
+use crate::error::NativeExtensionsResult;
+
+#[async_trait(?Send)]
+pub trait FormatConverter {
+    fn source_format(&self) -> &str;
+    fn target_format(&self) -> &str;
+    async fn convert(&self, data: Value) -> NativeExtensionsResult<Value>;
+}
+
+/// Built-in converters use negative ids so that they never collide with
+/// converters registered by Dart, which are numbered from 1.
+pub const FIRST_IMAGE_CONVERTER_ID: i64 = -1000;
+pub const RTF_TO_HTML_CONVERTER_ID: i64 = -2000;
+
+/// Converter implemented in Dart.
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+pub struct DartFormatConverterDescriptor {
+    pub converter_id: i64,
+    pub source_format: String,
+    pub target_format: String,
+}
+
+/// Converters registered by one isolate, in registration order.
+#[derive(Default, Clone)]
+pub struct FormatConverters {
+    converters: Vec<(i64, Rc<dyn FormatConverter>)>,
+}
+
+impl FormatConverters {
+    /// Replaces converter with same id.
+    pub fn register(&mut self, id: i64, converter: Rc<dyn FormatConverter>) {
+        self.unregister(id);
+        self.converters.push((id, converter));
+    }
+
+    pub fn unregister(&mut self, id: i64) {
+        self.converters.retain(|c| c.0 != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.converters.is_empty()
+    }
+
+    /// Inserts formats that converters can synthesize after their source
+    /// format.
+    pub fn extend_formats(&self, formats: Vec<String>) -> Vec<String> {
+        let mut res = Vec::with_capacity(formats.len());
+        for format in &formats {
+            res.push(format.clone());
+            for (_, converter) in &self.converters {
+                let target = converter.target_format();
+                if converter.source_format() == format
+                    && !formats.iter().any(|f| f == target)
+                    && !res.iter().any(|f| f == target)
+                {
+                    res.push(target.to_owned());
+                }
+            }
+        }
+        res
+    }
+
+    /// Source formats of converters that synthesize `target`.
+    pub fn source_formats(&self, target: &str) -> Vec<String> {
+        self.converters
+            .iter()
+            .filter(|(_, c)| c.target_format() == target)
+            .map(|(_, c)| c.source_format().to_owned())
+            .collect()
+    }
+
+    /// Converter synthesizing `format` for an item with given (platform)
+    /// formats. Returns `None` if the item already has the format.
+    pub fn converter_for(
+        &self,
+        formats: &[String],
+        format: &str,
+    ) -> Option<Rc<dyn FormatConverter>> {
+        if formats.iter().any(|f| f == format) {
+            return None;
+        }
+        self.converters
+            .iter()
+            .find(|(_, c)| {
+                c.target_format() == format && formats.iter().any(|f| f == c.source_format())
+            })
+            .map(|(_, c)| c.clone())
+    }
+}
@@ -0,0 +1,143 @@
+//! Cross-platform fidelity rank of data formats.
+//!
+//! Rank is only meaningful when comparing alternative representations of the
+//! same content (i.e. HTML vs plain text, PNG vs DIB); higher rank means
+//! richer representation. Unknown formats have rank 0.
+//!
+//! Data providers may also declare fidelity of their representations
+//! explicitly. Platforms use representation order to express fidelity
+//! (`NSItemProvider` registered type identifiers and pasteboard item types on
+//! Apple platforms, `IEnumFORMATETC` order on Windows, target list order on
+//! Linux and MIME type order of `ClipDescription` on Android), so declared
+//! levels are applied by sorting representations before the provider is
+//! handed to platform code.
+
+use crate::api_model::DataProvider;
+
+/// Standard Windows clipboard formats don't have names and are reported
+/// with this prefix followed by format number.
+const WINDOWS_STANDARD_PREFIX: &str = "NativeShell_CF_";
+
+fn windows_standard_format_fidelity(format: u32) -> i64 {
+    match format {
+        1 | 7 => 10,  // CF_TEXT, CF_OEMTEXT
+        13 => 20,     // CF_UNICODETEXT
+        2 => 30,      // CF_BITMAP
+        3 | 14 => 65, // CF_METAFILEPICT, CF_ENHMETAFILE
+        6 => 55,      // CF_TIFF
+        8 => 45,      // CF_DIB
+        17 => 50,     // CF_DIBV5
+        _ => 0,
+    }
+}
+
+pub fn format_fidelity(format: &str) -> i64 {
+    if let Some(format) = format.strip_prefix(WINDOWS_STANDARD_PREFIX) {
+        return format
+            .parse::<u32>()
+            .map(windows_standard_format_fidelity)
+            .unwrap_or(0);
+    }
+    // Ignore parameters such as charset
+    let format = format.split(';').next().unwrap_or(format).trim();
+    match format.to_ascii_lowercase().as_str() {
+        // Rich documents
+        "com.apple.webarchive" => 90,
+        "public.html" | "text/html" | "html format" => 80,
+        "com.apple.flat-rtfd" | "com.apple.rtfd" => 75,
+        "public.rtf" | "text/rtf" | "application/rtf" | "rich text format" => 70,
+        // Plain text
+        "public.utf8-plain-text" | "public.utf16-plain-text" | "utf8_string" => 20,
+        "text/plain" => 20,
+        "public.plain-text" | "nsstringpboardtype" | "string" | "text" => 10,
+        // Vector images
+        "com.adobe.pdf" | "application/pdf" => 85,
+        "public.svg-image" | "image/svg+xml" => 85,
+        // Raster images
+        "public.png" | "image/png" | "png" => 60,
+        "public.tiff" | "image/tiff" => 55,
+        "public.heic" | "image/heic" => 50,
+        "com.microsoft.bmp" | "image/bmp" => 45,
+        "public.jpeg" | "image/jpeg" | "jfif" => 40,
+        "com.compuserve.gif" | "image/gif" | "gif" => 35,
+        _ => 0,
+    }
+}
+
+/// Orders representations by declared fidelity, highest first. Formats
+/// without declared level keep their relative order and follow the declared
+/// ones.
+pub fn apply_declared_fidelity(provider: &mut DataProvider) {
+    let Some(fidelity) = &provider.fidelity else {
+        return;
+    };
+    let level = |format: &str| {
+        fidelity
+            .iter()
+            .find(|f| f.format == format)
+            .map(|f| f.level)
+            .unwrap_or(i64::MIN)
+    };
+    // Stable sort, representations with equal level keep provider order.
+    provider
+        .representations
+        .sort_by_key(|r| std::cmp::Reverse(level(r.format())));
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use crate::api_model::{DataProvider, DataRepresentation, RepresentationFidelity};
+
+    use super::{apply_declared_fidelity, format_fidelity};
+
+    #[test]
+    fn test_format_fidelity() {
+        assert!(format_fidelity("text/html") > format_fidelity("text/plain"));
+        assert!(format_fidelity("public.png") > format_fidelity("public.jpeg"));
+        assert_eq!(format_fidelity("text/plain;charset=utf-8"), 20);
+        assert_eq!(format_fidelity("HTML Format"), 80);
+        // CF_UNICODETEXT over CF_TEXT, CF_DIBV5 over CF_DIB
+        assert!(format_fidelity("NativeShell_CF_13") > format_fidelity("NativeShell_CF_1"));
+        assert!(format_fidelity("NativeShell_CF_17") > format_fidelity("NativeShell_CF_8"));
+        assert_eq!(format_fidelity("NativeShell_CF_x"), 0);
+        assert_eq!(format_fidelity("application/x-unknown"), 0);
+    }
+
+    #[test]
+    fn test_apply_declared_fidelity() {
+        let representation = |format: &str| DataRepresentation::Simple {
+            format: format.into(),
+            data: Value::Null,
+        };
+        let level = |format: &str, level| RepresentationFidelity {
+            format: format.into(),
+            level,
+        };
+        let mut provider = DataProvider {
+            representations: vec![
+                representation("image/jpeg"),
+                representation("text/plain"),
+                representation("image/png"),
+                representation("text/uri-list"),
+            ],
+            suggested_name: None,
+            fidelity: Some(vec![level("image/jpeg", 1), level("image/png", 2)]),
+            expires_after_ms: None,
+            source_url: None,
+            synthesize_rtf: None,
+            owner_isolate_id: None,
+        };
+        apply_declared_fidelity(&mut provider);
+        let formats: Vec<_> = provider
+            .representations
+            .iter()
+            .map(|r| r.format())
+            .collect();
+        assert_eq!(
+            formats,
+            ["image/png", "image/jpeg", "text/plain", "text/uri-list"]
+        );
+    }
+}
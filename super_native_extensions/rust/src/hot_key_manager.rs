@@ -1,31 +1,77 @@
 use std::{
     cell::{Cell, RefCell},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryInto,
     rc::Rc,
 };
 
 use irondash_message_channel::{
     IntoPlatformResult, IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler,
-    MethodInvoker, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
+    MethodInvoker, PlatformError, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
 };
+use irondash_run_loop::spawn;
 
 use crate::{
+    api_model::DesktopFeatureCapability,
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::PlatformHotKeyManager,
+    shutdown,
     util::NextId,
 };
 
-#[derive(TryFromValue, Debug, Clone)]
+/// Keys without stable platform code across keyboards. Each platform maps
+/// them in its `hot_key` module; keys the platform can not register are
+/// reported as unsupported.
+#[derive(TryFromValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum SpecialKey {
+    MediaPlayPause,
+    MediaStop,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    AudioVolumeUp,
+    AudioVolumeDown,
+    AudioVolumeMute,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+}
+
+#[derive(TryFromValue, Debug, Clone, PartialEq)]
 #[irondash(rename_all = "camelCase")]
 pub struct HotKeyCreateRequest {
     pub alt: bool,
     pub shift: bool,
     pub meta: bool,
     pub control: bool,
+    /// Ignored when `special_key` is set.
     pub platform_code: i64,
+    pub special_key: Option<SpecialKey>,
+    /// When the key is held, only the first press is reported (default).
+    /// Otherwise auto-repeated presses are reported as well, where the
+    /// platform delivers them.
+    pub suppress_repeat: Option<bool>,
+    /// Windows only: detect the hot key through low level keyboard hook
+    /// instead of `RegisterHotKey`. Hooked hot keys can be bound to keys
+    /// that can not be registered and report key up immediately.
+    pub low_level_hook: Option<bool>,
+}
+
+impl HotKeyCreateRequest {
+    pub fn suppress_repeat(&self) -> bool {
+        self.suppress_repeat.unwrap_or(true)
+    }
 }
 
 #[derive(TryFromValue, Debug)]
@@ -36,9 +82,65 @@ struct HotKeyDestroyRequest {
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, IntoValue, TryFromValue)]
 pub struct HotKeyHandle(i64);
 
+#[derive(TryFromValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct HotKeyProfileDefineRequest {
+    name: String,
+    hot_keys: Vec<HotKeyCreateRequest>,
+}
+
+#[derive(TryFromValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct HotKeyProfileActivateRequest {
+    name: String,
+    /// When set and any hot key of the profile fails to register, the
+    /// previously active profile is kept intact.
+    rollback_on_failure: bool,
+}
+
+#[derive(TryFromValue, Debug)]
+struct HotKeyProfileRequest {
+    name: String,
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct HotKeyProfileFailure {
+    /// Index of the hot key in profile definition.
+    index: i64,
+    message: String,
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct HotKeyProfileActivateResult {
+    /// Whether the profile became active.
+    activated: bool,
+    /// Handles corresponding to hot keys in profile definition; `None` for
+    /// hot keys that failed to register.
+    handles: Vec<Option<HotKeyHandle>>,
+    failures: Vec<HotKeyProfileFailure>,
+}
+
+struct ActiveHotKeyProfile {
+    name: String,
+    hot_keys: Vec<(HotKeyCreateRequest, HotKeyHandle)>,
+}
+
+#[derive(Default)]
+struct IsolateHotKeyProfiles {
+    profiles: HashMap<String, Vec<HotKeyCreateRequest>>,
+    active: Option<ActiveHotKeyProfile>,
+}
+
 pub struct HotKeyManager {
     invoker: Late<MethodInvoker>,
     handle_to_isolate: RefCell<HashMap<HotKeyHandle, IsolateId>>,
+    /// Hot keys that report auto-repeated presses.
+    repeating: RefCell<HashSet<HotKeyHandle>>,
+    /// Hot keys currently held down.
+    pressed: RefCell<HashSet<HotKeyHandle>>,
+    profiles: RefCell<HashMap<IsolateId, IsolateHotKeyProfiles>>,
     next_id: Cell<i64>,
     platform_manager: Late<Rc<PlatformHotKeyManager>>,
 }
@@ -63,6 +165,9 @@ impl HotKeyManager {
         Self {
             invoker: Late::new(),
             handle_to_isolate: RefCell::new(HashMap::new()),
+            repeating: RefCell::new(HashSet::new()),
+            pressed: RefCell::new(HashSet::new()),
+            profiles: RefCell::new(HashMap::new()),
             next_id: Cell::new(1),
             platform_manager: Late::new(),
         }
@@ -75,6 +180,7 @@ impl HotKeyManager {
         request: HotKeyCreateRequest,
     ) -> NativeExtensionsResult<Option<HotKeyHandle>> {
         let handle = HotKeyHandle(self.next_id.next_id());
+        let suppress_repeat = request.suppress_repeat();
         let res = self.platform_manager.create_hot_key(handle, request);
         if let Err(NativeExtensionsError::UnsupportedOperation) = res {
             return Ok(None);
@@ -83,12 +189,170 @@ impl HotKeyManager {
         self.handle_to_isolate
             .borrow_mut()
             .insert(handle, isolate_id);
+        if !suppress_repeat {
+            self.repeating.borrow_mut().insert(handle);
+        }
         Ok(Some(handle))
     }
 
+    /// System-wide hot keys would stay registered after the isolate is gone.
+    /// Part of [`shutdown::shutdown`].
+    pub fn shutdown(&self, isolate: IsolateId) {
+        let handles = self
+            .handle_to_isolate
+            .borrow()
+            .iter()
+            .filter_map(|(handle, id)| if *id == isolate { Some(*handle) } else { None })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            self.release_hot_key(handle).ok_log();
+        }
+    }
+
+    fn release_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<()> {
+        self.handle_to_isolate.borrow_mut().remove(&handle);
+        self.repeating.borrow_mut().remove(&handle);
+        self.pressed.borrow_mut().remove(&handle);
+        self.platform_manager.destroy_hot_key(handle)
+    }
+
     fn destroy_hot_key(&self, request: HotKeyDestroyRequest) -> NativeExtensionsResult<()> {
-        self.handle_to_isolate.borrow_mut().remove(&request.handle);
-        self.platform_manager.destroy_hot_key(request.handle)
+        self.release_hot_key(request.handle)
+    }
+
+    fn define_profile(&self, isolate_id: IsolateId, request: HotKeyProfileDefineRequest) {
+        self.profiles
+            .borrow_mut()
+            .entry(isolate_id)
+            .or_default()
+            .profiles
+            .insert(request.name, request.hot_keys);
+    }
+
+    /// Swaps active profile. Hot keys of the new profile are registered before
+    /// hot keys of the previous profile are released so that there is no
+    /// moment without bindings. Accelerators present in both profiles keep
+    /// their registration and handle.
+    fn activate_profile(
+        &self,
+        isolate_id: IsolateId,
+        request: HotKeyProfileActivateRequest,
+    ) -> NativeExtensionsResult<HotKeyProfileActivateResult> {
+        let (definition, previous) = {
+            let mut profiles = self.profiles.borrow_mut();
+            let profiles = profiles.entry(isolate_id).or_default();
+            let definition = profiles
+                .profiles
+                .get(&request.name)
+                .cloned()
+                .ok_or_else(|| {
+                    NativeExtensionsError::OtherError(format!(
+                        "Unknown hot key profile: {}",
+                        request.name
+                    ))
+                })?;
+            (definition, profiles.active.take())
+        };
+        let previous_name = previous.as_ref().map(|p| p.name.clone());
+        let mut retained = previous.map(|p| p.hot_keys).unwrap_or_default();
+
+        let mut hot_keys = Vec::new();
+        let mut created = Vec::new();
+        let mut handles = Vec::new();
+        let mut failures = Vec::new();
+        for (index, hot_key) in definition.into_iter().enumerate() {
+            if let Some(position) = retained.iter().position(|(r, _)| r == &hot_key) {
+                let (request, handle) = retained.swap_remove(position);
+                handles.push(Some(handle));
+                hot_keys.push((request, handle));
+                continue;
+            }
+            match self.create_hot_key(isolate_id, hot_key.clone()) {
+                Ok(Some(handle)) => {
+                    handles.push(Some(handle));
+                    created.push(handle);
+                    hot_keys.push((hot_key, handle));
+                }
+                Ok(None) => {
+                    handles.push(None);
+                    failures.push(HotKeyProfileFailure {
+                        index: index as i64,
+                        message: "Hot keys are not supported on this platform".into(),
+                    });
+                }
+                Err(error) => {
+                    handles.push(None);
+                    failures.push(HotKeyProfileFailure {
+                        index: index as i64,
+                        message: error.to_string(),
+                    });
+                }
+            }
+        }
+
+        let (activated, stale) = if !failures.is_empty() && request.rollback_on_failure {
+            // Restore previous profile. Hot keys shared by both profiles were
+            // moved to `hot_keys` and need to be moved back.
+            hot_keys.retain(|(_, handle)| !created.contains(handle));
+            retained.append(&mut hot_keys);
+            (false, created)
+        } else {
+            let stale = retained.iter().map(|(_, handle)| *handle).collect();
+            (true, stale)
+        };
+
+        for handle in stale {
+            self.release_hot_key(handle).ok_log();
+        }
+
+        let mut profiles = self.profiles.borrow_mut();
+        let profiles = profiles.entry(isolate_id).or_default();
+        if activated {
+            profiles.active = Some(ActiveHotKeyProfile {
+                name: request.name,
+                hot_keys,
+            });
+        } else if let Some(name) = previous_name {
+            profiles.active = Some(ActiveHotKeyProfile {
+                name,
+                hot_keys: retained,
+            });
+        }
+
+        Ok(HotKeyProfileActivateResult {
+            activated,
+            handles: if activated { handles } else { Vec::new() },
+            failures,
+        })
+    }
+
+    fn deactivate_active_profile(&self, isolate_id: IsolateId) {
+        let active = self
+            .profiles
+            .borrow_mut()
+            .get_mut(&isolate_id)
+            .and_then(|p| p.active.take());
+        if let Some(active) = active {
+            for (_, handle) in active.hot_keys {
+                self.release_hot_key(handle).ok_log();
+            }
+        }
+    }
+
+    fn remove_profile(&self, isolate_id: IsolateId, request: HotKeyProfileRequest) {
+        let is_active = self
+            .profiles
+            .borrow()
+            .get(&isolate_id)
+            .and_then(|p| p.active.as_ref())
+            .map(|active| active.name == request.name)
+            .unwrap_or(false);
+        if is_active {
+            self.deactivate_active_profile(isolate_id);
+        }
+        if let Some(profiles) = self.profiles.borrow_mut().get_mut(&isolate_id) {
+            profiles.profiles.remove(&request.name);
+        }
     }
 
     fn on_method_call(&self, call: MethodCall) -> PlatformResult {
@@ -99,14 +363,55 @@ impl HotKeyManager {
             "destroyHotKey" => self
                 .destroy_hot_key(call.args.try_into()?)
                 .into_platform_result(),
-            _ => Ok(Value::Null),
+            "defineHotKeyProfile" => {
+                self.define_profile(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            "activateHotKeyProfile" => self
+                .activate_profile(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "deactivateHotKeyProfile" => {
+                self.deactivate_active_profile(call.isolate);
+                Ok(Value::Null)
+            }
+            "removeHotKeyProfile" => {
+                self.remove_profile(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
         }
     }
 }
 
+#[cfg(target_os = "linux")]
+async fn hot_key_capability() -> DesktopFeatureCapability {
+    crate::platform_impl::platform::hot_key_capability().await
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+async fn hot_key_capability() -> DesktopFeatureCapability {
+    DesktopFeatureCapability::supported(crate::api_model::DesktopFeatureBackend::Native)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+async fn hot_key_capability() -> DesktopFeatureCapability {
+    DesktopFeatureCapability::unsupported("Hot keys are not supported on this platform")
+}
+
 impl MethodHandler for HotKeyManager {
     fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
-        reply.send(self.on_method_call(call))
+        if call.method == "getCapability" {
+            // May need to query the session bus on Linux.
+            spawn(async move {
+                reply.send(hot_key_capability().await.into_platform_result());
+            });
+        } else {
+            reply.send(self.on_method_call(call))
+        }
     }
 
     fn assign_invoker(&self, invoker: MethodInvoker) {
@@ -120,21 +425,19 @@ impl MethodHandler for HotKeyManager {
     }
 
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
-        let handles = self
-            .handle_to_isolate
-            .borrow()
-            .iter()
-            .filter_map(|(handle, id)| if *id == isolate { Some(*handle) } else { None })
-            .collect::<Vec<_>>();
-        for handle in handles {
-            self.handle_to_isolate.borrow_mut().remove(&handle);
-            self.platform_manager.destroy_hot_key(handle).ok_log();
-        }
+        shutdown::shutdown(isolate);
+        self.profiles.borrow_mut().remove(&isolate);
     }
 }
 
+/// Platforms report every key down of a held hot key (including
+/// auto-repeat); repeats are filtered here according to `suppressRepeat`.
 impl HotKeyManagerDelegate for HotKeyManager {
     fn on_hot_key_pressed(&self, handle: HotKeyHandle) {
+        let is_repeat = !self.pressed.borrow_mut().insert(handle);
+        if is_repeat && !self.repeating.borrow().contains(&handle) {
+            return;
+        }
         let handle_to_isolate = self.handle_to_isolate.borrow();
         let isolate = handle_to_isolate.get(&handle);
         if let Some(isolate) = isolate {
@@ -145,6 +448,9 @@ impl HotKeyManagerDelegate for HotKeyManager {
         }
     }
     fn on_hot_key_released(&self, handle: HotKeyHandle) {
+        if !self.pressed.borrow_mut().remove(&handle) {
+            return;
+        }
         let handle_to_isolate = self.handle_to_isolate.borrow();
         let isolate = handle_to_isolate.get(&handle);
         if let Some(isolate) = isolate {
@@ -0,0 +1,124 @@
+//! Transcoding of clipboard images (`image_transcode` feature).
+//!
+//! Reading: items with image in a format the platform codec can decode (such
+//! as JPEG or TIFF) but without PNG get the platform PNG format synthesized.
+//! This is in addition to the always enabled bitmap to PNG conversions of
+//! Windows and macOS readers.
+//!
+//! Writing: data providers with PNG representation additionally offer the
+//! formats preferred by native applications of the platform, unless the
+//! provider has them already. These are encoded when first requested.
+//!
+//! Encoding and decoding uses platform codecs, so the feature adds no
+//! dependencies.
+
+use std::{rc::Rc, thread};
+
+use async_trait::async_trait;
+use irondash_message_channel::Value;
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop,
+};
+
+use crate::{
+    api_model::{DataProvider, DataRepresentation},
+    data_provider_manager::SynthesizedValue,
+    error::NativeExtensionsResult,
+    format_converter::{FormatConverter, FormatConverters, FIRST_IMAGE_CONVERTER_ID},
+    platform_impl::platform::image_codec,
+};
+
+struct ImageTranscoder {
+    source_format: String,
+    png_format: String,
+}
+
+#[async_trait(?Send)]
+impl FormatConverter for ImageTranscoder {
+    fn source_format(&self) -> &str {
+        &self.source_format
+    }
+
+    fn target_format(&self) -> &str {
+        &self.png_format
+    }
+
+    async fn convert(&self, data: Value) -> NativeExtensionsResult<Value> {
+        let Value::U8List(data) = data else {
+            return Ok(Value::Null);
+        };
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let sender = RunLoop::current().new_sender();
+        // Decoding large images can take a while.
+        thread::spawn(move || {
+            let res = image_codec::decode_to_png(&data);
+            sender.send(move || {
+                let completer = completer.take().unwrap();
+                completer.complete(res);
+            });
+        });
+        Ok(future.await?.into())
+    }
+}
+
+thread_local! {
+    static IMAGE_CONVERTERS: Vec<(i64, Rc<dyn FormatConverter>)> = {
+        let png_format = image_codec::png_format();
+        image_codec::decodable_formats()
+            .into_iter()
+            .enumerate()
+            .map(|(index, source_format)| {
+                let converter: Rc<dyn FormatConverter> = Rc::new(ImageTranscoder {
+                    source_format,
+                    png_format: png_format.clone(),
+                });
+                (FIRST_IMAGE_CONVERTER_ID - index as i64, converter)
+            })
+            .collect()
+    };
+}
+
+/// Adds image converters after converters registered by Dart, so that those
+/// take precedence.
+pub fn register_image_converters(converters: &mut FormatConverters) {
+    IMAGE_CONVERTERS.with(|image_converters| {
+        for (id, converter) in image_converters {
+            converters.register(*id, converter.clone());
+        }
+    });
+}
+
+/// Platform preferred image formats missing from provider with PNG data,
+/// together with their encoders.
+pub fn transcoded_images(provider: &DataProvider) -> Vec<(String, SynthesizedValue)> {
+    let png_format = image_codec::png_format();
+    let png = provider.representations.iter().find_map(|r| match r {
+        DataRepresentation::Simple {
+            format,
+            data: Value::U8List(data),
+        } if *format == png_format => Some(data.clone()),
+        _ => None,
+    });
+    let Some(png) = png else {
+        return Vec::new();
+    };
+    let png = Rc::new(png);
+    image_codec::encodable_formats()
+        .into_iter()
+        .filter(|format| {
+            !provider
+                .representations
+                .iter()
+                .any(|r| r.format() == format.as_str())
+        })
+        .map(|format| {
+            let png = png.clone();
+            let target = format.clone();
+            let value: SynthesizedValue =
+                Rc::new(move || Ok(image_codec::encode_from_png(&png, &target)?.into()));
+            (format, value)
+        })
+        .collect()
+}
@@ -0,0 +1,749 @@
+//! Declarative import of dropped or pasted items.
+//!
+//! Instead of querying formats and then fetching data step by step, Dart
+//! describes acceptable results for an item in order of preference. Format
+//! selection, virtual file extraction and size enforcement then happen here in
+//! a single call, producing one normalized result per item.
+//!
+//! [`ImportPipeline::resolve_to_file`] is a fixed pipeline for applications
+//! that only work with files: every item ends up as a local file, whatever
+//! the source provided.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use async_trait::async_trait;
+use irondash_message_channel::{IntoValue, TryFromValue, Value};
+use url::Url;
+
+use crate::{
+    error::NativeExtensionsResult,
+    file_policy::FilePolicy,
+    format_fidelity::format_fidelity,
+    log::OkLog,
+    platform::PlatformDataReader,
+    reader_manager::{ReadProgressHandle, VirtualFileReader},
+    util::{get_target_path, sanitize_file_name},
+};
+
+#[derive(TryFromValue, Debug)]
+#[irondash(tag = "type", rename_all = "camelCase")]
+pub enum ImportTarget {
+    /// Item data in any of the formats (first available wins). Virtual files in
+    /// these formats are read into memory.
+    #[irondash(rename_all = "camelCase")]
+    Data {
+        formats: Vec<String>,
+        max_size: Option<i64>,
+    },
+    /// Path to a local file. Virtual files in `formats` are copied into
+    /// `target_folder`; otherwise file URI read from `file_uri_formats` is
+    /// resolved to local path.
+    #[irondash(rename_all = "camelCase")]
+    File {
+        formats: Vec<String>,
+        file_uri_formats: Vec<String>,
+        target_folder: String,
+        max_size: Option<i64>,
+    },
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct ImportResult {
+    /// Index of the target that was satisfied; None if no target matched.
+    pub target_index: Option<i64>,
+    /// Format of the returned data or file.
+    pub format: Option<String>,
+    /// Data for `Data` targets.
+    pub data: Value,
+    /// Local path for `File` targets.
+    pub path: Option<String>,
+    /// Whether any target was skipped because the content was too large.
+    pub size_exceeded: bool,
+}
+
+/// How the file returned by [`ImportPipeline::resolve_to_file`] was obtained.
+#[derive(IntoValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum FileSource {
+    /// Item referenced existing local file.
+    LocalFile,
+    /// Virtual file was extracted into target folder.
+    VirtualFile,
+    /// Item data was written into a file in target folder.
+    SpilledData,
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct ResolvedFile {
+    pub item_handle: i64,
+    /// None if item has no data that could be stored in a file.
+    pub path: Option<String>,
+    pub format: Option<String>,
+    pub source: Option<FileSource>,
+}
+
+/// File extension guessed from format (MIME type, UTI or Windows format
+/// name).
+fn extension_for_format(format: &str) -> String {
+    let format = format.split(';').next().unwrap_or(format).trim();
+    if format.to_ascii_lowercase().contains("plain") {
+        return "txt".into();
+    }
+    let extension = format.rsplit(['/', '.']).next().unwrap_or(format);
+    let extension = extension.strip_prefix("x-").unwrap_or(extension);
+    if !extension.is_empty()
+        && extension.len() <= 5
+        && extension.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        extension.to_ascii_lowercase()
+    } else {
+        "bin".into()
+    }
+}
+
+enum Outcome {
+    Imported {
+        format: Option<String>,
+        data: Value,
+        path: Option<PathBuf>,
+    },
+    SizeExceeded,
+    Unavailable,
+}
+
+fn exceeds(size: u64, max_size: Option<i64>) -> bool {
+    matches!(max_size, Some(max_size) if size > max_size.max(0) as u64)
+}
+
+fn value_size(value: &Value) -> u64 {
+    match value {
+        Value::String(string) => string.len() as u64,
+        Value::U8List(data) => data.len() as u64,
+        _ => 0,
+    }
+}
+
+/// Extracts local path from file URI or plain path value.
+pub fn path_from_value(value: Value) -> Option<PathBuf> {
+    let string = match value {
+        Value::String(string) => string,
+        Value::U8List(data) => String::from_utf8_lossy(&data).into_owned(),
+        _ => return None,
+    };
+    let string = string.trim_matches(char::from(0));
+    let string = string.lines().next()?.trim();
+    if string.starts_with("file:") {
+        Url::parse(string).ok_log()?.to_file_path().ok()
+    } else if Path::new(string).is_absolute() {
+        Some(string.into())
+    } else {
+        None
+    }
+}
+
+/// Reader operations the pipeline is built on.
+#[async_trait(?Send)]
+pub trait ImportSource {
+    async fn get_formats_for_item(&self, item: i64) -> NativeExtensionsResult<Vec<String>>;
+
+    async fn get_suggested_name_for_item(
+        &self,
+        item: i64,
+    ) -> NativeExtensionsResult<Option<String>>;
+
+    async fn get_item_format_for_uri(&self, item: i64) -> NativeExtensionsResult<Option<String>>;
+
+    async fn get_data_for_item(
+        &self,
+        item: i64,
+        format: String,
+        progress: Option<ReadProgressHandle>,
+    ) -> NativeExtensionsResult<Value>;
+
+    async fn can_read_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<bool>;
+
+    async fn create_virtual_file_reader_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>>;
+
+    async fn can_copy_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<bool>;
+
+    async fn copy_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        target_folder: PathBuf,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<PathBuf>;
+}
+
+#[async_trait(?Send)]
+impl ImportSource for PlatformDataReader {
+    async fn get_formats_for_item(&self, item: i64) -> NativeExtensionsResult<Vec<String>> {
+        PlatformDataReader::get_formats_for_item(self, item).await
+    }
+
+    async fn get_suggested_name_for_item(
+        &self,
+        item: i64,
+    ) -> NativeExtensionsResult<Option<String>> {
+        PlatformDataReader::get_suggested_name_for_item(self, item).await
+    }
+
+    async fn get_item_format_for_uri(&self, item: i64) -> NativeExtensionsResult<Option<String>> {
+        PlatformDataReader::get_item_format_for_uri(self, item).await
+    }
+
+    async fn get_data_for_item(
+        &self,
+        item: i64,
+        format: String,
+        progress: Option<ReadProgressHandle>,
+    ) -> NativeExtensionsResult<Value> {
+        PlatformDataReader::get_data_for_item(self, item, format, progress).await
+    }
+
+    async fn can_read_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<bool> {
+        PlatformDataReader::can_read_virtual_file_for_item(self, item, format).await
+    }
+
+    async fn create_virtual_file_reader_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        PlatformDataReader::create_virtual_file_reader_for_item(self, item, format, progress).await
+    }
+
+    async fn can_copy_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<bool> {
+        PlatformDataReader::can_copy_virtual_file_for_item(self, item, format).await
+    }
+
+    async fn copy_virtual_file_for_item(
+        &self,
+        item: i64,
+        format: &str,
+        target_folder: PathBuf,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<PathBuf> {
+        PlatformDataReader::copy_virtual_file_for_item(self, item, format, target_folder, progress)
+            .await
+    }
+}
+
+pub struct ImportPipeline<'a, S: ImportSource> {
+    reader: &'a S,
+    item: i64,
+    progress: ReadProgressHandle,
+    file_policy: Option<Rc<FilePolicy>>,
+}
+
+impl<'a, S: ImportSource> ImportPipeline<'a, S> {
+    pub fn new(reader: &'a S, item: i64, progress: ReadProgressHandle) -> Self {
+        Self {
+            reader,
+            item,
+            progress,
+            file_policy: None,
+        }
+    }
+
+    /// Policy checked before files are written to target folder.
+    pub fn with_file_policy(mut self, file_policy: Option<Rc<FilePolicy>>) -> Self {
+        self.file_policy = file_policy;
+        self
+    }
+
+    /// Fails if file about to be written is blocked by the file policy.
+    /// Without `file_name` the suggested name of the item is checked.
+    async fn check_file_policy(
+        &self,
+        format: &str,
+        file_name: Option<&str>,
+    ) -> NativeExtensionsResult<()> {
+        let Some(policy) = &self.file_policy else {
+            return Ok(());
+        };
+        match file_name {
+            Some(file_name) => policy.check(Some(file_name), Some(format)),
+            None => {
+                let name = self.reader.get_suggested_name_for_item(self.item).await?;
+                policy.check(name.as_deref(), Some(format))
+            }
+        }
+    }
+
+    pub async fn run(&self, targets: Vec<ImportTarget>) -> NativeExtensionsResult<ImportResult> {
+        let formats = self.reader.get_formats_for_item(self.item).await?;
+        let mut size_exceeded = false;
+        for (index, target) in targets.into_iter().enumerate() {
+            let outcome = match target {
+                ImportTarget::Data {
+                    formats: wanted,
+                    max_size,
+                } => self.import_data(&formats, &wanted, max_size).await?,
+                ImportTarget::File {
+                    formats: wanted,
+                    file_uri_formats,
+                    target_folder,
+                    max_size,
+                } => {
+                    self.import_file(
+                        &formats,
+                        &wanted,
+                        &file_uri_formats,
+                        target_folder.into(),
+                        max_size,
+                    )
+                    .await?
+                }
+            };
+            match outcome {
+                Outcome::Imported { format, data, path } => {
+                    return Ok(ImportResult {
+                        target_index: Some(index as i64),
+                        format,
+                        data,
+                        path: path.map(|p| p.to_string_lossy().into_owned()),
+                        size_exceeded,
+                    })
+                }
+                Outcome::SizeExceeded => size_exceeded = true,
+                Outcome::Unavailable => {}
+            }
+        }
+        Ok(ImportResult {
+            target_index: None,
+            format: None,
+            data: Value::Null,
+            path: None,
+            size_exceeded,
+        })
+    }
+
+    async fn import_data(
+        &self,
+        formats: &[String],
+        wanted: &[String],
+        max_size: Option<i64>,
+    ) -> NativeExtensionsResult<Outcome> {
+        for format in wanted.iter().filter(|f| formats.contains(f)) {
+            if self
+                .reader
+                .can_read_virtual_file_for_item(self.item, format)
+                .await?
+            {
+                match self.read_virtual_file(format, max_size).await? {
+                    Outcome::Unavailable => continue,
+                    outcome => return Ok(outcome),
+                }
+            }
+            let data = self
+                .reader
+                .get_data_for_item(self.item, format.clone(), Some(self.progress.clone()))
+                .await?;
+            if data == Value::Null {
+                continue;
+            }
+            if exceeds(value_size(&data), max_size) {
+                return Ok(Outcome::SizeExceeded);
+            }
+            return Ok(Outcome::Imported {
+                format: Some(format.clone()),
+                data,
+                path: None,
+            });
+        }
+        Ok(Outcome::Unavailable)
+    }
+
+    async fn read_virtual_file(
+        &self,
+        format: &str,
+        max_size: Option<i64>,
+    ) -> NativeExtensionsResult<Outcome> {
+        let reader = self
+            .reader
+            .create_virtual_file_reader_for_item(self.item, format, self.progress.clone())
+            .await?;
+        let Some(reader) = reader else {
+            return Ok(Outcome::Unavailable);
+        };
+        if let Some(file_size) = reader.file_size()? {
+            if exceeds(file_size as u64, max_size) {
+                reader.close().ok_log();
+                return Ok(Outcome::SizeExceeded);
+            }
+        }
+        let mut data = Vec::new();
+        loop {
+            let chunk = reader.read_next().await?;
+            if chunk.is_empty() {
+                break;
+            }
+            data.extend_from_slice(&chunk);
+            // File size might not be known upfront
+            if exceeds(data.len() as u64, max_size) {
+                reader.close().ok_log();
+                return Ok(Outcome::SizeExceeded);
+            }
+        }
+        reader.close()?;
+        Ok(Outcome::Imported {
+            format: Some(format.into()),
+            data: data.into(),
+            path: None,
+        })
+    }
+
+    async fn import_file(
+        &self,
+        formats: &[String],
+        wanted: &[String],
+        file_uri_formats: &[String],
+        target_folder: PathBuf,
+        max_size: Option<i64>,
+    ) -> NativeExtensionsResult<Outcome> {
+        for format in wanted.iter().filter(|f| formats.contains(f)) {
+            if !self
+                .reader
+                .can_copy_virtual_file_for_item(self.item, format)
+                .await?
+            {
+                continue;
+            }
+            self.check_file_policy(format, None).await?;
+            let path = self
+                .reader
+                .copy_virtual_file_for_item(
+                    self.item,
+                    format,
+                    target_folder.clone(),
+                    self.progress.clone(),
+                )
+                .await?;
+            if exceeds(fs::metadata(&path)?.len(), max_size) {
+                fs::remove_file(&path).ok_log();
+                return Ok(Outcome::SizeExceeded);
+            }
+            return Ok(Outcome::Imported {
+                format: Some(format.clone()),
+                data: Value::Null,
+                path: Some(path),
+            });
+        }
+        for uri_format in file_uri_formats.iter().filter(|f| formats.contains(f)) {
+            let value = self
+                .reader
+                .get_data_for_item(self.item, uri_format.clone(), None)
+                .await?;
+            let Some(path) = path_from_value(value) else {
+                continue;
+            };
+            let Some(metadata) = fs::metadata(&path).ok() else {
+                continue;
+            };
+            if exceeds(metadata.len(), max_size) {
+                return Ok(Outcome::SizeExceeded);
+            }
+            return Ok(Outcome::Imported {
+                format: self.reader.get_item_format_for_uri(self.item).await?,
+                data: Value::Null,
+                path: Some(path),
+            });
+        }
+        Ok(Outcome::Unavailable)
+    }
+
+    /// Resolves item to a local file, trying in order: file URI in one of
+    /// `file_uri_formats` pointing to existing file, virtual file extraction
+    /// and finally writing data of highest fidelity format to a file.
+    pub async fn resolve_to_file(
+        &self,
+        file_uri_formats: &[String],
+        target_folder: PathBuf,
+    ) -> NativeExtensionsResult<ResolvedFile> {
+        let formats = self.reader.get_formats_for_item(self.item).await?;
+        let resolved = |path: PathBuf, format: Option<String>, source| ResolvedFile {
+            item_handle: self.item,
+            path: Some(path.to_string_lossy().into_owned()),
+            format,
+            source: Some(source),
+        };
+        for uri_format in file_uri_formats.iter().filter(|f| formats.contains(f)) {
+            let value = self
+                .reader
+                .get_data_for_item(self.item, uri_format.clone(), None)
+                .await?;
+            if let Some(path) = path_from_value(value).filter(|p| p.is_file()) {
+                let format = self.reader.get_item_format_for_uri(self.item).await?;
+                return Ok(resolved(path, format, FileSource::LocalFile));
+            }
+        }
+        for format in &formats {
+            if self
+                .reader
+                .can_copy_virtual_file_for_item(self.item, format)
+                .await?
+            {
+                self.check_file_policy(format, None).await?;
+                let path = self
+                    .reader
+                    .copy_virtual_file_for_item(
+                        self.item,
+                        format,
+                        target_folder.clone(),
+                        self.progress.clone(),
+                    )
+                    .await?;
+                return Ok(resolved(
+                    path,
+                    Some(format.clone()),
+                    FileSource::VirtualFile,
+                ));
+            }
+        }
+        let mut candidates: Vec<_> = formats
+            .iter()
+            .filter(|f| !file_uri_formats.contains(f))
+            .collect();
+        candidates.sort_by_key(|f| -format_fidelity(f));
+        for format in candidates {
+            let data = self
+                .reader
+                .get_data_for_item(self.item, format.clone(), Some(self.progress.clone()))
+                .await?;
+            let data = match data {
+                Value::U8List(data) => data,
+                Value::String(string) => string.into_bytes(),
+                _ => continue,
+            };
+            let suggested_name = self.reader.get_suggested_name_for_item(self.item).await?;
+            let name = match suggested_name.as_deref().and_then(sanitize_file_name) {
+                Some(name) => name.to_owned(),
+                None => format!("Item {}.{}", self.item + 1, extension_for_format(format)),
+            };
+            self.check_file_policy(format, Some(&name)).await?;
+            fs::create_dir_all(&target_folder)?;
+            let path = get_target_path(&target_folder, &name);
+            fs::write(&path, data)?;
+            return Ok(resolved(
+                path,
+                Some(format.clone()),
+                FileSource::SpilledData,
+            ));
+        }
+        Ok(ResolvedFile {
+            item_handle: self.item,
+            path: None,
+            format: None,
+            source: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        future::Future,
+        path::PathBuf,
+        pin::pin,
+        rc::Rc,
+        sync::Arc,
+        task::{Context, Poll, Waker},
+    };
+
+    use async_trait::async_trait;
+    use irondash_message_channel::Value;
+
+    use crate::{
+        error::{NativeExtensionsError, NativeExtensionsResult},
+        reader_manager::{ReadProgressHandle, VirtualFileReader},
+        util::DropNotifier,
+    };
+
+    use super::{ImportPipeline, ImportResult, ImportSource, ImportTarget};
+
+    /// Single item with in-memory data and no virtual files.
+    struct TestSource {
+        formats: Vec<String>,
+        data: HashMap<String, Value>,
+    }
+
+    impl TestSource {
+        fn new(data: Vec<(&str, Value)>) -> Self {
+            Self {
+                formats: data.iter().map(|(format, _)| format.to_string()).collect(),
+                data: data
+                    .into_iter()
+                    .map(|(format, value)| (format.to_string(), value))
+                    .collect(),
+            }
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl ImportSource for TestSource {
+        async fn get_formats_for_item(&self, _item: i64) -> NativeExtensionsResult<Vec<String>> {
+            Ok(self.formats.clone())
+        }
+
+        async fn get_suggested_name_for_item(
+            &self,
+            _item: i64,
+        ) -> NativeExtensionsResult<Option<String>> {
+            Ok(None)
+        }
+
+        async fn get_item_format_for_uri(
+            &self,
+            _item: i64,
+        ) -> NativeExtensionsResult<Option<String>> {
+            Ok(None)
+        }
+
+        async fn get_data_for_item(
+            &self,
+            _item: i64,
+            format: String,
+            _progress: Option<ReadProgressHandle>,
+        ) -> NativeExtensionsResult<Value> {
+            Ok(self.data.get(&format).cloned().unwrap_or(Value::Null))
+        }
+
+        async fn can_read_virtual_file_for_item(
+            &self,
+            _item: i64,
+            _format: &str,
+        ) -> NativeExtensionsResult<bool> {
+            Ok(false)
+        }
+
+        async fn create_virtual_file_reader_for_item(
+            &self,
+            _item: i64,
+            _format: &str,
+            _progress: ReadProgressHandle,
+        ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+            Ok(None)
+        }
+
+        async fn can_copy_virtual_file_for_item(
+            &self,
+            _item: i64,
+            _format: &str,
+        ) -> NativeExtensionsResult<bool> {
+            Ok(false)
+        }
+
+        async fn copy_virtual_file_for_item(
+            &self,
+            _item: i64,
+            _format: &str,
+            _target_folder: PathBuf,
+            _progress: ReadProgressHandle,
+        ) -> NativeExtensionsResult<PathBuf> {
+            Err(NativeExtensionsError::VirtualFileUnsupported)
+        }
+    }
+
+    fn run(source: &TestSource, targets: Vec<ImportTarget>) -> ImportResult {
+        let progress = ReadProgressHandle::new(Arc::new(DropNotifier::new(|| {})), |_| {}, |_| {});
+        let pipeline = ImportPipeline::new(source, 0, progress);
+        // Test source never suspends.
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(pipeline.run(targets)).poll(&mut cx) {
+            Poll::Ready(res) => res.unwrap(),
+            Poll::Pending => panic!("import did not complete"),
+        }
+    }
+
+    fn data_target(formats: &[&str], max_size: Option<i64>) -> ImportTarget {
+        ImportTarget::Data {
+            formats: formats.iter().map(|f| f.to_string()).collect(),
+            max_size,
+        }
+    }
+
+    #[test]
+    fn test_target_selection() {
+        let source = TestSource::new(vec![
+            ("text/plain", Value::String("plain".into())),
+            ("text/html", Value::String("<b>html</b>".into())),
+            ("image/png", Value::Null),
+        ]);
+        let res = run(
+            &source,
+            vec![
+                // Not provided at all.
+                data_target(&["image/jpeg"], None),
+                // Listed but without data.
+                data_target(&["image/png"], None),
+                // Order of target formats wins over order of item formats.
+                data_target(&["text/html", "text/plain"], None),
+                data_target(&["text/plain"], None),
+            ],
+        );
+        assert_eq!(res.target_index, Some(2));
+        assert_eq!(res.format.as_deref(), Some("text/html"));
+        assert_eq!(res.data, Value::String("<b>html</b>".into()));
+        assert!(!res.size_exceeded);
+
+        let res = run(&source, vec![data_target(&["image/jpeg"], None)]);
+        assert_eq!(res.target_index, None);
+        assert_eq!(res.data, Value::Null);
+    }
+
+    #[test]
+    fn test_max_size() {
+        let source = TestSource::new(vec![
+            ("image/png", Value::U8List(vec![0; 100])),
+            ("text/plain", Value::String("hello".into())),
+        ]);
+        let res = run(
+            &source,
+            vec![
+                data_target(&["image/png"], Some(99)),
+                data_target(&["text/plain"], Some(5)),
+            ],
+        );
+        assert_eq!(res.target_index, Some(1));
+        assert_eq!(res.format.as_deref(), Some("text/plain"));
+        assert!(res.size_exceeded);
+
+        let res = run(&source, vec![data_target(&["image/png"], Some(100))]);
+        assert_eq!(res.target_index, Some(0));
+        assert!(!res.size_exceeded);
+
+        let res = run(&source, vec![data_target(&["text/plain"], Some(4))]);
+        assert_eq!(res.target_index, None);
+        assert!(res.size_exceeded);
+    }
+}
@@ -29,10 +29,107 @@ pub struct KeyboardLayout {
     pub keys: Vec<Key>,
 }
 
+/// Characters produced by a physical key, used by shortcut conflict tooling
+/// and on-screen keyboard overlays.
+#[derive(IntoValue, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct KeySnapshot {
+    pub platform: i64,
+    pub physical: i64,
+    pub logical: Option<i64>,
+    pub character: Option<String>,
+    pub shifted_character: Option<String>,
+    pub alt_gr_character: Option<String>,
+    pub alt_gr_shifted_character: Option<String>,
+}
+
+#[derive(IntoValue, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct KeyboardLayoutSnapshot {
+    pub keys: Vec<KeySnapshot>,
+}
+
+/// Physical key producing a logical key, used to render accelerator labels.
+#[derive(IntoValue, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct LogicalKeyMapping {
+    pub logical: i64,
+    pub physical: i64,
+    /// Whether the logical key requires shift.
+    pub shift: bool,
+}
+
+#[derive(IntoValue, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct CurrentKeyboardLayout {
+    /// Layout selected by the user: localized name on macOS, layout
+    /// identifier (KLID) on Windows and XKB layout on X11. Keys may be
+    /// resolved with a different, ASCII capable layout.
+    pub name: Option<String>,
+    pub keys: Vec<LogicalKeyMapping>,
+}
+
+impl CurrentKeyboardLayout {
+    fn new(name: Option<String>, layout: &KeyboardLayout) -> Self {
+        // Unshifted keys take precedence when logical key is produced by
+        // more than one physical key.
+        let unshifted = layout.keys.iter().map(|k| (k.logical, k.physical, false));
+        let shifted = layout
+            .keys
+            .iter()
+            .map(|k| (k.logical_shift, k.physical, true));
+        let mut seen = HashSet::new();
+        let keys = unshifted
+            .chain(shifted)
+            .filter_map(|(logical, physical, shift)| {
+                let logical = logical?;
+                seen.insert(logical).then_some(LogicalKeyMapping {
+                    logical,
+                    physical,
+                    shift,
+                })
+            })
+            .collect();
+        Self { name, keys }
+    }
+}
+
+/// Logical keys for printable characters are the unicode code point itself;
+/// anything above the unicode plane is a non printable key.
+fn logical_to_character(logical: Option<i64>) -> Option<String> {
+    let logical = u32::try_from(logical?).ok()?;
+    char::from_u32(logical)
+        .filter(|c| !c.is_control())
+        .map(String::from)
+}
+
+impl From<&KeyboardLayout> for KeyboardLayoutSnapshot {
+    fn from(layout: &KeyboardLayout) -> Self {
+        Self {
+            keys: layout
+                .keys
+                .iter()
+                .map(|key| KeySnapshot {
+                    platform: key.platform,
+                    physical: key.physical,
+                    logical: key.logical,
+                    character: logical_to_character(key.logical),
+                    shifted_character: logical_to_character(key.logical_shift),
+                    alt_gr_character: logical_to_character(key.logical_alt),
+                    alt_gr_shifted_character: logical_to_character(key.logical_alt_shift),
+                })
+                .collect(),
+        }
+    }
+}
+
 pub struct KeyboardLayoutManager {
     pub(crate) platform_layout: Late<Rc<PlatformKeyboardLayout>>,
     invoker: Late<MethodInvoker>,
     isolates: RefCell<HashSet<IsolateId>>,
+    /// Isolates that receive `keyboardLayoutChanged`, between
+    /// `startLayoutMonitoring` and `stopLayoutMonitoring`.
+    current_layout_isolates: RefCell<HashSet<IsolateId>>,
 }
 
 pub trait KeyboardLayoutDelegate {
@@ -55,9 +152,16 @@ impl KeyboardLayoutManager {
             platform_layout: Late::new(),
             invoker: Late::new(),
             isolates: RefCell::new(HashSet::new()),
+            current_layout_isolates: RefCell::new(HashSet::new()),
         }
         .register("KeyboardLayoutManager")
     }
+
+    fn current_layout(&self) -> Option<CurrentKeyboardLayout> {
+        let layout = self.platform_layout.get_current_layout()?;
+        let name = self.platform_layout.get_current_layout_name();
+        Some(CurrentKeyboardLayout::new(name, &layout))
+    }
 }
 
 impl MethodHandler for KeyboardLayoutManager {
@@ -69,6 +173,29 @@ impl MethodHandler for KeyboardLayoutManager {
                 let layout = self.platform_layout.get_current_layout();
                 reply.send_ok(layout);
             }
+            "getKeyboardLayoutSnapshot" => {
+                let snapshot = self
+                    .platform_layout
+                    .get_current_layout()
+                    .as_ref()
+                    .map(KeyboardLayoutSnapshot::from);
+                reply.send_ok(snapshot);
+            }
+            "getCurrentKeyboardLayout" => {
+                reply.send_ok(self.current_layout());
+            }
+            "startLayoutMonitoring" => {
+                self.current_layout_isolates
+                    .borrow_mut()
+                    .insert(call.isolate);
+                reply.send_ok(Value::Null);
+            }
+            "stopLayoutMonitoring" => {
+                self.current_layout_isolates
+                    .borrow_mut()
+                    .remove(&call.isolate);
+                reply.send_ok(Value::Null);
+            }
             _ => {}
         }
     }
@@ -88,6 +215,7 @@ impl MethodHandler for KeyboardLayoutManager {
     /// Called when isolate is about to be destroyed.
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
         self.isolates.borrow_mut().remove(&isolate);
+        self.current_layout_isolates.borrow_mut().remove(&isolate);
     }
 }
 
@@ -102,5 +230,16 @@ impl KeyboardLayoutDelegate for KeyboardLayoutManager {
                     r.ok_log();
                 });
         }
+        let current_layout: Value = self.current_layout().into();
+        for isolate in self.current_layout_isolates.borrow().iter() {
+            self.invoker.call_method(
+                *isolate,
+                "keyboardLayoutChanged",
+                current_layout.clone(),
+                |r| {
+                    r.ok_log();
+                },
+            );
+        }
     }
 }
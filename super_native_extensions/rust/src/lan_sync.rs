@@ -0,0 +1,713 @@
+//! Clipboard sharing between instances of the same application on a local
+//! network (`lan_sync` feature).
+//!
+//! Instances advertise themselves over mDNS as [`SERVICE_TYPE`] services,
+//! with application identifier and device name in TXT records, and discover
+//! peers of the same application the same way. Items published by one
+//! instance are pushed to every peer over a new TCP connection. Connections
+//! start with Noise `NNpsk0` handshake keyed by a secret shared out of band
+//! (pairing), so only instances that know the key can exchange data and the
+//! payload is encrypted. Received items are delivered to Dart, which makes
+//! them available through the regular clipboard.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    rc::{Rc, Weak},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use irondash_message_channel::{
+    IntoPlatformResult, IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler,
+    MethodInvoker, PlatformError, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
+};
+use irondash_run_loop::RunLoop;
+use log::warn;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rand::{distributions::Alphanumeric, Rng};
+use snow::{HandshakeState, TransportState};
+
+use crate::{
+    context::Context,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+};
+
+const SERVICE_TYPE: &str = "_sne-clipboard._tcp.local.";
+const NOISE_PARAMS: &str = "Noise_NNpsk0_25519_ChaChaPoly_BLAKE2s";
+const PROTOCOL_VERSION: u8 = 1;
+
+const KEY_LEN: usize = 32;
+const MAX_NOISE_MESSAGE: usize = 65535;
+const NOISE_TAG_LEN: usize = 16;
+/// Larger payloads are rejected by both sides.
+const MAX_PAYLOAD_SIZE: u64 = 64 * 1024 * 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(TryFromValue, IntoValue, Clone, Debug, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct LanRepresentation {
+    pub format: String,
+    /// Only strings and binary data are sent.
+    pub data: Value,
+}
+
+type LanItem = Vec<LanRepresentation>;
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct StartLanSyncRequest {
+    /// Only instances with the same identifier discover each other.
+    application_id: String,
+    /// Name shown to peers.
+    device_name: String,
+    /// 32 byte secret shared by paired instances.
+    key: Vec<u8>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct PublishRequest {
+    items: Vec<LanItem>,
+}
+
+#[derive(IntoValue, Clone, Debug, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+struct LanPeer {
+    /// mDNS instance name, unique on the network.
+    id: String,
+    name: String,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct LanClipboardReceived {
+    sender_name: String,
+    items: Vec<LanItem>,
+}
+
+#[derive(Clone)]
+struct PeerEntry {
+    name: String,
+    addresses: Vec<SocketAddr>,
+}
+
+/// State shared between main thread and network threads of one session.
+struct Shared {
+    application_id: String,
+    key: [u8; KEY_LEN],
+    stopped: AtomicBool,
+    peers: Mutex<HashMap<String, PeerEntry>>,
+}
+
+struct LanSession {
+    id: u64,
+    isolate_id: IsolateId,
+    device_name: String,
+    fullname: String,
+    daemon: ServiceDaemon,
+    listener_port: u16,
+    shared: Arc<Shared>,
+}
+
+impl Drop for LanSession {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        self.daemon.unregister(&self.fullname).ok_log();
+        // Also ends the browse thread by disconnecting its receiver.
+        self.daemon.shutdown().ok_log();
+        // Unblocks the accept loop, which then notices the stopped flag.
+        TcpStream::connect((Ipv4Addr::LOCALHOST, self.listener_port)).ok();
+    }
+}
+
+pub struct LanSyncManager {
+    invoker: Late<MethodInvoker>,
+    session: RefCell<Option<LanSession>>,
+    next_session_id: Cell<u64>,
+}
+
+pub trait GetLanSyncManager {
+    fn lan_sync_manager(&self) -> Rc<LanSyncManager>;
+}
+
+impl GetLanSyncManager for Context {
+    fn lan_sync_manager(&self) -> Rc<LanSyncManager> {
+        self.get_attachment(LanSyncManager::new).handler()
+    }
+}
+
+fn mdns_error(err: mdns_sd::Error) -> NativeExtensionsError {
+    NativeExtensionsError::OtherError(format!("mDNS: {err}"))
+}
+
+fn noise_error(err: snow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+impl LanSyncManager {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {
+            invoker: Late::new(),
+            session: RefCell::new(None),
+            next_session_id: Cell::new(1),
+        }
+        .register("LanSyncManager")
+    }
+
+    /// Replaces running session, if any.
+    fn start(
+        &self,
+        isolate_id: IsolateId,
+        request: StartLanSyncRequest,
+    ) -> NativeExtensionsResult<()> {
+        let key: [u8; KEY_LEN] = request.key.as_slice().try_into().map_err(|_| {
+            NativeExtensionsError::OtherError(format!("LAN sync key must be {KEY_LEN} bytes"))
+        })?;
+        self.session.replace(None);
+
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        let listener_port = listener.local_addr()?.port();
+        let instance_id: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        let properties = [
+            ("app", request.application_id.as_str()),
+            ("name", request.device_name.as_str()),
+        ];
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance_id,
+            &format!("{instance_id}.local."),
+            "",
+            listener_port,
+            &properties[..],
+        )
+        .map_err(mdns_error)?
+        .enable_addr_auto();
+        let fullname = info.get_fullname().to_owned();
+        let daemon = ServiceDaemon::new().map_err(mdns_error)?;
+        daemon.register(info).map_err(mdns_error)?;
+        let events = daemon.browse(SERVICE_TYPE).map_err(mdns_error)?;
+
+        let id = self.next_session_id.get();
+        self.next_session_id.set(id + 1);
+        let shared = Arc::new(Shared {
+            application_id: request.application_id,
+            key,
+            stopped: AtomicBool::new(false),
+            peers: Mutex::new(HashMap::new()),
+        });
+
+        let sender = RunLoop::current().new_sender();
+        let browse_shared = shared.clone();
+        let own_fullname = fullname.clone();
+        thread::spawn(move || {
+            while let Ok(event) = events.recv() {
+                if browse_shared.update_peers(event, &own_fullname) {
+                    sender.send(move || {
+                        Context::get().lan_sync_manager().peers_changed(id);
+                    });
+                }
+            }
+        });
+
+        let sender = RunLoop::current().new_sender();
+        let accept_shared = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_shared.stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let shared = accept_shared.clone();
+                let sender = sender.clone();
+                thread::spawn(move || match receive(stream, &shared.key) {
+                    Ok((sender_name, items)) => sender.send(move || {
+                        Context::get()
+                            .lan_sync_manager()
+                            .received(id, sender_name, items);
+                    }),
+                    Err(err) => warn!("Failed to receive LAN clipboard: {err}"),
+                });
+            }
+        });
+
+        self.session.replace(Some(LanSession {
+            id,
+            isolate_id,
+            device_name: request.device_name,
+            fullname,
+            daemon,
+            listener_port,
+            shared,
+        }));
+        Ok(())
+    }
+
+    fn stop(&self) -> NativeExtensionsResult<()> {
+        self.session.replace(None);
+        Ok(())
+    }
+
+    fn peers(&self) -> Vec<LanPeer> {
+        match self.session.borrow().as_ref() {
+            Some(session) => session.shared.peer_list(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Sends items to all peers known at this point; returns their count.
+    /// Delivery happens in background, failures are only logged.
+    fn publish(&self, request: PublishRequest) -> NativeExtensionsResult<i64> {
+        let session = self.session.borrow();
+        let session = session
+            .as_ref()
+            .ok_or_else(|| NativeExtensionsError::OtherError("LAN sync is not running".into()))?;
+        let payload = encode_payload(&session.device_name, &request.items);
+        if payload.len() as u64 > MAX_PAYLOAD_SIZE {
+            return Err(NativeExtensionsError::OtherError(
+                "LAN clipboard payload is too large".into(),
+            ));
+        }
+        let payload = Arc::new(payload);
+        let peers: Vec<_> = session
+            .shared
+            .peers
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        for peer in &peers {
+            let peer = peer.clone();
+            let payload = payload.clone();
+            let key = session.shared.key;
+            thread::spawn(move || {
+                if let Err(err) = send_to_peer(&peer, &key, &payload) {
+                    warn!("Failed to send LAN clipboard to {}: {err}", peer.name);
+                }
+            });
+        }
+        Ok(peers.len() as i64)
+    }
+
+    fn peers_changed(&self, session_id: u64) {
+        let session = self.session.borrow();
+        let Some(session) = session.as_ref().filter(|s| s.id == session_id) else {
+            return;
+        };
+        self.invoker.call_method(
+            session.isolate_id,
+            "lanPeersChanged",
+            session.shared.peer_list(),
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
+
+    fn received(&self, session_id: u64, sender_name: String, items: Vec<LanItem>) {
+        let session = self.session.borrow();
+        let Some(session) = session.as_ref().filter(|s| s.id == session_id) else {
+            return;
+        };
+        self.invoker.call_method(
+            session.isolate_id,
+            "lanClipboardReceived",
+            LanClipboardReceived { sender_name, items },
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "startLanSync" => self
+                .start(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "stopLanSync" => self.stop().into_platform_result(),
+            "getLanPeers" => Ok(self.peers().into()),
+            "publishLanClipboard" => self.publish(call.args.try_into()?).into_platform_result(),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+impl MethodHandler for LanSyncManager {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
+
+    fn assign_invoker(&self, invoker: MethodInvoker) {
+        self.invoker.set(invoker);
+    }
+
+    fn assign_weak_self(&self, _weak_self: Weak<Self>) {}
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        let owned = matches!(self.session.borrow().as_ref(), Some(s) if s.isolate_id == isolate);
+        if owned {
+            self.session.replace(None);
+        }
+    }
+}
+
+impl Shared {
+    /// Returns whether the peer list changed.
+    fn update_peers(&self, event: ServiceEvent, own_fullname: &str) -> bool {
+        let mut peers = self.peers.lock().unwrap();
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                let fullname = info.get_fullname();
+                if fullname == own_fullname
+                    || info.get_property_val_str("app") != Some(self.application_id.as_str())
+                {
+                    return false;
+                }
+                let peer = PeerEntry {
+                    name: info.get_property_val_str("name").unwrap_or("").to_owned(),
+                    addresses: info
+                        .get_addresses()
+                        .iter()
+                        .map(|ip: &IpAddr| SocketAddr::new(*ip, info.get_port()))
+                        .collect(),
+                };
+                let changed = peers
+                    .get(fullname)
+                    .map(|p| p.name != peer.name)
+                    .unwrap_or(true);
+                peers.insert(fullname.to_owned(), peer);
+                changed
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => peers.remove(&fullname).is_some(),
+            _ => false,
+        }
+    }
+
+    fn peer_list(&self) -> Vec<LanPeer> {
+        self.peers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, peer)| LanPeer {
+                id: id.clone(),
+                name: peer.name.clone(),
+            })
+            .collect()
+    }
+}
+
+fn write_frame(stream: &mut TcpStream, data: &[u8]) -> io::Result<()> {
+    stream.write_all(&(data.len() as u16).to_be_bytes())?;
+    stream.write_all(data)
+}
+
+fn read_frame<'a>(stream: &mut TcpStream, buf: &'a mut [u8]) -> io::Result<&'a [u8]> {
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len)?;
+    let len = u16::from_be_bytes(len) as usize;
+    stream.read_exact(&mut buf[..len])?;
+    Ok(&buf[..len])
+}
+
+fn noise_builder(key: &[u8; KEY_LEN]) -> io::Result<snow::Builder<'_>> {
+    let params = NOISE_PARAMS.parse().map_err(noise_error)?;
+    Ok(snow::Builder::new(params).psk(0, key))
+}
+
+struct EncryptedStream {
+    stream: TcpStream,
+    transport: TransportState,
+    buf: Vec<u8>,
+    message: Vec<u8>,
+}
+
+impl EncryptedStream {
+    fn new(stream: TcpStream, handshake: HandshakeState) -> io::Result<Self> {
+        Ok(Self {
+            stream,
+            transport: handshake.into_transport_mode().map_err(noise_error)?,
+            buf: vec![0u8; MAX_NOISE_MESSAGE],
+            message: vec![0u8; MAX_NOISE_MESSAGE],
+        })
+    }
+
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        for chunk in data.chunks(MAX_NOISE_MESSAGE - NOISE_TAG_LEN) {
+            let len = self
+                .transport
+                .write_message(chunk, &mut self.message)
+                .map_err(noise_error)?;
+            write_frame(&mut self.stream, &self.message[..len])?;
+        }
+        Ok(())
+    }
+
+    fn receive_exact(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut res = Vec::with_capacity(len);
+        while res.len() < len {
+            let frame = read_frame(&mut self.stream, &mut self.message)?;
+            let read = self
+                .transport
+                .read_message(frame, &mut self.buf)
+                .map_err(noise_error)?;
+            res.extend_from_slice(&self.buf[..read]);
+        }
+        if res.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected message length",
+            ));
+        }
+        Ok(res)
+    }
+}
+
+/// Connects to the first reachable address of the peer and sends payload.
+fn send_to_peer(peer: &PeerEntry, key: &[u8; KEY_LEN], payload: &[u8]) -> io::Result<()> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "peer has no address");
+    for address in &peer.addresses {
+        match TcpStream::connect_timeout(address, CONNECT_TIMEOUT) {
+            Ok(stream) => return send(stream, key, payload),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+fn send(mut stream: TcpStream, key: &[u8; KEY_LEN], payload: &[u8]) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut handshake = noise_builder(key)?.build_initiator().map_err(noise_error)?;
+    let mut message = vec![0u8; MAX_NOISE_MESSAGE];
+    let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+    let len = handshake
+        .write_message(&[], &mut message)
+        .map_err(noise_error)?;
+    write_frame(&mut stream, &message[..len])?;
+    let frame = read_frame(&mut stream, &mut message)?;
+    handshake
+        .read_message(frame, &mut buf)
+        .map_err(noise_error)?;
+    let mut stream = EncryptedStream::new(stream, handshake)?;
+    stream.send(&(payload.len() as u64).to_le_bytes())?;
+    stream.send(payload)
+}
+
+/// Fails the handshake unless the peer uses the same key.
+fn receive(mut stream: TcpStream, key: &[u8; KEY_LEN]) -> io::Result<(String, Vec<LanItem>)> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut handshake = noise_builder(key)?.build_responder().map_err(noise_error)?;
+    let mut message = vec![0u8; MAX_NOISE_MESSAGE];
+    let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+    let frame = read_frame(&mut stream, &mut message)?;
+    handshake
+        .read_message(frame, &mut buf)
+        .map_err(noise_error)?;
+    let len = handshake
+        .write_message(&[], &mut message)
+        .map_err(noise_error)?;
+    write_frame(&mut stream, &message[..len])?;
+    let mut stream = EncryptedStream::new(stream, handshake)?;
+    let len = stream.receive_exact(8)?;
+    let len = u64::from_le_bytes(len.try_into().unwrap());
+    if len > MAX_PAYLOAD_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "payload is too large",
+        ));
+    }
+    let payload = stream.receive_exact(len as usize)?;
+    decode_payload(&payload)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed payload"))
+}
+
+const KIND_BINARY: u8 = 0;
+const KIND_STRING: u8 = 1;
+
+fn put_bytes(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// Representations with data other than string or binary are skipped.
+fn encode_payload(sender_name: &str, items: &[LanItem]) -> Vec<u8> {
+    let mut out = vec![PROTOCOL_VERSION];
+    put_bytes(&mut out, sender_name.as_bytes());
+    out.extend_from_slice(&(items.len() as u32).to_le_bytes());
+    for item in items {
+        let representations: Vec<_> = item
+            .iter()
+            .filter_map(|r| match &r.data {
+                Value::String(s) => Some((r, KIND_STRING, s.as_bytes())),
+                Value::U8List(data) => Some((r, KIND_BINARY, data.as_slice())),
+                _ => None,
+            })
+            .collect();
+        out.extend_from_slice(&(representations.len() as u32).to_le_bytes());
+        for (representation, kind, data) in representations {
+            put_bytes(&mut out, representation.format.as_bytes());
+            out.push(kind);
+            put_bytes(&mut out, data);
+        }
+    }
+    out
+}
+
+struct PayloadReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PayloadReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (res, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(res)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?.to_vec()).ok()
+    }
+}
+
+fn decode_payload(data: &[u8]) -> Option<(String, Vec<LanItem>)> {
+    let mut reader = PayloadReader { data };
+    if reader.u8()? != PROTOCOL_VERSION {
+        return None;
+    }
+    let sender_name = reader.string()?;
+    // Counts are not trusted for preallocation.
+    let mut items = Vec::new();
+    for _ in 0..reader.u32()? {
+        let mut item = Vec::new();
+        for _ in 0..reader.u32()? {
+            let format = reader.string()?;
+            let data = match reader.u8()? {
+                KIND_STRING => Value::String(reader.string()?),
+                KIND_BINARY => Value::U8List(reader.bytes()?.to_vec()),
+                _ => return None,
+            };
+            item.push(LanRepresentation { format, data });
+        }
+        items.push(item);
+    }
+    if !reader.data.is_empty() {
+        return None;
+    }
+    Some((sender_name, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, TcpListener, TcpStream},
+        thread,
+    };
+
+    use irondash_message_channel::Value;
+
+    use super::{decode_payload, encode_payload, receive, send, LanRepresentation};
+
+    fn items() -> Vec<Vec<LanRepresentation>> {
+        vec![
+            vec![
+                LanRepresentation {
+                    format: "text/plain".into(),
+                    data: Value::String("Hello".into()),
+                },
+                LanRepresentation {
+                    format: "image/png".into(),
+                    data: Value::U8List(vec![1, 2, 3]),
+                },
+            ],
+            vec![],
+        ]
+    }
+
+    #[test]
+    fn test_payload() {
+        let payload = encode_payload("Laptop", &items());
+        assert_eq!(
+            decode_payload(&payload),
+            Some(("Laptop".to_owned(), items()))
+        );
+        assert_eq!(decode_payload(&payload[..payload.len() - 1]), None);
+
+        let skipped = vec![vec![LanRepresentation {
+            format: "number".into(),
+            data: Value::I64(1),
+        }]];
+        let payload = encode_payload("Laptop", &skipped);
+        assert_eq!(
+            decode_payload(&payload),
+            Some(("Laptop".to_owned(), vec![vec![]]))
+        );
+    }
+
+    #[test]
+    fn test_transport() {
+        let key = [7u8; 32];
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        // Payload spanning multiple Noise messages.
+        let payload = vec![42u8; 200_000];
+        let expected = payload.clone();
+        let receiver = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let (stream_b, _) = listener.accept().unwrap();
+            (receive(stream, &key), receive(stream_b, &[8u8; 32]))
+        });
+        let payload = encode_payload(
+            "Desktop",
+            &[vec![LanRepresentation {
+                format: "data".into(),
+                data: Value::U8List(payload),
+            }]],
+        );
+        send(TcpStream::connect(address).unwrap(), &key, &payload).unwrap();
+        // Mismatched key fails the handshake.
+        send(TcpStream::connect(address).unwrap(), &key, &payload).ok();
+        let (received, mismatched) = receiver.join().unwrap();
+        let (sender_name, items) = received.unwrap();
+        assert_eq!(sender_name, "Desktop");
+        assert_eq!(items[0][0].data, Value::U8List(expected));
+        assert!(mismatched.is_err());
+    }
+}
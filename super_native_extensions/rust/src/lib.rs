@@ -11,37 +11,85 @@
 use std::ffi::c_void;
 
 use ::log::debug;
+use clipboard_monitor::GetClipboardMonitor;
 use clipboard_reader::GetClipboardReader;
 use clipboard_writer::GetClipboardWriter;
 use context::Context;
 use data_provider_manager::GetDataProviderManager;
 use drag_manager::GetDragManager;
+use drag_monitor::GetDragMonitor;
 use drop_manager::GetDropManager;
 use hot_key_manager::GetHotKeyManager;
 use keyboard_layout_manager::GetKeyboardLayoutDelegate;
+use local_transfer::GetLocalTransferManager;
+use log_handler::GetLogHandler;
 use menu_manager::GetMenuManager;
 
 use irondash_message_channel::{irondash_init_message_channel_context, FunctionResult};
 use reader_manager::GetDataReaderManager;
+use resource_sweeper::GetResourceSweeper;
+use services_manager::GetServicesManager;
+use tray_icon_manager::GetTrayIconManager;
 
 mod api_model;
+mod archive;
+mod auto_scroll;
+mod binary_protocol;
 mod blur;
+mod cancellation;
+mod cf_html;
+mod clipboard_monitor;
 mod clipboard_reader;
 mod clipboard_writer;
 mod context;
 mod data_provider_manager;
 mod drag_manager;
+mod drag_monitor;
+mod drop_analytics;
 mod drop_manager;
+mod drop_manifest;
+mod drop_regions;
 mod error;
+mod file_policy;
+mod file_uri_list;
+mod format_converter;
+mod format_fidelity;
 mod hot_key_manager;
+#[cfg(feature = "image_transcode")]
+mod image_transcode;
+mod import_pipeline;
 mod keyboard_layout_manager;
+#[cfg(feature = "lan_sync")]
+mod lan_sync;
+mod link_detection;
+mod local_transfer;
 mod log;
+mod log_handler;
+mod managed_directory;
+mod media_info;
 mod menu_manager;
+mod read_cache;
 mod reader_manager;
+mod reader_snapshot;
+mod resource_sweeper;
+mod rich_text;
+mod rtf_html;
+mod services_manager;
 mod shadow;
+mod shared_buffer;
+mod shared_texture;
+mod shutdown;
+mod source_url;
+mod task_scope;
+#[cfg(feature = "test_injection")]
+mod test_injection;
+mod throttle;
+mod transform_rules;
+mod tray_icon_manager;
 mod util;
 mod value_coerce;
 mod value_promise;
+mod web_archive;
 
 #[allow(dead_code)]
 mod segmented_queue;
@@ -85,15 +133,32 @@ impl DataTransferPlugin {
     fn new() -> Self {
         let context = Context::new();
         // eagerly initialize
+        context.log_handler();
         context.data_provider_manager();
         context.data_reader_manager();
         context.clipboard_writer();
         context.clipboard_reader();
+        context.clipboard_monitor();
         context.drag_manager();
+        context.drag_monitor();
         context.drop_manager();
         context.keyboard_map_manager();
         context.hot_key_manager();
+        context.tray_icon_manager();
         context.menu_manager();
+        context.local_transfer_manager();
+        context.resource_sweeper();
+        context.services_manager();
+        #[cfg(feature = "test_injection")]
+        {
+            use test_injection::GetTestInjection;
+            context.test_injection();
+        }
+        #[cfg(feature = "lan_sync")]
+        {
+            use lan_sync::GetLanSyncManager;
+            context.lan_sync_manager();
+        }
         DataTransferPlugin { _context: context }
     }
 }
@@ -106,14 +171,20 @@ fn init(init_loger: bool) {
     if init_loger {
         #[cfg(not(target_os = "ios"))]
         {
-            simple_logger::init_with_level(::log::Level::Info).ok();
+            crate::log::init_logger(
+                Box::new(simple_logger::SimpleLogger::new().with_level(::log::LevelFilter::Info)),
+                ::log::LevelFilter::Info,
+            );
         }
         #[cfg(target_os = "ios")]
         {
-            oslog::OsLogger::new("supernativeextensions")
-                .level_filter(::log::LevelFilter::Debug)
-                .init()
-                .ok();
+            crate::log::init_logger(
+                Box::new(
+                    oslog::OsLogger::new("supernativeextensions")
+                        .level_filter(::log::LevelFilter::Debug),
+                ),
+                ::log::LevelFilter::Debug,
+            );
         }
     }
     // Lazily initialize the thread local
@@ -161,10 +232,13 @@ mod android {
         // Without this clipboard access may deadlock.
         RunLoop::set_main_thread();
 
-        android_logger::init_once(
-            Config::default()
-                .with_min_level(Level::Info)
-                .with_tag("flutter"),
+        crate::log::init_logger(
+            Box::new(android_logger::AndroidLogger::new(
+                Config::default()
+                    .with_min_level(Level::Info)
+                    .with_tag("flutter"),
+            )),
+            Level::Info.to_level_filter(),
         );
         JAVA_VM.get_or_init(|| {
             env.get_java_vm()
@@ -0,0 +1,368 @@
+//! Detection of links and other entities in text read from readers.
+//!
+//! Platform detectors are used where available (`NSDataDetector` on macOS
+//! and iOS, `Linkify` on Android); other platforms use the built-in
+//! detector in this module, which recognizes URLs, email addresses, phone
+//! numbers and ISO 8601 dates. Entity ranges are in UTF-16 code units so that
+//! they can be used directly with Dart strings.
+
+use irondash_message_channel::{IntoValue, TryFromValue};
+
+use crate::{log::OkLog, platform::PlatformDataReader};
+
+#[derive(TryFromValue, IntoValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum EntityKind {
+    Url,
+    Email,
+    PhoneNumber,
+    Date,
+    Address,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 5] = [
+        EntityKind::Url,
+        EntityKind::Email,
+        EntityKind::PhoneNumber,
+        EntityKind::Date,
+        EntityKind::Address,
+    ];
+}
+
+#[derive(IntoValue, Debug, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub struct DetectedEntity {
+    pub kind: EntityKind,
+    /// Start of the entity in UTF-16 code units.
+    pub start: i64,
+    /// End (exclusive) of the entity in UTF-16 code units.
+    pub end: i64,
+    /// Normalized value: absolute URL for links (`mailto:` and `tel:` for
+    /// email addresses and phone numbers), `YYYY-MM-DD` for dates.
+    pub value: Option<String>,
+}
+
+/// Detects entities of given kinds, preferring platform detector.
+pub fn detect_entities(text: &str, kinds: &[EntityKind]) -> Vec<DetectedEntity> {
+    let mut res = match PlatformDataReader::detect_entities(text, kinds).ok_log() {
+        Some(Some(entities)) => entities,
+        _ => detect_entities_fallback(text, kinds),
+    };
+    res.retain(|e| kinds.contains(&e.kind));
+    res.sort_by_key(|e| e.start);
+    res
+}
+
+struct Candidate {
+    kind: EntityKind,
+    /// Byte range.
+    start: usize,
+    end: usize,
+    value: String,
+}
+
+/// Built-in detector. Addresses are not recognized.
+pub fn detect_entities_fallback(text: &str, kinds: &[EntityKind]) -> Vec<DetectedEntity> {
+    let mut candidates = Vec::new();
+    if kinds.contains(&EntityKind::Url) || kinds.contains(&EntityKind::Email) {
+        for (start, end) in tokens(text) {
+            let token = &text[start..end];
+            if let Some(candidate) = url_candidate(token, start) {
+                candidates.push(candidate);
+            } else if let Some(candidate) = email_candidate(token, start) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    if kinds.contains(&EntityKind::Date) {
+        candidates.extend(date_candidates(text));
+    }
+    if kinds.contains(&EntityKind::PhoneNumber) {
+        candidates.extend(phone_candidates(text));
+    }
+    candidates.retain(|c| kinds.contains(&c.kind));
+    // Earlier kinds win on overlap (URL containing digits, date looking
+    // like phone number).
+    let mut accepted: Vec<Candidate> = Vec::new();
+    for candidate in candidates {
+        if !accepted
+            .iter()
+            .any(|a| a.start < candidate.end && candidate.start < a.end)
+        {
+            accepted.push(candidate);
+        }
+    }
+    accepted.sort_by_key(|c| c.start);
+    accepted
+        .into_iter()
+        .map(|c| DetectedEntity {
+            kind: c.kind,
+            start: utf16_offset(text, c.start),
+            end: utf16_offset(text, c.end),
+            value: Some(c.value),
+        })
+        .collect()
+}
+
+/// Formats seconds since Unix epoch as `YYYY-MM-DD` (UTC).
+pub fn format_date(seconds_since_epoch: i64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = seconds_since_epoch.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn utf16_offset(text: &str, byte_offset: usize) -> i64 {
+    text[..byte_offset].encode_utf16().count() as i64
+}
+
+/// Whitespace separated tokens with surrounding punctuation removed.
+fn tokens(text: &str) -> Vec<(usize, usize)> {
+    let mut res = Vec::new();
+    let mut push = |start: usize, end: usize| {
+        let token = &text[start..end];
+        let trimmed_start = token.trim_start_matches(['(', '<', '[', '"', '\'']);
+        let start = start + (token.len() - trimmed_start.len());
+        let trimmed = trim_trailing_punctuation(trimmed_start);
+        if !trimmed.is_empty() {
+            res.push((start, start + trimmed.len()));
+        }
+    };
+    let mut token_start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_whitespace(), token_start) {
+            (true, Some(start)) => {
+                push(start, index);
+                token_start = None;
+            }
+            (false, None) => token_start = Some(index),
+            _ => {}
+        }
+    }
+    if let Some(start) = token_start {
+        push(start, text.len());
+    }
+    res
+}
+
+fn trim_trailing_punctuation(token: &str) -> &str {
+    let mut token = token;
+    loop {
+        let trimmed = token.trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'', '>', ']']);
+        // Keep closing parenthesis if it is balanced inside the token
+        // (https://en.wikipedia.org/wiki/Rust_(programming_language)).
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(stripped) if trimmed.matches('(').count() < trimmed.matches(')').count() => {
+                stripped
+            }
+            _ => trimmed,
+        };
+        if trimmed.len() == token.len() {
+            return token;
+        }
+        token = trimmed;
+    }
+}
+
+fn url_candidate(token: &str, start: usize) -> Option<Candidate> {
+    let lower = token.to_ascii_lowercase();
+    let value = if ["http://", "https://", "ftp://"]
+        .iter()
+        .any(|p| lower.starts_with(p) && lower.len() > p.len())
+    {
+        token.to_owned()
+    } else if lower.starts_with("www.") && token[4..].contains('.') {
+        format!("http://{token}")
+    } else {
+        return None;
+    };
+    Some(Candidate {
+        kind: EntityKind::Url,
+        start,
+        end: start + token.len(),
+        value,
+    })
+}
+
+fn email_candidate(token: &str, start: usize) -> Option<Candidate> {
+    let (token, start) = match token.strip_prefix("mailto:") {
+        Some(token) => (token, start + "mailto:".len()),
+        None => (token, start),
+    };
+    let (local, domain) = token.split_once('@')?;
+    let local_valid = !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || "._%+-".contains(c));
+    let labels: Vec<_> = domain.split('.').collect();
+    let domain_valid = labels.len() >= 2
+        && labels
+            .iter()
+            .all(|l| !l.is_empty() && l.chars().all(|c| c.is_alphanumeric() || c == '-'))
+        && labels.last().map(|l| l.len() >= 2) == Some(true);
+    if !local_valid || !domain_valid {
+        return None;
+    }
+    Some(Candidate {
+        kind: EntityKind::Email,
+        start,
+        end: start + token.len(),
+        value: format!("mailto:{token}"),
+    })
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.map(|c| c.is_alphanumeric()).unwrap_or(false)
+}
+
+fn date_candidates(text: &str) -> Vec<Candidate> {
+    let bytes = text.as_bytes();
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i + 10 <= bytes.len() {
+        let candidate = &bytes[i..i + 10];
+        let digits_at = |range: std::ops::Range<usize>| {
+            range.into_iter().all(|j| candidate[j].is_ascii_digit())
+        };
+        if digits_at(0..4)
+            && candidate[4] == b'-'
+            && digits_at(5..7)
+            && candidate[7] == b'-'
+            && digits_at(8..10)
+            && text.is_char_boundary(i)
+            && !is_word_char(text[..i].chars().next_back())
+            && !is_word_char(text[i + 10..].chars().next())
+        {
+            let date = &text[i..i + 10];
+            let month: u32 = date[5..7].parse().unwrap_or(0);
+            let day: u32 = date[8..10].parse().unwrap_or(0);
+            if (1..=12).contains(&month) && (1..=31).contains(&day) {
+                res.push(Candidate {
+                    kind: EntityKind::Date,
+                    start: i,
+                    end: i + 10,
+                    value: date.to_owned(),
+                });
+                i += 10;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    res
+}
+
+fn phone_candidates(text: &str) -> Vec<Candidate> {
+    let is_separator = |c: char| matches!(c, ' ' | '-' | '.' | '(' | ')');
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, first) = chars[i];
+        let previous = i.checked_sub(1).map(|p| chars[p].1);
+        if !(first == '+' || first == '(' || first.is_ascii_digit()) || is_word_char(previous) {
+            i += 1;
+            continue;
+        }
+        let mut digits = String::new();
+        let mut has_separator = false;
+        let mut last_digit = None;
+        let mut j = if first == '+' { i + 1 } else { i };
+        while let Some(&(_, c)) = chars.get(j) {
+            if c.is_ascii_digit() {
+                digits.push(c);
+                last_digit = Some(j);
+            } else if is_separator(c) {
+                let previous = chars[j - 1].1;
+                // Consecutive separators end the number unless parenthesis is
+                // involved ("(555) 123").
+                if j > i && is_separator(previous) && c != '(' && previous != ')' {
+                    break;
+                }
+                has_separator = true;
+            } else {
+                break;
+            }
+            j += 1;
+        }
+        let Some(last_digit) = last_digit else {
+            i += 1;
+            continue;
+        };
+        let international = first == '+';
+        let next = chars.get(last_digit + 1).map(|c| c.1);
+        if (7..=15).contains(&digits.len())
+            && (international || has_separator || digits.len() >= 10)
+            && !is_word_char(next)
+        {
+            let prefix = if international { "+" } else { "" };
+            res.push(Candidate {
+                kind: EntityKind::PhoneNumber,
+                start,
+                end: chars[last_digit].0 + 1,
+                value: format!("tel:{prefix}{digits}"),
+            });
+        }
+        i = last_digit + 1;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_entities_fallback, EntityKind};
+
+    #[test]
+    fn test_detect_entities() {
+        let text = "Visit https://example.com/a_(b). or www.rust-lang.org, \
+                    mail me@example.org, call +1 (555) 123-4567 on 2024-03-09 ✓ 🙂 x.com";
+        let entities = detect_entities_fallback(text, &EntityKind::ALL);
+        let found: Vec<_> = entities
+            .iter()
+            .map(|e| {
+                let units: Vec<u16> = text.encode_utf16().collect();
+                let slice = String::from_utf16(&units[e.start as usize..e.end as usize]).unwrap();
+                (e.kind, slice, e.value.clone().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    EntityKind::Url,
+                    "https://example.com/a_(b)".to_owned(),
+                    "https://example.com/a_(b)".to_owned()
+                ),
+                (
+                    EntityKind::Url,
+                    "www.rust-lang.org".to_owned(),
+                    "http://www.rust-lang.org".to_owned()
+                ),
+                (
+                    EntityKind::Email,
+                    "me@example.org".to_owned(),
+                    "mailto:me@example.org".to_owned()
+                ),
+                (
+                    EntityKind::PhoneNumber,
+                    "+1 (555) 123-4567".to_owned(),
+                    "tel:+15551234567".to_owned()
+                ),
+                (
+                    EntityKind::Date,
+                    "2024-03-09".to_owned(),
+                    "2024-03-09".to_owned()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use gdk::{glib::SignalHandlerId, prelude::ObjectExt, Display};
+use gtk::Clipboard;
+use irondash_message_channel::Late;
+
+use crate::{
+    clipboard_monitor::ClipboardMonitorDelegate,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    task_scope::TaskScope,
+};
+
+use super::data_control::DataControl;
+
+enum Connection {
+    Gtk(Clipboard, SignalHandlerId),
+    /// Used on Wayland when the compositor supports data control; GTK
+    /// doesn't report changes while the application is not focused.
+    DataControl(Rc<DataControl>),
+}
+
+pub struct PlatformClipboardMonitor {
+    delegate: Weak<dyn ClipboardMonitorDelegate>,
+    connection: RefCell<Option<Connection>>,
+    weak_self: Late<Weak<Self>>,
+    tasks: TaskScope,
+}
+
+impl PlatformClipboardMonitor {
+    pub fn new(delegate: Weak<dyn ClipboardMonitorDelegate>) -> Self {
+        Self {
+            delegate,
+            connection: RefCell::new(None),
+            weak_self: Late::new(),
+            tasks: TaskScope::new("PlatformClipboardMonitor"),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformClipboardMonitor>) {
+        self.weak_self.set(weak);
+    }
+
+    fn clipboard_changed(weak_self: &Weak<Self>) {
+        if let Some(delegate) = weak_self.upgrade().and_then(|s| s.delegate.upgrade()) {
+            delegate.clipboard_changed();
+        }
+    }
+
+    fn use_data_control(&self, data_control: Rc<DataControl>) {
+        let weak_self = self.weak_self.clone();
+        data_control.set_selection_listener(Some(Rc::new(move || {
+            Self::clipboard_changed(&weak_self);
+        })));
+        let previous = self
+            .connection
+            .replace(Some(Connection::DataControl(data_control)));
+        if let Some(Connection::Gtk(clipboard, handler)) = previous {
+            clipboard.disconnect(handler);
+        }
+    }
+
+    pub fn start(&self) -> NativeExtensionsResult<()> {
+        if self.connection.borrow().is_some() {
+            return Ok(());
+        }
+        if let Some(data_control) = DataControl::current() {
+            self.use_data_control(data_control);
+            return Ok(());
+        }
+        unsafe { gtk::set_initialized() };
+        let display = Display::default()
+            .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
+        let clipboard = Clipboard::default(&display)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Clipboard not found".into()))?;
+        let weak_self = self.weak_self.clone();
+        let handler = clipboard.connect_owner_change(move |_, _| {
+            Self::clipboard_changed(&weak_self);
+        });
+        self.connection
+            .replace(Some(Connection::Gtk(clipboard, handler)));
+        // GTK monitors until data control connection, if any, is set up.
+        let weak_self = self.weak_self.clone();
+        self.tasks.spawn(async move {
+            let data_control = DataControl::get().await;
+            if let (Some(this), Some(data_control)) = (weak_self.upgrade(), data_control) {
+                if matches!(&*this.connection.borrow(), Some(Connection::Gtk(..))) {
+                    this.use_data_control(data_control);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        match self.connection.take() {
+            Some(Connection::Gtk(clipboard, handler)) => clipboard.disconnect(handler),
+            Some(Connection::DataControl(data_control)) => {
+                data_control.set_selection_listener(None)
+            }
+            None => {}
+        }
+    }
+}
+
+impl Drop for PlatformClipboardMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
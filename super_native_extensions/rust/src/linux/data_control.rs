@@ -0,0 +1,744 @@
+//! Clipboard through Wayland data control protocols.
+//!
+//! On Wayland GTK only sees clipboard changes and can only set the selection
+//! while one of its windows has keyboard focus. `ext-data-control-v1` and its
+//! predecessor `wlr-data-control-unstable-v1`, provided by wlroots based
+//! compositors and KDE, are meant for clipboard managers and work without
+//! focus. When the session is Wayland and the compositor advertises either
+//! manager, clipboard monitoring and writes go through it; GTK is used
+//! otherwise.
+//!
+//! This is a minimal client of the Wayland wire protocol on its own
+//! connection to the compositor. Only the seat, the manager and the data
+//! device are bound, so it doesn't need `libwayland-client` or generated
+//! protocol bindings. Both protocols have the same requests and events.
+//!
+//! The socket is non-blocking and serviced from the main loop: requests are
+//! queued and written as the socket accepts them, and the registry roundtrip
+//! during setup is answered through the same watch.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    env,
+    fs::File,
+    io::{self, ErrorKind, Write},
+    mem::{size_of, size_of_val},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        raw::{c_int, c_void},
+        unix::net::UnixStream,
+    },
+    path::PathBuf,
+    ptr,
+    rc::{Rc, Weak},
+    slice, thread,
+    time::Duration,
+};
+
+use gtk::glib::{self, IOCondition, SourceId};
+use irondash_run_loop::{util::FutureCompleter, RunLoop};
+use log::warn;
+
+use super::{common::TYPE_TEXT, data_provider::DataObject};
+
+#[repr(C)]
+struct IoVec {
+    base: *mut c_void,
+    len: usize,
+}
+
+#[repr(C)]
+struct MsgHdr {
+    name: *mut c_void,
+    name_len: u32,
+    iov: *mut IoVec,
+    iov_len: usize,
+    control: *mut c_void,
+    control_len: usize,
+    flags: c_int,
+}
+
+#[repr(C)]
+struct CmsgHdr {
+    len: usize,
+    level: c_int,
+    type_: c_int,
+}
+
+extern "C" {
+    fn recvmsg(fd: c_int, msg: *mut MsgHdr, flags: c_int) -> isize;
+}
+
+const SOL_SOCKET: c_int = 1;
+const SCM_RIGHTS: c_int = 1;
+const MSG_DONTWAIT: c_int = 0x40;
+const MSG_CMSG_CLOEXEC: c_int = 0x40000000;
+
+const SETUP_TIMEOUT: Duration = Duration::from_millis(500);
+
+const EXT_MANAGER_INTERFACE: &str = "ext_data_control_manager_v1";
+const WLR_MANAGER_INTERFACE: &str = "zwlr_data_control_manager_v1";
+
+const DISPLAY_ID: u32 = 1;
+const DISPLAY_SYNC: u16 = 0;
+const DISPLAY_GET_REGISTRY: u16 = 1;
+const DISPLAY_ERROR_EVENT: u16 = 0;
+
+const REGISTRY_BIND: u16 = 0;
+const REGISTRY_GLOBAL_EVENT: u16 = 0;
+
+const MANAGER_CREATE_DATA_SOURCE: u16 = 0;
+const MANAGER_GET_DATA_DEVICE: u16 = 1;
+
+const DEVICE_SET_SELECTION: u16 = 0;
+const DEVICE_DATA_OFFER_EVENT: u16 = 0;
+const DEVICE_SELECTION_EVENT: u16 = 1;
+const DEVICE_FINISHED_EVENT: u16 = 2;
+const DEVICE_PRIMARY_SELECTION_EVENT: u16 = 3;
+
+const SOURCE_OFFER: u16 = 0;
+const SOURCE_DESTROY: u16 = 1;
+const SOURCE_SEND_EVENT: u16 = 0;
+const SOURCE_CANCELLED_EVENT: u16 = 1;
+
+const OFFER_DESTROY: u16 = 1;
+
+/// Mime types offered for `text/plain` representation, same as GTK text
+/// targets.
+const TEXT_MIME_TYPES: &[&str] = &[
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "TEXT",
+    "STRING",
+    "text/plain",
+];
+
+struct MessageWriter {
+    data: Vec<u8>,
+    opcode: u16,
+}
+
+impl MessageWriter {
+    fn new(object: u32, opcode: u16) -> Self {
+        let mut data = Vec::new();
+        data.extend_from_slice(&object.to_ne_bytes());
+        data.extend_from_slice(&0u32.to_ne_bytes());
+        Self { data, opcode }
+    }
+
+    fn uint(mut self, value: u32) -> Self {
+        self.data.extend_from_slice(&value.to_ne_bytes());
+        self
+    }
+
+    fn string(mut self, value: &str) -> Self {
+        self = self.uint(value.len() as u32 + 1);
+        self.data.extend_from_slice(value.as_bytes());
+        self.data.push(0);
+        while self.data.len() % 4 != 0 {
+            self.data.push(0);
+        }
+        self
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let word = ((self.data.len() as u32) << 16) | self.opcode as u32;
+        self.data[4..8].copy_from_slice(&word.to_ne_bytes());
+        self.data
+    }
+}
+
+struct Message {
+    object: u32,
+    opcode: u16,
+    args: Vec<u8>,
+    offset: usize,
+}
+
+impl Message {
+    fn uint(&mut self) -> Option<u32> {
+        let bytes = self.args.get(self.offset..self.offset + 4)?;
+        self.offset += 4;
+        Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.uint()? as usize;
+        let bytes = self.args.get(self.offset..self.offset + len)?;
+        self.offset += (len + 3) & !3;
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+struct Connection {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+    fds: VecDeque<OwnedFd>,
+    /// Requests not yet accepted by the socket, possibly starting in the
+    /// middle of a message.
+    outgoing: Vec<u8>,
+}
+
+fn align(len: usize) -> usize {
+    let alignment = size_of::<usize>();
+    (len + alignment - 1) & !(alignment - 1)
+}
+
+impl Connection {
+    fn connect() -> io::Result<Self> {
+        let display = env::var_os("WAYLAND_DISPLAY").unwrap_or_else(|| "wayland-0".into());
+        let mut path = PathBuf::from(display);
+        if path.is_relative() {
+            let runtime_dir = env::var_os("XDG_RUNTIME_DIR")
+                .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "XDG_RUNTIME_DIR not set"))?;
+            path = PathBuf::from(runtime_dir).join(path);
+        }
+        let stream = UnixStream::connect(path)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            fds: VecDeque::new(),
+            outgoing: Vec::new(),
+        })
+    }
+
+    fn send(&mut self, message: MessageWriter) -> io::Result<()> {
+        self.outgoing.extend_from_slice(&message.finish());
+        self.flush()
+    }
+
+    /// Writes queued requests until the socket would block.
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match (&self.stream).write(&self.outgoing) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(len) => {
+                    self.outgoing.drain(..len);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads available bytes together with file descriptors passed along.
+    fn read(&mut self, flags: c_int) -> io::Result<()> {
+        let mut data = [0u8; 4096];
+        // u64 for alignment of control messages.
+        let mut control = [0u64; 32];
+        let mut iov = IoVec {
+            base: data.as_mut_ptr() as *mut c_void,
+            len: data.len(),
+        };
+        let mut header = MsgHdr {
+            name: ptr::null_mut(),
+            name_len: 0,
+            iov: &mut iov,
+            iov_len: 1,
+            control: control.as_mut_ptr() as *mut c_void,
+            control_len: size_of_val(&control),
+            flags: 0,
+        };
+        let len = unsafe {
+            recvmsg(
+                self.stream.as_raw_fd(),
+                &mut header,
+                flags | MSG_CMSG_CLOEXEC,
+            )
+        };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if len == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        self.buffer.extend_from_slice(&data[..len as usize]);
+
+        let control =
+            unsafe { slice::from_raw_parts(control.as_ptr() as *const u8, header.control_len) };
+        let header_len = align(size_of::<CmsgHdr>());
+        let mut offset = 0;
+        while offset + header_len <= control.len() {
+            let cmsg =
+                unsafe { ptr::read_unaligned(control.as_ptr().add(offset) as *const CmsgHdr) };
+            if cmsg.len < header_len || offset + cmsg.len > control.len() {
+                break;
+            }
+            if cmsg.level == SOL_SOCKET && cmsg.type_ == SCM_RIGHTS {
+                let count = (cmsg.len - header_len) / size_of::<c_int>();
+                for i in 0..count {
+                    let fd = unsafe {
+                        ptr::read_unaligned(
+                            control
+                                .as_ptr()
+                                .add(offset + header_len + i * size_of::<c_int>())
+                                as *const c_int,
+                        )
+                    };
+                    self.fds.push_back(unsafe { OwnedFd::from_raw_fd(fd) });
+                }
+            }
+            offset += align(cmsg.len);
+        }
+        Ok(())
+    }
+
+    fn next_message(&mut self) -> Option<Message> {
+        let header = self.buffer.get(..8)?;
+        let object = u32::from_ne_bytes(header[..4].try_into().ok()?);
+        let word = u32::from_ne_bytes(header[4..].try_into().ok()?);
+        let size = ((word >> 16) as usize).max(8);
+        if self.buffer.len() < size {
+            return None;
+        }
+        let args = self.buffer[8..size].to_vec();
+        self.buffer.drain(..size);
+        Some(Message {
+            object,
+            opcode: (word & 0xFFFF) as u16,
+            args,
+            offset: 0,
+        })
+    }
+}
+
+/// Data control is only used on Wayland sessions; on X11 GTK clipboard
+/// works without focus.
+fn is_wayland_session() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_some()
+        && env::var("XDG_SESSION_TYPE")
+            .map(|session_type| session_type != "x11")
+            .unwrap_or(true)
+}
+
+thread_local! {
+    static DATA_CONTROL: RefCell<State> = const { RefCell::new(State::Initial) };
+}
+
+enum State {
+    Initial,
+    /// Registry roundtrip is in progress.
+    Connecting(
+        Rc<DataControl>,
+        Vec<FutureCompleter<Option<Rc<DataControl>>>>,
+    ),
+    Done(Option<Rc<DataControl>>),
+}
+
+/// Registry roundtrip of the setup.
+struct Setup {
+    registry: u32,
+    callback: u32,
+    /// (name, interface, version)
+    globals: Vec<(u32, String, u32)>,
+}
+
+pub struct DataControl {
+    weak_self: Weak<Self>,
+    connection: RefCell<Connection>,
+    connected: Cell<bool>,
+    setup: RefCell<Option<Setup>>,
+    next_id: Cell<u32>,
+    manager: Cell<u32>,
+    device: Cell<u32>,
+    /// Offers announced by the device that were not yet assigned.
+    pending_offers: RefCell<Vec<u32>>,
+    selection_offer: Cell<Option<u32>>,
+    /// Initial selection is reported right after binding the device; it is
+    /// not a change.
+    received_selection: Cell<bool>,
+    sources: RefCell<HashMap<u32, Rc<DataObject>>>,
+    current_source: Cell<Option<u32>>,
+    selection_listener: RefCell<Option<Rc<dyn Fn()>>>,
+    watch: RefCell<Option<SourceId>>,
+    /// Present while queued requests wait for the socket.
+    write_watch: RefCell<Option<SourceId>>,
+}
+
+impl DataControl {
+    /// Connects on first call. Resolves to `None` if the session is not
+    /// Wayland, the compositor doesn't support data control, doesn't answer
+    /// within [`SETUP_TIMEOUT`] or the connection was lost.
+    pub async fn get() -> Option<Rc<Self>> {
+        let future = DATA_CONTROL.with(|state| {
+            let mut state = state.borrow_mut();
+            if let State::Initial = &*state {
+                *state = match is_wayland_session().then(Self::connect) {
+                    Some(Ok(data_control)) => State::Connecting(data_control, Vec::new()),
+                    Some(Err(err)) => {
+                        warn!("Wayland data control not available: {err}");
+                        State::Done(None)
+                    }
+                    None => State::Done(None),
+                };
+            }
+            match &mut *state {
+                State::Connecting(_, waiters) => {
+                    let (future, completer) = FutureCompleter::new();
+                    waiters.push(completer);
+                    Err(future)
+                }
+                State::Done(data_control) => Ok(data_control.clone()),
+                State::Initial => Ok(None),
+            }
+        });
+        match future {
+            Ok(data_control) => data_control,
+            Err(future) => future.await,
+        }
+    }
+
+    /// Returns connection if it has been set up already.
+    pub fn current() -> Option<Rc<Self>> {
+        DATA_CONTROL.with(|state| match &*state.borrow() {
+            State::Done(data_control) => data_control.clone(),
+            _ => None,
+        })
+    }
+
+    fn finish_setup(result: Option<Rc<Self>>) {
+        let previous = DATA_CONTROL.with(|state| state.replace(State::Done(result.clone())));
+        if let State::Connecting(_, waiters) = previous {
+            for waiter in waiters {
+                waiter.complete(result.clone());
+            }
+        }
+    }
+
+    fn connect() -> io::Result<Rc<Self>> {
+        let mut connection = Connection::connect()?;
+        let fd = connection.stream.as_raw_fd();
+        let registry = 2;
+        let callback = 3;
+        connection.send(MessageWriter::new(DISPLAY_ID, DISPLAY_GET_REGISTRY).uint(registry))?;
+        connection.send(MessageWriter::new(DISPLAY_ID, DISPLAY_SYNC).uint(callback))?;
+        let res = Rc::new_cyclic(|weak_self| Self {
+            weak_self: weak_self.clone(),
+            connection: RefCell::new(connection),
+            connected: Cell::new(true),
+            setup: RefCell::new(Some(Setup {
+                registry,
+                callback,
+                globals: Vec::new(),
+            })),
+            next_id: Cell::new(7),
+            manager: Cell::new(0),
+            device: Cell::new(0),
+            pending_offers: RefCell::new(Vec::new()),
+            selection_offer: Cell::new(None),
+            received_selection: Cell::new(false),
+            sources: RefCell::new(HashMap::new()),
+            current_source: Cell::new(None),
+            selection_listener: RefCell::new(None),
+            watch: RefCell::new(None),
+            write_watch: RefCell::new(None),
+        });
+        let weak = Rc::downgrade(&res);
+        let watch = glib::unix_fd_add_local(
+            fd,
+            IOCondition::IN | IOCondition::HUP | IOCondition::ERR,
+            move |_, _| match weak.upgrade() {
+                Some(this) => this.dispatch(),
+                None => glib::Continue(false),
+            },
+        );
+        res.watch.replace(Some(watch));
+        res.watch_writable();
+
+        let weak = Rc::downgrade(&res);
+        RunLoop::current()
+            .schedule(SETUP_TIMEOUT, move || {
+                if let Some(this) = weak.upgrade() {
+                    if this.setup.borrow().is_some() {
+                        warn!("Wayland data control setup timed out");
+                        this.disconnect();
+                    }
+                }
+            })
+            .detach();
+        Ok(res)
+    }
+
+    /// Binds the manager once all globals are known.
+    fn finish_registry(&self, setup: Setup) {
+        let global = |interface: &str| setup.globals.iter().find(|g| g.1 == interface);
+        let manager_global = global(EXT_MANAGER_INTERFACE)
+            .map(|g| (g, 1))
+            .or_else(|| global(WLR_MANAGER_INTERFACE).map(|g| (g, g.2.min(2))));
+        let (Some(((manager_name, manager_interface, _), manager_version)), Some(seat_global)) =
+            (manager_global, global("wl_seat"))
+        else {
+            self.disconnect();
+            return;
+        };
+
+        let seat = 4;
+        let manager = 5;
+        let device = 6;
+        self.send(
+            MessageWriter::new(setup.registry, REGISTRY_BIND)
+                .uint(seat_global.0)
+                .string("wl_seat")
+                .uint(1)
+                .uint(seat),
+        );
+        self.send(
+            MessageWriter::new(setup.registry, REGISTRY_BIND)
+                .uint(*manager_name)
+                .string(manager_interface)
+                .uint(manager_version)
+                .uint(manager),
+        );
+        self.send(
+            MessageWriter::new(manager, MANAGER_GET_DATA_DEVICE)
+                .uint(device)
+                .uint(seat),
+        );
+        self.manager.set(manager);
+        self.device.set(device);
+        if self.connected.get() {
+            Self::finish_setup(self.weak_self.upgrade());
+        }
+    }
+
+    fn new_id(&self) -> u32 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+
+    /// Queues the request; connection is dropped if it can't be written.
+    fn send(&self, message: MessageWriter) {
+        if !self.connected.get() {
+            return;
+        }
+        let res = self.connection.borrow_mut().send(message);
+        match res {
+            Ok(()) => self.watch_writable(),
+            Err(err) => {
+                warn!("Wayland data control connection lost: {err}");
+                self.disconnect();
+            }
+        }
+    }
+
+    /// Flushes remaining requests once the socket accepts more data.
+    fn watch_writable(&self) {
+        if self.connection.borrow().outgoing.is_empty() || self.write_watch.borrow().is_some() {
+            return;
+        }
+        let fd = self.connection.borrow().stream.as_raw_fd();
+        let weak = self.weak_self.clone();
+        let watch = glib::unix_fd_add_local(fd, IOCondition::OUT, move |_, _| {
+            let Some(this) = weak.upgrade() else {
+                return glib::Continue(false);
+            };
+            let res = this.connection.borrow_mut().flush();
+            if let Err(err) = res {
+                warn!("Wayland data control connection lost: {err}");
+                this.disconnect();
+            }
+            let done = !this.connected.get() || this.connection.borrow().outgoing.is_empty();
+            if done {
+                // Removed by returning `Continue(false)`.
+                this.write_watch.take();
+            }
+            glib::Continue(!done)
+        });
+        self.write_watch.replace(Some(watch));
+    }
+
+    /// Called with every selection change, including changes made by this
+    /// process.
+    pub fn set_selection_listener(&self, listener: Option<Rc<dyn Fn()>>) {
+        self.selection_listener.replace(listener);
+    }
+
+    pub fn set_selection(&self, data_object: Rc<DataObject>) {
+        let source = self.new_id();
+        self.send(MessageWriter::new(self.manager.get(), MANAGER_CREATE_DATA_SOURCE).uint(source));
+        for mime_type in data_object.mime_types() {
+            if mime_type == TYPE_TEXT {
+                for text_type in TEXT_MIME_TYPES {
+                    self.send(MessageWriter::new(source, SOURCE_OFFER).string(text_type));
+                }
+            } else {
+                self.send(MessageWriter::new(source, SOURCE_OFFER).string(&mime_type));
+            }
+        }
+        self.send(MessageWriter::new(self.device.get(), DEVICE_SET_SELECTION).uint(source));
+        self.sources.borrow_mut().insert(source, data_object);
+        // Previous source gets cancelled by the compositor.
+        self.current_source.set(Some(source));
+    }
+
+    /// Only clears the selection while it is owned by this process.
+    pub fn clear_selection(&self) {
+        if self.current_source.take().is_some() {
+            self.send(MessageWriter::new(self.device.get(), DEVICE_SET_SELECTION).uint(0));
+        }
+    }
+
+    fn dispatch(&self) -> glib::Continue {
+        loop {
+            let res = self.connection.borrow_mut().read(MSG_DONTWAIT);
+            match res {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => {
+                    warn!("Wayland data control connection lost: {err}");
+                    self.disconnect();
+                    break;
+                }
+            }
+        }
+        while self.connected.get() {
+            let message = self.connection.borrow_mut().next_message();
+            let Some(message) = message else {
+                break;
+            };
+            if !self.handle_message(message) {
+                self.disconnect();
+            }
+        }
+        if !self.connected.get() {
+            // Removed by returning `Continue(false)`.
+            self.watch.take();
+        }
+        glib::Continue(self.connected.get())
+    }
+
+    /// Collects globals until the initial sync is done.
+    fn handle_setup_message(&self, mut message: Message) {
+        let mut setup = self.setup.borrow_mut();
+        let Some(state) = setup.as_mut() else {
+            return;
+        };
+        if message.object == state.registry && message.opcode == REGISTRY_GLOBAL_EVENT {
+            if let (Some(name), Some(interface), Some(version)) =
+                (message.uint(), message.string(), message.uint())
+            {
+                state.globals.push((name, interface, version));
+            }
+        } else if message.object == state.callback {
+            let state = setup.take();
+            drop(setup);
+            if let Some(state) = state {
+                self.finish_registry(state);
+            }
+        }
+    }
+
+    /// Returns false if the connection can not be used anymore.
+    fn handle_message(&self, mut message: Message) -> bool {
+        match (message.object, message.opcode) {
+            (DISPLAY_ID, DISPLAY_ERROR_EVENT) => {
+                let (object, code, text) = (message.uint(), message.uint(), message.string());
+                warn!("Wayland data control error {code:?} on {object:?}: {text:?}");
+                return false;
+            }
+            _ if self.setup.borrow().is_some() => self.handle_setup_message(message),
+            (id, opcode) if id == self.device.get() => match opcode {
+                DEVICE_DATA_OFFER_EVENT => {
+                    if let Some(offer) = message.uint() {
+                        self.pending_offers.borrow_mut().push(offer);
+                    }
+                }
+                DEVICE_SELECTION_EVENT => {
+                    let offer = message.uint().filter(|offer| *offer != 0);
+                    self.discard_pending_offers(offer);
+                    if let Some(previous) = self.selection_offer.replace(offer) {
+                        self.send(MessageWriter::new(previous, OFFER_DESTROY));
+                    }
+                    if self.received_selection.replace(true) {
+                        let listener = self.selection_listener.borrow().clone();
+                        if let Some(listener) = listener {
+                            listener();
+                        }
+                    }
+                }
+                // Only the regular clipboard is monitored.
+                DEVICE_PRIMARY_SELECTION_EVENT => self.discard_pending_offers(None),
+                DEVICE_FINISHED_EVENT => {
+                    warn!("Wayland data control device finished");
+                    return false;
+                }
+                _ => {}
+            },
+            (id, SOURCE_SEND_EVENT) if self.sources.borrow().contains_key(&id) => {
+                let mime_type = message.string();
+                let fd = self.connection.borrow_mut().fds.pop_front();
+                if let (Some(mime_type), Some(fd)) = (mime_type, fd) {
+                    self.send_data(id, &mime_type, fd);
+                }
+            }
+            (id, SOURCE_CANCELLED_EVENT) if self.sources.borrow().contains_key(&id) => {
+                self.sources.borrow_mut().remove(&id);
+                if self.current_source.get() == Some(id) {
+                    self.current_source.set(None);
+                }
+                self.send(MessageWriter::new(id, SOURCE_DESTROY));
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Destroys announced offers other than `keep`.
+    fn discard_pending_offers(&self, keep: Option<u32>) {
+        let pending: Vec<_> = self.pending_offers.borrow_mut().drain(..).collect();
+        for offer in pending {
+            if Some(offer) != keep {
+                self.send(MessageWriter::new(offer, OFFER_DESTROY));
+            }
+        }
+    }
+
+    fn send_data(&self, source: u32, mime_type: &str, fd: OwnedFd) {
+        let data_object = self.sources.borrow().get(&source).cloned();
+        let Some(data_object) = data_object else {
+            return;
+        };
+        let mime_type = if TEXT_MIME_TYPES.contains(&mime_type) {
+            TYPE_TEXT
+        } else {
+            mime_type
+        };
+        // Lazy data may need to run the run loop.
+        let Some(data) = data_object.data_for_target(mime_type) else {
+            return;
+        };
+        // Receiver might read slowly; don't block the run loop.
+        thread::spawn(move || {
+            let mut file = File::from(fd);
+            file.write_all(&data).ok();
+        });
+    }
+
+    fn disconnect(&self) {
+        if !self.connected.replace(false) {
+            return;
+        }
+        self.setup.take();
+        self.sources.borrow_mut().clear();
+        // Watches are removed when they fire next or when dropped.
+        Self::finish_setup(None);
+    }
+}
+
+impl Drop for DataControl {
+    fn drop(&mut self) {
+        if let Some(watch) = self.watch.take() {
+            watch.remove();
+        }
+        if let Some(watch) = self.write_watch.take() {
+            watch.remove();
+        }
+    }
+}
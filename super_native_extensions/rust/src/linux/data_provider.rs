@@ -19,7 +19,10 @@ use crate::{
     value_coerce::{CoerceToData, StringFormat},
 };
 
-use super::common::{target_includes_text, TargetListExt, TYPE_TEXT, TYPE_URI};
+use super::{
+    common::{target_includes_text, TargetListExt, TYPE_TEXT, TYPE_URI},
+    data_control::DataControl,
+};
 
 pub fn platform_stream_write(_handle: i32, _data: &[u8]) -> i32 {
     0
@@ -56,8 +59,46 @@ impl PlatformDataProvider {
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
     ) -> NativeExtensionsResult<()> {
         let data_object = DataObject::new(providers);
+        if let Some(data_control) = DataControl::get().await {
+            data_control.set_selection(data_object);
+            return Ok(());
+        }
         data_object.write_to_clipboard()
     }
+
+    pub async fn write_to_primary_selection(
+        providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+    ) -> NativeExtensionsResult<()> {
+        let data_object = DataObject::new(providers);
+        data_object.write_to_primary_selection()
+    }
+
+    pub async fn clear_clipboard() -> NativeExtensionsResult<()> {
+        if let Some(data_control) = DataControl::get().await {
+            data_control.clear_selection();
+            return Ok(());
+        }
+        unsafe { gtk::set_initialized() };
+        let display = Display::default()
+            .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
+        let clipboard = Clipboard::default(&display)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Clipboard not found".into()))?;
+        // Only clears the clipboard while we own it.
+        clipboard.clear();
+        Ok(())
+    }
+
+    /// Hands data that can still be provided to the clipboard manager, if
+    /// there is one, so that it outlives the application.
+    pub fn flush_clipboard() -> NativeExtensionsResult<()> {
+        unsafe { gtk::set_initialized() };
+        let display = Display::default()
+            .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
+        let clipboard = Clipboard::default(&display)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Clipboard not found".into()))?;
+        clipboard.store();
+        Ok(())
+    }
 }
 
 struct ProviderEntry {
@@ -136,47 +177,84 @@ impl DataObject {
         None
     }
 
-    pub fn get_data(&self, selection_data: &SelectionData) -> NativeExtensionsResult<()> {
-        let target = selection_data.target();
-        let is_text = target_includes_text(&target);
-
-        let target = if is_text {
-            TYPE_TEXT.to_owned()
-        } else {
-            target.name().as_str().to_owned()
-        };
+    /// Data for target format; text targets are expected to be mapped to
+    /// [`TYPE_TEXT`].
+    pub fn data_for_target(&self, target: &str) -> Option<Vec<u8>> {
         if target == TYPE_URI {
             // merge URIs from all items
             let mut data = Vec::<u8>::new();
             for item in &self.providers {
-                if let Some(item_data) = self.get_data_for_item(&item.provider, &target) {
+                if let Some(item_data) = self.get_data_for_item(&item.provider, target) {
                     data.extend_from_slice(&item_data);
                     data.push(b'\r');
                     data.push(b'\n');
                 }
             }
+            Some(data)
+        } else {
+            let item = self.providers.first()?;
+            self.get_data_for_item(&item.provider, target)
+        }
+    }
+
+    pub fn get_data(&self, selection_data: &SelectionData) -> NativeExtensionsResult<()> {
+        let target = selection_data.target();
+        let is_text = target_includes_text(&target);
+
+        let target = if is_text {
+            TYPE_TEXT.to_owned()
+        } else {
+            target.name().as_str().to_owned()
+        };
+        if let Some(data) = self.data_for_target(&target) {
             Self::set_data_(selection_data, &data)?;
-        } else if let Some(item) = self.providers.first() {
-            if let Some(data) = self.get_data_for_item(&item.provider, &target) {
-                Self::set_data_(selection_data, &data)?;
-            }
         }
         Ok(())
     }
 
     pub fn write_to_clipboard(self: &Rc<Self>) -> NativeExtensionsResult<()> {
         unsafe { gtk::set_initialized() };
-        let list = self.create_target_list();
-        let targets = list.get_target_entries();
         let display = Display::default()
             .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
         let clipboard = Clipboard::default(&display)
             .ok_or_else(|| NativeExtensionsError::OtherError("Clipboard not found".into()))?;
+        self.set_clipboard_data(&clipboard);
+        Ok(())
+    }
+
+    pub fn write_to_primary_selection(self: &Rc<Self>) -> NativeExtensionsResult<()> {
+        unsafe { gtk::set_initialized() };
+        let display = Display::default()
+            .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
+        let clipboard = Clipboard::for_display(&display, &Atom::intern("PRIMARY"));
+        self.set_clipboard_data(&clipboard);
+        Ok(())
+    }
+
+    fn set_clipboard_data(self: &Rc<Self>, clipboard: &Clipboard) {
+        let list = self.create_target_list();
+        let targets = list.get_target_entries();
         let self_clone = self.clone();
         clipboard.set_with_data(&targets, move |_, selection_data, _| {
             self_clone.get_data(selection_data).ok_log();
         });
-        Ok(())
+    }
+
+    /// Formats of the first item; other items only contribute URIs.
+    pub fn mime_types(&self) -> Vec<String> {
+        let Some(item) = self.providers.first() else {
+            return Vec::new();
+        };
+        item.provider
+            .data
+            .representations
+            .iter()
+            .filter_map(|r| match r {
+                DataRepresentation::Simple { format, data: _ } => Some(format.clone()),
+                DataRepresentation::Lazy { format, id: _ } => Some(format.clone()),
+                _ => None,
+            })
+            .collect()
     }
 
     pub fn create_target_list(&self) -> TargetList {
@@ -0,0 +1,125 @@
+//! Detection of tray icon and global hot key support.
+//!
+//! Linux desktops differ in how (and whether) these features are provided.
+//! Tray icons use `StatusNotifierItem` when a watcher is present on the
+//! session bus (KDE, GNOME with AppIndicator extension, most Wayland
+//! compositors' panels) and fall back to XEmbed on X11. Global hot keys are
+//! only possible on X11, where keys are grabbed on the root window. Sandboxes
+//! (Flatpak, Snap) commonly filter session bus names and may hide the
+//! watcher, which is reported in the reason.
+//!
+//! Session bus queries are asynchronous so that an unresponsive bus can not
+//! block the main thread.
+
+use std::path::Path;
+
+use gdk::prelude::ObjectExt;
+use gtk::{
+    gio::{self, BusType, DBusCallFlags, DBusConnection},
+    glib::{ToVariant, VariantTy},
+};
+
+use crate::api_model::{DesktopFeatureBackend, DesktopFeatureCapability};
+
+const DBUS_TIMEOUT_MS: i32 = 500;
+
+pub const STATUS_NOTIFIER_WATCHER: &str = "org.kde.StatusNotifierWatcher";
+
+async fn session_bus() -> Option<DBusConnection> {
+    gio::bus_get_future(BusType::Session).await.ok()
+}
+
+async fn name_has_owner(connection: &DBusConnection, name: &str) -> bool {
+    connection
+        .call_future(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "NameHasOwner",
+            Some(&(name,).to_variant()),
+            VariantTy::new("(b)").ok(),
+            DBusCallFlags::NONE,
+            DBUS_TIMEOUT_MS,
+        )
+        .await
+        .ok()
+        .and_then(|reply| reply.get::<(bool,)>())
+        .map(|reply| reply.0)
+        .unwrap_or(false)
+}
+
+async fn has_global_shortcuts_portal(connection: &DBusConnection) -> bool {
+    connection
+        .call_future(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            Some(&("org.freedesktop.portal.GlobalShortcuts", "version").to_variant()),
+            None,
+            DBusCallFlags::NONE,
+            DBUS_TIMEOUT_MS,
+        )
+        .await
+        .is_ok()
+}
+
+pub(super) fn is_x11() -> bool {
+    gdk::Display::default()
+        .map(|display| display.type_().name() == "GdkX11Display")
+        .unwrap_or(false)
+}
+
+fn sandbox_name() -> Option<&'static str> {
+    if Path::new("/.flatpak-info").exists() {
+        Some("Flatpak")
+    } else if std::env::var_os("SNAP").is_some() {
+        Some("Snap")
+    } else {
+        None
+    }
+}
+
+pub async fn tray_capability() -> DesktopFeatureCapability {
+    let bus = session_bus().await;
+    let has_watcher = match &bus {
+        Some(bus) => name_has_owner(bus, STATUS_NOTIFIER_WATCHER).await,
+        None => false,
+    };
+    if has_watcher {
+        return DesktopFeatureCapability::supported(DesktopFeatureBackend::StatusNotifier);
+    }
+    if is_x11() {
+        return DesktopFeatureCapability::supported(DesktopFeatureBackend::XEmbed);
+    }
+    let mut reason = match bus {
+        Some(_) => format!("{STATUS_NOTIFIER_WATCHER} is not running and session is not X11"),
+        None => "Session bus is not available and session is not X11".to_owned(),
+    };
+    if let Some(sandbox) = sandbox_name() {
+        reason.push_str(&format!(
+            "; {sandbox} sandbox may need --talk-name={STATUS_NOTIFIER_WATCHER}"
+        ));
+    }
+    DesktopFeatureCapability::unsupported(reason)
+}
+
+pub async fn hot_key_capability() -> DesktopFeatureCapability {
+    if is_x11() {
+        return DesktopFeatureCapability::supported(DesktopFeatureBackend::X11Grab);
+    }
+    let portal = match session_bus().await {
+        Some(bus) => has_global_shortcuts_portal(&bus).await,
+        None => false,
+    };
+    if portal {
+        DesktopFeatureCapability::unsupported(
+            "Wayland session; global shortcuts are only available through \
+             GlobalShortcuts portal, which requires user to assign shortcuts",
+        )
+    } else {
+        DesktopFeatureCapability::unsupported(
+            "Wayland session without GlobalShortcuts portal; keys can not be grabbed",
+        )
+    }
+}
@@ -8,21 +8,27 @@ use std::{
 
 use gdk::{
     glib::{translate::from_glib_none, WeakRef},
+    keys,
     prelude::StaticType,
     traits::{DeviceExt, SeatExt},
-    Display, DragAction, DragCancelReason, DragContext, Event,
+    Display, DragAction, DragCancelReason as GdkDragCancelReason, DragContext, Event, Keymap,
+    ModifierType,
 };
 
-use gtk::{prelude::DragContextExtManual, traits::WidgetExt, SelectionData, Widget};
+use gtk::{prelude::DragContextExtManual, traits::WidgetExt, Inhibit, SelectionData, Widget};
 use gtk_sys::GtkWidget;
 use irondash_engine_context::EngineContext;
 use irondash_message_channel::{Late, Value};
 use irondash_run_loop::RunLoop;
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DragCancelReason, DragConfiguration, DragRequest, DropOperation,
+        KeyModifiers, Point, TargettedImage,
+    },
     drag_manager::{
         DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
+        UpdateDragImageRequest,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
@@ -42,6 +48,7 @@ pub struct PlatformDragContext {
     weak_self: Late<Weak<Self>>,
     pub(crate) view: WeakRef<Widget>,
     button_press_hook: Late<c_ulong>,
+    key_press_hook: Late<c_ulong>,
     pub(crate) last_button_press_event: RefCell<Option<Event>>,
     sessions: RefCell<HashMap<DragContext, Rc<Session>>>,
 }
@@ -52,9 +59,12 @@ struct Session {
     context_delegate: Weak<dyn PlatformDragContextDelegate>,
     data_object: Rc<DataObject>,
     configuration: DragConfiguration,
+    /// Drag images are positioned relative to this point.
+    start_position: Point,
     weak_self: Late<Weak<Self>>,
     last_position: RefCell<Point>,
     last_operation: Cell<DropOperation>,
+    cancel_reason: Cell<Option<DragCancelReason>>,
 }
 
 impl Session {
@@ -64,6 +74,7 @@ impl Session {
         context_delegate: Weak<dyn PlatformDragContextDelegate>,
         data_object: Rc<DataObject>,
         configuration: DragConfiguration,
+        start_position: Point,
     ) -> Rc<Self> {
         let res = Rc::new(Self {
             id,
@@ -71,9 +82,11 @@ impl Session {
             context_delegate,
             data_object,
             configuration,
+            start_position,
             weak_self: Late::new(),
             last_position: RefCell::new(Point::default()),
             last_operation: Cell::new(DropOperation::None),
+            cancel_reason: Cell::new(None),
         });
         res.weak_self.set(Rc::downgrade(&res));
         res.schedule_update_position();
@@ -108,6 +121,7 @@ impl Session {
                                 self.context_id,
                                 self.id,
                                 position,
+                                PlatformDragContext::current_modifiers(),
                             );
                         }
                     }
@@ -125,6 +139,7 @@ impl Drop for Session {
                 self.context_id,
                 self.id,
                 self.last_operation.get(),
+                self.cancel_reason.get(),
             );
         }
     }
@@ -148,6 +163,7 @@ impl PlatformDragContext {
             weak_self: Late::new(),
             view: weak,
             button_press_hook: Late::new(),
+            key_press_hook: Late::new(),
             delegate,
             last_button_press_event: RefCell::new(None),
             sessions: RefCell::new(HashMap::new()),
@@ -156,6 +172,20 @@ impl PlatformDragContext {
 
     pub fn assign_weak_self(&self, weak_self: Weak<Self>) {
         self.weak_self.set(weak_self.clone());
+        if let Some(signal) = Signal::lookup("key-press-event", Widget::static_type()) {
+            let weak_self = weak_self.clone();
+            let hook = signal.add_emission_hook(move |_, values| {
+                if let Some(this) = weak_self.upgrade() {
+                    if let Some(event) = values[1].get::<Event>().ok_log() {
+                        if event.keyval() == Some(keys::constants::Escape) {
+                            this.cancel_sessions();
+                        }
+                    }
+                }
+                true
+            });
+            self.key_press_hook.set(hook);
+        }
         if let Some(signal) = Signal::lookup("button-press-event", Widget::static_type()) {
             let hook = signal.add_emission_hook(move |_, values| {
                 if let Some(this) = weak_self.clone().upgrade() {
@@ -174,6 +204,45 @@ impl PlatformDragContext {
                     this.get_data(context, data);
                 }
             });
+            let weak_self = self.weak_self.clone();
+            view.connect_drag_failed(move |_, context, _result| {
+                let handled = weak_self
+                    .upgrade()
+                    .map(|this| this.drag_failed(context))
+                    .unwrap_or(false);
+                Inhibit(handled)
+            });
+        }
+    }
+
+    /// Used for Escape, as handling it during drag otherwise depends on GTK
+    /// getting keyboard grab, which is not reliable, and on shutdown.
+    pub fn cancel_sessions(&self) {
+        let contexts: Vec<DragContext> = self.sessions.borrow().keys().cloned().collect();
+        for context in contexts {
+            if let Some(session) = self.sessions.borrow().get(&context) {
+                session.cancel_reason.set(Some(DragCancelReason::Escape));
+            }
+            context.drag_cancel();
+        }
+    }
+
+    /// Returns true if the failure animation should be suppressed.
+    fn drag_failed(&self, context: &DragContext) -> bool {
+        let session = self.sessions.borrow().get(context).cloned();
+        match session {
+            Some(session)
+                if !session
+                    .configuration
+                    .animates_to_starting_position_on_cancel_or_fail =>
+            {
+                if let Some(delegate) = self.delegate.upgrade() {
+                    let location = session.last_position.borrow().clone();
+                    delegate.drag_session_did_cancel(self.id, session.id, location);
+                }
+                true
+            }
+            _ => false,
         }
     }
 
@@ -187,6 +256,19 @@ impl PlatformDragContext {
         true
     }
 
+    pub fn current_modifiers() -> KeyModifiers {
+        let state = Display::default()
+            .and_then(|display| Keymap::for_display(&display))
+            .map(|keymap| ModifierType::from_bits_truncate(keymap.modifier_state()))
+            .unwrap_or_else(ModifierType::empty);
+        KeyModifiers {
+            shift: state.contains(ModifierType::SHIFT_MASK),
+            control: state.contains(ModifierType::CONTROL_MASK),
+            alt: state.contains(ModifierType::MOD1_MASK),
+            meta: state.intersects(ModifierType::SUPER_MASK | ModifierType::META_MASK),
+        }
+    }
+
     fn view(&self) -> NativeExtensionsResult<Widget> {
         self.view
             .upgrade()
@@ -237,15 +319,8 @@ impl PlatformDragContext {
             request.position.y as i32,
         );
         if let Some(context) = context {
-            if let Some(image) = request.combined_drag_image {
-                let image = image.with_shadow(10);
-                let scale = image.image_data.device_pixel_ratio.unwrap_or(1.0);
-                let surface = surface_from_image_data(image.image_data, 0.8);
-                surface.set_device_offset(
-                    (image.rect.x - request.position.x) * scale,
-                    (image.rect.y - request.position.y) * scale,
-                );
-                context.drag_set_icon_surface(&surface)
+            if let Some(image) = &request.combined_drag_image {
+                Self::set_drag_icon(&context, image, &request.position);
             }
             let session = Session::new(
                 session_id,
@@ -253,18 +328,26 @@ impl PlatformDragContext {
                 self.delegate.clone(),
                 object,
                 request.configuration,
+                request.position,
             );
             self.sessions.borrow_mut().insert(context.clone(), session);
             let weak_self = self.weak_self.clone();
             context.connect_cancel(move |context, reason| {
                 if let Some(this) = weak_self.upgrade() {
                     if let Some(session) = this.sessions.borrow_mut().remove(context) {
-                        match reason {
-                            DragCancelReason::UserCancelled => {
-                                session.last_operation.replace(DropOperation::UserCancelled)
+                        let (operation, cancel_reason) = match reason {
+                            GdkDragCancelReason::UserCancelled => {
+                                (DropOperation::UserCancelled, DragCancelReason::Escape)
                             }
-                            _ => session.last_operation.replace(DropOperation::None),
+                            GdkDragCancelReason::NoTarget => {
+                                (DropOperation::None, DragCancelReason::NoTarget)
+                            }
+                            _ => (DropOperation::None, DragCancelReason::TargetRefused),
                         };
+                        session.last_operation.replace(operation);
+                        if session.cancel_reason.get().is_none() {
+                            session.cancel_reason.set(Some(cancel_reason));
+                        }
                     }
                 }
             });
@@ -272,9 +355,13 @@ impl PlatformDragContext {
             context.connect_dnd_finished(move |context| {
                 if let Some(this) = weak_self.upgrade() {
                     if let Some(session) = this.sessions.borrow_mut().remove(context) {
-                        session
-                            .last_operation
-                            .replace(DropOperation::from_platform(context.selected_action()));
+                        let operation = DropOperation::from_platform(context.selected_action());
+                        if operation == DropOperation::None {
+                            session
+                                .cancel_reason
+                                .set(Some(DragCancelReason::TargetRefused));
+                        }
+                        session.last_operation.replace(operation);
                     }
                 }
             });
@@ -283,13 +370,41 @@ impl PlatformDragContext {
         Ok(())
     }
 
-    pub fn get_local_data(&self) -> Option<Vec<Value>> {
+    /// Session and local data of items of local drag.
+    pub fn get_local_session(&self) -> Option<(DragSessionId, Vec<Value>)> {
         self.sessions
             .borrow()
             .iter()
             .next()
             .map(|a| a.1.clone())
-            .map(|s| s.configuration.get_local_data())
+            .map(|s| (s.id, s.configuration.get_local_data()))
+    }
+
+    fn set_drag_icon(context: &DragContext, image: &TargettedImage, position: &Point) {
+        let image = image.with_shadow(10);
+        let scale = image.image_data.device_pixel_ratio.unwrap_or(1.0);
+        let surface = surface_from_image_data(image.image_data, 0.8);
+        surface.set_device_offset(
+            (image.rect.x - position.x) * scale,
+            (image.rect.y - position.y) * scale,
+        );
+        context.drag_set_icon_surface(&surface)
+    }
+
+    pub fn update_drag_image(
+        &self,
+        request: &UpdateDragImageRequest,
+    ) -> NativeExtensionsResult<()> {
+        let sessions = self.sessions.borrow();
+        let (context, session) = sessions
+            .iter()
+            .find(|(_, session)| session.id == request.session_id)
+            .ok_or(NativeExtensionsError::DragSessionNotFound)?;
+        let image = request.combined_drag_image.as_ref().ok_or_else(|| {
+            NativeExtensionsError::OtherError("Missing combined drag image".into())
+        })?;
+        Self::set_drag_icon(context, image, &session.start_position);
+        Ok(())
     }
 
     pub fn get_local_data_for_session_id(
@@ -316,5 +431,8 @@ impl Drop for PlatformDragContext {
         if let Some(signal) = Signal::lookup("button-press-event", Widget::static_type()) {
             signal.remove_emission_hook(*self.button_press_hook);
         }
+        if let Some(signal) = Signal::lookup("key-press-event", Widget::static_type()) {
+            signal.remove_emission_hook(*self.key_press_hook);
+        }
     }
 }
@@ -151,19 +151,22 @@ impl PlatformDropContext {
     ) -> Option<DropEvent> {
         let reader_info = session.platform_reader.reader_info()?;
 
-        let local_data = self
+        let local_session = self
             .delegate()
             .ok()?
             .get_platform_drag_contexts()
             .iter()
-            .map(|c| c.get_local_data())
-            .find(|c| c.is_some())
-            .flatten()
-            .unwrap_or_default();
+            .find_map(|c| c.get_local_session());
+        let local_session_id = local_session.as_ref().map(|s| s.0);
+        let local_data = local_session.map(|s| s.1).unwrap_or_default();
 
         let number_of_items = local_data.len().max(reader_info.number_of_items);
         Some(DropEvent {
             session_id: session.id,
+            engine_handle: self.id.engine_handle,
+            region_id: None,
+            session_local_data: Value::Null,
+            local_session_id,
             location_in_view: Point {
                 x: x as f64,
                 y: y as f64,
@@ -180,6 +183,7 @@ impl PlatformDropContext {
                         .cloned()
                         .collect(),
                     local_data: local_data.get(i).cloned().unwrap_or(Value::Null),
+                    allowed_operations: None,
                 })
                 .collect(),
             reader: Some(session.registered_reader.clone()),
@@ -254,6 +258,7 @@ impl PlatformDropContext {
                     self.id,
                     BaseDropEvent {
                         session_id: session.id,
+                        engine_handle: self.id.engine_handle,
                     },
                 );
             } else {
@@ -271,18 +276,36 @@ impl PlatformDropContext {
                 self.id,
                 BaseDropEvent {
                     session_id: session.id,
+                    engine_handle: self.id.engine_handle,
                 },
             );
             self.delegate()?.send_drop_ended(
                 self.id,
                 BaseDropEvent {
                     session_id: session.id,
+                    engine_handle: self.id.engine_handle,
                 },
             );
         }
         Ok(())
     }
 
+    // Drag data is only available until drag_finish, which can not be delayed
+    // once the drop was acknowledged.
+    pub fn supports_deferred_drop(&self) -> bool {
+        false
+    }
+
+    pub fn show_busy_indicator(
+        &self,
+        _session_id: DropSessionId,
+        _location: &Point,
+    ) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+
+    pub fn hide_busy_indicator(&self, _session_id: DropSessionId) {}
+
     pub fn register_drop_formats(&self, formats: &[String]) -> NativeExtensionsResult<()> {
         let list = TargetList::new(&[]);
         for format in formats {
@@ -1,28 +1,342 @@
-use std::rc::Weak;
+//! Global hot keys on X11, implemented by grabbing keys on the root window.
+//! Wayland has no equivalent; see
+//! [`hot_key_capability`](super::desktop_capabilities::hot_key_capability).
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    os::raw::{c_int, c_uchar, c_uint, c_ulong, c_void},
+    rc::Weak,
+};
+
+use gdk::{glib::translate::ToGlibPtr, Display};
+use gdk_sys::{
+    gdk_window_add_filter, gdk_window_remove_filter, GdkEvent, GdkFilterReturn, GdkXEvent,
+    GDK_FILTER_CONTINUE, GDK_FILTER_REMOVE,
+};
+use irondash_message_channel::Late;
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
-    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate},
+    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate, SpecialKey},
 };
 
-pub struct PlatformHotKeyManager {}
+use super::desktop_capabilities::is_x11;
+
+pub(super) type XDisplay = c_void;
+pub(super) type XWindow = c_ulong;
+type KeySym = c_ulong;
+
+#[repr(C)]
+struct XErrorEvent {
+    type_: c_int,
+    display: *mut XDisplay,
+    resource_id: c_ulong,
+    serial: c_ulong,
+    error_code: c_uchar,
+    request_code: c_uchar,
+    minor_code: c_uchar,
+}
+
+#[repr(C)]
+struct XKeyEvent {
+    type_: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: *mut XDisplay,
+    window: XWindow,
+    root: XWindow,
+    subwindow: XWindow,
+    time: c_ulong,
+    x: c_int,
+    y: c_int,
+    x_root: c_int,
+    y_root: c_int,
+    state: c_uint,
+    keycode: c_uint,
+    same_screen: c_int,
+}
+
+type XErrorHandler = Option<unsafe extern "C" fn(*mut XDisplay, *mut XErrorEvent) -> c_int>;
+
+#[link(name = "X11")]
+extern "C" {
+    fn XGrabKey(
+        display: *mut XDisplay,
+        keycode: c_int,
+        modifiers: c_uint,
+        grab_window: XWindow,
+        owner_events: c_int,
+        pointer_mode: c_int,
+        keyboard_mode: c_int,
+    ) -> c_int;
+    fn XUngrabKey(
+        display: *mut XDisplay,
+        keycode: c_int,
+        modifiers: c_uint,
+        grab_window: XWindow,
+    ) -> c_int;
+    pub(super) fn XDefaultRootWindow(display: *mut XDisplay) -> XWindow;
+    fn XKeysymToKeycode(display: *mut XDisplay, keysym: KeySym) -> c_uchar;
+    fn XSync(display: *mut XDisplay, discard: c_int) -> c_int;
+    fn XSetErrorHandler(handler: XErrorHandler) -> XErrorHandler;
+    fn XkbSetDetectableAutoRepeat(
+        display: *mut XDisplay,
+        detectable: c_int,
+        supported: *mut c_int,
+    ) -> c_int;
+}
+
+extern "C" {
+    fn gdk_x11_display_get_xdisplay(display: *mut gdk_sys::GdkDisplay) -> *mut XDisplay;
+}
+
+const KEY_PRESS: c_int = 2;
+const KEY_RELEASE: c_int = 3;
+const GRAB_MODE_ASYNC: c_int = 1;
+const BAD_ACCESS: c_uchar = 10;
+
+const SHIFT_MASK: c_uint = 1 << 0;
+const LOCK_MASK: c_uint = 1 << 1;
+const CONTROL_MASK: c_uint = 1 << 2;
+const MOD1_MASK: c_uint = 1 << 3; // Alt
+const MOD2_MASK: c_uint = 1 << 4; // Num Lock
+const MOD4_MASK: c_uint = 1 << 6; // Super
+
+const RELEVANT_MODIFIERS: c_uint = SHIFT_MASK | CONTROL_MASK | MOD1_MASK | MOD4_MASK;
+
+/// Grabs are per modifier state, so the key needs to be grabbed also with
+/// each combination of lock modifiers, otherwise it would stop working with
+/// Caps Lock or Num Lock on.
+const LOCK_VARIANTS: [c_uint; 4] = [0, LOCK_MASK, MOD2_MASK, LOCK_MASK | MOD2_MASK];
+
+/// Keysyms from `XF86keysym.h` and `keysymdef.h`.
+fn special_key_to_keysym(key: SpecialKey) -> KeySym {
+    match key {
+        SpecialKey::MediaPlayPause => 0x1008FF14, // XF86AudioPlay
+        SpecialKey::MediaStop => 0x1008FF15,      // XF86AudioStop
+        SpecialKey::MediaTrackPrevious => 0x1008FF16, // XF86AudioPrev
+        SpecialKey::MediaTrackNext => 0x1008FF17, // XF86AudioNext
+        SpecialKey::AudioVolumeDown => 0x1008FF11, // XF86AudioLowerVolume
+        SpecialKey::AudioVolumeMute => 0x1008FF12, // XF86AudioMute
+        SpecialKey::AudioVolumeUp => 0x1008FF13,  // XF86AudioRaiseVolume
+        SpecialKey::F13 => 0xFFCA,
+        SpecialKey::F14 => 0xFFCB,
+        SpecialKey::F15 => 0xFFCC,
+        SpecialKey::F16 => 0xFFCD,
+        SpecialKey::F17 => 0xFFCE,
+        SpecialKey::F18 => 0xFFCF,
+        SpecialKey::F19 => 0xFFD0,
+        SpecialKey::F20 => 0xFFD1,
+        SpecialKey::F21 => 0xFFD2,
+        SpecialKey::F22 => 0xFFD3,
+        SpecialKey::F23 => 0xFFD4,
+        SpecialKey::F24 => 0xFFD5,
+    }
+}
+
+thread_local! {
+    static GRAB_FAILED: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe extern "C" fn on_grab_error(_: *mut XDisplay, event: *mut XErrorEvent) -> c_int {
+    if (*event).error_code == BAD_ACCESS {
+        GRAB_FAILED.with(|f| f.set(true));
+    }
+    0
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct GrabbedKey {
+    keycode: c_int,
+    modifiers: c_uint,
+}
+
+/// X display of the default GDK display, `None` unless running on X11.
+pub(super) fn x_display() -> Option<*mut XDisplay> {
+    if !is_x11() {
+        return None;
+    }
+    let display = Display::default()?;
+    let display = unsafe { gdk_x11_display_get_xdisplay(display.to_glib_none().0) };
+    if display.is_null() {
+        None
+    } else {
+        Some(display)
+    }
+}
+
+pub struct PlatformHotKeyManager {
+    delegate: Weak<dyn HotKeyManagerDelegate>,
+    hot_keys: RefCell<HashMap<HotKeyHandle, GrabbedKey>>,
+    weak_self: Late<Weak<Self>>,
+    filter_data: Cell<Option<*const PlatformHotKeyManager>>,
+}
 
 impl PlatformHotKeyManager {
-    pub fn new(_delegate: Weak<dyn HotKeyManagerDelegate>) -> Self {
-        Self {}
+    pub fn new(delegate: Weak<dyn HotKeyManagerDelegate>) -> Self {
+        Self {
+            delegate,
+            hot_keys: RefCell::new(HashMap::new()),
+            weak_self: Late::new(),
+            filter_data: Cell::new(None),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformHotKeyManager>) {
+        self.weak_self.set(weak);
+    }
+
+    fn ensure_filter(&self) {
+        if self.filter_data.get().is_some() {
+            return;
+        }
+        // Auto-repeat would otherwise be delivered as release and press
+        // pairs, indistinguishable from the key being pressed again.
+        if let Some(display) = x_display() {
+            unsafe { XkbSetDetectableAutoRepeat(display, 1, std::ptr::null_mut()) };
+        }
+        let data = Weak::into_raw(self.weak_self.clone());
+        unsafe { gdk_window_add_filter(std::ptr::null_mut(), Some(on_x_event), data as *mut _) };
+        self.filter_data.set(Some(data));
     }
 
-    pub fn assign_weak_self(&self, _weak: Weak<PlatformHotKeyManager>) {}
+    fn on_key_event(&self, event: &XKeyEvent) -> bool {
+        let key = GrabbedKey {
+            keycode: event.keycode as c_int,
+            modifiers: event.state & RELEVANT_MODIFIERS,
+        };
+        let handle = self
+            .hot_keys
+            .borrow()
+            .iter()
+            .find(|(_, k)| **k == key)
+            .map(|(handle, _)| *handle);
+        let (Some(handle), Some(delegate)) = (handle, self.delegate.upgrade()) else {
+            return false;
+        };
+        if event.type_ == KEY_PRESS {
+            delegate.on_hot_key_pressed(handle);
+        } else {
+            delegate.on_hot_key_released(handle);
+        }
+        true
+    }
 
     pub fn create_hot_key(
         &self,
-        _handle: HotKeyHandle,
-        _request: HotKeyCreateRequest,
+        handle: HotKeyHandle,
+        request: HotKeyCreateRequest,
     ) -> NativeExtensionsResult<()> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+        let display = x_display().ok_or(NativeExtensionsError::UnsupportedOperation)?;
+        let mut modifiers = 0;
+        if request.shift {
+            modifiers |= SHIFT_MASK;
+        }
+        if request.control {
+            modifiers |= CONTROL_MASK;
+        }
+        if request.alt {
+            modifiers |= MOD1_MASK;
+        }
+        if request.meta {
+            modifiers |= MOD4_MASK;
+        }
+        let keycode = match request.special_key {
+            Some(special_key) => {
+                // Keycode is 0 when keyboard mapping has no key for the keysym.
+                match unsafe { XKeysymToKeycode(display, special_key_to_keysym(special_key)) } {
+                    0 => return Err(NativeExtensionsError::UnsupportedOperation),
+                    keycode => keycode as c_int,
+                }
+            }
+            None => request.platform_code as c_int,
+        };
+        let key = GrabbedKey { keycode, modifiers };
+        let failed = unsafe {
+            let root = XDefaultRootWindow(display);
+            // Failed grab is only reported asynchronously through error handler.
+            XSync(display, 0);
+            GRAB_FAILED.with(|f| f.set(false));
+            let previous_handler = XSetErrorHandler(Some(on_grab_error));
+            for variant in LOCK_VARIANTS {
+                XGrabKey(
+                    display,
+                    key.keycode,
+                    key.modifiers | variant,
+                    root,
+                    0,
+                    GRAB_MODE_ASYNC,
+                    GRAB_MODE_ASYNC,
+                );
+            }
+            XSync(display, 0);
+            XSetErrorHandler(previous_handler);
+            GRAB_FAILED.with(|f| f.get())
+        };
+        if failed {
+            ungrab(display, key);
+            return Err(NativeExtensionsError::OtherError(
+                "Hot key is already grabbed by another application".into(),
+            ));
+        }
+        self.ensure_filter();
+        self.hot_keys.borrow_mut().insert(handle, key);
+        Ok(())
+    }
+
+    pub fn destroy_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<()> {
+        let key = self.hot_keys.borrow_mut().remove(&handle);
+        if let (Some(key), Some(display)) = (key, x_display()) {
+            // Other hot key may share the same grab.
+            if !self.hot_keys.borrow().values().any(|k| *k == key) {
+                ungrab(display, key);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ungrab(display: *mut XDisplay, key: GrabbedKey) {
+    unsafe {
+        let root = XDefaultRootWindow(display);
+        for variant in LOCK_VARIANTS {
+            XUngrabKey(display, key.keycode, key.modifiers | variant, root);
+        }
+        XSync(display, 0);
+    }
+}
+
+unsafe extern "C" fn on_x_event(
+    xevent: *mut GdkXEvent,
+    _event: *mut GdkEvent,
+    data: *mut c_void,
+) -> GdkFilterReturn {
+    let key_event = &*(xevent as *const XKeyEvent);
+    if key_event.type_ != KEY_PRESS && key_event.type_ != KEY_RELEASE {
+        return GDK_FILTER_CONTINUE;
+    }
+    let manager = data as *const PlatformHotKeyManager;
+    // Borrow the weak reference without consuming it.
+    let weak = std::mem::ManuallyDrop::new(Weak::from_raw(manager));
+    match weak.upgrade() {
+        Some(manager) if manager.on_key_event(key_event) => GDK_FILTER_REMOVE,
+        _ => GDK_FILTER_CONTINUE,
     }
+}
 
-    pub fn destroy_hot_key(&self, _handle: HotKeyHandle) -> NativeExtensionsResult<()> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+impl Drop for PlatformHotKeyManager {
+    fn drop(&mut self) {
+        if let Some(display) = x_display() {
+            for key in self.hot_keys.borrow().values() {
+                ungrab(display, *key);
+            }
+        }
+        if let Some(data) = self.filter_data.take() {
+            unsafe {
+                gdk_window_remove_filter(std::ptr::null_mut(), Some(on_x_event), data as *mut _);
+                drop(Weak::from_raw(data));
+            }
+        }
     }
 }
@@ -0,0 +1,41 @@
+//! Platform codec for the `image_transcode` feature, based on GdkPixbuf.
+
+use gdk::gdk_pixbuf::{prelude::PixbufLoaderExt, PixbufLoader};
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+pub fn png_format() -> String {
+    "image/png".into()
+}
+
+pub fn decodable_formats() -> Vec<String> {
+    vec![
+        "image/jpeg".into(),
+        "image/bmp".into(),
+        "image/tiff".into(),
+        "image/gif".into(),
+    ]
+}
+
+/// GTK applications read PNG directly.
+pub fn encodable_formats() -> Vec<String> {
+    Vec::new()
+}
+
+fn glib_error(error: gdk::glib::Error) -> NativeExtensionsError {
+    NativeExtensionsError::OtherError(error.to_string())
+}
+
+pub fn decode_to_png(data: &[u8]) -> NativeExtensionsResult<Vec<u8>> {
+    let loader = PixbufLoader::new();
+    loader.write(data).map_err(glib_error)?;
+    loader.close().map_err(glib_error)?;
+    let pixbuf = loader
+        .pixbuf()
+        .ok_or_else(|| NativeExtensionsError::OtherError("Failed to decode image".into()))?;
+    pixbuf.save_to_bufferv("png", &[]).map_err(glib_error)
+}
+
+pub fn encode_from_png(_data: &[u8], _format: &str) -> NativeExtensionsResult<Vec<u8>> {
+    Err(NativeExtensionsError::UnsupportedOperation)
+}
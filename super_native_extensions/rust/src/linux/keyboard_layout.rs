@@ -1,6 +1,6 @@
 use std::{
     cell::{Cell, RefCell},
-    os::raw::c_ulong,
+    os::raw::{c_char, c_int, c_long, c_uchar, c_ulong, c_void},
     rc::Weak,
 };
 
@@ -16,7 +16,88 @@ use crate::{
     log::OkLog,
 };
 
-use super::signal::Signal;
+use super::{
+    desktop_capabilities::is_x11,
+    hot_key::{x_display, XDefaultRootWindow, XDisplay, XWindow},
+    signal::Signal,
+};
+
+type Atom = c_ulong;
+
+#[link(name = "X11")]
+extern "C" {
+    fn XInternAtom(display: *mut XDisplay, name: *const c_char, only_if_exists: c_int) -> Atom;
+    #[allow(clippy::too_many_arguments)]
+    fn XGetWindowProperty(
+        display: *mut XDisplay,
+        window: XWindow,
+        property: Atom,
+        long_offset: c_long,
+        long_length: c_long,
+        delete: c_int,
+        req_type: Atom,
+        actual_type_return: *mut Atom,
+        actual_format_return: *mut c_int,
+        nitems_return: *mut c_ulong,
+        bytes_after_return: *mut c_ulong,
+        prop_return: *mut *mut c_uchar,
+    ) -> c_int;
+    fn XFree(data: *mut c_void) -> c_int;
+}
+
+const ANY_PROPERTY_TYPE: Atom = 0;
+const SUCCESS: c_int = 0;
+
+/// XKB rules names set on the root window: rules, model, layouts, variants
+/// and options, separated by NUL. Layouts and variants are comma separated
+/// lists with one entry per group.
+fn xkb_rules_names() -> Option<Vec<String>> {
+    if !is_x11() {
+        return None;
+    }
+    let display = x_display()?;
+    unsafe {
+        let atom = XInternAtom(display, b"_XKB_RULES_NAMES\0".as_ptr() as *const c_char, 1);
+        if atom == 0 {
+            return None;
+        }
+        let mut actual_type: Atom = 0;
+        let mut actual_format: c_int = 0;
+        let mut items: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+        let res = XGetWindowProperty(
+            display,
+            XDefaultRootWindow(display),
+            atom,
+            0,
+            1024,
+            0,
+            ANY_PROPERTY_TYPE,
+            &mut actual_type,
+            &mut actual_format,
+            &mut items,
+            &mut bytes_after,
+            &mut data,
+        );
+        if res != SUCCESS || data.is_null() {
+            return None;
+        }
+        let names = if actual_format == 8 {
+            let bytes = std::slice::from_raw_parts(data, items as usize);
+            Some(
+                bytes
+                    .split(|b| *b == 0)
+                    .map(|s| String::from_utf8_lossy(s).into_owned())
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        XFree(data as *mut _);
+        names
+    }
+}
 
 pub struct PlatformKeyboardLayout {
     current_layout: RefCell<Option<KeyboardLayout>>,
@@ -60,6 +141,26 @@ impl PlatformKeyboardLayout {
         )
     }
 
+    /// XKB layout of the current group, with variant if any (i.e. `us(intl)`).
+    pub fn get_current_layout_name(&self) -> Option<String> {
+        let names = xkb_rules_names()?;
+        let group = self.current_group.get() as usize;
+        let layout = names.get(2)?.split(',').nth(group)?.trim().to_owned();
+        if layout.is_empty() {
+            return None;
+        }
+        let variant = names
+            .get(3)
+            .and_then(|variants| variants.split(',').nth(group))
+            .map(str::trim)
+            .unwrap_or_default();
+        Some(if variant.is_empty() {
+            layout
+        } else {
+            format!("{layout}({variant})")
+        })
+    }
+
     fn create_keyboard_layout(&self) -> KeyboardLayout {
         let key_map = get_key_map();
         if let Some(display) = Display::default() {
@@ -19,7 +19,7 @@ use gtk::{
 use gtk_sys::GtkWidget;
 use irondash_engine_context::EngineContext;
 use irondash_message_channel::IsolateId;
-use irondash_run_loop::{spawn, util::FutureCompleter};
+use irondash_run_loop::util::FutureCompleter;
 
 use crate::{
     api_model::{
@@ -28,7 +28,9 @@ use crate::{
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
-    menu_manager::{PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate},
+    menu_manager::{
+        MenuItemStates, PlatformMenuContextDelegate, PlatformMenuContextId, PlatformMenuDelegate,
+    },
 };
 
 use super::common::{surface_from_image_data, synthesize_button_up};
@@ -41,6 +43,27 @@ pub struct PlatformMenuContext {
 pub struct PlatformMenu {
     menu: gtk::Menu,
     item_selected: Rc<Cell<bool>>,
+    states: Rc<ItemStates>,
+}
+
+/// Only actions created with state are check menu items and can be updated.
+#[derive(Default)]
+struct ItemStates {
+    items: MenuItemStates<gtk::CheckMenuItem>,
+    /// Changing active state emits `activate`, which must not be reported as
+    /// action.
+    updating: Cell<bool>,
+}
+
+impl ItemStates {
+    fn apply(&self, updates: Vec<(gtk::CheckMenuItem, MenuActionState)>) {
+        self.updating.set(true);
+        for (item, state) in updates {
+            item.set_active(state == MenuActionState::CheckOn || state == MenuActionState::RadioOn);
+            item.set_inconsistent(state == MenuActionState::CheckMixed);
+        }
+        self.updating.set(false);
+    }
 }
 
 impl std::fmt::Debug for PlatformMenu {
@@ -51,13 +74,15 @@ impl std::fmt::Debug for PlatformMenu {
 
 struct MenuContext {
     item_selected: Rc<Cell<bool>>,
+    states: Rc<ItemStates>,
     on_menu_open_callbacks: Vec<Box<dyn FnOnce(&gtk::Menu)>>,
 }
 
 impl MenuContext {
-    fn new(item_selected: Rc<Cell<bool>>) -> Self {
+    fn new(item_selected: Rc<Cell<bool>>, states: Rc<ItemStates>) -> Self {
         Self {
             item_selected,
+            states,
             on_menu_open_callbacks: Vec::new(),
         }
     }
@@ -152,26 +177,30 @@ impl PlatformMenu {
     fn translate_menu(
         menu: &Menu,
         item_selected: Rc<Cell<bool>>,
+        states: Rc<ItemStates>,
         isolate: IsolateId,
         delegate: &Weak<dyn PlatformMenuDelegate>,
     ) -> gtk::Menu {
         let res = gtk::Menu::new();
-        let mut context = MenuContext::new(item_selected);
+        let mut context = MenuContext::new(item_selected, states);
         for element in &menu.children {
             let menu_item = Self::translate_menu_element(element, &mut context, isolate, delegate);
             res.add(&menu_item);
         }
+        Self::finish_menu(&res, context);
+        res
+    }
 
+    fn finish_menu(menu: &gtk::Menu, context: MenuContext) {
         let callbacks = Rc::new(RefCell::new(Some(context.on_menu_open_callbacks)));
-        res.connect_show(move |menu| {
+        menu.connect_show(move |menu| {
             if let Some(callbacks) = callbacks.take() {
                 for callback in callbacks {
                     callback(menu);
                 }
             }
         });
-        res.show_all();
-        res
+        menu.show_all();
     }
 
     fn translate_menu_element(
@@ -195,6 +224,11 @@ impl PlatformMenu {
                             state == &MenuActionState::RadioOn
                                 || state == &MenuActionState::RadioOff,
                         );
+                        context.states.items.register(
+                            action.unique_id,
+                            res.clone(),
+                            action.radio_group.clone(),
+                        );
                         res.upcast()
                     }
                 };
@@ -231,9 +265,16 @@ impl PlatformMenu {
                 item.set_sensitive(!action.attributes.disabled);
 
                 let item_selected = context.item_selected.clone();
+                let states = Rc::downgrade(&context.states);
                 let delegate = delegate.clone();
                 let unique_id = action.unique_id;
                 item.connect_activate(move |_| {
+                    if let Some(states) = states.upgrade() {
+                        if states.updating.get() {
+                            return;
+                        }
+                        states.apply(states.items.updates_for_activation(unique_id));
+                    }
                     if let Some(delegate) = delegate.upgrade() {
                         item_selected.set(true);
                         delegate.on_action(isolate, unique_id);
@@ -246,56 +287,106 @@ impl PlatformMenu {
                 item.set_label(&Self::convert_mnemonics(
                     menu.title.as_deref().unwrap_or_default(),
                 ));
-                let submenu =
-                    Self::translate_menu(menu, context.item_selected.clone(), isolate, delegate);
+                let submenu = Self::translate_menu(
+                    menu,
+                    context.item_selected.clone(),
+                    context.states.clone(),
+                    isolate,
+                    delegate,
+                );
                 item.set_submenu(Some(&submenu));
                 item
             }
             MenuElement::Deferred(deferred) => {
+                Self::deferred_item(deferred.unique_id, false, context, isolate, delegate)
+            }
+            MenuElement::DeferredMenu(deferred) => {
                 let item = gtk::MenuItem::new();
-                let item_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
-                let spinner = gtk::Spinner::new();
-                spinner.start();
-                item_box.add(&spinner);
-                item.add(&item_box);
-                item.set_sensitive(false);
-
-                let item_clone = item.clone();
-                let delegate = delegate.clone();
-                let unique_id = deferred.unique_id;
-                let item_selected = context.item_selected.clone();
-                context.on_menu_open(move |menu| {
-                    if let Some(delegate) = delegate.upgrade() {
-                        let menu = menu.clone();
-                        spawn(async move {
-                            Self::load_deferred_menu_item(
-                                delegate,
-                                isolate,
-                                unique_id,
-                                menu,
-                                item_clone,
-                                item_selected,
-                            )
-                            .await;
-                        });
-                    }
-                });
-
+                item.set_label(&Self::convert_mnemonics(
+                    deferred.title.as_deref().unwrap_or_default(),
+                ));
+                // Spinner is replaced with the children once the submenu is
+                // shown.
+                let submenu = gtk::Menu::new();
+                let mut submenu_context =
+                    MenuContext::new(context.item_selected.clone(), context.states.clone());
+                let placeholder = Self::deferred_item(
+                    deferred.unique_id,
+                    true,
+                    &mut submenu_context,
+                    isolate,
+                    delegate,
+                );
+                submenu.add(&placeholder);
+                Self::finish_menu(&submenu, submenu_context);
+                item.set_submenu(Some(&submenu));
                 item
             }
             MenuElement::Separator(_) => gtk::SeparatorMenuItem::new().upcast(),
         }
     }
 
+    fn deferred_item(
+        unique_id: i64,
+        submenu: bool,
+        context: &mut MenuContext,
+        isolate: IsolateId,
+        delegate: &Weak<dyn PlatformMenuDelegate>,
+    ) -> gtk::MenuItem {
+        let item = gtk::MenuItem::new();
+        let item_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        let spinner = gtk::Spinner::new();
+        spinner.start();
+        item_box.add(&spinner);
+        item.add(&item_box);
+        item.set_sensitive(false);
+
+        let item_clone = item.clone();
+        let delegate = delegate.clone();
+        let item_selected = context.item_selected.clone();
+        let states = Rc::downgrade(&context.states);
+        context.on_menu_open(move |menu| {
+            if let (Some(delegate), Some(states)) = (delegate.upgrade(), states.upgrade()) {
+                let menu = menu.clone();
+                delegate.clone().spawn(
+                    isolate,
+                    Box::pin(async move {
+                        Self::load_deferred_menu_item(
+                            delegate,
+                            isolate,
+                            unique_id,
+                            submenu,
+                            menu,
+                            item_clone,
+                            item_selected,
+                            states,
+                        )
+                        .await;
+                    }),
+                );
+            }
+        });
+
+        item
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn load_deferred_menu_item(
         delegate: Rc<dyn PlatformMenuDelegate>,
         isolate: IsolateId,
         item_id: i64,
+        submenu: bool,
         menu: gtk::Menu,
         deferred_item: gtk::MenuItem,
         item_selected: Rc<Cell<bool>>,
+        states: Rc<ItemStates>,
     ) {
-        if let Some(result) = delegate.get_deferred_menu(isolate, item_id).await.ok_log() {
+        let result = if submenu {
+            delegate.get_deferred_menu_items(isolate, item_id).await
+        } else {
+            delegate.get_deferred_menu(isolate, item_id).await
+        };
+        if let Some(result) = result.ok_log() {
             let mut current_index = 0;
             let mut actual_index = None::<i32>;
             menu.forall(|item| {
@@ -306,7 +397,7 @@ impl PlatformMenu {
             });
 
             if let Some(mut index) = actual_index {
-                let mut context = MenuContext::new(item_selected);
+                let mut context = MenuContext::new(item_selected, states);
                 for element in result {
                     let translated = Self::translate_menu_element(
                         &element,
@@ -338,12 +429,30 @@ impl PlatformMenu {
         menu: Menu,
     ) -> NativeExtensionsResult<Rc<Self>> {
         let item_selected = Rc::new(Cell::new(false));
-        let menu = Self::translate_menu(&menu, item_selected.clone(), isolate, &delegate);
+        let states = Rc::new(ItemStates::default());
+        let menu = Self::translate_menu(
+            &menu,
+            item_selected.clone(),
+            states.clone(),
+            isolate,
+            &delegate,
+        );
         Ok(Rc::new(Self {
             menu,
             item_selected,
+            states,
         }))
     }
+
+    pub fn update_item_state(
+        &self,
+        unique_id: i64,
+        state: MenuActionState,
+    ) -> NativeExtensionsResult<()> {
+        self.states
+            .apply(self.states.items.updates_for_state(unique_id, state)?);
+        Ok(())
+    }
 }
 
 impl PlatformMenuContext {
@@ -1,19 +1,29 @@
 mod clipboard_async;
+mod clipboard_monitor;
 mod common;
+mod data_control;
 mod data_provider;
+mod desktop_capabilities;
 mod drag;
 mod drag_common;
 mod drop;
 mod hot_key;
+#[cfg(feature = "image_transcode")]
+pub mod image_codec;
 mod keyboard_layout;
 mod menu;
 mod reader;
 mod signal;
+mod status_notifier;
+mod tray_icon;
 
+pub use clipboard_monitor::*;
 pub use data_provider::*;
+pub use desktop_capabilities::{hot_key_capability, tray_capability};
 pub use drag::*;
 pub use drop::*;
 pub use hot_key::*;
 pub use keyboard_layout::*;
 pub use menu::*;
 pub use reader::*;
+pub use tray_icon::*;
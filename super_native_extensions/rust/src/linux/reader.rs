@@ -4,19 +4,24 @@ use std::{
     os::raw::c_uint,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
 };
 
 use gdk::{glib::SignalHandlerId, prelude::ObjectExt, Atom, Display, DragContext};
 use gtk::{traits::WidgetExt, Clipboard, SelectionData, Widget};
 
 use irondash_message_channel::{Late, Value};
-use irondash_run_loop::{spawn, util::FutureCompleter};
+use irondash_run_loop::util::FutureCompleter;
 use url::Url;
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
-    reader_manager::{ReadProgress, VirtualFileReader},
+    link_detection::{DetectedEntity, EntityKind},
+    media_info::MediaMetadata,
+    reader_manager::{
+        ExternalReaderSource, FormatConversion, ItemFormatConversion, ItemMetadata,
+        ReadProgressHandle, VirtualFileReader,
+    },
+    task_scope::TaskScope,
 };
 
 use super::{
@@ -25,9 +30,13 @@ use super::{
 };
 
 pub struct PlatformDataReader {
-    reader: Reader,
+    reader: Rc<Reader>,
+    /// Whether background initialization was started.
     initializing: Cell<bool>,
     inner: Late<Inner>,
+    /// Background initialization started by [`PlatformDataReader::reader_info`];
+    /// aborted when the reader is dropped.
+    tasks: TaskScope,
 }
 
 struct Inner {
@@ -77,30 +86,34 @@ pub struct ReaderInfo {
 
 impl PlatformDataReader {
     async fn init(&self) {
-        if !self.inner.is_set() && !self.initializing.get() {
-            self.initializing.set(true);
-            let mut targets = self.reader.get_targets().await;
-            let has_text = targets
-                .iter()
-                .any(|t| target_includes_text(&Atom::intern(t)));
-            if has_text {
-                // framework part only recognizes text/plain as text. Make sure
-                // to include it in types.
-                let has_text_type = targets.iter().any(|t| t == TYPE_TEXT);
-                if !has_text_type {
-                    targets.push(TYPE_TEXT.into());
-                }
-            }
-            let uris = if targets.iter().any(|t| t == TYPE_URI) {
-                self.reader.get_uri_list().await
-            } else {
-                Vec::new()
-            };
+        if !self.inner.is_set() {
+            let inner = Self::load(&self.reader).await;
             // double check - we might have been preempted
             if !self.inner.is_set() {
-                self.inner.set(Inner { targets, uris })
+                self.inner.set(inner)
+            }
+        }
+    }
+
+    async fn load(reader: &Reader) -> Inner {
+        let mut targets = reader.get_targets().await;
+        let has_text = targets
+            .iter()
+            .any(|t| target_includes_text(&Atom::intern(t)));
+        if has_text {
+            // framework part only recognizes text/plain as text. Make sure
+            // to include it in types.
+            let has_text_type = targets.iter().any(|t| t == TYPE_TEXT);
+            if !has_text_type {
+                targets.push(TYPE_TEXT.into());
             }
         }
+        let uris = if targets.iter().any(|t| t == TYPE_URI) {
+            reader.get_uri_list().await
+        } else {
+            Vec::new()
+        };
+        Inner { targets, uris }
     }
 
     pub fn reader_info(self: &Rc<Self>) -> Option<ReaderInfo> {
@@ -109,12 +122,20 @@ impl PlatformDataReader {
                 number_of_items: 1.max(self.inner.uris.len()),
                 targets: self.inner.targets.clone(),
             })
-        } else {
-            let this = self.clone();
-            spawn(async move {
-                this.init().await;
+        } else if !self.initializing.replace(true) {
+            let this = Rc::downgrade(self);
+            let reader = self.reader.clone();
+            self.tasks.spawn(async move {
+                let inner = Self::load(&reader).await;
+                if let Some(this) = this.upgrade() {
+                    if !this.inner.is_set() {
+                        this.inner.set(inner);
+                    }
+                }
             });
             None
+        } else {
+            None
         }
     }
 
@@ -175,7 +196,7 @@ impl PlatformDataReader {
         &self,
         item: i64,
         data_type: String,
-        _progress: Option<Arc<ReadProgress>>,
+        _progress: Option<ReadProgressHandle>,
     ) -> NativeExtensionsResult<Value> {
         let item = item as usize;
         if data_type == TYPE_URI && item < self.inner.uris.len() {
@@ -193,30 +214,107 @@ impl PlatformDataReader {
         }
     }
 
+    /// Formats from which `fileUriList` is synthesized.
+    pub fn file_uri_list_source_formats() -> Vec<String> {
+        vec![TYPE_URI.into()]
+    }
+
     pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
         unsafe { gtk::set_initialized() };
         let display = Display::default()
             .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
         let clipboard = Clipboard::default(&display)
             .ok_or_else(|| NativeExtensionsError::OtherError("Clipboard not found".into()))?;
-        let res = Rc::new(PlatformDataReader {
-            reader: Reader::Clipboard(ClipboardReader { clipboard }),
+        Ok(Self::new_with_clipboard(clipboard))
+    }
+
+    /// Reader for the PRIMARY selection (text selected in any application,
+    /// pasted with middle click).
+    pub fn new_primary_selection_reader() -> NativeExtensionsResult<Rc<Self>> {
+        unsafe { gtk::set_initialized() };
+        let display = Display::default()
+            .ok_or_else(|| NativeExtensionsError::OtherError("Display not found".into()))?;
+        let clipboard = Clipboard::for_display(&display, &Atom::intern("PRIMARY"));
+        Ok(Self::new_with_clipboard(clipboard))
+    }
+
+    fn new_with_clipboard(clipboard: Clipboard) -> Rc<Self> {
+        Rc::new(PlatformDataReader {
+            reader: Rc::new(Reader::Clipboard(ClipboardReader { clipboard })),
             initializing: Cell::new(false),
             inner: Late::new(),
-        });
-        Ok(res)
+            tasks: TaskScope::new("PlatformDataReader"),
+        })
+    }
+
+    pub fn new_with_external_source(
+        _source: ExternalReaderSource,
+    ) -> NativeExtensionsResult<Rc<Self>> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Out of process reading is only supported on Windows.
+    pub fn set_out_of_process_reading(_enabled: bool) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Formats are identified by name already; there is nothing to register.
+    pub fn register_clipboard_format(
+        _identifier: &str,
+        _platform_name: &str,
+    ) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+
+    /// Universal Clipboard is only available on Apple platforms.
+    pub fn is_remote_content(&self) -> NativeExtensionsResult<bool> {
+        Ok(false)
+    }
+
+    pub fn set_exclude_remote_content(_exclude: bool) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// GTK clipboard has no change counter; clipboard tokens are validated
+    /// by comparing formats.
+    pub fn clipboard_change_count() -> NativeExtensionsResult<Option<i64>> {
+        Ok(None)
+    }
+
+    /// Availability is determined by enumerating clipboard items.
+    pub fn clipboard_has_any_format(_formats: &[String]) -> NativeExtensionsResult<Option<bool>> {
+        Ok(None)
+    }
+
+    /// Entity detection is not provided by the platform, built-in detector
+    /// is used instead.
+    pub fn detect_entities(
+        _text: &str,
+        _kinds: &[EntityKind],
+    ) -> NativeExtensionsResult<Option<Vec<DetectedEntity>>> {
+        Ok(None)
     }
 
     pub fn new_with_widget_reader(
         widget_reader: Rc<WidgetReader>,
     ) -> NativeExtensionsResult<Rc<Self>> {
         Ok(Rc::new(PlatformDataReader {
-            reader: Reader::Widget(widget_reader),
+            reader: Rc::new(Reader::Widget(widget_reader)),
             initializing: Cell::new(false),
             inner: Late::new(),
+            tasks: TaskScope::new("PlatformDataReader"),
         }))
     }
 
+    pub fn set_format_conversion_enabled(&self, _conversion: FormatConversion, _enabled: bool) {}
+
+    pub fn get_format_conversions_for_item(
+        &self,
+        _item: i64,
+    ) -> NativeExtensionsResult<Vec<ItemFormatConversion>> {
+        Ok(Vec::new())
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
@@ -225,6 +323,39 @@ impl PlatformDataReader {
         Ok(false)
     }
 
+    /// Metafiles are Windows only.
+    pub async fn rasterize_metafile_for_item(
+        &self,
+        _item: i64,
+        _width: Option<i64>,
+        _height: Option<i64>,
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Metadata of local file the item refers to, if any.
+    pub async fn get_item_metadata(&self, item: i64) -> NativeExtensionsResult<ItemMetadata> {
+        self.init().await;
+        let path = self
+            .inner
+            .uris
+            .get(item as usize)
+            .and_then(|u| Url::parse(u).ok())
+            .and_then(|u| u.to_file_path().ok());
+        Ok(path
+            .map(|path| ItemMetadata::for_local_file(&path))
+            .unwrap_or_default())
+    }
+
+    /// No platform media API is used here; media info is parsed from the
+    /// container instead.
+    pub async fn get_media_metadata_for_item(
+        &self,
+        _item: i64,
+    ) -> NativeExtensionsResult<Option<MediaMetadata>> {
+        Ok(None)
+    }
+
     pub async fn can_copy_virtual_file_for_item(
         &self,
         _item: i64,
@@ -245,7 +376,18 @@ impl PlatformDataReader {
         &self,
         _item: i64,
         _format: &str,
-        _progress: Arc<ReadProgress>,
+        _progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        Ok(None)
+    }
+
+    /// Item data is only available in memory; caller falls back to
+    /// `get_data_for_item`.
+    pub async fn get_data_stream_for_item(
+        &self,
+        _item: i64,
+        _format: &str,
+        _progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
         Ok(None)
     }
@@ -255,9 +397,9 @@ impl PlatformDataReader {
         _item: i64,
         _format: &str,
         _target_folder: PathBuf,
-        _progress: Arc<ReadProgress>,
+        _progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<PathBuf> {
-        Err(NativeExtensionsError::UnsupportedOperation)
+        Err(NativeExtensionsError::VirtualFileUnsupported)
     }
 }
 
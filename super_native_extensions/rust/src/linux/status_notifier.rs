@@ -0,0 +1,246 @@
+//! Minimal `org.kde.StatusNotifierItem` implementation.
+//!
+//! Each item uses its own session bus connection and is registered with the
+//! watcher by object path, so the watcher uses the connection's unique name
+//! as the service. Closing the connection makes the item disappear. No D-Bus
+//! menu is exported, context menu requests are reported as right clicks.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk::{
+    gio::{
+        self, BusType, Cancellable, DBusAuthObserver, DBusCallFlags, DBusConnection,
+        DBusConnectionFlags, DBusNodeInfo, RegistrationId,
+    },
+    glib::{self, ToVariant},
+};
+
+use crate::{
+    api_model::{ImageData, Point},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    tray_icon_manager::TrayIconEventType,
+};
+
+use super::desktop_capabilities::STATUS_NOTIFIER_WATCHER;
+
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const REGISTER_TIMEOUT_MS: i32 = 5000;
+
+const ITEM_XML: &str = r#"
+<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <property name="IconPixmap" type="a(iiay)" access="read"/>
+    <property name="ToolTip" type="(sa(iiay)ss)" access="read"/>
+    <method name="Activate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="SecondaryActivate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="ContextMenu">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="Scroll">
+      <arg name="delta" type="i" direction="in"/>
+      <arg name="orientation" type="s" direction="in"/>
+    </method>
+    <signal name="NewIcon"/>
+    <signal name="NewToolTip"/>
+  </interface>
+</node>
+"#;
+
+type Pixmap = (i32, i32, Vec<u8>);
+
+struct ItemState {
+    id: String,
+    icon: Vec<Pixmap>,
+    tooltip: String,
+}
+
+pub struct StatusNotifierItem {
+    connection: DBusConnection,
+    registration: Option<RegistrationId>,
+    state: Rc<RefCell<ItemState>>,
+}
+
+/// Converts RGBA image to ARGB32 in network byte order.
+fn pixmap_from_image_data(image: &ImageData) -> NativeExtensionsResult<Pixmap> {
+    if !image.is_valid() {
+        return Err(NativeExtensionsError::OtherError(
+            "Invalid tray icon image data".into(),
+        ));
+    }
+    let mut data = Vec::with_capacity((image.width * image.height * 4) as usize);
+    for y in 0..image.height as usize {
+        let row = &image.data[y * image.bytes_per_row as usize..];
+        for pixel in row[..image.width as usize * 4].chunks_exact(4) {
+            data.extend_from_slice(&[pixel[3], pixel[0], pixel[1], pixel[2]]);
+        }
+    }
+    Ok((image.width, image.height, data))
+}
+
+thread_local! {
+    static NEXT_ITEM_INDEX: Cell<u32> = const { Cell::new(1) };
+}
+
+/// Hosts may remember per-item settings (such as visibility) by id, so the
+/// id is based on application name rather than process id.
+fn next_item_id() -> String {
+    let index = NEXT_ITEM_INDEX.with(|i| i.replace(i.get() + 1));
+    let name = glib::prgname()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| "flutter".to_owned());
+    format!("{name}-{index}")
+}
+
+fn dbus_error(error: glib::Error) -> NativeExtensionsError {
+    NativeExtensionsError::OtherError(error.to_string())
+}
+
+async fn private_session_connection() -> NativeExtensionsResult<DBusConnection> {
+    // Only resolves the address from environment, does not connect.
+    let address = gio::dbus_address_get_for_bus_sync(BusType::Session, Cancellable::NONE)
+        .map_err(dbus_error)?;
+    DBusConnection::for_address_future(
+        &address,
+        DBusConnectionFlags::AUTHENTICATION_CLIENT | DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+        DBusAuthObserver::NONE,
+    )
+    .await
+    .map_err(dbus_error)
+}
+
+impl StatusNotifierItem {
+    pub async fn new(
+        image: &ImageData,
+        tooltip: Option<String>,
+        on_event: Box<dyn Fn(TrayIconEventType, Point)>,
+    ) -> NativeExtensionsResult<Self> {
+        let icon = pixmap_from_image_data(image)?;
+        let connection = private_session_connection().await?;
+        let state = Rc::new(RefCell::new(ItemState {
+            id: next_item_id(),
+            icon: vec![icon],
+            tooltip: tooltip.unwrap_or_default(),
+        }));
+        let node = DBusNodeInfo::for_xml(ITEM_XML)
+            .map_err(|e| NativeExtensionsError::OtherError(e.to_string()))?;
+        let interface = node
+            .lookup_interface(ITEM_INTERFACE)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Missing interface".into()))?;
+        let property_state = state.clone();
+        let registration = connection
+            .register_object(
+                ITEM_PATH,
+                &interface,
+                move |_, _, _, _, method, parameters, invocation| {
+                    let position = parameters
+                        .get::<(i32, i32)>()
+                        .map(|(x, y)| Point {
+                            x: x as f64,
+                            y: y as f64,
+                        })
+                        .unwrap_or_default();
+                    let event_type = match method {
+                        "Activate" => Some(TrayIconEventType::LeftClick),
+                        "SecondaryActivate" => Some(TrayIconEventType::MiddleClick),
+                        "ContextMenu" => Some(TrayIconEventType::RightClick),
+                        _ => None,
+                    };
+                    invocation.return_value(None);
+                    if let Some(event_type) = event_type {
+                        on_event(event_type, position);
+                    }
+                },
+                move |_, _, _, _, property| {
+                    let state = property_state.borrow();
+                    match property {
+                        "Category" => "ApplicationStatus".to_variant(),
+                        "Id" => state.id.to_variant(),
+                        "Title" => state.tooltip.to_variant(),
+                        "Status" => "Active".to_variant(),
+                        "ItemIsMenu" => false.to_variant(),
+                        "IconPixmap" => state.icon.to_variant(),
+                        "ToolTip" => (
+                            String::new(),
+                            Vec::<Pixmap>::new(),
+                            state.tooltip.clone(),
+                            String::new(),
+                        )
+                            .to_variant(),
+                        _ => "".to_variant(),
+                    }
+                },
+                |_, _, _, _, _, _| false,
+            )
+            .map_err(|e| NativeExtensionsError::OtherError(e.to_string()))?;
+        let item = Self {
+            connection,
+            registration: Some(registration),
+            state,
+        };
+        item.register_with_watcher().await?;
+        Ok(item)
+    }
+
+    async fn register_with_watcher(&self) -> NativeExtensionsResult<()> {
+        self.connection
+            .call_future(
+                Some(STATUS_NOTIFIER_WATCHER),
+                "/StatusNotifierWatcher",
+                STATUS_NOTIFIER_WATCHER,
+                "RegisterStatusNotifierItem",
+                Some(&(ITEM_PATH,).to_variant()),
+                None,
+                DBusCallFlags::NONE,
+                REGISTER_TIMEOUT_MS,
+            )
+            .await
+            .map_err(dbus_error)?;
+        Ok(())
+    }
+
+    fn emit(&self, signal: &str) {
+        self.connection
+            .emit_signal(None, ITEM_PATH, ITEM_INTERFACE, signal, None)
+            .ok_log();
+    }
+
+    pub fn set_image(&self, image: &ImageData) -> NativeExtensionsResult<()> {
+        self.state.borrow_mut().icon = vec![pixmap_from_image_data(image)?];
+        self.emit("NewIcon");
+        Ok(())
+    }
+
+    pub fn set_tooltip(&self, tooltip: &str) {
+        self.state.borrow_mut().tooltip = tooltip.to_owned();
+        self.emit("NewToolTip");
+    }
+}
+
+impl Drop for StatusNotifierItem {
+    fn drop(&mut self) {
+        if let Some(registration) = self.registration.take() {
+            self.connection.unregister_object(registration).ok_log();
+        }
+        // Watcher removes the item once its bus name vanishes.
+        self.connection.close(Cancellable::NONE, |res| {
+            res.ok_log();
+        });
+    }
+}
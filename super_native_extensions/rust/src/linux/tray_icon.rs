@@ -0,0 +1,274 @@
+use std::{cell::RefCell, collections::HashMap, ffi::CString, rc::Weak};
+
+use gdk::{
+    gdk_pixbuf::{Colorspace, Pixbuf},
+    glib::{
+        translate::{from_glib_full, ToGlibPtr},
+        Object, ToValue,
+    },
+    prelude::ObjectExt,
+    Event, EventType,
+};
+use glib_sys::{GFALSE, GTRUE};
+use gtk_sys::{
+    gtk_status_icon_get_geometry, gtk_status_icon_new_from_pixbuf, gtk_status_icon_set_from_pixbuf,
+    gtk_status_icon_set_has_tooltip, gtk_status_icon_set_tooltip_text, gtk_status_icon_set_visible,
+    GtkStatusIcon,
+};
+use irondash_message_channel::Late;
+
+use crate::{
+    api_model::{DesktopFeatureBackend, ImageData, Point},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    tray_icon_manager::{
+        TrayIconCreateRequest, TrayIconEventType, TrayIconHandle, TrayIconManagerDelegate,
+    },
+};
+
+use super::{desktop_capabilities::tray_capability, status_notifier::StatusNotifierItem};
+
+/// XEmbed icon; only works on X11 with a system tray that supports it.
+struct StatusIcon {
+    status_icon: Object,
+}
+
+impl StatusIcon {
+    fn as_ptr(&self) -> *mut GtkStatusIcon {
+        let object: *mut gobject_sys::GObject = self.status_icon.to_glib_none().0;
+        object as *mut _
+    }
+
+    fn set_image(&self, image: ImageData) -> NativeExtensionsResult<()> {
+        let pixbuf = pixbuf_from_image_data(image)?;
+        unsafe { gtk_status_icon_set_from_pixbuf(self.as_ptr(), pixbuf.to_glib_none().0) };
+        Ok(())
+    }
+
+    fn set_tooltip(&self, tooltip: &str) {
+        let tooltip = CString::new(tooltip).unwrap_or_default();
+        unsafe { gtk_status_icon_set_tooltip_text(self.as_ptr(), tooltip.as_ptr()) };
+    }
+}
+
+impl Drop for StatusIcon {
+    fn drop(&mut self) {
+        // Status icon may be still referenced by the tray host, make sure it
+        // disappears.
+        unsafe { gtk_status_icon_set_visible(self.as_ptr(), GFALSE) };
+    }
+}
+
+enum TrayIcon {
+    XEmbed(StatusIcon),
+    StatusNotifier(StatusNotifierItem),
+}
+
+impl TrayIcon {
+    fn set_image(&self, image: ImageData) -> NativeExtensionsResult<()> {
+        match self {
+            TrayIcon::XEmbed(icon) => icon.set_image(image),
+            TrayIcon::StatusNotifier(item) => item.set_image(&image),
+        }
+    }
+
+    fn set_tooltip(&self, tooltip: &str) {
+        match self {
+            TrayIcon::XEmbed(icon) => icon.set_tooltip(tooltip),
+            TrayIcon::StatusNotifier(item) => item.set_tooltip(tooltip),
+        }
+    }
+}
+
+fn pixbuf_from_image_data(image: ImageData) -> NativeExtensionsResult<Pixbuf> {
+    if !image.is_valid() {
+        return Err(NativeExtensionsError::OtherError(
+            "Invalid tray icon image data".into(),
+        ));
+    }
+    Ok(Pixbuf::from_mut_slice(
+        image.data,
+        Colorspace::Rgb,
+        true,
+        8,
+        image.width,
+        image.height,
+        image.bytes_per_row,
+    ))
+}
+
+pub struct PlatformTrayIconManager {
+    delegate: Weak<dyn TrayIconManagerDelegate>,
+    icons: RefCell<HashMap<TrayIconHandle, TrayIcon>>,
+    weak_self: Late<Weak<Self>>,
+}
+
+impl PlatformTrayIconManager {
+    pub fn new(delegate: Weak<dyn TrayIconManagerDelegate>) -> Self {
+        Self {
+            delegate,
+            icons: RefCell::new(HashMap::new()),
+            weak_self: Late::new(),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformTrayIconManager>) {
+        self.weak_self.set(weak);
+    }
+
+    fn on_event(&self, handle: TrayIconHandle, event_type: TrayIconEventType, position: Point) {
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.on_tray_icon_event(handle, event_type, position);
+        }
+    }
+
+    fn on_button_press(&self, handle: TrayIconHandle, event: &Event) {
+        let event_type = match (event.event_type(), event.button()) {
+            (EventType::ButtonPress, Some(1)) => TrayIconEventType::LeftClick,
+            (EventType::DoubleButtonPress, Some(1)) => TrayIconEventType::DoubleClick,
+            (EventType::ButtonPress, Some(2)) => TrayIconEventType::MiddleClick,
+            (EventType::ButtonPress, Some(3)) => TrayIconEventType::RightClick,
+            _ => return,
+        };
+        let (x, y) = event.root_coords().unwrap_or_default();
+        self.on_event(handle, event_type, Point { x, y });
+    }
+
+    fn on_query_tooltip(&self, handle: TrayIconHandle, x: i32, y: i32) {
+        let icon_origin = self.icons.borrow().get(&handle).and_then(|icon| {
+            let TrayIcon::XEmbed(icon) = icon else {
+                return None;
+            };
+            let mut area = gdk_sys::GdkRectangle {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+            };
+            unsafe {
+                gtk_status_icon_get_geometry(
+                    icon.as_ptr(),
+                    std::ptr::null_mut(),
+                    &mut area,
+                    std::ptr::null_mut(),
+                )
+            };
+            Some((area.x, area.y))
+        });
+        if let Some((origin_x, origin_y)) = icon_origin {
+            let position = Point {
+                x: (origin_x + x) as f64,
+                y: (origin_y + y) as f64,
+            };
+            self.on_event(handle, TrayIconEventType::TooltipRequested, position);
+        }
+    }
+
+    pub async fn create_tray_icon(
+        &self,
+        handle: TrayIconHandle,
+        request: TrayIconCreateRequest,
+    ) -> NativeExtensionsResult<()> {
+        let icon = match tray_capability().await.backend {
+            Some(DesktopFeatureBackend::StatusNotifier) => {
+                self.create_status_notifier_item(handle, request).await?
+            }
+            Some(DesktopFeatureBackend::XEmbed) => {
+                TrayIcon::XEmbed(self.create_status_icon(handle, request)?)
+            }
+            _ => return Err(NativeExtensionsError::UnsupportedOperation),
+        };
+        self.icons.borrow_mut().insert(handle, icon);
+        Ok(())
+    }
+
+    async fn create_status_notifier_item(
+        &self,
+        handle: TrayIconHandle,
+        request: TrayIconCreateRequest,
+    ) -> NativeExtensionsResult<TrayIcon> {
+        let weak_self = self.weak_self.clone();
+        // Custom tooltips are not possible, the host draws tooltip itself.
+        let item = StatusNotifierItem::new(
+            &request.image,
+            request.tooltip,
+            Box::new(move |event_type, position| {
+                if let Some(this) = weak_self.upgrade() {
+                    this.on_event(handle, event_type, position);
+                }
+            }),
+        )
+        .await?;
+        Ok(TrayIcon::StatusNotifier(item))
+    }
+
+    fn create_status_icon(
+        &self,
+        handle: TrayIconHandle,
+        request: TrayIconCreateRequest,
+    ) -> NativeExtensionsResult<StatusIcon> {
+        let pixbuf = pixbuf_from_image_data(request.image)?;
+        let status_icon = unsafe { gtk_status_icon_new_from_pixbuf(pixbuf.to_glib_none().0) };
+        if status_icon.is_null() {
+            return Err(NativeExtensionsError::OtherError(
+                "Failed to create status icon".into(),
+            ));
+        }
+        let icon = StatusIcon {
+            status_icon: unsafe { from_glib_full(status_icon as *mut gobject_sys::GObject) },
+        };
+
+        let weak_self = self.weak_self.clone();
+        icon.status_icon
+            .connect_local("button-press-event", false, move |args| {
+                let event = args.get(1).and_then(|e| e.get::<Event>().ok());
+                if let (Some(this), Some(event)) = (weak_self.upgrade(), event) {
+                    this.on_button_press(handle, &event);
+                }
+                Some(false.to_value())
+            });
+
+        if request.custom_tooltip {
+            unsafe { gtk_status_icon_set_has_tooltip(icon.as_ptr(), GTRUE) };
+            let weak_self = self.weak_self.clone();
+            icon.status_icon
+                .connect_local("query-tooltip", false, move |args| {
+                    let x = args.get(1).and_then(|v| v.get::<i32>().ok());
+                    let y = args.get(2).and_then(|v| v.get::<i32>().ok());
+                    if let (Some(this), Some(x), Some(y)) = (weak_self.upgrade(), x, y) {
+                        this.on_query_tooltip(handle, x, y);
+                    }
+                    // Tooltip is presented by the application.
+                    Some(false.to_value())
+                });
+        } else if let Some(tooltip) = request.tooltip {
+            icon.set_tooltip(&tooltip);
+        }
+        Ok(icon)
+    }
+
+    pub fn update_tray_icon(
+        &self,
+        handle: TrayIconHandle,
+        image: Option<ImageData>,
+        tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        let icons = self.icons.borrow();
+        let icon = icons
+            .get(&handle)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Tray icon not found".into()))?;
+        if let Some(image) = image {
+            icon.set_image(image)?;
+        }
+        if let Some(tooltip) = tooltip {
+            icon.set_tooltip(&tooltip);
+        }
+        Ok(())
+    }
+
+    pub fn destroy_tray_icon(&self, handle: TrayIconHandle) -> NativeExtensionsResult<()> {
+        // Drop outside of borrow, disposing the icon may emit signals.
+        let icon = self.icons.borrow_mut().remove(&handle);
+        drop(icon);
+        Ok(())
+    }
+}
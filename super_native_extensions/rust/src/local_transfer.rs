@@ -0,0 +1,618 @@
+//! Direct transfer of large payloads between applications on same machine.
+//!
+//! Pasteboards and drag sessions are poorly suited for multi-gigabyte
+//! payloads; data is usually buffered in memory at least once on each side
+//! and some platforms impose hard size limits. When both source and target
+//! are built with this plugin, source can register the payload here and
+//! advertise it through small descriptor in [`FORMAT_LOCAL_TRANSFER`]. Target
+//! then connects to private local endpoint (abstract unix socket on Linux and
+//! Android, socket file on Apple platforms, named pipe on Windows) and
+//! streams the payload directly into file.
+//!
+//! Only holder of the random token contained in descriptor can fetch the
+//! payload. When the endpoint can not be reached (i.e. sandboxed
+//! application or source already gone) the read returns `None` and caller is
+//! expected to fall back to regular representations.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+};
+
+use irondash_message_channel::{
+    IntoValue, IsolateId, MethodCall, MethodCallReply, MethodHandler, PlatformError,
+    PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
+};
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop,
+};
+use log::warn;
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::{
+    cancellation::CancellationToken,
+    context::Context,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    platform::PlatformDataReader,
+    reader_manager::ReadProgressHandle,
+    value_coerce::{CoerceToData, StringFormat},
+};
+
+/// Format under which the descriptor of local transfer is advertised.
+pub const FORMAT_LOCAL_TRANSFER: &str = "dev.nativeshell.local-transfer";
+
+const PROTOCOL_VERSION: &str = "1";
+
+enum Payload {
+    File(PathBuf),
+    Data(Arc<Vec<u8>>),
+}
+
+struct PayloadEntry {
+    isolate: IsolateId,
+    payload: Payload,
+}
+
+type Payloads = Arc<Mutex<HashMap<String, PayloadEntry>>>;
+
+/// Listens on local endpoint and serves registered payloads. Started lazily
+/// when first payload is registered and kept running for the lifetime of
+/// the process.
+struct Server {
+    endpoint: String,
+    payloads: Payloads,
+}
+
+static SERVER: OnceLock<Option<Server>> = OnceLock::new();
+
+impl Server {
+    fn get() -> Option<&'static Server> {
+        SERVER
+            .get_or_init(|| match Self::start() {
+                Ok(server) => Some(server),
+                Err(err) => {
+                    warn!("Failed to start local transfer server: {err}");
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    fn start() -> io::Result<Server> {
+        let (listener, endpoint) = endpoint::bind()?;
+        let payloads = Payloads::default();
+        let payloads_clone = payloads.clone();
+        thread::spawn(move || loop {
+            match listener.accept() {
+                Ok(stream) => {
+                    let payloads = payloads_clone.clone();
+                    thread::spawn(move || {
+                        Self::serve(stream, &payloads).ok_log();
+                    });
+                }
+                Err(err) => {
+                    warn!("Local transfer server stopped: {err}");
+                    break;
+                }
+            }
+        });
+        Ok(Server { endpoint, payloads })
+    }
+
+    /// Handles single connection. Client sends token terminated by newline;
+    /// server responds with `ok <size>` or `error <message>` line followed by
+    /// raw payload bytes.
+    fn serve(stream: endpoint::Stream, payloads: &Payloads) -> io::Result<()> {
+        let mut reader = BufReader::new(stream);
+        let token = read_header_line(&mut reader)?;
+        let payload =
+            payloads
+                .lock()
+                .unwrap()
+                .get(token.trim_end())
+                .map(|entry| match &entry.payload {
+                    Payload::File(path) => Payload::File(path.clone()),
+                    Payload::Data(data) => Payload::Data(data.clone()),
+                });
+        let stream = reader.get_mut();
+        match payload {
+            Some(Payload::File(path)) => match File::open(&path) {
+                Ok(mut file) => {
+                    let size = file.metadata()?.len();
+                    writeln!(stream, "ok {size}")?;
+                    io::copy(&mut file, stream)?;
+                }
+                Err(err) => writeln!(stream, "error {err}")?,
+            },
+            Some(Payload::Data(data)) => {
+                writeln!(stream, "ok {}", data.len())?;
+                stream.write_all(&data)?;
+            }
+            None => writeln!(stream, "error unknown token")?,
+        }
+        stream.flush()
+    }
+
+    fn register(&self, isolate: IsolateId, payload: Payload) -> String {
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        self.payloads
+            .lock()
+            .unwrap()
+            .insert(token.clone(), PayloadEntry { isolate, payload });
+        token
+    }
+
+    fn unregister(&self, token: &str) {
+        self.payloads.lock().unwrap().remove(token);
+    }
+
+    fn unregister_isolate(&self, isolate: IsolateId) {
+        self.payloads
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.isolate != isolate);
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod endpoint {
+    use std::{
+        io,
+        os::unix::net::{SocketAddr, UnixListener, UnixStream},
+        path::PathBuf,
+    };
+
+    #[cfg(target_os = "android")]
+    use std::os::android::net::SocketAddrExt;
+    #[cfg(target_os = "linux")]
+    use std::os::linux::net::SocketAddrExt;
+
+    pub type Stream = UnixStream;
+
+    pub struct Listener(UnixListener);
+
+    impl Listener {
+        pub fn accept(&self) -> io::Result<Stream> {
+            self.0.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    /// Abstract sockets don't leave anything behind on the filesystem.
+    pub fn bind() -> io::Result<(Listener, String)> {
+        let name = format!("sne-{}-{}", std::process::id(), rand::random::<u32>());
+        let addr = SocketAddr::from_abstract_name(name.as_bytes())?;
+        Ok((Listener(UnixListener::bind_addr(&addr)?), name))
+    }
+
+    pub fn connect(endpoint: &str) -> io::Result<Stream> {
+        let addr = SocketAddr::from_abstract_name(endpoint.as_bytes())?;
+        UnixStream::connect_addr(&addr)
+    }
+
+    pub fn remove_stale() -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod endpoint {
+    use std::{
+        fs, io,
+        os::unix::net::{UnixListener, UnixStream},
+        path::PathBuf,
+    };
+
+    pub type Stream = UnixStream;
+
+    pub struct Listener(UnixListener);
+
+    impl Listener {
+        pub fn accept(&self) -> io::Result<Stream> {
+            self.0.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    /// Socket lives in temporary directory. For sandboxed applications this
+    /// is inside the container and thus unreachable by other applications,
+    /// in which case reader falls back to regular representations.
+    pub fn bind() -> io::Result<(Listener, String)> {
+        let path = std::env::temp_dir().join(format!(
+            "sne-{}-{}.sock",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let listener = UnixListener::bind(&path)?;
+        Ok((Listener(listener), path.to_string_lossy().into()))
+    }
+
+    pub fn connect(endpoint: &str) -> io::Result<Stream> {
+        UnixStream::connect(endpoint)
+    }
+
+    /// Removes socket files of processes that are no longer listening.
+    pub fn remove_stale() -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(std::env::temp_dir()) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                name.starts_with("sne-") && name.ends_with(".sock")
+            })
+            .filter(|path| {
+                matches!(
+                    UnixStream::connect(path),
+                    Err(err) if err.kind() == io::ErrorKind::ConnectionRefused
+                ) && fs::remove_file(path).is_ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod endpoint {
+    use std::{
+        fs::File,
+        io,
+        os::windows::{io::FromRawHandle, prelude::OsStrExt},
+        path::PathBuf,
+    };
+
+    use windows::{
+        core::PCWSTR,
+        Win32::{
+            Foundation::{ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE},
+            Storage::FileSystem::PIPE_ACCESS_DUPLEX,
+            System::Pipes::{
+                ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+            },
+        },
+    };
+
+    pub type Stream = File;
+
+    pub struct Listener {
+        name: Vec<u16>,
+    }
+
+    impl Listener {
+        /// Creates new pipe instance and waits for client to connect to it.
+        pub fn accept(&self) -> io::Result<Stream> {
+            let handle = unsafe {
+                CreateNamedPipeW(
+                    PCWSTR(self.name.as_ptr()),
+                    PIPE_ACCESS_DUPLEX,
+                    PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                    PIPE_UNLIMITED_INSTANCES,
+                    64 * 1024,
+                    64 * 1024,
+                    0,
+                    None,
+                )
+            };
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+            // Closes the pipe instance when dropped
+            let file = unsafe { File::from_raw_handle(handle.0 as _) };
+            if let Err(err) = unsafe { ConnectNamedPipe(handle, None) } {
+                // Client connected between CreateNamedPipe and ConnectNamedPipe
+                if err.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+                    return Err(io::Error::from_raw_os_error(err.code().0 & 0xFFFF));
+                }
+            }
+            Ok(file)
+        }
+    }
+
+    pub fn bind() -> io::Result<(Listener, String)> {
+        let name = format!(
+            r"\\.\pipe\sne-{}-{}",
+            std::process::id(),
+            rand::random::<u32>()
+        );
+        let wide_name = std::ffi::OsStr::new(&name)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        Ok((Listener { name: wide_name }, name))
+    }
+
+    pub fn connect(endpoint: &str) -> io::Result<Stream> {
+        File::options().read(true).write(true).open(endpoint)
+    }
+
+    /// Named pipes disappear with the process.
+    pub fn remove_stale() -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Removes endpoints left behind by processes that did not exit cleanly.
+pub fn remove_stale_endpoints() -> Vec<PathBuf> {
+    endpoint::remove_stale()
+}
+
+struct Descriptor {
+    endpoint: String,
+    token: String,
+}
+
+impl Descriptor {
+    /// Size is informative only; the authoritative value is sent by server.
+    fn encode(&self, size: u64) -> Vec<u8> {
+        format!(
+            "version={PROTOCOL_VERSION}\nendpoint={}\ntoken={}\nsize={size}\n",
+            self.endpoint, self.token
+        )
+        .into_bytes()
+    }
+
+    fn decode(data: &[u8]) -> Option<Descriptor> {
+        let data = std::str::from_utf8(data).ok()?;
+        let mut values: HashMap<_, _> = data
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .collect();
+        if values.get("version") != Some(&PROTOCOL_VERSION) {
+            return None;
+        }
+        Some(Descriptor {
+            endpoint: values.remove("endpoint")?.to_owned(),
+            token: values.remove("token")?.to_owned(),
+        })
+    }
+}
+
+/// Longest token or response header line; both are short but come from
+/// other process.
+const MAX_HEADER_LINE: u64 = 1024;
+
+fn read_header_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_HEADER_LINE).read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "header line too long or incomplete",
+        ));
+    }
+    Ok(line)
+}
+
+/// Sends token and parses response header. Returns `Ok(None)` if the source
+/// no longer has the payload.
+fn request_payload(
+    descriptor: &Descriptor,
+) -> io::Result<Option<(BufReader<endpoint::Stream>, u64)>> {
+    let mut stream = endpoint::connect(&descriptor.endpoint)?;
+    writeln!(stream, "{}", descriptor.token)?;
+    stream.flush()?;
+    let mut reader = BufReader::new(stream);
+    let header = read_header_line(&mut reader)?;
+    match header.trim_end().split_once(' ') {
+        Some(("ok", size)) => {
+            let size = size
+                .parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid size"))?;
+            Ok(Some((reader, size)))
+        }
+        Some(("error", message)) => {
+            warn!("Local transfer rejected: {message}");
+            Ok(None)
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid response",
+        )),
+    }
+}
+
+fn receive_to_file(
+    mut reader: impl Read,
+    size: u64,
+    target_path: &Path,
+    progress: &ReadProgressHandle,
+    cancellation: &CancellationToken,
+) -> NativeExtensionsResult<()> {
+    let mut file = File::create(target_path)?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut received: u64 = 0;
+    let mut last_reported_progress = 0f64;
+    while received < size {
+        if cancellation.is_cancelled() {
+            return Err(NativeExtensionsError::Cancelled);
+        }
+        let to_read = (size - received).min(buf.len() as u64) as usize;
+        let did_read = reader.read(&mut buf[..to_read])?;
+        if did_read == 0 {
+            return Err(NativeExtensionsError::OtherError(
+                "local transfer ended prematurely".into(),
+            ));
+        }
+        file.write_all(&buf[..did_read])?;
+        received += did_read as u64;
+
+        let fraction = received as f64 / size as f64;
+        if fraction >= last_reported_progress + 0.01 {
+            last_reported_progress = fraction;
+            progress.report_progress(Some(fraction));
+        }
+    }
+    file.flush()?;
+    progress.report_progress(Some(1.0));
+    Ok(())
+}
+
+/// Reads item payload through local transfer channel into `target_path`.
+/// Returns `Ok(None)` if item doesn't advertise local transfer or source can
+/// not be reached, in which case regular read should be used instead.
+pub async fn read_local_transfer(
+    reader: &PlatformDataReader,
+    item: i64,
+    target_path: PathBuf,
+    progress: ReadProgressHandle,
+) -> NativeExtensionsResult<Option<String>> {
+    let formats = reader.get_formats_for_item(item).await?;
+    if !formats.iter().any(|f| f == FORMAT_LOCAL_TRANSFER) {
+        return Ok(None);
+    }
+    let descriptor = reader
+        .get_data_for_item(item, FORMAT_LOCAL_TRANSFER.into(), None)
+        .await?
+        .coerce_to_data(StringFormat::Utf8)
+        .and_then(|data| Descriptor::decode(&data));
+    let Some(descriptor) = descriptor else {
+        return Ok(None);
+    };
+
+    let cancellation = progress.cancellation_token().clone();
+    progress.set_cancellable(true);
+    progress.report_progress(None);
+
+    let (future, completer) = FutureCompleter::new();
+    let mut completer = Capsule::new(completer);
+    let sender = RunLoop::current().new_sender();
+
+    thread::spawn(move || {
+        let res = match request_payload(&descriptor) {
+            Ok(Some((stream, size))) => {
+                match receive_to_file(stream, size, &target_path, &progress, &cancellation) {
+                    Ok(()) => Ok(Some(target_path.to_string_lossy().into())),
+                    Err(err) => {
+                        fs::remove_file(&target_path).ok_log();
+                        Err(err)
+                    }
+                }
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                warn!("Local transfer endpoint not reachable: {err}");
+                Ok(None)
+            }
+        };
+        sender.send(move || {
+            let completer = completer.take().unwrap();
+            completer.complete(res);
+        });
+    });
+
+    future.await
+}
+
+/// Registers payloads to be served over local transfer channel.
+pub struct LocalTransferManager {}
+
+pub trait GetLocalTransferManager {
+    fn local_transfer_manager(&self) -> Rc<LocalTransferManager>;
+}
+
+impl GetLocalTransferManager for Context {
+    fn local_transfer_manager(&self) -> Rc<LocalTransferManager> {
+        self.get_attachment(LocalTransferManager::new).handler()
+    }
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct RegisterPayloadRequest {
+    /// In-memory payload.
+    data: Option<Vec<u8>>,
+    /// Path to file containing payload. Preferred for large payloads as the
+    /// file is streamed without being loaded in memory.
+    path: Option<String>,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct RegisterPayloadResponse {
+    token: String,
+    /// Format under which the descriptor should be added to data provider.
+    descriptor_format: String,
+    descriptor: Vec<u8>,
+}
+
+impl LocalTransferManager {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {}.register("LocalTransferManager")
+    }
+
+    fn register_payload(
+        &self,
+        isolate: IsolateId,
+        request: RegisterPayloadRequest,
+    ) -> NativeExtensionsResult<RegisterPayloadResponse> {
+        let (payload, size) = match (request.path, request.data) {
+            (Some(path), _) => {
+                let size = fs::metadata(&path)?.len();
+                (Payload::File(path.into()), size)
+            }
+            (None, Some(data)) => {
+                let size = data.len() as u64;
+                (Payload::Data(Arc::new(data)), size)
+            }
+            (None, None) => return Err(NativeExtensionsError::InvalidData),
+        };
+        let server = Server::get().ok_or(NativeExtensionsError::UnsupportedOperation)?;
+        let token = server.register(isolate, payload);
+        let descriptor = Descriptor {
+            endpoint: server.endpoint.clone(),
+            token: token.clone(),
+        };
+        Ok(RegisterPayloadResponse {
+            token,
+            descriptor_format: FORMAT_LOCAL_TRANSFER.into(),
+            descriptor: descriptor.encode(size),
+        })
+    }
+
+    fn unregister_payload(&self, token: String) -> NativeExtensionsResult<()> {
+        if let Some(server) = SERVER.get().and_then(|s| s.as_ref()) {
+            server.unregister(&token);
+        }
+        Ok(())
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "registerPayload" => Ok(self
+                .register_payload(call.isolate, call.args.try_into()?)?
+                .into()),
+            "unregisterPayload" => {
+                self.unregister_payload(call.args.try_into()?)?;
+                Ok(Value::Null)
+            }
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+impl MethodHandler for LocalTransferManager {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        if let Some(server) = SERVER.get().and_then(|s| s.as_ref()) {
+            server.unregister_isolate(isolate);
+        }
+    }
+}
@@ -1,19 +1,245 @@
-use std::{fmt::Display, panic::Location};
+//! Logging facade.
+//!
+//! Events are written to the platform logger and forwarded to a sink
+//! ([`crate::log_handler::LogHandler`]), which passes them on to Dart.
+//! Records of the `log` crate macros are routed through the facade as well.
+//! Long running operations log their start and end through
+//! [`OperationSpan`], so that events of one operation can be correlated by
+//! id.
 
-use irondash_message_channel::{MethodCallError, SendMessageError};
-use log::{Level, Record};
+use std::{
+    cell::Cell,
+    fmt::Display,
+    panic::Location,
+    path::Path,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::Instant,
+};
+
+use irondash_message_channel::{IntoValue, MethodCallError, SendMessageError, TryFromValue};
+use log::{Level, LevelFilter, Log, Metadata, Record};
 
 use crate::error::NativeExtensionsError;
 
-fn log_error<E: Display>(err: E, location: &Location) {
-    log::logger().log(
+/// Ordered from most to least severe.
+#[derive(IntoValue, TryFromValue, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[irondash(rename_all = "camelCase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<Level> for LogLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Error => LogLevel::Error,
+            Level::Warn => LogLevel::Warn,
+            Level::Info => LogLevel::Info,
+            Level::Debug => LogLevel::Debug,
+            Level::Trace => LogLevel::Trace,
+        }
+    }
+}
+
+impl From<LogLevel> for Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => Level::Error,
+            LogLevel::Warn => Level::Warn,
+            LogLevel::Info => Level::Info,
+            LogLevel::Debug => Level::Debug,
+            LogLevel::Trace => Level::Trace,
+        }
+    }
+}
+
+#[derive(IntoValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct LogEvent {
+    pub level: LogLevel,
+    pub module: String,
+    pub message: String,
+    /// Set for events logged by [`OperationSpan`].
+    pub operation_id: Option<i64>,
+    pub operation: Option<String>,
+    /// Set for events ending an operation.
+    pub duration_ms: Option<f64>,
+    pub file: Option<String>,
+    pub line: Option<i64>,
+}
+
+/// Receives events logged on any thread.
+pub type EventSink = Arc<dyn Fn(&LogEvent) + Send + Sync>;
+
+/// Platform logger and the most detailed level it writes.
+static PLATFORM_LOGGER: OnceLock<(Box<dyn Log>, LevelFilter)> = OnceLock::new();
+static EVENT_SINK: Mutex<Option<EventSink>> = Mutex::new(None);
+
+thread_local! {
+    static IN_SINK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Installs function receiving all events.
+pub fn set_event_sink(sink: Option<EventSink>) {
+    if let Ok(mut s) = EVENT_SINK.lock() {
+        *s = sink;
+    }
+}
+
+/// Makes `platform` write events of the facade up to `level` and routes
+/// records of the `log` crate through the facade, so that they reach the
+/// sink too. Only the first call has effect.
+pub fn init_logger(platform: Box<dyn Log>, level: LevelFilter) {
+    if PLATFORM_LOGGER.set((platform, level)).is_ok() {
+        log::set_logger(&FACADE_LOGGER).ok();
+        // The sink may ask for more detail than the platform logger writes.
+        log::set_max_level(LevelFilter::Trace);
+    }
+}
+
+fn log_to_platform(record: &Record) {
+    if let Some((platform, level)) = PLATFORM_LOGGER.get() {
+        if record.level() <= *level && platform.enabled(record.metadata()) {
+            platform.log(record);
+        }
+    }
+}
+
+/// Event is only created when there is a sink.
+fn send_to_sink<F: FnOnce() -> LogEvent>(event: F) {
+    let Some(sink) = EVENT_SINK.lock().ok().and_then(|sink| sink.clone()) else {
+        return;
+    };
+    // Sink may log itself, i.e. when forwarding fails; such events are not
+    // sent again.
+    if IN_SINK.with(|s| s.replace(true)) {
+        return;
+    }
+    sink(&event());
+    IN_SINK.with(|s| s.set(false));
+}
+
+pub fn log_event(event: LogEvent) {
+    log_to_platform(
         &Record::builder()
-            .args(format_args!("Unexpected error {err} at {location}"))
-            .file(Some(location.file()))
-            .line(Some(location.line()))
-            .level(Level::Error)
+            .args(format_args!("{}", event.message))
+            .target(&event.module)
+            .file(event.file.as_deref())
+            .line(event.line.map(|line| line as u32))
+            .level(event.level.into())
             .build(),
     );
+    send_to_sink(|| event);
+}
+
+struct FacadeLogger;
+
+static FACADE_LOGGER: FacadeLogger = FacadeLogger;
+
+impl Log for FacadeLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        log_to_platform(record);
+        send_to_sink(|| LogEvent {
+            level: record.level().into(),
+            module: record.target().into(),
+            message: record.args().to_string(),
+            operation_id: None,
+            operation: None,
+            duration_ms: None,
+            file: record.file().map(Into::into),
+            line: record.line().map(|line| line as i64),
+        });
+    }
+
+    fn flush(&self) {
+        if let Some((platform, _)) = PLATFORM_LOGGER.get() {
+            platform.flush();
+        }
+    }
+}
+
+fn log_error<E: Display>(err: E, location: &Location) {
+    let module = Path::new(location.file())
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    log_event(LogEvent {
+        level: LogLevel::Error,
+        module,
+        message: format!("Unexpected error {err} at {location}"),
+        operation_id: None,
+        operation: None,
+        duration_ms: None,
+        file: Some(location.file().to_owned()),
+        line: Some(location.line() as i64),
+    });
+}
+
+static NEXT_OPERATION_ID: AtomicI64 = AtomicI64::new(1);
+
+/// Logs start and end of an operation together with its duration. Events
+/// are logged at debug level, failures as warnings. Span dropped without
+/// being finished (i.e. because the future was dropped) is logged as
+/// cancelled.
+pub struct OperationSpan {
+    id: i64,
+    module: &'static str,
+    operation: &'static str,
+    start: Instant,
+    finished: bool,
+}
+
+impl OperationSpan {
+    pub fn new(module: &'static str, operation: &'static str) -> Self {
+        let res = Self {
+            id: NEXT_OPERATION_ID.fetch_add(1, Ordering::Relaxed),
+            module,
+            operation,
+            start: Instant::now(),
+            finished: false,
+        };
+        res.log(LogLevel::Debug, "started".into(), false);
+        res
+    }
+
+    fn log(&self, level: LogLevel, message: String, ended: bool) {
+        log_event(LogEvent {
+            level,
+            module: self.module.into(),
+            message: format!("{} {message}", self.operation),
+            operation_id: Some(self.id),
+            operation: Some(self.operation.into()),
+            duration_ms: ended.then(|| self.start.elapsed().as_secs_f64() * 1000.0),
+            file: None,
+            line: None,
+        });
+    }
+
+    pub fn finish<T, E: Display>(mut self, result: &Result<T, E>) {
+        self.finished = true;
+        match result {
+            Ok(_) => self.log(LogLevel::Debug, "finished".into(), true),
+            Err(err) => self.log(LogLevel::Warn, format!("failed: {err}"), true),
+        }
+    }
+}
+
+impl Drop for OperationSpan {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.log(LogLevel::Debug, "cancelled".into(), true);
+        }
+    }
 }
 
 pub trait OkLog<T> {
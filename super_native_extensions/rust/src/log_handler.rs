@@ -0,0 +1,120 @@
+//! Forwarding of log events to Dart.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use irondash_message_channel::{
+    IsolateId, Late, MethodCall, MethodCallReply, MethodHandler, MethodInvoker, PlatformError,
+    PlatformResult, RegisteredMethodHandler, Value,
+};
+use irondash_run_loop::RunLoop;
+
+use crate::{
+    context::Context,
+    log::{set_event_sink, LogEvent, LogLevel},
+};
+
+thread_local! {
+    /// Only set on the main thread; events logged elsewhere are sent there.
+    static HANDLER: RefCell<Weak<LogHandler>> = RefCell::new(Weak::new());
+}
+
+fn forward_event(event: &LogEvent) {
+    if let Some(handler) = HANDLER.with(|h| h.borrow().upgrade()) {
+        handler.forward(event);
+    }
+}
+
+/// Forwards log events to isolates that set a log handler.
+pub struct LogHandler {
+    invoker: Late<MethodInvoker>,
+    /// Least severe level forwarded to each isolate.
+    isolates: RefCell<HashMap<IsolateId, LogLevel>>,
+}
+
+pub trait GetLogHandler {
+    fn log_handler(&self) -> Rc<LogHandler>;
+}
+
+impl GetLogHandler for Context {
+    fn log_handler(&self) -> Rc<LogHandler> {
+        self.get_attachment(LogHandler::new).handler()
+    }
+}
+
+impl LogHandler {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {
+            invoker: Late::new(),
+            isolates: RefCell::new(HashMap::new()),
+        }
+        .register("LogHandler")
+    }
+
+    /// Level `None` stops forwarding to the isolate.
+    fn set_log_handler(&self, isolate: IsolateId, level: Option<LogLevel>) {
+        let mut isolates = self.isolates.borrow_mut();
+        match level {
+            Some(level) => isolates.insert(isolate, level),
+            None => isolates.remove(&isolate),
+        };
+    }
+
+    fn forward(&self, event: &LogEvent) {
+        let Ok(isolates) = self.isolates.try_borrow() else {
+            return;
+        };
+        for (isolate, level) in isolates.iter() {
+            if event.level <= *level {
+                // Failures are not logged, that would log again.
+                self.invoker
+                    .call_method(*isolate, "onLog", event.clone(), |_| {});
+            }
+        }
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "setLogHandler" => {
+                self.set_log_handler(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+impl MethodHandler for LogHandler {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
+
+    fn assign_invoker(&self, invoker: MethodInvoker) {
+        self.invoker.set(invoker);
+    }
+
+    fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        HANDLER.with(|h| h.replace(weak_self));
+        let sender = RunLoop::current().new_sender();
+        set_event_sink(Some(Arc::new(move |event: &LogEvent| {
+            if sender.is_same_thread() {
+                forward_event(event);
+            } else {
+                let event = event.clone();
+                sender.send(move || forward_event(&event));
+            }
+        })));
+    }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        self.isolates.borrow_mut().remove(&isolate);
+    }
+}
@@ -0,0 +1,346 @@
+//! Session scoped directory for files produced by drops and pastes.
+//!
+//! Files received into this directory count against a quota; once exceeded
+//! the oldest files are evicted. Application can "claim" a file, moving it
+//! out of the managed space, in which case it is no longer subject to eviction.
+//! Leased files are kept in a separate folder; they don't count against the
+//! quota and are removed when the lease is released. The directory is removed
+//! when the session ends.
+
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime},
+};
+
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    util::get_target_path,
+};
+
+const LEASES_FOLDER: &str = "leases";
+
+pub struct ManagedDirectory {
+    path: RefCell<Option<PathBuf>>,
+    quota: Cell<Option<u64>>,
+}
+
+impl ManagedDirectory {
+    const DEFAULT_QUOTA: u64 = 1024 * 1024 * 1024;
+
+    /// Parent of session directories of all processes.
+    fn root() -> PathBuf {
+        std::env::temp_dir().join("super_native_extensions")
+    }
+
+    pub fn new() -> Self {
+        Self {
+            path: RefCell::new(None),
+            quota: Cell::new(Some(Self::DEFAULT_QUOTA)),
+        }
+    }
+
+    /// Returns the directory, creating it on first use.
+    pub fn path(&self) -> NativeExtensionsResult<PathBuf> {
+        if let Some(path) = self.path.borrow().as_ref() {
+            return Ok(path.clone());
+        }
+        let suffix: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(8)
+            .map(char::from)
+            .collect();
+        let path = Self::root().join(format!("session-{}-{}", process::id(), suffix));
+        fs::create_dir_all(&path)?;
+        // Canonical path so that prefix checks work with what platforms return.
+        let path = fs::canonicalize(&path)?;
+        self.path.replace(Some(path.clone()));
+        Ok(path)
+    }
+
+    /// Maximum total size of unclaimed files, None for unlimited.
+    pub fn set_quota(&self, quota: Option<u64>) {
+        self.quota.set(quota);
+        self.enforce_quota(None).ok_log();
+    }
+
+    /// Creates folder for leased files at `relative` path in the leases
+    /// folder.
+    pub fn create_leased_folder(&self, relative: &Path) -> NativeExtensionsResult<PathBuf> {
+        let path = self.path()?.join(LEASES_FOLDER).join(relative);
+        fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    /// Removes leased folder with its contents, if it exists.
+    pub fn remove_leased_folder(&self, relative: &Path) {
+        let Some(root) = self.path.borrow().clone() else {
+            return;
+        };
+        let path = root.join(LEASES_FOLDER).join(relative);
+        if path.exists() {
+            fs::remove_dir_all(path).ok_log();
+        }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        let root = self.path.borrow();
+        match (root.as_ref(), fs::canonicalize(path)) {
+            (Some(root), Ok(path)) => path.starts_with(root) && path != *root,
+            _ => false,
+        }
+    }
+
+    fn is_leased(&self, path: &Path) -> bool {
+        let root = self.path.borrow();
+        match (root.as_ref(), fs::canonicalize(path)) {
+            (Some(root), Ok(path)) => path.starts_with(root.join(LEASES_FOLDER)),
+            _ => false,
+        }
+    }
+
+    /// Must be called after a file was written. Does nothing for files
+    /// outside of managed directory.
+    pub fn file_added(&self, path: &Path) {
+        if self.contains(path) {
+            self.enforce_quota(Some(path)).ok_log();
+        }
+    }
+
+    fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) {
+        let Some(entries) = fs::read_dir(dir).ok_log() else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                Self::collect_files(&entry.path(), files);
+            } else {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((entry.path(), metadata.len(), modified));
+            }
+        }
+    }
+
+    /// Evicts oldest files until the directory fits within quota. `keep` is
+    /// the file that has just been added and must not be evicted.
+    fn enforce_quota(&self, keep: Option<&Path>) -> NativeExtensionsResult<()> {
+        let (Some(root), Some(quota)) = (self.path.borrow().clone(), self.quota.get()) else {
+            return Ok(());
+        };
+        let keep = keep.and_then(|k| fs::canonicalize(k).ok());
+        let mut files = Vec::new();
+        Self::collect_files(&root, &mut files);
+        let leases = root.join(LEASES_FOLDER);
+        files.retain(|f| !f.0.starts_with(&leases));
+        let mut total: u64 = files.iter().map(|f| f.1).sum();
+        files.sort_by_key(|f| f.2);
+        for (path, size, _) in files {
+            if total <= quota {
+                break;
+            }
+            if keep.as_ref() == Some(&path) {
+                continue;
+            }
+            fs::remove_file(&path)?;
+            total -= size;
+        }
+        Ok(())
+    }
+
+    /// Moves file out of managed directory into `target_folder`. Returns the
+    /// new location. Leased files can not be claimed.
+    pub fn claim(&self, path: &Path, target_folder: &Path) -> NativeExtensionsResult<PathBuf> {
+        if !self.contains(path) {
+            return Err(NativeExtensionsError::OtherError(format!(
+                "{} is not in managed directory",
+                path.to_string_lossy()
+            )));
+        }
+        if self.is_leased(path) {
+            return Err(NativeExtensionsError::OtherError(format!(
+                "{} is leased",
+                path.to_string_lossy()
+            )));
+        }
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| NativeExtensionsError::OtherError("Invalid file name".into()))?;
+        let target = get_target_path(target_folder, &file_name.to_string_lossy());
+        if fs::rename(path, &target).is_err() {
+            // Possibly different volume
+            fs::copy(path, &target)?;
+            fs::remove_file(path)?;
+        }
+        Ok(target)
+    }
+}
+
+/// Whether process with given id is alive; `None` if it can't be determined.
+#[cfg(unix)]
+fn is_process_running(pid: u32) -> Option<bool> {
+    extern "C" {
+        fn kill(pid: i32, sig: i32) -> i32;
+    }
+    const ESRCH: i32 = 3;
+    let pid = i32::try_from(pid).ok()?;
+    if unsafe { kill(pid, 0) } == 0 {
+        return Some(true);
+    }
+    match std::io::Error::last_os_error().raw_os_error() {
+        Some(ESRCH) => Some(false),
+        // EPERM: process exists but belongs to another user
+        _ => Some(true),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn is_process_running(pid: u32) -> Option<bool> {
+    use windows::Win32::{
+        Foundation::{CloseHandle, ERROR_INVALID_PARAMETER, STILL_ACTIVE},
+        System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+    };
+    let process = match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+        Ok(process) => process,
+        Err(err) if err.code() == ERROR_INVALID_PARAMETER.to_hresult() => return Some(false),
+        // Access denied means the process exists
+        Err(_) => return Some(true),
+    };
+    let mut exit_code = 0u32;
+    let res = unsafe { GetExitCodeProcess(process, &mut exit_code) };
+    unsafe { CloseHandle(process).ok_log() };
+    res.ok()?;
+    Some(exit_code == STILL_ACTIVE.0 as u32)
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn is_process_running(_pid: u32) -> Option<bool> {
+    None
+}
+
+/// Whether process that created the session directory is still alive;
+/// `None` if unknown, in which case the directory must be left alone.
+fn is_owner_running(session_name: &str) -> Option<bool> {
+    let (pid, _) = session_name.strip_prefix("session-")?.split_once('-')?;
+    is_process_running(pid.parse().ok()?)
+}
+
+/// Removes session directories left behind by processes that did not exit
+/// cleanly. Directories of current process or of processes that may still
+/// be running are never touched. Returns the removed directories.
+pub fn remove_stale_sessions(max_age: Duration) -> Vec<PathBuf> {
+    let own_prefix = format!("session-{}-", process::id());
+    let Ok(entries) = fs::read_dir(ManagedDirectory::root()) else {
+        return Vec::new();
+    };
+    let now = SystemTime::now();
+    let mut res = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !name.starts_with("session-")
+            || name.starts_with(&own_prefix)
+            || is_owner_running(&name) != Some(false)
+        {
+            continue;
+        }
+        let age = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok());
+        if age.map(|age| age >= max_age).unwrap_or(false)
+            && fs::remove_dir_all(entry.path()).ok_log().is_some()
+        {
+            res.push(entry.path());
+        }
+    }
+    res
+}
+
+impl Drop for ManagedDirectory {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.borrow_mut().take() {
+            fs::remove_dir_all(path).ok_log();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    };
+
+    use super::ManagedDirectory;
+
+    fn write_file(path: &Path, len: usize, age_secs: u64) -> PathBuf {
+        fs::write(path, vec![0u8; len]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_secs);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        fs::canonicalize(path).unwrap()
+    }
+
+    #[test]
+    fn test_evicts_oldest_over_quota() {
+        let directory = ManagedDirectory::new();
+        directory.set_quota(Some(100));
+        let root = directory.path().unwrap();
+        let oldest = write_file(&root.join("a"), 40, 30);
+        let older = write_file(&root.join("b"), 40, 20);
+        let leased_folder = directory.create_leased_folder(Path::new("1")).unwrap();
+        let leased = write_file(&leased_folder.join("c"), 1000, 40);
+        // Newest file must be kept even though other files are younger.
+        let added = write_file(&root.join("d"), 40, 10);
+        directory.file_added(&added);
+        assert!(!oldest.exists());
+        assert!(older.exists());
+        assert!(added.exists());
+        // Leased files don't count against the quota and are never evicted.
+        assert!(leased.exists());
+
+        directory.set_quota(Some(40));
+        assert!(!older.exists());
+        assert!(added.exists());
+        assert!(leased.exists());
+    }
+
+    #[test]
+    fn test_claim() {
+        let directory = ManagedDirectory::new();
+        let target = ManagedDirectory::new();
+        let root = directory.path().unwrap();
+        let target_folder = target.path().unwrap();
+
+        let file = write_file(&root.join("file.txt"), 10, 0);
+        let claimed = directory.claim(&file, &target_folder).unwrap();
+        assert!(!file.exists());
+        assert_eq!(claimed, target_folder.join("file.txt"));
+        assert_eq!(fs::read(&claimed).unwrap().len(), 10);
+
+        // Outside of managed directory.
+        assert!(directory.claim(&claimed, &target_folder).is_err());
+        assert!(claimed.exists());
+        // Managed directory itself.
+        assert!(directory.claim(&root, &target_folder).is_err());
+        // Leased file.
+        let leased_folder = directory.create_leased_folder(Path::new("1")).unwrap();
+        let leased = write_file(&leased_folder.join("leased.txt"), 10, 0);
+        assert!(directory.claim(&leased, &target_folder).is_err());
+        assert!(leased.exists());
+    }
+}
@@ -0,0 +1,557 @@
+//! Audio and video items.
+//!
+//! Media items are recognized by format (UTI, MIME type or Windows clipboard
+//! format) and described with duration and codec metadata. Metadata comes
+//! from platform media APIs where available, with a fallback to parsing the
+//! container header (ISO base media / QuickTime and RIFF WAVE). Container
+//! parsing seeks through the file, so even large clips are not read into
+//! memory.
+
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+};
+
+use irondash_message_channel::{IntoValue, Value};
+
+use crate::{
+    error::NativeExtensionsResult, import_pipeline::path_from_value, log::OkLog,
+    platform::PlatformDataReader,
+};
+
+#[derive(IntoValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum MediaKind {
+    Audio,
+    Video,
+}
+
+/// Metadata provided by platform media APIs or container parsing.
+#[derive(Debug, Default, Clone)]
+pub struct MediaMetadata {
+    pub duration_millis: Option<i64>,
+    pub has_video: Option<bool>,
+    pub has_audio: Option<bool>,
+    pub codecs: Vec<String>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub sample_rate: Option<i64>,
+    pub channel_count: Option<i64>,
+}
+
+impl MediaMetadata {
+    /// Fills missing values from `other`.
+    fn merge(&mut self, other: MediaMetadata) {
+        self.duration_millis = self.duration_millis.or(other.duration_millis);
+        self.has_video = self.has_video.or(other.has_video);
+        self.has_audio = self.has_audio.or(other.has_audio);
+        if self.codecs.is_empty() {
+            self.codecs = other.codecs;
+        }
+        self.width = self.width.or(other.width);
+        self.height = self.height.or(other.height);
+        self.sample_rate = self.sample_rate.or(other.sample_rate);
+        self.channel_count = self.channel_count.or(other.channel_count);
+    }
+
+    fn is_complete(&self) -> bool {
+        self.duration_millis.is_some() && !self.codecs.is_empty()
+    }
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct MediaInfo {
+    pub kind: MediaKind,
+    /// Media format of the item.
+    pub format: String,
+    pub duration_millis: Option<i64>,
+    /// Codec identifiers (i.e. `avc1`, `mp4a`, `pcm`), in track order.
+    pub codecs: Vec<String>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub sample_rate: Option<i64>,
+    pub channel_count: Option<i64>,
+    /// Local path of the media file, if item refers to one. Reading the file
+    /// directly avoids loading the clip into memory.
+    pub path: Option<String>,
+}
+
+const VIDEO_FORMATS: &[&str] = &[
+    "public.movie",
+    "public.video",
+    "public.mpeg",
+    "public.mpeg-4",
+    "public.avi",
+    "com.apple.quicktime-movie",
+    "com.apple.m4v-video",
+    "org.webmproject.webm",
+    "public.3gpp",
+];
+
+const AUDIO_FORMATS: &[&str] = &[
+    "public.audio",
+    "public.mp3",
+    "public.mpeg-4-audio",
+    "public.aiff-audio",
+    "public.aifc-audio",
+    "public.midi-audio",
+    "com.apple.m4a-audio",
+    "com.apple.coreaudio-format",
+    "com.microsoft.waveform-audio",
+    "org.xiph.flac",
+    // CF_RIFF, CF_WAVE
+    "NativeShell_CF_11",
+    "NativeShell_CF_12",
+];
+
+pub fn media_kind_for_format(format: &str) -> Option<MediaKind> {
+    if format.starts_with("video/") || VIDEO_FORMATS.contains(&format) {
+        Some(MediaKind::Video)
+    } else if format.starts_with("audio/") || AUDIO_FORMATS.contains(&format) {
+        Some(MediaKind::Audio)
+    } else {
+        None
+    }
+}
+
+/// Formats that may hold file URI (or path) of the media file.
+const FILE_URI_FORMATS: &[&str] = &[
+    "public.file-url",
+    "text/uri-list",
+    // CF_HDROP
+    "NativeShell_CF_15",
+];
+
+fn read_u16_be(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64_be(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+pub fn fourcc_to_string(code: &[u8]) -> String {
+    String::from_utf8_lossy(code).trim().to_owned()
+}
+
+struct BoxHeader {
+    kind: [u8; 4],
+    /// Start of box content.
+    content_start: u64,
+    /// End of box (exclusive).
+    end: u64,
+}
+
+/// Largest box content that is read into memory. Only small header boxes
+/// (`mvhd`, `tkhd`, `hdlr`, `stsd`) are read; media data is skipped.
+const MAX_BOX_READ: u64 = 64 * 1024;
+
+struct BoxReader<R: Read + Seek> {
+    reader: R,
+    len: u64,
+}
+
+impl<R: Read + Seek> BoxReader<R> {
+    fn new(mut reader: R) -> io::Result<Self> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        Ok(Self { reader, len })
+    }
+
+    fn read_header(&mut self, offset: u64, parent_end: u64) -> io::Result<Option<BoxHeader>> {
+        if offset.saturating_add(8) > parent_end {
+            return Ok(None);
+        }
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 8];
+        self.reader.read_exact(&mut header)?;
+        let size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+        let kind: [u8; 4] = header[4..8].try_into().unwrap();
+        let (content_start, end) = match size {
+            0 => (offset + 8, parent_end),
+            1 => {
+                let mut large = [0u8; 8];
+                self.reader.read_exact(&mut large)?;
+                // Malformed size could overflow and wrap around into range
+                match offset.checked_add(u64::from_be_bytes(large)) {
+                    Some(end) => (offset + 16, end),
+                    None => return Ok(None),
+                }
+            }
+            size => (offset + 8, offset + size),
+        };
+        if end < content_start || end > parent_end {
+            return Ok(None);
+        }
+        Ok(Some(BoxHeader {
+            kind,
+            content_start,
+            end,
+        }))
+    }
+
+    fn children(&mut self, start: u64, end: u64) -> io::Result<Vec<BoxHeader>> {
+        let mut res = Vec::new();
+        let mut offset = start;
+        while let Some(header) = self.read_header(offset, end)? {
+            offset = header.end;
+            res.push(header);
+        }
+        Ok(res)
+    }
+
+    fn child(&mut self, start: u64, end: u64, kind: &[u8; 4]) -> io::Result<Option<BoxHeader>> {
+        Ok(self
+            .children(start, end)?
+            .into_iter()
+            .find(|b| &b.kind == kind))
+    }
+
+    fn content(&mut self, header: &BoxHeader) -> io::Result<Vec<u8>> {
+        let len = (header.end - header.content_start).min(MAX_BOX_READ);
+        self.reader.seek(SeekFrom::Start(header.content_start))?;
+        let mut res = vec![0u8; len as usize];
+        self.reader.read_exact(&mut res)?;
+        Ok(res)
+    }
+
+    fn read_signature(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut res = vec![0u8; len.min(self.len as usize)];
+        self.reader.read_exact(&mut res)?;
+        Ok(res)
+    }
+}
+
+/// Parses ISO base media (MP4, M4A, MOV, 3GP) metadata from `moov` box.
+fn probe_iso_media<R: Read + Seek>(reader: &mut BoxReader<R>) -> io::Result<MediaMetadata> {
+    let mut res = MediaMetadata::default();
+    let len = reader.len;
+    let Some(moov) = reader.child(0, len, b"moov")? else {
+        return Ok(res);
+    };
+    if let Some(mvhd) = reader.child(moov.content_start, moov.end, b"mvhd")? {
+        let mvhd = reader.content(&mvhd)?;
+        let (timescale, duration) = if mvhd.first() == Some(&1) {
+            (read_u32_be(&mvhd, 20), read_u64_be(&mvhd, 24))
+        } else {
+            (
+                read_u32_be(&mvhd, 12),
+                read_u32_be(&mvhd, 16).map(u64::from),
+            )
+        };
+        if let (Some(timescale), Some(duration)) = (timescale, duration) {
+            if timescale > 0 {
+                res.duration_millis =
+                    i64::try_from(duration as u128 * 1000 / timescale as u128).ok();
+            }
+        }
+    }
+    for trak in reader.children(moov.content_start, moov.end)? {
+        if &trak.kind != b"trak" {
+            continue;
+        }
+        let Some(mdia) = reader.child(trak.content_start, trak.end, b"mdia")? else {
+            continue;
+        };
+        let handler = match reader.child(mdia.content_start, mdia.end, b"hdlr")? {
+            Some(hdlr) => reader.content(&hdlr)?.get(8..12).map(|h| h.to_vec()),
+            None => None,
+        };
+        let is_video = handler.as_deref() == Some(b"vide");
+        let is_audio = handler.as_deref() == Some(b"soun");
+        if !is_video && !is_audio {
+            continue;
+        }
+        let stsd = match reader.child(mdia.content_start, mdia.end, b"minf")? {
+            Some(minf) => match reader.child(minf.content_start, minf.end, b"stbl")? {
+                Some(stbl) => reader.child(stbl.content_start, stbl.end, b"stsd")?,
+                None => None,
+            },
+            None => None,
+        };
+        // First sample entry follows full box header and entry count.
+        let entry = match stsd {
+            Some(stsd) => reader.content(&stsd)?.get(8..).map(|e| e.to_vec()),
+            None => None,
+        };
+        if let Some(codec) = entry.as_ref().and_then(|e| e.get(4..8)) {
+            res.codecs.push(fourcc_to_string(codec));
+        }
+        if is_video {
+            res.has_video = Some(true);
+            if let Some(tkhd) = reader.child(trak.content_start, trak.end, b"tkhd")? {
+                let tkhd = reader.content(&tkhd)?;
+                let offset = if tkhd.first() == Some(&1) { 88 } else { 76 };
+                // 16.16 fixed point
+                if let (Some(width), Some(height)) =
+                    (read_u32_be(&tkhd, offset), read_u32_be(&tkhd, offset + 4))
+                {
+                    res.width = res.width.or(Some((width >> 16) as i64));
+                    res.height = res.height.or(Some((height >> 16) as i64));
+                }
+            }
+        } else {
+            res.has_audio = Some(true);
+            if let Some(entry) = &entry {
+                if let Some(channels) = read_u16_be(entry, 24) {
+                    res.channel_count = res.channel_count.or(Some(channels as i64));
+                }
+                if let Some(sample_rate) = read_u32_be(entry, 32) {
+                    res.sample_rate = res.sample_rate.or(Some((sample_rate >> 16) as i64));
+                }
+            }
+        }
+    }
+    if res.has_video.is_some() || res.has_audio.is_some() {
+        res.has_video = Some(res.has_video.unwrap_or(false));
+        res.has_audio = Some(res.has_audio.unwrap_or(false));
+    }
+    Ok(res)
+}
+
+fn wave_codec_name(format_tag: u16) -> String {
+    match format_tag {
+        0x0001 | 0xFFFE => "pcm".into(),
+        0x0003 => "float".into(),
+        0x0006 => "alaw".into(),
+        0x0007 => "ulaw".into(),
+        0x0011 => "ima-adpcm".into(),
+        0x0055 => "mp3".into(),
+        tag => format!("0x{tag:04x}"),
+    }
+}
+
+/// Parses RIFF WAVE `fmt ` and `data` chunks.
+fn probe_wave<R: Read + Seek>(reader: &mut BoxReader<R>) -> io::Result<MediaMetadata> {
+    let mut res = MediaMetadata {
+        has_video: Some(false),
+        has_audio: Some(true),
+        ..Default::default()
+    };
+    let mut byte_rate = None;
+    let mut data_len = None;
+    let mut offset = 12u64;
+    while offset + 8 <= reader.len {
+        reader.reader.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 8];
+        reader.reader.read_exact(&mut header)?;
+        let size = u32::from_le_bytes(header[4..8].try_into().unwrap()) as u64;
+        match &header[0..4] {
+            b"fmt " => {
+                let mut fmt = [0u8; 16];
+                reader.reader.read_exact(&mut fmt)?;
+                let format_tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                res.codecs.push(wave_codec_name(format_tag));
+                res.channel_count = Some(u16::from_le_bytes([fmt[2], fmt[3]]) as i64);
+                res.sample_rate = Some(u32::from_le_bytes(fmt[4..8].try_into().unwrap()) as i64);
+                byte_rate = Some(u32::from_le_bytes(fmt[8..12].try_into().unwrap()) as u64);
+            }
+            b"data" => data_len = Some(size.min(reader.len - offset - 8)),
+            _ => {}
+        }
+        // Chunks are word aligned
+        offset += 8 + size + (size & 1);
+    }
+    if let (Some(byte_rate), Some(data_len)) = (byte_rate, data_len) {
+        res.duration_millis = data_len
+            .checked_mul(1000)
+            .and_then(|d| d.checked_div(byte_rate))
+            .and_then(|d| i64::try_from(d).ok());
+    }
+    Ok(res)
+}
+
+/// Extracts metadata from media container. Unknown containers yield empty
+/// metadata.
+pub fn probe_container<R: Read + Seek>(reader: R) -> io::Result<MediaMetadata> {
+    let mut reader = BoxReader::new(reader)?;
+    let signature = reader.read_signature(12)?;
+    if signature.len() == 12 && &signature[0..4] == b"RIFF" && &signature[8..12] == b"WAVE" {
+        probe_wave(&mut reader)
+    } else if signature.len() >= 8
+        && matches!(
+            &signature[4..8],
+            b"ftyp" | b"moov" | b"wide" | b"mdat" | b"free"
+        )
+    {
+        probe_iso_media(&mut reader)
+    } else {
+        Ok(MediaMetadata::default())
+    }
+}
+
+async fn get_media_path(
+    reader: &PlatformDataReader,
+    item: i64,
+    formats: &[String],
+) -> NativeExtensionsResult<Option<String>> {
+    for format in FILE_URI_FORMATS {
+        if !formats.iter().any(|f| f == format) {
+            continue;
+        }
+        let value = reader
+            .get_data_for_item(item, (*format).into(), None)
+            .await?;
+        if let Some(path) = path_from_value(value) {
+            return Ok(Some(path.to_string_lossy().into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn read_media_info(
+    reader: &PlatformDataReader,
+    item: i64,
+) -> NativeExtensionsResult<Option<MediaInfo>> {
+    let formats = reader.get_formats_for_item(item).await?;
+    let mut media = formats
+        .iter()
+        .find_map(|f| media_kind_for_format(f).map(|kind| (kind, f.clone())));
+    if media.is_none() {
+        if let Some(format) = reader.get_item_format_for_uri(item).await? {
+            media = media_kind_for_format(&format).map(|kind| (kind, format));
+        }
+    }
+    let Some((kind, format)) = media else {
+        return Ok(None);
+    };
+
+    let mut metadata = reader
+        .get_media_metadata_for_item(item)
+        .await?
+        .unwrap_or_default();
+    let path = get_media_path(reader, item, &formats).await?;
+    if !metadata.is_complete() {
+        let probed = if let Some(path) = &path {
+            File::open(path).and_then(probe_container).ok_log()
+        } else if metadata.duration_millis.is_none()
+            && formats.contains(&format)
+            && !reader.can_read_virtual_file_for_item(item, &format).await?
+        {
+            // Only when platform knows nothing about the item, as this loads
+            // whole clip into memory.
+            let data = reader.get_data_for_item(item, format.clone(), None).await?;
+            match data {
+                Value::U8List(data) => probe_container(Cursor::new(data)).ok_log(),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        if let Some(probed) = probed {
+            metadata.merge(probed);
+        }
+    }
+    // Container may reveal that a generic movie only has audio track.
+    let kind = match (metadata.has_video, metadata.has_audio) {
+        (Some(false), Some(true)) => MediaKind::Audio,
+        _ => kind,
+    };
+    Ok(Some(MediaInfo {
+        kind,
+        format,
+        duration_millis: metadata.duration_millis,
+        codecs: metadata.codecs,
+        width: metadata.width,
+        height: metadata.height,
+        sample_rate: metadata.sample_rate,
+        channel_count: metadata.channel_count,
+        path,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{media_kind_for_format, probe_container, MediaKind};
+
+    fn mp4_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut res = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        res.extend_from_slice(kind);
+        res.extend_from_slice(content);
+        res
+    }
+
+    #[test]
+    fn test_media_kind() {
+        assert_eq!(media_kind_for_format("video/mp4"), Some(MediaKind::Video));
+        assert_eq!(media_kind_for_format("public.mp3"), Some(MediaKind::Audio));
+        assert_eq!(media_kind_for_format("public.png"), None);
+    }
+
+    #[test]
+    fn test_probe_mp4() {
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&600u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&1500u32.to_be_bytes());
+        let mut hdlr = vec![0u8; 24];
+        hdlr[8..12].copy_from_slice(b"vide");
+        let mut tkhd = vec![0u8; 84];
+        tkhd[76..80].copy_from_slice(&(1920u32 << 16).to_be_bytes());
+        tkhd[80..84].copy_from_slice(&(1080u32 << 16).to_be_bytes());
+        let mut stsd = vec![0u8; 8];
+        stsd.extend(mp4_box(b"avc1", &[0u8; 78]));
+        let stbl = mp4_box(b"stbl", &mp4_box(b"stsd", &stsd));
+        let minf = mp4_box(b"minf", &stbl);
+        let mut mdia = mp4_box(b"hdlr", &hdlr);
+        mdia.extend(minf);
+        let mut trak = mp4_box(b"tkhd", &tkhd);
+        trak.extend(mp4_box(b"mdia", &mdia));
+        let mut moov = mp4_box(b"mvhd", &mvhd);
+        moov.extend(mp4_box(b"trak", &trak));
+        let mut file = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        file.extend(mp4_box(b"moov", &moov));
+
+        let metadata = probe_container(Cursor::new(file)).unwrap();
+        assert_eq!(metadata.duration_millis, Some(2500));
+        assert_eq!(metadata.codecs, vec!["avc1".to_owned()]);
+        assert_eq!(metadata.width, Some(1920));
+        assert_eq!(metadata.height, Some(1080));
+        assert_eq!(metadata.has_audio, Some(false));
+    }
+
+    #[test]
+    fn test_probe_overflowing_box_size() {
+        let mut file = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        file.extend(1u32.to_be_bytes());
+        file.extend(b"moov");
+        file.extend(u64::MAX.to_be_bytes());
+        file.extend([0u8; 16]);
+        let metadata = probe_container(Cursor::new(file)).unwrap();
+        assert_eq!(metadata.duration_millis, None);
+        assert!(metadata.codecs.is_empty());
+    }
+
+    #[test]
+    fn test_probe_wave() {
+        let mut file = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        file.extend(16u32.to_le_bytes());
+        file.extend(1u16.to_le_bytes()); // PCM
+        file.extend(2u16.to_le_bytes());
+        file.extend(44100u32.to_le_bytes());
+        file.extend(176400u32.to_le_bytes());
+        file.extend(4u16.to_le_bytes());
+        file.extend(16u16.to_le_bytes());
+        file.extend(b"data");
+        file.extend(352800u32.to_le_bytes());
+        file.resize(file.len() + 352800, 0);
+
+        let metadata = probe_container(Cursor::new(file)).unwrap();
+        assert_eq!(metadata.duration_millis, Some(2000));
+        assert_eq!(metadata.codecs, vec!["pcm".to_owned()]);
+        assert_eq!(metadata.channel_count, Some(2));
+        assert_eq!(metadata.sample_rate, Some(44100));
+    }
+}
@@ -1,21 +1,22 @@
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
+    future::Future,
+    pin::Pin,
     rc::{Rc, Weak},
     sync::Arc,
 };
 
 use async_trait::async_trait;
 use irondash_message_channel::{
-    AsyncMethodHandler, AsyncMethodInvoker, IntoValue, IsolateId, Late, MethodCall, PlatformResult,
-    RegisteredAsyncMethodHandler, TryFromValue, Value,
+    AsyncMethodHandler, AsyncMethodInvoker, IntoValue, IsolateId, Late, MethodCall, PlatformError,
+    PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
-use irondash_run_loop::spawn;
 use log::warn;
 
 use crate::{
     api_model::{
-        DeferredMenuResponse, ImageData, MenuConfiguration, MenuElement, Point,
+        DeferredMenuResponse, ImageData, MenuActionState, MenuConfiguration, MenuElement, Point,
         ShowContextMenuRequest, ShowContextMenuResponse,
     },
     context::Context,
@@ -23,6 +24,7 @@ use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::{OkLog, OkLogUnexpected},
     platform_impl::platform::{PlatformDragContext, PlatformMenu, PlatformMenuContext},
+    task_scope::TaskScopes,
     util::NextId,
     value_promise::{Promise, PromiseResult},
 };
@@ -55,11 +57,95 @@ pub trait PlatformMenuContextDelegate {
 pub trait PlatformMenuDelegate {
     fn on_action(&self, isolate_id: IsolateId, action: i64);
 
+    /// Spawns future owned by the isolate. Used by platform menus to load
+    /// deferred items; the future is dropped when the isolate is destroyed.
+    fn spawn(&self, isolate_id: IsolateId, future: Pin<Box<dyn Future<Output = ()>>>);
+
     async fn get_deferred_menu(
         &self,
         isolate_id: IsolateId,
         id: i64,
     ) -> NativeExtensionsResult<Vec<MenuElement>>;
+
+    /// Children of [`crate::api_model::DeferredMenu`].
+    async fn get_deferred_menu_items(
+        &self,
+        isolate_id: IsolateId,
+        id: i64,
+    ) -> NativeExtensionsResult<Vec<MenuElement>>;
+}
+
+struct StatefulMenuItem<T> {
+    item: T,
+    radio_group: Option<String>,
+}
+
+/// Platform items of a menu that can change state after the menu was
+/// created, keyed by unique id of the action.
+pub struct MenuItemStates<T> {
+    items: RefCell<HashMap<i64, StatefulMenuItem<T>>>,
+}
+
+impl<T: Clone> MenuItemStates<T> {
+    pub fn new() -> Self {
+        Self {
+            items: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn register(&self, unique_id: i64, item: T, radio_group: Option<String>) {
+        self.items
+            .borrow_mut()
+            .insert(unique_id, StatefulMenuItem { item, radio_group });
+    }
+
+    /// Items to update when changing state of given item. Turning radio item
+    /// on turns off other items of its group.
+    pub fn updates_for_state(
+        &self,
+        unique_id: i64,
+        state: MenuActionState,
+    ) -> NativeExtensionsResult<Vec<(T, MenuActionState)>> {
+        let items = self.items.borrow();
+        let item = items
+            .get(&unique_id)
+            .ok_or(NativeExtensionsError::InvalidMenuElement)?;
+        let mut res = vec![(item.item.clone(), state)];
+        if let (MenuActionState::RadioOn, Some(group)) = (state, &item.radio_group) {
+            res.extend(
+                items
+                    .iter()
+                    .filter(|(id, other)| {
+                        **id != unique_id && other.radio_group.as_ref() == Some(group)
+                    })
+                    .map(|(_, other)| (other.item.clone(), MenuActionState::RadioOff)),
+            );
+        }
+        Ok(res)
+    }
+
+    /// Items to update when item is activated by the user; only items in a
+    /// radio group change state by themselves.
+    pub fn updates_for_activation(&self, unique_id: i64) -> Vec<(T, MenuActionState)> {
+        let in_group = self
+            .items
+            .borrow()
+            .get(&unique_id)
+            .map(|item| item.radio_group.is_some())
+            .unwrap_or(false);
+        if in_group {
+            self.updates_for_state(unique_id, MenuActionState::RadioOn)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl<T: Clone> Default for MenuItemStates<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct MenuManager {
@@ -68,6 +154,7 @@ pub struct MenuManager {
     contexts: RefCell<HashMap<PlatformMenuContextId, Rc<PlatformMenuContext>>>,
     next_id: Cell<i64>,
     menus: RefCell<HashMap<i64, Rc<PlatformMenu>>>,
+    tasks: TaskScopes<IsolateId>,
 }
 
 pub trait GetMenuManager {
@@ -86,6 +173,14 @@ struct MenuContextInitRequest {
     engine_handle: i64,
 }
 
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct UpdateMenuItemStateRequest {
+    menu_handle: i64,
+    unique_id: i64,
+    state: MenuActionState,
+}
+
 #[derive(TryFromValue)]
 #[irondash(rename_all = "camelCase")]
 struct UpdatePreviewImageRequest {
@@ -101,6 +196,7 @@ impl MenuManager {
             contexts: RefCell::new(HashMap::new()),
             next_id: Cell::new(0),
             menus: RefCell::new(HashMap::new()),
+            tasks: TaskScopes::new("MenuManager"),
         }
         .register("MenuManager")
     }
@@ -149,6 +245,19 @@ impl MenuManager {
         Ok(())
     }
 
+    fn update_menu_item_state(
+        &self,
+        request: UpdateMenuItemStateRequest,
+    ) -> NativeExtensionsResult<()> {
+        let menu = self
+            .menus
+            .borrow()
+            .get(&request.menu_handle)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformMenuNotFound)?;
+        menu.update_item_state(request.unique_id, request.state)
+    }
+
     fn update_preview_image(
         &self,
         request: UpdatePreviewImageRequest,
@@ -240,6 +349,10 @@ impl PlatformMenuDelegate for MenuManager {
             });
     }
 
+    fn spawn(&self, isolate_id: IsolateId, future: Pin<Box<dyn Future<Output = ()>>>) {
+        self.tasks.spawn(isolate_id, future);
+    }
+
     async fn get_deferred_menu(
         &self,
         isolate_id: IsolateId,
@@ -251,6 +364,18 @@ impl PlatformMenuDelegate for MenuManager {
             .await?;
         Ok(response.elements)
     }
+
+    async fn get_deferred_menu_items(
+        &self,
+        isolate_id: IsolateId,
+        id: i64,
+    ) -> NativeExtensionsResult<Vec<MenuElement>> {
+        let response: DeferredMenuResponse = self
+            .invoker
+            .call_method_cv(isolate_id, "getDeferredMenuItems", id)
+            .await?;
+        Ok(response.elements)
+    }
 }
 
 impl PlatformMenuContextDelegate for MenuManager {
@@ -304,27 +429,32 @@ impl PlatformMenuContextDelegate for MenuManager {
     ) -> Arc<Promise<PromiseResult<MenuConfiguration>>> {
         let res = Arc::new(Promise::new());
         let res_clone = res.clone();
+        let res_abort = res.clone();
         let weak_self = self.weak_self.clone();
-        spawn(async move {
-            let this = weak_self.upgrade();
-            if let Some(this) = this {
-                match this
-                    .get_menu_configuration_for_location(context_id, location)
-                    .await
-                    .ok_log_unexpected()
-                    .flatten()
-                {
-                    Some(data) => {
-                        res_clone.set(PromiseResult::Ok { value: data });
-                    }
-                    None => {
-                        res_clone.set(PromiseResult::Cancelled);
+        self.tasks.spawn_or_else(
+            context_id,
+            async move {
+                let this = weak_self.upgrade();
+                if let Some(this) = this {
+                    match this
+                        .get_menu_configuration_for_location(context_id, location)
+                        .await
+                        .ok_log_unexpected()
+                        .flatten()
+                    {
+                        Some(data) => {
+                            res_clone.set(PromiseResult::Ok { value: data });
+                        }
+                        None => {
+                            res_clone.set(PromiseResult::Cancelled);
+                        }
                     }
+                } else {
+                    res_clone.set(PromiseResult::Cancelled);
                 }
-            } else {
-                res_clone.set(PromiseResult::Cancelled);
-            }
-        });
+            },
+            move || res_abort.set(PromiseResult::Cancelled),
+        );
         res
     }
 }
@@ -355,6 +485,10 @@ impl AsyncMethodHandler for MenuManager {
                 self.dispose_menu(call.args.try_into()?).await?;
                 Ok(Value::Null)
             }
+            "updateMenuItemState" => {
+                self.update_menu_item_state(call.args.try_into()?)?;
+                Ok(Value::Null)
+            }
             "updatePreviewImage" => {
                 self.update_preview_image(call.args.try_into()?, call.isolate)?;
                 Ok(Value::Null)
@@ -365,7 +499,15 @@ impl AsyncMethodHandler for MenuManager {
                     .await?;
                 Ok(res.into())
             }
-            _ => Ok(Value::Null),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
         }
     }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        self.tasks.close(isolate);
+    }
 }
@@ -0,0 +1,122 @@
+//! In-memory LRU cache of item data.
+//!
+//! Rich paste previews tend to read the same representation several times
+//! (to sniff, to preview and to insert). Reader manager keeps data it read
+//! here keyed by reader, item, format and a generation of read settings
+//! (format converters, RTF synthesis and format conversions), and drops
+//! entries of a reader when it is disposed, refreshed or the clipboard
+//! changes. Clipboard readers without platform change count (Linux) are not
+//! cached, as there is no cheap way to tell their data is still current.
+
+use std::{collections::VecDeque, mem::size_of_val};
+
+use irondash_message_channel::Value;
+
+/// Approximate memory used by value.
+pub fn value_size(value: &Value) -> usize {
+    match value {
+        Value::String(string) => string.len(),
+        Value::U8List(data) => data.len(),
+        Value::I8List(data) => size_of_val(data.as_slice()),
+        Value::U16List(data) => size_of_val(data.as_slice()),
+        Value::I16List(data) => size_of_val(data.as_slice()),
+        Value::U32List(data) => size_of_val(data.as_slice()),
+        Value::I32List(data) => size_of_val(data.as_slice()),
+        Value::I64List(data) => size_of_val(data.as_slice()),
+        Value::F32List(data) => size_of_val(data.as_slice()),
+        Value::F64List(data) => size_of_val(data.as_slice()),
+        Value::List(values) => values.iter().map(value_size).sum(),
+        Value::Map(map) => map
+            .iter()
+            .map(|(key, value)| value_size(key) + value_size(value))
+            .sum(),
+        _ => 8,
+    }
+}
+
+struct CacheEntry<K> {
+    key: K,
+    value: Value,
+    size: usize,
+}
+
+pub struct ReadCache<K> {
+    /// Least recently used first.
+    entries: VecDeque<CacheEntry<K>>,
+    size: usize,
+    max_entries: usize,
+    max_size: usize,
+}
+
+impl<K: PartialEq> ReadCache<K> {
+    pub fn new(max_entries: usize, max_size: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            size: 0,
+            max_entries,
+            max_size,
+        }
+    }
+
+    fn remove_at(&mut self, index: usize) -> Option<CacheEntry<K>> {
+        let entry = self.entries.remove(index)?;
+        self.size -= entry.size;
+        Some(entry)
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<Value> {
+        let index = self.entries.iter().position(|e| &e.key == key)?;
+        let entry = self.remove_at(index)?;
+        let value = entry.value.clone();
+        self.size += entry.size;
+        self.entries.push_back(entry);
+        Some(value)
+    }
+
+    /// Values larger than the whole cache are not stored.
+    pub fn insert(&mut self, key: K, value: Value) {
+        if let Some(index) = self.entries.iter().position(|e| e.key == key) {
+            self.remove_at(index);
+        }
+        let size = value_size(&value);
+        if size > self.max_size {
+            return;
+        }
+        self.entries.push_back(CacheEntry { key, value, size });
+        self.size += size;
+        while self.entries.len() > self.max_entries || self.size > self.max_size {
+            self.remove_at(0);
+        }
+    }
+
+    /// Keeps only entries for which `f` returns true.
+    pub fn retain<F: Fn(&K) -> bool>(&mut self, f: F) {
+        self.entries.retain(|e| f(&e.key));
+        self.size = self.entries.iter().map(|e| e.size).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use super::ReadCache;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let data = |len: usize| Value::U8List(vec![0; len]);
+        let mut cache = ReadCache::new(3, 100);
+        cache.insert(1, data(40));
+        cache.insert(2, data(40));
+        assert!(cache.get(&1).is_some());
+        // Exceeds size, evicts 2 which was used least recently.
+        cache.insert(3, data(40));
+        assert!(cache.get(&2).is_none());
+        assert!(cache.get(&1).is_some());
+        cache.insert(4, data(1000));
+        assert!(cache.get(&4).is_none());
+        cache.retain(|key| *key != 1);
+        assert!(cache.get(&1).is_none());
+        assert!(cache.get(&3).is_some());
+    }
+}
@@ -1,8 +1,16 @@
 use std::{
     cell::{Cell, RefCell},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    future::{poll_fn, Future},
+    io::Read,
+    path::{Path, PathBuf},
+    pin::pin,
     rc::{Rc, Weak},
     sync::{self, Arc, Mutex},
+    task::Poll,
+    thread,
+    time::Duration,
 };
 
 use async_trait::async_trait;
@@ -12,14 +20,47 @@ use irondash_message_channel::{
     IsolateId, Late, MethodCall, PlatformError, PlatformResult, RegisteredAsyncMethodHandler,
     TryFromValue, Value,
 };
-use irondash_run_loop::{util::Capsule, RunLoop, RunLoopSender};
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop, RunLoopSender,
+};
 
 use crate::{
+    api_model::ClipboardTarget,
+    archive::{is_archive_format, with_archive, ArchiveEntry},
+    binary_protocol::{
+        binary_channels, configure_binary_channels, encode_progress, encode_record,
+        remove_binary_channels, RecordKind,
+    },
+    cancellation::CancellationToken,
+    clipboard_monitor::GetClipboardMonitor,
+    clipboard_reader::{new_clipboard_reader_with_token, ClipboardToken},
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
-    log::OkLog,
+    file_policy::FilePolicy,
+    file_uri_list::{self, FILE_URI_LIST_FORMAT},
+    format_converter::{DartFormatConverterDescriptor, FormatConverter, FormatConverters},
+    format_fidelity::format_fidelity,
+    import_pipeline::{
+        path_from_value, FileSource, ImportPipeline, ImportResult, ImportTarget, ResolvedFile,
+    },
+    link_detection::{detect_entities, DetectedEntity, EntityKind},
+    local_transfer::read_local_transfer,
+    log::{OkLog, OperationSpan},
+    managed_directory::ManagedDirectory,
+    media_info::{read_media_info, MediaInfo},
     platform::PlatformDataReader,
+    read_cache::ReadCache,
+    reader_snapshot::{ReaderSnapshot, SnapshotItem, DEFAULT_SNAPSHOT_SIZE_LIMIT},
+    rich_text::{read_rich_text, TextSpan},
+    rtf_html,
+    shared_buffer::{configure_shared_buffers, remove_shared_buffer_configuration, share_if_large},
+    shutdown,
+    source_url::{read_source_url, SourceUrl},
+    task_scope::TaskScopes,
+    transform_rules::{TransformRule, TransformRules},
     util::{DropNotifier, NextId},
+    web_archive::{read_web_archive, WebArchive},
 };
 
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
@@ -37,12 +78,54 @@ pub struct DataReaderManager {
     next_id: Cell<i64>,
     readers: RefCell<HashMap<DataReaderId, ReaderEntry>>,
     progresses: RefCell<HashMap<(IsolateId, i64), sync::Weak<ReadProgress>>>,
+    /// Parent of all progress tokens of the isolate.
+    isolate_tokens: RefCell<HashMap<IsolateId, CancellationToken>>,
     virtual_file_readers: RefCell<HashMap<(IsolateId, i64), Rc<dyn VirtualFileReader>>>,
+    managed_directory: ManagedDirectory,
+    transform_rules: RefCell<HashMap<IsolateId, Rc<TransformRules>>>,
+    file_policy: RefCell<Option<Rc<FilePolicy>>>,
+    format_converters: RefCell<HashMap<IsolateId, FormatConverters>>,
+    /// Registered converters combined with built-in ones, composed once per
+    /// isolate and dropped whenever the registration changes.
+    effective_converters: RefCell<HashMap<IsolateId, Option<Rc<FormatConverters>>>>,
+    /// Isolates that opted in to HTML synthesized from RTF.
+    rtf_html_isolates: RefCell<HashSet<IsolateId>>,
+    /// Keyed by reader, item, format and read settings generation.
+    read_cache: RefCell<ReadCache<(DataReaderId, i64, String, i64)>>,
+    /// Bumped whenever settings that change what a read returns (converters,
+    /// RTF synthesis, format conversions) change, so that reads started
+    /// before the change are not cached under current settings.
+    read_settings_generation: Cell<i64>,
+    /// Active format availability subscriptions.
+    format_subscriptions: RefCell<HashMap<(IsolateId, i64), FormatSubscription>>,
+    /// Child progress ids registered with `createCompositeProgress` that
+    /// were not used by a request yet.
+    composite_children: RefCell<HashMap<(IsolateId, i64), (CompositeReadProgress, usize)>>,
+    /// Leases of files written by `getVirtualFileManaged`.
+    file_leases: RefCell<HashMap<i64, FileLease>>,
+    tasks: TaskScopes<IsolateId>,
+}
+
+/// Sources may add representations shortly after copy (browsers and Office
+/// do that); availability subscriptions poll for them at this interval.
+const FORMAT_SUBSCRIPTION_DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+const READ_CACHE_MAX_ENTRIES: usize = 64;
+const READ_CACHE_MAX_SIZE: usize = 32 * 1024 * 1024;
+
+struct FileLease {
+    reader: DataReaderId,
+    _finalizable_handle: Arc<FinalizableHandle>,
 }
 
 struct ReaderEntry {
+    isolate_id: IsolateId,
     platform_reader: Rc<PlatformDataReader>,
     _finalizable_handle: Arc<FinalizableHandle>,
+    /// Clipboard state reader was bound to; only set for clipboard readers.
+    clipboard_token: Option<ClipboardToken>,
+    /// Contents read upfront; set for readers created by `snapshotReader`.
+    snapshot: Option<Rc<ReaderSnapshot>>,
 }
 
 pub trait GetDataReaderManager {
@@ -56,36 +139,45 @@ impl GetDataReaderManager for Context {
 }
 
 struct ReadProgressInner {
-    cancellation_handler: Option<Box<dyn FnOnce() + Send>>,
-    on_set_cancellation_handler: Box<dyn Fn(bool /* is cancellable */)>,
-    on_progress: Box<dyn Fn(Option<f64>)>,
+    on_set_cancellable: Box<dyn Fn(bool)>,
+    on_progress: Box<dyn Fn(ReadProgressUpdate)>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReadProgressUpdate {
+    /// 0.0 - 1.0, `None` when indeterminate.
+    pub fraction: Option<f64>,
+    pub bytes_transferred: Option<i64>,
+    pub total_bytes: Option<i64>,
 }
 
-pub struct ReadProgress {
+struct ReadProgress {
     _drop_notifier: Arc<DropNotifier>,
     sender: RunLoopSender,
+    token: CancellationToken,
     inner: Mutex<Capsule<ReadProgressInner>>,
 }
 
-/// Progress is thread safe. It must be created on main thread. Callbacks
-/// specified in constructor are guaranteed to be invoked on main thread.
+/// Must be created on main thread. All state lives on the main thread;
+/// calls made elsewhere are forwarded there.
 impl ReadProgress {
-    pub fn new<F1, F2>(
+    fn new<F1, F2>(
         drop_notifier: Arc<DropNotifier>,
-        on_set_cancellation_handler: F1,
+        token: CancellationToken,
+        on_set_cancellable: F1,
         on_progress: F2,
     ) -> Self
     where
         F1: Fn(bool) + 'static,
-        F2: Fn(Option<f64>) + 'static,
+        F2: Fn(ReadProgressUpdate) + 'static,
     {
         Self {
             _drop_notifier: drop_notifier,
             sender: RunLoop::current().new_sender(),
+            token,
             inner: Mutex::new(Capsule::new_with_sender(
                 ReadProgressInner {
-                    cancellation_handler: None,
-                    on_set_cancellation_handler: Box::new(on_set_cancellation_handler),
+                    on_set_cancellable: Box::new(on_set_cancellable),
                     on_progress: Box::new(on_progress),
                 },
                 RunLoop::current().new_sender(),
@@ -93,52 +185,275 @@ impl ReadProgress {
         }
     }
 
-    #[allow(dead_code)]
-    pub fn set_cancellation_handler(self: &Arc<Self>, handler: Option<Box<dyn FnOnce() + Send>>) {
+    fn set_cancellable(self: &Arc<Self>, cancellable: bool) {
         if self.sender.is_same_thread() {
-            let mut inner = self.inner.lock().unwrap();
-            let inner = inner.get_mut().unwrap();
-            (inner.on_set_cancellation_handler)(handler.is_some());
-            inner.cancellation_handler = handler;
+            let inner = self.inner.lock().unwrap();
+            let inner = inner.get_ref().unwrap();
+            (inner.on_set_cancellable)(cancellable);
         } else {
             let self_clone = self.clone();
             self.sender.send(move || {
-                self_clone.set_cancellation_handler(handler);
+                self_clone.set_cancellable(cancellable);
             });
         }
     }
-    #[allow(dead_code)]
-    pub fn report_progress(self: &Arc<Self>, fraction: Option<f64>) {
+
+    fn report(self: &Arc<Self>, update: ReadProgressUpdate) {
         if self.sender.is_same_thread() {
             let inner = self.inner.lock().unwrap();
             let inner = inner.get_ref().unwrap();
-            (inner.on_progress)(fraction);
+            (inner.on_progress)(update);
         } else {
             let self_clone = self.clone();
             self.sender.send(move || {
-                self_clone.report_progress(fraction);
+                self_clone.report(update);
             });
         }
     }
 
-    #[allow(dead_code)]
-    pub fn cancel(self: &Arc<Self>) {
-        if self.sender.is_same_thread() {
-            let mut inner = self.inner.lock().unwrap();
-            let inner = inner.get_mut().unwrap();
-            let handler = inner.cancellation_handler.take();
-            if let Some(handler) = handler {
-                handler();
-            }
-        } else {
-            let self_clone = self.clone();
-            self.sender.send(move || {
-                self_clone.cancel();
-            });
+    fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+/// Progress of a read request as seen by platform reader implementations.
+///
+/// The handle is `Send + Sync` and can be cloned and moved freely between
+/// threads. It must be created on main thread; calls made from other threads
+/// are marshaled to the main thread, so the callbacks given to the
+/// constructor always run there. Calls made from other threads are
+/// asynchronous and take effect in order.
+///
+/// Cancellation is backed by a [`CancellationToken`]; progress created with
+/// [`ReadProgressHandle::child`] is cancelled together with its parent.
+/// Platform code observes cancellation through [`Self::cancellation_token`],
+/// either by polling it or by registering a handler with
+/// [`CancellationToken::on_cancel`].
+#[derive(Clone)]
+pub struct ReadProgressHandle {
+    progress: Arc<ReadProgress>,
+}
+
+impl ReadProgressHandle {
+    /// `on_set_cancellable` is invoked whenever the request becomes
+    /// cancellable or stops being cancellable, `on_progress` with reported
+    /// updates.
+    pub fn new<F1, F2>(
+        drop_notifier: Arc<DropNotifier>,
+        on_set_cancellable: F1,
+        on_progress: F2,
+    ) -> Self
+    where
+        F1: Fn(bool) + 'static,
+        F2: Fn(ReadProgressUpdate) + 'static,
+    {
+        Self::new_with_token(
+            drop_notifier,
+            CancellationToken::new(),
+            on_set_cancellable,
+            on_progress,
+        )
+    }
+
+    fn new_with_token<F1, F2>(
+        drop_notifier: Arc<DropNotifier>,
+        token: CancellationToken,
+        on_set_cancellable: F1,
+        on_progress: F2,
+    ) -> Self
+    where
+        F1: Fn(bool) + 'static,
+        F2: Fn(ReadProgressUpdate) + 'static,
+    {
+        Self {
+            progress: Arc::new(ReadProgress::new(
+                drop_notifier,
+                token,
+                on_set_cancellable,
+                on_progress,
+            )),
+        }
+    }
+
+    /// Progress for part of the operation; cancelled when this progress is
+    /// cancelled. Updates are passed to `on_progress`.
+    pub fn child<F>(&self, on_progress: F) -> Self
+    where
+        F: Fn(ReadProgressUpdate) + 'static,
+    {
+        Self::new_with_token(
+            Arc::new(DropNotifier::new(|| {})),
+            self.progress.token.child(),
+            |_| {},
+            on_progress,
+        )
+    }
+
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.progress.token
+    }
+
+    /// Marks the request as cancellable (or not); determines whether user
+    /// can cancel it. Operations that support cancellation observe
+    /// [`Self::cancellation_token`].
+    pub fn set_cancellable(&self, cancellable: bool) {
+        self.progress.set_cancellable(cancellable)
+    }
+
+    pub fn report_progress(&self, fraction: Option<f64>) {
+        self.report(ReadProgressUpdate {
+            fraction,
+            ..Default::default()
+        })
+    }
+
+    /// Reports transferred bytes; fraction is derived from total size when
+    /// known.
+    pub fn report_bytes(&self, bytes_transferred: i64, total_bytes: Option<i64>) {
+        let fraction = total_bytes
+            .filter(|total| *total > 0)
+            .map(|total| (bytes_transferred as f64 / total as f64).min(1.0));
+        self.report(ReadProgressUpdate {
+            fraction,
+            bytes_transferred: Some(bytes_transferred),
+            total_bytes,
+        })
+    }
+
+    pub fn report(&self, update: ReadProgressUpdate) {
+        self.progress.report(update)
+    }
+
+    pub fn cancel(&self) {
+        self.progress.cancel()
+    }
+
+    fn downgrade(&self) -> sync::Weak<ReadProgress> {
+        Arc::downgrade(&self.progress)
+    }
+}
+
+struct CompositeState {
+    weights: Vec<f64>,
+    fractions: Vec<f64>,
+}
+
+impl CompositeState {
+    /// Returns new weighted total, or `None` if the part didn't change.
+    fn update(&mut self, index: usize, fraction: f64) -> Option<f64> {
+        let current = self.fractions.get_mut(index)?;
+        // Finished parts stay finished.
+        let fraction = fraction.clamp(0.0, 1.0).max(*current);
+        if fraction == *current {
+            return None;
+        }
+        *current = fraction;
+        let total_weight: f64 = self.weights.iter().sum();
+        let done: f64 = self
+            .weights
+            .iter()
+            .zip(&self.fractions)
+            .map(|(w, f)| w * f)
+            .sum();
+        Some(done / total_weight)
+    }
+}
+
+/// Progress of a batch operation, such as reading many dropped files, made
+/// of weighted parts. Fractions of the parts roll up into the parent as a
+/// weighted total and cancelling the parent cancels all parts. Parts that
+/// are dropped count as finished unless the parent was cancelled.
+#[derive(Clone)]
+pub struct CompositeReadProgress {
+    parent: ReadProgressHandle,
+    state: Arc<Mutex<CompositeState>>,
+}
+
+impl CompositeReadProgress {
+    /// Weights that are not positive or not finite are treated as 1. Parent
+    /// is marked as cancellable.
+    pub fn new(parent: ReadProgressHandle, weights: Vec<f64>) -> Self {
+        let weights: Vec<_> = weights
+            .into_iter()
+            .map(|w| if w.is_finite() && w > 0.0 { w } else { 1.0 })
+            .collect();
+        parent.set_cancellable(true);
+        Self {
+            parent,
+            state: Arc::new(Mutex::new(CompositeState {
+                fractions: vec![0.0; weights.len()],
+                weights,
+            })),
+        }
+    }
+
+    pub fn with_equal_weights(parent: ReadProgressHandle, count: usize) -> Self {
+        Self::new(parent, vec![1.0; count])
+    }
+
+    pub fn parent(&self) -> &ReadProgressHandle {
+        &self.parent
+    }
+
+    /// Progress for part at `index`; cancelled together with the parent.
+    pub fn part(&self, index: usize) -> ReadProgressHandle {
+        self.part_with_drop_notifier(index, || {})
+    }
+
+    /// Like [`CompositeReadProgress::part`]; `on_drop` is invoked after the
+    /// part is dropped.
+    fn part_with_drop_notifier<F>(&self, index: usize, on_drop: F) -> ReadProgressHandle
+    where
+        F: FnOnce() + 'static,
+    {
+        let this = self.clone();
+        let on_progress = self.clone();
+        ReadProgressHandle::new_with_token(
+            Arc::new(DropNotifier::new(move || {
+                if !this.parent.cancellation_token().is_cancelled() {
+                    this.finish_part(index);
+                }
+                on_drop();
+            })),
+            self.parent.cancellation_token().child(),
+            |_| {},
+            move |update: ReadProgressUpdate| {
+                if let Some(fraction) = update.fraction {
+                    on_progress.update_part(index, fraction);
+                }
+            },
+        )
+    }
+
+    pub fn finish_part(&self, index: usize) {
+        self.update_part(index, 1.0);
+    }
+
+    fn update_part(&self, index: usize, fraction: f64) {
+        let total = self.state.lock().unwrap().update(index, fraction);
+        if let Some(total) = total {
+            self.parent.report_progress(Some(total));
         }
     }
 }
 
+/// `progressId` of request arguments, if any.
+fn progress_id_argument(args: &Value) -> Option<i64> {
+    let Value::Map(map) = args else {
+        return None;
+    };
+    map.iter().find_map(|(key, value)| match (key, value) {
+        (Value::String(key), Value::I64(value)) if key == "progressId" => Some(*value),
+        _ => None,
+    })
+}
+
+#[allow(dead_code)]
+fn assert_read_progress_handle_is_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ReadProgressHandle>();
+}
+
 impl DataReaderManager {
     pub fn new() -> RegisteredAsyncMethodHandler<Self> {
         Self {
@@ -147,12 +462,45 @@ impl DataReaderManager {
             next_id: Cell::new(1),
             readers: RefCell::new(HashMap::new()),
             progresses: RefCell::new(HashMap::new()),
+            isolate_tokens: RefCell::new(HashMap::new()),
             virtual_file_readers: RefCell::new(HashMap::new()),
+            managed_directory: ManagedDirectory::new(),
+            transform_rules: RefCell::new(HashMap::new()),
+            file_policy: RefCell::new(None),
+            format_converters: RefCell::new(HashMap::new()),
+            effective_converters: RefCell::new(HashMap::new()),
+            rtf_html_isolates: RefCell::new(HashSet::new()),
+            read_cache: RefCell::new(ReadCache::new(READ_CACHE_MAX_ENTRIES, READ_CACHE_MAX_SIZE)),
+            read_settings_generation: Cell::new(0),
+            format_subscriptions: RefCell::new(HashMap::new()),
+            composite_children: RefCell::new(HashMap::new()),
+            file_leases: RefCell::new(HashMap::new()),
+            tasks: TaskScopes::new("DataReaderManager"),
         }
         .register("DataReaderManager")
     }
 
-    fn new_read_progress(&self, isolate_id: IsolateId, progress_id: i64) -> Arc<ReadProgress> {
+    /// Progress ids that are part of a composite progress report into it.
+    fn new_read_progress(&self, isolate_id: IsolateId, progress_id: i64) -> ReadProgressHandle {
+        let composite = self
+            .composite_children
+            .borrow_mut()
+            .remove(&(isolate_id, progress_id));
+        if let Some((composite, index)) = composite {
+            let weak_self = self.weak_self.clone();
+            let res = composite.part_with_drop_notifier(index, move || {
+                if let Some(this) = weak_self.upgrade() {
+                    this.progresses
+                        .borrow_mut()
+                        .remove(&(isolate_id, progress_id));
+                }
+            });
+            // Lets `cancelProgress` cancel the part alone.
+            self.progresses
+                .borrow_mut()
+                .insert((isolate_id, progress_id), res.downgrade());
+            return res;
+        }
         #[derive(IntoValue)]
         #[irondash(rename_all = "camelCase")]
         struct SetProgressCancellable {
@@ -164,11 +512,19 @@ impl DataReaderManager {
         struct ProgressUpdate {
             progress_id: i64,
             fraction: Option<f64>,
+            bytes_transferred: Option<i64>,
+            total_bytes: Option<i64>,
         }
         let weak_self_1 = self.weak_self.clone();
         let weak_self_2 = self.weak_self.clone();
         let weak_self_3 = self.weak_self.clone();
-        let res = Arc::new(ReadProgress::new(
+        let token = self
+            .isolate_tokens
+            .borrow_mut()
+            .entry(isolate_id)
+            .or_default()
+            .child();
+        let res = ReadProgressHandle::new_with_token(
             Arc::new(DropNotifier::new(move || {
                 if let Some(this) = weak_self_1.upgrade() {
                     this.progresses
@@ -176,6 +532,7 @@ impl DataReaderManager {
                         .remove(&(isolate_id, progress_id));
                 }
             })),
+            token,
             move |cancellable| {
                 if let Some(this) = weak_self_2.upgrade() {
                     this.invoker.call_method_sync(
@@ -191,14 +548,33 @@ impl DataReaderManager {
                     );
                 }
             },
-            move |fraction| {
+            move |update: ReadProgressUpdate| {
                 if let Some(this) = weak_self_3.upgrade() {
+                    if binary_channels(isolate_id).progress {
+                        let update = encode_progress(
+                            progress_id,
+                            update.fraction,
+                            update.bytes_transferred,
+                            update.total_bytes,
+                        );
+                        this.invoker.call_method_sync(
+                            isolate_id,
+                            "updateProgressBinary",
+                            update,
+                            |r| {
+                                r.ok_log();
+                            },
+                        );
+                        return;
+                    }
                     this.invoker.call_method_sync(
                         isolate_id,
                         "updateProgress",
                         ProgressUpdate {
                             progress_id,
-                            fraction,
+                            fraction: update.fraction,
+                            bytes_transferred: update.bytes_transferred,
+                            total_bytes: update.total_bytes,
                         },
                         |r| {
                             r.ok_log();
@@ -206,10 +582,10 @@ impl DataReaderManager {
                     );
                 }
             },
-        ));
+        );
         self.progresses
             .borrow_mut()
-            .insert((isolate_id, progress_id), Arc::downgrade(&res));
+            .insert((isolate_id, progress_id), res.downgrade());
         res
     }
 
@@ -217,20 +593,70 @@ impl DataReaderManager {
         &self,
         platform_reader: Rc<PlatformDataReader>,
         isolate_id: IsolateId,
+    ) -> RegisteredDataReader {
+        self.register_reader(platform_reader, None, None, isolate_id)
+    }
+
+    pub fn register_clipboard_reader(
+        &self,
+        platform_reader: Rc<PlatformDataReader>,
+        token: ClipboardToken,
+        isolate_id: IsolateId,
+    ) -> RegisteredDataReader {
+        self.register_reader(platform_reader, Some(token), None, isolate_id)
+    }
+
+    /// Registers reader that serves contents of `snapshot` instead of reading
+    /// them from `platform_reader`.
+    pub fn register_reader_with_snapshot(
+        &self,
+        platform_reader: Rc<PlatformDataReader>,
+        snapshot: Rc<ReaderSnapshot>,
+        isolate_id: IsolateId,
+    ) -> RegisteredDataReader {
+        self.register_reader(platform_reader, None, Some(snapshot), isolate_id)
+    }
+
+    /// Registers reader that serves its current contents from memory.
+    pub async fn register_snapshot_reader(
+        &self,
+        platform_reader: Rc<PlatformDataReader>,
+        isolate_id: IsolateId,
+    ) -> NativeExtensionsResult<(RegisteredDataReader, Rc<ReaderSnapshot>)> {
+        let policy = self.file_policy.borrow().clone();
+        let snapshot = Rc::new(
+            platform_reader
+                .snapshot(policy.as_deref(), DEFAULT_SNAPSHOT_SIZE_LIMIT, None)
+                .await?,
+        );
+        let reader =
+            self.register_reader(platform_reader, None, Some(snapshot.clone()), isolate_id);
+        Ok((reader, snapshot))
+    }
+
+    fn register_reader(
+        &self,
+        platform_reader: Rc<PlatformDataReader>,
+        clipboard_token: Option<ClipboardToken>,
+        snapshot: Option<Rc<ReaderSnapshot>>,
+        isolate_id: IsolateId,
     ) -> RegisteredDataReader {
         let id: DataReaderId = self.next_id.next_id().into();
         let weak_self = self.weak_self.clone();
         let finalizable_handle = Arc::new(FinalizableHandle::new(32, isolate_id, move || {
             if let Some(manager) = weak_self.upgrade() {
-                manager.readers.borrow_mut().remove(&id);
+                manager.dispose_reader(id).ok_log();
             }
         }));
 
         self.readers.borrow_mut().insert(
             id,
             ReaderEntry {
+                isolate_id,
                 platform_reader,
                 _finalizable_handle: finalizable_handle.clone(),
+                clipboard_token,
+                snapshot,
             },
         );
 
@@ -240,125 +666,1730 @@ impl DataReaderManager {
         }
     }
 
+    fn clipboard_token(
+        &self,
+        reader: DataReaderId,
+    ) -> NativeExtensionsResult<Option<ClipboardToken>> {
+        let readers = self.readers.borrow();
+        let entry = readers
+            .get(&reader)
+            .ok_or(NativeExtensionsError::ReaderNotFound)?;
+        Ok(entry.clipboard_token.clone())
+    }
+
+    /// Whether clipboard changed since the reader was created.
+    /// Always `false` for readers that are not clipboard readers.
+    async fn is_stale(&self, reader: DataReaderId) -> NativeExtensionsResult<bool> {
+        match self.clipboard_token(reader)? {
+            Some(token) => Ok(!token.is_current().await?),
+            None => Ok(false),
+        }
+    }
+
+    /// Creates new reader for current clipboard contents if they changed
+    /// since `reader` was created, `None` otherwise. The original reader is
+    /// left intact, so that item handles obtained from it keep reading the
+    /// contents they were bound to.
+    async fn refresh(
+        &self,
+        isolate_id: IsolateId,
+        reader: DataReaderId,
+    ) -> NativeExtensionsResult<Option<RegisteredDataReader>> {
+        let previous = self.clipboard_token(reader)?.ok_or_else(|| {
+            NativeExtensionsError::OtherError("Reader is not a clipboard reader".into())
+        })?;
+        let (platform_reader, token) =
+            new_clipboard_reader_with_token(previous.target().clone()).await?;
+        if token == previous {
+            return Ok(None);
+        }
+        Ok(Some(self.register_clipboard_reader(
+            platform_reader,
+            token,
+            isolate_id,
+        )))
+    }
+
+    /// Registers the platform reader of `reader` for another isolate, so
+    /// that it can read the data without going through this isolate. The
+    /// finalizable handle can only be sent to the isolate it belongs to, so
+    /// the clone is delivered to the target isolate through
+    /// `readerTransferred`; caller gets the handle to match it with. The clone
+    /// shares clipboard token and snapshot with the original but is disposed
+    /// independently.
+    fn clone_reader_for_isolate(
+        &self,
+        isolate_id: IsolateId,
+        request: CloneReaderRequest,
+    ) -> NativeExtensionsResult<DataReaderId> {
+        let (platform_reader, clipboard_token, snapshot) = {
+            let readers = self.readers.borrow();
+            let entry = readers
+                .get(&request.reader_handle)
+                .ok_or(NativeExtensionsError::ReaderNotFound)?;
+            (
+                entry.platform_reader.clone(),
+                entry.clipboard_token.clone(),
+                entry.snapshot.clone(),
+            )
+        };
+        let target_isolate = IsolateId(request.target_isolate_id);
+        let reader =
+            self.register_reader(platform_reader, clipboard_token, snapshot, target_isolate);
+        let handle = reader.handle;
+        let weak_self = self.weak_self.clone();
+        self.invoker.call_method_sync(
+            target_isolate,
+            "readerTransferred",
+            ReaderTransferred {
+                source_isolate_id: isolate_id.0,
+                reader,
+            },
+            move |r| {
+                // Target isolate is gone, handle would never be finalized.
+                if r.ok_log().is_none() {
+                    if let Some(manager) = weak_self.upgrade() {
+                        manager.dispose_reader(handle).ok_log();
+                    }
+                }
+            },
+        );
+        Ok(handle)
+    }
+
     fn dispose_reader(&self, reader: DataReaderId) -> NativeExtensionsResult<()> {
         self.readers.borrow_mut().remove(&reader);
+        let subscriptions: Vec<_> = self
+            .format_subscriptions
+            .borrow()
+            .iter()
+            .filter(|(_, s)| s.reader == reader)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in subscriptions {
+            self.end_format_subscription(key);
+        }
+        self.invalidate_read_cache(reader);
+        self.file_leases
+            .borrow_mut()
+            .retain(|_, lease| lease.reader != reader);
+        self.managed_directory
+            .remove_leased_folder(&Self::reader_lease_folder(reader));
         Ok(())
     }
 
-    fn get_reader(&self, reader: DataReaderId) -> NativeExtensionsResult<Rc<PlatformDataReader>> {
-        if let Some(entry) = self.readers.borrow().get(&reader) {
-            Ok(entry.platform_reader.clone())
-        } else {
-            Err(NativeExtensionsError::ReaderNotFound)
+    /// Handles are not guaranteed to be finalized when the isolate shuts
+    /// down. Part of [`shutdown::shutdown`].
+    pub fn shutdown(&self, isolate: IsolateId) {
+        let readers: Vec<_> = self
+            .readers
+            .borrow()
+            .iter()
+            .filter(|(_, entry)| entry.isolate_id == isolate)
+            .map(|(id, _)| *id)
+            .collect();
+        for reader in readers {
+            self.dispose_reader(reader).ok_log();
         }
     }
 
-    async fn get_items(&self, reader: DataReaderId) -> NativeExtensionsResult<Vec<i64>> {
-        self.get_reader(reader)?.get_items().await
+    fn invalidate_read_cache(&self, reader: DataReaderId) {
+        self.read_cache.borrow_mut().retain(|key| key.0 != reader);
     }
 
-    async fn get_item_formats(
-        &self,
-        request: ItemFormatsRequest,
-    ) -> NativeExtensionsResult<Vec<String>> {
-        self.get_reader(request.reader_handle)?
-            .get_formats_for_item(request.item_handle)
-            .await
+    /// Drops all cached data; called when settings affecting read results
+    /// change.
+    fn read_settings_changed(&self) {
+        self.read_settings_generation
+            .set(self.read_settings_generation.get() + 1);
+        self.read_cache.borrow_mut().retain(|_| false);
     }
 
-    async fn get_item_info(
-        &self,
-        request: ItemInfoRequest,
-    ) -> NativeExtensionsResult<ItemInfoResponse> {
-        let mut res = Vec::with_capacity(request.item_handles.len());
-        let reader = self.get_reader(request.reader_handle)?;
-        let start = std::time::Instant::now();
-        for item_handle in request.item_handles {
-            let formats = reader.get_formats_for_item(item_handle).await?;
-            let mut synthesized_formats = Vec::new();
-            let mut read_virtual_file_formats = Vec::new();
-            let mut copy_virtual_file_formats = Vec::new();
-            for format in &formats {
-                if reader.item_format_is_synthesized(item_handle, format)? {
-                    synthesized_formats.push(format.clone());
-                }
-                if reader
-                    .can_read_virtual_file_for_item(item_handle, format)
-                    .await?
-                {
-                    read_virtual_file_formats.push(format.clone());
-                }
-                if reader
-                    .can_copy_virtual_file_for_item(item_handle, format)
-                    .await?
-                {
-                    copy_virtual_file_formats.push(format.clone());
-                }
-            }
-            let suggested_name = reader.get_suggested_name_for_item(item_handle).await?;
-            let file_uri_format =
-                if copy_virtual_file_formats.is_empty() && read_virtual_file_formats.is_empty() {
-                    reader.get_item_format_for_uri(item_handle).await?
-                } else {
-                    None
-                };
-            res.push(ItemInfo {
-                handle: item_handle,
-                formats,
-                synthesized_formats,
-                copy_virtual_file_formats,
-                read_virtual_file_formats,
-                suggested_name,
-                file_uri_format,
+    /// Drops cached data of clipboard readers and checks format
+    /// subscriptions.
+    pub fn clipboard_changed(&self) {
+        {
+            let readers = self.readers.borrow();
+            self.read_cache.borrow_mut().retain(|key| {
+                readers
+                    .get(&key.0)
+                    .map(|entry| entry.clipboard_token.is_none())
+                    .unwrap_or(false)
             });
-            if let Some(timeout) = request.timeout_millis {
-                if start.elapsed().as_millis() > timeout as u128 {
-                    break;
-                }
-            }
         }
-        Ok(ItemInfoResponse { items: res })
+        self.update_format_subscriptions();
     }
 
-    async fn get_item_data(
+    /// When enabled, data from other applications is read in a helper process
+    /// (Windows only). Applies to readers created afterwards.
+    fn set_out_of_process_reading(&self, enabled: bool) -> NativeExtensionsResult<()> {
+        PlatformDataReader::set_out_of_process_reading(enabled)
+    }
+
+    /// Makes named platform clipboard format appear as `identifier` when
+    /// reading and writing (Windows only, no-op elsewhere).
+    fn register_clipboard_format(
         &self,
-        isolate_id: IsolateId,
-        request: ItemDataRequest,
-    ) -> NativeExtensionsResult<Value> {
-        let reader = self.get_reader(request.reader_handle)?;
-        let progress = self.new_read_progress(isolate_id, request.progress_id);
-        reader
-            .get_data_for_item(request.item_handle, request.format, Some(progress))
-            .await
+        request: RegisterClipboardFormatRequest,
+    ) -> NativeExtensionsResult<()> {
+        PlatformDataReader::register_clipboard_format(&request.identifier, &request.platform_name)
     }
 
-    fn cancel_progress(
+    /// When enabled, clipboard readers created afterwards ignore content
+    /// coming from other devices through Universal Clipboard (Apple only).
+    fn set_exclude_remote_content(&self, exclude: bool) -> NativeExtensionsResult<()> {
+        PlatformDataReader::set_exclude_remote_content(exclude)
+    }
+
+    fn is_remote_content(&self, reader: DataReaderId) -> NativeExtensionsResult<bool> {
+        self.get_reader(reader)?.is_remote_content()
+    }
+
+    fn new_external_reader(
         &self,
         isolate_id: IsolateId,
-        progress_id: i64,
+        source: ExternalReaderSource,
+    ) -> NativeExtensionsResult<RegisteredDataReader> {
+        let platform_reader = PlatformDataReader::new_with_external_source(source)?;
+        Ok(self.register_platform_reader(platform_reader, isolate_id))
+    }
+
+    fn set_format_conversion_enabled(
+        &self,
+        request: SetFormatConversionEnabledRequest,
     ) -> NativeExtensionsResult<()> {
-        let progress = self
-            .progresses
-            .borrow_mut()
-            .remove(&(isolate_id, progress_id));
-        if let Some(progress) = progress.and_then(|p| p.upgrade()) {
-            progress.cancel();
-        }
+        let reader = self.get_reader(request.reader_handle)?;
+        reader.set_format_conversion_enabled(request.conversion, request.enabled);
+        self.read_settings_changed();
         Ok(())
     }
 
-    async fn virtual_file_reader_create(
+    async fn get_item_metadata(
         &self,
-        isolate_id: IsolateId,
-        request: VirtualFileReaderRequest,
-    ) -> NativeExtensionsResult<VirtualFileReaderResponse> {
+        request: ItemFormatsRequest,
+    ) -> NativeExtensionsResult<ItemMetadata> {
         let reader = self.get_reader(request.reader_handle)?;
-        let progress = self.new_read_progress(isolate_id, request.progress_id);
-        let res = reader
-            .create_virtual_file_reader_for_item(request.item_handle, &request.format, progress)
-            .await?;
-        match res {
-            Some(reader) => {
+        reader.get_item_metadata(request.item_handle).await
+    }
+
+    async fn get_item_format_conversions(
+        &self,
+        request: ItemFormatsRequest,
+    ) -> NativeExtensionsResult<Vec<ItemFormatConversion>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        reader.get_format_conversions_for_item(request.item_handle)
+    }
+
+    fn get_reader(&self, reader: DataReaderId) -> NativeExtensionsResult<Rc<PlatformDataReader>> {
+        if let Some(entry) = self.readers.borrow().get(&reader) {
+            Ok(entry.platform_reader.clone())
+        } else {
+            Err(NativeExtensionsError::ReaderNotFound)
+        }
+    }
+
+    fn get_snapshot(&self, reader: DataReaderId) -> Option<Rc<ReaderSnapshot>> {
+        self.readers
+            .borrow()
+            .get(&reader)
+            .and_then(|entry| entry.snapshot.clone())
+    }
+
+    /// Creates reader that serves data of all items from memory, so that
+    /// reading does not fail or return different data when clipboard
+    /// contents change. Virtual files are still read from the source.
+    async fn snapshot_reader(
+        &self,
+        isolate_id: IsolateId,
+        request: SnapshotReaderRequest,
+    ) -> NativeExtensionsResult<RegisteredDataReader> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let policy = self.file_policy.borrow().clone();
+        let size_limit = request
+            .size_limit
+            .map(|limit| limit.max(0) as u64)
+            .unwrap_or(DEFAULT_SNAPSHOT_SIZE_LIMIT);
+        let snapshot = reader
+            .snapshot(policy.as_deref(), size_limit, Some(progress))
+            .await?;
+        Ok(self.register_reader(reader, None, Some(Rc::new(snapshot)), isolate_id))
+    }
+
+    async fn get_items(&self, reader: DataReaderId) -> NativeExtensionsResult<Vec<i64>> {
+        match self.get_snapshot(reader) {
+            Some(snapshot) => Ok(snapshot.items()),
+            None => self.get_reader(reader)?.get_items().await,
+        }
+    }
+
+    async fn get_item_formats(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemFormatsRequest,
+    ) -> NativeExtensionsResult<Vec<String>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let snapshot = self.get_snapshot(request.reader_handle);
+        self.get_formats_for_item(
+            isolate_id,
+            &reader,
+            snapshot.as_deref(),
+            request.item_handle,
+        )
+        .await
+    }
+
+    /// Formats of the item as reported by platform reader, or as captured by
+    /// snapshot.
+    async fn platform_formats_for_item(
+        reader: &PlatformDataReader,
+        snapshot: Option<&ReaderSnapshot>,
+        item: i64,
+    ) -> NativeExtensionsResult<Vec<String>> {
+        match snapshot {
+            Some(snapshot) => Ok(snapshot
+                .item(item)
+                .map(|item| item.formats.clone())
+                .unwrap_or_default()),
+            None => reader.get_formats_for_item(item).await,
+        }
+    }
+
+    fn format_converters(&self, isolate_id: IsolateId) -> Option<Rc<FormatConverters>> {
+        if let Some(converters) = self.effective_converters.borrow().get(&isolate_id) {
+            return converters.clone();
+        }
+        let mut converters = self.format_converters.borrow().get(&isolate_id).cloned();
+        if self.rtf_html_isolates.borrow().contains(&isolate_id) {
+            rtf_html::register_converters(converters.get_or_insert_with(Default::default));
+        }
+        let converters = Self::with_builtin_converters(converters).map(Rc::new);
+        self.effective_converters
+            .borrow_mut()
+            .insert(isolate_id, converters.clone());
+        converters
+    }
+
+    #[cfg(feature = "image_transcode")]
+    fn with_builtin_converters(converters: Option<FormatConverters>) -> Option<FormatConverters> {
+        let mut converters = converters.unwrap_or_default();
+        crate::image_transcode::register_image_converters(&mut converters);
+        Some(converters)
+    }
+
+    #[cfg(not(feature = "image_transcode"))]
+    fn with_builtin_converters(converters: Option<FormatConverters>) -> Option<FormatConverters> {
+        converters
+    }
+
+    /// Adds `fileUriList` and formats synthesized by converters registered
+    /// for the isolate to platform formats.
+    fn extend_formats(&self, isolate_id: IsolateId, formats: Vec<String>) -> Vec<String> {
+        let formats = file_uri_list::extend_formats(
+            formats,
+            &PlatformDataReader::file_uri_list_source_formats(),
+        );
+        match self.format_converters(isolate_id) {
+            Some(converters) => converters.extend_formats(formats),
+            None => formats,
+        }
+    }
+
+    /// Platform formats from which any of `formats` can be read, including
+    /// the formats themselves.
+    pub fn formats_satisfying(&self, isolate_id: IsolateId, formats: Vec<String>) -> Vec<String> {
+        let converters = self.format_converters(isolate_id);
+        let mut res = formats.clone();
+        let mut push = |format: String| {
+            if !res.contains(&format) {
+                res.push(format);
+            }
+        };
+        for format in &formats {
+            if format == file_uri_list::FILE_URI_LIST_FORMAT {
+                PlatformDataReader::file_uri_list_source_formats()
+                    .into_iter()
+                    .for_each(&mut push);
+            }
+            if let Some(converters) = &converters {
+                converters
+                    .source_formats(format)
+                    .into_iter()
+                    .for_each(&mut push);
+            }
+        }
+        res
+    }
+
+    /// Platform formats followed by synthesized formats.
+    async fn get_formats_for_item(
+        &self,
+        isolate_id: IsolateId,
+        reader: &PlatformDataReader,
+        snapshot: Option<&ReaderSnapshot>,
+        item: i64,
+    ) -> NativeExtensionsResult<Vec<String>> {
+        let formats = Self::platform_formats_for_item(reader, snapshot, item).await?;
+        Ok(self.extend_formats(isolate_id, formats))
+    }
+
+    /// Converter that synthesizes `format` for the item, if any.
+    async fn converter_for_item(
+        &self,
+        isolate_id: IsolateId,
+        reader: &PlatformDataReader,
+        snapshot: Option<&ReaderSnapshot>,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<Option<Rc<dyn FormatConverter>>> {
+        let Some(converters) = self.format_converters(isolate_id) else {
+            return Ok(None);
+        };
+        let formats = Self::platform_formats_for_item(reader, snapshot, item).await?;
+        Ok(converters.converter_for(&formats, format))
+    }
+
+    /// Reads item data, running registered converter if `format` is
+    /// synthesized.
+    async fn read_item_data(
+        &self,
+        isolate_id: IsolateId,
+        reader: &PlatformDataReader,
+        snapshot: Option<&ReaderSnapshot>,
+        item: i64,
+        format: String,
+        progress: Option<ReadProgressHandle>,
+    ) -> NativeExtensionsResult<Value> {
+        if format == FILE_URI_LIST_FORMAT {
+            let formats = Self::platform_formats_for_item(reader, snapshot, item).await?;
+            let sources = PlatformDataReader::file_uri_list_source_formats();
+            if let Some(source) = file_uri_list::source_format(&formats, &sources) {
+                let data =
+                    Self::read_platform_data(reader, snapshot, item, source.clone(), progress)
+                        .await?;
+                return Ok(file_uri_list::paths_from_value(data).into());
+            }
+        }
+        let converter = self
+            .converter_for_item(isolate_id, reader, snapshot, item, &format)
+            .await?;
+        let format = match &converter {
+            Some(converter) => converter.source_format().to_owned(),
+            None => format,
+        };
+        let data = Self::read_platform_data(reader, snapshot, item, format, progress).await?;
+        match converter {
+            Some(converter) if data != Value::Null => converter.convert(data).await,
+            _ => Ok(data),
+        }
+    }
+
+    /// Reads item data in platform format, from snapshot if available. Read
+    /// fails with `Cancelled` as soon as progress is cancelled.
+    async fn read_platform_data(
+        reader: &PlatformDataReader,
+        snapshot: Option<&ReaderSnapshot>,
+        item: i64,
+        format: String,
+        progress: Option<ReadProgressHandle>,
+    ) -> NativeExtensionsResult<Value> {
+        let snapshot_data = snapshot
+            .and_then(|snapshot| snapshot.item(item))
+            .and_then(|item| item.data(&format));
+        match (snapshot_data, progress) {
+            (Some(data), _) => Ok(data),
+            // Progress token also ends reads of platforms that ignore it.
+            (None, Some(progress)) => {
+                let token = progress.cancellation_token().clone();
+                token
+                    .run(reader.get_data_for_item(item, format, Some(progress)))
+                    .await
+            }
+            (None, None) => reader.get_data_for_item(item, format, None).await,
+        }
+    }
+
+    /// Reports formats added to clipboard contents after the reader was
+    /// created. Formats present at subscription time are not reported.
+    /// Clipboard is checked whenever clipboard monitor reports a change.
+    /// Subscription ends when all requested formats are available, after
+    /// timeout, when unsubscribed or when reader is disposed;
+    /// `formatSubscriptionEnded` is sent in all cases except unsubscribing.
+    /// Contents of other readers don't change, so their subscriptions end
+    /// right away, as they do where clipboard can not be monitored.
+    async fn subscribe_to_formats(
+        &self,
+        isolate_id: IsolateId,
+        request: FormatSubscriptionRequest,
+    ) -> NativeExtensionsResult<()> {
+        let token = self.clipboard_token(request.reader_handle)?;
+        let key = (isolate_id, request.subscription_id);
+        let timeout = request
+            .timeout_ms
+            .map(|t| Duration::from_millis(t.max(0) as u64))
+            .unwrap_or(FORMAT_SUBSCRIPTION_DEFAULT_TIMEOUT);
+        let monitor = Context::get().clipboard_monitor();
+        let known = match token {
+            Some(token) if monitor.retain()? => {
+                let known = self
+                    .formats_of_items(isolate_id, request.reader_handle)
+                    .await;
+                // Reader may have been disposed while awaiting.
+                match known.and_then(|known| {
+                    self.get_reader(request.reader_handle)?;
+                    Ok(known)
+                }) {
+                    Ok(known) => Some((token, known)),
+                    Err(err) => {
+                        monitor.release();
+                        return Err(err);
+                    }
+                }
+            }
+            _ => None,
+        };
+        let Some((token, known)) = known else {
+            self.send_format_subscription_ended(key);
+            return Ok(());
+        };
+        self.format_subscriptions.borrow_mut().insert(
+            key,
+            FormatSubscription {
+                reader: request.reader_handle,
+                target: token.target().clone(),
+                formats: request.formats,
+                known,
+            },
+        );
+        let weak_self = self.weak_self.clone();
+        self.tasks.spawn(isolate_id, async move {
+            RunLoop::current().wait(timeout).await;
+            if let Some(this) = weak_self.upgrade() {
+                this.end_format_subscription(key);
+            }
+        });
+        Ok(())
+    }
+
+    fn unsubscribe_from_formats(
+        &self,
+        isolate_id: IsolateId,
+        subscription_id: i64,
+    ) -> NativeExtensionsResult<()> {
+        let removed = self
+            .format_subscriptions
+            .borrow_mut()
+            .remove(&(isolate_id, subscription_id));
+        if removed.is_some() {
+            Context::get().clipboard_monitor().release();
+        }
+        Ok(())
+    }
+
+    /// Removes subscription and notifies Dart, unless already removed.
+    fn end_format_subscription(&self, key: (IsolateId, i64)) {
+        let removed = self.format_subscriptions.borrow_mut().remove(&key);
+        if removed.is_some() {
+            Context::get().clipboard_monitor().release();
+            self.send_format_subscription_ended(key);
+        }
+    }
+
+    fn send_format_subscription_ended(&self, key: (IsolateId, i64)) {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct FormatSubscriptionEnded {
+            subscription_id: i64,
+        }
+        self.invoker.call_method(
+            key.0,
+            "formatSubscriptionEnded",
+            FormatSubscriptionEnded {
+                subscription_id: key.1,
+            },
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
+
+    /// Formats of all items, keyed by item handle.
+    async fn formats_of_items(
+        &self,
+        isolate_id: IsolateId,
+        reader: DataReaderId,
+    ) -> NativeExtensionsResult<HashMap<i64, Vec<String>>> {
+        let platform_reader = self.get_reader(reader)?;
+        let snapshot = self.get_snapshot(reader);
+        let mut res = HashMap::new();
+        for item in self.get_items(reader).await? {
+            let formats = self
+                .get_formats_for_item(isolate_id, &platform_reader, snapshot.as_deref(), item)
+                .await?;
+            res.insert(item, formats);
+        }
+        Ok(res)
+    }
+
+    /// Formats of all items of a new reader for current contents of
+    /// `target`. Items are keyed by position, as are items of clipboard
+    /// readers.
+    async fn formats_of_clipboard(
+        &self,
+        isolate_id: IsolateId,
+        target: ClipboardTarget,
+    ) -> NativeExtensionsResult<HashMap<i64, Vec<String>>> {
+        let (platform_reader, _) = new_clipboard_reader_with_token(target).await?;
+        let mut res = HashMap::new();
+        for item in platform_reader.get_items().await? {
+            let formats = self
+                .get_formats_for_item(isolate_id, &platform_reader, None, item)
+                .await?;
+            res.insert(item, formats);
+        }
+        Ok(res)
+    }
+
+    /// Checks live clipboard for every format subscription.
+    fn update_format_subscriptions(&self) {
+        let keys: Vec<_> = self.format_subscriptions.borrow().keys().copied().collect();
+        for key in keys {
+            let weak_self = self.weak_self.clone();
+            self.tasks.spawn(key.0, async move {
+                let Some(this) = weak_self.upgrade() else {
+                    return;
+                };
+                this.update_format_subscription(key).await;
+            });
+        }
+    }
+
+    async fn update_format_subscription(&self, key: (IsolateId, i64)) {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct FormatsAvailable {
+            subscription_id: i64,
+            item_handle: i64,
+            formats: Vec<String>,
+        }
+        let Some(target) = self
+            .format_subscriptions
+            .borrow()
+            .get(&key)
+            .map(|s| s.target.clone())
+        else {
+            return;
+        };
+        let Some(current) = self.formats_of_clipboard(key.0, target).await.ok_log() else {
+            self.end_format_subscription(key);
+            return;
+        };
+        let mut available = Vec::new();
+        let all_available = {
+            let mut subscriptions = self.format_subscriptions.borrow_mut();
+            // Ended while reading the clipboard.
+            let Some(subscription) = subscriptions.get_mut(&key) else {
+                return;
+            };
+            let mut items: Vec<_> = current.keys().copied().collect();
+            items.sort();
+            for item in items {
+                let previous = subscription.known.get(&item);
+                let formats: Vec<_> = current[&item]
+                    .iter()
+                    .filter(|f| subscription.is_requested(f))
+                    .filter(|f| previous.map(|p| !p.contains(f)).unwrap_or(true))
+                    .cloned()
+                    .collect();
+                if !formats.is_empty() {
+                    available.push(FormatsAvailable {
+                        subscription_id: key.1,
+                        item_handle: item,
+                        formats,
+                    });
+                }
+            }
+            let all_available = subscription.formats.as_ref().map(|formats| {
+                formats
+                    .iter()
+                    .all(|f| current.values().any(|formats| formats.contains(f)))
+            });
+            subscription.known = current;
+            all_available == Some(true)
+        };
+        for available in available {
+            self.invoker
+                .call_method(key.0, "formatsAvailable", available, |r| {
+                    r.ok_log();
+                });
+        }
+        if all_available {
+            self.end_format_subscription(key);
+        }
+    }
+
+    fn register_format_converter(
+        &self,
+        isolate_id: IsolateId,
+        descriptor: DartFormatConverterDescriptor,
+    ) -> NativeExtensionsResult<()> {
+        let id = descriptor.converter_id;
+        if id <= 0 {
+            return Err(NativeExtensionsError::OtherError(
+                "Format converter id must be positive".into(),
+            ));
+        }
+        let converter = Rc::new(DartFormatConverter {
+            isolate_id,
+            descriptor,
+            manager: self.weak_self.clone(),
+        });
+        self.format_converters
+            .borrow_mut()
+            .entry(isolate_id)
+            .or_default()
+            .register(id, converter);
+        self.effective_converters.borrow_mut().remove(&isolate_id);
+        self.read_settings_changed();
+        Ok(())
+    }
+
+    fn unregister_format_converter(
+        &self,
+        isolate_id: IsolateId,
+        converter_id: i64,
+    ) -> NativeExtensionsResult<()> {
+        let mut converters = self.format_converters.borrow_mut();
+        if let Some(isolate_converters) = converters.get_mut(&isolate_id) {
+            isolate_converters.unregister(converter_id);
+            if isolate_converters.is_empty() {
+                converters.remove(&isolate_id);
+            }
+        }
+        self.effective_converters.borrow_mut().remove(&isolate_id);
+        self.read_settings_changed();
+        Ok(())
+    }
+
+    /// Opt-in for synthesizing HTML from RTF for readers of the isolate.
+    fn set_rtf_html_conversion_enabled(
+        &self,
+        isolate_id: IsolateId,
+        enabled: bool,
+    ) -> NativeExtensionsResult<()> {
+        let mut isolates = self.rtf_html_isolates.borrow_mut();
+        if enabled {
+            isolates.insert(isolate_id);
+        } else {
+            isolates.remove(&isolate_id);
+        }
+        self.effective_converters.borrow_mut().remove(&isolate_id);
+        self.read_settings_changed();
+        Ok(())
+    }
+
+    async fn get_item_info(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemInfoRequest,
+    ) -> NativeExtensionsResult<ItemInfoResponse> {
+        let mut res = Vec::with_capacity(request.item_handles.len());
+        let reader = self.get_reader(request.reader_handle)?;
+        if let Some(snapshot) = self.get_snapshot(request.reader_handle) {
+            for item_handle in request.item_handles {
+                if let Some(item) = snapshot.item(item_handle) {
+                    res.push(self.snapshot_item_info(isolate_id, item));
+                }
+            }
+            return Ok(ItemInfoResponse { items: res });
+        }
+        let start = std::time::Instant::now();
+        for item_handle in request.item_handles {
+            let platform_formats = reader.get_formats_for_item(item_handle).await?;
+            let formats = self.extend_formats(isolate_id, platform_formats.clone());
+            let mut synthesized_formats = Vec::new();
+            let mut read_virtual_file_formats = Vec::new();
+            let mut copy_virtual_file_formats = Vec::new();
+            for format in &formats {
+                if !platform_formats.contains(format) {
+                    synthesized_formats.push(format.clone());
+                    continue;
+                }
+                if reader.item_format_is_synthesized(item_handle, format)? {
+                    synthesized_formats.push(format.clone());
+                }
+                if reader
+                    .can_read_virtual_file_for_item(item_handle, format)
+                    .await?
+                {
+                    read_virtual_file_formats.push(format.clone());
+                }
+                if reader
+                    .can_copy_virtual_file_for_item(item_handle, format)
+                    .await?
+                {
+                    copy_virtual_file_formats.push(format.clone());
+                }
+            }
+            let suggested_name = reader.get_suggested_name_for_item(item_handle).await?;
+            let file_uri_format =
+                if copy_virtual_file_formats.is_empty() && read_virtual_file_formats.is_empty() {
+                    reader.get_item_format_for_uri(item_handle).await?
+                } else {
+                    None
+                };
+            let format_fidelity = formats.iter().map(|f| format_fidelity(f)).collect();
+            res.push(ItemInfo {
+                handle: item_handle,
+                formats,
+                format_fidelity,
+                synthesized_formats,
+                copy_virtual_file_formats,
+                read_virtual_file_formats,
+                suggested_name,
+                file_uri_format,
+            });
+            if let Some(timeout) = request.timeout_millis {
+                if start.elapsed().as_millis() > timeout as u128 {
+                    break;
+                }
+            }
+        }
+        Ok(ItemInfoResponse { items: res })
+    }
+
+    fn snapshot_item_info(&self, isolate_id: IsolateId, item: &SnapshotItem) -> ItemInfo {
+        let formats = self.extend_formats(isolate_id, item.formats.clone());
+        let synthesized_formats = formats
+            .iter()
+            .filter(|f| !item.formats.contains(f) || item.synthesized_formats.contains(f))
+            .cloned()
+            .collect();
+        ItemInfo {
+            handle: item.handle,
+            format_fidelity: formats.iter().map(|f| format_fidelity(f)).collect(),
+            formats,
+            synthesized_formats,
+            read_virtual_file_formats: item.read_virtual_file_formats.clone(),
+            copy_virtual_file_formats: item.copy_virtual_file_formats.clone(),
+            suggested_name: item.suggested_name.clone(),
+            file_uri_format: item.file_uri_format.clone(),
+        }
+    }
+
+    async fn get_item_data(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemDataRequest,
+    ) -> NativeExtensionsResult<Value> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let span = OperationSpan::new(module_path!(), "getItemData");
+        let data = with_timeout(
+            request.timeout_ms,
+            progress.clone(),
+            self.get_item_data_with_progress(isolate_id, &reader, &request, progress),
+        )
+        .await;
+        span.finish(&data);
+        let data = data?;
+        if !binary_channels(isolate_id).item_data {
+            return Ok(share_if_large(isolate_id, data));
+        }
+        // Large payloads end up in attachments of binary record.
+        match encode_record(RecordKind::ItemData, data)? {
+            Value::List(parts) => Ok(Value::List(
+                parts
+                    .into_iter()
+                    .map(|part| share_if_large(isolate_id, part))
+                    .collect(),
+            )),
+            data => Ok(share_if_large(isolate_id, data)),
+        }
+    }
+
+    async fn get_item_data_with_progress(
+        &self,
+        isolate_id: IsolateId,
+        reader: &PlatformDataReader,
+        request: &ItemDataRequest,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Value> {
+        // Capture rules before awaiting so that reads already in flight are
+        // not affected by rules changing.
+        let rules = self.transform_rules.borrow().get(&isolate_id).cloned();
+        let policy = self.file_policy.borrow().clone();
+        let allow_partial = request.allow_partial.unwrap_or(false);
+        let snapshot = self.get_snapshot(request.reader_handle);
+        let converter = self
+            .converter_for_item(
+                isolate_id,
+                reader,
+                snapshot.as_deref(),
+                request.item_handle,
+                &request.format,
+            )
+            .await?;
+        // Converted, synthesized and transformed values can not be salvaged
+        // partially; snapshot data is already in memory.
+        let transformed = rules
+            .as_ref()
+            .map(|rules| rules.applies_to(&request.format))
+            .unwrap_or(false);
+        let (data, was_truncated) = if allow_partial
+            && converter.is_none()
+            && snapshot.is_none()
+            && !transformed
+            && request.format != FILE_URI_LIST_FORMAT
+        {
+            self.read_item_data_partial(isolate_id, reader, request, progress)
+                .await?
+        } else {
+            let data = self
+                .read_item_data_cached(isolate_id, reader, snapshot.as_deref(), request, progress)
+                .await?;
+            (data, false)
+        };
+        if let Some(policy) = policy {
+            let uri_format = reader.get_item_format_for_uri(request.item_handle).await?;
+            if uri_format.as_ref() == Some(&request.format) {
+                if let Some(path) = path_from_value(data.clone()) {
+                    policy.check_path(&path, None)?;
+                }
+            }
+        }
+        let data = match rules {
+            Some(rules) => rules.apply(&request.format, data),
+            None => data,
+        };
+        if allow_partial {
+            Ok(PartialItemData {
+                data,
+                was_truncated,
+            }
+            .into())
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// Reads item data, serving repeated reads from cache. Data of stale
+    /// clipboard readers is not cached, nor is data of clipboard readers
+    /// whose staleness can't be detected cheaply (no change count).
+    async fn read_item_data_cached(
+        &self,
+        isolate_id: IsolateId,
+        reader: &PlatformDataReader,
+        snapshot: Option<&ReaderSnapshot>,
+        request: &ItemDataRequest,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Value> {
+        let uncheckable = self
+            .clipboard_token(request.reader_handle)?
+            .is_some_and(|token| !token.has_change_count());
+        // Snapshot data is already in memory.
+        let key = if snapshot.is_some() || uncheckable {
+            None
+        } else if self.is_stale(request.reader_handle).await? {
+            self.invalidate_read_cache(request.reader_handle);
+            None
+        } else {
+            Some((
+                request.reader_handle,
+                request.item_handle,
+                request.format.clone(),
+                self.read_settings_generation.get(),
+            ))
+        };
+        if let Some(data) = key
+            .as_ref()
+            .and_then(|k| self.read_cache.borrow_mut().get(k))
+        {
+            return Ok(data);
+        }
+        let data = self
+            .read_item_data(
+                isolate_id,
+                reader,
+                snapshot,
+                request.item_handle,
+                request.format.clone(),
+                Some(progress),
+            )
+            .await?;
+        // Reader may have been disposed or refreshed while reading.
+        let current = self.get_reader(request.reader_handle).ok();
+        if let (Some(key), Some(current)) = (key, current) {
+            if std::ptr::eq(current.as_ref(), reader) {
+                self.read_cache.borrow_mut().insert(key, data.clone());
+            }
+        }
+        Ok(data)
+    }
+
+    /// Reads data for multiple (item, format) pairs in one call. Failures are
+    /// reported per entry; timeout, if any, applies to each entry separately.
+    /// When cancelled, entries not read yet are omitted from the result.
+    async fn get_item_data_multi(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemDataMultiRequest,
+    ) -> NativeExtensionsResult<Vec<ItemDataResult>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let composite =
+            CompositeReadProgress::with_equal_weights(progress.clone(), request.items.len());
+        let mut res = Vec::with_capacity(request.items.len());
+        for (index, item) in request.items.into_iter().enumerate() {
+            if progress.cancellation_token().is_cancelled() {
+                break;
+            }
+            let item_progress = composite.part(index);
+            let item_request = ItemDataRequest {
+                item_handle: item.item_handle,
+                reader_handle: request.reader_handle,
+                format: item.format,
+                progress_id: request.progress_id,
+                allow_partial: None,
+                timeout_ms: request.timeout_ms,
+            };
+            // Parts have their own cancellation tokens, so timing out cancels
+            // only this entry.
+            let data = with_timeout(
+                item_request.timeout_ms,
+                item_progress.clone(),
+                self.get_item_data_with_progress(isolate_id, &reader, &item_request, item_progress),
+            )
+            .await;
+            let (data, error) = match data {
+                Ok(data) => (data, None),
+                // Read of the item was cancelled along with the request.
+                Err(_) if progress.cancellation_token().is_cancelled() => break,
+                Err(err) => (Value::Null, Some(err.to_string())),
+            };
+            res.push(ItemDataResult {
+                item_handle: item_request.item_handle,
+                format: item_request.format,
+                data,
+                error,
+            });
+            composite.finish_part(index);
+        }
+        progress.set_cancellable(false);
+        Ok(res)
+    }
+
+    /// Reads item data in chunks so that the read can stop once progress is
+    /// cancelled, returning data received so far and whether it was
+    /// truncated. Platforms only stream formats that full reads return as
+    /// raw bytes. If platform can not stream the item, it is read like a full
+    /// read and nothing can be salvaged on cancellation.
+    async fn read_item_data_partial(
+        &self,
+        isolate_id: IsolateId,
+        reader: &PlatformDataReader,
+        request: &ItemDataRequest,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<(Value, bool)> {
+        let parent = progress.clone();
+        let read_progress = progress.child(move |update| parent.report(update));
+        let cancellation = progress.cancellation_token().clone();
+        progress.set_cancellable(true);
+        let res = match self
+            .open_partial_stream(reader, request, read_progress.clone())
+            .await
+        {
+            Ok(Some(stream)) => Self::read_stream_partial(stream.as_ref(), &cancellation).await,
+            // Same path as full reads, so that the value is the same.
+            Ok(None) => {
+                let data = self
+                    .read_item_data_cached(isolate_id, reader, None, request, read_progress)
+                    .await;
+                match data {
+                    Ok(data) => Ok((data, false)),
+                    Err(_) if cancellation.is_cancelled() => Ok((Value::Null, true)),
+                    Err(err) => Err(err),
+                }
+            }
+            Err(err) => Err(err),
+        };
+        progress.set_cancellable(false);
+        res
+    }
+
+    /// Opens raw item data as a stream, subject to the same file policy check
+    /// as `getItemDataStream`.
+    async fn open_partial_stream(
+        &self,
+        reader: &PlatformDataReader,
+        request: &ItemDataRequest,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        let stream = reader
+            .get_data_stream_for_item(request.item_handle, &request.format, progress)
+            .await?;
+        let Some(stream) = stream else {
+            return Ok(None);
+        };
+        if let Err(err) = self
+            .check_file_policy_for_item(reader, request.item_handle, &request.format)
+            .await
+        {
+            stream.close().ok_log();
+            return Err(err);
+        }
+        Ok(Some(stream))
+    }
+
+    async fn read_stream_partial(
+        stream: &dyn VirtualFileReader,
+        cancellation: &CancellationToken,
+    ) -> NativeExtensionsResult<(Value, bool)> {
+        let mut data = Vec::new();
+        let mut was_truncated = false;
+        loop {
+            if cancellation.is_cancelled() {
+                was_truncated = true;
+                break;
+            }
+            match stream.read_next().await {
+                Ok(chunk) if chunk.is_empty() => break,
+                Ok(chunk) => data.extend_from_slice(&chunk),
+                Err(_) if cancellation.is_cancelled() => {
+                    was_truncated = true;
+                    break;
+                }
+                Err(err) => {
+                    stream.close().ok_log();
+                    return Err(err);
+                }
+            }
+        }
+        stream.close().ok_log();
+        Ok((Value::U8List(data), was_truncated))
+    }
+
+    /// Replaces transformation rules applied to item data read by the isolate.
+    fn set_transform_rules(
+        &self,
+        isolate_id: IsolateId,
+        request: SetTransformRulesRequest,
+    ) -> NativeExtensionsResult<()> {
+        let mut transform_rules = self.transform_rules.borrow_mut();
+        if request.rules.is_empty() {
+            transform_rules.remove(&isolate_id);
+        } else {
+            transform_rules.insert(isolate_id, Rc::new(TransformRules::new(request.rules)));
+        }
+        Ok(())
+    }
+
+    /// Replaces policy for incoming files; `None` allows all files.
+    fn set_file_policy(&self, request: SetFilePolicyRequest) -> NativeExtensionsResult<()> {
+        self.file_policy.replace(request.policy.map(Rc::new));
+        Ok(())
+    }
+
+    /// Checks item about to be written to disk against the file policy. The
+    /// final file name is only known once written, so suggested name of the
+    /// item is checked instead.
+    async fn check_file_policy_for_item(
+        &self,
+        reader: &PlatformDataReader,
+        item: i64,
+        format: &str,
+    ) -> NativeExtensionsResult<()> {
+        let policy = self.file_policy.borrow().clone();
+        let Some(policy) = policy else {
+            return Ok(());
+        };
+        let name = reader.get_suggested_name_for_item(item).await?;
+        policy.check(name.as_deref(), Some(format))
+    }
+
+    /// Checks file about to be delivered to Dart against the file policy.
+    /// Blocked files created while reading (`created`) are removed.
+    fn enforce_file_policy(
+        &self,
+        path: &Path,
+        format: Option<&str>,
+        created: bool,
+    ) -> NativeExtensionsResult<()> {
+        let policy = self.file_policy.borrow().clone();
+        let res = match policy {
+            Some(policy) => policy.check_path(path, format),
+            None => Ok(()),
+        };
+        if res.is_err() && created {
+            fs::remove_file(path).ok_log();
+        }
+        res
+    }
+
+    async fn get_item_rich_text(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemRichTextRequest,
+    ) -> NativeExtensionsResult<Option<Vec<TextSpan>>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        read_rich_text(&reader, request.item_handle, progress).await
+    }
+
+    async fn get_item_text_with_entities(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemTextWithEntitiesRequest,
+    ) -> NativeExtensionsResult<Option<TextWithEntities>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        // Same path as `getItemData` so that transform rules, file policy
+        // and read cache apply to the text entities are detected in.
+        let data_request = ItemDataRequest {
+            item_handle: request.item_handle,
+            reader_handle: request.reader_handle,
+            format: request.format,
+            progress_id: request.progress_id,
+            allow_partial: None,
+            timeout_ms: None,
+        };
+        let data = self
+            .get_item_data_with_progress(isolate_id, &reader, &data_request, progress)
+            .await?;
+        let text = match data {
+            Value::String(text) => text,
+            Value::U8List(data) => String::from_utf8_lossy(&data).into_owned(),
+            _ => return Ok(None),
+        };
+        let kinds = request.kinds.unwrap_or_else(|| EntityKind::ALL.to_vec());
+        let entities = detect_entities(&text, &kinds);
+        Ok(Some(TextWithEntities { text, entities }))
+    }
+
+    async fn get_item_source_url(
+        &self,
+        request: ItemFormatsRequest,
+    ) -> NativeExtensionsResult<Option<SourceUrl>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        read_source_url(&reader, request.item_handle).await
+    }
+
+    async fn get_item_web_archive(
+        &self,
+        request: ItemFormatsRequest,
+    ) -> NativeExtensionsResult<Option<WebArchive>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        read_web_archive(&reader, request.item_handle).await
+    }
+
+    async fn get_item_media_info(
+        &self,
+        request: ItemFormatsRequest,
+    ) -> NativeExtensionsResult<Option<MediaInfo>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        read_media_info(&reader, request.item_handle).await
+    }
+
+    async fn rasterize_item_metafile(
+        &self,
+        request: RasterizeMetafileRequest,
+    ) -> NativeExtensionsResult<Option<Value>> {
+        // Sizes come straight from Dart; reject anything that would not fit
+        // a reasonable bitmap before platform code allocates it.
+        const MAX_DIMENSION: i64 = 16384;
+        let valid = |v: Option<i64>| v.map_or(true, |v| (1..=MAX_DIMENSION).contains(&v));
+        if !valid(request.width) || !valid(request.height) {
+            return Err(NativeExtensionsError::InvalidData);
+        }
+        let reader = self.get_reader(request.reader_handle)?;
+        let png = reader
+            .rasterize_metafile_for_item(request.item_handle, request.width, request.height)
+            .await?;
+        Ok(png.map(Value::U8List))
+    }
+
+    async fn read_item_local_transfer(
+        &self,
+        isolate_id: IsolateId,
+        request: LocalTransferRequest,
+    ) -> NativeExtensionsResult<Option<String>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        read_local_transfer(
+            &reader,
+            request.item_handle,
+            request.target_path.into(),
+            progress,
+        )
+        .await
+    }
+
+    async fn import_item(
+        &self,
+        isolate_id: IsolateId,
+        request: ImportItemRequest,
+    ) -> NativeExtensionsResult<ImportResult> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let target_folders: Vec<_> = request
+            .targets
+            .iter()
+            .map(|target| match target {
+                ImportTarget::File { target_folder, .. } => Some(PathBuf::from(target_folder)),
+                ImportTarget::Data { .. } => None,
+            })
+            .collect();
+        let policy = self.file_policy.borrow().clone();
+        let res = ImportPipeline::new(&*reader, request.item_handle, progress)
+            .with_file_policy(policy)
+            .run(request.targets)
+            .await?;
+        if let Some(path) = &res.path {
+            let path = Path::new(path);
+            // Files outside of target folder are existing local files.
+            let created = res
+                .target_index
+                .and_then(|index| target_folders.get(index as usize).cloned().flatten())
+                .map(|folder| path.starts_with(folder))
+                .unwrap_or(false);
+            self.enforce_file_policy(path, res.format.as_deref(), created)?;
+            if created {
+                self.managed_directory.file_added(path);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Resolves items to local files. Progress covers all items; cancelling
+    /// it stops before next item and returns files resolved so far.
+    async fn resolve_items_to_files(
+        &self,
+        isolate_id: IsolateId,
+        request: ResolveItemsToFilesRequest,
+    ) -> NativeExtensionsResult<Vec<ResolvedFile>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let items = match request.item_handles {
+            Some(items) => items,
+            None => reader.get_items().await?,
+        };
+        let composite = CompositeReadProgress::with_equal_weights(progress.clone(), items.len());
+        let target_folder = PathBuf::from(request.target_folder);
+        let policy = self.file_policy.borrow().clone();
+        let mut res = Vec::new();
+        for (index, item) in items.into_iter().enumerate() {
+            if progress.cancellation_token().is_cancelled() {
+                break;
+            }
+            let item_progress = composite.part(index);
+            let resolved = ImportPipeline::new(&*reader, item, item_progress)
+                .with_file_policy(policy.clone())
+                .resolve_to_file(&request.file_uri_formats, target_folder.clone())
+                .await;
+            let resolved = match resolved {
+                Ok(resolved) => resolved,
+                Err(_) if progress.cancellation_token().is_cancelled() => break,
+                Err(err) => return Err(err),
+            };
+            if let (Some(path), Some(source)) = (&resolved.path, resolved.source) {
+                self.enforce_file_policy(
+                    Path::new(path),
+                    resolved.format.as_deref(),
+                    source != FileSource::LocalFile,
+                )?;
+                if source != FileSource::LocalFile {
+                    self.managed_directory.file_added(Path::new(path));
+                }
+            }
+            res.push(resolved);
+            composite.finish_part(index);
+        }
+        progress.set_cancellable(false);
+        Ok(res)
+    }
+
+    /// Lists entries if the item is a zip archive. Archives that are not
+    /// local files are stored in managed directory first; the returned path
+    /// identifies the archive in `readArchiveEntry` and `extractArchiveEntry`.
+    async fn get_archive_entries(
+        &self,
+        isolate_id: IsolateId,
+        request: ArchiveEntriesRequest,
+    ) -> NativeExtensionsResult<Option<ArchiveListing>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let item = request.item_handle;
+        let formats = reader.get_formats_for_item(item).await?;
+        let uri_format = reader.get_item_format_for_uri(item).await?;
+        let suggested_name = reader.get_suggested_name_for_item(item).await?;
+        let is_archive = formats.iter().any(|f| is_archive_format(f))
+            || uri_format
+                .as_deref()
+                .map(is_archive_format)
+                .unwrap_or(false)
+            || suggested_name
+                .map(|n| n.to_ascii_lowercase().ends_with(".zip"))
+                .unwrap_or(false);
+        if !is_archive {
+            return Ok(None);
+        }
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let resolved = ImportPipeline::new(&*reader, item, progress)
+            .resolve_to_file(&request.file_uri_formats, self.managed_directory.path()?)
+            .await?;
+        let (Some(path), Some(source)) = (resolved.path, resolved.source) else {
+            return Ok(None);
+        };
+        let created = source != FileSource::LocalFile;
+        match with_archive(path.clone().into(), |archive| Ok(archive.entries())).await {
+            Ok(entries) => {
+                if created {
+                    self.managed_directory.file_added(Path::new(&path));
+                }
+                Ok(Some(ArchiveListing {
+                    archive_path: path,
+                    entries,
+                }))
+            }
+            Err(err) => {
+                log::warn!("Item is not a readable zip archive: {err}");
+                if created {
+                    fs::remove_file(&path).ok_log();
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Opens archive entry as stream read through `virtualFileReaderRead`.
+    async fn read_archive_entry(
+        &self,
+        isolate_id: IsolateId,
+        request: ArchiveEntryRequest,
+    ) -> NativeExtensionsResult<VirtualFileReaderResponse> {
+        let path = PathBuf::from(&request.archive_path);
+        let index = request.index;
+        let file_name =
+            with_archive(path.clone(), move |archive| archive.entry_file_name(index)).await?;
+        let policy = self.file_policy.borrow().clone();
+        if let Some(policy) = policy {
+            policy.check(Some(&file_name), None)?;
+        }
+        let data = with_archive(path, move |archive| archive.read_entry(index)).await?;
+        let stream = MemoryStreamReader::from_value(Value::U8List(data))
+            .ok_or(NativeExtensionsError::InvalidData)?;
+        let reader_handle = self.next_id.next_id();
+        let file_size = stream.file_size()?;
+        self.virtual_file_readers
+            .borrow_mut()
+            .insert((isolate_id, reader_handle), Rc::new(stream));
+        Ok(VirtualFileReaderResponse {
+            reader_handle,
+            file_name: Some(file_name),
+            file_size,
+        })
+    }
+
+    async fn extract_archive_entry(
+        &self,
+        request: ExtractArchiveEntryRequest,
+    ) -> NativeExtensionsResult<String> {
+        let path = PathBuf::from(&request.archive_path);
+        let index = request.index;
+        let file_name =
+            with_archive(path.clone(), move |archive| archive.entry_file_name(index)).await?;
+        let policy = self.file_policy.borrow().clone();
+        if let Some(policy) = policy {
+            policy.check(Some(&file_name), None)?;
+        }
+        let target_folder = PathBuf::from(&request.target_folder);
+        let path = with_archive(path, move |archive| {
+            archive.extract_entry(index, &target_folder)
+        })
+        .await?;
+        self.managed_directory.file_added(&path);
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// Requests made with child progress ids report into `progressId`,
+    /// weighted by `weights` (such as file sizes). Cancelling it cancels all
+    /// children.
+    fn create_composite_progress(
+        &self,
+        isolate_id: IsolateId,
+        request: CompositeProgressRequest,
+    ) -> NativeExtensionsResult<()> {
+        let count = request.child_progress_ids.len();
+        let weights = request.weights.unwrap_or_else(|| vec![1.0; count]);
+        if weights.len() != count {
+            return Err(NativeExtensionsError::OtherError(
+                "weights must match child progress ids".into(),
+            ));
+        }
+        if weights.iter().any(|w| !w.is_finite()) {
+            return Err(NativeExtensionsError::OtherError(
+                "weights must be finite".into(),
+            ));
+        }
+        let parent = self.new_read_progress(isolate_id, request.progress_id);
+        let composite = CompositeReadProgress::new(parent, weights);
+        let mut children = self.composite_children.borrow_mut();
+        for (index, child_id) in request.child_progress_ids.into_iter().enumerate() {
+            children.insert((isolate_id, child_id), (composite.clone(), index));
+        }
+        Ok(())
+    }
+
+    async fn handle_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            // Lets isolates tell each other the target of `cloneReaderForIsolate`.
+            "getIsolateId" => Ok(call.isolate.0.into()),
+            "cloneReaderForIsolate" => self
+                .clone_reader_for_isolate(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "disposeReader" => self
+                .dispose_reader(call.args.try_into()?)
+                .into_platform_result(),
+            "setOutOfProcessReading" => self
+                .set_out_of_process_reading(call.args.try_into()?)
+                .into_platform_result(),
+            "registerClipboardFormat" => self
+                .register_clipboard_format(call.args.try_into()?)
+                .into_platform_result(),
+            "setExcludeRemoteContent" => self
+                .set_exclude_remote_content(call.args.try_into()?)
+                .into_platform_result(),
+            "isRemoteContent" => self
+                .is_remote_content(call.args.try_into()?)
+                .into_platform_result(),
+            "newExternalReader" => self
+                .new_external_reader(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setFormatConversionEnabled" => self
+                .set_format_conversion_enabled(call.args.try_into()?)
+                .into_platform_result(),
+            "getItemFormatConversions" => self
+                .get_item_format_conversions(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "snapshotReader" => self
+                .snapshot_reader(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItems" => self
+                .get_items(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "registerFormatConverter" => self
+                .register_format_converter(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "unregisterFormatConverter" => self
+                .unregister_format_converter(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setRtfHtmlConversionEnabled" => self
+                .set_rtf_html_conversion_enabled(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "getItemFormats" => self
+                .get_item_formats(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItemMetadata" => self
+                .get_item_metadata(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItemData" => self
+                .get_item_data(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "setBinaryProtocol" => {
+                configure_binary_channels(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            "setSharedBufferTransfer" => {
+                configure_shared_buffers(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            "getItemDataMulti" => self
+                .get_item_data_multi(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "subscribeToFormats" => self
+                .subscribe_to_formats(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "unsubscribeFromFormats" => self
+                .unsubscribe_from_formats(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "isStale" => self
+                .is_stale(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "refresh" => self
+                .refresh(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItemDataStream" => self
+                .get_item_data_stream(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "setTransformRules" => self
+                .set_transform_rules(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setFilePolicy" => self
+                .set_file_policy(call.args.try_into()?)
+                .into_platform_result(),
+            "getItemRichText" => self
+                .get_item_rich_text(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItemTextWithEntities" => self
+                .get_item_text_with_entities(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItemSourceUrl" => self
+                .get_item_source_url(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItemWebArchive" => self
+                .get_item_web_archive(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getItemMediaInfo" => self
+                .get_item_media_info(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "rasterizeItemMetafile" => self
+                .rasterize_item_metafile(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "readItemLocalTransfer" => self
+                .read_item_local_transfer(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "importItem" => self
+                .import_item(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "resolveItemsToFiles" => self
+                .resolve_items_to_files(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getArchiveEntries" => self
+                .get_archive_entries(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "readArchiveEntry" => self
+                .read_archive_entry(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "extractArchiveEntry" => self
+                .extract_archive_entry(call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "createCompositeProgress" => self
+                .create_composite_progress(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "cancelProgress" => self
+                .cancel_progress(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "getItemInfo" => self
+                .get_item_info(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "virtualFileReaderCreate" => self
+                .virtual_file_reader_create(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "virtualFileReaderRead" => self
+                .virtual_file_reader_read(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "virtualFileReaderClose" => self
+                .virtual_file_reader_close(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "copyVirtualFile" => self
+                .copy_virtual_file(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getVirtualFileData" => self
+                .get_virtual_file_data(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getManagedDirectory" => self.get_managed_directory().into_platform_result(),
+            "setManagedDirectoryQuota" => self
+                .set_managed_directory_quota(call.args.try_into()?)
+                .into_platform_result(),
+            "claimManagedFile" => self
+                .claim_managed_file(call.args.try_into()?)
+                .into_platform_result(),
+            "getVirtualFileManaged" => self
+                .get_virtual_file_managed(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "disposeFileLease" => self
+                .dispose_file_lease(call.args.try_into()?)
+                .into_platform_result(),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+
+    /// Child of composite progress whose request failed before it started,
+    /// or that was cancelled, counts as finished; otherwise the parent would
+    /// never complete.
+    fn release_composite_child(&self, isolate_id: IsolateId, progress_id: i64) {
+        let composite = self
+            .composite_children
+            .borrow_mut()
+            .remove(&(isolate_id, progress_id));
+        if let Some((composite, index)) = composite {
+            composite.finish_part(index);
+        }
+    }
+
+    fn cancel_progress(
+        &self,
+        isolate_id: IsolateId,
+        progress_id: i64,
+    ) -> NativeExtensionsResult<()> {
+        let progress = self
+            .progresses
+            .borrow_mut()
+            .remove(&(isolate_id, progress_id));
+        if let Some(progress) = progress.and_then(|p| p.upgrade()) {
+            progress.cancel();
+        }
+        // Child of composite progress that did not start is skipped.
+        self.release_composite_child(isolate_id, progress_id);
+        // Children that did not start yet hold the composite.
+        self.composite_children
+            .borrow_mut()
+            .retain(|_, (composite, _)| !composite.parent().cancellation_token().is_cancelled());
+        Ok(())
+    }
+
+    async fn virtual_file_reader_create(
+        &self,
+        isolate_id: IsolateId,
+        request: VirtualFileReaderRequest,
+    ) -> NativeExtensionsResult<VirtualFileReaderResponse> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let res = reader
+            .create_virtual_file_reader_for_item(request.item_handle, &request.format, progress)
+            .await?;
+        match res {
+            Some(reader) => {
+                let policy = self.file_policy.borrow().clone();
+                if let Some(policy) = policy {
+                    let file_name = reader.file_name();
+                    if let Err(err) = policy.check(file_name.as_deref(), Some(&request.format)) {
+                        reader.close().ok_log();
+                        return Err(err);
+                    }
+                }
                 let reader_handle = self.next_id.next_id();
                 let file_size = reader.file_size()?;
                 let file_name = reader.file_name();
@@ -377,6 +2408,81 @@ impl DataReaderManager {
         }
     }
 
+    /// Opens item data as a stream. Chunks are pulled by Dart through
+    /// `virtualFileReaderRead` and the stream must be closed with
+    /// `virtualFileReaderClose`. Returns `None` if data is not available.
+    async fn get_item_data_stream(
+        &self,
+        isolate_id: IsolateId,
+        request: ItemDataRequest,
+    ) -> NativeExtensionsResult<Option<VirtualFileReaderResponse>> {
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        with_timeout(
+            request.timeout_ms,
+            progress.clone(),
+            self.open_item_data_stream(isolate_id, &request, progress),
+        )
+        .await
+    }
+
+    async fn open_item_data_stream(
+        &self,
+        isolate_id: IsolateId,
+        request: &ItemDataRequest,
+        progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<VirtualFileReaderResponse>> {
+        let reader = self.get_reader(request.reader_handle)?;
+        let snapshot = self.get_snapshot(request.reader_handle);
+        let rules = self.transform_rules.borrow().get(&isolate_id).cloned();
+        self.check_file_policy_for_item(&reader, request.item_handle, &request.format)
+            .await?;
+        // Transformed values can not be streamed.
+        let transformed = rules
+            .as_ref()
+            .map(|rules| rules.applies_to(&request.format))
+            .unwrap_or(false);
+        let stream = if snapshot.is_some() || transformed {
+            None
+        } else {
+            reader
+                .get_data_stream_for_item(request.item_handle, &request.format, progress.clone())
+                .await?
+        };
+        let stream = match stream {
+            Some(stream) => stream,
+            None => {
+                let data = self
+                    .read_item_data(
+                        isolate_id,
+                        &reader,
+                        snapshot.as_deref(),
+                        request.item_handle,
+                        request.format.clone(),
+                        Some(progress),
+                    )
+                    .await?;
+                let data = match rules {
+                    Some(rules) => rules.apply(&request.format, data),
+                    None => data,
+                };
+                match MemoryStreamReader::from_value(data) {
+                    Some(stream) => Rc::new(stream),
+                    None => return Ok(None),
+                }
+            }
+        };
+        let reader_handle = self.next_id.next_id();
+        let file_size = stream.file_size()?;
+        self.virtual_file_readers
+            .borrow_mut()
+            .insert((isolate_id, reader_handle), stream);
+        Ok(Some(VirtualFileReaderResponse {
+            reader_handle,
+            file_size,
+            file_name: None,
+        }))
+    }
+
     async fn virtual_file_reader_read(
         &self,
         isolate_id: IsolateId,
@@ -393,61 +2499,556 @@ impl DataReaderManager {
         }
     }
 
-    fn virtual_file_reader_close(
-        &self,
-        isolate_id: IsolateId,
-        virtual_reader_id: i64,
-    ) -> NativeExtensionsResult<()> {
-        let reader = self
-            .virtual_file_readers
-            .borrow_mut()
-            .remove(&(isolate_id, virtual_reader_id));
-        if let Some(reader) = reader {
-            reader.close()?;
+    fn virtual_file_reader_close(
+        &self,
+        isolate_id: IsolateId,
+        virtual_reader_id: i64,
+    ) -> NativeExtensionsResult<()> {
+        let reader = self
+            .virtual_file_readers
+            .borrow_mut()
+            .remove(&(isolate_id, virtual_reader_id));
+        if let Some(reader) = reader {
+            reader.close()?;
+        }
+        Ok(())
+    }
+
+    async fn copy_virtual_file(
+        &self,
+        isolate_id: IsolateId,
+        request: VirtualFileCopyRequest,
+    ) -> NativeExtensionsResult<String> {
+        let reader = self.get_reader(request.reader_handle)?;
+        self.check_file_policy_for_item(&reader, request.item_handle, &request.format)
+            .await?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let res = with_timeout(
+            request.timeout_ms,
+            progress.clone(),
+            reader.copy_virtual_file_for_item(
+                request.item_handle,
+                &request.format,
+                request.target_folder.into(),
+                progress,
+            ),
+        )
+        .await?;
+        self.enforce_file_policy(&res, Some(&request.format), true)?;
+        self.managed_directory.file_added(&res);
+        Ok(res.to_string_lossy().into_owned())
+    }
+
+    /// Reads virtual file contents in chunks without a target folder. Formats
+    /// that can only be copied are staged in a private folder that is
+    /// removed when the reader is closed.
+    async fn get_virtual_file_data(
+        &self,
+        isolate_id: IsolateId,
+        request: VirtualFileReaderRequest,
+    ) -> NativeExtensionsResult<VirtualFileReaderResponse> {
+        let reader = self.get_reader(request.reader_handle)?;
+        if reader
+            .can_read_virtual_file_for_item(request.item_handle, &request.format)
+            .await?
+        {
+            return self.virtual_file_reader_create(isolate_id, request).await;
+        }
+        self.check_file_policy_for_item(&reader, request.item_handle, &request.format)
+            .await?;
+        let reader_handle = self.next_id.next_id();
+        let staging_folder = self
+            .managed_directory
+            .path()?
+            .join(format!("stream-{reader_handle}"));
+        fs::create_dir_all(&staging_folder)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let path = with_timeout(
+            request.timeout_ms,
+            progress.clone(),
+            reader.copy_virtual_file_for_item(
+                request.item_handle,
+                &request.format,
+                staging_folder.clone(),
+                progress,
+            ),
+        )
+        .await;
+        let stream = path.and_then(|path| {
+            self.enforce_file_policy(&path, Some(&request.format), true)?;
+            StagedFileReader::new(path, staging_folder.clone())
+        });
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                fs::remove_dir_all(&staging_folder).ok_log();
+                return Err(err);
+            }
+        };
+        let file_size = stream.file_size()?;
+        let file_name = stream.file_name();
+        self.virtual_file_readers
+            .borrow_mut()
+            .insert((isolate_id, reader_handle), Rc::new(stream));
+        Ok(VirtualFileReaderResponse {
+            reader_handle,
+            file_name,
+            file_size,
+        })
+    }
+
+    fn reader_lease_folder(reader: DataReaderId) -> PathBuf {
+        PathBuf::from(format!("reader-{}", reader.0))
+    }
+
+    fn lease_folder(reader: DataReaderId, lease: i64) -> PathBuf {
+        Self::reader_lease_folder(reader).join(format!("lease-{lease}"))
+    }
+
+    /// Like `copyVirtualFile` but the file is written to a temporary folder
+    /// of the reader. The file is removed when the returned lease is disposed
+    /// or finalized, or when the reader is disposed.
+    async fn get_virtual_file_managed(
+        &self,
+        isolate_id: IsolateId,
+        request: VirtualFileManagedRequest,
+    ) -> NativeExtensionsResult<ManagedVirtualFile> {
+        let reader = self.get_reader(request.reader_handle)?;
+        self.check_file_policy_for_item(&reader, request.item_handle, &request.format)
+            .await?;
+        let lease = self.next_id.next_id();
+        let relative_folder = Self::lease_folder(request.reader_handle, lease);
+        let folder = self
+            .managed_directory
+            .create_leased_folder(&relative_folder)?;
+        let progress = self.new_read_progress(isolate_id, request.progress_id);
+        let res = with_timeout(
+            request.timeout_ms,
+            progress.clone(),
+            reader.copy_virtual_file_for_item(
+                request.item_handle,
+                &request.format,
+                folder,
+                progress,
+            ),
+        )
+        .await
+        .and_then(|path| {
+            self.enforce_file_policy(&path, Some(&request.format), true)?;
+            // Reader might have been disposed while copying.
+            if !self.readers.borrow().contains_key(&request.reader_handle) {
+                return Err(NativeExtensionsError::ReaderNotFound);
+            }
+            Ok(path)
+        });
+        match res {
+            Ok(path) => {
+                let weak_self = self.weak_self.clone();
+                let finalizable_handle =
+                    Arc::new(FinalizableHandle::new(32, isolate_id, move || {
+                        if let Some(manager) = weak_self.upgrade() {
+                            manager.dispose_file_lease(lease).ok_log();
+                        }
+                    }));
+                self.file_leases.borrow_mut().insert(
+                    lease,
+                    FileLease {
+                        reader: request.reader_handle,
+                        _finalizable_handle: finalizable_handle.clone(),
+                    },
+                );
+                Ok(ManagedVirtualFile {
+                    path: path.to_string_lossy().into_owned(),
+                    lease,
+                    finalizable_handle: finalizable_handle.into(),
+                })
+            }
+            Err(err) => {
+                self.managed_directory
+                    .remove_leased_folder(&relative_folder);
+                Err(err)
+            }
+        }
+    }
+
+    fn dispose_file_lease(&self, lease: i64) -> NativeExtensionsResult<()> {
+        let entry = self.file_leases.borrow_mut().remove(&lease);
+        if let Some(entry) = entry {
+            self.managed_directory
+                .remove_leased_folder(&Self::lease_folder(entry.reader, lease));
+        }
+        Ok(())
+    }
+
+    fn get_managed_directory(&self) -> NativeExtensionsResult<String> {
+        Ok(self
+            .managed_directory
+            .path()?
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    fn set_managed_directory_quota(&self, quota: Option<i64>) -> NativeExtensionsResult<()> {
+        self.managed_directory
+            .set_quota(quota.map(|q| q.max(0) as u64));
+        Ok(())
+    }
+
+    fn claim_managed_file(&self, request: ClaimFileRequest) -> NativeExtensionsResult<String> {
+        let res = self
+            .managed_directory
+            .claim(Path::new(&request.path), Path::new(&request.target_folder))?;
+        Ok(res.to_string_lossy().into_owned())
+    }
+}
+
+#[derive(IntoValue, TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct RegisteredDataReader {
+    handle: DataReaderId,
+    finalizable_handle: Value,
+}
+
+/// Platform object obtained outside of this plugin (i.e. from another Flutter
+/// plugin) to be wrapped in a reader.
+#[derive(TryFromValue, Debug)]
+#[irondash(tag = "type", rename_all = "camelCase")]
+pub enum ExternalReaderSource {
+    /// Platform object registered by native code of another plugin, through
+    /// `super_native_extensions_register_data_object` on Windows or
+    /// `SuperNativeExtensionsPlugin.registerClipData` on Android. Redeeming
+    /// the handle hands the registered reference over to the reader.
+    #[irondash(rename_all = "camelCase")]
+    Handle { handle: i64 },
+    /// Named pasteboard on macOS and iOS.
+    #[irondash(rename_all = "camelCase")]
+    PasteboardName { name: String },
+}
+
+/// Built-in conversions readers perform to provide formats missing in the
+/// source data.
+#[derive(TryFromValue, IntoValue, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[irondash(rename_all = "camelCase")]
+pub enum FormatConversion {
+    /// PNG from TIFF (macOS).
+    TiffToPng,
+    /// PNG from DIB or DIBV5 bitmap (Windows).
+    BitmapToPng,
+    /// `text/html` fragment from CF_HTML (Windows).
+    CfHtmlToHtml,
+}
+
+/// Item properties available without reading item data.
+#[derive(IntoValue, Debug, Default, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct ItemMetadata {
+    /// Estimated size of item content in bytes.
+    pub size: Option<i64>,
+    /// Milliseconds since Unix epoch.
+    pub last_modified: Option<i64>,
+    /// Identifier of application that provided the item (bundle identifier,
+    /// executable path).
+    pub source_application: Option<String>,
+}
+
+impl ItemMetadata {
+    /// Metadata of local file; empty if file can not be accessed.
+    pub fn for_local_file(path: &Path) -> Self {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return Self::default();
+        };
+        Self {
+            size: metadata.is_file().then_some(metadata.len() as i64),
+            last_modified: metadata
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64),
+            source_application: None,
+        }
+    }
+}
+
+/// Conversion that applies to an item given its source formats.
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct ItemFormatConversion {
+    pub conversion: FormatConversion,
+    pub source_format: String,
+    pub target_format: String,
+    /// Whether conversion is enabled for the reader. Disabled conversions
+    /// are not reported in item formats.
+    pub enabled: bool,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetFormatConversionEnabledRequest {
+    reader_handle: DataReaderId,
+    conversion: FormatConversion,
+    enabled: bool,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ItemFormatsRequest {
+    item_handle: i64,
+    reader_handle: DataReaderId,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct RasterizeMetafileRequest {
+    item_handle: i64,
+    reader_handle: DataReaderId,
+    /// Target size in pixels. When only one dimension is specified the other
+    /// is derived from picture aspect ratio; natural size is used otherwise.
+    width: Option<i64>,
+    height: Option<i64>,
+}
+
+/// Fails with [`NativeExtensionsError::Timeout`] if `read` does not finish in
+/// time. Progress is cancelled so that platform read stops where it can;
+/// otherwise the read is abandoned.
+async fn with_timeout<T>(
+    timeout_ms: Option<i64>,
+    progress: ReadProgressHandle,
+    read: impl Future<Output = NativeExtensionsResult<T>>,
+) -> NativeExtensionsResult<T> {
+    let Some(timeout_ms) = timeout_ms else {
+        return read.await;
+    };
+    let duration = Duration::from_millis(timeout_ms.max(0) as u64);
+    let mut read = pin!(read);
+    let mut timer = pin!(RunLoop::current().wait(duration));
+    poll_fn(|cx| {
+        if let Poll::Ready(res) = read.as_mut().poll(cx) {
+            return Poll::Ready(res);
+        }
+        if timer.as_mut().poll(cx).is_ready() {
+            progress.cancel();
+            return Poll::Ready(Err(NativeExtensionsError::Timeout));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ItemDataRequest {
+    item_handle: i64,
+    reader_handle: DataReaderId,
+    format: String,
+    progress_id: i64,
+    /// When set, cancelled read returns data received so far as
+    /// [`PartialItemData`] instead of failing.
+    allow_partial: Option<bool>,
+    timeout_ms: Option<i64>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct RegisterClipboardFormatRequest {
+    identifier: String,
+    platform_name: String,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct CloneReaderRequest {
+    reader_handle: DataReaderId,
+    target_isolate_id: i64,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ReaderTransferred {
+    source_isolate_id: i64,
+    reader: RegisteredDataReader,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SnapshotReaderRequest {
+    reader_handle: DataReaderId,
+    progress_id: i64,
+    /// Maximum total size of data copied, in bytes.
+    size_limit: Option<i64>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ItemFormat {
+    item_handle: i64,
+    format: String,
+}
+
+struct FormatSubscription {
+    reader: DataReaderId,
+    target: ClipboardTarget,
+    formats: Option<Vec<String>>,
+    /// Formats of clipboard items at last check.
+    known: HashMap<i64, Vec<String>>,
+}
+
+impl FormatSubscription {
+    /// All formats are reported when subscribed without formats.
+    fn is_requested(&self, format: &String) -> bool {
+        match &self.formats {
+            Some(formats) => formats.contains(format),
+            None => true,
         }
-        Ok(())
     }
+}
 
-    async fn copy_virtual_file(
-        &self,
-        isolate_id: IsolateId,
-        request: VirtualFileCopyRequest,
-    ) -> NativeExtensionsResult<String> {
-        let reader = self.get_reader(request.reader_handle)?;
-        let progress = self.new_read_progress(isolate_id, request.progress_id);
-        let res = reader
-            .copy_virtual_file_for_item(
-                request.item_handle,
-                &request.format,
-                request.target_folder.into(),
-                progress,
-            )
-            .await?;
-        Ok(res.to_string_lossy().into_owned())
-    }
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct FormatSubscriptionRequest {
+    reader_handle: DataReaderId,
+    subscription_id: i64,
+    /// Formats to report; all formats when `None`.
+    formats: Option<Vec<String>>,
+    timeout_ms: Option<i64>,
 }
 
-#[derive(IntoValue, TryFromValue, Debug, Clone)]
+#[derive(TryFromValue)]
 #[irondash(rename_all = "camelCase")]
-pub struct RegisteredDataReader {
-    handle: DataReaderId,
-    finalizable_handle: Value,
+struct CompositeProgressRequest {
+    progress_id: i64,
+    child_progress_ids: Vec<i64>,
+    /// Relative weights of children; equal when missing.
+    weights: Option<Vec<f64>>,
 }
 
 #[derive(TryFromValue)]
 #[irondash(rename_all = "camelCase")]
-struct ItemFormatsRequest {
+struct ItemDataMultiRequest {
+    reader_handle: DataReaderId,
+    items: Vec<ItemFormat>,
+    progress_id: i64,
+    timeout_ms: Option<i64>,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ItemDataResult {
+    item_handle: i64,
+    format: String,
+    data: Value,
+    /// Set if reading this entry failed; `data` is null in that case.
+    error: Option<String>,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct PartialItemData {
+    data: Value,
+    was_truncated: bool,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetTransformRulesRequest {
+    /// Applied in order; empty list removes all rules.
+    rules: Vec<TransformRule>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetFilePolicyRequest {
+    policy: Option<FilePolicy>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ItemRichTextRequest {
     item_handle: i64,
     reader_handle: DataReaderId,
+    progress_id: i64,
 }
 
 #[derive(TryFromValue)]
 #[irondash(rename_all = "camelCase")]
-struct ItemDataRequest {
+struct ItemTextWithEntitiesRequest {
     item_handle: i64,
     reader_handle: DataReaderId,
     format: String,
     progress_id: i64,
+    /// Defaults to all kinds.
+    kinds: Option<Vec<EntityKind>>,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct TextWithEntities {
+    text: String,
+    entities: Vec<DetectedEntity>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct LocalTransferRequest {
+    item_handle: i64,
+    reader_handle: DataReaderId,
+    /// File the payload is streamed into.
+    target_path: String,
+    progress_id: i64,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ImportItemRequest {
+    item_handle: i64,
+    reader_handle: DataReaderId,
+    /// Acceptable results in order of preference.
+    targets: Vec<ImportTarget>,
+    progress_id: i64,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ArchiveEntriesRequest {
+    reader_handle: DataReaderId,
+    item_handle: i64,
+    /// Formats containing file URI on current platform.
+    file_uri_formats: Vec<String>,
+    progress_id: i64,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ArchiveListing {
+    archive_path: String,
+    entries: Vec<ArchiveEntry>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ArchiveEntryRequest {
+    archive_path: String,
+    index: i64,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ExtractArchiveEntryRequest {
+    archive_path: String,
+    index: i64,
+    target_folder: String,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ResolveItemsToFilesRequest {
+    reader_handle: DataReaderId,
+    /// Items to resolve; all reader items when not specified.
+    item_handles: Option<Vec<i64>>,
+    /// Formats containing file URI on current platform.
+    file_uri_formats: Vec<String>,
+    /// Folder virtual files and spilled data are written to.
+    target_folder: String,
+    progress_id: i64,
 }
 
 #[derive(TryFromValue)]
@@ -457,6 +3058,8 @@ struct VirtualFileReaderRequest {
     reader_handle: DataReaderId,
     format: String,
     progress_id: i64,
+    /// Only applies to staging files that can not be read directly.
+    timeout_ms: Option<i64>,
 }
 
 #[derive(IntoValue)]
@@ -475,6 +3078,34 @@ struct VirtualFileCopyRequest {
     format: String,
     progress_id: i64,
     target_folder: String,
+    timeout_ms: Option<i64>,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct VirtualFileManagedRequest {
+    item_handle: i64,
+    reader_handle: DataReaderId,
+    format: String,
+    progress_id: i64,
+    timeout_ms: Option<i64>,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ManagedVirtualFile {
+    path: String,
+    /// Token for `disposeFileLease`.
+    lease: i64,
+    /// Disposes the lease when finalized.
+    finalizable_handle: Value,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct ClaimFileRequest {
+    path: String,
+    target_folder: String,
 }
 
 #[derive(TryFromValue)]
@@ -489,8 +3120,12 @@ struct ItemInfoRequest {
 #[irondash(rename_all = "camelCase")]
 struct ItemInfo {
     handle: i64,
-    /// All formats for this item.
+    /// All formats for this item, in platform order (pasteboard type order on
+    /// macOS, format enumeration order on Windows).
     formats: Vec<String>,
+    /// Fidelity rank for each format in `formats`. Higher rank means richer
+    /// representation of the same content.
+    format_fidelity: Vec<i64>,
     /// Formats that are synthesized from other formats.
     synthesized_formats: Vec<String>,
     /// Formats that need to be read through virtual file reader.
@@ -518,6 +3153,190 @@ pub trait VirtualFileReader {
     fn close(&self) -> NativeExtensionsResult<()>;
 }
 
+/// Converter implemented in Dart; data is sent to the isolate that
+/// registered it.
+struct DartFormatConverter {
+    isolate_id: IsolateId,
+    descriptor: DartFormatConverterDescriptor,
+    manager: Weak<DataReaderManager>,
+}
+
+#[async_trait(?Send)]
+impl FormatConverter for DartFormatConverter {
+    fn source_format(&self) -> &str {
+        &self.descriptor.source_format
+    }
+
+    fn target_format(&self) -> &str {
+        &self.descriptor.target_format
+    }
+
+    async fn convert(&self, data: Value) -> NativeExtensionsResult<Value> {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct ConvertFormatRequest {
+            converter_id: i64,
+            data: Value,
+        }
+        let manager = self
+            .manager
+            .upgrade()
+            .ok_or(NativeExtensionsError::UnknownError)?;
+        let res: Value = manager
+            .invoker
+            .call_method_cv(
+                self.isolate_id,
+                "convertFormat",
+                ConvertFormatRequest {
+                    converter_id: self.descriptor.converter_id,
+                    data,
+                },
+            )
+            .await?;
+        Ok(res)
+    }
+}
+
+/// Serves data that is already in memory in chunks, used when platform can
+/// not stream item data.
+struct MemoryStreamReader {
+    data: Vec<u8>,
+    offset: Cell<usize>,
+}
+
+impl MemoryStreamReader {
+    const CHUNK_SIZE: usize = 1024 * 1024;
+
+    fn from_value(value: Value) -> Option<Self> {
+        let data = match value {
+            Value::U8List(data) => data,
+            Value::String(string) => string.into_bytes(),
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            offset: Cell::new(0),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl VirtualFileReader for MemoryStreamReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        let offset = self.offset.get();
+        let end = (offset + Self::CHUNK_SIZE).min(self.data.len());
+        self.offset.set(end);
+        Ok(self.data[offset..end].to_vec())
+    }
+
+    fn file_size(&self) -> NativeExtensionsResult<Option<i64>> {
+        Ok(Some(self.data.len() as i64))
+    }
+
+    fn file_name(&self) -> Option<String> {
+        None
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+}
+
+/// Serves file staged by `getVirtualFileData` in chunks. Staging folder is
+/// removed when closed or dropped.
+struct StagedFileReader {
+    /// `None` while a chunk is being read on the background thread.
+    file: RefCell<Option<File>>,
+    closed: Cell<bool>,
+    path: PathBuf,
+    staging_folder: PathBuf,
+    size: i64,
+}
+
+impl StagedFileReader {
+    const CHUNK_SIZE: usize = 1024 * 1024;
+
+    fn new(path: PathBuf, staging_folder: PathBuf) -> NativeExtensionsResult<Self> {
+        let file = File::open(&path)?;
+        let size = file.metadata()?.len() as i64;
+        Ok(Self {
+            file: RefCell::new(Some(file)),
+            closed: Cell::new(false),
+            path,
+            staging_folder,
+            size,
+        })
+    }
+
+    fn remove_staging_folder(&self) {
+        if self.staging_folder.exists() {
+            fs::remove_dir_all(&self.staging_folder).ok_log();
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl VirtualFileReader for StagedFileReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        if self.closed.get() {
+            return Ok(Vec::new());
+        }
+        let Some(mut file) = self.file.take() else {
+            return Err(NativeExtensionsError::OtherError(
+                "Read already in progress".into(),
+            ));
+        };
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let sender = RunLoop::current().new_sender();
+        thread::spawn(move || {
+            let mut buf = vec![0; Self::CHUNK_SIZE];
+            let res = file.read(&mut buf).map(|len| {
+                buf.truncate(len);
+                buf
+            });
+            sender.send(move || {
+                let completer = completer.take().unwrap();
+                completer.complete((file, res));
+            });
+        });
+        let (file, res) = future.await;
+        if self.closed.get() {
+            // Closed while reading; folder could not be removed with the
+            // file still open.
+            drop(file);
+            self.remove_staging_folder();
+            return Ok(Vec::new());
+        }
+        self.file.replace(Some(file));
+        Ok(res?)
+    }
+
+    fn file_size(&self) -> NativeExtensionsResult<Option<i64>> {
+        Ok(Some(self.size))
+    }
+
+    fn file_name(&self) -> Option<String> {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        if !self.closed.replace(true) {
+            self.file.take();
+            self.remove_staging_folder();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StagedFileReader {
+    fn drop(&mut self) {
+        self.close().ok_log();
+    }
+}
+
 #[async_trait(?Send)]
 impl AsyncMethodHandler for DataReaderManager {
     fn assign_weak_self(&self, weak_self: Weak<Self>) {
@@ -529,17 +3348,47 @@ impl AsyncMethodHandler for DataReaderManager {
     }
 
     fn on_isolate_destroyed(&self, destroyed_isolate_id: IsolateId) {
-        let mut progresses = self.progresses.borrow_mut();
-        progresses.retain(|(isolate_id, _), progress| {
-            if *isolate_id == destroyed_isolate_id {
-                if let Some(progress) = progress.upgrade() {
-                    progress.cancel();
+        shutdown::shutdown(destroyed_isolate_id);
+        remove_binary_channels(destroyed_isolate_id);
+        remove_shared_buffer_configuration(destroyed_isolate_id);
+        self.transform_rules
+            .borrow_mut()
+            .remove(&destroyed_isolate_id);
+        self.format_converters
+            .borrow_mut()
+            .remove(&destroyed_isolate_id);
+        self.effective_converters
+            .borrow_mut()
+            .remove(&destroyed_isolate_id);
+        self.rtf_html_isolates
+            .borrow_mut()
+            .remove(&destroyed_isolate_id);
+        let monitor = Context::get().clipboard_monitor();
+        self.format_subscriptions
+            .borrow_mut()
+            .retain(|(isolate_id, _), _| {
+                let retain = *isolate_id != destroyed_isolate_id;
+                if !retain {
+                    monitor.release();
                 }
-                false
-            } else {
-                true
-            }
-        });
+                retain
+            });
+        self.composite_children
+            .borrow_mut()
+            .retain(|(isolate_id, _), _| *isolate_id != destroyed_isolate_id);
+        self.tasks.close(destroyed_isolate_id);
+
+        // Cancels all progresses of the isolate.
+        let token = self
+            .isolate_tokens
+            .borrow_mut()
+            .remove(&destroyed_isolate_id);
+        if let Some(token) = token {
+            token.cancel();
+        }
+        self.progresses
+            .borrow_mut()
+            .retain(|(isolate_id, _), _| *isolate_id != destroyed_isolate_id);
 
         let mut readers = self.virtual_file_readers.borrow_mut();
         readers.retain(|(isolate_id, _), reader| {
@@ -553,50 +3402,49 @@ impl AsyncMethodHandler for DataReaderManager {
     }
 
     async fn on_method_call(&self, call: MethodCall) -> PlatformResult {
-        match call.method.as_str() {
-            "disposeReader" => self
-                .dispose_reader(call.args.try_into()?)
-                .into_platform_result(),
-            "getItems" => self
-                .get_items(call.args.try_into()?)
-                .await
-                .into_platform_result(),
-            "getItemFormats" => self
-                .get_item_formats(call.args.try_into()?)
-                .await
-                .into_platform_result(),
-            "getItemData" => self
-                .get_item_data(call.isolate, call.args.try_into()?)
-                .await
-                .into_platform_result(),
-            "cancelProgress" => self
-                .cancel_progress(call.isolate, call.args.try_into()?)
-                .into_platform_result(),
-            "getItemInfo" => self
-                .get_item_info(call.args.try_into()?)
-                .await
-                .into_platform_result(),
-            "virtualFileReaderCreate" => self
-                .virtual_file_reader_create(call.isolate, call.args.try_into()?)
-                .await
-                .into_platform_result(),
-            "virtualFileReaderRead" => self
-                .virtual_file_reader_read(call.isolate, call.args.try_into()?)
-                .await
-                .into_platform_result(),
-            "virtualFileReaderClose" => self
-                .virtual_file_reader_close(call.isolate, call.args.try_into()?)
-                .into_platform_result(),
-            "copyVirtualFile" => self
-                .copy_virtual_file(call.isolate, call.args.try_into()?)
-                .await
-                .into_platform_result(),
-            _ => Err(PlatformError {
-                code: "invalid_method".into(),
-                message: Some(format!("Unknown Method: {}", call.method)),
-                detail: Value::Null,
-            }),
+        let isolate_id = call.isolate;
+        let progress_id = progress_id_argument(&call.args);
+        let res = self.handle_method_call(call).await;
+        if let (Err(_), Some(progress_id)) = (&res, progress_id) {
+            self.release_composite_child(isolate_id, progress_id);
         }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use super::{progress_id_argument, CompositeState};
+
+    #[test]
+    fn test_composite_weighted_total() {
+        let mut state = CompositeState {
+            weights: vec![1.0, 3.0],
+            fractions: vec![0.0, 0.0],
+        };
+        assert_eq!(state.update(1, 0.5), Some(0.375));
+        assert_eq!(state.update(0, 1.0), Some(0.625));
+        // Parts don't go back and unchanged parts report nothing.
+        assert_eq!(state.update(1, 0.25), None);
+        assert_eq!(state.update(0, 1.0), None);
+        assert_eq!(state.update(2, 1.0), None);
+        // Fractions are clamped.
+        assert_eq!(state.update(1, 2.0), Some(1.0));
+    }
+
+    #[test]
+    fn test_progress_id_argument() {
+        let args = Value::Map(
+            vec![
+                (Value::String("format".into()), Value::String("html".into())),
+                (Value::String("progressId".into()), Value::I64(7)),
+            ]
+            .into(),
+        );
+        assert_eq!(progress_id_argument(&args), Some(7));
+        assert_eq!(progress_id_argument(&Value::I64(7)), None);
     }
 }
 
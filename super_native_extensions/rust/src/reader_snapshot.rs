@@ -0,0 +1,169 @@
+//! In-memory copy of reader contents.
+//!
+//! Clipboard readers read lazily from the source application. If the user
+//! copies something else (or the source application quits) while a paste is
+//! being processed, later reads fail or return data of the new contents.
+//! Snapshot reads everything upfront so that the paste can be completed from
+//! memory. Virtual files are not copied; reading them still goes to the
+//! source. Formats blocked by file policy are not copied either; transform
+//! rules and file policy for paths still apply when snapshot data is read.
+
+use std::collections::HashMap;
+
+use irondash_message_channel::Value;
+
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    file_policy::FilePolicy,
+    log::OkLog,
+    platform::PlatformDataReader,
+    read_cache::value_size,
+    reader_manager::ReadProgressHandle,
+};
+
+/// Default limit for total size of data copied by snapshot.
+pub const DEFAULT_SNAPSHOT_SIZE_LIMIT: u64 = 256 * 1024 * 1024;
+
+pub struct SnapshotItem {
+    pub handle: i64,
+    pub formats: Vec<String>,
+    pub synthesized_formats: Vec<String>,
+    pub read_virtual_file_formats: Vec<String>,
+    pub copy_virtual_file_formats: Vec<String>,
+    pub suggested_name: Option<String>,
+    pub file_uri_format: Option<String>,
+    /// Formats that failed to read are missing.
+    data: HashMap<String, Value>,
+}
+
+impl SnapshotItem {
+    /// Item with data that doesn't come from a platform reader.
+    pub fn new(
+        handle: i64,
+        formats: Vec<String>,
+        suggested_name: Option<String>,
+        data: HashMap<String, Value>,
+    ) -> Self {
+        Self {
+            handle,
+            formats,
+            synthesized_formats: Vec::new(),
+            read_virtual_file_formats: Vec::new(),
+            copy_virtual_file_formats: Vec::new(),
+            suggested_name,
+            file_uri_format: None,
+            data,
+        }
+    }
+
+    pub fn data(&self, format: &str) -> Option<Value> {
+        self.data.get(format).cloned()
+    }
+}
+
+pub struct ReaderSnapshot {
+    items: Vec<SnapshotItem>,
+}
+
+impl ReaderSnapshot {
+    pub fn from_items(items: Vec<SnapshotItem>) -> Self {
+        Self { items }
+    }
+
+    pub fn items(&self) -> Vec<i64> {
+        self.items.iter().map(|i| i.handle).collect()
+    }
+
+    pub fn item(&self, handle: i64) -> Option<&SnapshotItem> {
+        self.items.iter().find(|i| i.handle == handle)
+    }
+}
+
+impl PlatformDataReader {
+    /// Reads data of all items in all formats that are not virtual files or
+    /// blocked by `policy`. Fails once data read exceeds `size_limit` bytes,
+    /// and with `Cancelled` as soon as progress is cancelled.
+    pub async fn snapshot(
+        &self,
+        policy: Option<&FilePolicy>,
+        size_limit: u64,
+        progress: Option<ReadProgressHandle>,
+    ) -> NativeExtensionsResult<ReaderSnapshot> {
+        match progress.as_ref().map(|p| p.cancellation_token().clone()) {
+            Some(token) => {
+                token
+                    .run(self.snapshot_inner(policy, size_limit, progress))
+                    .await
+            }
+            None => self.snapshot_inner(policy, size_limit, None).await,
+        }
+    }
+
+    async fn snapshot_inner(
+        &self,
+        policy: Option<&FilePolicy>,
+        size_limit: u64,
+        progress: Option<ReadProgressHandle>,
+    ) -> NativeExtensionsResult<ReaderSnapshot> {
+        let mut size = 0u64;
+        let handles = self.get_items().await?;
+        let count = handles.len().max(1) as f64;
+        let mut items = Vec::with_capacity(handles.len());
+        for (index, handle) in handles.into_iter().enumerate() {
+            let formats = self.get_formats_for_item(handle).await?;
+            let mut item = SnapshotItem {
+                handle,
+                formats: formats.clone(),
+                synthesized_formats: Vec::new(),
+                read_virtual_file_formats: Vec::new(),
+                copy_virtual_file_formats: Vec::new(),
+                suggested_name: self.get_suggested_name_for_item(handle).await?,
+                file_uri_format: None,
+                data: HashMap::new(),
+            };
+            for format in formats {
+                if self.item_format_is_synthesized(handle, &format)? {
+                    item.synthesized_formats.push(format.clone());
+                }
+                let mut is_virtual_file = false;
+                if self.can_read_virtual_file_for_item(handle, &format).await? {
+                    item.read_virtual_file_formats.push(format.clone());
+                    is_virtual_file = true;
+                }
+                if self.can_copy_virtual_file_for_item(handle, &format).await? {
+                    item.copy_virtual_file_formats.push(format.clone());
+                    is_virtual_file = true;
+                }
+                let blocked = policy
+                    .map(|policy| policy.check(None, Some(&format)).is_err())
+                    .unwrap_or(false);
+                if is_virtual_file || blocked {
+                    continue;
+                }
+                if let Some(data) = self
+                    .get_data_for_item(handle, format.clone(), None)
+                    .await
+                    .ok_log()
+                {
+                    size += value_size(&data) as u64;
+                    if size > size_limit {
+                        return Err(NativeExtensionsError::OtherError(format!(
+                            "reader contents exceed snapshot size limit of {size_limit} bytes"
+                        )));
+                    }
+                    item.data.insert(format, data);
+                }
+            }
+            if item.read_virtual_file_formats.is_empty()
+                && item.copy_virtual_file_formats.is_empty()
+            {
+                item.file_uri_format = self.get_item_format_for_uri(handle).await?;
+            }
+            items.push(item);
+            if let Some(progress) = &progress {
+                progress.report_progress(Some((index + 1) as f64 / count));
+            }
+        }
+        Ok(ReaderSnapshot { items })
+    }
+}
@@ -0,0 +1,212 @@
+//! Periodic reclamation of native resources leaked by the application or by
+//! processes that did not exit cleanly.
+//!
+//! Each sweep removes stale temporary files, releases data providers that
+//! are no longer referenced by any clipboard or drag session and (on
+//! Android) releases drag and drop URI permissions held for too long.
+//! Anything reclaimed is reported to subscribed isolates through
+//! `onSweepReport`, which makes leaks easy to spot during development.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use irondash_message_channel::{
+    IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler, MethodInvoker,
+    PlatformError, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
+};
+use irondash_run_loop::RunLoop;
+
+use crate::{
+    context::Context, data_provider_manager::GetDataProviderManager,
+    local_transfer::remove_stale_endpoints, log::OkLog, managed_directory::remove_stale_sessions,
+    task_scope::task_diagnostics,
+};
+
+#[cfg(target_os = "android")]
+use crate::platform_impl::platform::release_expired_drop_permissions;
+
+#[cfg(not(target_os = "android"))]
+fn release_expired_drop_permissions(_max_age: Duration) -> usize {
+    0
+}
+
+#[derive(TryFromValue, Clone)]
+#[irondash(rename_all = "camelCase")]
+struct SweeperConfiguration {
+    /// Interval between sweeps. `None` disables periodic sweeping.
+    interval_millis: Option<i64>,
+    /// Temporary files of other processes older than this are removed.
+    stale_file_age_millis: i64,
+    /// Data providers unreferenced for this long are released. `None` keeps
+    /// them until Dart unregisters them.
+    idle_provider_timeout_millis: Option<i64>,
+    /// Android drag and drop permissions held longer are released. `None`
+    /// keeps them for the lifetime of the reader.
+    drop_permission_timeout_millis: Option<i64>,
+}
+
+impl Default for SweeperConfiguration {
+    fn default() -> Self {
+        Self {
+            interval_millis: None,
+            stale_file_age_millis: 24 * 60 * 60 * 1000,
+            idle_provider_timeout_millis: None,
+            drop_permission_timeout_millis: None,
+        }
+    }
+}
+
+fn millis(value: i64) -> Duration {
+    Duration::from_millis(value.max(0) as u64)
+}
+
+#[derive(IntoValue, Default, Clone)]
+#[irondash(rename_all = "camelCase")]
+struct SweepReport {
+    removed_files: Vec<String>,
+    released_providers: i64,
+    released_drop_permissions: i64,
+}
+
+impl SweepReport {
+    fn is_empty(&self) -> bool {
+        self.removed_files.is_empty()
+            && self.released_providers == 0
+            && self.released_drop_permissions == 0
+    }
+}
+
+pub struct ResourceSweeper {
+    weak_self: Late<Weak<Self>>,
+    invoker: Late<MethodInvoker>,
+    configuration: RefCell<SweeperConfiguration>,
+    /// Incremented on reconfiguration to invalidate previously scheduled
+    /// sweep.
+    generation: Cell<u64>,
+    /// Isolates receiving sweep reports.
+    isolates: RefCell<HashSet<IsolateId>>,
+}
+
+pub trait GetResourceSweeper {
+    fn resource_sweeper(&self) -> Rc<ResourceSweeper>;
+}
+
+impl GetResourceSweeper for Context {
+    fn resource_sweeper(&self) -> Rc<ResourceSweeper> {
+        self.get_attachment(ResourceSweeper::new).handler()
+    }
+}
+
+impl ResourceSweeper {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {
+            weak_self: Late::new(),
+            invoker: Late::new(),
+            configuration: RefCell::new(SweeperConfiguration::default()),
+            generation: Cell::new(0),
+            isolates: RefCell::new(HashSet::new()),
+        }
+        .register("ResourceSweeper")
+    }
+
+    fn configure(&self, isolate: IsolateId, configuration: SweeperConfiguration) {
+        self.isolates.borrow_mut().insert(isolate);
+        self.configuration.replace(configuration);
+        self.generation.set(self.generation.get() + 1);
+        self.schedule_sweep();
+    }
+
+    fn schedule_sweep(&self) {
+        let Some(interval) = self.configuration.borrow().interval_millis else {
+            return;
+        };
+        let generation = self.generation.get();
+        let weak_self = self.weak_self.clone();
+        RunLoop::current()
+            .schedule(millis(interval), move || {
+                if let Some(this) = weak_self.upgrade() {
+                    if this.generation.get() == generation {
+                        let report = this.sweep();
+                        this.notify(&report);
+                        this.schedule_sweep();
+                    }
+                }
+            })
+            .detach();
+    }
+
+    fn sweep(&self) -> SweepReport {
+        let configuration = self.configuration.borrow().clone();
+        let mut report = SweepReport::default();
+        let stale_age = millis(configuration.stale_file_age_millis);
+        report.removed_files = remove_stale_sessions(stale_age)
+            .into_iter()
+            .chain(remove_stale_endpoints())
+            .map(|path| path.to_string_lossy().into())
+            .collect();
+        if let Some(timeout) = configuration.idle_provider_timeout_millis {
+            report.released_providers = Context::get()
+                .data_provider_manager()
+                .release_idle_providers(millis(timeout))
+                as i64;
+        }
+        if let Some(timeout) = configuration.drop_permission_timeout_millis {
+            report.released_drop_permissions =
+                release_expired_drop_permissions(millis(timeout)) as i64;
+        }
+        report
+    }
+
+    fn notify(&self, report: &SweepReport) {
+        if report.is_empty() {
+            return;
+        }
+        let report: Value = report.clone().into();
+        for isolate in self.isolates.borrow().iter() {
+            self.invoker
+                .call_method(*isolate, "onSweepReport", report.clone(), |r| {
+                    r.ok_log();
+                });
+        }
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "configure" => {
+                self.configure(call.isolate, call.args.try_into()?);
+                Ok(Value::Null)
+            }
+            "sweepNow" => Ok(self.sweep().into()),
+            // Pending manager tasks; these should drop to zero once isolates
+            // are gone.
+            "getTaskDiagnostics" => Ok(task_diagnostics().into()),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+impl MethodHandler for ResourceSweeper {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
+
+    fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self);
+    }
+
+    fn assign_invoker(&self, invoker: MethodInvoker) {
+        self.invoker.set(invoker);
+    }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        self.isolates.borrow_mut().remove(&isolate);
+    }
+}
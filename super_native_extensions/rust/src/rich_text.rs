@@ -0,0 +1,688 @@
+//! Conversion of rich text (HTML, RTF) into a flat list of styled runs, so that
+//! Flutter rich text editors don't need their own parsers for platform
+//! formats. Only inline styling is preserved (bold, italic, underline, links);
+//! block elements are converted to line breaks.
+
+use irondash_message_channel::IntoValue;
+
+use crate::{
+    cf_html::strip_header, error::NativeExtensionsResult, format_fidelity::format_fidelity,
+    platform::PlatformDataReader, reader_manager::ReadProgressHandle,
+    value_coerce::string_from_value,
+};
+
+#[derive(IntoValue, Debug, Clone, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub struct TextSpan {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Clone, Default, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    link: Option<String>,
+}
+
+#[derive(Default)]
+struct SpanBuilder {
+    spans: Vec<TextSpan>,
+}
+
+impl SpanBuilder {
+    fn push(&mut self, text: &str, style: &Style) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(last) = self.spans.last_mut() {
+            if last.bold == style.bold
+                && last.italic == style.italic
+                && last.underline == style.underline
+                && last.link == style.link
+            {
+                last.text.push_str(text);
+                return;
+            }
+        }
+        self.spans.push(TextSpan {
+            text: text.into(),
+            bold: style.bold,
+            italic: style.italic,
+            underline: style.underline,
+            link: style.link.clone(),
+        });
+    }
+
+    fn ends_with_newline(&self) -> bool {
+        self.spans
+            .last()
+            .map(|s| s.text.ends_with('\n'))
+            .unwrap_or(true)
+    }
+
+    fn ends_with_whitespace(&self) -> bool {
+        self.spans
+            .last()
+            .map(|s| s.text.ends_with(char::is_whitespace))
+            .unwrap_or(true)
+    }
+
+    fn finish(mut self) -> Vec<TextSpan> {
+        // Drop trailing line breaks produced by closing block elements.
+        while let Some(last) = self.spans.last_mut() {
+            let len = last.text.trim_end_matches('\n').len();
+            last.text.truncate(len);
+            if last.text.is_empty() {
+                self.spans.pop();
+            } else {
+                break;
+            }
+        }
+        self.spans
+    }
+}
+
+//
+// HTML
+//
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = if let Some(hex) = number.strip_prefix(['x', 'X']) {
+            u32::from_str_radix(hex, 16).ok()?
+        } else {
+            number.parse().ok()?
+        };
+        return char::from_u32(code);
+    }
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => None,
+    }
+}
+
+fn parse_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut res = Vec::new();
+    let mut chars = tag.chars().peekable();
+    loop {
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == '=' || c == '/' {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+        if name.is_empty() {
+            if chars.next().is_none() {
+                break;
+            }
+            continue;
+        }
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            chars.next();
+        }
+        let mut value = String::new();
+        if chars.peek() == Some(&'=') {
+            chars.next();
+            while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+                chars.next();
+            }
+            match chars.peek().cloned() {
+                Some(quote @ ('"' | '\'')) => {
+                    chars.next();
+                    for c in chars.by_ref() {
+                        if c == quote {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                }
+                _ => {
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() {
+                            break;
+                        }
+                        value.push(c);
+                        chars.next();
+                    }
+                }
+            }
+        }
+        res.push((name.to_ascii_lowercase(), decode_entities(&value)));
+    }
+    res
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        res.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end < 10)
+            .and_then(|end| decode_entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                res.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+fn apply_css(style: &mut Style, css: &str) {
+    for declaration in css.split(';') {
+        let Some((property, value)) = declaration.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_ascii_lowercase();
+        match property.trim().to_ascii_lowercase().as_str() {
+            "font-weight" => {
+                style.bold = value == "bold"
+                    || value == "bolder"
+                    || value.parse::<u32>().map(|w| w >= 600).unwrap_or(false)
+            }
+            "font-style" => style.italic = value == "italic" || value == "oblique",
+            "text-decoration" | "text-decoration-line" => {
+                style.underline = value.contains("underline")
+            }
+            _ => {}
+        }
+    }
+}
+
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "li",
+    "ul",
+    "ol",
+    "tr",
+    "table",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "pre",
+];
+
+const IGNORED_ELEMENTS: &[&str] = &["head", "script", "style", "title"];
+
+pub fn spans_from_html(html: &str) -> Vec<TextSpan> {
+    let html = strip_header(html);
+    let html = html.as_ref();
+    let mut builder = SpanBuilder::default();
+    // (tag name, style in effect before the tag was opened)
+    let mut stack = Vec::<(String, Style)>::new();
+    let mut style = Style::default();
+    let mut ignored_depth = 0;
+    let mut preformatted = 0;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map(|i| &comment[i + 3..]).unwrap_or("");
+            continue;
+        }
+        if rest.starts_with('<') {
+            let end = rest.find('>').unwrap_or(rest.len());
+            let tag = &rest[1..end];
+            rest = rest.get(end + 1..).unwrap_or("");
+            if tag.starts_with('!') || tag.starts_with('?') {
+                continue;
+            }
+            let closing = tag.starts_with('/');
+            let tag = tag.trim_start_matches('/');
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            let name = name.to_ascii_lowercase();
+            if closing {
+                if let Some(index) = stack.iter().rposition(|(n, _)| *n == name) {
+                    style = stack[index].1.clone();
+                    stack.truncate(index);
+                }
+                if IGNORED_ELEMENTS.contains(&name.as_str()) {
+                    ignored_depth = (ignored_depth as i32 - 1).max(0) as usize;
+                }
+                if name == "pre" {
+                    preformatted = (preformatted as i32 - 1).max(0) as usize;
+                }
+                if BLOCK_ELEMENTS.contains(&name.as_str()) && !builder.ends_with_newline() {
+                    builder.push("\n", &style);
+                }
+                continue;
+            }
+            if name == "br" {
+                builder.push("\n", &style);
+                continue;
+            }
+            if BLOCK_ELEMENTS.contains(&name.as_str()) && !builder.ends_with_newline() {
+                builder.push("\n", &style);
+            }
+            if self_closing || matches!(name.as_str(), "img" | "hr" | "meta" | "link" | "input") {
+                continue;
+            }
+            if IGNORED_ELEMENTS.contains(&name.as_str()) {
+                ignored_depth += 1;
+            }
+            if name == "pre" {
+                preformatted += 1;
+            }
+            stack.push((name.clone(), style.clone()));
+            match name.as_str() {
+                "b" | "strong" => style.bold = true,
+                "i" | "em" | "cite" => style.italic = true,
+                "u" | "ins" => style.underline = true,
+                _ => {}
+            }
+            for (attribute, value) in parse_attributes(attributes) {
+                match attribute.as_str() {
+                    "href" if name == "a" => style.link = Some(value),
+                    "style" => apply_css(&mut style, &value),
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        let end = rest.find('<').unwrap_or(rest.len());
+        let text = &rest[..end];
+        rest = &rest[end..];
+        if ignored_depth > 0 {
+            continue;
+        }
+        let text = decode_entities(text);
+        if preformatted > 0 {
+            builder.push(&text, &style);
+            continue;
+        }
+        // Collapse whitespace
+        let mut collapsed = String::with_capacity(text.len());
+        let mut last_whitespace = builder.ends_with_whitespace();
+        for c in text.chars() {
+            if c.is_whitespace() && c != '\u{a0}' {
+                if !last_whitespace {
+                    collapsed.push(' ');
+                }
+                last_whitespace = true;
+            } else {
+                collapsed.push(c);
+                last_whitespace = false;
+            }
+        }
+        builder.push(&collapsed, &style);
+    }
+    builder.finish()
+}
+
+//
+// RTF
+//
+
+#[derive(Clone, Default)]
+struct RtfGroup {
+    style: Style,
+    skip: bool,
+    /// Collecting field instruction instead of output.
+    field_instruction: Option<String>,
+    /// Hyperlink target of a `\field` group, applied to its `\fldrslt`.
+    field_link: Option<String>,
+    /// Number of characters following `\uN` to skip.
+    unicode_skip: usize,
+}
+
+const RTF_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "info",
+    "pict",
+    "header",
+    "footer",
+    "headerl",
+    "headerr",
+    "footerl",
+    "footerr",
+    "footnote",
+    "object",
+    "themedata",
+    "colorschememapping",
+    "latentstyles",
+    "datastore",
+    "xmlnstbl",
+    "listtable",
+    "listoverridetable",
+    "rsidtbl",
+    "generator",
+    "filetbl",
+];
+
+fn hyperlink_from_instruction(instruction: &str) -> Option<String> {
+    let rest = instruction.trim().strip_prefix("HYPERLINK")?.trim();
+    let link = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next()?,
+        None => rest.split_whitespace().next()?,
+    };
+    Some(link.to_string())
+}
+
+/// Maps windows-1252 specific characters; the rest matches latin-1.
+fn cp1252_to_char(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+        '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9f => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+pub fn spans_from_rtf(rtf: &str) -> Vec<TextSpan> {
+    let mut builder = SpanBuilder::default();
+    let mut stack = vec![RtfGroup::default()];
+    let mut chars = rtf.chars().peekable();
+    let mut ignorable_destination = false;
+    let mut pending_skip = 0usize;
+    // Characters outside BMP are written as two `\u` escapes.
+    let mut high_surrogate = None::<u32>;
+
+    fn output(stack: &mut [RtfGroup], builder: &mut SpanBuilder, text: &str) {
+        let group = stack.last_mut().unwrap();
+        if let Some(instruction) = &mut group.field_instruction {
+            instruction.push_str(text);
+        } else if !group.skip {
+            builder.push(text, &group.style);
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let mut group = stack.last().cloned().unwrap_or_default();
+                group.field_link = None;
+                // Nested groups of field instruction still belong to it
+                group.field_instruction = group.field_instruction.as_ref().map(|_| String::new());
+                stack.push(group);
+                pending_skip = 0;
+            }
+            '}' => {
+                if stack.len() > 1 {
+                    let group = stack.pop().unwrap();
+                    if let Some(instruction) = group.field_instruction {
+                        if let Some(parent) = stack.last_mut() {
+                            match &mut parent.field_instruction {
+                                Some(parent_instruction) => {
+                                    parent_instruction.push_str(&instruction)
+                                }
+                                None => {
+                                    parent.field_link = hyperlink_from_instruction(&instruction)
+                                }
+                            }
+                        }
+                    }
+                }
+                pending_skip = 0;
+            }
+            '\\' => {
+                let Some(&next) = chars.peek() else {
+                    break;
+                };
+                if !next.is_ascii_alphabetic() {
+                    chars.next();
+                    match next {
+                        '*' => ignorable_destination = true,
+                        '\'' => {
+                            let hex: String = chars.by_ref().take(2).collect();
+                            if pending_skip > 0 {
+                                pending_skip -= 1;
+                            } else if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                                output(&mut stack, &mut builder, &cp1252_to_char(byte).to_string());
+                            }
+                        }
+                        '~' => output(&mut stack, &mut builder, "\u{a0}"),
+                        '\n' | '\r' => output(&mut stack, &mut builder, "\n"),
+                        '\\' | '{' | '}' => output(&mut stack, &mut builder, &next.to_string()),
+                        _ => {}
+                    }
+                    continue;
+                }
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_alphabetic() {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                let mut param = String::new();
+                if chars.peek() == Some(&'-') {
+                    param.push('-');
+                    chars.next();
+                }
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    param.push(c);
+                    chars.next();
+                }
+                // Space delimiter is part of the control word
+                if chars.peek() == Some(&' ') {
+                    chars.next();
+                }
+                let param: Option<i32> = param.parse().ok();
+                let enabled = param != Some(0);
+                let is_ignorable = std::mem::take(&mut ignorable_destination);
+                let group = stack.last_mut().unwrap();
+                match word.as_str() {
+                    "b" => group.style.bold = enabled,
+                    "i" => group.style.italic = enabled,
+                    "ul" => group.style.underline = enabled,
+                    "ulnone" => group.style.underline = false,
+                    "plain" => {
+                        group.style.bold = false;
+                        group.style.italic = false;
+                        group.style.underline = false;
+                    }
+                    "par" | "line" | "row" => output(&mut stack, &mut builder, "\n"),
+                    "tab" | "cell" => output(&mut stack, &mut builder, "\t"),
+                    "emdash" => output(&mut stack, &mut builder, "—"),
+                    "endash" => output(&mut stack, &mut builder, "–"),
+                    "lquote" => output(&mut stack, &mut builder, "‘"),
+                    "rquote" => output(&mut stack, &mut builder, "’"),
+                    "ldblquote" => output(&mut stack, &mut builder, "“"),
+                    "rdblquote" => output(&mut stack, &mut builder, "”"),
+                    "bullet" => output(&mut stack, &mut builder, "•"),
+                    "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                    "u" => {
+                        let code = param.unwrap_or(0);
+                        let code = if code < 0 { code + 65536 } else { code } as u32;
+                        let skip = group.unicode_skip;
+                        let c = match code {
+                            0xD800..=0xDBFF => {
+                                high_surrogate = Some(code);
+                                None
+                            }
+                            0xDC00..=0xDFFF => high_surrogate.take().and_then(|high| {
+                                char::from_u32(0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00))
+                            }),
+                            code => char::from_u32(code),
+                        };
+                        if let Some(c) = c {
+                            output(&mut stack, &mut builder, &c.to_string());
+                        }
+                        pending_skip = skip;
+                    }
+                    "fldinst" => group.field_instruction = Some(String::new()),
+                    "fldrslt" => {
+                        let len = stack.len();
+                        let link = if len >= 2 {
+                            stack[len - 2].field_link.clone()
+                        } else {
+                            None
+                        };
+                        let group = stack.last_mut().unwrap();
+                        if link.is_some() {
+                            group.style.link = link;
+                            group.style.underline = true;
+                        }
+                    }
+                    word if RTF_DESTINATIONS.contains(&word) => group.skip = true,
+                    _ => {
+                        if is_ignorable {
+                            group.skip = true;
+                        }
+                    }
+                }
+            }
+            '\r' | '\n' => {}
+            c => {
+                if pending_skip > 0 {
+                    pending_skip -= 1;
+                } else {
+                    output(&mut stack, &mut builder, &c.to_string());
+                }
+            }
+        }
+    }
+    builder.finish()
+}
+
+enum RichTextFormat {
+    Html,
+    Rtf,
+}
+
+impl RichTextFormat {
+    fn from_format(format: &str) -> Option<Self> {
+        let format = format.split(';').next().unwrap_or(format).trim();
+        match format.to_ascii_lowercase().as_str() {
+            "public.html" | "text/html" | "html format" => Some(Self::Html),
+            "public.rtf" | "text/rtf" | "application/rtf" | "rich text format" => Some(Self::Rtf),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the richest HTML or RTF representation of the item and converts it
+/// to spans. Returns None if item has no rich text.
+pub async fn read_rich_text(
+    reader: &PlatformDataReader,
+    item: i64,
+    progress: ReadProgressHandle,
+) -> NativeExtensionsResult<Option<Vec<TextSpan>>> {
+    let mut formats: Vec<_> = reader
+        .get_formats_for_item(item)
+        .await?
+        .into_iter()
+        .filter_map(|f| RichTextFormat::from_format(&f).map(|kind| (f, kind)))
+        .collect();
+    formats.sort_by_key(|(f, _)| -format_fidelity(f));
+    for (format, kind) in formats {
+        let data = reader
+            .get_data_for_item(item, format, Some(progress.clone()))
+            .await?;
+        let Some(text) = string_from_value(&data) else {
+            continue;
+        };
+        let text = text.trim_end_matches(char::from(0));
+        return Ok(Some(match kind {
+            RichTextFormat::Html => spans_from_html(text),
+            RichTextFormat::Rtf => spans_from_rtf(text),
+        }));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cf_html::wrap_html;
+
+    fn span(text: &str, bold: bool, italic: bool, link: Option<&str>) -> TextSpan {
+        TextSpan {
+            text: text.into(),
+            bold,
+            italic,
+            underline: false,
+            link: link.map(|l| l.into()),
+        }
+    }
+
+    #[test]
+    fn test_html() {
+        let spans = spans_from_html(
+            "<p>Hello <b>bold <i>both</i></b> &amp; <a href=\"https://x.com\">link</a></p>",
+        );
+        assert_eq!(
+            spans,
+            vec![
+                span("Hello ", false, false, None),
+                span("bold ", true, false, None),
+                span("both", true, true, None),
+                span(" & ", false, false, None),
+                span("link", false, false, Some("https://x.com")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cf_html() {
+        let data = wrap_html("<b>Bold</b>", None);
+        let data = String::from_utf8(data).unwrap();
+        assert_eq!(
+            spans_from_html(&data),
+            vec![span("Bold", true, false, None)]
+        );
+    }
+
+    #[test]
+    fn test_rtf() {
+        let spans = spans_from_rtf(
+            "{\\rtf1\\ansi{\\fonttbl{\\f0 Helvetica;}}\\f0 Hello \\b bold\\b0  caf\\'e9 \
+             {\\field{\\*\\fldinst{HYPERLINK \"https://x.com\"}}{\\fldrslt link}}\\par}",
+        );
+        assert_eq!(
+            spans,
+            vec![
+                span("Hello ", false, false, None),
+                span("bold", true, false, None),
+                span(" café ", false, false, None),
+                TextSpan {
+                    text: "link".into(),
+                    bold: false,
+                    italic: false,
+                    underline: true,
+                    link: Some("https://x.com".into()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Opt-in conversion between RTF and HTML.
+//!
+//! Native word processors often put only RTF on the clipboard while Flutter
+//! rich text editors import HTML. When enabled for an isolate, readers
+//! synthesize HTML for items that only have RTF; the conversion is a built-in
+//! format converter, so the format is reported as synthesized. Data providers
+//! that opt in get RTF synthesized from their HTML representation for
+//! applications that don't read HTML. Both directions go through
+//! [`TextSpan`]s, so only inline styling and links are preserved.
+
+use std::rc::Rc;
+
+use async_trait::async_trait;
+use irondash_message_channel::Value;
+
+use crate::{
+    api_model::{DataProvider, DataRepresentation},
+    cf_html::strip_header,
+    error::NativeExtensionsResult,
+    format_converter::{FormatConverter, FormatConverters, RTF_TO_HTML_CONVERTER_ID},
+    rich_text::{spans_from_html, spans_from_rtf, TextSpan},
+    value_coerce::string_from_value,
+};
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod formats {
+    pub const HTML: &str = "public.html";
+    pub const HTML_SOURCES: &[&str] = &[HTML];
+    pub const RTF: &str = "public.rtf";
+}
+
+#[cfg(target_os = "windows")]
+mod formats {
+    pub const HTML: &str = "text/html";
+    pub const HTML_SOURCES: &[&str] = &[HTML, "HTML Format"];
+    pub const RTF: &str = "Rich Text Format";
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod formats {
+    pub const HTML: &str = "text/html";
+    pub const HTML_SOURCES: &[&str] = &[HTML];
+    pub const RTF: &str = "text/rtf";
+}
+
+/// Schemes of links preserved by the conversion. Links with other schemes
+/// (such as `javascript:`) are dropped and only their text is kept.
+const ALLOWED_LINK_SCHEMES: &[&str] = &["http", "https", "mailto", "ftp", "tel"];
+
+/// Returns link safe to be written as `href` or `HYPERLINK` target, or `None`
+/// if it should be dropped. Relative links are kept.
+fn sanitize_link(link: &str) -> Option<String> {
+    // Browsers ignore whitespace and control characters in the scheme.
+    let link: String = link
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    let compact: String = link.chars().filter(|c| !c.is_whitespace()).collect();
+    if let Some((scheme, _)) = compact.split_once(':') {
+        let is_scheme = !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if is_scheme
+            && !ALLOWED_LINK_SCHEMES
+                .iter()
+                .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+        {
+            return None;
+        }
+    }
+    Some(link.replace('"', "%22"))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\n' => res.push_str("<br>"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+pub fn html_from_spans(spans: &[TextSpan]) -> String {
+    let mut res = String::new();
+    for span in spans {
+        let mut close = Vec::new();
+        if let Some(link) = span.link.as_deref().and_then(sanitize_link) {
+            res.push_str(&format!("<a href=\"{}\">", escape_html(&link)));
+            close.push("</a>");
+        }
+        for (enabled, open, end) in [
+            (span.bold, "<b>", "</b>"),
+            (span.italic, "<i>", "</i>"),
+            (span.underline, "<u>", "</u>"),
+        ] {
+            if enabled {
+                res.push_str(open);
+                close.push(end);
+            }
+        }
+        res.push_str(&escape_html(&span.text));
+        close.iter().rev().for_each(|end| res.push_str(end));
+    }
+    res
+}
+
+fn escape_rtf(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                res.push('\\');
+                res.push(c);
+            }
+            '\n' => res.push_str("\\par\n"),
+            c if c.is_ascii() => res.push(c),
+            // RTF unicode escapes are signed 16-bit, followed by ANSI fallback.
+            c => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    res.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    res
+}
+
+pub fn rtf_from_spans(spans: &[TextSpan]) -> String {
+    let mut res =
+        String::from("{\\rtf1\\ansi\\ansicpg1252\\deff0{\\fonttbl{\\f0 Helvetica;}}\\uc1\\f0 ");
+    for span in spans {
+        let mut controls = String::new();
+        for (enabled, control) in [
+            (span.bold, "\\b"),
+            (span.italic, "\\i"),
+            (span.underline, "\\ul"),
+        ] {
+            if enabled {
+                controls.push_str(control);
+            }
+        }
+        // Space after the last control word is a delimiter, not text.
+        let text = if controls.is_empty() {
+            format!("{{{}}}", escape_rtf(&span.text))
+        } else {
+            format!("{{{controls} {}}}", escape_rtf(&span.text))
+        };
+        match span.link.as_deref().and_then(sanitize_link) {
+            Some(link) => res.push_str(&format!(
+                "{{\\field{{\\*\\fldinst{{HYPERLINK \"{}\"}}}}{{\\fldrslt{text}}}}}",
+                escape_rtf(&link)
+            )),
+            None => res.push_str(&text),
+        }
+    }
+    res.push('}');
+    res
+}
+
+struct RtfToHtmlConverter;
+
+#[async_trait(?Send)]
+impl FormatConverter for RtfToHtmlConverter {
+    fn source_format(&self) -> &str {
+        formats::RTF
+    }
+
+    fn target_format(&self) -> &str {
+        formats::HTML
+    }
+
+    async fn convert(&self, data: Value) -> NativeExtensionsResult<Value> {
+        let Some(rtf) = string_from_value(&data) else {
+            return Ok(Value::Null);
+        };
+        let spans = spans_from_rtf(rtf.trim_end_matches(char::from(0)));
+        Ok(html_from_spans(&spans).into())
+    }
+}
+
+thread_local! {
+    static RTF_TO_HTML_CONVERTER: Rc<dyn FormatConverter> = Rc::new(RtfToHtmlConverter);
+}
+
+/// Adds converter synthesizing HTML from RTF after converters registered by
+/// Dart, so that those take precedence.
+pub fn register_converters(converters: &mut FormatConverters) {
+    RTF_TO_HTML_CONVERTER
+        .with(|converter| converters.register(RTF_TO_HTML_CONVERTER_ID, converter.clone()));
+}
+
+/// Adds RTF representation to provider with HTML data and no RTF. Lazy
+/// representations are not converted.
+pub fn add_synthesized_rtf(provider: &mut DataProvider) {
+    let representations = &provider.representations;
+    if representations.iter().any(|r| r.format() == formats::RTF) {
+        return;
+    }
+    let html = representations.iter().find_map(|r| match r {
+        DataRepresentation::Simple { format, data }
+            if formats::HTML_SOURCES.contains(&format.as_str()) =>
+        {
+            string_from_value(data)
+        }
+        _ => None,
+    });
+    let Some(html) = html else {
+        return;
+    };
+    let html = strip_header(html.trim_end_matches(char::from(0)));
+    let rtf = rtf_from_spans(&spans_from_html(&html));
+    provider.representations.push(DataRepresentation::Simple {
+        format: formats::RTF.into(),
+        data: rtf.into_bytes().into(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{html_from_spans, rtf_from_spans, sanitize_link};
+    use crate::rich_text::{spans_from_html, spans_from_rtf};
+
+    #[test]
+    fn test_round_trip() {
+        let html = "Plain <b>bold</b> <i>caf\u{e9} \u{1f600}</i> <a href=\"https://example.com\"><u>link</u></a>";
+        let spans = spans_from_html(html);
+        assert_eq!(spans_from_rtf(&rtf_from_spans(&spans)), spans);
+        assert_eq!(spans_from_html(&html_from_spans(&spans)), spans);
+    }
+
+    #[test]
+    fn test_unsafe_links_are_dropped() {
+        assert_eq!(sanitize_link("javascript:alert(1)"), None);
+        assert_eq!(sanitize_link(" JavaScript:alert(1)"), None);
+        assert_eq!(sanitize_link("java\tscript:alert(1)"), None);
+        assert_eq!(sanitize_link("data:text/html,x"), None);
+        assert_eq!(
+            sanitize_link("https://example.com/?q=\"x\"").as_deref(),
+            Some("https://example.com/?q=%22x%22")
+        );
+        assert_eq!(sanitize_link("docs/a:b").as_deref(), Some("docs/a:b"));
+        let html = "<a href=\"javascript:alert(1)\">text</a>";
+        assert_eq!(html_from_spans(&spans_from_html(html)), "text");
+    }
+}
@@ -0,0 +1,323 @@
+//! macOS Services menu integration.
+//!
+//! Data providers advertised with `setServicesContent` are offered to
+//! services that operate on the current selection, such as "Open URL" or
+//! translation. Data that a service returns, and data sent to the application
+//! when it acts as a service provider (`NSMessage` of `handleServiceRequest`
+//! in `NSServices` of Info.plist), is delivered to Dart as a reader.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use irondash_message_channel::{
+    IntoPlatformResult, IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler,
+    MethodInvoker, PlatformError, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
+};
+
+#[cfg(target_os = "macos")]
+use crate::platform_impl::platform::PlatformServicesManager;
+use crate::{
+    api_model::DataProviderId,
+    context::Context,
+    data_provider_manager::{DataProviderHandle, GetDataProviderManager},
+    error::NativeExtensionsResult,
+    log::OkLog,
+    platform_impl::platform::{PlatformDataProvider, PlatformDataReader},
+    reader_manager::{GetDataReaderManager, RegisteredDataReader},
+    util::DropNotifier,
+};
+
+#[cfg(not(target_os = "macos"))]
+use self::unsupported::PlatformServicesManager;
+
+#[derive(TryFromValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct SetServicesContentRequest {
+    provider_ids: Vec<DataProviderId>,
+    /// Formats the application accepts back from services.
+    return_types: Vec<String>,
+}
+
+#[derive(IntoValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum ServicesDataSource {
+    /// Service replaced the advertised content.
+    ReturnedData,
+    /// Application was invoked as a service provider.
+    ServiceRequest,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct ServicesDataEvent {
+    reader: RegisteredDataReader,
+    source: ServicesDataSource,
+    user_data: Option<String>,
+}
+
+pub struct ServicesContent {
+    pub providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+    pub return_types: Vec<String>,
+}
+
+pub trait ServicesManagerDelegate {
+    fn on_services_data(
+        &self,
+        reader: Rc<PlatformDataReader>,
+        source: ServicesDataSource,
+        user_data: Option<String>,
+    );
+}
+
+/// Advertised provider. Dart is asked to release the provider once the
+/// handle is no longer referenced by content nor by the platform.
+#[derive(Clone)]
+struct AdvertisedProvider {
+    id: DataProviderId,
+    provider: Rc<PlatformDataProvider>,
+    handle: Arc<DataProviderHandle>,
+}
+
+pub struct ServicesManager {
+    weak_self: Late<Weak<Self>>,
+    invoker: Late<MethodInvoker>,
+    /// Isolate that last advertised content or enabled the provider. Data
+    /// from services is delivered here.
+    isolate: Cell<Option<IsolateId>>,
+    providers: RefCell<Vec<AdvertisedProvider>>,
+    return_types: RefCell<Vec<String>>,
+    provider_enabled: Cell<bool>,
+    platform_manager: Late<Rc<PlatformServicesManager>>,
+}
+
+pub trait GetServicesManager {
+    fn services_manager(&self) -> Rc<ServicesManager>;
+}
+
+impl GetServicesManager for Context {
+    fn services_manager(&self) -> Rc<ServicesManager> {
+        self.get_attachment(ServicesManager::new).handler()
+    }
+}
+
+impl ServicesManager {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {
+            weak_self: Late::new(),
+            invoker: Late::new(),
+            isolate: Cell::new(None),
+            providers: RefCell::new(Vec::new()),
+            return_types: RefCell::new(Vec::new()),
+            provider_enabled: Cell::new(false),
+            platform_manager: Late::new(),
+        }
+        .register("ServicesManager")
+    }
+
+    fn release_data_provider(&self, isolate_id: IsolateId, provider_id: DataProviderId) {
+        self.invoker
+            .call_method_sync(isolate_id, "releaseDataProvider", provider_id, |r| {
+                r.ok_log();
+            })
+    }
+
+    /// Providers that are advertised already keep their handle, so that
+    /// advertising them again doesn't release them.
+    fn advertised_providers(
+        &self,
+        isolate_id: IsolateId,
+        provider_ids: Vec<DataProviderId>,
+    ) -> NativeExtensionsResult<Vec<AdvertisedProvider>> {
+        let data_provider_manager = Context::get().data_provider_manager();
+        let mut providers = Vec::new();
+        for id in provider_ids {
+            let existing = self.providers.borrow().iter().find(|p| p.id == id).cloned();
+            if let Some(existing) = existing {
+                providers.push(existing);
+                continue;
+            }
+            let provider = data_provider_manager.get_platform_data_provider(id)?;
+            let weak_self = self.weak_self.clone();
+            let notifier = DropNotifier::new(move || {
+                if let Some(this) = weak_self.upgrade() {
+                    this.release_data_provider(isolate_id, id);
+                }
+            });
+            providers.push(AdvertisedProvider {
+                id,
+                provider,
+                handle: Arc::new(notifier.into()),
+            });
+        }
+        Ok(providers)
+    }
+
+    /// Hands current providers and return types to the platform.
+    fn update_content(&self) -> NativeExtensionsResult<()> {
+        let providers = self.providers.borrow();
+        let return_types = self.return_types.borrow();
+        let content = if providers.is_empty() && return_types.is_empty() {
+            None
+        } else {
+            Some(ServicesContent {
+                providers: providers
+                    .iter()
+                    .map(|p| (p.provider.clone(), p.handle.clone()))
+                    .collect(),
+                return_types: return_types.clone(),
+            })
+        };
+        self.platform_manager.set_content(content)
+    }
+
+    fn set_services_content(
+        &self,
+        isolate_id: IsolateId,
+        request: Option<SetServicesContentRequest>,
+    ) -> NativeExtensionsResult<()> {
+        let (providers, return_types) = match request {
+            Some(request) => (
+                self.advertised_providers(isolate_id, request.provider_ids)?,
+                request.return_types,
+            ),
+            None => (Vec::new(), Vec::new()),
+        };
+        let previous = self.providers.replace(providers);
+        self.return_types.replace(return_types);
+        self.update_content()?;
+        self.isolate.set(Some(isolate_id));
+        // Handles of providers no longer advertised are released once the
+        // platform lets go of previous content.
+        drop(previous);
+        Ok(())
+    }
+
+    /// Stops advertising provider disposed by Dart.
+    pub fn provider_unregistered(&self, provider_id: DataProviderId) {
+        let removed = {
+            let mut providers = self.providers.borrow_mut();
+            let len = providers.len();
+            providers.retain(|p| p.id != provider_id);
+            providers.len() != len
+        };
+        if removed {
+            self.update_content().ok_log();
+        }
+    }
+
+    fn set_services_provider_enabled(
+        &self,
+        isolate_id: IsolateId,
+        enabled: bool,
+    ) -> NativeExtensionsResult<()> {
+        self.platform_manager.set_provider_enabled(enabled)?;
+        self.provider_enabled.set(enabled);
+        self.isolate.set(Some(isolate_id));
+        Ok(())
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "setServicesContent" => self
+                .set_services_content(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setServicesProviderEnabled" => self
+                .set_services_provider_enabled(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+impl MethodHandler for ServicesManager {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
+
+    fn assign_invoker(&self, invoker: MethodInvoker) {
+        self.invoker.set(invoker);
+    }
+
+    fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self.clone());
+        let platform_manager = Rc::new(PlatformServicesManager::new(weak_self));
+        platform_manager.assign_weak_self(Rc::downgrade(&platform_manager));
+        self.platform_manager.set(platform_manager);
+    }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        if self.isolate.get() != Some(isolate) {
+            return;
+        }
+        self.isolate.set(None);
+        self.providers.borrow_mut().clear();
+        self.return_types.borrow_mut().clear();
+        self.platform_manager.set_content(None).ok_log();
+        if self.provider_enabled.replace(false) {
+            self.platform_manager.set_provider_enabled(false).ok_log();
+        }
+    }
+}
+
+impl ServicesManagerDelegate for ServicesManager {
+    fn on_services_data(
+        &self,
+        reader: Rc<PlatformDataReader>,
+        source: ServicesDataSource,
+        user_data: Option<String>,
+    ) {
+        let Some(isolate) = self.isolate.get() else {
+            return;
+        };
+        let reader = Context::get()
+            .data_reader_manager()
+            .register_platform_reader(reader, isolate);
+        self.invoker.call_method(
+            isolate,
+            "onServicesData",
+            ServicesDataEvent {
+                reader,
+                source,
+                user_data,
+            },
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod unsupported {
+    use std::rc::Weak;
+
+    use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+    use super::{ServicesContent, ServicesManagerDelegate};
+
+    /// Services menu is specific to macOS.
+    pub struct PlatformServicesManager {}
+
+    impl PlatformServicesManager {
+        pub fn new(_delegate: Weak<dyn ServicesManagerDelegate>) -> Self {
+            Self {}
+        }
+
+        pub fn assign_weak_self(&self, _weak: Weak<PlatformServicesManager>) {}
+
+        pub fn set_content(&self, _content: Option<ServicesContent>) -> NativeExtensionsResult<()> {
+            Err(NativeExtensionsError::UnsupportedOperation)
+        }
+
+        pub fn set_provider_enabled(&self, _enabled: bool) -> NativeExtensionsResult<()> {
+            Err(NativeExtensionsError::UnsupportedOperation)
+        }
+    }
+}
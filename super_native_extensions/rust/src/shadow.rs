@@ -89,6 +89,7 @@ impl WithShadow for TargettedImage {
         TargettedImage {
             image_data,
             rect: self.rect.inflated(radius as f64, radius as f64),
+            shared_texture: None,
         }
     }
 }
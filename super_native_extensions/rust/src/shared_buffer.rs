@@ -0,0 +1,316 @@
+//! Transfer of large byte payloads through memory shared with Dart.
+//!
+//! Sending `Uint8List` through the message channel copies it, so with large
+//! payloads (such as pasted images) both copies are alive at the same time.
+//! Instead, buffers over the threshold configured by the isolate are kept in
+//! native memory and Dart only receives their address:
+//!
+//! - Rust to Dart: payload is replaced with `{__sharedBuffer: true, id,
+//!   address, length}`. Dart immediately wraps the memory in external typed data
+//!   (`Pointer<Uint8>.asTypedList`) with
+//!   `super_native_extensions_shared_buffer_finalize` attached as finalizer
+//!   and buffer id as token, so the buffer lives exactly as long as the view.
+//! - Dart to Rust: Dart allocates buffer through
+//!   `super_native_extensions_shared_buffer_allocate`, fills it and responds
+//!   with `{__sharedBuffer: true, id}` (optionally with `length` if less was
+//!   written). The buffer is then taken over without copying. Only buffers
+//!   allocated by Dart can be taken over; buffers sent to Dart may still back
+//!   a view there.
+//!
+//! References are tagged with `__sharedBuffer` key so that ordinary map
+//! values are never mistaken for them.
+//!
+//! Buffers are released from arbitrary threads (the finalizer runs on the Dart
+//! thread), so the registry is a global mutex. Buffers sent to an isolate
+//! are also released when the isolate is destroyed, in case the reference
+//! never made it to Dart; releasing is idempotent.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    ffi::c_void,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex,
+    },
+};
+
+use irondash_message_channel::{IsolateId, TryFromValue, Value};
+use log::warn;
+
+struct SharedBuffer {
+    data: Vec<u8>,
+    /// Isolate the buffer was sent to. `None` for buffers allocated by Dart.
+    isolate: Option<IsolateId>,
+}
+
+const SHARED_BUFFER_TAG: &str = "__sharedBuffer";
+
+static BUFFERS: Mutex<BTreeMap<i64, SharedBuffer>> = Mutex::new(BTreeMap::new());
+static NEXT_BUFFER_ID: AtomicI64 = AtomicI64::new(1);
+
+thread_local! {
+    static THRESHOLDS: RefCell<HashMap<IsolateId, usize>> = RefCell::new(HashMap::new());
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+pub struct SharedBufferConfiguration {
+    /// Payloads of at least this size are shared. `None` disables sharing.
+    threshold_bytes: Option<i64>,
+}
+
+pub fn configure_shared_buffers(isolate: IsolateId, configuration: SharedBufferConfiguration) {
+    THRESHOLDS.with(|t| {
+        let mut thresholds = t.borrow_mut();
+        match configuration.threshold_bytes {
+            Some(threshold) => thresholds.insert(isolate, threshold.max(0) as usize),
+            None => thresholds.remove(&isolate),
+        }
+    });
+}
+
+/// Removes configuration and releases buffers sent to the isolate.
+pub fn remove_shared_buffer_configuration(isolate: IsolateId) {
+    THRESHOLDS.with(|t| t.borrow_mut().remove(&isolate));
+    BUFFERS
+        .lock()
+        .unwrap()
+        .retain(|_, buffer| buffer.isolate != Some(isolate));
+}
+
+fn register_buffer(mut data: Vec<u8>, isolate: Option<IsolateId>) -> (i64, *mut u8, usize) {
+    let id = NEXT_BUFFER_ID.fetch_add(1, Ordering::Relaxed);
+    let (address, length) = (data.as_mut_ptr(), data.len());
+    // Vector is not resized while registered, so the address stays valid.
+    BUFFERS
+        .lock()
+        .unwrap()
+        .insert(id, SharedBuffer { data, isolate });
+    (id, address, length)
+}
+
+fn release_buffer(id: i64) -> Option<Vec<u8>> {
+    BUFFERS
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .map(|buffer| buffer.data)
+}
+
+/// Removes buffer allocated by Dart. Buffers sent to an isolate are left in
+/// place.
+fn take_dart_buffer(id: i64) -> Result<Vec<u8>, &'static str> {
+    let mut buffers = BUFFERS.lock().unwrap();
+    match buffers.get(&id) {
+        Some(buffer) if buffer.isolate.is_none() => Ok(buffers
+            .remove(&id)
+            .map(|buffer| buffer.data)
+            .unwrap_or_default()),
+        Some(_) => Err("was sent to Dart and can not be taken over"),
+        None => Err("not found"),
+    }
+}
+
+fn shared_buffer_reference_value(id: i64, address: *mut u8, length: usize) -> Value {
+    Value::Map(
+        vec![
+            (Value::String(SHARED_BUFFER_TAG.into()), Value::Bool(true)),
+            (Value::String("id".into()), Value::I64(id)),
+            (Value::String("address".into()), Value::I64(address as i64)),
+            (Value::String("length".into()), Value::I64(length as i64)),
+        ]
+        .into(),
+    )
+}
+
+/// Replaces byte payload at or over the isolate threshold with reference to
+/// shared buffer. Other values are returned unchanged.
+pub fn share_if_large(isolate: IsolateId, value: Value) -> Value {
+    let Some(threshold) = THRESHOLDS.with(|t| t.borrow().get(&isolate).copied()) else {
+        return value;
+    };
+    match value {
+        Value::U8List(data) if data.len() >= threshold => {
+            let (id, address, length) = register_buffer(data, Some(isolate));
+            shared_buffer_reference_value(id, address, length)
+        }
+        value => value,
+    }
+}
+
+/// Buffer id and length of `{__sharedBuffer: true, id, length?}` map.
+fn shared_buffer_reference(value: &Value) -> Option<(i64, Option<i64>)> {
+    let Value::Map(map) = value else {
+        return None;
+    };
+    let mut tagged = false;
+    let mut id = None;
+    let mut length = None;
+    for (key, value) in map.iter() {
+        match (key, value) {
+            (Value::String(key), Value::Bool(true)) if key == SHARED_BUFFER_TAG => tagged = true,
+            (Value::String(key), Value::I64(value)) if key == "id" => id = Some(*value),
+            (Value::String(key), Value::I64(value)) if key == "length" => length = Some(*value),
+            _ => return None,
+        }
+    }
+    if tagged {
+        id.map(|id| (id, length))
+    } else {
+        None
+    }
+}
+
+/// Takes over buffer referenced by value received from Dart. Other values
+/// are returned unchanged.
+pub fn resolve_shared_buffer(value: Value) -> Value {
+    let Some((id, length)) = shared_buffer_reference(&value) else {
+        return value;
+    };
+    match take_dart_buffer(id) {
+        Ok(mut data) => {
+            if let Some(length) = length {
+                data.truncate(length.max(0) as usize);
+            }
+            Value::U8List(data)
+        }
+        Err(reason) => {
+            warn!("Shared buffer {id} {reason}");
+            Value::Null
+        }
+    }
+}
+
+/// Allocates zeroed buffer for Dart to fill. Returns null if `len` is
+/// negative or memory can not be allocated.
+#[no_mangle]
+pub extern "C" fn super_native_extensions_shared_buffer_allocate(
+    len: i64,
+    out_handle: *mut i64,
+) -> *mut u8 {
+    let Ok(len) = usize::try_from(len) else {
+        return std::ptr::null_mut();
+    };
+    if out_handle.is_null() {
+        return std::ptr::null_mut();
+    }
+    let mut data = Vec::new();
+    if data.try_reserve_exact(len).is_err() {
+        warn!("Failed to allocate shared buffer of {len} bytes");
+        return std::ptr::null_mut();
+    }
+    data.resize(len, 0);
+    let (id, address, _) = register_buffer(data, None);
+    unsafe { *out_handle = id };
+    address
+}
+
+/// Releases buffer allocated by Dart that was not handed over.
+#[no_mangle]
+pub extern "C" fn super_native_extensions_shared_buffer_release(handle: i64) {
+    release_buffer(handle);
+}
+
+/// Native finalizer of Dart views of buffers sent to Dart; `token` is the
+/// buffer id. May be invoked on any thread.
+#[no_mangle]
+pub extern "C" fn super_native_extensions_shared_buffer_finalize(token: *mut c_void) {
+    release_buffer(token as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::{IsolateId, Value};
+
+    use super::{
+        configure_shared_buffers, register_buffer, remove_shared_buffer_configuration,
+        resolve_shared_buffer, share_if_large, shared_buffer_reference, SharedBufferConfiguration,
+        BUFFERS, SHARED_BUFFER_TAG,
+    };
+
+    fn reference(id: i64, length: Option<i64>) -> Value {
+        let mut entries = vec![
+            (Value::String(SHARED_BUFFER_TAG.into()), Value::Bool(true)),
+            (Value::String("id".into()), Value::I64(id)),
+        ];
+        if let Some(length) = length {
+            entries.push((Value::String("length".into()), Value::I64(length)));
+        }
+        Value::Map(entries.into())
+    }
+
+    fn is_registered(id: i64) -> bool {
+        BUFFERS.lock().unwrap().contains_key(&id)
+    }
+
+    #[test]
+    fn test_threshold() {
+        let isolate = IsolateId(1001);
+        let data = Value::U8List(vec![1; 10]);
+        // Not configured.
+        assert_eq!(share_if_large(isolate, data.clone()), data);
+        configure_shared_buffers(
+            isolate,
+            SharedBufferConfiguration {
+                threshold_bytes: Some(10),
+            },
+        );
+        let small = Value::U8List(vec![1; 9]);
+        assert_eq!(share_if_large(isolate, small.clone()), small);
+        let shared = share_if_large(isolate, data);
+        let (id, _) = shared_buffer_reference(&shared).unwrap();
+        assert_eq!(BUFFERS.lock().unwrap()[&id].data, vec![1; 10]);
+        remove_shared_buffer_configuration(isolate);
+    }
+
+    #[test]
+    fn test_length_truncation() {
+        let (id, _, _) = register_buffer(vec![1, 2, 3, 4], None);
+        assert_eq!(
+            resolve_shared_buffer(reference(id, Some(2))),
+            Value::U8List(vec![1, 2])
+        );
+        assert!(!is_registered(id));
+        let (id, _, _) = register_buffer(vec![1, 2, 3, 4], None);
+        assert_eq!(
+            resolve_shared_buffer(reference(id, None)),
+            Value::U8List(vec![1, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn test_buffer_sent_to_dart_is_not_taken_over() {
+        let (id, _, _) = register_buffer(vec![1, 2, 3], Some(IsolateId(1002)));
+        assert_eq!(resolve_shared_buffer(reference(id, None)), Value::Null);
+        assert!(is_registered(id));
+        remove_shared_buffer_configuration(IsolateId(1002));
+    }
+
+    #[test]
+    fn test_untagged_map_is_unchanged() {
+        let (id, _, _) = register_buffer(vec![1, 2, 3], None);
+        let value = Value::Map(
+            vec![
+                (Value::String("sharedBuffer".into()), Value::I64(id)),
+                (Value::String("length".into()), Value::I64(1)),
+            ]
+            .into(),
+        );
+        assert_eq!(resolve_shared_buffer(value.clone()), value);
+        assert!(is_registered(id));
+    }
+
+    #[test]
+    fn test_release_on_isolate_removal() {
+        let isolate = IsolateId(1003);
+        let (sent, _, _) = register_buffer(vec![1], Some(isolate));
+        let (other, _, _) = register_buffer(vec![1], Some(IsolateId(1004)));
+        let (allocated, _, _) = register_buffer(vec![1], None);
+        remove_shared_buffer_configuration(isolate);
+        assert!(!is_registered(sent));
+        assert!(is_registered(other));
+        assert!(is_registered(allocated));
+        remove_shared_buffer_configuration(IsolateId(1004));
+    }
+}
@@ -0,0 +1,107 @@
+//! Drag images backed by textures shared with the engine.
+//!
+//! Producing drag image in Dart requires reading the rendered layer back
+//! from GPU and sending the pixels over message channel, which for large
+//! widgets causes noticeable jank when drag starts. Instead Dart can pass
+//! handle of texture the engine already rendered into (see
+//! [`SharedTextureKind`]) and pixels are copied directly on native side.
+
+use crate::{
+    api_model::{DragRequest, ImageData, SharedTexture, SharedTextureKind, TargettedImage},
+    error::NativeExtensionsResult,
+};
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows",
+    target_os = "android"
+))]
+use crate::platform_impl::platform::shared_texture::{
+    image_data_from_shared_texture, SHARED_TEXTURE_KIND,
+};
+
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows",
+    target_os = "android"
+)))]
+const SHARED_TEXTURE_KIND: Option<SharedTextureKind> = None;
+
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows",
+    target_os = "android"
+)))]
+fn image_data_from_shared_texture(_texture: &SharedTexture) -> NativeExtensionsResult<ImageData> {
+    Err(crate::error::NativeExtensionsError::UnsupportedOperation)
+}
+
+/// Texture kind current platform can import, if any.
+pub fn shared_texture_kind() -> Option<SharedTextureKind> {
+    SHARED_TEXTURE_KIND
+}
+
+/// Order of color components in texture memory.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PixelOrder {
+    Rgba,
+    Bgra,
+}
+
+/// Copies mapped texture memory into straight RGBA image data.
+#[allow(dead_code)]
+pub fn image_data_from_pixels(
+    pixels: &[u8],
+    width: i32,
+    height: i32,
+    bytes_per_row: usize,
+    order: PixelOrder,
+    device_pixel_ratio: Option<f64>,
+) -> ImageData {
+    let row_len = width as usize * 4;
+    let mut data = Vec::with_capacity(row_len * height as usize);
+    for row in pixels.chunks(bytes_per_row).take(height as usize) {
+        let row = &row[..row_len];
+        match order {
+            PixelOrder::Rgba => data.extend_from_slice(row),
+            PixelOrder::Bgra => {
+                for pixel in row.chunks_exact(4) {
+                    data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                }
+            }
+        }
+    }
+    ImageData {
+        width,
+        height,
+        bytes_per_row: row_len as i32,
+        data,
+        device_pixel_ratio,
+    }
+}
+
+pub fn resolve_image(image: &mut TargettedImage) -> NativeExtensionsResult<()> {
+    if let Some(texture) = image.shared_texture.take() {
+        image.image_data = image_data_from_shared_texture(&texture)?;
+    }
+    Ok(())
+}
+
+/// Replaces texture backed images in drag request with pixel data so that
+/// platform code only needs to deal with [`ImageData`].
+pub fn resolve_drag_request(request: &mut DragRequest) -> NativeExtensionsResult<()> {
+    for item in request.configuration.items.iter_mut() {
+        resolve_image(&mut item.image)?;
+        if let Some(lift_image) = item.lift_image.as_mut() {
+            resolve_image(lift_image)?;
+        }
+    }
+    if let Some(image) = request.combined_drag_image.as_mut() {
+        resolve_image(image)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Teardown of platform state left behind by an isolate that shuts down
+//! (hot restart, engine destroyed).
+//!
+//! Managers are notified through `on_isolate_destroyed` in no particular
+//! order, but teardown has to happen in dependency order: drag sessions are
+//! cancelled before readers are disposed, and clipboard is flushed while
+//! data providers still exist. Managers taking part call [`shutdown`] first
+//! thing in `on_isolate_destroyed`; the first call runs the whole cascade,
+//! later calls for the same isolate do nothing.
+
+use std::{cell::RefCell, collections::HashSet};
+
+use irondash_message_channel::IsolateId;
+
+use crate::{
+    clipboard_writer::GetClipboardWriter, context::Context, drag_manager::GetDragManager,
+    hot_key_manager::GetHotKeyManager, reader_manager::GetDataReaderManager,
+};
+
+thread_local! {
+    /// Isolate ids are never reused, so finished isolates are kept.
+    static SHUT_DOWN: RefCell<HashSet<IsolateId>> = RefCell::new(HashSet::new());
+}
+
+pub fn shutdown(isolate: IsolateId) {
+    if !SHUT_DOWN.with(|s| s.borrow_mut().insert(isolate)) {
+        return;
+    }
+    let context = Context::get();
+    // No more drop or data requests from sessions of the isolate.
+    context.drag_manager().shutdown(isolate);
+    context.data_reader_manager().shutdown(isolate);
+    context.clipboard_writer().shutdown(isolate);
+    context.hot_key_manager().shutdown(isolate);
+}
@@ -0,0 +1,96 @@
+//! Origin of copied or dragged content.
+//!
+//! Browsers attach the URL of the page (or image) content comes from in
+//! various ways; this collects them so that applications can attribute pasted
+//! content.
+
+use irondash_message_channel::{IntoValue, Value};
+
+use crate::{
+    cf_html::CF_HTML_FORMAT,
+    error::NativeExtensionsResult,
+    platform::PlatformDataReader,
+    value_coerce::string_from_value,
+    web_archive::{decode_web_archive, FORMAT_WEB_ARCHIVE},
+};
+
+/// Chrome on macOS.
+const FORMAT_CHROMIUM_SOURCE_URL: &str = "org.chromium.source-url";
+const FORMAT_URL: &str = "public.url";
+/// CF_HTML on Windows, contains optional SourceURL header.
+const FORMAT_CF_HTML: &str = CF_HTML_FORMAT;
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct SourceUrl {
+    pub url: String,
+    /// Format the URL was extracted from.
+    pub source_format: String,
+}
+
+fn trimmed_string_from_value(value: &Value) -> Option<String> {
+    let string = string_from_value(value)?;
+    let string = string.trim_matches(char::from(0)).trim();
+    if string.is_empty() {
+        None
+    } else {
+        Some(string.to_owned())
+    }
+}
+
+fn url_from_web_archive(archive: &Value) -> Option<String> {
+    decode_web_archive(archive)?.main_resource.url
+}
+
+fn url_from_cf_html(data: &Value) -> Option<String> {
+    let html = trimmed_string_from_value(data)?;
+    // Header ends where HTML starts
+    let header = &html[..html.find('<').unwrap_or(html.len())];
+    header
+        .lines()
+        .find_map(|line| line.strip_prefix("SourceURL:"))
+        .map(|url| url.trim().to_owned())
+        .filter(|url| !url.is_empty())
+}
+
+/// `public.url` is only considered source URL when item has other
+/// content, otherwise the URL itself is the content.
+fn has_non_url_content(formats: &[String]) -> bool {
+    formats.iter().any(|f| {
+        !f.starts_with("public.url") && f != "public.utf8-plain-text" && f != "NSStringPboardType"
+    })
+}
+
+pub async fn read_source_url(
+    reader: &PlatformDataReader,
+    item: i64,
+) -> NativeExtensionsResult<Option<SourceUrl>> {
+    let formats = reader.get_formats_for_item(item).await?;
+    let candidates = [
+        FORMAT_CHROMIUM_SOURCE_URL,
+        FORMAT_WEB_ARCHIVE,
+        FORMAT_URL,
+        FORMAT_CF_HTML,
+    ];
+    for format in candidates {
+        if !formats.iter().any(|f| f == format) {
+            continue;
+        }
+        if format == FORMAT_URL && !has_non_url_content(&formats) {
+            continue;
+        }
+        let data = reader.get_data_for_item(item, format.into(), None).await?;
+        let url = match format {
+            FORMAT_WEB_ARCHIVE => url_from_web_archive(&data),
+            FORMAT_CF_HTML => url_from_cf_html(&data),
+            _ => trimmed_string_from_value(&data),
+        };
+        if let Some(url) = url {
+            return Ok(Some(SourceUrl {
+                url,
+                source_format: format.into(),
+            }));
+        }
+    }
+    Ok(None)
+}
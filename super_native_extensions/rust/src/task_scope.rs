@@ -0,0 +1,216 @@
+//! Ownership of futures spawned by managers.
+//!
+//! Futures spawned through a [`TaskScope`] are only polled while the scope is
+//! open. Closing or dropping the scope wakes all of its pending futures, which
+//! then complete without being polled again and drop everything they
+//! captured. A future spawned for an isolate therefore can not resume (and
+//! call the invoker) after the isolate or the manager is gone.
+//!
+//! Scopes live on the main thread. Task counts of all open scopes are
+//! available through [`task_diagnostics`].
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::{poll_fn, Future},
+    hash::Hash,
+    rc::{Rc, Weak},
+    task::{Poll, Waker},
+};
+
+use irondash_message_channel::IntoValue;
+use irondash_run_loop::spawn;
+
+struct ScopeState {
+    name: &'static str,
+    closed: Cell<bool>,
+    next_task_id: Cell<usize>,
+    /// Pending tasks with waker from their last poll.
+    tasks: RefCell<HashMap<usize, Option<Waker>>>,
+}
+
+impl ScopeState {
+    fn close(&self) {
+        if self.closed.replace(true) {
+            return;
+        }
+        let wakers: Vec<_> = self
+            .tasks
+            .borrow_mut()
+            .drain()
+            .filter_map(|(_, waker)| waker)
+            .collect();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn spawn_or_else<F, A>(self: &Rc<Self>, future: F, on_abort: A)
+    where
+        F: Future<Output = ()> + 'static,
+        A: FnOnce() + 'static,
+    {
+        if self.closed.get() {
+            on_abort();
+            return;
+        }
+        let id = self.next_task_id.get();
+        self.next_task_id.set(id + 1);
+        self.tasks.borrow_mut().insert(id, None);
+        let state = Rc::downgrade(self);
+        spawn(async move {
+            let mut future = Box::pin(future);
+            let completed = poll_fn(|cx| {
+                let Some(state) = state.upgrade() else {
+                    return Poll::Ready(false);
+                };
+                if state.closed.get() {
+                    return Poll::Ready(false);
+                }
+                state
+                    .tasks
+                    .borrow_mut()
+                    .insert(id, Some(cx.waker().clone()));
+                drop(state);
+                future.as_mut().poll(cx).map(|_| true)
+            })
+            .await;
+            // Captured state is released before invoking the abort handler.
+            drop(future);
+            if let Some(state) = state.upgrade() {
+                state.tasks.borrow_mut().remove(&id);
+            }
+            if !completed {
+                on_abort();
+            }
+        });
+    }
+}
+
+thread_local! {
+    static SCOPES: RefCell<Vec<Weak<ScopeState>>> = const { RefCell::new(Vec::new()) };
+}
+
+pub struct TaskScope {
+    state: Rc<ScopeState>,
+}
+
+impl TaskScope {
+    /// `name` identifies the owner in diagnostics.
+    pub fn new(name: &'static str) -> Self {
+        let state = Rc::new(ScopeState {
+            name,
+            closed: Cell::new(false),
+            next_task_id: Cell::new(0),
+            tasks: RefCell::new(HashMap::new()),
+        });
+        SCOPES.with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            scopes.retain(|s| s.strong_count() > 0);
+            scopes.push(Rc::downgrade(&state));
+        });
+        Self { state }
+    }
+
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.spawn_or_else(future, || {});
+    }
+
+    /// Like [`TaskScope::spawn`], but `on_abort` is invoked when the future
+    /// does not run to completion because the scope was closed. Used to
+    /// resolve promises that platform code may be waiting for.
+    pub fn spawn_or_else<F, A>(&self, future: F, on_abort: A)
+    where
+        F: Future<Output = ()> + 'static,
+        A: FnOnce() + 'static,
+    {
+        self.state.spawn_or_else(future, on_abort);
+    }
+}
+
+impl Drop for TaskScope {
+    fn drop(&mut self) {
+        self.state.close();
+    }
+}
+
+/// Task scopes keyed by owner (isolate, reader, session).
+pub struct TaskScopes<K> {
+    name: &'static str,
+    scopes: RefCell<HashMap<K, TaskScope>>,
+}
+
+impl<K: Hash + Eq + Copy> TaskScopes<K> {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            scopes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn spawn<F>(&self, key: K, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.spawn_or_else(key, future, || {});
+    }
+
+    pub fn spawn_or_else<F, A>(&self, key: K, future: F, on_abort: A)
+    where
+        F: Future<Output = ()> + 'static,
+        A: FnOnce() + 'static,
+    {
+        // `on_abort` may run synchronously (and spawn again), so the scope
+        // map must not be borrowed while spawning.
+        let state = self
+            .scopes
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| TaskScope::new(self.name))
+            .state
+            .clone();
+        state.spawn_or_else(future, on_abort);
+    }
+
+    /// Aborts all tasks of the owner.
+    pub fn close(&self, key: K) {
+        let scope = self.scopes.borrow_mut().remove(&key);
+        drop(scope);
+    }
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct TaskScopeDiagnostics {
+    pub name: String,
+    pub scope_count: i64,
+    pub task_count: i64,
+}
+
+/// Number of open scopes and their pending tasks, grouped by scope name.
+pub fn task_diagnostics() -> Vec<TaskScopeDiagnostics> {
+    let mut res = Vec::<TaskScopeDiagnostics>::new();
+    SCOPES.with(|scopes| {
+        for scope in scopes.borrow().iter().filter_map(|s| s.upgrade()) {
+            if scope.closed.get() {
+                continue;
+            }
+            let task_count = scope.tasks.borrow().len() as i64;
+            match res.iter_mut().find(|d| d.name == scope.name) {
+                Some(diagnostics) => {
+                    diagnostics.scope_count += 1;
+                    diagnostics.task_count += task_count;
+                }
+                None => res.push(TaskScopeDiagnostics {
+                    name: scope.name.into(),
+                    scope_count: 1,
+                    task_count,
+                }),
+            }
+        }
+    });
+    res
+}
@@ -0,0 +1,285 @@
+//! Synthesized input for integration tests (`test_injection` feature).
+//!
+//! Drag sessions are simulated by sending the same sequence of events to
+//! Dart that platform drop contexts send for a real drag: an update for each
+//! location, then either perform drop and drop ended, or drop leave. The
+//! view must have a drop target registered and, like the platform would, the
+//! drag is only delivered when the payload contains one of the formats
+//! registered for it. Translating native drag events is not exercised.
+//!
+//! The payload is given as data providers registered by Dart. Their data is
+//! read upfront into an in-memory snapshot that the drop reader serves, so
+//! the system clipboard is left untouched. Virtual files are not simulated.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use async_trait::async_trait;
+use irondash_message_channel::{
+    AsyncMethodHandler, AsyncMethodInvoker, IntoPlatformResult, IntoValue, IsolateId, Late,
+    MethodCall, PlatformError, PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
+};
+use irondash_run_loop::util::FutureCompleter;
+
+use crate::{
+    api_model::{DataProviderId, DataRepresentation, DropOperation, Point},
+    context::Context,
+    data_provider_manager::{GetDataProviderManager, PlatformDataProviderDelegate},
+    drop_manager::{
+        BaseDropEvent, DropEvent, DropItem, DropSessionId, GetDropManager,
+        PlatformDropContextDelegate, PlatformDropContextId,
+    },
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    platform::PlatformDataReader,
+    reader_manager::{GetDataReaderManager, RegisteredDataReader},
+    reader_snapshot::{ReaderSnapshot, SnapshotItem},
+    value_promise::ValuePromiseResult,
+};
+
+pub struct TestInjection {
+    weak_self: Late<Weak<Self>>,
+    /// Simulated sessions use negative ids so that they never collide with
+    /// platform sessions.
+    next_session_id: Cell<i64>,
+}
+
+pub trait GetTestInjection {
+    fn test_injection(&self) -> Rc<TestInjection>;
+}
+
+impl GetTestInjection for Context {
+    fn test_injection(&self) -> Rc<TestInjection> {
+        self.get_attachment(TestInjection::new).handler()
+    }
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SimulateDragRequest {
+    /// Primary view of the isolate if not set.
+    engine_handle: Option<i64>,
+    provider_ids: Vec<DataProviderId>,
+    /// Drag enters at the first location and moves through the rest.
+    locations: Vec<Point>,
+    allowed_operations: Vec<DropOperation>,
+    /// Whether to drop at the last location or leave the view.
+    perform_drop: bool,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct SimulateDragResult {
+    /// `None` if no registered format matched and the drag was not delivered.
+    session_id: Option<DropSessionId>,
+    /// Operation accepted at the last location; `None` if the drag left.
+    operation: DropOperation,
+    dropped: bool,
+}
+
+impl TestInjection {
+    pub fn new() -> RegisteredAsyncMethodHandler<Self> {
+        Self {
+            weak_self: Late::new(),
+            next_session_id: Cell::new(-1),
+        }
+        .register("TestInjection")
+    }
+
+    /// Reads simple and lazy representations of the providers, one item per
+    /// provider.
+    async fn provider_snapshot(
+        provider_ids: &[DataProviderId],
+    ) -> NativeExtensionsResult<ReaderSnapshot> {
+        let manager = Context::get().data_provider_manager();
+        let mut items = Vec::with_capacity(provider_ids.len());
+        for (handle, provider_id) in provider_ids.iter().enumerate() {
+            let (provider, isolate) = manager.get_data_provider(*provider_id)?;
+            let mut formats = Vec::new();
+            let mut data = HashMap::new();
+            for representation in provider.representations {
+                let (format, value) = match representation {
+                    DataRepresentation::Simple { format, data } => (format, data),
+                    DataRepresentation::Lazy { id, format } => {
+                        match manager.get_lazy_data_async(isolate, id).await {
+                            ValuePromiseResult::Ok { value } => (format, value),
+                            ValuePromiseResult::Cancelled => {
+                                return Err(NativeExtensionsError::Cancelled)
+                            }
+                        }
+                    }
+                    _ => continue,
+                };
+                formats.push(format.clone());
+                data.insert(format, value);
+            }
+            items.push(SnapshotItem::new(
+                handle as i64,
+                formats,
+                provider.suggested_name,
+                data,
+            ));
+        }
+        Ok(ReaderSnapshot::from_items(items))
+    }
+
+    fn drop_event(
+        engine_handle: i64,
+        session_id: DropSessionId,
+        request: &SimulateDragRequest,
+        location: &Point,
+        accepted_operation: Option<DropOperation>,
+        snapshot: &ReaderSnapshot,
+        reader: &RegisteredDataReader,
+    ) -> DropEvent {
+        let items = snapshot
+            .items()
+            .into_iter()
+            .filter_map(|handle| snapshot.item(handle))
+            .map(|item| DropItem {
+                item_id: item.handle.into(),
+                formats: item.formats.clone(),
+                local_data: Value::Null,
+                allowed_operations: None,
+            })
+            .collect();
+        DropEvent {
+            session_id,
+            engine_handle,
+            region_id: None,
+            session_local_data: Value::Null,
+            local_session_id: None,
+            location_in_view: location.clone(),
+            allowed_operations: request.allowed_operations.clone(),
+            accepted_operation,
+            items,
+            reader: Some(reader.clone()),
+        }
+    }
+
+    async fn simulate_drag(
+        &self,
+        isolate: IsolateId,
+        request: SimulateDragRequest,
+    ) -> NativeExtensionsResult<SimulateDragResult> {
+        let drop_manager = Context::get().drop_manager();
+        let engine_handle = request
+            .engine_handle
+            .or_else(|| drop_manager.primary_view(isolate))
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        let Some(last_location) = request.locations.last() else {
+            return Err(NativeExtensionsError::OtherError(
+                "Simulated drag needs at least one location".into(),
+            ));
+        };
+        let id = PlatformDropContextId {
+            isolate,
+            engine_handle,
+        };
+        let registered_formats = drop_manager
+            .registered_drop_formats(id)
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+
+        let snapshot = Rc::new(Self::provider_snapshot(&request.provider_ids).await?);
+        let delivered = snapshot.items().into_iter().any(|handle| {
+            snapshot.item(handle).is_some_and(|item| {
+                item.formats
+                    .iter()
+                    .any(|format| registered_formats.contains(format))
+            })
+        });
+        if !delivered {
+            return Ok(SimulateDragResult {
+                session_id: None,
+                operation: DropOperation::None,
+                dropped: false,
+            });
+        }
+        // Formats missing from snapshot are never listed for its items, so
+        // the clipboard reader backing it is not read from.
+        let reader = Context::get()
+            .data_reader_manager()
+            .register_reader_with_snapshot(
+                PlatformDataReader::new_clipboard_reader()?,
+                snapshot.clone(),
+                isolate,
+            );
+
+        let session_id: DropSessionId = self.next_session_id.get().into();
+        self.next_session_id.set(self.next_session_id.get() - 1);
+
+        let mut operation = None;
+        for location in &request.locations {
+            let event = Self::drop_event(
+                engine_handle,
+                session_id,
+                &request,
+                location,
+                operation,
+                &snapshot,
+                &reader,
+            );
+            let (future, completer) = FutureCompleter::new();
+            drop_manager.send_drop_update(id, event, Box::new(move |r| completer.complete(r)));
+            operation = Some(future.await?);
+        }
+        let operation = operation.unwrap_or(DropOperation::None);
+
+        let base_event = BaseDropEvent {
+            session_id,
+            engine_handle,
+        };
+        let dropped = request.perform_drop && operation != DropOperation::None;
+        if dropped {
+            let event = Self::drop_event(
+                engine_handle,
+                session_id,
+                &request,
+                last_location,
+                Some(operation),
+                &snapshot,
+                &reader,
+            );
+            let (future, completer) = FutureCompleter::new();
+            drop_manager.send_perform_drop(id, event, Box::new(move |r| completer.complete(r)));
+            future.await?;
+            drop_manager.send_drop_ended(id, base_event);
+        } else {
+            drop_manager.send_drop_leave(id, base_event);
+        }
+        Ok(SimulateDragResult {
+            session_id: Some(session_id),
+            operation: if dropped {
+                operation
+            } else {
+                DropOperation::None
+            },
+            dropped,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl AsyncMethodHandler for TestInjection {
+    fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self);
+    }
+
+    fn assign_invoker(&self, _invoker: AsyncMethodInvoker) {}
+
+    async fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "simulateDrag" => self
+                .simulate_drag(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Bandwidth limiting for data served to other applications.
+//!
+//! When a limit is set, each virtual file session gets its own token bucket
+//! limiting the session to configured number of bytes per second. On Windows
+//! the consumer reading the stream is paced. On other platforms data is
+//! written directly to destination by the provider, so the writing isolate is
+//! told how long to wait before next write and delays it with a timer.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Bytes per second; zero means unlimited.
+static BANDWIDTH_LIMIT: AtomicI64 = AtomicI64::new(0);
+
+pub fn set_bandwidth_limit(bytes_per_second: Option<i64>) {
+    BANDWIDTH_LIMIT.store(bytes_per_second.unwrap_or(0).max(0), Ordering::Relaxed);
+}
+
+struct ThrottleState {
+    /// Bytes that can be transferred without waiting. Negative when in debt.
+    available: f64,
+    last_refill: Instant,
+}
+
+pub struct Throttle {
+    bytes_per_second: f64,
+    state: Mutex<ThrottleState>,
+}
+
+impl Throttle {
+    /// Returns throttle for new session or `None` if bandwidth is not limited.
+    pub fn for_new_session() -> Option<Self> {
+        let limit = BANDWIDTH_LIMIT.load(Ordering::Relaxed);
+        if limit > 0 {
+            Some(Self::new(limit as f64))
+        } else {
+            None
+        }
+    }
+
+    fn new(bytes_per_second: f64) -> Self {
+        Self {
+            bytes_per_second,
+            state: Mutex::new(ThrottleState {
+                // Allow short initial burst.
+                available: bytes_per_second / 10.0,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Accounts for transferred bytes and returns how long the caller needs
+    /// to wait to stay within the limit.
+    fn reserve(&self, bytes: usize) -> Duration {
+        self.reserve_at(bytes, Instant::now())
+    }
+
+    fn reserve_at(&self, bytes: usize, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        // Bucket capacity is one second worth of data.
+        state.available =
+            (state.available + elapsed * self.bytes_per_second).min(self.bytes_per_second);
+        state.last_refill = now;
+        state.available -= bytes as f64;
+        if state.available < 0.0 {
+            Duration::from_secs_f64(-state.available / self.bytes_per_second)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Accounts for transferred bytes, sleeping current thread if the session
+    /// is over its budget.
+    pub fn consume(&self, bytes: usize) {
+        let wait = self.reserve(bytes);
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+static STREAM_THROTTLES: Mutex<BTreeMap<i32, Option<Throttle>>> = Mutex::new(BTreeMap::new());
+
+/// Accounts for write to virtual file stream and returns how long the writer
+/// must wait before writing more. Throttle is created on first write so that
+/// limit in effect when the session starts applies for its duration.
+pub fn stream_write_delay(handle: i32, bytes: usize) -> Duration {
+    STREAM_THROTTLES
+        .lock()
+        .unwrap()
+        .entry(handle)
+        .or_insert_with(Throttle::for_new_session)
+        .as_ref()
+        .map(|throttle| throttle.reserve(bytes))
+        .unwrap_or_default()
+}
+
+/// Must be called whenever virtual file stream ends, whether it was closed,
+/// failed or cancelled.
+pub fn stream_closed(handle: i32) {
+    STREAM_THROTTLES.lock().unwrap().remove(&handle);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        set_bandwidth_limit, stream_closed, stream_write_delay, Throttle, STREAM_THROTTLES,
+    };
+
+    #[test]
+    fn test_delay() {
+        let throttle = Throttle::new(1000.0);
+        let start = throttle.state.lock().unwrap().last_refill;
+        // Initial burst is 1/10 of a second worth of data.
+        assert_eq!(throttle.reserve_at(100, start), Duration::ZERO);
+        assert_eq!(throttle.reserve_at(500, start), Duration::from_millis(500));
+        // Debt is repaid before more data can be sent.
+        assert_eq!(throttle.reserve_at(500, start), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_refill() {
+        let throttle = Throttle::new(1000.0);
+        let start = throttle.state.lock().unwrap().last_refill;
+        throttle.reserve_at(600, start);
+        // -500 + 1000 after one second.
+        let now = start + Duration::from_secs(1);
+        assert_eq!(throttle.reserve_at(500, now), Duration::ZERO);
+        assert_eq!(throttle.reserve_at(100, now).as_millis(), 100);
+        // Bucket holds at most one second worth of data.
+        let now = now + Duration::from_secs(10);
+        assert_eq!(throttle.reserve_at(1100, now).as_millis(), 100);
+    }
+
+    #[test]
+    fn test_stream_throttles() {
+        set_bandwidth_limit(Some(1000));
+        let handle = -1001;
+        assert_eq!(stream_write_delay(handle, 10), Duration::ZERO);
+        assert!(stream_write_delay(handle, 1000) > Duration::ZERO);
+        set_bandwidth_limit(None);
+        // Limit in effect when the stream started still applies.
+        assert!(stream_write_delay(handle, 1000) > Duration::ZERO);
+        stream_closed(handle);
+        assert!(!STREAM_THROTTLES.lock().unwrap().contains_key(&handle));
+        assert_eq!(stream_write_delay(handle, 1000), Duration::ZERO);
+        stream_closed(handle);
+    }
+}
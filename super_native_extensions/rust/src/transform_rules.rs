@@ -0,0 +1,241 @@
+//! Declarative transformations applied to text read from readers.
+//!
+//! Dart registers an ordered list of rules per isolate. Every rule lists the
+//! formats it applies to; when item data in one of these formats is read the
+//! rules run in order before the value is sent to Dart. Only textual values
+//! (strings or UTF-8 data) are transformed, anything else passes unchanged.
+
+use irondash_message_channel::{TryFromValue, Value};
+use url::Url;
+
+/// Query parameters removed by [`TransformRule::StripUrlTracking`] when no
+/// explicit list is given. Entries ending with `*` match by prefix.
+const DEFAULT_TRACKING_PARAMETERS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "_hsenc",
+    "_hsmi", "yclid", "twclid",
+];
+
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(tag = "type", rename_all = "camelCase")]
+pub enum TransformRule {
+    /// Removes leading and trailing whitespace.
+    #[irondash(rename_all = "camelCase")]
+    TrimWhitespace { formats: Vec<String> },
+    /// Removes tracking query parameters from every line that is an http(s)
+    /// URL.
+    #[irondash(rename_all = "camelCase")]
+    StripUrlTracking {
+        formats: Vec<String>,
+        parameters: Option<Vec<String>>,
+    },
+    /// Converts lines that are absolute Windows paths (`C:\dir\file`) to WSL
+    /// paths (`/mnt/c/dir/file`).
+    #[irondash(rename_all = "camelCase")]
+    WindowsPathToWsl {
+        formats: Vec<String>,
+        mount_root: Option<String>,
+    },
+    /// Replaces every occurrence of `pattern` (matched literally).
+    #[irondash(rename_all = "camelCase")]
+    Replace {
+        formats: Vec<String>,
+        pattern: String,
+        replacement: String,
+    },
+}
+
+impl TransformRule {
+    fn formats(&self) -> &[String] {
+        match self {
+            TransformRule::TrimWhitespace { formats } => formats,
+            TransformRule::StripUrlTracking { formats, .. } => formats,
+            TransformRule::WindowsPathToWsl { formats, .. } => formats,
+            TransformRule::Replace { formats, .. } => formats,
+        }
+    }
+
+    fn apply(&self, text: &str) -> String {
+        match self {
+            TransformRule::TrimWhitespace { .. } => text.trim().into(),
+            TransformRule::StripUrlTracking { parameters, .. } => {
+                let defaults: Vec<String>;
+                let parameters = match parameters {
+                    Some(parameters) => parameters,
+                    None => {
+                        defaults = DEFAULT_TRACKING_PARAMETERS
+                            .iter()
+                            .map(|p| p.to_string())
+                            .collect();
+                        &defaults
+                    }
+                };
+                map_lines(text, |line| strip_url_tracking(line, parameters))
+            }
+            TransformRule::WindowsPathToWsl { mount_root, .. } => {
+                let mount_root = mount_root.as_deref().unwrap_or("/mnt");
+                map_lines(text, |line| windows_path_to_wsl(line, mount_root))
+            }
+            TransformRule::Replace {
+                pattern,
+                replacement,
+                ..
+            } => {
+                if pattern.is_empty() {
+                    text.into()
+                } else {
+                    text.replace(pattern.as_str(), replacement)
+                }
+            }
+        }
+    }
+}
+
+/// Applies `f` to each line, preserving line endings.
+fn map_lines<F: Fn(&str) -> Option<String>>(text: &str, f: F) -> String {
+    let mut res = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[content.len()..];
+        match f(content.trim()) {
+            Some(replacement) => res.push_str(&replacement),
+            None => res.push_str(content),
+        }
+        res.push_str(ending);
+    }
+    res
+}
+
+fn parameter_matches(name: &str, patterns: &[String]) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        })
+}
+
+fn strip_url_tracking(line: &str, parameters: &[String]) -> Option<String> {
+    let mut url = Url::parse(line).ok()?;
+    if !matches!(url.scheme(), "http" | "https") || url.query().is_none() {
+        return None;
+    }
+    let retained: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !parameter_matches(name, parameters))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if retained.len() == url.query_pairs().count() {
+        return None;
+    }
+    if retained.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(retained);
+    }
+    Some(url.into())
+}
+
+fn windows_path_to_wsl(line: &str, mount_root: &str) -> Option<String> {
+    let mut chars = line.chars();
+    let drive = chars.next().filter(|c| c.is_ascii_alphabetic())?;
+    if chars.next() != Some(':') || !matches!(chars.next(), Some('\\') | Some('/')) {
+        return None;
+    }
+    let rest = line[3..].replace('\\', "/");
+    Some(format!(
+        "{}/{}/{}",
+        mount_root.trim_end_matches('/'),
+        drive.to_ascii_lowercase(),
+        rest
+    ))
+}
+
+#[derive(Default)]
+pub struct TransformRules {
+    rules: Vec<TransformRule>,
+}
+
+impl TransformRules {
+    pub fn new(rules: Vec<TransformRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Whether any rule transforms values in `format`.
+    pub fn applies_to(&self, format: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|r| r.formats().iter().any(|f| f == format))
+    }
+
+    fn transform_text(&self, format: &str, text: &str) -> Option<String> {
+        let mut res: Option<String> = None;
+        for rule in self
+            .rules
+            .iter()
+            .filter(|r| r.formats().iter().any(|f| f == format))
+        {
+            res = Some(rule.apply(res.as_deref().unwrap_or(text)));
+        }
+        res
+    }
+
+    /// Applies rules registered for `format` to the value.
+    pub fn apply(&self, format: &str, value: Value) -> Value {
+        match value {
+            Value::String(text) => match self.transform_text(format, &text) {
+                Some(text) => Value::String(text),
+                None => Value::String(text),
+            },
+            Value::U8List(data) => {
+                let transformed = std::str::from_utf8(&data)
+                    .ok()
+                    .and_then(|text| self.transform_text(format, text));
+                match transformed {
+                    Some(text) => Value::U8List(text.into_bytes()),
+                    None => Value::U8List(data),
+                }
+            }
+            value => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use super::{TransformRule, TransformRules};
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let formats = vec!["text/plain".to_string()];
+        let rules = TransformRules::new(vec![
+            TransformRule::TrimWhitespace {
+                formats: formats.clone(),
+            },
+            TransformRule::StripUrlTracking {
+                formats: formats.clone(),
+                parameters: None,
+            },
+            TransformRule::WindowsPathToWsl {
+                formats,
+                mount_root: None,
+            },
+        ]);
+        let value = Value::String("  https://example.com/a?id=1&utm_source=x&fbclid=y \n".into());
+        assert_eq!(
+            rules.apply("text/plain", value),
+            Value::String("https://example.com/a?id=1".into())
+        );
+        let value = Value::U8List(b"C:\\Users\\me\\file.txt".to_vec());
+        assert_eq!(
+            rules.apply("text/plain", value),
+            Value::U8List(b"/mnt/c/Users/me/file.txt".to_vec())
+        );
+        let value = Value::String(" untouched ".into());
+        assert_eq!(
+            rules.apply("text/html", value),
+            Value::String(" untouched ".into())
+        );
+    }
+}
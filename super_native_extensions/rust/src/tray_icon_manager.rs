@@ -0,0 +1,238 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use irondash_message_channel::{
+    IntoPlatformResult, IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler,
+    MethodInvoker, PlatformError, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
+};
+use irondash_run_loop::spawn;
+
+use crate::{
+    api_model::{DesktopFeatureCapability, ImageData, Point},
+    context::Context,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    platform_impl::platform::PlatformTrayIconManager,
+    util::NextId,
+};
+
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct TrayIconCreateRequest {
+    pub image: ImageData,
+    pub tooltip: Option<String>,
+    /// When set the platform tooltip is not shown; instead `tooltipRequested`
+    /// event is sent when pointer hovers over the icon so that application
+    /// can present its own.
+    pub custom_tooltip: bool,
+}
+
+#[derive(TryFromValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct TrayIconUpdateRequest {
+    handle: TrayIconHandle,
+    image: Option<ImageData>,
+    tooltip: Option<String>,
+}
+
+#[derive(TryFromValue, Debug)]
+struct TrayIconDestroyRequest {
+    handle: TrayIconHandle,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, IntoValue, TryFromValue)]
+pub struct TrayIconHandle(i64);
+
+#[derive(IntoValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum TrayIconEventType {
+    LeftClick,
+    RightClick,
+    MiddleClick,
+    DoubleClick,
+    TooltipRequested,
+}
+
+#[derive(IntoValue)]
+#[irondash(rename_all = "camelCase")]
+struct TrayIconEvent {
+    handle: TrayIconHandle,
+    event_type: TrayIconEventType,
+    /// Pointer location in screen coordinates.
+    position: Point,
+}
+
+pub struct TrayIconManager {
+    invoker: Late<MethodInvoker>,
+    handle_to_isolate: RefCell<HashMap<TrayIconHandle, IsolateId>>,
+    next_id: Cell<i64>,
+    platform_manager: Late<Rc<PlatformTrayIconManager>>,
+    weak_self: Late<Weak<Self>>,
+}
+
+pub trait TrayIconManagerDelegate {
+    fn on_tray_icon_event(
+        &self,
+        handle: TrayIconHandle,
+        event_type: TrayIconEventType,
+        position: Point,
+    );
+}
+
+pub trait GetTrayIconManager {
+    fn tray_icon_manager(&self) -> Rc<TrayIconManager>;
+}
+
+impl GetTrayIconManager for Context {
+    fn tray_icon_manager(&self) -> Rc<TrayIconManager> {
+        self.get_attachment(TrayIconManager::new).handler()
+    }
+}
+
+impl TrayIconManager {
+    pub fn new() -> RegisteredMethodHandler<Self> {
+        Self {
+            invoker: Late::new(),
+            handle_to_isolate: RefCell::new(HashMap::new()),
+            next_id: Cell::new(1),
+            platform_manager: Late::new(),
+            weak_self: Late::new(),
+        }
+        .register("TrayIconManager")
+    }
+
+    async fn create_tray_icon(
+        &self,
+        isolate_id: IsolateId,
+        request: TrayIconCreateRequest,
+    ) -> NativeExtensionsResult<Option<TrayIconHandle>> {
+        let handle = TrayIconHandle(self.next_id.next_id());
+        let res = self
+            .platform_manager
+            .create_tray_icon(handle, request)
+            .await;
+        if let Err(NativeExtensionsError::UnsupportedOperation) = res {
+            return Ok(None);
+        }
+        res?;
+        self.handle_to_isolate
+            .borrow_mut()
+            .insert(handle, isolate_id);
+        Ok(Some(handle))
+    }
+
+    fn update_tray_icon(&self, request: TrayIconUpdateRequest) -> NativeExtensionsResult<()> {
+        self.platform_manager
+            .update_tray_icon(request.handle, request.image, request.tooltip)
+    }
+
+    fn destroy_tray_icon(&self, request: TrayIconDestroyRequest) -> NativeExtensionsResult<()> {
+        self.handle_to_isolate.borrow_mut().remove(&request.handle);
+        self.platform_manager.destroy_tray_icon(request.handle)
+    }
+
+    /// Methods that may need to query the session bus on Linux.
+    async fn on_async_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "createTrayIcon" => self
+                .create_tray_icon(call.isolate, call.args.try_into()?)
+                .await
+                .into_platform_result(),
+            "getCapability" => tray_capability().await.into_platform_result(),
+            _ => self.on_method_call(call),
+        }
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
+        match call.method.as_str() {
+            "updateTrayIcon" => self
+                .update_tray_icon(call.args.try_into()?)
+                .into_platform_result(),
+            "destroyTrayIcon" => self
+                .destroy_tray_icon(call.args.try_into()?)
+                .into_platform_result(),
+            _ => Err(PlatformError {
+                code: "invalid_method".into(),
+                message: Some(format!("Unknown Method: {}", call.method)),
+                detail: Value::Null,
+            }),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn tray_capability() -> DesktopFeatureCapability {
+    crate::platform_impl::platform::tray_capability().await
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+async fn tray_capability() -> DesktopFeatureCapability {
+    DesktopFeatureCapability::supported(crate::api_model::DesktopFeatureBackend::Native)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+async fn tray_capability() -> DesktopFeatureCapability {
+    DesktopFeatureCapability::unsupported("Tray icons are not supported on this platform")
+}
+
+impl MethodHandler for TrayIconManager {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        let weak_self = self.weak_self.clone();
+        spawn(async move {
+            if let Some(this) = weak_self.upgrade() {
+                reply.send(this.on_async_method_call(call).await);
+            }
+        });
+    }
+
+    fn assign_invoker(&self, invoker: MethodInvoker) {
+        self.invoker.set(invoker);
+    }
+
+    fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self.clone());
+        let platform_manager = Rc::new(PlatformTrayIconManager::new(weak_self));
+        platform_manager.assign_weak_self(Rc::downgrade(&platform_manager));
+        self.platform_manager.set(platform_manager);
+    }
+
+    fn on_isolate_destroyed(&self, isolate: IsolateId) {
+        let handles = self
+            .handle_to_isolate
+            .borrow()
+            .iter()
+            .filter_map(|(handle, id)| if *id == isolate { Some(*handle) } else { None })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            self.handle_to_isolate.borrow_mut().remove(&handle);
+            self.platform_manager.destroy_tray_icon(handle).ok_log();
+        }
+    }
+}
+
+impl TrayIconManagerDelegate for TrayIconManager {
+    fn on_tray_icon_event(
+        &self,
+        handle: TrayIconHandle,
+        event_type: TrayIconEventType,
+        position: Point,
+    ) {
+        if let Some(isolate) = self.handle_to_isolate.borrow().get(&handle) {
+            self.invoker.call_method(
+                *isolate,
+                "onTrayIconEvent",
+                TrayIconEvent {
+                    handle,
+                    event_type,
+                    position,
+                },
+                |r| {
+                    r.ok_log();
+                },
+            );
+        }
+    }
+}
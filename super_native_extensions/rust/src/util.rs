@@ -59,8 +59,59 @@ impl NextId for Cell<i64> {
     }
 }
 
+/// Last path component of `file_name`, interpreting both `/` and `\\` as
+/// separators. Returns `None` for names that don't denote a file (empty, `.`
+/// or `..`).
+pub fn sanitize_file_name(file_name: &str) -> Option<&str> {
+    let name = file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    (!name.is_empty() && name != "." && name != "..").then_some(name)
+}
+
+/// Single path component usable as file or folder name on all platforms.
+/// Characters reserved on Windows and control characters are replaced with
+/// `_`, trailing dots and spaces are removed and reserved device names (such
+/// as `CON` or `LPT1`) are prefixed with `_`. Returns `None` for components
+/// that don't denote a file (empty, `.` or `..`).
+pub fn sanitize_path_component(component: &str) -> Option<String> {
+    let component = component.trim();
+    if component.is_empty() || component == "." || component == ".." {
+        return None;
+    }
+    let mut res: String = component
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    res.truncate(res.trim_end_matches(['.', ' ']).len());
+    if res.is_empty() {
+        return None;
+    }
+    let stem = res.split('.').next().unwrap_or_default().trim_end();
+    let reserved = ["CON", "PRN", "AUX", "NUL"]
+        .iter()
+        .any(|r| stem.eq_ignore_ascii_case(r))
+        || matches!(stem.as_bytes(), [a, b, c, b'1'..=b'9']
+            if [*a, *b, *c].eq_ignore_ascii_case(b"COM") || [*a, *b, *c].eq_ignore_ascii_case(b"LPT"));
+    if reserved {
+        res.insert(0, '_');
+    }
+    Some(res)
+}
+
+/// Path for a new file named `file_name` in `target_folder`. The name is
+/// stripped of path components so that the result never points outside the
+/// folder; names that can not be used are replaced with `file`. Existing
+/// files are not overwritten, a number is appended to the name instead.
 #[allow(dead_code)]
 pub fn get_target_path(target_folder: &Path, file_name: &str) -> PathBuf {
+    let file_name = sanitize_file_name(file_name).unwrap_or("file");
     let target_path = target_folder.join(file_name);
     if !target_path.exists() {
         target_path
@@ -69,8 +120,8 @@ pub fn get_target_path(target_folder: &Path, file_name: &str) -> PathBuf {
         let source_path = Path::new(file_name);
         let stem = source_path
             .file_stem()
-            .expect("Couldn't get file stem")
-            .to_string_lossy();
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or(file_name.into());
         let extension = source_path.extension();
         let suffix = extension
             .map(|a| format!(".{}", a.to_string_lossy()))
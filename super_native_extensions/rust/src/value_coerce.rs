@@ -46,6 +46,15 @@ impl CoerceToData for Value {
     }
 }
 
+/// Text of string value, or of UTF-8 encoded data.
+pub fn string_from_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::U8List(data) => Some(String::from_utf8_lossy(data).into_owned()),
+        _ => None,
+    }
+}
+
 unsafe fn transform_slice<T>(s: &[T]) -> &[u8] {
     std::slice::from_raw_parts(s.as_ptr() as *const u8, std::mem::size_of_val(s))
 }
@@ -0,0 +1,216 @@
+//! Decoder for `com.apple.webarchive` (Safari) pasteboard content.
+//!
+//! Pasteboard readers on Apple platforms decode the archive property list
+//! into a map, from which the main resource and subresources (images,
+//! stylesheets, subframes) are extracted here.
+
+use irondash_message_channel::{IntoValue, Value};
+
+use crate::{error::NativeExtensionsResult, platform::PlatformDataReader};
+
+pub const FORMAT_WEB_ARCHIVE: &str = "com.apple.webarchive";
+
+#[derive(IntoValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct WebResource {
+    pub url: Option<String>,
+    pub mime_type: Option<String>,
+    pub text_encoding: Option<String>,
+    pub data: Value,
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct WebArchive {
+    pub main_resource: WebResource,
+    /// Subresources of main resource followed by resources of subframes.
+    pub subresources: Vec<WebResource>,
+}
+
+/// Looks up value for key in a decoded property list dictionary.
+pub fn map_get<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Map(map) => map
+            .iter()
+            .find(|(k, _)| matches!(k, Value::String(k) if k == key))
+            .map(|(_, v)| v),
+        _ => None,
+    }
+}
+
+fn string_for_key(value: &Value, key: &str) -> Option<String> {
+    match map_get(value, key)? {
+        Value::String(string) => Some(string.clone()),
+        _ => None,
+    }
+}
+
+fn decode_resource(value: &Value) -> Option<WebResource> {
+    let data = match map_get(value, "WebResourceData")? {
+        Value::U8List(data) => Value::U8List(data.clone()),
+        Value::String(string) => Value::U8List(string.as_bytes().to_vec()),
+        _ => return None,
+    };
+    Some(WebResource {
+        url: string_for_key(value, "WebResourceURL"),
+        mime_type: string_for_key(value, "WebResourceMIMEType"),
+        text_encoding: string_for_key(value, "WebResourceTextEncodingName"),
+        data,
+    })
+}
+
+fn collect_subresources(archive: &Value, res: &mut Vec<WebResource>) {
+    if let Some(Value::List(resources)) = map_get(archive, "WebSubresources") {
+        res.extend(resources.iter().filter_map(decode_resource));
+    }
+    if let Some(Value::List(frames)) = map_get(archive, "WebSubframeArchives") {
+        for frame in frames {
+            if let Some(main) = map_get(frame, "WebMainResource").and_then(decode_resource) {
+                res.push(main);
+            }
+            collect_subresources(frame, res);
+        }
+    }
+}
+
+/// Decodes archive from its property list representation. Returns None if
+/// value is not a decoded web archive.
+pub fn decode_web_archive(archive: &Value) -> Option<WebArchive> {
+    let main_resource = decode_resource(map_get(archive, "WebMainResource")?)?;
+    let mut subresources = Vec::new();
+    collect_subresources(archive, &mut subresources);
+    Some(WebArchive {
+        main_resource,
+        subresources,
+    })
+}
+
+pub async fn read_web_archive(
+    reader: &PlatformDataReader,
+    item: i64,
+) -> NativeExtensionsResult<Option<WebArchive>> {
+    let formats = reader.get_formats_for_item(item).await?;
+    if !formats.iter().any(|f| f == FORMAT_WEB_ARCHIVE) {
+        return Ok(None);
+    }
+    let data = reader
+        .get_data_for_item(item, FORMAT_WEB_ARCHIVE.into(), None)
+        .await?;
+    Ok(decode_web_archive(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use irondash_message_channel::Value;
+
+    use super::decode_web_archive;
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (Value::String(k.into()), v))
+                .collect::<Vec<_>>()
+                .into(),
+        )
+    }
+
+    fn resource(url: &str, data: Value) -> Value {
+        map(vec![
+            ("WebResourceURL", Value::String(url.into())),
+            ("WebResourceMIMEType", Value::String("text/html".into())),
+            ("WebResourceData", data),
+        ])
+    }
+
+    #[test]
+    fn test_decode_web_archive() {
+        let archive = map(vec![
+            (
+                "WebMainResource",
+                resource("https://a.com", Value::U8List(b"<p>a</p>".to_vec())),
+            ),
+            (
+                "WebSubresources",
+                Value::List(vec![resource(
+                    "https://a.com/img.png",
+                    Value::U8List(vec![1, 2, 3]),
+                )]),
+            ),
+            (
+                "WebSubframeArchives",
+                Value::List(vec![map(vec![
+                    (
+                        "WebMainResource",
+                        resource("https://b.com", Value::String("<p>b</p>".into())),
+                    ),
+                    (
+                        "WebSubresources",
+                        Value::List(vec![resource(
+                            "https://b.com/style.css",
+                            Value::U8List(vec![4]),
+                        )]),
+                    ),
+                ])]),
+            ),
+        ]);
+        let archive = decode_web_archive(&archive).unwrap();
+        assert_eq!(archive.main_resource.url.as_deref(), Some("https://a.com"));
+        assert_eq!(
+            archive.main_resource.mime_type.as_deref(),
+            Some("text/html")
+        );
+        assert_eq!(archive.main_resource.text_encoding, None);
+        assert_eq!(
+            archive.main_resource.data,
+            Value::U8List(b"<p>a</p>".to_vec())
+        );
+        let urls: Vec<_> = archive
+            .subresources
+            .iter()
+            .map(|r| r.url.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.com/img.png",
+                "https://b.com",
+                "https://b.com/style.css"
+            ]
+        );
+        // String data is converted to bytes.
+        assert_eq!(
+            archive.subresources[1].data,
+            Value::U8List(b"<p>b</p>".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        assert!(decode_web_archive(&Value::Null).is_none());
+        assert!(decode_web_archive(&Value::U8List(b"bplist00".to_vec())).is_none());
+        // Main resource without data.
+        let archive = map(vec![(
+            "WebMainResource",
+            map(vec![(
+                "WebResourceURL",
+                Value::String("https://a.com".into()),
+            )]),
+        )]);
+        assert!(decode_web_archive(&archive).is_none());
+        // Invalid subresources are skipped.
+        let archive = map(vec![
+            (
+                "WebMainResource",
+                resource("https://a.com", Value::U8List(vec![])),
+            ),
+            (
+                "WebSubresources",
+                Value::List(vec![Value::I64(1), resource("x", Value::I64(2))]),
+            ),
+            ("WebSubframeArchives", Value::String("invalid".into())),
+        ]);
+        let archive = decode_web_archive(&archive).unwrap();
+        assert!(archive.subresources.is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+//! Optional out-of-process access to data objects of other applications.
+//!
+//! When enabled, `IDataObject` instances received from drops or read from
+//! the clipboard are marshalled to a helper process, which does the actual
+//! `EnumFormatEtc` / `GetData` calls and streams the results back. A crashing
+//! or hanging shell extension in the source application can then only take
+//! down the helper, which gets killed after a timeout. The helper is
+//! `rundll32.exe` hosting this library (see [`SneBrokerMain`]), talking to us
+//! over its standard input and output.
+
+use std::{
+    ffi::c_void,
+    io::{self, Read, Write},
+    os::windows::process::CommandExt,
+    path::PathBuf,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+use windows::{
+    core::{ComInterface, HRESULT, PCSTR},
+    Win32::{
+        Foundation::{DV_E_FORMATETC, HINSTANCE, HMODULE, HWND, MAX_PATH},
+        System::{
+            Com::{
+                IDataObject,
+                Marshal::{CoMarshalInterface, CoUnmarshalInterface},
+                FORMATETC, MSHCTX_LOCAL, MSHLFLAGS_NORMAL, STREAM_SEEK_SET, TYMED,
+            },
+            LibraryLoader::{
+                GetModuleFileNameW, GetModuleHandleExW, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+                GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            },
+            Ole::OleGetClipboard,
+            Threading::CREATE_NO_WINDOW,
+        },
+        UI::Shell::SHCreateMemStream,
+    },
+};
+
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+};
+
+use super::{
+    common::{extract_formats, make_format_with_tymed, read_stream_fully, run_on_worker_thread},
+    data_object::GetData,
+    remote_session, OleInitializer,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+const OP_OPEN_DATA_OBJECT: u8 = 1;
+const OP_OPEN_CLIPBOARD: u8 = 2;
+const OP_GET_FORMATS: u8 = 3;
+const OP_GET_DATA: u8 = 4;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+
+fn write_message<W: Write>(w: &mut W, data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_le_bytes())?;
+    w.write_all(data)?;
+    w.flush()
+}
+
+fn read_message<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len)?;
+    let mut data = vec![0u8; u32::from_le_bytes(len) as usize];
+    r.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+type Reply = io::Result<Vec<u8>>;
+
+/// Sending side of helper process requests. Can be moved to worker thread
+/// in order not to block the caller while waiting for reply.
+#[derive(Clone)]
+struct BrokerChannel {
+    requests: mpsc::Sender<(Vec<u8>, mpsc::Sender<Reply>)>,
+    child: Arc<Mutex<Child>>,
+}
+
+/// Helper process serving single data object.
+pub struct BrokerSession {
+    channel: BrokerChannel,
+}
+
+impl BrokerSession {
+    fn launch() -> NativeExtensionsResult<Self> {
+        let library = library_path()?;
+        let mut child = Command::new("rundll32.exe")
+            .arg(format!("{},SneBrokerMain", library.to_string_lossy()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .creation_flags(CREATE_NO_WINDOW.0)
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or_else(|| {
+            NativeExtensionsError::OtherError("Broker process is missing stdin".into())
+        })?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            NativeExtensionsError::OtherError("Broker process is missing stdout".into())
+        })?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || Self::run_requests(stdin, stdout, receiver));
+        Ok(Self {
+            channel: BrokerChannel {
+                requests: sender,
+                child: Arc::new(Mutex::new(child)),
+            },
+        })
+    }
+
+    fn run_requests(
+        mut stdin: ChildStdin,
+        mut stdout: ChildStdout,
+        receiver: mpsc::Receiver<(Vec<u8>, mpsc::Sender<Reply>)>,
+    ) {
+        while let Ok((request, reply)) = receiver.recv() {
+            let res = write_message(&mut stdin, &request).and_then(|_| read_message(&mut stdout));
+            let failed = res.is_err();
+            let _ = reply.send(res);
+            if failed {
+                break;
+            }
+        }
+    }
+
+    /// Creates helper process for data object from another application.
+    pub fn new_for_data_object(data_object: &IDataObject) -> NativeExtensionsResult<Self> {
+        let marshalled = marshal_data_object(data_object)?;
+        let session = Self::launch()?;
+        let mut request = vec![OP_OPEN_DATA_OBJECT];
+        request.extend_from_slice(&marshalled);
+        session.request(request)?;
+        Ok(session)
+    }
+
+    /// Creates helper process that reads current clipboard contents.
+    pub fn new_for_clipboard() -> NativeExtensionsResult<Self> {
+        let session = Self::launch()?;
+        session.request(vec![OP_OPEN_CLIPBOARD])?;
+        Ok(session)
+    }
+
+    fn request(&self, request: Vec<u8>) -> NativeExtensionsResult<Vec<u8>> {
+        self.channel.request(request)
+    }
+
+    /// Formats (and their tymed) provided by the data object.
+    pub fn get_formats(&self) -> NativeExtensionsResult<Vec<FORMATETC>> {
+        let payload = self.request(vec![OP_GET_FORMATS])?;
+        Ok(payload
+            .chunks_exact(8)
+            .map(|c| {
+                let format = read_u32(c, 0).unwrap();
+                let tymed = TYMED(read_u32(c, 4).unwrap() as i32);
+                make_format_with_tymed(format, tymed)
+            })
+            .collect())
+    }
+
+    pub fn get_data(&self, format: u32) -> NativeExtensionsResult<Vec<u8>> {
+        self.channel.get_data(format)
+    }
+
+    /// Same as [`BrokerSession::get_data`], but waits for the helper on
+    /// worker thread instead of blocking the caller for up to
+    /// [`BrokerChannel::REQUEST_TIMEOUT`].
+    pub async fn get_data_async(&self, format: u32) -> NativeExtensionsResult<Vec<u8>> {
+        let channel = self.channel.clone();
+        run_on_worker_thread(move || channel.get_data(format)).await
+    }
+}
+
+impl BrokerChannel {
+    /// Maximum time a single request may take before the helper is considered
+    /// hung and gets killed.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn request(&self, request: Vec<u8>) -> NativeExtensionsResult<Vec<u8>> {
+        let (reply_sender, reply_receiver) = mpsc::channel();
+        self.requests
+            .send((request, reply_sender))
+            .map_err(|_| NativeExtensionsError::OtherError("Broker process is gone".into()))?;
+        let timeout = remote_session::request_timeout(Self::REQUEST_TIMEOUT);
+        let reply = match reply_receiver.recv_timeout(timeout) {
+            Ok(reply) => reply?,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                self.kill();
+                return Err(NativeExtensionsError::SourceAppUnresponsive(Some(
+                    "Broker process timed out".into(),
+                )));
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(NativeExtensionsError::OtherError(
+                    "Broker process is gone".into(),
+                ));
+            }
+        };
+        match reply.split_first() {
+            Some((&STATUS_OK, payload)) => Ok(payload.into()),
+            Some((&STATUS_ERROR, payload)) => {
+                let code = read_u32(payload, 0).unwrap_or(0);
+                Err(windows::core::Error::from(HRESULT(code as i32)).into())
+            }
+            _ => Err(NativeExtensionsError::OtherError(
+                "Malformed broker reply".into(),
+            )),
+        }
+    }
+
+    fn get_data(&self, format: u32) -> NativeExtensionsResult<Vec<u8>> {
+        let mut request = vec![OP_GET_DATA];
+        request.extend_from_slice(&format.to_le_bytes());
+        self.request(request)
+    }
+
+    fn child(&self) -> MutexGuard<Child> {
+        // Child is only killed while locked, poisoned lock leaves it usable.
+        self.child.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn kill(&self) {
+        self.child().kill().ok_log();
+    }
+}
+
+impl Drop for BrokerSession {
+    fn drop(&mut self) {
+        // Helper exits when its stdin is closed, but make sure that hung
+        // helper doesn't linger around.
+        let _ = self.channel.child().kill();
+    }
+}
+
+fn marshal_data_object(data_object: &IDataObject) -> NativeExtensionsResult<Vec<u8>> {
+    unsafe {
+        let stream = SHCreateMemStream(None).ok_or_else(|| {
+            NativeExtensionsError::OtherError("Failed to create memory stream".into())
+        })?;
+        CoMarshalInterface(
+            &stream,
+            &IDataObject::IID,
+            data_object,
+            MSHCTX_LOCAL.0 as u32,
+            None,
+            MSHLFLAGS_NORMAL.0 as u32,
+        )?;
+        stream.Seek(0, STREAM_SEEK_SET, None)?;
+        Ok(read_stream_fully(&stream)?)
+    }
+}
+
+/// Path of this library, to be loaded by the helper process.
+fn library_path() -> NativeExtensionsResult<PathBuf> {
+    let mut module = HMODULE::default();
+    unsafe {
+        GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            windows::core::PCWSTR(library_path as *const c_void as *const u16),
+            &mut module as *mut _,
+        )?;
+    }
+    let mut buf = [0u16; MAX_PATH as usize];
+    let len = unsafe { GetModuleFileNameW(module, &mut buf) } as usize;
+    if len == 0 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    Ok(String::from_utf16_lossy(&buf[..len]).into())
+}
+
+/// Entry point of the helper process, invoked through rundll32.
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn SneBrokerMain(
+    _hwnd: HWND,
+    _instance: HINSTANCE,
+    _cmd_line: PCSTR,
+    _show: i32,
+) {
+    let _ole = OleInitializer::new();
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    let mut data_object = None::<IDataObject>;
+    // Exits when parent process closes the pipe
+    while let Ok(request) = read_message(&mut stdin) {
+        let reply = match handle_request(&request, &mut data_object) {
+            Ok(payload) => {
+                let mut reply = vec![STATUS_OK];
+                reply.extend_from_slice(&payload);
+                reply
+            }
+            Err(error) => {
+                let mut reply = vec![STATUS_ERROR];
+                reply.extend_from_slice(&(error.code().0 as u32).to_le_bytes());
+                reply
+            }
+        };
+        if write_message(&mut stdout, &reply).is_err() {
+            break;
+        }
+    }
+}
+
+fn handle_request(
+    request: &[u8],
+    data_object: &mut Option<IDataObject>,
+) -> windows::core::Result<Vec<u8>> {
+    let no_data_object = || windows::core::Error::from(DV_E_FORMATETC);
+    match request.split_first() {
+        Some((&OP_OPEN_DATA_OBJECT, payload)) => {
+            let stream = unsafe { SHCreateMemStream(Some(payload)) }.ok_or_else(no_data_object)?;
+            *data_object = Some(unsafe { CoUnmarshalInterface(&stream) }?);
+            Ok(Vec::new())
+        }
+        Some((&OP_OPEN_CLIPBOARD, _)) => {
+            *data_object = Some(unsafe { OleGetClipboard() }?);
+            Ok(Vec::new())
+        }
+        Some((&OP_GET_FORMATS, _)) => {
+            let data_object = data_object.as_ref().ok_or_else(no_data_object)?;
+            let mut res = Vec::new();
+            for format in extract_formats(data_object)? {
+                res.extend_from_slice(&(format.cfFormat as u32).to_le_bytes());
+                res.extend_from_slice(&format.tymed.to_le_bytes());
+            }
+            Ok(res)
+        }
+        Some((&OP_GET_DATA, payload)) => {
+            let data_object = data_object.as_ref().ok_or_else(no_data_object)?;
+            let format = read_u32(payload, 0).ok_or_else(no_data_object)?;
+            data_object.get_data(format)
+        }
+        _ => Err(no_data_object()),
+    }
+}
@@ -0,0 +1,55 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use irondash_message_channel::Late;
+
+use crate::{clipboard_monitor::ClipboardMonitorDelegate, error::NativeExtensionsResult};
+
+use super::{ClipboardWatcher, ClipboardWatcherDelegate};
+
+pub struct PlatformClipboardMonitor {
+    delegate: Weak<dyn ClipboardMonitorDelegate>,
+    watcher: RefCell<Option<Rc<ClipboardWatcher>>>,
+    weak_self: Late<Weak<Self>>,
+}
+
+impl PlatformClipboardMonitor {
+    pub fn new(delegate: Weak<dyn ClipboardMonitorDelegate>) -> Self {
+        Self {
+            delegate,
+            watcher: RefCell::new(None),
+            weak_self: Late::new(),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformClipboardMonitor>) {
+        self.weak_self.set(weak);
+    }
+
+    pub fn start(&self) -> NativeExtensionsResult<()> {
+        let mut watcher = self.watcher.borrow_mut();
+        if watcher.is_none() {
+            let delegate: Weak<dyn ClipboardWatcherDelegate> = self.weak_self.clone();
+            let new_watcher = Rc::new(ClipboardWatcher::new(delegate));
+            new_watcher.assign_weak_self(Rc::downgrade(&new_watcher));
+            watcher.replace(new_watcher);
+        }
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        // Watcher unregisters from clipboard when dropped.
+        let watcher = self.watcher.take();
+        drop(watcher);
+    }
+}
+
+impl ClipboardWatcherDelegate for PlatformClipboardMonitor {
+    fn clipboard_did_change(&self) {
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.clipboard_changed();
+        }
+    }
+}
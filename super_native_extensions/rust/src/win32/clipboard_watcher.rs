@@ -0,0 +1,130 @@
+use std::{cell::Cell, rc::Weak};
+
+use irondash_message_channel::Late;
+use irondash_run_loop::{platform::MessageListener, RunLoop};
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, WPARAM},
+    System::DataExchange::{
+        AddClipboardFormatListener, ChangeClipboardChain, GetClipboardSequenceNumber,
+        RemoveClipboardFormatListener, SetClipboardViewer,
+    },
+    UI::WindowsAndMessaging::{
+        SendMessageW, WM_CHANGECBCHAIN, WM_CLIPBOARDUPDATE, WM_DRAWCLIPBOARD,
+    },
+};
+
+use crate::log::OkLog;
+
+pub trait ClipboardWatcherDelegate {
+    fn clipboard_did_change(&self);
+}
+
+/// Watches system clipboard for changes.
+///
+/// Uses both clipboard format listener and the legacy clipboard viewer chain
+/// (`WM_DRAWCLIPBOARD`). Format listener notifications are not reliable on
+/// some older configurations and inside certain RDP sessions, while the
+/// viewer chain can be broken by misbehaving applications. Notifications
+/// coming from both sources are deduplicated using clipboard sequence number.
+pub struct ClipboardWatcher {
+    delegate: Weak<dyn ClipboardWatcherDelegate>,
+    weak_self: Late<Weak<Self>>,
+    format_listener: Cell<bool>,
+    in_viewer_chain: Cell<bool>,
+    next_viewer: Cell<HWND>,
+    last_sequence_number: Cell<u32>,
+}
+
+impl ClipboardWatcher {
+    pub fn new(delegate: Weak<dyn ClipboardWatcherDelegate>) -> Self {
+        Self {
+            delegate,
+            weak_self: Late::new(),
+            format_listener: Cell::new(false),
+            in_viewer_chain: Cell::new(false),
+            next_viewer: Cell::new(HWND(0)),
+            last_sequence_number: Cell::new(unsafe { GetClipboardSequenceNumber() }),
+        }
+    }
+
+    fn hwnd() -> HWND {
+        HWND(RunLoop::current().platform_run_loop.hwnd())
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<Self>) {
+        self.weak_self.set(weak.clone());
+        RunLoop::current()
+            .platform_run_loop
+            .register_message_listener(weak);
+        let hwnd = Self::hwnd();
+        self.format_listener.set(
+            unsafe { AddClipboardFormatListener(hwnd) }
+                .ok_log()
+                .is_some(),
+        );
+        // SetClipboardViewer returns null both for failure and when there is
+        // no other viewer in the chain.
+        self.next_viewer.set(unsafe { SetClipboardViewer(hwnd) });
+        self.in_viewer_chain.set(true);
+    }
+
+    fn clipboard_did_change(&self) {
+        let sequence_number = unsafe { GetClipboardSequenceNumber() };
+        // Sequence number is 0 when the window station has no access to
+        // clipboard; do not deduplicate in that case.
+        if sequence_number != 0 && sequence_number == self.last_sequence_number.get() {
+            return;
+        }
+        self.last_sequence_number.set(sequence_number);
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.clipboard_did_change();
+        }
+    }
+
+    fn forward_to_next_viewer(&self, message: u32, w_param: usize, l_param: isize) {
+        let next = self.next_viewer.get();
+        if next.0 != 0 {
+            unsafe { SendMessageW(next, message, WPARAM(w_param), LPARAM(l_param)) };
+        }
+    }
+}
+
+impl Drop for ClipboardWatcher {
+    fn drop(&mut self) {
+        let Ok(run_loop) = RunLoop::try_current() else {
+            return;
+        };
+        let hwnd = HWND(run_loop.platform_run_loop.hwnd());
+        if self.format_listener.get() {
+            unsafe { RemoveClipboardFormatListener(hwnd) }.ok_log();
+        }
+        if self.in_viewer_chain.get() {
+            unsafe { ChangeClipboardChain(hwnd, self.next_viewer.get()) };
+        }
+        let message_listener: Weak<dyn MessageListener> = self.weak_self.clone();
+        run_loop
+            .platform_run_loop
+            .unregister_message_listener(&message_listener);
+    }
+}
+
+impl MessageListener for ClipboardWatcher {
+    fn on_window_message(&self, _hwnd: isize, message: u32, w_param: usize, l_param: isize) {
+        match message {
+            WM_CLIPBOARDUPDATE => self.clipboard_did_change(),
+            WM_DRAWCLIPBOARD => {
+                self.clipboard_did_change();
+                self.forward_to_next_viewer(message, w_param, l_param);
+            }
+            WM_CHANGECBCHAIN => {
+                if w_param as isize == self.next_viewer.get().0 {
+                    // Next viewer is leaving the chain
+                    self.next_viewer.set(HWND(l_param));
+                } else {
+                    self.forward_to_next_viewer(message, w_param, l_param);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,10 +1,19 @@
-use std::{fs::OpenOptions, io::Write, mem::size_of, path::Path, ptr::null_mut};
+use std::{
+    fs::OpenOptions, io::Write, mem::size_of, path::Path, ptr::null_mut, sync::Mutex, thread,
+};
 
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop,
+};
 use once_cell::sync::Lazy;
 use windows::{
     core::{s, ComInterface, GUID, HRESULT, HSTRING},
     Win32::{
-        Foundation::{E_UNEXPECTED, HANDLE, HWND, S_OK},
+        Foundation::{
+            DV_E_FORMATETC, E_ABORT, E_ACCESSDENIED, E_UNEXPECTED, HANDLE, HWND,
+            RPC_E_CALL_REJECTED, RPC_E_SERVERCALL_RETRYLATER, S_OK,
+        },
         Graphics::Gdi::{
             CreateDIBSection, GetDC, GetDeviceCaps, MonitorFromWindow, ReleaseDC, BITMAPINFO,
             BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP, HMONITOR, LOGPIXELSX,
@@ -24,12 +33,90 @@ use windows::{
 
 use crate::{
     api_model::ImageData,
+    cf_html::{CF_HTML_FORMAT, HTML_FORMAT},
     error::{NativeExtensionsError, NativeExtensionsResult},
+    file_uri_list::FILE_URI_LIST_FORMAT,
 };
 
+use super::remote_session;
+
 const INTERNAL_PREFIX: &str = "NativeShell_CF_";
 
+/// Application identifiers of named formats registered through
+/// [`register_format_alias`], with the registered format.
+///
+/// Aliases only exist in this process. Broker process exchanges formats as
+/// registered format ids, which are shared by all processes of the session,
+/// so they are translated here on both ends of the exchange.
+static FORMAT_ALIASES: Mutex<Vec<(String, u32)>> = Mutex::new(Vec::new());
+
+/// Names handled by the crate itself. Aliasing them, either as identifier
+/// or as platform name, would change what internal lookups resolve to.
+const RESERVED_FORMAT_NAMES: &[&str] = &[
+    "PNG",
+    "GIF",
+    "JFIF",
+    "Rich Text Format",
+    "FileGroupDescriptorW",
+    "FileContents",
+    CF_HTML_FORMAT,
+    HTML_FORMAT,
+    FILE_URI_LIST_FORMAT,
+];
+
+fn is_reserved_format_name(name: &str) -> bool {
+    name.starts_with(INTERNAL_PREFIX) || RESERVED_FORMAT_NAMES.contains(&name)
+}
+
+/// Registers clipboard format `name` (i.e. private format of a legacy
+/// application) and makes it appear as `identifier` in format lists, both
+/// when reading and writing. Registering identifier again replaces the
+/// previous format. Reserved names are rejected for both arguments.
+pub fn register_format_alias(identifier: &str, name: &str) -> NativeExtensionsResult<()> {
+    if is_reserved_format_name(identifier) {
+        return Err(NativeExtensionsError::OtherError(format!(
+            "Format identifier \"{identifier}\" is reserved"
+        )));
+    }
+    let format = unsafe { RegisterClipboardFormatW(&HSTRING::from(name)) };
+    if format == 0 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    // Format names are case insensitive, compare registered formats.
+    let reserved = RESERVED_FORMAT_NAMES
+        .iter()
+        .any(|reserved| unsafe { RegisterClipboardFormatW(&HSTRING::from(*reserved)) } == format);
+    if reserved {
+        return Err(NativeExtensionsError::OtherError(format!(
+            "Clipboard format \"{name}\" is reserved"
+        )));
+    }
+    let mut aliases = FORMAT_ALIASES.lock().unwrap();
+    aliases.retain(|(i, f)| i != identifier && *f != format);
+    aliases.push((identifier.to_owned(), format));
+    Ok(())
+}
+
+fn format_alias_identifier(format: u32) -> Option<String> {
+    let aliases = FORMAT_ALIASES.lock().unwrap();
+    aliases
+        .iter()
+        .find(|(_, f)| *f == format)
+        .map(|(identifier, _)| identifier.clone())
+}
+
+fn format_alias_format(identifier: &str) -> Option<u32> {
+    let aliases = FORMAT_ALIASES.lock().unwrap();
+    aliases
+        .iter()
+        .find(|(i, _)| i == identifier)
+        .map(|(_, format)| *format)
+}
+
 pub fn format_to_string(format: u32) -> String {
+    if let Some(identifier) = format_alias_identifier(format) {
+        return identifier;
+    }
     let mut buf: [_; 1024] = [0u16; 1024];
     let len = unsafe { GetClipboardFormatNameW(format, &mut buf) };
     if len == 0 {
@@ -40,6 +127,9 @@ pub fn format_to_string(format: u32) -> String {
 }
 
 pub fn format_from_string(format: &str) -> u32 {
+    if let Some(format) = format_alias_format(format) {
+        return format;
+    }
     if let Some(format) = format.strip_prefix(INTERNAL_PREFIX) {
         format.parse::<u32>().ok().unwrap_or(0)
     } else {
@@ -63,7 +153,20 @@ pub fn make_format_with_tymed_index(format: u32, tymed: TYMED, index: i32) -> FO
 
 impl From<windows::core::Error> for NativeExtensionsError {
     fn from(error: windows::core::Error) -> Self {
-        NativeExtensionsError::OtherError(format!("Windows Error: {error}"))
+        match error.code() {
+            E_ABORT => NativeExtensionsError::Cancelled,
+            E_ACCESSDENIED => {
+                NativeExtensionsError::AccessDenied(Some(error.message().to_string()))
+            }
+            DV_E_FORMATETC => {
+                NativeExtensionsError::FormatNotAvailable(error.message().to_string())
+            }
+            // Source application is busy (e.g. showing modal dialog).
+            RPC_E_CALL_REJECTED | RPC_E_SERVERCALL_RETRYLATER => {
+                NativeExtensionsError::SourceAppUnresponsive(Some(error.message().to_string()))
+            }
+            _ => NativeExtensionsError::OtherError(format!("Windows Error: {error}")),
+        }
     }
 }
 
@@ -89,6 +192,16 @@ pub fn extract_formats(object: &IDataObject) -> windows::core::Result<Vec<FORMAT
 }
 
 pub fn image_data_to_hbitmap(image: &ImageData) -> NativeExtensionsResult<HBITMAP> {
+    _image_data_to_hbitmap(image, false)
+}
+
+/// Same as [`image_data_to_hbitmap`] but with premultiplied alpha, which is
+/// what UpdateLayeredWindow expects.
+pub fn image_data_to_hbitmap_premultiplied(image: &ImageData) -> NativeExtensionsResult<HBITMAP> {
+    _image_data_to_hbitmap(image, true)
+}
+
+fn _image_data_to_hbitmap(image: &ImageData, premultiply: bool) -> NativeExtensionsResult<HBITMAP> {
     let bitmap = BITMAPINFO {
         bmiHeader: BITMAPINFOHEADER {
             biSize: size_of::<BITMAPINFOHEADER>() as u32,
@@ -120,7 +233,7 @@ pub fn image_data_to_hbitmap(image: &ImageData) -> NativeExtensionsResult<HBITMA
             0,
         )?;
 
-        // Bitmap needs to be flipped and unpremultiplied
+        // Bitmap needs to be flipped and (unless requested otherwise) unpremultiplied
 
         let dst_stride = (image.width * 4) as isize;
         let ptr = ptr as *mut u8;
@@ -133,13 +246,19 @@ pub fn image_data_to_hbitmap(image: &ImageData) -> NativeExtensionsResult<HBITMA
             let dst_line = ptr.offset(y * dst_stride);
 
             for x in (0..dst_stride).step_by(4) {
-                let (r, g, b, a) = (
+                let (mut r, mut g, mut b, a) = (
                     *src_line.offset(x) as i32,
                     *src_line.offset(x + 1) as i32,
                     *src_line.offset(x + 2) as i32,
                     *src_line.offset(x + 3) as i32,
                 );
 
+                if premultiply {
+                    r = r * a / 255;
+                    g = g * a / 255;
+                    b = b * a / 255;
+                }
+
                 // ByteFormat.rawStraightRgba already has unpremultiplied alpha
                 // but channel order is different.
 
@@ -235,7 +354,7 @@ fn read_stream_fully_with<F: FnMut(&[u8]) -> bool>(
     stream: &IStream,
     mut fun: F,
 ) -> windows::core::Result<()> {
-    let mut buf: [u8; 256 * 1024] = [0; 256 * 1024];
+    let mut buf = vec![0u8; remote_session::read_chunk_size(256 * 1024)];
     loop {
         let mut num_read: u32 = 0;
         let res = unsafe {
@@ -290,3 +409,23 @@ pub fn copy_stream_to_file(stream: &IStream, path: &Path) -> NativeExtensionsRes
 
     res
 }
+
+/// Runs `f` on a new thread and resolves with its result on the calling
+/// (main) thread. Used for calls into other applications that may block for
+/// a long time, so that the caller can give up on them without the UI hanging.
+pub async fn run_on_worker_thread<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (future, completer) = FutureCompleter::new();
+    let sender = RunLoop::current().new_sender();
+    let mut completer = Capsule::new_with_sender(completer, sender.clone());
+    thread::spawn(move || {
+        let res = f();
+        sender.send(move || {
+            completer.take().unwrap().complete(res);
+        });
+    });
+    future.await
+}
@@ -8,7 +8,7 @@ use std::{
     time::Duration,
 };
 
-use irondash_message_channel::IsolateId;
+use irondash_message_channel::{IsolateId, Value};
 use irondash_run_loop::{platform::PollSession, RunLoop};
 use threadpool::ThreadPool;
 use windows::{
@@ -16,17 +16,18 @@ use windows::{
     Win32::{
         Foundation::{
             GlobalFree, BOOL, DATA_S_SAMEFORMATETC, DV_E_FORMATETC, E_NOTIMPL, E_OUTOFMEMORY,
-            HGLOBAL, OLE_E_ADVISENOTSUPPORTED, POINT, S_FALSE, S_OK,
+            HGLOBAL, MAX_PATH, OLE_E_ADVISENOTSUPPORTED, POINT, S_FALSE, S_OK,
         },
+        Storage::FileSystem::FILE_ATTRIBUTE_DIRECTORY,
         System::{
             Com::{
                 IAdviseSink, IBindCtx, IDataObject, IDataObject_Impl, IStream, DATADIR_GET,
                 FORMATETC, STGMEDIUM, STGMEDIUM_0, STREAM_SEEK_END, STREAM_SEEK_SET, TYMED,
-                TYMED_HGLOBAL, TYMED_ISTREAM,
+                TYMED_ENHMF, TYMED_HGLOBAL, TYMED_ISTREAM,
             },
             DataExchange::RegisterClipboardFormatW,
             Memory::{GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GLOBAL_ALLOC_FLAGS},
-            Ole::{ReleaseStgMedium, CF_DIB, CF_DIBV5, CF_HDROP, DROPEFFECT},
+            Ole::{ReleaseStgMedium, CF_DIB, CF_DIBV5, CF_ENHMETAFILE, CF_HDROP, DROPEFFECT},
         },
         UI::Shell::{
             IDataObjectAsyncCapability, IDataObjectAsyncCapability_Impl, SHCreateMemStream,
@@ -38,11 +39,18 @@ use windows::{
 };
 
 use crate::{
-    api_model::{DataProviderValueId, DataRepresentation, VirtualFileStorage},
-    data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult},
+    api_model::{
+        DataProvider, DataProviderValueId, DataRepresentation, DataTransferProgress,
+        DataTransferState, VirtualFileStorage,
+    },
+    cf_html::{cf_html_from_value, CF_HTML_FORMAT, HTML_FORMAT},
+    data_provider_manager::{
+        DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult, WriteProgressUpdate,
+    },
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     segmented_queue::{new_segmented_queue, QueueConfiguration},
-    util::DropNotifier,
+    util::{sanitize_path_component, DropNotifier},
     value_coerce::{CoerceToData, StringFormat},
     value_promise::{Promise, ValuePromiseResult},
 };
@@ -54,12 +62,22 @@ use super::{
         make_format_with_tymed_index, read_stream_fully,
     },
     image_conversion::convert_to_dib,
+    metafile::EnhMetaFile,
     virtual_file_stream::{VirtualFileStream, VirtualStreamSession},
     PlatformDataProvider,
 };
 
 const DATA_E_FORMATETC: HRESULT = HRESULT(-2147221404 + 1);
 
+/// Metafiles are transferred as GDI handle; everything else in global memory.
+fn tymed_for_format(format: u32) -> TYMED {
+    if format == CF_ENHMETAFILE.0 as u32 {
+        TYMED_ENHMF
+    } else {
+        TYMED_HGLOBAL
+    }
+}
+
 struct ProviderEntry {
     provider: Rc<PlatformDataProvider>,
     _handle: Arc<DataProviderHandle>,
@@ -70,10 +88,16 @@ pub struct DataObject {
     providers: Vec<ProviderEntry>,
     extra_data: RefCell<HashMap<u16, Vec<u8>>>,
     in_operation: Cell<bool>, // async stream
+    async_mode: Cell<bool>,
+    /// Receives progress of transfers performed through
+    /// `IDataObjectAsyncCapability`, set for drag sessions.
+    transfer_observer: Option<TransferObserver>,
     virtual_stream_notifiers: RefCell<Vec<Arc<DropNotifier>>>,
     thread_pool: RefCell<Option<ThreadPool>>,
 }
 
+pub type TransferObserver = Rc<dyn Fn(DataTransferProgress)>;
+
 /// These formats are not commonly supported on Windows. If they
 /// are present as payload, DataObject will provide on-demand
 /// DIB and DIBV5 representation (unless the payload already contains
@@ -83,6 +107,13 @@ static FOREIGN_IMAGE_FORMATS: &[&str] = &["PNG", "GIF", "JFIF"];
 impl DataObject {
     pub fn create(
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+    ) -> IDataObject {
+        Self::create_with_transfer_observer(providers, None)
+    }
+
+    pub fn create_with_transfer_observer(
+        providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+        transfer_observer: Option<TransferObserver>,
     ) -> IDataObject {
         let data_object = Self {
             providers: providers
@@ -94,6 +125,8 @@ impl DataObject {
                 .collect(),
             extra_data: RefCell::new(HashMap::new()),
             in_operation: Cell::new(false),
+            async_mode: Cell::new(true),
+            transfer_observer,
             virtual_stream_notifiers: RefCell::new(Vec::new()),
             thread_pool: RefCell::new(None),
         };
@@ -115,20 +148,18 @@ impl DataObject {
         }
     }
 
-    fn lazy_data_for_id(
+    fn lazy_value_for_id(
         &self,
         provider: &PlatformDataProvider,
         id: DataProviderValueId,
-    ) -> Option<Vec<u8>> {
+    ) -> Option<Value> {
         let delegate = provider.delegate.upgrade();
         if let Some(delegate) = delegate {
             let data = delegate.get_lazy_data(provider.isolate_id, id, None);
             let mut poll_session = PollSession::new();
             loop {
                 match data.try_take() {
-                    Some(ValuePromiseResult::Ok { value }) => {
-                        return value.coerce_to_data(StringFormat::Utf16NullTerminated)
-                    }
+                    Some(ValuePromiseResult::Ok { value }) => return Some(value),
                     Some(ValuePromiseResult::Cancelled) => return None,
                     None => RunLoop::current()
                         .platform_run_loop
@@ -140,7 +171,7 @@ impl DataObject {
         }
     }
 
-    fn data_for_format(&self, format: u32, index: usize) -> Option<Vec<u8>> {
+    fn value_for_format(&self, format: u32, index: usize) -> Option<Value> {
         let provider = self.providers.get(index).as_ref().cloned();
         if let Some(provider) = provider {
             let provider = &provider.provider;
@@ -149,12 +180,12 @@ impl DataObject {
                 match representation {
                     DataRepresentation::Simple { format, data } => {
                         if &format_string == format {
-                            return data.coerce_to_data(StringFormat::Utf16NullTerminated);
+                            return Some(data.clone());
                         }
                     }
                     DataRepresentation::Lazy { format, id } => {
                         if &format_string == format {
-                            return self.lazy_data_for_id(provider, *id);
+                            return self.lazy_value_for_id(provider, *id);
                         }
                     }
                     _ => {}
@@ -166,6 +197,32 @@ impl DataObject {
         }
     }
 
+    fn data_for_format(&self, format: u32, index: usize) -> Option<Vec<u8>> {
+        self.value_for_format(format, index)?
+            .coerce_to_data(StringFormat::Utf16NullTerminated)
+    }
+
+    /// CF_HTML from `HTML Format` or `text/html` representation of first
+    /// item, wrapped in header if Dart provided plain HTML.
+    fn data_for_cf_html(&self) -> Option<Vec<u8>> {
+        let source_url = self.providers.first()?.provider.data.source_url.clone();
+        [CF_HTML_FORMAT, HTML_FORMAT].iter().find_map(|format| {
+            let value = self.value_for_format(format_from_string(format), 0)?;
+            cf_html_from_value(&value, source_url.as_deref())
+        })
+    }
+
+    /// Whether first item has `text/html` but no `HTML Format`, which most
+    /// Windows applications expect.
+    fn needs_synthesize_cf_html(&self) -> bool {
+        let Some(provider) = self.providers.first() else {
+            return false;
+        };
+        let representations = &provider.provider.data.representations;
+        let has_format = |format: &str| representations.iter().any(|r| r.format() == format);
+        has_format(HTML_FORMAT) && !has_format(CF_HTML_FORMAT)
+    }
+
     /// Bundles slice of utf16 encoded string into CF_HDROP
     pub fn bundle_files(files: &[Vec<u8>]) -> Vec<u8> {
         let mut res = Vec::new();
@@ -245,24 +302,21 @@ impl DataObject {
 
     fn get_formats(&self) -> Vec<FORMATETC> {
         let mut res = Vec::<_>::new();
-        let mut index = 0;
         // Put virtual files first
-        for provider in &self.providers {
-            for repr in &provider.provider.data.representations {
-                if repr.is_virtual_file() {
-                    if index == 0 {
-                        res.push(make_format_with_tymed(
-                            unsafe { RegisterClipboardFormatW(CFSTR_FILEDESCRIPTOR) },
-                            TYMED_HGLOBAL,
-                        ));
-                    }
-                    res.push(make_format_with_tymed_index(
-                        unsafe { RegisterClipboardFormatW(CFSTR_FILECONTENTS) },
-                        TYMED_ISTREAM,
-                        index,
-                    ));
-                    index += 1;
-                }
+        let virtual_entries = self.virtual_file_entries();
+        if !virtual_entries.is_empty() {
+            res.push(make_format_with_tymed(
+                unsafe { RegisterClipboardFormatW(CFSTR_FILEDESCRIPTOR) },
+                TYMED_HGLOBAL,
+            ));
+        }
+        for (index, entry) in virtual_entries.iter().enumerate() {
+            if entry.file.is_some() {
+                res.push(make_format_with_tymed_index(
+                    unsafe { RegisterClipboardFormatW(CFSTR_FILECONTENTS) },
+                    TYMED_ISTREAM,
+                    index as i32,
+                ));
             }
         }
         // Regular and lazy items second
@@ -272,11 +326,11 @@ impl DataObject {
                 match representation {
                     DataRepresentation::Simple { format, data: _ } => {
                         let format = format_from_string(format);
-                        res.push(make_format_with_tymed(format, TYMED_HGLOBAL));
+                        res.push(make_format_with_tymed(format, tymed_for_format(format)));
                     }
                     DataRepresentation::Lazy { format, id: _ } => {
                         let format = format_from_string(format);
-                        res.push(make_format_with_tymed(format, TYMED_HGLOBAL));
+                        res.push(make_format_with_tymed(format, tymed_for_format(format)));
                     }
                     _ => {}
                 }
@@ -288,6 +342,13 @@ impl DataObject {
             res.push(make_format_with_tymed(CF_DIBV5.0 as u32, TYMED_HGLOBAL));
         }
 
+        if self.needs_synthesize_cf_html() {
+            res.push(make_format_with_tymed(
+                format_from_string(CF_HTML_FORMAT),
+                TYMED_HGLOBAL,
+            ));
+        }
+
         // Extra data (set through SetData) last
         let extra_data = self.extra_data.borrow();
         for format in extra_data.keys() {
@@ -299,33 +360,85 @@ impl DataObject {
         res
     }
 
-    fn file_descriptor_for_item(file_name: &str) -> FILEDESCRIPTORW {
-        let mut name_buf: [u16; 260] = [0; 260];
-        let mut name_str: Vec<_> = file_name.encode_utf16().collect();
-        name_str.truncate(name_buf.len() - 1);
+    /// Entries of FILEGROUPDESCRIPTOR in order. Virtual file is a single
+    /// entry, virtual folder is the folder followed by its contents (with
+    /// paths relative to the drop target).
+    fn virtual_file_entries(&self) -> Vec<VirtualEntry<'_>> {
+        let mut res = Vec::new();
+        let mut cnt = 0;
+        for provider in &self.providers {
+            let provider: &PlatformDataProvider = &provider.provider;
+            let data = &provider.data;
+            let Some(repr) = data.representations.iter().find(|r| r.is_virtual_file()) else {
+                continue;
+            };
+            cnt += 1;
+            let name = data
+                .suggested_name
+                .as_ref()
+                .cloned()
+                .unwrap_or_else(|| format!("File {cnt}"));
+            match repr {
+                DataRepresentation::VirtualFile {
+                    id,
+                    format: _,
+                    storage_suggestion,
+                } => res.push(VirtualEntry {
+                    path: name,
+                    file: Some((provider, *id, storage_suggestion)),
+                }),
+                DataRepresentation::VirtualFolder { format: _, entries } => {
+                    let name =
+                        sanitize_path_component(&name).unwrap_or_else(|| format!("File {cnt}"));
+                    res.push(VirtualEntry {
+                        path: name.clone(),
+                        file: None,
+                    });
+                    for entry in entries {
+                        let components = entry.components();
+                        if components.is_empty() {
+                            continue;
+                        }
+                        res.push(VirtualEntry {
+                            path: format!("{}\\{}", name, components.join("\\")),
+                            file: entry.id.map(|id| (provider, id, &entry.storage_suggestion)),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        res
+    }
+
+    /// `None` if the path doesn't fit into `cFileName`; registering virtual
+    /// folder with such entries fails, see [`check_virtual_folder`].
+    fn file_descriptor_for_entry(entry: &VirtualEntry) -> Option<FILEDESCRIPTORW> {
+        let mut name_buf: [u16; MAX_PATH as usize] = [0; MAX_PATH as usize];
+        let name_str: Vec<_> = entry.path.encode_utf16().collect();
+        if name_str.len() >= name_buf.len() {
+            return None;
+        }
         name_buf[0..name_str.len()].copy_from_slice(&name_str);
-        FILEDESCRIPTORW {
+        let attributes = if entry.file.is_none() {
+            FILE_ATTRIBUTE_DIRECTORY.0
+        } else {
+            0
+        };
+        Some(FILEDESCRIPTORW {
             dwFlags: (FD_ATTRIBUTES.0 | FD_PROGRESSUI.0) as u32,
+            dwFileAttributes: attributes,
             cFileName: name_buf,
             ..FILEDESCRIPTORW::default()
-        }
+        })
     }
 
     fn data_for_file_group_descritor(&self) -> Option<Vec<u8>> {
-        let mut cnt = 0;
-        let mut descriptors = Vec::<FILEDESCRIPTORW>::new();
-        for provider in &self.providers {
-            let data = &provider.provider.data;
-            if data.representations.iter().any(|a| a.is_virtual_file()) {
-                cnt += 1;
-                let name = data
-                    .suggested_name
-                    .as_ref()
-                    .cloned()
-                    .unwrap_or_else(|| format!("File {cnt}"));
-                descriptors.push(Self::file_descriptor_for_item(&name));
-            }
-        }
+        let descriptors: Vec<_> = self
+            .virtual_file_entries()
+            .iter()
+            .map(Self::file_descriptor_for_entry)
+            .collect::<Option<_>>()?;
         let mut res = Vec::new();
         let len = descriptors.len() as u32;
         if len == 0 {
@@ -343,6 +456,7 @@ impl DataObject {
         isolate_id: IsolateId,
         virtual_file_id: DataProviderValueId,
         configuration: QueueConfiguration,
+        on_progress: Box<dyn Fn(WriteProgressUpdate)>,
     ) -> VirtualStreamSession {
         let (writer, reader) = new_segmented_queue(configuration);
         let stream_handle = add_stream_entry(writer);
@@ -355,7 +469,7 @@ impl DataObject {
             virtual_file_id,
             stream_handle,
             Box::new(move |size| size_promise_clone.set(size)),
-            Box::new(move |_progress| {}),
+            on_progress,
             Box::new(move |result| {
                 if let VirtualFileResult::Error { message } = result {
                     error_promise_clone.set(message);
@@ -375,6 +489,7 @@ impl DataObject {
         provider: &PlatformDataProvider,
         virtual_file_id: DataProviderValueId,
         storage_suggestion: &Option<VirtualFileStorage>,
+        file_index: usize,
         agile: bool,
     ) -> Option<IStream> {
         if let Some(delegate) = provider.delegate.upgrade() {
@@ -394,12 +509,25 @@ impl DataObject {
                 }
             };
             let isolate_id = provider.isolate_id;
+            let observer = self.transfer_observer.clone();
             let provider = move || {
+                let on_progress = Box::new(move |update: WriteProgressUpdate| {
+                    if let Some(observer) = &observer {
+                        observer(DataTransferProgress {
+                            state: DataTransferState::InProgress,
+                            file_index: Some(file_index as i64),
+                            fraction: update.fraction,
+                            bytes_written: update.bytes_written,
+                            total_bytes: update.total_bytes,
+                        });
+                    }
+                });
                 Self::create_virtual_stream_session(
                     delegate,
                     isolate_id,
                     virtual_file_id,
                     configuration,
+                    on_progress,
                 )
             };
             let (stream, notifier) = if agile {
@@ -420,33 +548,51 @@ impl DataObject {
         }
     }
 
-    fn stream_for_virtual_file_index(&self, mut index: usize, agile: bool) -> Option<IStream> {
-        for provider in &self.providers {
-            let provider = &provider.provider;
-            // Skip all virtual files before the requested one.
-            if index > 0
-                && provider
-                    .data
-                    .representations
-                    .iter()
-                    .any(|r| r.is_virtual_file())
-            {
-                index -= 1;
-                continue;
-            }
-            for repr in &provider.data.representations {
-                if let DataRepresentation::VirtualFile {
-                    id,
-                    format: _,
-                    storage_suggestion,
-                } = repr
-                {
-                    return self.stream_for_virtual_file(provider, *id, storage_suggestion, agile);
+    fn stream_for_virtual_file_index(&self, index: usize, agile: bool) -> Option<IStream> {
+        let entries = self.virtual_file_entries();
+        // Directories have no contents.
+        let (provider, id, storage_suggestion) = entries.get(index)?.file?;
+        self.stream_for_virtual_file(provider, id, storage_suggestion, index, agile)
+    }
+}
+
+struct VirtualEntry<'a> {
+    path: String,
+    file: Option<(
+        &'a PlatformDataProvider,
+        DataProviderValueId,
+        &'a Option<VirtualFileStorage>,
+    )>,
+}
+
+/// Fails if path of a virtual folder entry doesn't fit into
+/// `FILEDESCRIPTORW::cFileName`, which would otherwise truncate it.
+pub fn check_virtual_folder(data: &DataProvider) -> NativeExtensionsResult<()> {
+    // Unnamed folders are named "File <n>" when dropped.
+    let name = data
+        .suggested_name
+        .as_deref()
+        .and_then(sanitize_path_component)
+        .unwrap_or_else(|| "File 0000".into());
+    for representation in &data.representations {
+        if let DataRepresentation::VirtualFolder { format: _, entries } = representation {
+            for entry in entries {
+                let len = name.encode_utf16().count()
+                    + entry
+                        .components()
+                        .iter()
+                        .map(|c| c.encode_utf16().count() + 1)
+                        .sum::<usize>();
+                if len >= MAX_PATH as usize {
+                    return Err(NativeExtensionsError::OtherError(format!(
+                        "Virtual folder entry path is too long: {}",
+                        entry.path
+                    )));
                 }
             }
         }
-        None
     }
+    Ok(())
 }
 
 thread_local! {
@@ -505,6 +651,7 @@ impl IDataObject_Impl for DataObject {
         }
 
         let needs_generate_bitmap = self.needs_synthesize_bitmap();
+        let format_cf_html = format_from_string(CF_HTML_FORMAT);
 
         let data = self
             .extra_data
@@ -520,6 +667,8 @@ impl IDataObject_Impl for DataObject {
                     self.synthesize_bitmap_data(false).ok_log()
                 } else if needs_generate_bitmap && format.cfFormat == CF_DIBV5.0 {
                     self.synthesize_bitmap_data(true).ok_log()
+                } else if format.cfFormat as u32 == format_cf_html {
+                    self.data_for_cf_html()
                 } else {
                     self.data_for_format(format.cfFormat as u32, 0)
                 }
@@ -529,7 +678,18 @@ impl IDataObject_Impl for DataObject {
 
         match data {
             Some(data) => {
-                if (format.tymed & TYMED_HGLOBAL.0 as u32) != 0 {
+                if format.cfFormat == CF_ENHMETAFILE.0 && (format.tymed & TYMED_ENHMF.0 as u32) != 0
+                {
+                    // Representation data is EMF file content
+                    let metafile = EnhMetaFile::from_bits(&data)?;
+                    Ok(STGMEDIUM {
+                        tymed: TYMED_ENHMF.0 as u32,
+                        u: STGMEDIUM_0 {
+                            hEnhMetaFile: metafile.into_raw(),
+                        },
+                        pUnkForRelease: ManuallyDrop::new(None),
+                    })
+                } else if (format.tymed & TYMED_HGLOBAL.0 as u32) != 0 {
                     let global = self.global_from_data(&data)?;
                     Ok(STGMEDIUM {
                         tymed: TYMED_HGLOBAL.0 as u32,
@@ -690,16 +850,20 @@ impl IDataObject_Impl for DataObject {
 
 #[allow(non_snake_case)]
 impl IDataObjectAsyncCapability_Impl for DataObject {
-    fn SetAsyncMode(&self, _fdoopasync: BOOL) -> windows::core::Result<()> {
+    fn SetAsyncMode(&self, fdoopasync: BOOL) -> windows::core::Result<()> {
+        self.async_mode.set(fdoopasync.as_bool());
         Ok(())
     }
 
     fn GetAsyncMode(&self) -> windows::core::Result<BOOL> {
-        Ok(true.into())
+        Ok(self.async_mode.get().into())
     }
 
+    /// Called by drop target that extracts data on background thread after
+    /// `DoDragDrop` returned.
     fn StartOperation(&self, _pbcreserved: Option<&IBindCtx>) -> windows::core::Result<()> {
         self.in_operation.replace(true);
+        self.notify_transfer_state(DataTransferState::Started);
         Ok(())
     }
 
@@ -709,15 +873,35 @@ impl IDataObjectAsyncCapability_Impl for DataObject {
 
     fn EndOperation(
         &self,
-        _hresult: windows::core::HRESULT,
+        hresult: windows::core::HRESULT,
         _pbcreserved: Option<&IBindCtx>,
         _dweffects: u32,
     ) -> windows::core::Result<()> {
-        self.in_operation.replace(false);
+        if self.in_operation.replace(false) {
+            self.notify_transfer_state(if hresult.is_ok() {
+                DataTransferState::Completed
+            } else {
+                DataTransferState::Failed
+            });
+        }
         Ok(())
     }
 }
 
+impl DataObject {
+    fn notify_transfer_state(&self, state: DataTransferState) {
+        if let Some(observer) = &self.transfer_observer {
+            observer(DataTransferProgress {
+                state,
+                file_index: None,
+                fraction: None,
+                bytes_written: None,
+                total_bytes: None,
+            });
+        }
+    }
+}
+
 pub trait GetData {
     unsafe fn do_get_data(
         &self,
@@ -5,18 +5,19 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use irondash_message_channel::{IsolateId, Late};
+use irondash_message_channel::{IsolateId, Late, Value};
 use once_cell::sync::Lazy;
-use windows::Win32::System::Ole::OleSetClipboard;
+use windows::Win32::System::Ole::{OleFlushClipboard, OleSetClipboard};
 
 use crate::{
-    api_model::DataProvider,
+    api_model::{DataProvider, DataRepresentation},
     data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate},
     error::NativeExtensionsResult,
     segmented_queue::SegmentedQueueWriter,
+    value_promise::ValuePromiseResult,
 };
 
-use super::data_object::DataObject;
+use super::{data_object::DataObject, remote_session};
 
 static STREAM_ENTRIES: Lazy<Mutex<HashMap<i32, SegmentedQueueWriter>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
@@ -83,13 +84,60 @@ impl PlatformDataProvider {
         self.weak_self.set(weak_self);
     }
 
+    /// Returns provider with lazy representations replaced by their values.
+    async fn resolve_lazy_data(self: &Rc<Self>) -> Rc<Self> {
+        let Some(delegate) = self.delegate.upgrade() else {
+            return self.clone();
+        };
+        let mut data = self.data.clone();
+        for representation in data.representations.iter_mut() {
+            if let DataRepresentation::Lazy { id, format } = representation {
+                let value = match delegate.get_lazy_data_async(self.isolate_id, *id).await {
+                    ValuePromiseResult::Ok { value } => value,
+                    ValuePromiseResult::Cancelled => Value::Null,
+                };
+                *representation = DataRepresentation::Simple {
+                    format: format.clone(),
+                    data: value,
+                };
+            }
+        }
+        let res = Rc::new(Self::new(self.delegate.clone(), self.isolate_id, data));
+        res.assign_weak_self(Rc::downgrade(&res));
+        res
+    }
+
     pub async fn write_to_clipboard(
         providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
     ) -> NativeExtensionsResult<()> {
+        // Delayed rendering is unreliable with remote session clipboard
+        // redirection.
+        let providers = if remote_session::eager_rendering() {
+            let mut resolved = Vec::new();
+            for (provider, handle) in providers {
+                resolved.push((provider.resolve_lazy_data().await, handle));
+            }
+            resolved
+        } else {
+            providers
+        };
         let data_object = DataObject::create(providers);
         unsafe {
             OleSetClipboard(&data_object)?;
         }
         Ok(())
     }
+
+    pub async fn clear_clipboard() -> NativeExtensionsResult<()> {
+        // Releases our data object and empties the clipboard.
+        unsafe { OleSetClipboard(None)? };
+        Ok(())
+    }
+
+    /// Renders all formats that can still be provided into the clipboard and
+    /// releases our data object.
+    pub fn flush_clipboard() -> NativeExtensionsResult<()> {
+        unsafe { OleFlushClipboard()? };
+        Ok(())
+    }
 }
@@ -8,27 +8,34 @@ use irondash_engine_context::EngineContext;
 use irondash_message_channel::{Late, Value};
 use irondash_run_loop::RunLoop;
 use windows::{
-    core::implement,
+    core::{implement, w},
     Win32::{
         Foundation::{
             BOOL, COLORREF, DRAGDROP_S_CANCEL, DRAGDROP_S_DROP, DRAGDROP_S_USEDEFAULTCURSORS, HWND,
-            POINT, SIZE, S_OK,
+            LPARAM, POINT, SIZE, S_OK, WPARAM,
         },
         System::{
+            Com::IDataObject,
+            DataExchange::RegisterClipboardFormatW,
             Ole::{DoDragDrop, IDropSource, IDropSource_Impl, DROPEFFECT, DROPEFFECT_NONE},
-            SystemServices::{MK_LBUTTON, MODIFIERKEYS_FLAGS},
+            SystemServices::{MK_ALT, MK_CONTROL, MK_LBUTTON, MK_SHIFT, MODIFIERKEYS_FLAGS},
         },
         UI::{
+            Input::KeyboardAndMouse::{GetAsyncKeyState, VIRTUAL_KEY, VK_LWIN, VK_RWIN},
             Shell::{CLSID_DragDropHelper, IDragSourceHelper, SHDRAGIMAGE},
-            WindowsAndMessaging::GetCursorPos,
+            WindowsAndMessaging::{GetCursorPos, SendMessageW, WM_USER},
         },
     },
 };
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DragCancelReason, DragConfiguration, DragRequest, DropOperation,
+        KeyModifiers, Point, Size, TargettedImage,
+    },
     drag_manager::{
         DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
+        UpdateDragImageRequest,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
@@ -38,29 +45,42 @@ use crate::{
 
 use super::{
     common::{create_instance, image_data_to_hbitmap},
-    data_object::DataObjectExt,
+    data_object::{DataObjectExt, GetData},
     drag_common::DropOperationExt,
+    touch_drag::{set_system_press_and_hold_enabled, PointerShim},
 };
 
 struct DragSession {
     id: DragSessionId,
     configuration: DragConfiguration,
+    image_size: Size,
+    data_object: IDataObject,
+    /// Drag images are positioned relative to this point.
+    start_position: Point,
 }
 
+/// Sent to the drag image window to reload image from the data object.
+const DDWM_UPDATEWINDOW: u32 = WM_USER + 3;
+
 pub struct PlatformDragContext {
     id: PlatformDragContextId,
-    _view: HWND,
+    view: HWND,
     delegate: Weak<dyn PlatformDragContextDelegate>,
     weak_self: Late<Weak<Self>>,
     current_session: RefCell<Option<DragSession>>,
+    /// Set by [`PlatformDragContext::cancel_sessions`], checked by drop
+    /// source.
+    cancel_requested: Cell<bool>,
 }
 
 #[implement(IDropSource)]
 pub struct DropSource {
     platform_context: Weak<PlatformDragContext>,
-    last_reported_location: RefCell<Point>,
+    last_reported_location: RefCell<(Point, KeyModifiers)>,
     session_id: DragSessionId,
     cancelled: Rc<Cell<bool>>,
+    /// Present for drags started by touch or pen.
+    pointer_shim: RefCell<Option<PointerShim>>,
 }
 
 #[allow(non_snake_case)]
@@ -69,12 +89,14 @@ impl DropSource {
         platform_context: Weak<PlatformDragContext>,
         session_id: DragSessionId,
         cancelled: Rc<Cell<bool>>,
+        pointer_shim: Option<PointerShim>,
     ) -> IDropSource {
         Self {
             platform_context,
             session_id,
-            last_reported_location: RefCell::new(Point::default()),
+            last_reported_location: RefCell::new(Default::default()),
             cancelled,
+            pointer_shim: RefCell::new(pointer_shim),
         }
         .into()
     }
@@ -87,6 +109,17 @@ impl IDropSource_Impl for DropSource {
         fescapepressed: BOOL,
         grfkeystate: MODIFIERKEYS_FLAGS,
     ) -> windows::core::HRESULT {
+        if let Some(shim) = self.pointer_shim.borrow_mut().as_mut() {
+            shim.update();
+        }
+        let cancel_requested = self
+            .platform_context
+            .upgrade()
+            .map(|context| context.cancel_requested.get())
+            .unwrap_or(true);
+        if cancel_requested {
+            return DRAGDROP_S_CANCEL;
+        }
         if fescapepressed.as_bool() {
             self.cancelled.replace(true);
             DRAGDROP_S_CANCEL
@@ -101,13 +134,16 @@ impl IDropSource_Impl for DropSource {
                         x: cursor_pos.x as f64,
                         y: cursor_pos.y as f64,
                     };
-                    if *self.last_reported_location.borrow() != location {
+                    let modifiers = PlatformDragContext::modifiers_from_key_state(grfkeystate);
+                    let reported = (location, modifiers);
+                    if *self.last_reported_location.borrow() != reported {
                         delegate.drag_session_did_move_to_location(
                             context.id,
                             self.session_id,
-                            location.clone(),
+                            reported.0.clone(),
+                            modifiers,
                         );
-                        self.last_reported_location.replace(location);
+                        self.last_reported_location.replace(reported);
                     }
                 }
             }
@@ -130,10 +166,11 @@ impl PlatformDragContext {
 
         Ok(Self {
             id,
-            _view: HWND(view),
+            view: HWND(view),
             delegate,
             weak_self: Late::new(),
             current_session: RefCell::new(None),
+            cancel_requested: Cell::new(false),
         })
     }
 
@@ -145,6 +182,31 @@ impl PlatformDragContext {
         true
     }
 
+    /// Makes the OLE drag loop of current session return next time it
+    /// queries the drop source.
+    pub fn cancel_sessions(&self) {
+        if self.current_session.borrow().is_some() {
+            self.cancel_requested.set(true);
+        }
+    }
+
+    pub fn set_system_press_and_hold_enabled(&self, enabled: bool) {
+        set_system_press_and_hold_enabled(self.view, enabled);
+    }
+
+    /// Modifiers from key state passed to drop source by the OLE drag loop.
+    /// Message queue key state is not updated during the loop. Key state
+    /// doesn't include Windows keys, these are queried directly.
+    fn modifiers_from_key_state(key_state: MODIFIERKEYS_FLAGS) -> KeyModifiers {
+        let is_down = |key: VIRTUAL_KEY| unsafe { GetAsyncKeyState(key.0 as i32) } < 0;
+        KeyModifiers {
+            shift: key_state.0 & MK_SHIFT.0 != 0,
+            control: key_state.0 & MK_CONTROL.0 != 0,
+            alt: key_state.0 & MK_ALT.0 != 0,
+            meta: is_down(VK_LWIN) || is_down(VK_RWIN),
+        }
+    }
+
     pub async fn start_drag(
         &self,
         request: DragRequest,
@@ -184,54 +246,55 @@ impl PlatformDragContext {
             NativeExtensionsError::OtherError("Missing combined drag image".into())
         })?;
 
-        let drag_image = drag_image.with_shadow(10);
-
-        let data_object = DataObject::create(providers);
-        let helper: IDragSourceHelper = create_instance(&CLSID_DragDropHelper)?;
-        let hbitmap = image_data_to_hbitmap(&drag_image.image_data)?;
-        let device_pixel_ratio = drag_image.image_data.device_pixel_ratio.unwrap_or(1.0);
-        let point_in_rect = Point {
-            x: (request.position.x - drag_image.rect.x) * device_pixel_ratio,
-            y: (request.position.y - drag_image.rect.y) * device_pixel_ratio,
-        };
-
-        let mut image = SHDRAGIMAGE {
-            sizeDragImage: SIZE {
-                cx: drag_image.image_data.width,
-                cy: drag_image.image_data.height,
-            },
-            ptOffset: POINT {
-                x: point_in_rect.x as i32,
-                y: point_in_rect.y as i32,
-            },
-            hbmpDragImage: hbitmap,
-            crColorKey: COLORREF(0xFFFFFFFF),
-        };
-        unsafe {
-            helper.InitializeFromBitmap(&mut image as *mut _, &data_object)?;
-        }
+        // Explorer may keep extracting virtual files after DoDragDrop
+        // returned; progress is reported for the session.
+        let delegate = self.delegate.clone();
+        let id = self.id;
+        let data_object = DataObject::create_with_transfer_observer(
+            providers,
+            Some(Rc::new(move |progress| {
+                if let Some(delegate) = delegate.upgrade() {
+                    delegate.drag_session_data_transfer_progress(id, session_id, progress);
+                }
+            })),
+        );
+        let image_size = Self::set_drag_image(&data_object, drag_image, &request.position)?;
 
         let mut allowed_effects: u32 = 0;
         for operation in &request.configuration.allowed_operations {
             allowed_effects |= operation.to_platform().0;
         }
 
+        self.cancel_requested.set(false);
         self.current_session.replace(Some(DragSession {
             id: session_id,
             configuration: request.configuration,
+            image_size,
+            data_object: data_object.clone(),
+            start_position: request.position.clone(),
         }));
 
+        let pointer_shim = request
+            .device_kind
+            .filter(|kind| kind.is_touch_or_stylus())
+            .map(|_| PointerShim::begin(self.view, &request.position));
+
         let cancelled = Rc::new(Cell::new(false));
-        let drop_source = DropSource::create(self.weak_self.clone(), session_id, cancelled.clone());
+        let drop_source = DropSource::create(
+            self.weak_self.clone(),
+            session_id,
+            cancelled.clone(),
+            pointer_shim,
+        );
         let mut effects_out = DROPEFFECT_NONE;
-        unsafe {
-            let _ = DoDragDrop(
+        let drag_result = unsafe {
+            DoDragDrop(
                 &data_object,
                 &drop_source,
                 DROPEFFECT(allowed_effects),
                 &mut effects_out as *mut DROPEFFECT,
-            );
-        }
+            )
+        };
 
         // Data source might be still in use through IDataObjectAsyncCapability,
         // but we want to let user know that drag session ended immediately.
@@ -245,7 +308,46 @@ impl PlatformDragContext {
             } else {
                 operation
             };
-            delegate.drag_session_did_end_with_operation(self.id, session_id, operation);
+            let animates = self
+                .current_session
+                .borrow()
+                .as_ref()
+                .map(|s| {
+                    s.configuration
+                        .animates_to_starting_position_on_cancel_or_fail
+                })
+                .unwrap_or(true);
+            // Windows has no snap-back animation, but the application might
+            // still want to run its own.
+            if matches!(
+                operation,
+                DropOperation::None | DropOperation::UserCancelled
+            ) && !animates
+            {
+                let mut cursor_pos = POINT::default();
+                unsafe { GetCursorPos(&mut cursor_pos as *mut _).ok_log() };
+                let location = Point {
+                    x: cursor_pos.x as f64,
+                    y: cursor_pos.y as f64,
+                };
+                delegate.drag_session_did_cancel(self.id, session_id, location);
+            }
+            let cancel_reason = if cancelled.get() {
+                Some(DragCancelReason::Escape)
+            } else if operation != DropOperation::None {
+                None
+            } else if drag_result == DRAGDROP_S_DROP {
+                // Drop target was invoked but didn't perform any operation
+                Some(DragCancelReason::TargetRefused)
+            } else {
+                Some(DragCancelReason::NoTarget)
+            };
+            delegate.drag_session_did_end_with_operation(
+                self.id,
+                session_id,
+                operation,
+                cancel_reason,
+            );
             for c in delegate.get_platform_drop_contexts() {
                 c.local_dragging_did_end()?;
             }
@@ -255,11 +357,86 @@ impl PlatformDragContext {
         Ok(())
     }
 
-    pub fn get_local_data(&self) -> Option<Vec<Value>> {
+    /// Returns size of the image in logical pixels.
+    fn set_drag_image(
+        data_object: &IDataObject,
+        drag_image: &TargettedImage,
+        position: &Point,
+    ) -> NativeExtensionsResult<Size> {
+        let image_size = Size {
+            width: drag_image.rect.width,
+            height: drag_image.rect.height,
+        };
+        let drag_image = drag_image.with_shadow(10);
+
+        let helper: IDragSourceHelper = create_instance(&CLSID_DragDropHelper)?;
+        let hbitmap = image_data_to_hbitmap(&drag_image.image_data)?;
+        let device_pixel_ratio = drag_image.image_data.device_pixel_ratio.unwrap_or(1.0);
+        let point_in_rect = Point {
+            x: (position.x - drag_image.rect.x) * device_pixel_ratio,
+            y: (position.y - drag_image.rect.y) * device_pixel_ratio,
+        };
+
+        let mut image = SHDRAGIMAGE {
+            sizeDragImage: SIZE {
+                cx: drag_image.image_data.width,
+                cy: drag_image.image_data.height,
+            },
+            ptOffset: POINT {
+                x: point_in_rect.x as i32,
+                y: point_in_rect.y as i32,
+            },
+            hbmpDragImage: hbitmap,
+            crColorKey: COLORREF(0xFFFFFFFF),
+        };
+        unsafe {
+            helper.InitializeFromBitmap(&mut image as *mut _, data_object)?;
+        }
+        Ok(image_size)
+    }
+
+    pub fn update_drag_image(
+        &self,
+        request: &UpdateDragImageRequest,
+    ) -> NativeExtensionsResult<()> {
+        let data_object = {
+            let mut session = self.current_session.borrow_mut();
+            let session = session
+                .as_mut()
+                .filter(|s| s.id == request.session_id)
+                .ok_or(NativeExtensionsError::DragSessionNotFound)?;
+            let image = request.combined_drag_image.as_ref().ok_or_else(|| {
+                NativeExtensionsError::OtherError("Missing combined drag image".into())
+            })?;
+            session.image_size =
+                Self::set_drag_image(&session.data_object, image, &session.start_position)?;
+            session.data_object.clone()
+        };
+        // The shell keeps handle of the drag image window in the data object;
+        // the window only picks up the new bitmap when asked to.
+        let format = unsafe { RegisterClipboardFormatW(w!("DragWindow")) };
+        let window = data_object.get_data(format).ok_log();
+        if let Some(window) = window.filter(|w| w.len() >= 4) {
+            let hwnd = u32::from_ne_bytes(window[..4].try_into().unwrap());
+            unsafe { SendMessageW(HWND(hwnd as isize), DDWM_UPDATEWINDOW, WPARAM(0), LPARAM(0)) };
+        }
+        Ok(())
+    }
+
+    /// Session and local data of items of local drag.
+    pub fn get_local_session(&self) -> Option<(DragSessionId, Vec<Value>)> {
+        self.current_session
+            .borrow()
+            .as_ref()
+            .map(|s| (s.id, s.configuration.get_local_data()))
+    }
+
+    /// Size of the combined drag image (in logical pixels) for active session.
+    pub fn get_drag_image_size(&self) -> Option<Size> {
         self.current_session
             .borrow()
             .as_ref()
-            .map(|s| s.configuration.get_local_data())
+            .map(|s| s.image_size.clone())
     }
 
     pub fn is_dragging_active(&self) -> bool {
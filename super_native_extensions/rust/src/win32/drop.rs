@@ -1,8 +1,9 @@
 use std::{
     cell::{Cell, RefCell},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     rc::{Rc, Weak},
     sync::Arc,
+    time::Duration,
 };
 
 use irondash_engine_context::EngineContext;
@@ -26,8 +27,13 @@ use windows::{
         },
         UI::{
             Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK},
-            Shell::{CLSID_DragDropHelper, IDataObjectAsyncCapability, IDropTargetHelper},
-            WindowsAndMessaging::{EVENT_OBJECT_DESTROY, OBJID_WINDOW, WINEVENT_INCONTEXT},
+            Shell::{
+                CLSID_DragDropHelper, IDataObjectAsyncCapability, IDropTargetHelper, ITaskbarList3,
+                TaskbarList, TBPF_INDETERMINATE, TBPF_NOPROGRESS,
+            },
+            WindowsAndMessaging::{
+                GetAncestor, EVENT_OBJECT_DESTROY, GA_ROOT, OBJID_WINDOW, WINEVENT_INCONTEXT,
+            },
         },
     },
 };
@@ -35,18 +41,20 @@ use windows::{
 use crate::{
     api_model::{DropOperation, Point},
     drop_manager::{
-        BaseDropEvent, DropEvent, DropItem, DropSessionId, PlatformDropContextDelegate,
-        PlatformDropContextId,
+        BaseDropEvent, DropEvent, DropItem, DropSessionId, ItemPreview, ItemPreviewRequest,
+        PlatformDropContextDelegate, PlatformDropContextId,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     reader_manager::RegisteredDataReader,
     util::{DropNotifier, NextId},
+    value_promise::PromiseResult,
 };
 
 use super::{
     common::{create_instance, get_dpi_for_window},
     drag_common::DropOperationExt,
+    drop_preview::DropPreview,
     PlatformDataReader,
 };
 
@@ -58,6 +66,7 @@ pub struct PlatformDropContext {
     hook: Late<HWINEVENTHOOK>,
     next_session_id: Cell<i64>,
     current_session: RefCell<Option<Rc<Session>>>,
+    busy_sessions: RefCell<HashSet<DropSessionId>>,
 }
 
 thread_local! {
@@ -90,9 +99,59 @@ impl PlatformDropContext {
             hook: Late::new(),
             next_session_id: Cell::new(0),
             current_session: RefCell::new(None),
+            busy_sessions: RefCell::new(HashSet::new()),
         })
     }
 
+    /// Data object stays valid after the drop as long as the reader keeps it
+    /// alive; with async capability source is notified once the reader is
+    /// released.
+    pub fn supports_deferred_drop(&self) -> bool {
+        true
+    }
+
+    fn set_taskbar_progress(&self, busy: bool) -> NativeExtensionsResult<()> {
+        let taskbar: ITaskbarList3 = create_instance(&TaskbarList)?;
+        unsafe {
+            taskbar.HrInit()?;
+            let window = GetAncestor(self.view, GA_ROOT);
+            let state = if busy {
+                TBPF_INDETERMINATE
+            } else {
+                TBPF_NOPROGRESS
+            };
+            taskbar.SetProgressState(window, state)?;
+        }
+        Ok(())
+    }
+
+    /// Shows indeterminate taskbar progress while deferred drops are pending.
+    pub fn show_busy_indicator(
+        &self,
+        session_id: DropSessionId,
+        _location: &Point,
+    ) -> NativeExtensionsResult<()> {
+        let first = {
+            let mut busy_sessions = self.busy_sessions.borrow_mut();
+            busy_sessions.insert(session_id);
+            busy_sessions.len() == 1
+        };
+        if first {
+            self.set_taskbar_progress(true)?;
+        }
+        Ok(())
+    }
+
+    pub fn hide_busy_indicator(&self, session_id: DropSessionId) {
+        let last = {
+            let mut busy_sessions = self.busy_sessions.borrow_mut();
+            busy_sessions.remove(&session_id) && busy_sessions.is_empty()
+        };
+        if last {
+            self.set_taskbar_progress(false).ok_log();
+        }
+    }
+
     pub fn register_drop_formats(&self, _formats: &[String]) -> NativeExtensionsResult<()> {
         Ok(())
     }
@@ -171,6 +230,7 @@ impl PlatformDropContext {
                 self.id,
                 BaseDropEvent {
                     session_id: session.id,
+                    engine_handle: self.id.engine_handle,
                 },
             );
         }
@@ -183,6 +243,7 @@ impl PlatformDropContext {
                 self.id,
                 BaseDropEvent {
                     session_id: session.id,
+                    engine_handle: self.id.engine_handle,
                 },
             );
         }
@@ -218,14 +279,13 @@ impl PlatformDropContext {
         mask: DROPEFFECT,
         accepted_operation: Option<DropOperation>,
     ) -> NativeExtensionsResult<DropEvent> {
-        let local_data = self
+        let local_session = self
             .delegate()?
             .get_platform_drag_contexts()
             .iter()
-            .map(|c| c.get_local_data())
-            .find(|c| c.is_some())
-            .flatten()
-            .unwrap_or_default();
+            .find_map(|c| c.get_local_session());
+        let local_session_id = local_session.as_ref().map(|s| s.0);
+        let local_data = local_session.map(|s| s.1).unwrap_or_default();
 
         let mut pt = POINT { x: pt.x, y: pt.y };
         unsafe {
@@ -244,12 +304,17 @@ impl PlatformDropContext {
                         None => Vec::new(),
                     },
                     local_data: local_data.get(index).cloned().unwrap_or(Value::Null),
+                    allowed_operations: None,
                 })
             })
             .collect::<NativeExtensionsResult<_>>()?;
 
         Ok(DropEvent {
             session_id: session.id,
+            engine_handle: self.id.engine_handle,
+            region_id: None,
+            session_local_data: Value::Null,
+            local_session_id,
             location_in_view: Point {
                 x: pt.x as f64 / scaling,
                 y: pt.y as f64 / scaling,
@@ -261,6 +326,70 @@ impl PlatformDropContext {
         })
     }
 
+    const DEFAULT_FADE_OUT_DELAY: f64 = 0.330; // 20 frames at 60fps
+    const DEFAULT_FADE_OUT_DURATION: f64 = 0.150;
+
+    /// Asks Dart for preview of the dropped item. Windows only has single
+    /// (combined) drag image so preview is requested for the first item.
+    fn get_preview(
+        &self,
+        session: &Rc<Session>,
+        event: &DropEvent,
+    ) -> NativeExtensionsResult<Option<ItemPreview>> {
+        let Some(item) = event.items.first() else {
+            return Ok(None);
+        };
+        let delegate = self.delegate()?;
+        let size = delegate
+            .get_platform_drag_contexts()
+            .iter()
+            .find_map(|c| c.get_drag_image_size())
+            .unwrap_or_default();
+        let preview_promise = delegate.get_preview_for_item(
+            self.id,
+            ItemPreviewRequest {
+                session_id: session.id,
+                item_id: item.item_id,
+                size,
+                fade_out_delay: Self::DEFAULT_FADE_OUT_DELAY,
+                fade_out_duration: Self::DEFAULT_FADE_OUT_DURATION,
+            },
+        );
+        let mut poll_session = PollSession::new();
+        loop {
+            if let Some(result) = preview_promise.try_take() {
+                match result {
+                    PromiseResult::Ok { value } => return Ok(value.preview),
+                    PromiseResult::Cancelled => return Ok(None),
+                }
+            }
+            RunLoop::current()
+                .platform_run_loop
+                .poll_once(&mut poll_session);
+        }
+    }
+
+    fn show_preview(&self, preview: ItemPreview) -> NativeExtensionsResult<()> {
+        if let Some(image) = preview.destination_image {
+            DropPreview::show(
+                self.view,
+                &image,
+                &preview.destination_rect,
+                Duration::from_secs_f64(
+                    preview
+                        .fade_out_delay
+                        .unwrap_or(Self::DEFAULT_FADE_OUT_DELAY),
+                ),
+                Duration::from_secs_f64(
+                    preview
+                        .fade_out_duration
+                        .unwrap_or(Self::DEFAULT_FADE_OUT_DURATION),
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
     fn on_drag_enter(
         &self,
         pdataobj: Option<&IDataObject>,
@@ -302,10 +431,21 @@ impl PlatformDropContext {
                             }
                         }
                     }));
-                    let reader = PlatformDataReader::new_with_data_object(
-                        data_object.clone(),
-                        Some(drop_notifier),
-                    );
+                    let local_dragging = delegate
+                        .get_platform_drag_contexts()
+                        .iter()
+                        .any(|c| c.is_dragging_active());
+                    let reader = if local_dragging {
+                        PlatformDataReader::new_with_data_object(
+                            data_object.clone(),
+                            Some(drop_notifier),
+                        )
+                    } else {
+                        PlatformDataReader::new_with_foreign_data_object(
+                            data_object.clone(),
+                            Some(drop_notifier),
+                        )
+                    };
                     let registered_reader =
                         delegate.register_platform_reader(self.id, reader.clone());
                     Rc::new(Session {
@@ -400,6 +540,13 @@ impl PlatformDropContext {
                 *effect,
                 Some(session.last_operation.get()),
             )?;
+            // Preview must be requested before perform drop, session might be
+            // gone on Dart side afterwards.
+            let preview = if session.last_operation.get() != DropOperation::None {
+                self.get_preview(&session, &event).ok_log().flatten()
+            } else {
+                None
+            };
             let done = Rc::new(Cell::new(false));
             let done_clone = done.clone();
             self.delegate()?.send_perform_drop(
@@ -432,6 +579,9 @@ impl PlatformDropContext {
                     .platform_run_loop
                     .poll_once(&mut poll_session);
             }
+            if let Some(preview) = preview {
+                self.show_preview(preview).ok_log();
+            }
             self.drop_end()?;
         } else {
             *effect = DROPEFFECT_NONE;
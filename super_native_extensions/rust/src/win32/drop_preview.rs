@@ -0,0 +1,159 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use irondash_run_loop::RunLoop;
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{COLORREF, HWND, POINT, SIZE},
+        Graphics::Gdi::{
+            ClientToScreen, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, ReleaseDC,
+            SelectObject, AC_SRC_ALPHA, AC_SRC_OVER, BLENDFUNCTION, HBITMAP,
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DestroyWindow, ShowWindow, UpdateLayeredWindow, HMENU, SW_SHOWNA,
+            ULW_ALPHA, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST,
+            WS_EX_TRANSPARENT, WS_POPUP,
+        },
+    },
+};
+
+use crate::{
+    api_model::{ImageData, Rect},
+    error::NativeExtensionsResult,
+    log::OkLog,
+};
+
+use super::common::{get_dpi_for_window, image_data_to_hbitmap_premultiplied};
+
+/// Windows has no API to animate the shell drag image into a drop target.
+/// Instead, once the drop is performed, the destination image is shown in
+/// a click-through layered window placed over the destination rect and faded
+/// out after a delay, by which time the application should have rendered the
+/// dropped item in its place.
+pub struct DropPreview {
+    hwnd: HWND,
+    bitmap: HBITMAP,
+    position: POINT,
+    size: SIZE,
+    alpha: Cell<u8>,
+}
+
+impl DropPreview {
+    const FADE_OUT_STEP: Duration = Duration::from_millis(16);
+
+    /// Shows the preview. `rect` is in logical coordinates of `view`.
+    pub fn show(
+        view: HWND,
+        image: &ImageData,
+        rect: &Rect,
+        fade_out_delay: Duration,
+        fade_out_duration: Duration,
+    ) -> NativeExtensionsResult<()> {
+        let scaling = get_dpi_for_window(view) as f64 / 96.0;
+        let center = rect.center();
+        let mut position = POINT {
+            x: (center.x * scaling) as i32 - image.width / 2,
+            y: (center.y * scaling) as i32 - image.height / 2,
+        };
+        unsafe { ClientToScreen(view, &mut position as *mut _) };
+        let size = SIZE {
+            cx: image.width,
+            cy: image.height,
+        };
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_LAYERED
+                    | WS_EX_TRANSPARENT
+                    | WS_EX_TOOLWINDOW
+                    | WS_EX_TOPMOST
+                    | WS_EX_NOACTIVATE,
+                w!("STATIC"),
+                w!(""),
+                WS_POPUP,
+                position.x,
+                position.y,
+                size.cx,
+                size.cy,
+                view,
+                HMENU(0),
+                None,
+                None,
+            )
+        };
+        let preview = Rc::new(Self {
+            hwnd,
+            bitmap: image_data_to_hbitmap_premultiplied(image)?,
+            position,
+            size,
+            alpha: Cell::new(255),
+        });
+        preview.update()?;
+        unsafe { ShowWindow(hwnd, SW_SHOWNA) };
+
+        let steps = (fade_out_duration.as_secs_f64() / Self::FADE_OUT_STEP.as_secs_f64())
+            .ceil()
+            .max(1.0);
+        let alpha_step = (255.0 / steps).ceil() as u8;
+        RunLoop::current()
+            .schedule(fade_out_delay, move || {
+                preview.fade_out(alpha_step);
+            })
+            .detach();
+        Ok(())
+    }
+
+    fn update(&self) -> NativeExtensionsResult<()> {
+        let blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER as u8,
+            BlendFlags: 0,
+            SourceConstantAlpha: self.alpha.get(),
+            AlphaFormat: AC_SRC_ALPHA as u8,
+        };
+        unsafe {
+            let screen_dc = GetDC(HWND(0));
+            let mem_dc = CreateCompatibleDC(screen_dc);
+            let previous = SelectObject(mem_dc, self.bitmap);
+            let source_position = POINT::default();
+            let res = UpdateLayeredWindow(
+                self.hwnd,
+                screen_dc,
+                Some(&self.position as *const _),
+                Some(&self.size as *const _),
+                mem_dc,
+                Some(&source_position as *const _),
+                COLORREF(0),
+                Some(&blend as *const _),
+                ULW_ALPHA,
+            );
+            SelectObject(mem_dc, previous);
+            DeleteDC(mem_dc);
+            ReleaseDC(HWND(0), screen_dc);
+            res?;
+        }
+        Ok(())
+    }
+
+    fn fade_out(self: Rc<Self>, alpha_step: u8) {
+        let alpha = self.alpha.get().saturating_sub(alpha_step);
+        if alpha == 0 {
+            // Dropping last reference destroys the window.
+            return;
+        }
+        self.alpha.set(alpha);
+        self.update().ok_log();
+        RunLoop::current()
+            .schedule(Self::FADE_OUT_STEP, move || {
+                self.fade_out(alpha_step);
+            })
+            .detach();
+    }
+}
+
+impl Drop for DropPreview {
+    fn drop(&mut self) {
+        unsafe {
+            DestroyWindow(self.hwnd).ok_log();
+            DeleteObject(self.bitmap);
+        }
+    }
+}
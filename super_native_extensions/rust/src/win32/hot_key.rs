@@ -1,32 +1,174 @@
 use std::{
     cell::{Cell, RefCell},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     rc::{Rc, Weak},
+    sync::{mpsc, Mutex},
+    thread::{self, JoinHandle},
     time::Duration,
 };
 
 use irondash_message_channel::Late;
-use irondash_run_loop::{platform::MessageListener, RunLoop};
-use windows::Win32::{
-    Foundation::HWND,
-    UI::{
-        Input::KeyboardAndMouse::{
-            GetAsyncKeyState, MapVirtualKeyW, RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS,
-            MAPVK_VSC_TO_VK, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+use irondash_run_loop::{platform::MessageListener, RunLoop, RunLoopSender};
+use once_cell::sync::Lazy;
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
+        System::{LibraryLoader::GetModuleHandleW, Threading::GetCurrentThreadId},
+        UI::{
+            Input::KeyboardAndMouse::{
+                GetAsyncKeyState, MapVirtualKeyW, RegisterHotKey, UnregisterHotKey,
+                HOT_KEY_MODIFIERS, MAPVK_VSC_TO_VK, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT,
+                MOD_WIN, VIRTUAL_KEY, VK_CONTROL, VK_F13, VK_F14, VK_F15, VK_F16, VK_F17, VK_F18,
+                VK_F19, VK_F20, VK_F21, VK_F22, VK_F23, VK_F24, VK_LWIN, VK_MEDIA_NEXT_TRACK,
+                VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK, VK_MEDIA_STOP, VK_MENU, VK_RWIN,
+                VK_SHIFT, VK_VOLUME_DOWN, VK_VOLUME_MUTE, VK_VOLUME_UP,
+            },
+            WindowsAndMessaging::{
+                CallNextHookEx, GetMessageW, PeekMessageW, PostThreadMessageW, SetWindowsHookExW,
+                UnhookWindowsHookEx, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, MSG, PM_NOREMOVE,
+                WH_KEYBOARD_LL, WM_HOTKEY, WM_KEYDOWN, WM_QUIT, WM_SYSKEYDOWN,
+            },
         },
-        WindowsAndMessaging::WM_HOTKEY,
     },
 };
 
 use crate::{
-    error::NativeExtensionsResult,
-    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate, SpecialKey},
 };
 
+fn special_key_to_virtual_key(key: SpecialKey) -> VIRTUAL_KEY {
+    match key {
+        SpecialKey::MediaPlayPause => VK_MEDIA_PLAY_PAUSE,
+        SpecialKey::MediaStop => VK_MEDIA_STOP,
+        SpecialKey::MediaTrackNext => VK_MEDIA_NEXT_TRACK,
+        SpecialKey::MediaTrackPrevious => VK_MEDIA_PREV_TRACK,
+        SpecialKey::AudioVolumeUp => VK_VOLUME_UP,
+        SpecialKey::AudioVolumeDown => VK_VOLUME_DOWN,
+        SpecialKey::AudioVolumeMute => VK_VOLUME_MUTE,
+        SpecialKey::F13 => VK_F13,
+        SpecialKey::F14 => VK_F14,
+        SpecialKey::F15 => VK_F15,
+        SpecialKey::F16 => VK_F16,
+        SpecialKey::F17 => VK_F17,
+        SpecialKey::F18 => VK_F18,
+        SpecialKey::F19 => VK_F19,
+        SpecialKey::F20 => VK_F20,
+        SpecialKey::F21 => VK_F21,
+        SpecialKey::F22 => VK_F22,
+        SpecialKey::F23 => VK_F23,
+        SpecialKey::F24 => VK_F24,
+    }
+}
+
+/// Virtual key of the hot key; platform code is a scan code.
+fn virtual_key(request: &HotKeyCreateRequest) -> u32 {
+    match request.special_key {
+        Some(key) => special_key_to_virtual_key(key).0 as u32,
+        None => unsafe { MapVirtualKeyW(request.platform_code as u32, MAPVK_VSC_TO_VK) },
+    }
+}
+
+/// Hot key detected through the low level keyboard hook.
+struct HookedHotKey {
+    handle: HotKeyHandle,
+    vk: u32,
+    request: HotKeyCreateRequest,
+}
+
+/// Hooked hot keys, shared with the hook thread which decides whether to
+/// swallow the key event.
+struct HookState {
+    hot_keys: Vec<HookedHotKey>,
+    /// Hooked hot keys currently held down.
+    down: HashSet<HotKeyHandle>,
+    /// Main thread, where hot key events are delivered.
+    sender: Option<RunLoopSender>,
+}
+
+static HOOK_STATE: Lazy<Mutex<HookState>> = Lazy::new(|| {
+    Mutex::new(HookState {
+        hot_keys: Vec::new(),
+        down: HashSet::new(),
+        sender: None,
+    })
+});
+
+fn hook_state() -> std::sync::MutexGuard<'static, HookState> {
+    HOOK_STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+thread_local! {
+    /// Manager receiving events from the low level keyboard hook.
+    static HOOK_MANAGER: RefCell<Weak<PlatformHotKeyManager>> = RefCell::new(Weak::new());
+}
+
+/// Low level hooks are called on the thread that installed them, which must
+/// keep pumping messages. Running the hook on its own thread keeps UI stalls
+/// from delaying keyboard input system-wide, and from getting the hook
+/// silently removed once it exceeds `LowLevelHooksTimeout`.
+struct HookThread {
+    thread_id: u32,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HookThread {
+    fn start() -> NativeExtensionsResult<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let hook = unsafe {
+                GetModuleHandleW(PCWSTR::null()).and_then(|module| {
+                    SetWindowsHookExW(
+                        WH_KEYBOARD_LL,
+                        Some(low_level_keyboard_proc),
+                        HINSTANCE(module.0),
+                        0,
+                    )
+                })
+            };
+            let hook = match hook {
+                Ok(hook) => hook,
+                Err(err) => {
+                    sender.send(Err(err)).ok();
+                    return;
+                }
+            };
+            let mut message = MSG::default();
+            unsafe {
+                // Make sure the message queue exists before WM_QUIT can be posted.
+                PeekMessageW(&mut message, HWND::default(), 0, 0, PM_NOREMOVE);
+                sender.send(Ok(GetCurrentThreadId())).ok();
+                while GetMessageW(&mut message, HWND::default(), 0, 0).0 > 0 {}
+                UnhookWindowsHookEx(hook).ok();
+            }
+        });
+        let thread_id = receiver.recv().map_err(|_| {
+            NativeExtensionsError::OtherError("Keyboard hook thread exited".into())
+        })??;
+        Ok(Self {
+            thread_id,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for HookThread {
+    fn drop(&mut self) {
+        unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0)).ok() };
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
 pub struct PlatformHotKeyManager {
     delegate: Weak<dyn HotKeyManagerDelegate>,
     next_id: Cell<i32>,
     hot_keys: RefCell<HashMap<i32, (HotKeyHandle, HotKeyCreateRequest)>>,
+    /// Registered hot keys currently polled for release.
+    waiting_for_release: RefCell<HashSet<HotKeyHandle>>,
+    hook_thread: RefCell<Option<HookThread>>,
     weak_self: Late<Weak<Self>>,
 }
 
@@ -36,6 +178,8 @@ impl PlatformHotKeyManager {
             delegate,
             next_id: Cell::new(65536),
             hot_keys: RefCell::new(HashMap::new()),
+            waiting_for_release: RefCell::new(HashSet::new()),
+            hook_thread: RefCell::new(None),
             weak_self: Late::new(),
         }
     }
@@ -56,6 +200,9 @@ impl PlatformHotKeyManager {
         handle: HotKeyHandle,
         request: HotKeyCreateRequest,
     ) -> NativeExtensionsResult<()> {
+        if request.low_level_hook == Some(true) {
+            return self.create_hooked_hot_key(handle, request);
+        }
         let mut modifiers = HOT_KEY_MODIFIERS::default();
         if request.alt {
             modifiers |= MOD_ALT;
@@ -69,18 +216,23 @@ impl PlatformHotKeyManager {
         if request.meta {
             modifiers |= MOD_WIN;
         }
-        modifiers |= MOD_NOREPEAT;
+        if request.suppress_repeat() {
+            modifiers |= MOD_NOREPEAT;
+        }
         let id = self.next_id.get();
         self.next_id.replace(id + 1);
         unsafe {
-            let vk = MapVirtualKeyW(request.platform_code as u32, MAPVK_VSC_TO_VK);
-            RegisterHotKey(Self::hwnd(), id, modifiers, vk)?;
+            RegisterHotKey(Self::hwnd(), id, modifiers, virtual_key(&request))?;
         }
         self.hot_keys.borrow_mut().insert(id, (handle, request));
         Ok(())
     }
 
     pub fn destroy_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<()> {
+        self.waiting_for_release.borrow_mut().remove(&handle);
+        if self.destroy_hooked_hot_key(handle)? {
+            return Ok(());
+        }
         let mut hot_keys = self.hot_keys.borrow_mut();
 
         let hot_key_id = hot_keys
@@ -95,20 +247,83 @@ impl PlatformHotKeyManager {
         Ok(())
     }
 
+    fn create_hooked_hot_key(
+        &self,
+        handle: HotKeyHandle,
+        request: HotKeyCreateRequest,
+    ) -> NativeExtensionsResult<()> {
+        if self.hook_thread.borrow().is_none() {
+            HOOK_MANAGER.with(|m| m.replace(self.weak_self.clone()));
+            hook_state().sender = Some(RunLoop::current().new_sender());
+            let hook_thread = HookThread::start()?;
+            self.hook_thread.replace(Some(hook_thread));
+        }
+        let vk = virtual_key(&request);
+        hook_state().hot_keys.push(HookedHotKey {
+            handle,
+            vk,
+            request,
+        });
+        Ok(())
+    }
+
+    /// Returns whether the handle belonged to hooked hot key. The hook is
+    /// removed with the last hooked hot key.
+    fn destroy_hooked_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<bool> {
+        let is_empty = {
+            let mut state = hook_state();
+            let Some(index) = state.hot_keys.iter().position(|k| k.handle == handle) else {
+                return Ok(false);
+            };
+            state.hot_keys.remove(index);
+            state.down.remove(&handle);
+            state.hot_keys.is_empty()
+        };
+        if is_empty {
+            // Joins the hook thread, must not hold the state lock.
+            self.hook_thread.take();
+        }
+        Ok(true)
+    }
+
+    fn is_down(vk: VIRTUAL_KEY) -> bool {
+        unsafe { GetAsyncKeyState(vk.0 as i32) < 0 }
+    }
+
+    fn modifiers_match(request: &HotKeyCreateRequest) -> bool {
+        Self::is_down(VK_MENU) == request.alt
+            && Self::is_down(VK_CONTROL) == request.control
+            && Self::is_down(VK_SHIFT) == request.shift
+            && (Self::is_down(VK_LWIN) || Self::is_down(VK_RWIN)) == request.meta
+    }
+
+    fn on_hooked_hot_key(&self, handle: HotKeyHandle, is_down: bool) {
+        if let Some(delegate) = self.delegate.upgrade() {
+            if is_down {
+                delegate.on_hot_key_pressed(handle);
+            } else {
+                delegate.on_hot_key_released(handle);
+            }
+        }
+    }
+
     fn wait_until_release(
+        weak_self: Weak<Self>,
         request: HotKeyCreateRequest,
         handle: HotKeyHandle,
         delegate: Rc<dyn HotKeyManagerDelegate>,
     ) {
-        let vk = unsafe { MapVirtualKeyW(request.platform_code as u32, MAPVK_VSC_TO_VK) };
-        let key_state = unsafe { GetAsyncKeyState(vk as i32) };
+        let key_state = unsafe { GetAsyncKeyState(virtual_key(&request) as i32) };
         if key_state < 0 {
             RunLoop::current()
                 .schedule(Duration::from_millis(10), move || {
-                    Self::wait_until_release(request, handle, delegate);
+                    Self::wait_until_release(weak_self, request, handle, delegate);
                 })
                 .detach();
         } else {
+            if let Some(this) = weak_self.upgrade() {
+                this.waiting_for_release.borrow_mut().remove(&handle);
+            }
             delegate.on_hot_key_released(handle);
         }
     }
@@ -118,13 +333,71 @@ impl PlatformHotKeyManager {
         let delegate = self.delegate.upgrade();
         if let (Some((handle, request)), Some(delegate)) = (hot_key, delegate) {
             delegate.on_hot_key_pressed(handle);
-            Self::wait_until_release(request, handle, delegate);
+            // Without MOD_NOREPEAT WM_HOTKEY is repeated while the key is held.
+            if self.waiting_for_release.borrow_mut().insert(handle) {
+                Self::wait_until_release(self.weak_self.clone(), request, handle, delegate);
+            }
+        }
+    }
+}
+
+/// Called on the hook thread. Returns whether the event belongs to hot key
+/// and should not be passed on. The manager is notified on main thread, the
+/// hook must return quickly.
+fn on_low_level_key(vk: u32, is_down: bool) -> bool {
+    let mut state = hook_state();
+    let handle = state
+        .hot_keys
+        .iter()
+        .find(|k| {
+            // Modifiers may be released before the key itself.
+            k.vk == vk
+                && (state.down.contains(&k.handle)
+                    || PlatformHotKeyManager::modifiers_match(&k.request))
+        })
+        .map(|k| k.handle);
+    let Some(handle) = handle else {
+        return false;
+    };
+    if is_down {
+        state.down.insert(handle);
+    } else if !state.down.remove(&handle) {
+        return false;
+    }
+    if let Some(sender) = &state.sender {
+        sender.send(move || {
+            let manager = HOOK_MANAGER.with(|m| m.borrow().upgrade());
+            if let Some(manager) = manager {
+                manager.on_hooked_hot_key(handle, is_down);
+            }
+        });
+    }
+    true
+}
+
+unsafe extern "system" fn low_level_keyboard_proc(
+    code: i32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    if code == HC_ACTION as i32 {
+        let event = &*(l_param.0 as *const KBDLLHOOKSTRUCT);
+        let is_down = matches!(w_param.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN);
+        if on_low_level_key(event.vkCode, is_down) {
+            return LRESULT(1);
         }
     }
+    CallNextHookEx(HHOOK::default(), code, w_param, l_param)
 }
 
 impl Drop for PlatformHotKeyManager {
     fn drop(&mut self) {
+        if self.hook_thread.take().is_some() {
+            let mut state = hook_state();
+            state.hot_keys.clear();
+            state.down.clear();
+            state.sender = None;
+        }
         let message_listener: Weak<dyn MessageListener> = self.weak_self.clone();
         if let Ok(run_loop) = RunLoop::try_current() {
             run_loop
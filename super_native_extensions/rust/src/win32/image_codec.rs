@@ -0,0 +1,39 @@
+//! Platform codec for the `image_transcode` feature, based on WIC.
+
+use windows::Win32::{System::Ole::CF_TIFF, UI::Shell::SHCreateMemStream};
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+use super::{common::format_to_string, image_conversion::convert_to_png, ComInitializer};
+
+pub fn png_format() -> String {
+    "PNG".into()
+}
+
+/// Bitmaps (`CF_DIB`, `CF_DIBV5`) are converted by the reader regardless of
+/// the feature.
+pub fn decodable_formats() -> Vec<String> {
+    vec![
+        "JFIF".into(),
+        "image/jpeg".into(),
+        "image/gif".into(),
+        format_to_string(CF_TIFF.0 as u32),
+    ]
+}
+
+/// Data object already synthesizes `CF_DIB` and `CF_DIBV5` from PNG.
+pub fn encodable_formats() -> Vec<String> {
+    Vec::new()
+}
+
+/// Called on a worker thread.
+pub fn decode_to_png(data: &[u8]) -> NativeExtensionsResult<Vec<u8>> {
+    let _com = ComInitializer::new();
+    let stream = unsafe { SHCreateMemStream(Some(data)) }
+        .ok_or_else(|| NativeExtensionsError::OtherError("Failed to create stream".into()))?;
+    Ok(convert_to_png(stream)?)
+}
+
+pub fn encode_from_png(_data: &[u8], _format: &str) -> NativeExtensionsResult<Vec<u8>> {
+    Err(NativeExtensionsError::UnsupportedOperation)
+}
@@ -6,7 +6,8 @@ use windows::{
         Foundation::{HGLOBAL, VARIANT_BOOL},
         Graphics::Imaging::{
             CLSID_WICImagingFactory, GUID_ContainerFormatBmp, GUID_ContainerFormatPng,
-            IWICBitmapFrameEncode, IWICImagingFactory, WICBitmapEncoderNoCache,
+            GUID_WICPixelFormat32bppBGRA, IWICBitmapFrameEncode, IWICBitmapSource,
+            IWICImagingFactory, WICBitmapEncoderNoCache,
         },
         System::{
             Com::{
@@ -29,15 +30,44 @@ pub fn convert_to_png(input_stream: IStream) -> windows::core::Result<Vec<u8>> {
     unsafe {
         let decoder =
             factory.CreateDecoderFromStream(&input_stream, null_mut(), Default::default())?;
+        let frame = decoder.GetFrame(0)?;
+        encode_png(&factory, &frame)
+    }
+}
+
+/// Encodes 32bpp BGRA pixel data to PNG.
+pub fn encode_bgra_to_png(
+    width: u32,
+    height: u32,
+    stride: u32,
+    data: &[u8],
+) -> windows::core::Result<Vec<u8>> {
+    let factory: IWICImagingFactory = create_instance(&CLSID_WICImagingFactory)?;
+    unsafe {
+        let bitmap = factory.CreateBitmapFromMemory(
+            width,
+            height,
+            &GUID_WICPixelFormat32bppBGRA,
+            stride,
+            data,
+        )?;
+        encode_png(&factory, &bitmap)
+    }
+}
+
+unsafe fn encode_png(
+    factory: &IWICImagingFactory,
+    source: &IWICBitmapSource,
+) -> windows::core::Result<Vec<u8>> {
+    {
         let encoder = factory.CreateEncoder(&GUID_ContainerFormatPng, null_mut())?;
         let output_stream = CreateStreamOnHGlobal(HGLOBAL::default(), true)?;
         encoder.Initialize(&output_stream, WICBitmapEncoderNoCache)?;
-        let frame = decoder.GetFrame(0)?;
         let mut encoder_frame = Option::<IWICBitmapFrameEncode>::None;
         encoder.CreateNewFrame(&mut encoder_frame as *mut _, null_mut())?;
         let encoder_frame = encoder_frame.unwrap();
         encoder_frame.Initialize(None)?;
-        encoder_frame.WriteSource(&frame, std::ptr::null_mut())?;
+        encoder_frame.WriteSource(source, std::ptr::null_mut())?;
         encoder_frame.Commit()?;
         encoder.Commit()?;
         let hglobal = GetHGlobalFromStream(&output_stream)?;
@@ -11,8 +11,9 @@ use windows::{
         Foundation::BOOL,
         UI::{
             Input::KeyboardAndMouse::{
-                GetKeyboardLayout, GetKeyboardLayoutList, MapVirtualKeyW, ToUnicodeEx,
-                MAPVK_VK_TO_VSC, MAPVK_VSC_TO_VK, VK_CONTROL, VK_MENU, VK_SHIFT, VK_SPACE,
+                GetKeyboardLayout, GetKeyboardLayoutList, GetKeyboardLayoutNameW, MapVirtualKeyW,
+                ToUnicodeEx, MAPVK_VK_TO_VSC, MAPVK_VSC_TO_VK, VK_CONTROL, VK_MENU, VK_SHIFT,
+                VK_SPACE,
             },
             TextServices::{
                 CLSID_TF_InputProcessorProfiles, ITfInputProcessorProfiles,
@@ -60,6 +61,13 @@ impl PlatformKeyboardLayout {
         )
     }
 
+    pub fn get_current_layout_name(&self) -> Option<String> {
+        let mut name = [0u16; 9]; // KL_NAMELENGTH
+        unsafe { GetKeyboardLayoutNameW(&mut name) }.ok_log()?;
+        let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+        Some(String::from_utf16_lossy(&name[..len]))
+    }
+
     fn create_keyboard_layout(&self) -> KeyboardLayout {
         let key_map = get_key_map();
 
@@ -0,0 +1,207 @@
+//! Enhanced (EMF) and legacy (WMF) metafile clipboard formats.
+//!
+//! Metafiles are transferred as GDI handles (`TYMED_ENHMF`, `TYMED_MFPICT`)
+//! rather than global memory. Dart side always sees EMF bytes; legacy
+//! `CF_METAFILEPICT` content is converted to EMF because bare WMF data does
+//! not carry picture dimensions.
+
+use std::{mem::size_of, ptr::null_mut, slice};
+
+use windows::Win32::{
+    Foundation::{E_FAIL, E_INVALIDARG, HGLOBAL, RECT},
+    Graphics::Gdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteEnhMetaFile, DeleteObject,
+        GetEnhMetaFileBits, GetEnhMetaFileHeader, GetMetaFileBitsEx, PatBlt, PlayEnhMetaFile,
+        SelectObject, SetEnhMetaFileBits, SetWinMetaFileBits, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+        DIB_RGB_COLORS, ENHMETAHEADER, HENHMETAFILE, WHITENESS,
+    },
+    System::{
+        Com::{IDataObject, STGMEDIUM, TYMED, TYMED_ENHMF, TYMED_MFPICT},
+        Memory::{GlobalLock, GlobalUnlock},
+        Ole::{ReleaseStgMedium, CF_ENHMETAFILE, CF_METAFILEPICT, METAFILEPICT},
+    },
+};
+
+use super::{common::make_format_with_tymed, image_conversion::encode_bgra_to_png};
+
+/// Largest bitmap (in pixels) a metafile is rendered to.
+const MAX_PIXELS: i64 = 64 * 1024 * 1024;
+
+pub fn is_metafile_format(format: u32) -> bool {
+    format == CF_ENHMETAFILE.0 as u32 || format == CF_METAFILEPICT.0 as u32
+}
+
+/// Tymed used to transfer given metafile format.
+pub fn metafile_tymed(format: u32) -> TYMED {
+    if format == CF_ENHMETAFILE.0 as u32 {
+        TYMED_ENHMF
+    } else {
+        TYMED_MFPICT
+    }
+}
+
+/// Owned enhanced metafile handle.
+pub struct EnhMetaFile(HENHMETAFILE);
+
+impl Drop for EnhMetaFile {
+    fn drop(&mut self) {
+        unsafe { DeleteEnhMetaFile(self.0) };
+    }
+}
+
+impl EnhMetaFile {
+    pub fn from_bits(data: &[u8]) -> windows::core::Result<Self> {
+        let handle = unsafe { SetEnhMetaFileBits(data) };
+        if handle.is_invalid() {
+            Err(windows::core::Error::from_win32())
+        } else {
+            Ok(Self(handle))
+        }
+    }
+
+    /// Gives up ownership of the handle, i.e. when passing it in STGMEDIUM.
+    pub fn into_raw(self) -> HENHMETAFILE {
+        let handle = self.0;
+        std::mem::forget(self);
+        handle
+    }
+
+    pub fn bits(&self) -> Vec<u8> {
+        let len = unsafe { GetEnhMetaFileBits(self.0, None) };
+        let mut data = vec![0u8; len as usize];
+        unsafe { GetEnhMetaFileBits(self.0, Some(&mut data)) };
+        data
+    }
+
+    /// Picture size in pixels at 96 DPI.
+    fn natural_size(&self) -> (i32, i32) {
+        let mut header = ENHMETAHEADER::default();
+        unsafe {
+            GetEnhMetaFileHeader(
+                self.0,
+                size_of::<ENHMETAHEADER>() as u32,
+                Some(&mut header as *mut _),
+            )
+        };
+        // rclFrame is in .01 millimeter units
+        let frame = header.rclFrame;
+        let to_pixels = |v: i32| (v as f64 * 96.0 / 2540.0).round() as i32;
+        (
+            to_pixels(frame.right - frame.left).max(1),
+            to_pixels(frame.bottom - frame.top).max(1),
+        )
+    }
+
+    /// Renders metafile on white background. If only one dimension is
+    /// specified the other one is derived from picture aspect ratio.
+    pub fn rasterize_to_png(
+        &self,
+        width: Option<i32>,
+        height: Option<i32>,
+    ) -> windows::core::Result<Vec<u8>> {
+        let (natural_width, natural_height) = self.natural_size();
+        let aspect = natural_width as f64 / natural_height as f64;
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) => (width, (width as f64 / aspect).round() as i32),
+            (None, Some(height)) => ((height as f64 * aspect).round() as i32, height),
+            (None, None) => (natural_width, natural_height),
+        };
+        let (width, height) = (width.max(1), height.max(1));
+        // Derived or natural size of a malformed picture can be arbitrarily
+        // large.
+        if width as i64 * height as i64 > MAX_PIXELS {
+            return Err(E_INVALIDARG.into());
+        }
+        unsafe {
+            let dc = CreateCompatibleDC(None);
+            let info = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: width,
+                    biHeight: -height, // top-down
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut bits = null_mut();
+            let bitmap = CreateDIBSection(dc, &info, DIB_RGB_COLORS, &mut bits, None, 0);
+            let bitmap = match bitmap {
+                Ok(bitmap) => bitmap,
+                Err(error) => {
+                    DeleteDC(dc);
+                    return Err(error);
+                }
+            };
+            let previous = SelectObject(dc, bitmap);
+            PatBlt(dc, 0, 0, width, height, WHITENESS);
+            let rect = RECT {
+                left: 0,
+                top: 0,
+                right: width,
+                bottom: height,
+            };
+            let played = PlayEnhMetaFile(dc, self.0, &rect).as_bool();
+            let stride = width as usize * 4;
+            let mut data: Vec<u8> =
+                slice::from_raw_parts(bits as *const u8, stride * height as usize).into();
+            SelectObject(dc, previous);
+            DeleteObject(bitmap);
+            DeleteDC(dc);
+            if !played {
+                return Err(E_FAIL.into());
+            }
+            // GDI leaves alpha channel zeroed
+            for pixel in data.chunks_exact_mut(4) {
+                pixel[3] = 0xFF;
+            }
+            encode_bgra_to_png(width as u32, height as u32, stride as u32, &data)
+        }
+    }
+}
+
+/// Converts legacy METAFILEPICT global to enhanced metafile.
+unsafe fn enh_metafile_from_metafile_pict(global: HGLOBAL) -> windows::core::Result<EnhMetaFile> {
+    let pict = GlobalLock(global) as *const METAFILEPICT;
+    if pict.is_null() {
+        return Err(windows::core::Error::from_win32());
+    }
+    let pict_copy = *pict;
+    GlobalUnlock(global).ok();
+    let len = GetMetaFileBitsEx(pict_copy.hMF, None);
+    let mut data = vec![0u8; len as usize];
+    GetMetaFileBitsEx(pict_copy.hMF, Some(&mut data));
+    let handle = SetWinMetaFileBits(&data, None, Some(&pict_copy));
+    if handle.is_invalid() {
+        Err(windows::core::Error::from_win32())
+    } else {
+        Ok(EnhMetaFile(handle))
+    }
+}
+
+/// Retrieves metafile in given format from data object as EMF.
+pub fn get_enh_metafile(
+    data_object: &IDataObject,
+    format: u32,
+) -> windows::core::Result<EnhMetaFile> {
+    let format_etc = make_format_with_tymed(format, metafile_tymed(format));
+    unsafe {
+        let mut medium: STGMEDIUM = data_object.GetData(&format_etc)?;
+        let res = if medium.tymed == TYMED_ENHMF.0 as u32 {
+            // Copy, the original handle is released with medium
+            let original = EnhMetaFile(medium.u.hEnhMetaFile);
+            let copy = EnhMetaFile::from_bits(&original.bits());
+            std::mem::forget(original);
+            copy
+        } else if medium.tymed == TYMED_MFPICT.0 as u32 {
+            enh_metafile_from_metafile_pict(HGLOBAL(medium.u.hMetaFilePict as _))
+        } else {
+            Err(E_FAIL.into())
+        };
+        ReleaseStgMedium(&mut medium as *mut STGMEDIUM);
+        res
+    }
+}
@@ -1,17 +1,31 @@
+mod broker;
+mod clipboard_monitor;
+mod clipboard_watcher;
 mod common;
 mod data_object;
 mod data_provider;
 mod drag;
 mod drag_common;
 mod drop;
+mod drop_preview;
 mod hot_key;
+#[cfg(feature = "image_transcode")]
+pub mod image_codec;
 mod image_conversion;
 mod keyboard_layout;
 mod menu;
+mod metafile;
 mod ole_initializer;
 mod reader;
+pub mod remote_session;
+pub mod shared_texture;
+mod touch_drag;
+mod tray_icon;
 mod virtual_file_stream;
 
+pub use clipboard_monitor::*;
+pub use clipboard_watcher::*;
+pub use data_object::check_virtual_folder;
 pub use data_provider::*;
 pub use drag::*;
 pub use drop::*;
@@ -20,3 +34,4 @@ pub use keyboard_layout::*;
 pub use menu::*;
 pub use ole_initializer::*;
 pub use reader::*;
+pub use tray_icon::*;
@@ -1,4 +1,7 @@
-use windows::Win32::System::Ole::{OleInitialize, OleUninitialize};
+use windows::Win32::System::{
+    Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED},
+    Ole::{OleInitialize, OleUninitialize},
+};
 
 pub struct OleInitializer {
     need_uninit: bool,
@@ -22,3 +25,28 @@ impl Drop for OleInitializer {
         }
     }
 }
+
+/// Initializes COM (multithreaded apartment) on worker threads that use WIC
+/// or other COM objects.
+pub struct ComInitializer {
+    need_uninit: bool,
+}
+
+impl ComInitializer {
+    pub fn new() -> ComInitializer {
+        let res = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+        ComInitializer {
+            need_uninit: res.is_ok(),
+        }
+    }
+}
+
+impl Drop for ComInitializer {
+    fn drop(&mut self) {
+        if self.need_uninit {
+            unsafe {
+                CoUninitialize();
+            }
+        }
+    }
+}
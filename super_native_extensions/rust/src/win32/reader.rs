@@ -8,70 +8,133 @@ use irondash_run_loop::{
 use rand::{distributions::Alphanumeric, Rng};
 use std::{
     cell::{Cell, RefCell},
-    ffi::CStr,
+    collections::{HashMap, HashSet},
+    ffi::{c_void, CStr},
     fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
     rc::{Rc, Weak},
     slice,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        atomic::{AtomicI64, Ordering},
+        mpsc, Arc, Mutex,
     },
     thread,
 };
 use threadpool::ThreadPool;
 use windows::{
-    core::{w, HSTRING},
+    core::{w, ComInterface, Interface, HSTRING, PWSTR},
     Win32::{
-        Foundation::S_OK,
+        Foundation::{CloseHandle, S_OK},
         Storage::FileSystem::{
             SetFileAttributesW, FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_HIDDEN,
             FILE_ATTRIBUTE_TEMPORARY,
         },
         System::{
             Com::{
-                IDataObject, IStream, STATFLAG_NONAME, STATSTG, STGMEDIUM, STREAM_SEEK_SET, TYMED,
+                CoGetInterfaceAndReleaseStream, CoMarshalInterThreadInterfaceInStream, IDataObject,
+                IStream, FORMATETC, STATFLAG_NONAME, STATSTG, STGMEDIUM, STREAM_SEEK_SET, TYMED,
                 TYMED_HGLOBAL, TYMED_ISTREAM,
             },
-            DataExchange::RegisterClipboardFormatW,
+            DataExchange::{
+                GetClipboardOwner, GetClipboardSequenceNumber, IsClipboardFormatAvailable,
+                RegisterClipboardFormatW,
+            },
             Memory::{GlobalLock, GlobalSize, GlobalUnlock},
             Ole::{
-                OleGetClipboard, ReleaseStgMedium, CF_DIB, CF_DIBV5, CF_HDROP, CF_TIFF,
-                CF_UNICODETEXT,
+                OleGetClipboard, ReleaseStgMedium, CF_DIB, CF_DIBV5, CF_ENHMETAFILE, CF_HDROP,
+                CF_METAFILEPICT, CF_TIFF, CF_UNICODETEXT,
+            },
+            Threading::{
+                GetCurrentProcessId, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+                PROCESS_QUERY_LIMITED_INFORMATION,
             },
         },
-        UI::Shell::{
-            SHCreateMemStream, CFSTR_FILECONTENTS, CFSTR_FILEDESCRIPTOR, DROPFILES,
-            FILEDESCRIPTORW, FILEGROUPDESCRIPTORW,
+        UI::{
+            Shell::{
+                SHCreateMemStream, CFSTR_FILECONTENTS, CFSTR_FILEDESCRIPTOR, DROPFILES,
+                FD_FILESIZE, FD_WRITESTIME, FILEDESCRIPTORW, FILEGROUPDESCRIPTORW,
+            },
+            WindowsAndMessaging::GetWindowThreadProcessId,
         },
     },
 };
 
 use crate::{
+    cf_html::{extract_fragment, CF_HTML_FORMAT, HTML_FORMAT},
     error::{NativeExtensionsError, NativeExtensionsResult},
+    link_detection::{DetectedEntity, EntityKind},
     log::OkLog,
-    platform_impl::platform::common::make_format_with_tymed_index,
-    reader_manager::{ReadProgress, VirtualFileReader},
+    media_info::MediaMetadata,
+    platform_impl::platform::common::{make_format_with_tymed, make_format_with_tymed_index},
+    reader_manager::{
+        ExternalReaderSource, FormatConversion, ItemFormatConversion, ItemMetadata,
+        ReadProgressHandle, VirtualFileReader,
+    },
     util::{get_target_path, DropNotifier, Movable},
 };
 
 use super::{
+    broker::{self, BrokerSession},
     common::{
         copy_stream_to_file, extract_formats, format_from_string, format_to_string,
-        read_stream_fully,
+        read_stream_fully, register_format_alias, run_on_worker_thread,
     },
     data_object::{DataObject, GetData},
     image_conversion::convert_to_png,
+    metafile::{get_enh_metafile, is_metafile_format, metafile_tymed, EnhMetaFile},
+    remote_session, ComInitializer,
 };
 
+/// Executable path of the process owning the clipboard.
+fn clipboard_owner_executable() -> Option<String> {
+    unsafe {
+        let owner = GetClipboardOwner();
+        if owner.0 == 0 {
+            return None;
+        }
+        let mut process_id = 0u32;
+        GetWindowThreadProcessId(owner, Some(&mut process_id as *mut _));
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let res = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len as *mut _,
+        );
+        CloseHandle(process).ok_log();
+        res.ok()?;
+        Some(String::from_utf16_lossy(&buf[..len as usize]))
+    }
+}
+
+fn clipboard_owned_by_current_process() -> bool {
+    unsafe {
+        let owner = GetClipboardOwner();
+        if owner.0 == 0 {
+            return false;
+        }
+        let mut process_id = 0u32;
+        GetWindowThreadProcessId(owner, Some(&mut process_id as *mut _));
+        process_id == GetCurrentProcessId()
+    }
+}
+
 pub struct PlatformDataReader {
     data_object: IDataObject,
+    broker: Option<BrokerSession>,
+    /// Whether data object is provided by another application.
+    foreign: bool,
     _drop_notifier: Option<Arc<DropNotifier>>,
+    /// Executable of the clipboard owner at the time reader was created.
+    source_application: Option<String>,
     supports_async: Cell<bool>,
     formats_raw: RefCell<Option<Vec<u32>>>,
     file_descriptors: RefCell<Option<Option<Vec<FileDescriptor>>>>,
     hdrop: RefCell<Option<Option<Vec<String>>>>,
+    disabled_conversions: RefCell<HashSet<FormatConversion>>,
 }
 
 /// Virtual file descriptor
@@ -80,6 +143,9 @@ struct FileDescriptor {
     name: String,
     format: String,
     index: usize,
+    size: Option<i64>,
+    /// Milliseconds since Unix epoch.
+    last_modified: Option<i64>,
 }
 
 impl PlatformDataReader {
@@ -110,11 +176,18 @@ impl PlatformDataReader {
         match formats {
             Some(formats) => Ok(formats),
             None => {
-                let formats: Vec<u32> = extract_formats(&self.data_object)?
+                let formats: Vec<u32> = self
+                    .extract_formats()?
                     .iter()
                     .filter_map(|f| {
+                        let format = f.cfFormat as u32;
+                        // GDI handles can not be transferred from broker process.
+                        let metafile = self.broker.is_none()
+                            && is_metafile_format(format)
+                            && (f.tymed & metafile_tymed(format).0 as u32) != 0;
                         if (f.tymed & TYMED_HGLOBAL.0 as u32) != 0
                             || (f.tymed & TYMED_ISTREAM.0 as u32) != 0
+                            || metafile
                         {
                             Some(f.cfFormat as u32)
                         } else {
@@ -129,6 +202,17 @@ impl PlatformDataReader {
     }
 
     fn need_to_synthesize_png(&self) -> NativeExtensionsResult<bool> {
+        if self
+            .disabled_conversions
+            .borrow()
+            .contains(&FormatConversion::BitmapToPng)
+        {
+            return Ok(false);
+        }
+        self.can_synthesize_png()
+    }
+
+    fn can_synthesize_png(&self) -> NativeExtensionsResult<bool> {
         let png = unsafe { RegisterClipboardFormatW(w!("PNG")) };
         let formats = self.data_object_formats_raw()?;
         let has_dib =
@@ -137,12 +221,35 @@ impl PlatformDataReader {
         Ok(has_dib && !has_png)
     }
 
+    fn need_to_synthesize_html(&self) -> NativeExtensionsResult<bool> {
+        if self
+            .disabled_conversions
+            .borrow()
+            .contains(&FormatConversion::CfHtmlToHtml)
+        {
+            return Ok(false);
+        }
+        self.can_synthesize_html()
+    }
+
+    fn can_synthesize_html(&self) -> NativeExtensionsResult<bool> {
+        let formats = self.data_object_formats_raw()?;
+        Ok(formats.contains(&format_from_string(CF_HTML_FORMAT))
+            && !formats.contains(&format_from_string(HTML_FORMAT)))
+    }
+
     fn data_object_formats(&self) -> NativeExtensionsResult<Vec<u32>> {
         let mut res = self.data_object_formats_raw()?;
         if self.need_to_synthesize_png()? {
             let png = unsafe { RegisterClipboardFormatW(w!("PNG")) };
             res.push(png);
         }
+        if self.need_to_synthesize_html()? {
+            // Right after the source so that it keeps its priority.
+            let cf_html = format_from_string(CF_HTML_FORMAT);
+            let index = res.iter().position(|f| *f == cf_html).unwrap_or_default();
+            res.insert(index + 1, format_from_string(HTML_FORMAT));
+        }
         Ok(res)
     }
 
@@ -175,12 +282,52 @@ impl PlatformDataReader {
         self.get_formats_for_item_sync(item)
     }
 
+    pub fn set_format_conversion_enabled(&self, conversion: FormatConversion, enabled: bool) {
+        if enabled {
+            self.disabled_conversions.borrow_mut().remove(&conversion);
+        } else {
+            self.disabled_conversions.borrow_mut().insert(conversion);
+        }
+    }
+
+    pub fn get_format_conversions_for_item(
+        &self,
+        item: i64,
+    ) -> NativeExtensionsResult<Vec<ItemFormatConversion>> {
+        let mut res = Vec::new();
+        // Data object formats only belong to first item
+        if item == 0 && self.can_synthesize_png()? {
+            let formats = self.data_object_formats_raw()?;
+            let source = if formats.contains(&(CF_DIBV5.0 as u32)) {
+                CF_DIBV5.0
+            } else {
+                CF_DIB.0
+            };
+            res.push(ItemFormatConversion {
+                conversion: FormatConversion::BitmapToPng,
+                source_format: format_to_string(source as u32),
+                target_format: "PNG".into(),
+                enabled: self.need_to_synthesize_png()?,
+            });
+        }
+        if item == 0 && self.can_synthesize_html()? {
+            res.push(ItemFormatConversion {
+                conversion: FormatConversion::CfHtmlToHtml,
+                source_format: CF_HTML_FORMAT.into(),
+                target_format: HTML_FORMAT.into(),
+                enabled: self.need_to_synthesize_html()?,
+            });
+        }
+        Ok(res)
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
         format: &str,
     ) -> NativeExtensionsResult<bool> {
-        Ok(format == "PNG" && self.need_to_synthesize_png()?)
+        Ok((format == "PNG" && self.need_to_synthesize_png()?)
+            || (format == HTML_FORMAT && self.need_to_synthesize_html()?))
     }
 
     pub async fn can_copy_virtual_file_for_item(
@@ -218,17 +365,87 @@ impl PlatformDataReader {
         Ok(None)
     }
 
+    /// Renders metafile content of the item to PNG. Returns None if item has
+    /// no metafile representation.
+    pub async fn rasterize_metafile_for_item(
+        &self,
+        item: i64,
+        width: Option<i64>,
+        height: Option<i64>,
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        if item != 0 || self.broker.is_some() {
+            return Ok(None);
+        }
+        let formats = self.data_object_formats_raw()?;
+        let format = [CF_ENHMETAFILE.0 as u32, CF_METAFILEPICT.0 as u32]
+            .into_iter()
+            .find(|f| formats.contains(f));
+        let Some(format) = format else {
+            return Ok(None);
+        };
+        let bits = get_enh_metafile(&self.data_object, format)?.bits();
+
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let sender = RunLoop::current().new_sender();
+
+        // Rendering large metafiles can take a while, do it on worker thread
+        thread::spawn(move || {
+            // PNG encoding goes through WIC
+            let _com = ComInitializer::new();
+            let res = EnhMetaFile::from_bits(&bits)
+                .and_then(|metafile| {
+                    metafile.rasterize_to_png(width.map(|w| w as i32), height.map(|h| h as i32))
+                })
+                .map(Some)
+                .map_err(NativeExtensionsError::from);
+            sender.send(move || {
+                let completer = completer.take().unwrap();
+                completer.complete(res);
+            });
+        });
+
+        future.await
+    }
+
+    /// Metadata from virtual file descriptor or of the dropped file. Source
+    /// application is only known for clipboard readers.
+    pub async fn get_item_metadata(&self, item: i64) -> NativeExtensionsResult<ItemMetadata> {
+        let metadata = match self.descriptor_for_item(item)? {
+            Some(descriptor) => ItemMetadata {
+                size: descriptor.size,
+                last_modified: descriptor.last_modified,
+                source_application: None,
+            },
+            None => self
+                .hdrop_for_item(item)?
+                .map(|path| ItemMetadata::for_local_file(Path::new(&path)))
+                .unwrap_or_default(),
+        };
+        Ok(ItemMetadata {
+            source_application: self.source_application.clone(),
+            ..metadata
+        })
+    }
+
+    /// No platform media API is used here; media info is parsed from the
+    /// container instead.
+    pub async fn get_media_metadata_for_item(
+        &self,
+        _item: i64,
+    ) -> NativeExtensionsResult<Option<MediaMetadata>> {
+        Ok(None)
+    }
+
     async fn generate_png(&self) -> NativeExtensionsResult<Vec<u8>> {
         let formats = self.data_object_formats()?;
         // prefer DIBV5 with alpha channel
         let data = if formats.contains(&(CF_DIBV5.0 as u32)) {
-            Ok(self.data_object.get_data(CF_DIBV5.0 as u32)?)
+            Ok(self.read_data(CF_DIBV5.0 as u32).await?)
         } else if formats.contains(&(CF_DIB.0 as u32)) {
-            Ok(self.data_object.get_data(CF_DIB.0 as u32)?)
+            Ok(self.read_data(CF_DIB.0 as u32).await?)
         } else {
-            Err(NativeExtensionsError::OtherError(
-                "No DIB or DIBV5 data found in data object".into(),
-            ))
+            Err(NativeExtensionsError::FormatNotAvailable("CF_DIB".into()))
         }?;
         let mut bmp = Vec::<u8>::new();
         bmp.extend_from_slice(&[0x42, 0x4D]); // BM
@@ -261,7 +478,7 @@ impl PlatformDataReader {
         &self,
         item: i64,
         data_type: String,
-        _progress: Option<Arc<ReadProgress>>,
+        _progress: Option<ReadProgressHandle>,
     ) -> NativeExtensionsResult<Value> {
         let format = format_from_string(&data_type);
         let png = unsafe { RegisterClipboardFormatW(w!("PNG")) };
@@ -275,10 +492,21 @@ impl PlatformDataReader {
         } else if format == png && self.need_to_synthesize_png()? {
             let png_data = self.generate_png().await?;
             Ok(png_data.into())
+        } else if data_type == HTML_FORMAT && self.need_to_synthesize_html()? {
+            let data = self.read_data(format_from_string(CF_HTML_FORMAT)).await?;
+            Ok(extract_fragment(&data)
+                .map(Value::from)
+                .unwrap_or(Value::Null))
+        } else if is_metafile_format(format) && self.broker.is_none() {
+            if self.data_object_formats_raw()?.contains(&format) {
+                Ok(get_enh_metafile(&self.data_object, format)?.bits().into())
+            } else {
+                Ok(Value::Null)
+            }
         } else {
             let formats = self.data_object_formats()?;
             if formats.contains(&format) {
-                let mut data = self.data_object.get_data(format)?;
+                let mut data = self.read_data(format).await?;
                 // CF_UNICODETEXT text may be null terminated - in which case trucate
                 // the text before sending it to Dart.
                 if format == CF_UNICODETEXT.0 as u32 {
@@ -298,14 +526,43 @@ impl PlatformDataReader {
     pub fn new_with_data_object(
         data_object: IDataObject,
         drop_notifier: Option<Arc<DropNotifier>>,
+    ) -> Rc<Self> {
+        Self::new_with_broker(data_object, drop_notifier, false, None, None)
+    }
+
+    /// Creates reader for data object provided by another application.
+    /// If broker mode is enabled the data object will be accessed from
+    /// helper process.
+    pub fn new_with_foreign_data_object(
+        data_object: IDataObject,
+        drop_notifier: Option<Arc<DropNotifier>>,
+    ) -> Rc<Self> {
+        let broker = if broker::is_enabled() {
+            BrokerSession::new_for_data_object(&data_object).ok_log()
+        } else {
+            None
+        };
+        Self::new_with_broker(data_object, drop_notifier, true, broker, None)
+    }
+
+    fn new_with_broker(
+        data_object: IDataObject,
+        drop_notifier: Option<Arc<DropNotifier>>,
+        foreign: bool,
+        broker: Option<BrokerSession>,
+        source_application: Option<String>,
     ) -> Rc<Self> {
         let res = Rc::new(PlatformDataReader {
             data_object,
+            broker,
+            foreign,
             _drop_notifier: drop_notifier,
+            source_application,
             supports_async: Cell::new(false),
             formats_raw: RefCell::new(None),
             file_descriptors: RefCell::new(None),
             hdrop: RefCell::new(None),
+            disabled_conversions: RefCell::new(HashSet::new()),
         });
         res.assign_weak_self(Rc::downgrade(&res));
         res
@@ -315,9 +572,175 @@ impl PlatformDataReader {
         self.supports_async.set(true);
     }
 
+    /// Formats from which `fileUriList` is synthesized.
+    pub fn file_uri_list_source_formats() -> Vec<String> {
+        vec![format_to_string(CF_HDROP.0 as u32)]
+    }
+
     pub fn new_clipboard_reader() -> NativeExtensionsResult<Rc<Self>> {
         let data_object = unsafe { OleGetClipboard() }?;
-        Ok(Self::new_with_data_object(data_object, None))
+        // Helper process would deadlock trying to call back into
+        // our own data object.
+        let foreign = !clipboard_owned_by_current_process();
+        let broker = if broker::is_enabled() && foreign {
+            BrokerSession::new_for_clipboard().ok_log()
+        } else {
+            None
+        };
+        Ok(Self::new_with_broker(
+            data_object,
+            None,
+            foreign,
+            broker,
+            clipboard_owner_executable(),
+        ))
+    }
+
+    pub fn new_with_external_source(
+        source: ExternalReaderSource,
+    ) -> NativeExtensionsResult<Rc<Self>> {
+        match source {
+            ExternalReaderSource::Handle { handle } => {
+                let data_object = EXTERNAL_DATA_OBJECTS
+                    .with(|o| o.borrow_mut().remove(&handle))
+                    .ok_or_else(|| {
+                        NativeExtensionsError::OtherError(format!(
+                            "External data object {handle} not registered"
+                        ))
+                    })?;
+                Ok(Self::new_with_foreign_data_object(data_object, None))
+            }
+            _ => Err(NativeExtensionsError::UnsupportedOperation),
+        }
+    }
+
+    pub fn set_out_of_process_reading(enabled: bool) -> NativeExtensionsResult<()> {
+        broker::set_enabled(enabled);
+        Ok(())
+    }
+
+    pub fn register_clipboard_format(
+        identifier: &str,
+        platform_name: &str,
+    ) -> NativeExtensionsResult<()> {
+        register_format_alias(identifier, platform_name)
+    }
+
+    /// Universal Clipboard is only available on Apple platforms.
+    pub fn is_remote_content(&self) -> NativeExtensionsResult<bool> {
+        Ok(false)
+    }
+
+    pub fn set_exclude_remote_content(_exclude: bool) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    /// Clipboard sequence number or `None` when the window station has no
+    /// clipboard access.
+    pub fn clipboard_change_count() -> NativeExtensionsResult<Option<i64>> {
+        let sequence_number = unsafe { GetClipboardSequenceNumber() };
+        Ok((sequence_number != 0).then_some(sequence_number as i64))
+    }
+
+    /// Answers from formats available on clipboard without opening it.
+    /// Returns `None` when the clipboard has virtual files, whose formats
+    /// are only known after reading the file descriptors.
+    pub fn clipboard_has_any_format(formats: &[String]) -> NativeExtensionsResult<Option<bool>> {
+        let available = |format: u32| unsafe { IsClipboardFormatAvailable(format) }.is_ok();
+        for format in formats {
+            let res = match format.as_str() {
+                // Synthesized from bitmap and CF_HTML by the reader.
+                "PNG" => {
+                    available(format_from_string(format))
+                        || available(CF_DIBV5.0 as u32)
+                        || available(CF_DIB.0 as u32)
+                }
+                HTML_FORMAT => {
+                    available(format_from_string(format))
+                        || available(format_from_string(CF_HTML_FORMAT))
+                }
+                format => available(format_from_string(format)),
+            };
+            if res {
+                return Ok(Some(true));
+            }
+        }
+        if available(unsafe { RegisterClipboardFormatW(CFSTR_FILEDESCRIPTOR) }) {
+            return Ok(None);
+        }
+        Ok(Some(false))
+    }
+
+    /// Entity detection is not provided by the platform, built-in detector
+    /// is used instead.
+    pub fn detect_entities(
+        _text: &str,
+        _kinds: &[EntityKind],
+    ) -> NativeExtensionsResult<Option<Vec<DetectedEntity>>> {
+        Ok(None)
+    }
+
+    fn extract_formats(&self) -> NativeExtensionsResult<Vec<FORMATETC>> {
+        match &self.broker {
+            Some(broker) => broker.get_formats(),
+            None => Ok(extract_formats(&self.data_object)?),
+        }
+    }
+
+    fn get_data(&self, format: u32) -> NativeExtensionsResult<Vec<u8>> {
+        let res = match &self.broker {
+            Some(broker) => broker.get_data(format),
+            None => Ok(self.data_object.get_data(format)?),
+        };
+        res.map_err(|e| Self::map_get_data_error(e, format))
+    }
+
+    /// Reads item data. Data of other applications is read on worker thread,
+    /// so that a hung source application doesn't block the UI and the read
+    /// can time out.
+    async fn read_data(&self, format: u32) -> NativeExtensionsResult<Vec<u8>> {
+        let res = match &self.broker {
+            Some(broker) => broker.get_data_async(format).await,
+            None if self.foreign => {
+                let marshalled = unsafe {
+                    Movable::new(CoMarshalInterThreadInterfaceInStream(
+                        &IDataObject::IID,
+                        &self.data_object,
+                    )?)
+                };
+                run_on_worker_thread(move || -> NativeExtensionsResult<_> {
+                    let _com = ComInitializer::new();
+                    let marshalled = marshalled.take();
+                    let data_object =
+                        unsafe { CoGetInterfaceAndReleaseStream::<_, IDataObject>(&marshalled) };
+                    // Released by the call regardless of the result.
+                    std::mem::forget(marshalled);
+                    Ok(data_object?.get_data(format)?)
+                })
+                .await
+            }
+            None => Ok(self.data_object.get_data(format)?),
+        };
+        res.map_err(|e| Self::map_get_data_error(e, format))
+    }
+
+    fn map_get_data_error(error: NativeExtensionsError, format: u32) -> NativeExtensionsError {
+        match error {
+            NativeExtensionsError::FormatNotAvailable(_) => {
+                NativeExtensionsError::FormatNotAvailable(format_to_string(format))
+            }
+            e => e,
+        }
+    }
+
+    fn has_data(&self, format: u32) -> bool {
+        match &self.broker {
+            Some(_) => self
+                .data_object_formats_raw()
+                .map(|f| f.contains(&format))
+                .unwrap_or(false),
+            None => self.data_object.has_data(format),
+        }
     }
 
     pub fn assign_weak_self(&self, _weak: Weak<PlatformDataReader>) {}
@@ -328,8 +751,8 @@ impl PlatformDataReader {
         F: FnOnce(Option<&[String]>) -> NativeExtensionsResult<R>,
     {
         if self.hdrop.borrow().is_none() {
-            let files = if self.data_object.has_data(CF_HDROP.0 as u32) {
-                let data = self.data_object.get_data(CF_HDROP.0 as u32)?;
+            let files = if self.has_data(CF_HDROP.0 as u32) {
+                let data = self.get_data(CF_HDROP.0 as u32)?;
                 let files = Self::extract_drop_files(data)?;
 
                 Some(files)
@@ -359,8 +782,8 @@ impl PlatformDataReader {
     {
         if self.file_descriptors.borrow().is_none() {
             let format = unsafe { RegisterClipboardFormatW(CFSTR_FILEDESCRIPTOR) };
-            let descriptors = if self.data_object.has_data(format) {
-                let data = self.data_object.get_data(format)?;
+            let descriptors = if self.has_data(format) {
+                let data = self.get_data(format)?;
                 Some(Self::extract_file_descriptors(data)?)
             } else {
                 None
@@ -420,10 +843,20 @@ impl PlatformDataReader {
                 let name = String::from_utf16_lossy(&file_name[0..len]);
                 let format = mime_from_name(&name);
                 let format = mime_to_windows(format);
+                let size = (f.dwFlags & FD_FILESIZE.0 as u32 != 0)
+                    .then(|| ((f.nFileSizeHigh as i64) << 32) | f.nFileSizeLow as i64);
+                let last_modified = (f.dwFlags & FD_WRITESTIME.0 as u32 != 0).then(|| {
+                    let time = ((f.ftLastWriteTime.dwHighDateTime as i64) << 32)
+                        | f.ftLastWriteTime.dwLowDateTime as i64;
+                    // FILETIME counts 100ns intervals since 1601-01-01.
+                    (time - 116_444_736_000_000_000) / 10_000
+                });
                 FileDescriptor {
                     name,
                     format,
                     index,
+                    size,
+                    last_modified,
                 }
             })
             .collect();
@@ -519,7 +952,7 @@ impl PlatformDataReader {
         &self,
         item: i64,
         _format: &str,
-        _progress: Arc<ReadProgress>,
+        _progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
         let descriptor = self.descriptor_for_virtual_file(item)?;
         let mut medium = self.medium_for_virtual_file(&descriptor)?;
@@ -529,7 +962,7 @@ impl PlatformDataReader {
 
         if self.supports_async.get() {
             let stream = unsafe { Movable::new(stream) };
-            let reader = AsyncStreamReader::new(stream, descriptor.name).await?;
+            let reader = AsyncStreamReader::new(stream, Some(descriptor.name)).await?;
             Ok(Some(Rc::new(reader)))
         } else {
             let reader = EagerStreamReader::new(stream, descriptor.name)?;
@@ -537,11 +970,48 @@ impl PlatformDataReader {
         }
     }
 
+    /// Returns chunked reader for item data provided as `IStream`. Returns
+    /// `None` for data that is already in memory (`HGLOBAL`), synthesized or
+    /// read through broker; caller falls back to `get_data_for_item` for
+    /// these.
+    pub async fn get_data_stream_for_item(
+        &self,
+        _item: i64,
+        data_type: &str,
+        _progress: ReadProgressHandle,
+    ) -> NativeExtensionsResult<Option<Rc<dyn VirtualFileReader>>> {
+        let format = format_from_string(data_type);
+        // Full reads of CF_UNICODETEXT strip the null terminator.
+        if self.broker.is_some()
+            || format == CF_HDROP.0 as u32
+            || format == CF_UNICODETEXT.0 as u32
+            || is_metafile_format(format)
+            || !self.data_object_formats_raw()?.contains(&format)
+        {
+            return Ok(None);
+        }
+        let format = make_format_with_tymed(format, TYMED_ISTREAM);
+        if !self.data_object.has_data_for_format(&format) {
+            return Ok(None);
+        }
+        let mut medium =
+            unsafe { DataObject::with_local_request(|| self.data_object.GetData(&format))? };
+        let stream = Self::stream_from_medium(&medium);
+        unsafe { ReleaseStgMedium(&mut medium as *mut STGMEDIUM) };
+        let stream = unsafe { Movable::new(stream?) };
+        if self.supports_async.get() {
+            let reader = AsyncStreamReader::new(stream, None).await?;
+            Ok(Some(Rc::new(reader)))
+        } else {
+            Ok(Some(Rc::new(MarshalledStreamReader::new(&stream).await?)))
+        }
+    }
+
     fn do_copy_virtual_file(
         medium: &STGMEDIUM,
         file_name: &str,
         target_folder: PathBuf,
-        progress: Arc<ReadProgress>,
+        progress: ReadProgressHandle,
         supports_async: bool,
         completer: FutureCompleter<NativeExtensionsResult<PathBuf>>,
     ) {
@@ -653,7 +1123,7 @@ impl PlatformDataReader {
         item: i64,
         _format: &str,
         target_folder: PathBuf,
-        progress: Arc<ReadProgress>,
+        progress: ReadProgressHandle,
     ) -> NativeExtensionsResult<PathBuf> {
         let descriptor = self.descriptor_for_virtual_file(item)?;
         let mut medium = self.medium_for_virtual_file(&descriptor)?;
@@ -713,10 +1183,105 @@ impl VirtualFileReader for EagerStreamReader {
     }
 }
 
+type StreamRequest = Box<dyn FnOnce(&IStream) + Send>;
+
+/// Reads stream in chunks on a dedicated thread. The stream may only be usable
+/// from the apartment it was obtained in, so it is marshalled to the thread
+/// instead of being used directly.
+struct MarshalledStreamReader {
+    length: u64,
+    requests: mpsc::Sender<StreamRequest>,
+    read_state: Arc<Mutex<Option<ReadState>>>,
+}
+
+impl MarshalledStreamReader {
+    async fn new(stream: &IStream) -> NativeExtensionsResult<Self> {
+        let marshalled = unsafe {
+            Movable::new(CoMarshalInterThreadInterfaceInStream(
+                &IStream::IID,
+                stream,
+            )?)
+        };
+        let (requests, receiver) = mpsc::channel::<StreamRequest>();
+        thread::spawn(move || {
+            let _com = ComInitializer::new();
+            let marshalled = marshalled.take();
+            let stream = unsafe { CoGetInterfaceAndReleaseStream::<_, IStream>(&marshalled) };
+            // Released by the call regardless of the result.
+            std::mem::forget(marshalled);
+            let stream = match stream {
+                Ok(stream) => stream,
+                // Pending requests are dropped together with the receiver.
+                Err(err) => {
+                    log::warn!("Failed to unmarshal stream: {err}");
+                    return;
+                }
+            };
+            // Exits when reader is dropped.
+            while let Ok(request) = receiver.recv() {
+                request(&stream);
+            }
+        });
+        let length = Self::request(&requests, |stream| {
+            let mut stat = STATSTG::default();
+            unsafe { stream.Stat(&mut stat as *mut _, STATFLAG_NONAME)? };
+            Ok(stat.cbSize)
+        })
+        .await?;
+        Ok(Self {
+            length,
+            requests,
+            read_state: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    async fn request<T: Send + 'static>(
+        requests: &mpsc::Sender<StreamRequest>,
+        f: impl FnOnce(&IStream) -> NativeExtensionsResult<T> + Send + 'static,
+    ) -> NativeExtensionsResult<T> {
+        let (future, completer) = FutureCompleter::new();
+        let sender = RunLoop::current().new_sender();
+        let mut completer = Capsule::new_with_sender(completer, sender.clone());
+        requests
+            .send(Box::new(move |stream| {
+                let res = f(stream);
+                sender.send(move || {
+                    completer.take().unwrap().complete(res);
+                });
+            }))
+            .map_err(|_| NativeExtensionsError::OtherError("Stream is not available".into()))?;
+        future.await
+    }
+}
+
+#[async_trait(?Send)]
+impl VirtualFileReader for MarshalledStreamReader {
+    async fn read_next(&self) -> NativeExtensionsResult<Vec<u8>> {
+        let length = self.length;
+        let read_state = self.read_state.clone();
+        Self::request(&self.requests, move |stream| {
+            AsyncStreamReader::read(unsafe { Movable::new(stream.clone()) }, length, read_state)
+        })
+        .await
+    }
+
+    fn file_size(&self) -> NativeExtensionsResult<Option<i64>> {
+        Ok(Some(self.length as i64))
+    }
+
+    fn file_name(&self) -> Option<String> {
+        None
+    }
+
+    fn close(&self) -> NativeExtensionsResult<()> {
+        Ok(())
+    }
+}
+
 struct AsyncStreamReader {
     stream: Movable<IStream>,
     length: u64,
-    file_name: String,
+    file_name: Option<String>,
     // Single thread thread-pool so that all requests are run in background
     // but serialized.
     thread_pool: ThreadPool,
@@ -728,7 +1293,10 @@ struct ReadState {
 }
 
 impl AsyncStreamReader {
-    async fn new(stream: Movable<IStream>, file_name: String) -> NativeExtensionsResult<Self> {
+    async fn new(
+        stream: Movable<IStream>,
+        file_name: Option<String>,
+    ) -> NativeExtensionsResult<Self> {
         let thread_pool = ThreadPool::new(1);
         let length = Self::stream_length(&stream, &thread_pool).await?;
         Ok(AsyncStreamReader {
@@ -778,7 +1346,7 @@ impl AsyncStreamReader {
                 stream.Seek(0, STREAM_SEEK_SET, None)?;
             }
         }
-        let mut buf = vec![0u8; 1024 * 1024];
+        let mut buf = vec![0u8; remote_session::read_chunk_size(1024 * 1024)];
         let state = state.as_mut().unwrap();
         let to_read = (length - state.num_read).min(buf.len() as u64) as u32;
         if to_read == 0 {
@@ -826,7 +1394,7 @@ impl VirtualFileReader for AsyncStreamReader {
     }
 
     fn file_name(&self) -> Option<String> {
-        Some(self.file_name.clone())
+        self.file_name.clone()
     }
 
     fn close(&self) -> NativeExtensionsResult<()> {
@@ -843,7 +1411,7 @@ struct AsyncVirtualStreamCopier {
     stream: Movable<IStream>,
     file_name: String,
     target_folder: PathBuf,
-    progress: Arc<ReadProgress>,
+    progress: ReadProgressHandle,
     completer: Capsule<FutureCompleter<NativeExtensionsResult<PathBuf>>>,
 }
 
@@ -888,15 +1456,11 @@ impl AsyncVirtualStreamCopier {
     }
 
     fn read_and_write(&self, mut f: File) -> NativeExtensionsResult<()> {
-        let cancelled = Arc::new(AtomicBool::new(false));
-        let cancelled_clone = cancelled.clone();
-        self.progress
-            .set_cancellation_handler(Some(Box::new(move || {
-                cancelled_clone.store(true, Ordering::Release);
-            })));
+        self.progress.set_cancellable(true);
+        let token = self.progress.cancellation_token().clone();
         let length = self.get_length()?;
         let mut num_read: u64 = 0;
-        let mut buf = vec![0u8; 1024 * 1024];
+        let mut buf = vec![0u8; remote_session::read_chunk_size(1024 * 1024)];
         let mut last_reported_progress = 0f64;
 
         unsafe {
@@ -904,7 +1468,7 @@ impl AsyncVirtualStreamCopier {
         }
 
         loop {
-            if cancelled.load(Ordering::Acquire) {
+            if token.is_cancelled() {
                 return Err(NativeExtensionsError::VirtualFileReceiveError(
                     "cancelled".into(),
                 ));
@@ -935,10 +1499,12 @@ impl AsyncVirtualStreamCopier {
             let progress = num_read as f64 / length as f64;
             if progress >= last_reported_progress + 0.05 {
                 last_reported_progress = progress;
-                self.progress.report_progress(Some(progress));
+                self.progress
+                    .report_bytes(num_read as i64, Some(length as i64));
             }
         }
-        self.progress.report_progress(Some(1.0));
+        self.progress
+            .report_bytes(num_read as i64, Some(length as i64));
 
         Ok(())
     }
@@ -976,3 +1542,39 @@ fn mime_from_name(name: &str) -> String {
             )
         })
 }
+
+thread_local! {
+    static EXTERNAL_DATA_OBJECTS: RefCell<HashMap<i64, IDataObject>> =
+        RefCell::new(HashMap::new());
+}
+
+static NEXT_EXTERNAL_HANDLE: AtomicI64 = AtomicI64::new(1);
+
+/// Registers `IDataObject` obtained by native code of another plugin so that
+/// Dart can wrap it in a reader with `newExternalReader`. The data object is
+/// retained until the returned handle is redeemed or released through
+/// [`super_native_extensions_release_data_object`]; the caller keeps its own
+/// reference. Must be called on the platform thread. Returns 0 for null
+/// data object.
+///
+/// # Safety
+///
+/// `data_object` must be null or a valid `IDataObject` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn super_native_extensions_register_data_object(
+    data_object: *mut c_void,
+) -> i64 {
+    let Some(data_object) = IDataObject::from_raw_borrowed(&data_object).cloned() else {
+        return 0;
+    };
+    let handle = NEXT_EXTERNAL_HANDLE.fetch_add(1, Ordering::Relaxed);
+    EXTERNAL_DATA_OBJECTS.with(|o| o.borrow_mut().insert(handle, data_object));
+    handle
+}
+
+/// Releases data object registered with
+/// [`super_native_extensions_register_data_object`] that was not redeemed.
+#[no_mangle]
+pub extern "C" fn super_native_extensions_release_data_object(handle: i64) {
+    EXTERNAL_DATA_OBJECTS.with(|o| o.borrow_mut().remove(&handle));
+}
@@ -0,0 +1,86 @@
+//! Detection of remote desktop sessions (RDP, Citrix) and clipboard behavior
+//! adjustments for them.
+//!
+//! Clipboard redirection in remote sessions is considerably slower and less
+//! robust than local clipboard. When tuning is active, requests get longer
+//! timeouts, streams are read in smaller chunks and lazy clipboard data is
+//! rendered eagerly, since delayed rendering through the redirector is
+//! frequently broken.
+
+use std::{
+    sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
+};
+
+use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_REMOTESESSION};
+
+use crate::api_model::{RemoteSessionInfo, RemoteSessionKind};
+
+const TUNING_AUTO: u8 = 0;
+const TUNING_ENABLED: u8 = 1;
+const TUNING_DISABLED: u8 = 2;
+
+static TUNING: AtomicU8 = AtomicU8::new(TUNING_AUTO);
+
+/// Returns kind of current remote session or None for local session.
+/// Evaluated on each call because session can be reconnected between
+/// console and remote client at any time.
+pub fn remote_session_kind() -> Option<RemoteSessionKind> {
+    if unsafe { GetSystemMetrics(SM_REMOTESESSION) } == 0 {
+        return None;
+    }
+    let session_name = std::env::var("SESSIONNAME").unwrap_or_default();
+    if session_name.to_ascii_uppercase().starts_with("ICA") {
+        Some(RemoteSessionKind::Citrix)
+    } else {
+        Some(RemoteSessionKind::Rdp)
+    }
+}
+
+/// `None` restores automatic behavior (tuned in remote sessions only).
+pub fn set_tuning(enabled: Option<bool>) {
+    let value = match enabled {
+        None => TUNING_AUTO,
+        Some(true) => TUNING_ENABLED,
+        Some(false) => TUNING_DISABLED,
+    };
+    TUNING.store(value, Ordering::Relaxed);
+}
+
+pub fn tuning_enabled() -> bool {
+    match TUNING.load(Ordering::Relaxed) {
+        TUNING_ENABLED => true,
+        TUNING_DISABLED => false,
+        _ => remote_session_kind().is_some(),
+    }
+}
+
+pub fn remote_session_info() -> RemoteSessionInfo {
+    RemoteSessionInfo {
+        kind: remote_session_kind(),
+        tuning_enabled: tuning_enabled(),
+    }
+}
+
+pub fn request_timeout(default: Duration) -> Duration {
+    if tuning_enabled() {
+        default * 3
+    } else {
+        default
+    }
+}
+
+/// Redirected streams tend to fail or stall on large reads.
+pub fn read_chunk_size(default: usize) -> usize {
+    if tuning_enabled() {
+        default.min(64 * 1024)
+    } else {
+        default
+    }
+}
+
+/// Whether lazy data should be resolved before placing data object on
+/// clipboard.
+pub fn eager_rendering() -> bool {
+    tuning_enabled()
+}
@@ -0,0 +1,100 @@
+use std::slice;
+
+use windows::Win32::{
+    Foundation::{HANDLE, HMODULE},
+    Graphics::{
+        Direct3D::D3D_DRIVER_TYPE_HARDWARE,
+        Direct3D11::{
+            D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D,
+            D3D11_CPU_ACCESS_READ, D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_MAPPED_SUBRESOURCE,
+            D3D11_MAP_READ, D3D11_SDK_VERSION, D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING,
+        },
+        Dxgi::Common::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R8G8B8A8_UNORM},
+    },
+};
+
+use crate::{
+    api_model::{ImageData, SharedTexture, SharedTextureKind},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    shared_texture::{image_data_from_pixels, PixelOrder},
+};
+
+pub const SHARED_TEXTURE_KIND: Option<SharedTextureKind> =
+    Some(SharedTextureKind::DxgiSharedHandle);
+
+fn create_device() -> NativeExtensionsResult<(ID3D11Device, ID3D11DeviceContext)> {
+    let mut device = None;
+    let mut context = None;
+    unsafe {
+        D3D11CreateDevice(
+            None,
+            D3D_DRIVER_TYPE_HARDWARE,
+            HMODULE::default(),
+            D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+            None,
+            D3D11_SDK_VERSION,
+            Some(&mut device),
+            None,
+            Some(&mut context),
+        )?;
+    }
+    match (device, context) {
+        (Some(device), Some(context)) => Ok((device, context)),
+        _ => Err(NativeExtensionsError::OtherError(
+            "failed to create D3D11 device".into(),
+        )),
+    }
+}
+
+/// Copies content of texture shared through DXGI shared handle. The texture
+/// is copied into staging texture on GPU first, which is the only part
+/// mapped into CPU memory.
+pub fn image_data_from_shared_texture(
+    texture: &SharedTexture,
+) -> NativeExtensionsResult<ImageData> {
+    if texture.kind != SharedTextureKind::DxgiSharedHandle {
+        return Err(NativeExtensionsError::UnsupportedOperation);
+    }
+    let (device, context) = create_device()?;
+    unsafe {
+        let shared: ID3D11Texture2D = device.OpenSharedResource(HANDLE(texture.handle as isize))?;
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        shared.GetDesc(&mut desc);
+        let order = match desc.Format {
+            DXGI_FORMAT_B8G8R8A8_UNORM => PixelOrder::Bgra,
+            DXGI_FORMAT_R8G8B8A8_UNORM => PixelOrder::Rgba,
+            _ => return Err(NativeExtensionsError::InvalidData),
+        };
+        let staging_desc = D3D11_TEXTURE2D_DESC {
+            MipLevels: 1,
+            ArraySize: 1,
+            Usage: D3D11_USAGE_STAGING,
+            BindFlags: 0,
+            CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32,
+            MiscFlags: 0,
+            ..desc
+        };
+        let mut staging = None;
+        device.CreateTexture2D(&staging_desc, None, Some(&mut staging))?;
+        let staging = staging.ok_or(NativeExtensionsError::UnknownError)?;
+        context.CopyResource(&staging, &shared);
+
+        let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+        context.Map(&staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+        let bytes_per_row = mapped.RowPitch as usize;
+        let pixels = slice::from_raw_parts(
+            mapped.pData as *const u8,
+            bytes_per_row * desc.Height as usize,
+        );
+        let image = image_data_from_pixels(
+            pixels,
+            desc.Width as i32,
+            desc.Height as i32,
+            bytes_per_row,
+            order,
+            texture.device_pixel_ratio,
+        );
+        context.Unmap(&staging, 0);
+        Ok(image)
+    }
+}
@@ -0,0 +1,172 @@
+//! Support for drags started by touch or pen.
+//!
+//! `DoDragDrop` runs a modal loop driven by mouse state: the drag ends as
+//! soon as left button is not pressed and the drag image follows the cursor.
+//! Touch and pen contacts handled through `WM_POINTER` messages are not
+//! promoted to mouse input, so without help the drag would end immediately.
+//! While the drag is active [`PointerShim`] injects left button press, moves
+//! the cursor along with the contact and releases the button when the
+//! contact is lifted or cancelled.
+//!
+//! The contact is identified by pointer id of the first pointer message seen
+//! during the drag; its position and state are queried through
+//! `GetPointerInfo` rather than decoded from message parameters.
+
+use std::mem::size_of;
+
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HANDLE, HWND, POINT, WPARAM},
+        Graphics::Gdi::ClientToScreen,
+        UI::{
+            Input::{
+                KeyboardAndMouse::{
+                    SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_LEFTDOWN,
+                    MOUSEEVENTF_LEFTUP, MOUSEINPUT, MOUSE_EVENT_FLAGS,
+                },
+                Pointer::{
+                    GetPointerInfo, POINTER_FLAG_CANCELED, POINTER_FLAG_INCONTACT, POINTER_INFO,
+                },
+            },
+            WindowsAndMessaging::{
+                PeekMessageW, RemovePropW, SetCursorPos, SetPropW, MSG, PEEK_MESSAGE_REMOVE_TYPE,
+                PM_NOREMOVE, PM_REMOVE, WM_POINTERCAPTURECHANGED, WM_POINTERUP, WM_POINTERUPDATE,
+            },
+        },
+    },
+};
+
+use crate::{api_model::Point, log::OkLog};
+
+use super::common::get_dpi_for_window;
+
+pub struct PointerShim {
+    view: HWND,
+    /// Contact that started the drag. Not known until first pointer message
+    /// for the view is seen.
+    pointer_id: Option<u32>,
+    button_down: bool,
+}
+
+/// Equivalent of `GET_POINTERID_WPARAM`.
+fn pointer_id_from_wparam(wparam: WPARAM) -> u32 {
+    (wparam.0 & 0xFFFF) as u32
+}
+
+impl PointerShim {
+    /// `position` is location of the contact in view, in logical pixels.
+    pub fn begin(view: HWND, position: &Point) -> Self {
+        let scale = get_dpi_for_window(view) as f64 / 96.0;
+        let mut point = POINT {
+            x: (position.x * scale) as i32,
+            y: (position.y * scale) as i32,
+        };
+        unsafe {
+            ClientToScreen(view, &mut point as *mut _);
+            SetCursorPos(point.x, point.y).ok_log();
+        }
+        send_mouse_input(MOUSEEVENTF_LEFTDOWN);
+        Self {
+            view,
+            pointer_id: None,
+            button_down: true,
+        }
+    }
+
+    /// Called from `QueryContinueDrag`. Moves cursor to latest contact
+    /// position and releases the injected button once contact is lifted, which
+    /// makes the drag loop perform the drop.
+    pub fn update(&mut self) {
+        if !self.button_down {
+            return;
+        }
+        let mut msg = MSG::default();
+        // Pointer updates are consumed, the same way mouse moves are captured
+        // by the drag loop.
+        while self.peek_message(&mut msg, WM_POINTERUPDATE, PM_REMOVE) {
+            self.pointer_id
+                .get_or_insert(pointer_id_from_wparam(msg.wParam));
+        }
+        // Pointer up and capture loss are left in the queue so that Flutter
+        // can finish the gesture after the drag.
+        if self.contact_ended_message(&mut msg, WM_POINTERUP)
+            || self.contact_ended_message(&mut msg, WM_POINTERCAPTURECHANGED)
+        {
+            self.release();
+            return;
+        }
+        let Some(pointer_id) = self.pointer_id else {
+            return;
+        };
+        let mut info = POINTER_INFO::default();
+        let in_contact = unsafe { GetPointerInfo(pointer_id, &mut info as *mut _) }.is_ok()
+            && (info.pointerFlags & POINTER_FLAG_INCONTACT).0 != 0
+            && (info.pointerFlags & POINTER_FLAG_CANCELED).0 == 0;
+        if in_contact {
+            let location = info.ptPixelLocation;
+            unsafe { SetCursorPos(location.x, location.y) }.ok_log();
+        } else {
+            self.release();
+        }
+    }
+
+    fn peek_message(&self, msg: &mut MSG, message: u32, remove: PEEK_MESSAGE_REMOVE_TYPE) -> bool {
+        unsafe { PeekMessageW(msg as *mut _, self.view, message, message, remove) }.as_bool()
+    }
+
+    /// Whether there is message of given kind pending for tracked contact.
+    /// Also matches when no pointer update has been seen yet, i.e. the
+    /// contact was lifted before moving.
+    fn contact_ended_message(&self, msg: &mut MSG, message: u32) -> bool {
+        self.peek_message(msg, message, PM_NOREMOVE)
+            && self
+                .pointer_id
+                .map(|id| id == pointer_id_from_wparam(msg.wParam))
+                .unwrap_or(true)
+    }
+
+    fn release(&mut self) {
+        if self.button_down {
+            send_mouse_input(MOUSEEVENTF_LEFTUP);
+            self.button_down = false;
+        }
+    }
+}
+
+impl Drop for PointerShim {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+fn send_mouse_input(flags: MOUSE_EVENT_FLAGS) {
+    let input = INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx: 0,
+                dy: 0,
+                mouseData: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    unsafe { SendInput(&[input], size_of::<INPUT>() as i32) };
+}
+
+/// Enables or disables system press-and-hold gesture (right click emulation
+/// with visual feedback) for touch and pen input in the view.
+pub fn set_system_press_and_hold_enabled(view: HWND, enabled: bool) {
+    const TABLET_DISABLE_PRESSANDHOLD: isize = 0x00000001;
+    let property = w!("MicrosoftTabletPenServiceProperty");
+    unsafe {
+        if enabled {
+            RemovePropW(view, property).ok_log();
+        } else {
+            SetPropW(view, property, HANDLE(TABLET_DISABLE_PRESSANDHOLD)).ok_log();
+        }
+    }
+}
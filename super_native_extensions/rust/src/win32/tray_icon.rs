@@ -0,0 +1,302 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    mem::{size_of, ManuallyDrop},
+    rc::Weak,
+};
+
+use irondash_message_channel::Late;
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, TRUE, WPARAM},
+        Graphics::Gdi::{CreateBitmap, DeleteObject},
+        UI::{
+            Shell::{
+                DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass, Shell_NotifyIconW,
+                NIF_ICON, NIF_MESSAGE, NIF_SHOWTIP, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY,
+                NIM_SETVERSION, NIN_POPUPOPEN, NOTIFYICONDATAW, NOTIFYICON_VERSION_4,
+                NOTIFY_ICON_DATA_FLAGS,
+            },
+            WindowsAndMessaging::{
+                ChangeWindowMessageFilterEx, CreateIconIndirect, CreateWindowExW, DestroyIcon,
+                DestroyWindow, RegisterWindowMessageW, HICON, HMENU, ICONINFO, MSGFLT_ALLOW,
+                WM_APP, WM_CONTEXTMENU, WM_LBUTTONDBLCLK, WM_LBUTTONUP, WM_MBUTTONUP,
+            },
+        },
+    },
+};
+
+use crate::{
+    api_model::{ImageData, Point},
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+    tray_icon_manager::{
+        TrayIconCreateRequest, TrayIconEventType, TrayIconHandle, TrayIconManagerDelegate,
+    },
+};
+
+use super::common::image_data_to_hbitmap;
+
+/// Callback message sent by the shell for tray icon interaction.
+const WM_TRAY_ICON: u32 = WM_APP + 0x0e31;
+
+const SUBCLASS_ID: usize = 0x0e31;
+
+struct TrayIcon {
+    id: u32,
+    hwnd: HWND,
+    icon: Cell<HICON>,
+    tooltip: RefCell<Option<String>>,
+    custom_tooltip: bool,
+}
+
+impl TrayIcon {
+    fn notify_icon_data(&self) -> NOTIFYICONDATAW {
+        let mut flags: NOTIFY_ICON_DATA_FLAGS = NIF_MESSAGE | NIF_ICON | NIF_TIP;
+        // Without NIF_SHOWTIP shell sends NIN_POPUPOPEN instead of showing
+        // the standard tooltip.
+        if !self.custom_tooltip {
+            flags |= NIF_SHOWTIP;
+        }
+        let mut data = NOTIFYICONDATAW {
+            cbSize: size_of::<NOTIFYICONDATAW>() as u32,
+            hWnd: self.hwnd,
+            uID: self.id,
+            uFlags: flags,
+            uCallbackMessage: WM_TRAY_ICON,
+            hIcon: self.icon.get(),
+            ..Default::default()
+        };
+        if let Some(tooltip) = self.tooltip.borrow().as_ref() {
+            let tooltip: Vec<u16> = tooltip.encode_utf16().collect();
+            let len = tooltip.len().min(data.szTip.len() - 1);
+            data.szTip[..len].copy_from_slice(&tooltip[..len]);
+        }
+        data.Anonymous.uVersion = NOTIFYICON_VERSION_4;
+        data
+    }
+
+    fn add(&self) -> NativeExtensionsResult<()> {
+        let data = self.notify_icon_data();
+        unsafe {
+            Shell_NotifyIconW(NIM_ADD, &data).ok()?;
+            Shell_NotifyIconW(NIM_SETVERSION, &data).ok()?;
+        }
+        Ok(())
+    }
+
+    fn modify(&self) -> NativeExtensionsResult<()> {
+        let data = self.notify_icon_data();
+        unsafe { Shell_NotifyIconW(NIM_MODIFY, &data).ok()? };
+        Ok(())
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        let data = self.notify_icon_data();
+        unsafe {
+            Shell_NotifyIconW(NIM_DELETE, &data);
+            DestroyIcon(self.icon.get()).ok_log();
+        }
+    }
+}
+
+fn hicon_from_image_data(image: &ImageData) -> NativeExtensionsResult<HICON> {
+    let color = image_data_to_hbitmap(image)?;
+    // Mask is ignored for icons with alpha channel but must be present.
+    let mask_stride = ((image.width + 15) / 16 * 2) as usize;
+    let mask_bits = vec![0u8; mask_stride * image.height as usize];
+    unsafe {
+        let mask = CreateBitmap(
+            image.width,
+            image.height,
+            1,
+            1,
+            Some(mask_bits.as_ptr() as *const _),
+        );
+        let info = ICONINFO {
+            fIcon: TRUE,
+            xHotspot: 0,
+            yHotspot: 0,
+            hbmMask: mask,
+            hbmColor: color,
+        };
+        let icon = CreateIconIndirect(&info);
+        DeleteObject(mask);
+        DeleteObject(color);
+        Ok(icon?)
+    }
+}
+
+pub struct PlatformTrayIconManager {
+    delegate: Weak<dyn TrayIconManagerDelegate>,
+    next_id: Cell<u32>,
+    icons: RefCell<HashMap<TrayIconHandle, TrayIcon>>,
+    /// Broadcast when explorer restarts; all icons must be added again.
+    taskbar_created_message: u32,
+    /// Hidden top-level window receiving icon callbacks. The run loop window
+    /// can not be used because message-only windows don't get broadcasts.
+    hwnd: Late<HWND>,
+    weak_self: Late<Weak<Self>>,
+}
+
+impl PlatformTrayIconManager {
+    pub fn new(delegate: Weak<dyn TrayIconManagerDelegate>) -> Self {
+        Self {
+            delegate,
+            next_id: Cell::new(1),
+            icons: RefCell::new(HashMap::new()),
+            taskbar_created_message: unsafe { RegisterWindowMessageW(w!("TaskbarCreated")) },
+            hwnd: Late::new(),
+            weak_self: Late::new(),
+        }
+    }
+
+    pub fn assign_weak_self(&self, weak: Weak<PlatformTrayIconManager>) {
+        self.weak_self.set(weak.clone());
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_TOOLWINDOW,
+                w!("STATIC"),
+                w!(""),
+                WS_POPUP,
+                0,
+                0,
+                0,
+                0,
+                HWND(0),
+                HMENU(0),
+                None,
+                None,
+            )
+        };
+        unsafe {
+            // Reference is released in drop.
+            SetWindowSubclass(
+                hwnd,
+                Some(subclass_proc),
+                SUBCLASS_ID,
+                Weak::into_raw(weak) as usize,
+            );
+            // Let the broadcast through when running elevated.
+            ChangeWindowMessageFilterEx(hwnd, self.taskbar_created_message, MSGFLT_ALLOW, None)
+                .ok_log();
+        }
+        self.hwnd.set(hwnd);
+    }
+
+    pub async fn create_tray_icon(
+        &self,
+        handle: TrayIconHandle,
+        request: TrayIconCreateRequest,
+    ) -> NativeExtensionsResult<()> {
+        let id = self.next_id.get();
+        self.next_id.replace(id + 1);
+        let icon = TrayIcon {
+            id,
+            hwnd: *self.hwnd,
+            icon: Cell::new(hicon_from_image_data(&request.image)?),
+            tooltip: RefCell::new(request.tooltip),
+            custom_tooltip: request.custom_tooltip,
+        };
+        icon.add()?;
+        self.icons.borrow_mut().insert(handle, icon);
+        Ok(())
+    }
+
+    pub fn update_tray_icon(
+        &self,
+        handle: TrayIconHandle,
+        image: Option<ImageData>,
+        tooltip: Option<String>,
+    ) -> NativeExtensionsResult<()> {
+        let icons = self.icons.borrow();
+        let icon = icons
+            .get(&handle)
+            .ok_or_else(|| NativeExtensionsError::OtherError("Tray icon not found".into()))?;
+        if let Some(image) = image {
+            let previous = icon.icon.replace(hicon_from_image_data(&image)?);
+            unsafe { DestroyIcon(previous).ok_log() };
+        }
+        if tooltip.is_some() {
+            icon.tooltip.replace(tooltip);
+        }
+        icon.modify()
+    }
+
+    pub fn destroy_tray_icon(&self, handle: TrayIconHandle) -> NativeExtensionsResult<()> {
+        let icon = self.icons.borrow_mut().remove(&handle);
+        drop(icon);
+        Ok(())
+    }
+
+    fn on_tray_icon_message(&self, w_param: usize, l_param: isize) {
+        let event_type = match (l_param & 0xFFFF) as u32 {
+            WM_LBUTTONUP => TrayIconEventType::LeftClick,
+            WM_LBUTTONDBLCLK => TrayIconEventType::DoubleClick,
+            WM_MBUTTONUP => TrayIconEventType::MiddleClick,
+            WM_CONTEXTMENU => TrayIconEventType::RightClick,
+            NIN_POPUPOPEN => TrayIconEventType::TooltipRequested,
+            _ => return,
+        };
+        let id = ((l_param >> 16) & 0xFFFF) as u32;
+        let handle = self
+            .icons
+            .borrow()
+            .iter()
+            .find(|(_, icon)| icon.id == id)
+            .map(|(handle, _)| *handle);
+        // With NOTIFYICON_VERSION_4 wParam contains anchor coordinates.
+        let position = Point {
+            x: (w_param & 0xFFFF) as u16 as i16 as f64,
+            y: ((w_param >> 16) & 0xFFFF) as u16 as i16 as f64,
+        };
+        if let (Some(handle), Some(delegate)) = (handle, self.delegate.upgrade()) {
+            delegate.on_tray_icon_event(handle, event_type, position);
+        }
+    }
+}
+
+impl Drop for PlatformTrayIconManager {
+    fn drop(&mut self) {
+        // Icons must be removed while the window still exists.
+        self.icons.borrow_mut().clear();
+        if self.hwnd.is_set() {
+            let hwnd = *self.hwnd;
+            unsafe {
+                RemoveWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID);
+                drop(Weak::from_raw(Weak::as_ptr(&self.weak_self)));
+                DestroyWindow(hwnd).ok_log();
+            }
+        }
+    }
+}
+
+unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    message: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+    _id: usize,
+    ref_data: usize,
+) -> LRESULT {
+    let manager = ManuallyDrop::new(Weak::from_raw(ref_data as *const PlatformTrayIconManager));
+    if let Some(manager) = manager.upgrade() {
+        manager.on_window_message(message, w_param.0, l_param.0);
+    }
+    DefSubclassProc(hwnd, message, w_param, l_param)
+}
+
+impl PlatformTrayIconManager {
+    fn on_window_message(&self, message: u32, w_param: usize, l_param: isize) {
+        if message == WM_TRAY_ICON {
+            self.on_tray_icon_message(w_param, l_param);
+        } else if message == self.taskbar_created_message && message != 0 {
+            for icon in self.icons.borrow().values() {
+                icon.add().ok_log();
+            }
+        }
+    }
+}
@@ -9,7 +9,7 @@ use threadpool::ThreadPool;
 use windows::{
     core::{implement, ComInterface},
     Win32::{
-        Foundation::{E_FAIL, E_NOTIMPL, S_FALSE, S_OK},
+        Foundation::{E_ABORT, E_FAIL, E_NOTIMPL, S_FALSE, S_OK},
         System::Com::{
             CoInitialize, CoUninitialize, ISequentialStream_Impl, IStream, IStream_Impl,
             Marshal::CoMarshalInterThreadInterfaceInStream,
@@ -22,6 +22,7 @@ use windows::{
 use crate::{
     data_provider_manager::VirtualSessionHandle,
     segmented_queue::SegmentedQueueReader,
+    throttle::Throttle,
     util::{DropNotifier, Movable},
     value_promise::Promise,
 };
@@ -30,8 +31,9 @@ struct StreamState {
     reader: SegmentedQueueReader,
     size_promise: Arc<Promise<Option<i64>>>,
     error_promise: Arc<Promise<String>>,
-    _handle: Arc<VirtualSessionHandle>,
+    handle: Arc<VirtualSessionHandle>,
     position: Cell<i64>,
+    throttle: Option<Throttle>,
 }
 
 struct StreamInner {
@@ -85,6 +87,9 @@ impl Stream {
                 let data_out = unsafe { slice::from_raw_parts_mut(pv as *mut u8, data.len()) };
                 data_out.copy_from_slice(&data);
                 *pcbread = data.len() as u32;
+                if let Some(throttle) = stream.throttle.as_ref() {
+                    throttle.consume(data.len());
+                }
 
                 stream
                     .position
@@ -94,6 +99,9 @@ impl Stream {
                     // TODO(knopp): Can we somehow pass the message?
                     return E_FAIL;
                 }
+                if data.is_empty() && stream.handle.progress().is_cancelled() {
+                    return E_ABORT;
+                }
                 if !data.is_empty() {
                     S_OK
                 } else {
@@ -183,8 +191,9 @@ where
                 reader: session.reader,
                 size_promise: session.size_promise,
                 error_promise: session.error_promise,
-                _handle: session.handle,
+                handle: session.handle,
                 position: Cell::new(0),
+                throttle: Throttle::for_new_session(),
             })
         });
         res.wait()